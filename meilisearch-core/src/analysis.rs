@@ -0,0 +1,123 @@
+//! Describes how a text is handled by the indexing and the search pipelines of an index, under
+//! its current settings, to understand why a document matches a query or not.
+use meilisearch_tokenizer::{split_query_string_with_separators, Tokenizer};
+use serde::Serialize;
+
+use crate::automaton::max_typos;
+use crate::database::MainT;
+use crate::query_tree::{create_query_tree, fetch_synonyms, Context, MAX_NGRAM};
+use crate::raw_indexer::indexed_words;
+use crate::settings::TokenizerMode;
+use crate::{Index, MResult};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Analysis {
+    /// The words stored when the text is a value of a document.
+    pub indexing: Vec<IndexedToken>,
+    /// The words searched when the text is a query.
+    pub search: SearchAnalysis,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedToken {
+    pub token: String,
+    /// The position of the word in the attribute, used by the proximity ranking rule.
+    pub word_index: usize,
+    pub char_index: usize,
+    pub stop_word: bool,
+    /// The words under which the token is found, empty if it is not indexed.
+    pub indexed_as: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchAnalysis {
+    pub tokens: Vec<QueryToken>,
+    /// The synonyms of the words of the query, stop words removed, and of the groups of
+    /// consecutive words.
+    pub synonyms: Vec<SynonymExpansion>,
+    /// The alternatives searched for the query.
+    pub query_tree: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryToken {
+    pub token: String,
+    pub normalized: String,
+    pub stop_word: bool,
+    /// The number of typos tolerated when the word is searched, the last word of the query
+    /// is also searched as a prefix.
+    pub max_typos: u8,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SynonymExpansion {
+    pub words: String,
+    pub alternatives: Vec<String>,
+}
+
+pub fn analyze(reader: &heed::RoTxn<MainT>, index: &Index, text: &str) -> MResult<Analysis> {
+    let stop_words = index.main.stop_words_fst(reader)?;
+    let separators = index.main.separators(reader)?;
+    let typo_tolerance = index.main.typo_tolerance(reader)?;
+    let normalization = index.main.normalization(reader)?;
+
+    let indexing = Tokenizer::with_separators(text, &separators)
+        .map(|token| {
+            let word = normalization.normalize_word(token.word);
+            let stop_word = stop_words.contains(word.to_lowercase());
+            let indexed_as = if stop_word { Vec::new() } else { indexed_words(&word, &normalization) };
+            IndexedToken {
+                token: token.word.to_string(),
+                word_index: token.word_index,
+                char_index: token.char_index,
+                stop_word,
+                indexed_as: indexed_as.into_iter().map(|w| String::from_utf8_lossy(&w).into_owned()).collect(),
+            }
+        })
+        .collect();
+
+    let tokens: Vec<_> = split_query_string_with_separators(text, &separators)
+        .map(|token| {
+            let normalized = normalization.normalize_word(token);
+            let stop_word = stop_words.contains(normalized.to_lowercase());
+            let max_typos = if typo_tolerance.allows_typos(&normalized) { max_typos(&normalized) } else { 0 };
+            QueryToken { token: token.to_string(), normalized, stop_word, max_typos }
+        })
+        .collect();
+
+    let context = Context {
+        words_set: index.main.words_fst(reader)?,
+        stop_words,
+        separators,
+        synonyms: index.synonyms,
+        postings_lists: index.postings_lists,
+        prefix_postings_lists: index.prefix_postings_lists_cache,
+        typo_tolerance,
+        normalization,
+        keyword_attributes: index.main.attribute_tokenizers(reader)?.values().any(|mode| *mode == TokenizerMode::Keyword),
+    };
+
+    let words: Vec<&str> = tokens.iter().filter(|t| !t.stop_word && !t.normalized.is_empty()).map(|t| t.normalized.as_str()).collect();
+    let mut synonyms = Vec::new();
+    for start in 0..words.len() {
+        for ngram in (1..=MAX_NGRAM).filter_map(|len| words.get(start..start + len)) {
+            let alternatives = fetch_synonyms(reader, &context, ngram)?;
+            if !alternatives.is_empty() {
+                synonyms.push(SynonymExpansion {
+                    words: normalization.normalize(&ngram.join(" ")),
+                    alternatives: alternatives.into_iter().map(|words| words.join(" ")).collect(),
+                });
+            }
+        }
+    }
+
+    let (operation, _) = create_query_tree(reader, &context, text)?;
+    let search = SearchAnalysis { tokens, synonyms, query_tree: format!("{:?}", operation) };
+
+    Ok(Analysis { indexing, search })
+}
@@ -14,15 +14,15 @@ enum PrefixSetting {
 fn build_dfa_with_setting(query: &str, setting: PrefixSetting) -> DFA {
     use PrefixSetting::{NoPrefix, Prefix};
 
-    match query.len() {
-        0..=4 => {
+    match max_typos(query) {
+        0 => {
             let builder = LEVDIST0.get_or_init(|| LevBuilder::new(0, true));
             match setting {
                 Prefix => builder.build_prefix_dfa(query),
                 NoPrefix => builder.build_dfa(query),
             }
         }
-        5..=8 => {
+        1 => {
             let builder = LEVDIST1.get_or_init(|| LevBuilder::new(1, true));
             match setting {
                 Prefix => builder.build_prefix_dfa(query),
@@ -39,6 +39,15 @@ fn build_dfa_with_setting(query: &str, setting: PrefixSetting) -> DFA {
     }
 }
 
+/// The number of typos tolerated in a word of the query, depending on its length in bytes.
+pub fn max_typos(query: &str) -> u8 {
+    match query.len() {
+        0..=4 => 0,
+        5..=8 => 1,
+        _ => 2,
+    }
+}
+
 pub fn build_prefix_dfa(query: &str) -> DFA {
     build_dfa_with_setting(query, PrefixSetting::Prefix)
 }
@@ -51,3 +60,8 @@ pub fn build_exact_dfa(query: &str) -> DFA {
     let builder = LEVDIST0.get_or_init(|| LevBuilder::new(0, true));
     builder.build_dfa(query)
 }
+
+pub fn build_exact_prefix_dfa(query: &str) -> DFA {
+    let builder = LEVDIST0.get_or_init(|| LevBuilder::new(0, true));
+    builder.build_prefix_dfa(query)
+}
@@ -5,7 +5,7 @@ use std::ops::Deref;
 use std::ops::Range;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::fmt;
 
 use compact_arena::{SmallArena, Idx32, mk_arena};
@@ -23,6 +23,7 @@ use crate::{store, Document, DocumentId, MResult, Index, RankedMap, MainReader,
 use crate::query_tree::{create_query_tree, traverse_query_tree};
 use crate::query_tree::{Operation, QueryResult, QueryKind, QueryId, PostingsKey};
 use crate::query_tree::Context as QTContext;
+use crate::settings::TokenizerMode;
 
 #[derive(Debug, Default)]
 pub struct SortResult {
@@ -30,7 +31,59 @@ pub struct SortResult {
     pub nb_hits: usize,
     pub exhaustive_nb_hit: bool,
     pub facets: Option<HashMap<String, HashMap<String, usize>>>,
+    /// The counts of the facet values ignoring the facet filters on their own attribute.
+    pub disjunctive_facets: Option<HashMap<String, HashMap<String, usize>>>,
     pub exhaustive_facets_count: Option<bool>,
+    pub profile: SortProfile,
+}
+
+/// The time spent in each phase of a search and the number of documents it went through.
+#[derive(Debug, Default, Clone)]
+pub struct SortProfile {
+    /// Tokenizing the query and building its query tree.
+    pub query_tree: Duration,
+    /// Fetching the postings lists of the query tree and applying the facet filters.
+    pub candidates_retrieval: Duration,
+    /// Counting the facet values of the candidates.
+    pub facets_count: Duration,
+    /// Grouping the matches of the candidates by document.
+    pub candidates_building: Duration,
+    /// The ranking rules in the order they were applied.
+    pub criteria: Vec<CriterionProfile>,
+    /// Filtering, deduplicating and building the documents of the requested range.
+    pub documents_building: Duration,
+    /// The number of postings lists fetched for the query.
+    pub postings_lists: usize,
+    /// The number of documents matching the query.
+    pub candidates: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CriterionProfile {
+    pub name: String,
+    /// The time spent preparing and sorting the documents.
+    pub duration: Duration,
+    /// The documents sorted by the criterion, the buckets past the requested range are not.
+    pub sorted_documents: usize,
+    /// The number of buckets of equivalent documents the criterion produced.
+    pub buckets: usize,
+}
+
+impl CriterionProfile {
+    fn new(name: &str) -> CriterionProfile {
+        CriterionProfile { name: name.to_string(), duration: Duration::default(), sorted_documents: 0, buckets: 0 }
+    }
+}
+
+/// The documents of each value of the attributes counted without their own facet filters, with
+/// the documents of the facet filters on the other attributes, `None` if there are none.
+pub type DisjunctiveCountDocids<'a> = HashMap<String, (Option<SetBuf<DocumentId>>, HashMap<String, (&'a str, Cow<'a, Set<DocumentId>>)>)>;
+
+/// The number of hits of a search: its candidates and the pinned documents that are not
+/// candidates, the pinned documents are returned whether they match the query or not.
+pub fn count_hits(candidates: &Set<DocumentId>, pinned: &[DocumentId]) -> usize {
+    let others = pinned.iter().filter(|id| candidates.binary_search(*id).is_err()).count();
+    candidates.len() + others
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -40,7 +93,9 @@ pub fn bucket_sort<'c, FI>(
     range: Range<usize>,
     facets_docids: Option<SetBuf<DocumentId>>,
     facet_count_docids: Option<HashMap<String, HashMap<String, (&str, Cow<Set<DocumentId>>)>>>,
+    disjunctive_count_docids: Option<DisjunctiveCountDocids>,
     filter: Option<FI>,
+    pinned: &[DocumentId],
     criteria: Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
     index: &Index,
@@ -59,7 +114,9 @@ where
             range,
             facets_docids,
             facet_count_docids,
+            disjunctive_count_docids,
             filter,
+            pinned,
             distinct,
             distinct_size,
             criteria,
@@ -72,16 +129,24 @@ where
 
     let words_set = index.main.words_fst(reader)?;
     let stop_words = index.main.stop_words_fst(reader)?;
+    let separators = index.main.separators(reader)?;
+    let ranking_behavior = index.main.ranking_behavior(reader)?;
 
     let context = QTContext {
         words_set,
         stop_words,
+        separators,
         synonyms: index.synonyms,
         postings_lists: index.postings_lists,
         prefix_postings_lists: index.prefix_postings_lists_cache,
+        typo_tolerance: index.main.typo_tolerance(reader)?,
+        normalization: index.main.normalization(reader)?,
+        keyword_attributes: index.main.attribute_tokenizers(reader)?.values().any(|mode| *mode == TokenizerMode::Keyword),
     };
 
+    let before_query_tree = Instant::now();
     let (operation, mapping) = create_query_tree(reader, &context, query)?;
+    result.profile.query_tree = before_query_tree.elapsed();
     debug!("operation:\n{:?}", operation);
     debug!("mapping:\n{:?}", mapping);
 
@@ -96,21 +161,35 @@ where
     let mut queries_kinds = HashMap::new();
     recurs_operation(&mut queries_kinds, &operation);
 
+    let before_candidates_retrieval = Instant::now();
     let QueryResult { mut docids, queries } = traverse_query_tree(reader, &context, &operation)?;
+    result.profile.postings_lists = queries.len();
     debug!("found {} documents", docids.len());
     debug!("number of postings {:?}", queries.len());
 
+    // the disjunctive counts ignore some of the facet filters
+    let query_docids = disjunctive_count_docids.as_ref().map(|_| docids.clone());
     if let Some(facets_docids) = facets_docids {
         let intersection = sdset::duo::OpBuilder::new(docids.as_ref(), facets_docids.as_set())
             .intersection()
             .into_set_buf();
         docids = Cow::Owned(intersection);
     }
+    result.profile.candidates_retrieval = before_candidates_retrieval.elapsed();
+    result.profile.candidates = docids.len();
 
     if let Some(f) = facet_count_docids {
+        let before_facets_count = Instant::now();
         // hardcoded value, until approximation optimization
         result.exhaustive_facets_count = Some(true);
         result.facets = Some(facet_count(f, &docids));
+        result.profile.facets_count = before_facets_count.elapsed();
+    }
+    if let (Some(f), Some(query_docids)) = (disjunctive_count_docids, query_docids) {
+        let before_facets_count = Instant::now();
+        result.exhaustive_facets_count = Some(true);
+        result.disjunctive_facets = Some(disjunctive_facet_count(f, &query_docids));
+        result.profile.facets_count += before_facets_count.elapsed();
     }
 
     let before = Instant::now();
@@ -130,6 +209,7 @@ where
         raw_documents.len(),
         before_raw_documents_building.elapsed(),
     );
+    result.profile.candidates_building = before.elapsed();
 
     let before_criterion_loop = Instant::now();
     let proximity_count = AtomicUsize::new(0);
@@ -137,6 +217,8 @@ where
     let mut groups = vec![raw_documents.as_mut_slice()];
 
     'criteria: for criterion in criteria.as_ref() {
+        result.profile.criteria.push(CriterionProfile::new(criterion.name()));
+        let profile = result.profile.criteria.last_mut().unwrap();
         let tmp_groups = mem::replace(&mut groups, Vec::new());
         let mut documents_seen = 0;
 
@@ -148,6 +230,7 @@ where
                 postings_lists: &mut arena,
                 query_mapping: &mapping,
                 documents_fields_counts_store: index.documents_fields_counts,
+                ranking_behavior: &ranking_behavior,
             };
 
             criterion.prepare(ctx, &mut group)?;
@@ -156,14 +239,18 @@ where
             let ctx = Context {
                 postings_lists: &arena,
                 query_mapping: &mapping,
+                ranking_behavior: &ranking_behavior,
             };
 
             let before_criterion_sort = Instant::now();
             group.sort_unstable_by(|a, b| criterion.evaluate(&ctx, a, b));
             debug!("{:?} evaluation took {:.02?}", criterion.name(), before_criterion_sort.elapsed());
+            profile.duration += before_criterion_preparation.elapsed();
+            profile.sorted_documents += group.len();
 
             for group in group.binary_group_by_mut(|a, b| criterion.eq(&ctx, a, b)) {
                 debug!("{:?} produced a group of size {}", criterion.name(), group.len());
+                profile.buckets += 1;
 
                 documents_seen += group.len();
                 groups.push(group);
@@ -180,15 +267,17 @@ where
     debug!("criterion loop took {:.02?}", before_criterion_loop.elapsed());
     debug!("proximity evaluation called {} times", proximity_count.load(Ordering::Relaxed));
 
+    let before_documents_building = Instant::now();
     let schema = index.main.schema(reader)?.ok_or(Error::SchemaMissing)?;
     let iter = raw_documents.into_iter().skip(range.start).take(range.len());
     let iter = iter.map(|rd| Document::from_raw(rd, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema));
     let documents = iter.collect();
+    result.profile.documents_building = before_documents_building.elapsed();
 
     debug!("bucket sort took {:.02?}", before_bucket_sort.elapsed());
 
     result.documents = documents;
-    result.nb_hits = docids.len();
+    result.nb_hits = count_hits(&docids, pinned);
 
     Ok(result)
 }
@@ -200,7 +289,9 @@ pub fn bucket_sort_with_distinct<'c, FI, FD>(
     range: Range<usize>,
     facets_docids: Option<SetBuf<DocumentId>>,
     facet_count_docids: Option<HashMap<String, HashMap<String, (&str, Cow<Set<DocumentId>>)>>>,
+    disjunctive_count_docids: Option<DisjunctiveCountDocids>,
     filter: Option<FI>,
+    pinned: &[DocumentId],
     distinct: FD,
     distinct_size: usize,
     criteria: Criteria<'c>,
@@ -215,16 +306,24 @@ where
 
     let words_set = index.main.words_fst(reader)?;
     let stop_words = index.main.stop_words_fst(reader)?;
+    let separators = index.main.separators(reader)?;
+    let ranking_behavior = index.main.ranking_behavior(reader)?;
 
     let context = QTContext {
         words_set,
         stop_words,
+        separators,
         synonyms: index.synonyms,
         postings_lists: index.postings_lists,
         prefix_postings_lists: index.prefix_postings_lists_cache,
+        typo_tolerance: index.main.typo_tolerance(reader)?,
+        normalization: index.main.normalization(reader)?,
+        keyword_attributes: index.main.attribute_tokenizers(reader)?.values().any(|mode| *mode == TokenizerMode::Keyword),
     };
 
+    let before_query_tree = Instant::now();
     let (operation, mapping) = create_query_tree(reader, &context, query)?;
+    result.profile.query_tree = before_query_tree.elapsed();
     debug!("operation:\n{:?}", operation);
     debug!("mapping:\n{:?}", mapping);
 
@@ -239,21 +338,35 @@ where
     let mut queries_kinds = HashMap::new();
     recurs_operation(&mut queries_kinds, &operation);
 
+    let before_candidates_retrieval = Instant::now();
     let QueryResult { mut docids, queries } = traverse_query_tree(reader, &context, &operation)?;
+    result.profile.postings_lists = queries.len();
     debug!("found {} documents", docids.len());
     debug!("number of postings {:?}", queries.len());
 
+    // the disjunctive counts ignore some of the facet filters
+    let query_docids = disjunctive_count_docids.as_ref().map(|_| docids.clone());
     if let Some(facets_docids) = facets_docids {
         let intersection = OpBuilder::new(docids.as_ref(), facets_docids.as_set())
             .intersection()
             .into_set_buf();
         docids = Cow::Owned(intersection);
     }
+    result.profile.candidates_retrieval = before_candidates_retrieval.elapsed();
+    result.profile.candidates = docids.len();
 
     if let Some(f) = facet_count_docids {
+        let before_facets_count = Instant::now();
         // hardcoded value, until approximation optimization
         result.exhaustive_facets_count = Some(true);
         result.facets = Some(facet_count(f, &docids));
+        result.profile.facets_count = before_facets_count.elapsed();
+    }
+    if let (Some(f), Some(query_docids)) = (disjunctive_count_docids, query_docids) {
+        let before_facets_count = Instant::now();
+        result.exhaustive_facets_count = Some(true);
+        result.disjunctive_facets = Some(disjunctive_facet_count(f, &query_docids));
+        result.profile.facets_count += before_facets_count.elapsed();
     }
 
     let before = Instant::now();
@@ -271,6 +384,7 @@ where
         raw_documents.len(),
         before_raw_documents_building.elapsed(),
     );
+    result.profile.candidates_building = before.elapsed();
 
     let mut groups = vec![raw_documents.as_mut_slice()];
     let mut key_cache = HashMap::new();
@@ -283,6 +397,8 @@ where
     let mut distinct_raw_offset = 0;
 
     'criteria: for criterion in criteria.as_ref() {
+        result.profile.criteria.push(CriterionProfile::new(criterion.name()));
+        let profile = result.profile.criteria.last_mut().unwrap();
         let tmp_groups = mem::replace(&mut groups, Vec::new());
         let mut buf_distinct = BufferedDistinctMap::new(&mut distinct_map);
         let mut documents_seen = 0;
@@ -301,6 +417,7 @@ where
                 postings_lists: &mut arena,
                 query_mapping: &mapping,
                 documents_fields_counts_store: index.documents_fields_counts,
+                ranking_behavior: &ranking_behavior,
             };
 
             let before_criterion_preparation = Instant::now();
@@ -310,13 +427,18 @@ where
             let ctx = Context {
                 postings_lists: &arena,
                 query_mapping: &mapping,
+                ranking_behavior: &ranking_behavior,
             };
 
             let before_criterion_sort = Instant::now();
             group.sort_unstable_by(|a, b| criterion.evaluate(&ctx, a, b));
             debug!("{:?} evaluation took {:.02?}", criterion.name(), before_criterion_sort.elapsed());
+            profile.duration += before_criterion_preparation.elapsed();
+            profile.sorted_documents += group.len();
 
             for group in group.binary_group_by_mut(|a, b| criterion.eq(&ctx, a, b)) {
+                profile.buckets += 1;
+
                 // we must compute the real distinguished len of this sub-group
                 for document in group.iter() {
                     let filter_accepted = match &filter {
@@ -364,6 +486,7 @@ where
 
     // once we classified the documents related to the current
     // automatons we save that as the next valid result
+    let before_documents_building = Instant::now();
     let mut seen = BufferedDistinctMap::new(&mut distinct_map);
     let schema = index.main.schema(reader)?.ok_or(Error::SchemaMissing)?;
 
@@ -395,8 +518,10 @@ where
             }
         }
     }
+    result.profile.documents_building = before_documents_building.elapsed();
+
     result.documents = documents;
-    result.nb_hits = docids.len();
+    result.nb_hits = count_hits(&docids, pinned);
 
     Ok(result)
 }
@@ -642,6 +767,25 @@ pub fn placeholder_document_sort(
 }
 
 /// For each entry in facet_docids, calculates the number of documents in the intersection with candidate_docids.
+/// Counts the documents of each value among the documents of the query matching the facet
+/// filters on the other attributes.
+pub fn disjunctive_facet_count(
+    facet_docids: DisjunctiveCountDocids,
+    query_docids: &Set<DocumentId>,
+) -> HashMap<String, HashMap<String, usize>> {
+    let mut facets_counts = HashMap::with_capacity(facet_docids.len());
+    for (key, (others_docids, doc_map)) in facet_docids {
+        let candidate_docids = match others_docids {
+            Some(others_docids) => Cow::Owned(OpBuilder::new(query_docids, others_docids.as_set()).intersection().into_set_buf()),
+            None => Cow::Borrowed(query_docids),
+        };
+        let mut doc_maps = HashMap::with_capacity(1);
+        doc_maps.insert(key, doc_map);
+        facets_counts.extend(facet_count(doc_maps, &candidate_docids));
+    }
+    facets_counts
+}
+
 pub fn facet_count(
     facet_docids: HashMap<String, HashMap<String, (&str, Cow<Set<DocumentId>>)>>,
     candidate_docids: &Set<DocumentId>,
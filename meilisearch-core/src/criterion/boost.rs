@@ -0,0 +1,31 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use crate::{DocumentId, RawDocument};
+use super::{Criterion, Context};
+
+/// Sorts the documents by the product of the multipliers given to some of
+/// their facet values, the documents without a multiplier have a score of 1.
+pub struct Boost {
+    scores: HashMap<DocumentId, f64>,
+}
+
+impl Boost {
+    pub fn new(scores: HashMap<DocumentId, f64>) -> Boost {
+        Boost { scores }
+    }
+
+    fn score(&self, id: &DocumentId) -> f64 {
+        self.scores.get(id).copied().unwrap_or(1.0)
+    }
+}
+
+impl Criterion for Boost {
+    fn name(&self) -> &str { "boost" }
+
+    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        let lhs = self.score(&lhs.id);
+        let rhs = self.score(&rhs.id);
+
+        rhs.partial_cmp(&lhs).unwrap_or(Ordering::Equal)
+    }
+}
@@ -4,6 +4,7 @@ use meilisearch_schema::IndexedPos;
 use slice_group_by::GroupBy;
 use crate::{RawDocument, MResult};
 use crate::bucket_sort::BareMatch;
+use crate::settings::ExactnessMatch;
 use super::{Criterion, Context, ContextMut};
 
 pub struct Exactness;
@@ -17,6 +18,11 @@ impl Criterion for Exactness {
         documents: &mut [RawDocument<'r, 'tag>],
     ) -> MResult<()>
     {
+        // the fields counts are only needed to find the attributes equal to a query word
+        if ctx.ranking_behavior.exactness == ExactnessMatch::Word {
+            return Ok(());
+        }
+
         let store = ctx.documents_fields_counts_store;
         let reader = ctx.reader;
 
@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use crate::database::MainT;
+use crate::{store, DocumentId, RawDocument};
+use super::{Criterion, Context};
+
+/// The mean radius of the Earth in meters.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Returns the distance in meters between two points given by their latitude and longitude in degrees.
+pub fn haversine_distance((lat1, lng1): (f64, f64), (lat2, lng2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let delta_lat = lat2 - lat1;
+    let delta_lng = (lng2 - lng1).to_radians();
+
+    let a = (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// Sorts the documents by their distance to a point. The locations are read from the store
+/// the first time a document is compared, the documents without a location come last.
+pub struct GeoDistance<'a> {
+    reader: &'a heed::RoTxn<MainT>,
+    locations: store::GeoLocations,
+    point: (f64, f64),
+    reversed: bool,
+    distances: RefCell<HashMap<DocumentId, Option<f64>>>,
+}
+
+impl<'a> GeoDistance<'a> {
+    pub fn nearest_first(reader: &'a heed::RoTxn<MainT>, locations: store::GeoLocations, point: (f64, f64)) -> GeoDistance<'a> {
+        GeoDistance::new(reader, locations, point, false)
+    }
+
+    pub fn farthest_first(reader: &'a heed::RoTxn<MainT>, locations: store::GeoLocations, point: (f64, f64)) -> GeoDistance<'a> {
+        GeoDistance::new(reader, locations, point, true)
+    }
+
+    fn new(reader: &'a heed::RoTxn<MainT>, locations: store::GeoLocations, point: (f64, f64), reversed: bool) -> GeoDistance<'a> {
+        GeoDistance { reader, locations, point, reversed, distances: RefCell::default() }
+    }
+
+    fn distance(&self, id: DocumentId) -> Option<f64> {
+        if let Some(distance) = self.distances.borrow().get(&id) {
+            return *distance;
+        }
+
+        let distance = match self.locations.location(self.reader, id) {
+            Ok(location) => location.map(|location| haversine_distance(self.point, (location.lat, location.lng))),
+            Err(e) => {
+                log::warn!("cannot read the location of the document {:?}; {}", id, e);
+                None
+            }
+        };
+        self.distances.borrow_mut().insert(id, distance);
+        distance
+    }
+
+    pub fn compare(&self, lhs: DocumentId, rhs: DocumentId) -> Ordering {
+        match (self.distance(lhs), self.distance(rhs)) {
+            (Some(lhs), Some(rhs)) => {
+                let order = lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal);
+                if self.reversed { order.reverse() } else { order }
+            }
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+impl Criterion for GeoDistance<'_> {
+    fn name(&self) -> &str { "geo distance" }
+
+    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        self.compare(lhs.id, rhs.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::GeoLocation;
+    use crate::{Database, DatabaseOptions};
+
+    #[test]
+    fn distances() {
+        let paris = (48.8566, 2.3522);
+        let london = (51.5074, -0.1278);
+        let distance = haversine_distance(paris, london);
+        assert!((distance - 343_500.0).abs() < 1_000.0, "{}", distance);
+        assert_eq!(haversine_distance(paris, paris), 0.0);
+    }
+
+    #[test]
+    fn documents_without_location_come_last() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let mut writer = database.main_write_txn().unwrap();
+        index.geo_locations.put_location(&mut writer, DocumentId(1), GeoLocation { lat: 0.1, lng: 0.0 }).unwrap();
+        index.geo_locations.put_location(&mut writer, DocumentId(2), GeoLocation { lat: 0.05, lng: 0.0 }).unwrap();
+        writer.commit().unwrap();
+
+        let reader = database.main_read_txn().unwrap();
+        let nearest = GeoDistance::nearest_first(&reader, index.geo_locations, (0.0, 0.0));
+        assert_eq!(nearest.compare(DocumentId(2), DocumentId(1)), Ordering::Less);
+        assert_eq!(nearest.compare(DocumentId(3), DocumentId(1)), Ordering::Greater);
+
+        let farthest = GeoDistance::farthest_first(&reader, index.geo_locations, (0.0, 0.0));
+        assert_eq!(farthest.compare(DocumentId(2), DocumentId(1)), Ordering::Greater);
+        assert_eq!(farthest.compare(DocumentId(3), DocumentId(1)), Ordering::Greater);
+    }
+}
@@ -9,6 +9,7 @@ use slice_group_by::GroupBy;
 use crate::bucket_sort::{SimpleMatch, PostingsListView};
 use crate::database::MainT;
 use crate::query_tree::QueryId;
+use crate::settings::RankingBehavior;
 use crate::{store, RawDocument, MResult};
 
 mod typo;
@@ -19,6 +20,8 @@ mod words_position;
 mod exactness;
 mod document_id;
 mod sort_by_attr;
+mod boost;
+mod geo_distance;
 
 pub use self::typo::Typo;
 pub use self::words::Words;
@@ -28,6 +31,8 @@ pub use self::words_position::WordsPosition;
 pub use self::exactness::Exactness;
 pub use self::document_id::DocumentId;
 pub use self::sort_by_attr::SortByAttr;
+pub use self::boost::Boost;
+pub use self::geo_distance::{haversine_distance, GeoDistance};
 
 pub trait Criterion {
     fn name(&self) -> &str;
@@ -65,11 +70,13 @@ pub struct ContextMut<'h, 'p, 'tag, 'txn, 'q> {
     pub postings_lists: &'p mut SmallArena<'tag, PostingsListView<'txn>>,
     pub query_mapping: &'q HashMap<QueryId, Range<usize>>,
     pub documents_fields_counts_store: store::DocumentsFieldsCounts,
+    pub ranking_behavior: &'q RankingBehavior,
 }
 
 pub struct Context<'p, 'tag, 'txn, 'q> {
     pub postings_lists: &'p SmallArena<'tag, PostingsListView<'txn>>,
     pub query_mapping: &'q HashMap<QueryId, Range<usize>>,
+    pub ranking_behavior: &'q RankingBehavior,
 }
 
 #[derive(Default)]
@@ -4,8 +4,6 @@ use crate::bucket_sort::{SimpleMatch};
 use crate::{RawDocument, MResult};
 use super::{Criterion, Context, ContextMut, prepare_bare_matches};
 
-const MAX_DISTANCE: u16 = 8;
-
 pub struct Proximity;
 
 impl Criterion for Proximity {
@@ -21,47 +19,53 @@ impl Criterion for Proximity {
         Ok(())
     }
 
-    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        fn index_proximity(lhs: u16, rhs: u16) -> u16 {
+    fn evaluate(&self, ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        fn index_proximity(lhs: u16, rhs: u16, max: u16) -> u16 {
             if lhs < rhs {
-                cmp::min(rhs - lhs, MAX_DISTANCE)
+                cmp::min(rhs - lhs, max)
             } else {
-                cmp::min(lhs - rhs, MAX_DISTANCE) + 1
+                cmp::min(lhs - rhs, max).saturating_add(1)
             }
         }
 
-        fn attribute_proximity(lhs: SimpleMatch, rhs: SimpleMatch) -> u16 {
-            if lhs.attribute != rhs.attribute { MAX_DISTANCE }
-            else { index_proximity(lhs.word_index, rhs.word_index) }
+        fn attribute_proximity(lhs: SimpleMatch, rhs: SimpleMatch, max: u16) -> u16 {
+            if lhs.attribute != rhs.attribute { max }
+            else { index_proximity(lhs.word_index, rhs.word_index, max) }
         }
 
-        fn min_proximity(lhs: &[SimpleMatch], rhs: &[SimpleMatch]) -> u16 {
+        fn min_proximity(lhs: &[SimpleMatch], rhs: &[SimpleMatch], max: u16) -> u16 {
             let mut min_prox = u16::max_value();
             for a in lhs {
                 for b in rhs {
-                    let prox = attribute_proximity(*a, *b);
+                    let prox = attribute_proximity(*a, *b, max);
                     min_prox = cmp::min(min_prox, prox);
                 }
             }
             min_prox
         }
 
-        fn matches_proximity(matches: &[SimpleMatch],) -> u16 {
+        fn matches_proximity(matches: &[SimpleMatch], max: u16) -> u16 {
             let mut proximity = 0;
             let mut iter = matches.linear_group_by_key(|m| m.query_index);
 
             // iterate over groups by windows of size 2
             let mut last = iter.next();
             while let (Some(lhs), Some(rhs)) = (last, iter.next()) {
-                proximity += min_proximity(lhs, rhs);
+                proximity = min_proximity(lhs, rhs, max).saturating_add(proximity);
                 last = Some(rhs);
             }
 
             proximity
         }
 
-        let lhs = matches_proximity(&lhs.processed_matches);
-        let rhs = matches_proximity(&rhs.processed_matches);
+        // the proximity is disabled, the documents are not compared
+        let max = ctx.ranking_behavior.max_proximity;
+        if max == 0 {
+            return Ordering::Equal;
+        }
+
+        let lhs = matches_proximity(&lhs.processed_matches, max);
+        let rhs = matches_proximity(&rhs.processed_matches, max);
 
         lhs.cmp(&rhs)
     }
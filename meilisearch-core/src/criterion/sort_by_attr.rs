@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt;
 use meilisearch_schema::{Schema, FieldId};
-use crate::{RankedMap, RawDocument};
+use crate::{DocumentId, RankedMap, RawDocument};
 use super::{Criterion, Context};
 
 /// An helper struct that permit to sort documents by
@@ -83,16 +83,10 @@ impl<'a> SortByAttr<'a> {
             reversed,
         })
     }
-}
-
-impl Criterion for SortByAttr<'_> {
-    fn name(&self) -> &str {
-        "sort by attribute"
-    }
 
-    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        let lhs = self.ranked_map.get(lhs.id, self.field_id);
-        let rhs = self.ranked_map.get(rhs.id, self.field_id);
+    pub fn compare(&self, lhs: DocumentId, rhs: DocumentId) -> Ordering {
+        let lhs = self.ranked_map.get(lhs, self.field_id);
+        let rhs = self.ranked_map.get(rhs, self.field_id);
 
         match (lhs, rhs) {
             (Some(lhs), Some(rhs)) => {
@@ -110,6 +104,16 @@ impl Criterion for SortByAttr<'_> {
     }
 }
 
+impl Criterion for SortByAttr<'_> {
+    fn name(&self) -> &str {
+        "sort by attribute"
+    }
+
+    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        self.compare(lhs.id, rhs.id)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SortByAttrError {
     AttributeNotFound,
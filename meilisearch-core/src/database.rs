@@ -2,24 +2,38 @@ use std::collections::hash_map::{Entry, HashMap};
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::{fs, thread};
 use std::io::{Read, Write, ErrorKind};
 
 use chrono::{DateTime, Utc};
 use crossbeam_channel::{Receiver, Sender};
 use heed::CompactionOption;
-use heed::types::{Str, Unit, SerdeBincode};
-use log::{debug, error};
+use heed::types::{Str, Unit, SerdeBincode, SerdeJson};
+use log::{debug, error, warn};
 use meilisearch_schema::Schema;
 use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{store, update, Index, MResult, Error};
 
 pub type BoxUpdateFn = Box<dyn Fn(&str, update::ProcessedUpdateResult) + Send + Sync + 'static>;
 
+/// Called with every update successfully applied to an index, in the order they were applied.
+/// Called with the updates successfully applied, serialized in JSON as they were stored.
+pub type BoxJournalFn = Box<dyn Fn(&str, &[u8], &update::ProcessedUpdateResult) + Send + Sync + 'static>;
+
+/// Tells whether the updates of an index must be given to the journal callback,
+/// their bytes are only copied for these indexes.
+pub type BoxJournalFilterFn = Box<dyn Fn(&str) -> bool + Send + Sync + 'static>;
+
 type ArcSwapFn = arc_swap::ArcSwapOption<BoxUpdateFn>;
 
+type ArcSwapJournalFn = arc_swap::ArcSwapOption<(BoxJournalFilterFn, BoxJournalFn)>;
+
+type ArcSwapImportFn = arc_swap::ArcSwapOption<update::BoxImportFn>;
+
 type SerdeDatetime = SerdeBincode<DateTime<Utc>>;
 
 pub type MainWriter<'a> = heed::RwTxn<'a, MainT>;
@@ -30,6 +44,11 @@ pub type UpdateReader = heed::RoTxn<UpdateT>;
 
 const UNHEALTHY_KEY: &str = "_is_unhealthy";
 const LAST_UPDATE_KEY: &str = "last-update";
+const API_KEYS_KEY: &str = "api-keys";
+const INGESTION_OFFSETS_KEY: &str = "ingestion-offsets";
+const FIREHOSES_KEY: &str = "firehoses";
+const EXPERIMENTAL_FEATURES_KEY: &str = "experimental-features";
+const INDEX_STORAGE_KEY: &str = "index-storage";
 
 pub struct MainT;
 pub struct UpdateT;
@@ -39,14 +58,52 @@ pub struct Database {
     update_env: heed::Env,
     common_store: heed::PolyDatabase,
     indexes_store: heed::Database<Str, Unit>,
-    indexes: RwLock<HashMap<String, (Index, thread::JoinHandle<MResult<()>>)>>,
+    trash_store: heed::Database<Str, SerdeBincode<TrashedIndex>>,
+    /// How long the deleted indexes are kept in the trash, they are deleted right away if none.
+    trash_retention: Option<chrono::Duration>,
+    indexes: RwLock<HashMap<String, OpenedIndex>>,
+    /// Serializes the swaps of indexes.
+    swap_lock: Mutex<()>,
     update_fn: Arc<ArcSwapFn>,
+    journal_fn: Arc<ArcSwapJournalFn>,
+    import_fn: Arc<ArcSwapImportFn>,
+    /// Set when the database is being closed, the update loops stop after their current update.
+    stopping: Arc<AtomicBool>,
+    /// Set while the storage is almost full, the indexes refuse the updates meanwhile.
+    storage_full: Arc<AtomicBool>,
     database_version: (u32, u32, u32),
+    map_sizes: (usize, usize),
+}
+
+/// The uid under which the update loop of an index reports its updates, it changes when the
+/// index is swapped with another one.
+type SharedUid = Arc<RwLock<String>>;
+
+struct OpenedIndex {
+    index: Index,
+    /// The name of the LMDB databases of the index, the uid it has been created with.
+    storage: String,
+    uid: SharedUid,
+    update_loop: thread::JoinHandle<MResult<()>>,
+}
+
+/// An index that has been deleted, it can be restored until it is purged from the trash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedIndex {
+    /// The name of the LMDB databases of the index.
+    storage: String,
+    pub deleted_at: DateTime<Utc>,
 }
 
 pub struct DatabaseOptions {
     pub main_map_size: usize,
     pub update_map_size: usize,
+    /// The size up to which the maps are grown when they are opened almost full,
+    /// they are never grown if none.
+    pub max_map_size: Option<usize>,
+    /// How long the indexes deleted with [`Database::trash_index`] can be restored,
+    /// they are deleted right away if none.
+    pub trash_retention: Option<chrono::Duration>,
 }
 
 impl Default for DatabaseOptions {
@@ -54,8 +111,57 @@ impl Default for DatabaseOptions {
         DatabaseOptions {
             main_map_size: 100 * 1024 * 1024 * 1024, //100Gb
             update_map_size: 100 * 1024 * 1024 * 1024, //100Gb
+            max_map_size: None,
+            trash_retention: None,
+        }
+    }
+}
+
+/// The utilization of a map above which it is grown when the environment is opened.
+const MAP_GROWTH_THRESHOLD: f64 = 0.75;
+
+/// Returns the size of the map to open the environment with. The map size is doubled
+/// until the data file fills less than the growth threshold of it, the doubled sizes
+/// remain multiples of the page size and never exceed the maximum map size.
+///
+/// The maps are only grown here: heed can't resize an open environment, and closing it
+/// means dropping every transaction and update loop borrowing the `Database`. The
+/// capacity alerts of the server ask to restart MeiliSearch when a map can be grown.
+fn grown_map_size(env_path: &Path, map_size: usize, max_map_size: Option<usize>) -> usize {
+    let max_map_size = match max_map_size {
+        Some(max_map_size) => max_map_size,
+        None => return map_size,
+    };
+
+    let used = fs::metadata(env_path.join("data.mdb")).map_or(0, |metadata| metadata.len()) as f64;
+    let is_almost_full = |map_size: usize| used >= map_size as f64 * MAP_GROWTH_THRESHOLD;
+
+    let mut new_map_size = map_size;
+    while is_almost_full(new_map_size) {
+        match new_map_size.checked_mul(2) {
+            Some(size) if size <= max_map_size => new_map_size = size,
+            _ => break,
         }
     }
+
+    if new_map_size != map_size {
+        warn!("the map of {} is grown from {} to {} bytes", env_path.display(), map_size, new_map_size);
+    }
+    if is_almost_full(new_map_size) {
+        error!(
+            "the map of {} is almost full and can't be grown above the maximum map size of {} bytes",
+            env_path.display(),
+            max_map_size,
+        );
+    }
+
+    new_map_size
+}
+
+/// The key of the common store under which the name of the databases of an index is stored,
+/// when it isn't its uid because the index has been swapped.
+fn storage_key(index_uid: &str) -> String {
+    format!("{}-{}", INDEX_STORAGE_KEY, index_uid)
 }
 
 macro_rules! r#break_try {
@@ -73,18 +179,23 @@ macro_rules! r#break_try {
 pub enum UpdateEvent {
     NewUpdate,
     MustClear,
+    MustStop,
 }
 
 pub type UpdateEvents = Receiver<UpdateEvent>;
 pub type UpdateEventsEmitter = Sender<UpdateEvent>;
 
+#[allow(clippy::too_many_arguments)]
 fn update_awaiter(
     receiver: UpdateEvents,
     env: heed::Env,
     update_env: heed::Env,
-    index_uid: &str,
+    index_uid: SharedUid,
     update_fn: Arc<ArcSwapFn>,
+    journal_fn: Arc<ArcSwapJournalFn>,
+    import_fn: Arc<ArcSwapImportFn>,
     index: Index,
+    stopping: Arc<AtomicBool>,
 ) -> MResult<()> {
     for event in receiver {
 
@@ -98,19 +209,31 @@ fn update_awaiter(
             writer.commit()?;
             update_writer.commit()?;
 
-            debug!("store {} cleared", index_uid);
+            debug!("store {} cleared", index_uid.read().unwrap());
 
             break
         }
 
+        // the index is trashed, its databases are kept as they are
+        if let UpdateEvent::MustStop = event {
+            debug!("store {} trashed", index_uid.read().unwrap());
+            break
+        }
+
         loop {
+            // the database is being closed, the enqueued updates are processed on the next start
+            if stopping.load(Ordering::SeqCst) {
+                debug!("update loop stopped, the enqueued updates are kept");
+                break;
+            }
+
             // We instantiate a *write* transaction to *block* the thread
             // until the *other*, notifiying, thread commits
             let result = update_env.typed_write_txn::<UpdateT>();
             let update_reader = break_try!(result, "LMDB read transaction (update) begin failed");
 
             // retrieve the update that needs to be processed
-            let result = index.updates.first_update(&update_reader);
+            let result = index.updates.next_update(&update_reader);
             let (update_id, update) = match break_try!(result, "pop front update failed") {
                 Some(value) => value,
                 None => {
@@ -119,45 +242,109 @@ fn update_awaiter(
                 }
             };
 
-            // do not keep the reader for too long
-            break_try!(update_reader.abort(), "aborting update transaction failed");
+            // retrieve the settings updates enqueued right after this one, they are
+            // applied at once to avoid reindexing the documents for every one of them
+            let result = update::coalescable_settings_updates(&update_reader, index.updates, update_id, &update);
+            let following = break_try!(result, "retrieving coalescable updates failed");
+
+            let mut updates = vec![(update_id, update)];
+            updates.extend(following);
+
+            // the updates are consumed when applied, keep their serialized bytes for the journal,
+            // copying them is much cheaper than cloning the documents of the updates
+            let mut journaled = Vec::new();
+            let journal_needed = match *journal_fn.load() {
+                Some(ref journal) => (journal.0)(&index_uid.read().unwrap()),
+                None => false,
+            };
+            if journal_needed {
+                for (update_id, update) in &updates {
+                    // the imports are journaled without the credentials of their files
+                    if let update::UpdateData::DocumentsImport(_) = update.data() {
+                        let result = serde_json::to_vec(&update.without_credentials());
+                        journaled.push((*update_id, break_try!(result, "serializing the update to journal failed")));
+                        continue;
+                    }
+                    let result = index.updates.get_raw(&update_reader, *update_id);
+                    if let Some(bytes) = break_try!(result, "retrieving the update to journal failed") {
+                        journaled.push((*update_id, bytes.to_vec()));
+                    }
+                }
+            }
 
-            // instantiate a transaction to touch to the main env
-            let result = env.typed_write_txn::<MainT>();
-            let mut main_writer = break_try!(result, "LMDB nested write transaction failed");
+            // mark the updates as being processed before releasing the update transaction,
+            // this way they can't be canceled anymore
+            *index.processing_updates.lock().unwrap() = updates.iter().map(|(id, _)| *id).collect();
 
-            // try to apply the update to the database using the main transaction
-            let result = update::update_task(&mut main_writer, &index, update_id, update);
-            let status = break_try!(result, "update task failed");
+            // do not keep the reader for too long
+            break_try!(update_reader.abort(), "aborting update transaction failed");
 
-            // commit the main transaction if the update was successful, abort it otherwise
-            if status.error.is_none() {
-                break_try!(main_writer.commit(), "commit nested transaction failed");
-            } else {
-                break_try!(main_writer.abort(), "abborting nested transaction failed");
+            // apply the coalesced settings updates at once, in a transaction of the main env
+            let mut statuses = Vec::with_capacity(updates.len());
+            if updates.len() > 1 {
+                let result = env.typed_write_txn::<MainT>();
+                let mut main_writer = break_try!(result, "LMDB nested write transaction failed");
+                match update::coalesced_settings_task(&mut main_writer, &index, updates.clone()) {
+                    Ok(coalesced) => {
+                        break_try!(main_writer.commit(), "commit nested transaction failed");
+                        statuses = coalesced;
+                        updates.clear();
+                    }
+                    // the merged update is invalid, the updates are processed one by one
+                    Err(error) => {
+                        debug!("coalesced settings updates failed, processing them one by one: {}", error);
+                        break_try!(main_writer.abort(), "abborting nested transaction failed");
+                    }
+                }
             }
 
-            // now that the update has been processed we can instantiate
-            // a transaction to move the result to the updates-results store
-            let result = update_env.typed_write_txn::<UpdateT>();
-            let mut update_writer = break_try!(result, "LMDB write transaction begin failed");
+            // the updates that were not coalesced are applied in their own transactions, the
+            // ones processed before a failure are reported and the others are kept enqueued
+            let mut failed = false;
+            for (update_id, update) in updates {
+                match apply_update(&env, &index, update_id, update, import_fn.load().as_deref()) {
+                    Ok(status) => statuses.push(status),
+                    Err(e) => {
+                        error!("update task failed: {}", e);
+                        failed = true;
+                        break;
+                    }
+                }
+            }
 
-            // definitely remove the update from the updates store
-            index.updates.del_update(&mut update_writer, update_id)?;
+            // now that the updates have been processed we can move
+            // the results to the updates-results store
+            let result = write_update_results(&update_env, &index, &statuses);
+            break_try!(result, "update transaction commit failed");
+            index.processing_updates.lock().unwrap().clear();
 
-            // write the result of the updates-results store
-            let updates_results = index.updates_results;
-            let result = updates_results.put_update_result(&mut update_writer, update_id, &status);
+            // the index can't be swapped while its updates are reported
+            let index_uid = index_uid.read().unwrap();
 
-            // always commit the main transaction, even if the update was unsuccessful
-            break_try!(result, "update result store commit failed");
-            break_try!(update_writer.commit(), "update transaction commit failed");
+            // journal the applied updates before notifying the user callback
+            if let Some(ref journal) = *journal_fn.load() {
+                for status in statuses.iter().filter(|status| status.error.is_none()) {
+                    if let Some((_, bytes)) = journaled.iter().find(|(id, _)| *id == status.update_id) {
+                        (journal.1)(&index_uid, &bytes[..], status);
+                    }
+                }
+            }
 
-            // call the user callback when the update and the result are written consistently
+            // call the user callback when the updates and the results are written consistently
             if let Some(ref callback) = *update_fn.load() {
-                (callback)(index_uid, status);
+                for status in statuses {
+                    (callback)(&index_uid, status);
+                }
+            }
+
+            if failed {
+                break;
             }
         }
+
+        // the loop can be left before the results are written, the
+        // updates it was processing can be canceled from now on
+        index.processing_updates.lock().unwrap().clear();
     }
 
     debug!("update loop system stopped");
@@ -165,6 +352,37 @@ fn update_awaiter(
     Ok(())
 }
 
+/// Applies the update in its own transaction of the main env, the
+/// transaction is only committed if the update was successful.
+fn apply_update(
+    env: &heed::Env,
+    index: &Index,
+    update_id: u64,
+    update: update::Update,
+    importer: Option<&update::BoxImportFn>,
+) -> MResult<update::ProcessedUpdateResult> {
+    let mut main_writer = env.typed_write_txn::<MainT>()?;
+    let status = update::update_task(&mut main_writer, index, update_id, update, importer)?;
+    if status.error.is_none() {
+        main_writer.commit()?;
+    } else {
+        main_writer.abort()?;
+    }
+    Ok(status)
+}
+
+/// Removes the processed updates from the queue and stores their results, whether they were
+/// successful or not.
+fn write_update_results(update_env: &heed::Env, index: &Index, statuses: &[update::ProcessedUpdateResult]) -> MResult<()> {
+    let mut update_writer = update_env.typed_write_txn::<UpdateT>()?;
+    for status in statuses {
+        index.updates.del_update(&mut update_writer, status.update_id)?;
+        index.updates_results.put_update_result(&mut update_writer, status.update_id, status)?;
+    }
+    update_writer.commit()?;
+    Ok(())
+}
+
 /// Ensures Meilisearch version is compatible with the database, returns an error versions mismatch.
 /// If create is set to true, a VERSION file is created with the current version.
 fn version_guard(path: &Path, create: bool) -> MResult<(u32, u32, u32)> {
@@ -241,36 +459,44 @@ impl Database {
         let database_version = version_guard(path.as_ref(), !main_path.exists() && !update_path.exists())?;
 
         fs::create_dir_all(&main_path)?;
+        let main_map_size = grown_map_size(&main_path, options.main_map_size, options.max_map_size);
         let env = heed::EnvOpenOptions::new()
-            .map_size(options.main_map_size)
+            .map_size(main_map_size)
             .max_dbs(3000)
             .open(main_path)?;
 
         fs::create_dir_all(&update_path)?;
+        let update_map_size = grown_map_size(&update_path, options.update_map_size, options.max_map_size);
         let update_env = heed::EnvOpenOptions::new()
-            .map_size(options.update_map_size)
+            .map_size(update_map_size)
             .max_dbs(3000)
             .open(update_path)?;
 
         let common_store = env.create_poly_database(Some("common"))?;
         let indexes_store = env.create_database::<Str, Unit>(Some("indexes"))?;
+        let trash_store = env.create_database::<Str, SerdeBincode<TrashedIndex>>(Some("trash"))?;
         let update_fn = Arc::new(ArcSwapFn::empty());
+        let journal_fn = Arc::new(ArcSwapJournalFn::empty());
+        let import_fn = Arc::new(ArcSwapImportFn::empty());
+        let stopping = Arc::new(AtomicBool::new(false));
+        let storage_full = Arc::new(AtomicBool::new(false));
 
         // list all indexes that needs to be opened
         let mut must_open = Vec::new();
         let reader = env.read_txn()?;
         for result in indexes_store.iter(&reader)? {
             let (index_uid, _) = result?;
-            must_open.push(index_uid.to_owned());
+            let storage = common_store.get::<_, Str, Str>(&reader, &storage_key(index_uid))?.unwrap_or(index_uid);
+            must_open.push((index_uid.to_owned(), storage.to_owned()));
         }
 
         reader.abort()?;
 
         // open the previously aggregated indexes
         let mut indexes = HashMap::new();
-        for index_uid in must_open {
+        for (index_uid, storage) in must_open {
             let (sender, receiver) = crossbeam_channel::unbounded();
-            let index = match store::open(&env, &update_env, &index_uid, sender.clone())? {
+            let index = match store::open(&env, &update_env, &storage, sender.clone(), storage_full.clone())? {
                 Some(index) => index,
                 None => {
                     log::warn!(
@@ -281,20 +507,31 @@ impl Database {
                 }
             };
 
+            let reader = env.typed_read_txn::<MainT>()?;
+            index.read_only.store(index.main.read_only(&reader)?, Ordering::SeqCst);
+            reader.abort()?;
+
             let env_clone = env.clone();
             let update_env_clone = update_env.clone();
             let index_clone = index.clone();
-            let name_clone = index_uid.clone();
+            let uid = Arc::new(RwLock::new(index_uid.clone()));
+            let uid_clone = uid.clone();
             let update_fn_clone = update_fn.clone();
+            let journal_fn_clone = journal_fn.clone();
+            let import_fn_clone = import_fn.clone();
+            let stopping_clone = stopping.clone();
 
-            let handle = thread::spawn(move || {
+            let update_loop = thread::spawn(move || {
                 update_awaiter(
                     receiver,
                     env_clone,
                     update_env_clone,
-                    &name_clone,
+                    uid_clone,
                     update_fn_clone,
+                    journal_fn_clone,
+                    import_fn_clone,
                     index_clone,
+                    stopping_clone,
                 )
             });
 
@@ -302,7 +539,7 @@ impl Database {
             // possible pre-boot updates are consumed
             sender.send(UpdateEvent::NewUpdate).unwrap();
 
-            let result = indexes.insert(index_uid, (index, handle));
+            let result = indexes.insert(index_uid, OpenedIndex { index, storage, uid, update_loop });
             assert!(
                 result.is_none(),
                 "The index should not have been already open"
@@ -314,16 +551,24 @@ impl Database {
             update_env,
             common_store,
             indexes_store,
+            trash_store,
+            trash_retention: options.trash_retention,
             indexes: RwLock::new(indexes),
+            swap_lock: Mutex::new(()),
             update_fn,
+            journal_fn,
+            import_fn,
+            stopping,
+            storage_full,
             database_version,
+            map_sizes: (main_map_size, update_map_size),
         })
     }
 
     pub fn open_index(&self, name: impl AsRef<str>) -> Option<Index> {
         let indexes_lock = self.indexes.read().unwrap();
         match indexes_lock.get(name.as_ref()) {
-            Some((index, ..)) => Some(index.clone()),
+            Some(opened) => Some(opened.index.clone()),
             None => None,
         }
     }
@@ -339,14 +584,27 @@ impl Database {
         let name = name.as_ref();
         let mut indexes_lock = self.indexes.write().unwrap();
 
+        // the databases named after the uid may belong to an index it has been swapped with
+        // or to an index in the trash
+        let trashed_storages = self.trashed_storages()?;
+        let mut storage = name.to_owned();
+        let mut suffix = 0;
+        while indexes_lock.values().any(|opened| opened.storage == storage) || trashed_storages.contains(&storage) {
+            suffix += 1;
+            storage = format!("{}-{}", name, suffix);
+        }
+
         match indexes_lock.entry(name.to_owned()) {
             Entry::Occupied(_) => Err(crate::Error::IndexAlreadyExists),
             Entry::Vacant(entry) => {
                 let (sender, receiver) = crossbeam_channel::unbounded();
-                let index = store::create(&self.env, &self.update_env, name, sender)?;
+                let index = store::create(&self.env, &self.update_env, &storage, sender, self.storage_full.clone())?;
 
                 let mut writer = self.env.typed_write_txn::<MainT>()?;
                 self.indexes_store.put(&mut writer, name, &())?;
+                if storage != name {
+                    self.common_store.put::<_, Str, Str>(&mut writer, &storage_key(name), &storage)?;
+                }
 
                 index.main.put_name(&mut writer, name)?;
                 index.main.put_created_at(&mut writer)?;
@@ -356,22 +614,29 @@ impl Database {
                 let env_clone = self.env.clone();
                 let update_env_clone = self.update_env.clone();
                 let index_clone = index.clone();
-                let name_clone = name.to_owned();
+                let uid = Arc::new(RwLock::new(name.to_owned()));
+                let uid_clone = uid.clone();
                 let update_fn_clone = self.update_fn.clone();
+                let journal_fn_clone = self.journal_fn.clone();
+                let import_fn_clone = self.import_fn.clone();
+                let stopping_clone = self.stopping.clone();
 
-                let handle = thread::spawn(move || {
+                let update_loop = thread::spawn(move || {
                     update_awaiter(
                         receiver,
                         env_clone,
                         update_env_clone,
-                        &name_clone,
+                        uid_clone,
                         update_fn_clone,
+                        journal_fn_clone,
+                        import_fn_clone,
                         index_clone,
+                        stopping_clone,
                     )
                 });
 
                 writer.commit()?;
-                entry.insert((index.clone(), handle));
+                entry.insert(OpenedIndex { index: index.clone(), storage, uid, update_loop });
 
                 Ok(index)
             }
@@ -382,21 +647,71 @@ impl Database {
         let name = name.as_ref();
         let mut indexes_lock = self.indexes.write().unwrap();
 
+        if indexes_lock.get(name).map_or(false, |opened| opened.index.is_read_only()) {
+            return Err(Error::IndexReadOnly);
+        }
+
         match indexes_lock.remove_entry(name) {
-            Some((name, (index, handle))) => {
+            Some((name, opened)) => {
                 // remove the index name from the list of indexes
                 // and clear all the LMDB dbi
                 let mut writer = self.env.write_txn()?;
                 self.indexes_store.delete(&mut writer, &name)?;
+                self.common_store.delete::<_, Str>(&mut writer, &storage_key(&name))?;
                 writer.commit()?;
 
                 // send a stop event to the update loop of the index
-                index.updates_notifier.send(UpdateEvent::MustClear).unwrap();
+                opened.index.updates_notifier.send(UpdateEvent::MustClear).unwrap();
+
+                drop(indexes_lock);
+
+                // join the update loop thread to ensure it is stopped
+                opened.update_loop.join().unwrap()?;
+
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Deletes an index, it is kept in the trash and can be restored with [`Database::restore_index`]
+    /// until the trash retention expires. An index previously trashed under the same uid is purged.
+    /// The index is deleted right away if there is no trash retention.
+    pub fn trash_index(&self, name: impl AsRef<str>) -> MResult<bool> {
+        if self.trash_retention.is_none() {
+            return self.delete_index(name);
+        }
+
+        let name = name.as_ref();
+        let mut indexes_lock = self.indexes.write().unwrap();
+
+        if indexes_lock.get(name).map_or(false, |opened| opened.index.is_read_only()) {
+            return Err(Error::IndexReadOnly);
+        }
+
+        match indexes_lock.remove_entry(name) {
+            Some((name, opened)) => {
+                let reader = self.env.typed_read_txn::<MainT>()?;
+                let previous = self.trash_store.get(&reader, &name)?;
+                reader.abort()?;
+                if let Some(previous) = previous {
+                    self.clear_storage(&previous.storage)?;
+                }
+
+                let trashed = TrashedIndex { storage: opened.storage.clone(), deleted_at: Utc::now() };
+                let mut writer = self.env.typed_write_txn::<MainT>()?;
+                self.indexes_store.delete(&mut writer, &name)?;
+                self.common_store.delete::<_, Str>(&mut writer, &storage_key(&name))?;
+                self.trash_store.put(&mut writer, &name, &trashed)?;
+                writer.commit()?;
+
+                // send a stop event to the update loop of the index, the enqueued updates are kept
+                opened.index.updates_notifier.send(UpdateEvent::MustStop).unwrap();
 
                 drop(indexes_lock);
 
                 // join the update loop thread to ensure it is stopped
-                handle.join().unwrap()?;
+                opened.update_loop.join().unwrap()?;
 
                 Ok(true)
             }
@@ -404,6 +719,246 @@ impl Database {
         }
     }
 
+    /// Brings back an index from the trash under the uid it had, its enqueued updates are resumed.
+    /// Returns none if there is no index in the trash with this uid.
+    pub fn restore_index(&self, name: impl AsRef<str>) -> MResult<Option<Index>> {
+        let name = name.as_ref();
+        let mut indexes_lock = self.indexes.write().unwrap();
+
+        if indexes_lock.contains_key(name) {
+            return Err(Error::IndexAlreadyExists);
+        }
+
+        let reader = self.env.typed_read_txn::<MainT>()?;
+        let trashed = self.trash_store.get(&reader, name)?;
+        reader.abort()?;
+        let storage = match trashed {
+            Some(trashed) => trashed.storage,
+            None => return Ok(None),
+        };
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let index = match store::open(&self.env, &self.update_env, &storage, sender.clone(), self.storage_full.clone())? {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        let mut writer = self.env.typed_write_txn::<MainT>()?;
+        self.trash_store.delete(&mut writer, name)?;
+        self.indexes_store.put(&mut writer, name, &())?;
+        self.put_index_storage(&mut writer, name, &storage)?;
+        index.read_only.store(index.main.read_only(&writer)?, Ordering::SeqCst);
+
+        let env_clone = self.env.clone();
+        let update_env_clone = self.update_env.clone();
+        let index_clone = index.clone();
+        let uid = Arc::new(RwLock::new(name.to_owned()));
+        let uid_clone = uid.clone();
+        let update_fn_clone = self.update_fn.clone();
+        let journal_fn_clone = self.journal_fn.clone();
+        let import_fn_clone = self.import_fn.clone();
+        let stopping_clone = self.stopping.clone();
+
+        let update_loop = thread::spawn(move || {
+            update_awaiter(
+                receiver,
+                env_clone,
+                update_env_clone,
+                uid_clone,
+                update_fn_clone,
+                journal_fn_clone,
+                import_fn_clone,
+                index_clone,
+                stopping_clone,
+            )
+        });
+
+        writer.commit()?;
+        // process the updates that were enqueued when the index was trashed
+        sender.send(UpdateEvent::NewUpdate).unwrap();
+        indexes_lock.insert(name.to_owned(), OpenedIndex { index: index.clone(), storage, uid, update_loop });
+
+        Ok(Some(index))
+    }
+
+    /// Returns the uids of the indexes in the trash along with their deletion date.
+    pub fn trashed_indexes(&self) -> MResult<Vec<(String, TrashedIndex)>> {
+        let reader = self.env.typed_read_txn::<MainT>()?;
+        let mut trashed = Vec::new();
+        for result in self.trash_store.iter(&reader)? {
+            let (uid, index) = result?;
+            trashed.push((uid.to_owned(), index));
+        }
+        reader.abort()?;
+        Ok(trashed)
+    }
+
+    /// Definitely deletes the indexes that have been in the trash for longer than the trash
+    /// retention, returns the number of indexes deleted.
+    pub fn purge_trash(&self) -> MResult<usize> {
+        let retention = match self.trash_retention {
+            Some(retention) => retention,
+            None => return Ok(0),
+        };
+        let limit = match Utc::now().checked_sub_signed(retention) {
+            Some(limit) => limit,
+            None => return Ok(0),
+        };
+
+        // restoring an index while it is purged must not reopen it
+        let _indexes_lock = self.indexes.write().unwrap();
+
+        let mut purged = 0;
+        for (uid, trashed) in self.trashed_indexes()? {
+            if trashed.deleted_at < limit {
+                self.clear_storage(&trashed.storage)?;
+                let mut writer = self.env.typed_write_txn::<MainT>()?;
+                self.trash_store.delete(&mut writer, &uid)?;
+                writer.commit()?;
+                purged += 1;
+            }
+        }
+
+        Ok(purged)
+    }
+
+    fn trashed_storages(&self) -> MResult<Vec<String>> {
+        let trashed = self.trashed_indexes()?;
+        Ok(trashed.into_iter().map(|(_, trashed)| trashed.storage).collect())
+    }
+
+    /// Clears the databases of an index that is not opened, nothing is done if they don't exist.
+    fn clear_storage(&self, storage: &str) -> MResult<()> {
+        let (sender, _) = crossbeam_channel::unbounded();
+        if let Some(index) = store::open(&self.env, &self.update_env, storage, sender, Arc::default())? {
+            let mut writer = self.env.typed_write_txn::<MainT>()?;
+            let mut update_writer = self.update_env.typed_write_txn::<UpdateT>()?;
+            store::clear(&mut writer, &mut update_writer, &index)?;
+            writer.commit()?;
+            update_writer.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Exchanges the uids of two indexes at once: the searches and the updates made with one
+    /// uid are made on the documents and settings of the other index right after. The updates
+    /// enqueued before the swap are applied to the index they were enqueued on, whatever its uid.
+    /// The names of the indexes are only exchanged if they are their uids.
+    /// Returns false if one of the indexes doesn't exist.
+    pub fn swap_indexes(&self, lhs: &str, rhs: &str) -> MResult<bool> {
+        let _swap_lock = self.swap_lock.lock().unwrap();
+
+        let (lhs_shared, rhs_shared) = {
+            let indexes_lock = self.indexes.read().unwrap();
+            match (indexes_lock.get(lhs), indexes_lock.get(rhs)) {
+                (Some(lhs_opened), Some(rhs_opened)) => {
+                    if lhs_opened.index.is_read_only() || rhs_opened.index.is_read_only() {
+                        return Err(Error::IndexReadOnly);
+                    }
+                    (lhs_opened.uid.clone(), rhs_opened.uid.clone())
+                },
+                _ => return Ok(false),
+            }
+        };
+        if lhs == rhs {
+            return Ok(true);
+        }
+
+        // the update loops report their updates under their uid, waiting for them to be done
+        // ensures that no update is reported under the new uid before the swap is visible
+        let mut lhs_uid = lhs_shared.write().unwrap();
+        let mut rhs_uid = rhs_shared.write().unwrap();
+
+        let mut indexes_lock = self.indexes.write().unwrap();
+        let is_unchanged = |uid: &str, shared: &SharedUid| {
+            indexes_lock.get(uid).map_or(false, |opened| Arc::ptr_eq(&opened.uid, shared))
+        };
+        // one of the indexes has been deleted in the meantime
+        if !is_unchanged(lhs, &lhs_shared) || !is_unchanged(rhs, &rhs_shared) {
+            return Ok(false);
+        }
+
+        let mut writer = self.env.typed_write_txn::<MainT>()?;
+        self.put_index_storage(&mut writer, lhs, &indexes_lock[rhs].storage)?;
+        self.put_index_storage(&mut writer, rhs, &indexes_lock[lhs].storage)?;
+        // the names that are the uids of the indexes, the default ones, follow them
+        for (old_uid, new_uid) in &[(lhs, rhs), (rhs, lhs)] {
+            let main = indexes_lock[*old_uid].index.main;
+            if main.name(&writer)?.as_deref() == Some(*old_uid) {
+                main.put_name(&mut writer, new_uid)?;
+            }
+        }
+        writer.commit()?;
+
+        let lhs_opened = indexes_lock.remove(lhs).unwrap();
+        let rhs_opened = indexes_lock.remove(rhs).unwrap();
+        indexes_lock.insert(lhs.to_owned(), rhs_opened);
+        indexes_lock.insert(rhs.to_owned(), lhs_opened);
+        *lhs_uid = rhs.to_owned();
+        *rhs_uid = lhs.to_owned();
+
+        Ok(true)
+    }
+
+    /// Makes an index reject or accept the updates again, the flag is stored along with the
+    /// index. The updates enqueued before the index is made read-only are still applied.
+    /// Returns false if the index doesn't exist.
+    pub fn set_index_read_only(&self, name: &str, read_only: bool) -> MResult<bool> {
+        let indexes_lock = self.indexes.read().unwrap();
+        let index = match indexes_lock.get(name) {
+            Some(opened) => &opened.index,
+            None => return Ok(false),
+        };
+
+        let mut writer = self.env.typed_write_txn::<MainT>()?;
+        index.main.put_read_only(&mut writer, read_only)?;
+        writer.commit()?;
+        index.read_only.store(read_only, Ordering::SeqCst);
+
+        Ok(true)
+    }
+
+    fn put_index_storage(&self, writer: &mut MainWriter, index_uid: &str, storage: &str) -> MResult<()> {
+        if index_uid == storage {
+            self.common_store.delete::<_, Str>(writer, &storage_key(index_uid))?;
+        } else {
+            self.common_store.put::<_, Str, Str>(writer, &storage_key(index_uid), storage)?;
+        }
+        Ok(())
+    }
+
+    /// Stops the update loops once their current update is processed, the updates still
+    /// enqueued are kept and processed when the database is opened again.
+    pub fn stop_updates(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    /// Stops the update loops and waits for their current update to be committed, the indexes
+    /// can't be used anymore afterward.
+    pub fn close(&self) -> MResult<()> {
+        self.stop_updates();
+
+        let opened: Vec<_> = self.indexes.write().unwrap().drain().collect();
+        for (_, opened) in &opened {
+            // the update loop may already be stopped
+            let _ = opened.index.updates_notifier.send(UpdateEvent::MustStop);
+        }
+
+        for (name, opened) in opened {
+            opened.update_loop.join().unwrap()?;
+            debug!("update loop of {} stopped", name);
+        }
+
+        Ok(())
+    }
+
+    /// The flag making all the indexes refuse the updates with [`Error::InsufficientStorage`],
+    /// it is set by the caller monitoring the storage while it is almost full. The updates
+    /// already enqueued are still processed.
+    pub fn storage_full(&self) -> Arc<AtomicBool> {
+        self.storage_full.clone()
+    }
+
     pub fn set_update_callback(&self, update_fn: BoxUpdateFn) {
         let update_fn = Some(Arc::new(update_fn));
         self.update_fn.swap(update_fn);
@@ -413,6 +968,19 @@ impl Database {
         self.update_fn.swap(None);
     }
 
+    /// Sets the callback receiving the applied updates of the indexes accepted by the filter.
+    pub fn set_journal_callback(&self, filter_fn: BoxJournalFilterFn, journal_fn: BoxJournalFn) {
+        let journal_fn = Some(Arc::new((filter_fn, journal_fn)));
+        self.journal_fn.swap(journal_fn);
+    }
+
+    /// Sets the importer downloading the files of the documents imports, the imports
+    /// processed while none is set fail.
+    pub fn set_import_callback(&self, import_fn: update::BoxImportFn) {
+        let import_fn = Some(Arc::new(import_fn));
+        self.import_fn.swap(import_fn);
+    }
+
     pub fn main_read_txn(&self) -> MResult<MainReader> {
         Ok(self.env.typed_read_txn::<MainT>()?)
     }
@@ -480,6 +1048,12 @@ impl Database {
     }
 
     pub fn copy_and_compact_to_path<P: AsRef<Path>>(&self, path: P) -> MResult<(File, File)> {
+        self.copy_to_path(path, CompactionOption::Enabled)
+    }
+
+    /// Copies the environments to the path, without compaction the pages are copied as they are,
+    /// which is faster and keeps the unchanged pages at the same place between two copies.
+    pub fn copy_to_path<P: AsRef<Path>>(&self, path: P, compaction: CompactionOption) -> MResult<(File, File)> {
         let path = path.as_ref();
 
         let env_path = path.join("main");
@@ -498,10 +1072,10 @@ impl Database {
                 current_version_patch).as_bytes())?;
 
         let env_path = env_path.join("data.mdb");
-        let env_file = self.env.copy_to_path(&env_path, CompactionOption::Enabled)?;
+        let env_file = self.env.copy_to_path(&env_path, compaction)?;
 
         let env_update_path = env_update_path.join("data.mdb");
-        match self.update_env.copy_to_path(env_update_path, CompactionOption::Enabled) {
+        match self.update_env.copy_to_path(env_update_path, compaction) {
             Ok(update_env_file) => Ok((env_file, update_env_file)),
             Err(e) => {
                 fs::remove_file(env_path)?;
@@ -533,6 +1107,65 @@ impl Database {
         Ok(())
     }
 
+    /// Returns the API keys stored by the HTTP layer, the engine doesn't interpret them.
+    pub fn api_keys<T>(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<T>>
+    where T: Serialize + DeserializeOwned + 'static,
+    {
+        Ok(self.common_store().get::<_, Str, SerdeJson<T>>(reader, API_KEYS_KEY)?)
+    }
+
+    pub fn put_api_keys<T>(&self, writer: &mut heed::RwTxn<MainT>, keys: &T) -> MResult<()>
+    where T: Serialize + DeserializeOwned + 'static,
+    {
+        self.common_store().put::<_, Str, SerdeJson<T>>(writer, API_KEYS_KEY, keys)?;
+        Ok(())
+    }
+
+    /// Returns the targets to which the HTTP layer forwards the changes made to the documents.
+    pub fn firehoses<T>(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<T>>
+    where T: Serialize + DeserializeOwned + 'static,
+    {
+        Ok(self.common_store().get::<_, Str, SerdeJson<T>>(reader, FIREHOSES_KEY)?)
+    }
+
+    pub fn put_firehoses<T>(&self, writer: &mut heed::RwTxn<MainT>, firehoses: &T) -> MResult<()>
+    where T: Serialize + DeserializeOwned + 'static,
+    {
+        self.common_store().put::<_, Str, SerdeJson<T>>(writer, FIREHOSES_KEY, firehoses)?;
+        Ok(())
+    }
+
+    /// Returns the experimental features enabled on this instance by the HTTP layer.
+    pub fn experimental_features<T>(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<T>>
+    where T: Serialize + DeserializeOwned + 'static,
+    {
+        Ok(self.common_store().get::<_, Str, SerdeJson<T>>(reader, EXPERIMENTAL_FEATURES_KEY)?)
+    }
+
+    pub fn put_experimental_features<T>(&self, writer: &mut heed::RwTxn<MainT>, features: &T) -> MResult<()>
+    where T: Serialize + DeserializeOwned + 'static,
+    {
+        self.common_store().put::<_, Str, SerdeJson<T>>(writer, EXPERIMENTAL_FEATURES_KEY, features)?;
+        Ok(())
+    }
+
+    /// Returns the position reached by the HTTP layer in the stream of the given source,
+    /// from which it ingests documents.
+    pub fn ingestion_offsets<T>(&self, reader: &heed::RoTxn<MainT>, source: &str) -> MResult<Option<T>>
+    where T: Serialize + DeserializeOwned + 'static,
+    {
+        let key = format!("{}-{}", INGESTION_OFFSETS_KEY, source);
+        Ok(self.common_store().get::<_, Str, SerdeJson<T>>(reader, &key)?)
+    }
+
+    pub fn put_ingestion_offsets<T>(&self, writer: &mut heed::RwTxn<MainT>, source: &str, offsets: &T) -> MResult<()>
+    where T: Serialize + DeserializeOwned + 'static,
+    {
+        let key = format!("{}-{}", INGESTION_OFFSETS_KEY, source);
+        self.common_store().put::<_, Str, SerdeJson<T>>(writer, &key, offsets)?;
+        Ok(())
+    }
+
     pub fn set_healthy(&self, writer: &mut heed::RwTxn<MainT>) -> MResult<()> {
         let common_store = self.common_store();
         common_store.delete::<_, Str>(writer, UNHEALTHY_KEY)?;
@@ -589,6 +1222,10 @@ impl Database {
     }
 
     pub fn version(&self) -> (u32, u32, u32) { self.database_version }
+
+    /// The sizes of the main and update maps, they can be larger than
+    /// the ones requested if the maps were grown when opened.
+    pub fn map_sizes(&self) -> (usize, usize) { self.map_sizes }
 }
 
 #[cfg(test)]
@@ -598,7 +1235,7 @@ mod tests {
     use crate::bucket_sort::SortResult;
     use crate::criterion::{self, CriteriaBuilder};
     use crate::update::{ProcessedUpdateResult, UpdateStatus};
-    use crate::settings::Settings;
+    use crate::settings::{Settings, SettingsUpdate};
     use crate::{Document, DocumentId};
     use serde::de::IgnoredAny;
     use std::sync::mpsc;
@@ -1215,6 +1852,129 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn swap_indexes() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |name: &str, update: ProcessedUpdateResult| {
+            sender.send((name.to_string(), update.update_id)).unwrap()
+        };
+        database.create_index("products").unwrap();
+        let new_index = database.create_index("products_new").unwrap();
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        new_index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = new_index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|(_, id)| *id == update_id);
+
+        assert!(database.swap_indexes("products", "products_new").unwrap());
+        assert!(!database.swap_indexes("products", "unknown").unwrap());
+
+        let index = database.open_index("products").unwrap();
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 1);
+        assert_eq!(index.main.name(&reader).unwrap().as_deref(), Some("products"));
+        let old_index = database.open_index("products_new").unwrap();
+        assert_eq!(old_index.main.number_of_documents(&reader).unwrap(), 0);
+        reader.abort().unwrap();
+
+        // the updates are reported under the new uid of the index
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 2, "name": "Kevin" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let (name, _) = receiver.iter().find(|(_, id)| *id == update_id).unwrap();
+        assert_eq!(name, "products");
+
+        // the databases of the index now named products can't be reused by a new index
+        assert!(database.delete_index("products_new").unwrap());
+        let recreated = database.create_index("products_new").unwrap();
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(recreated.main.number_of_documents(&reader).unwrap(), 0);
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 2);
+    }
+
+    #[test]
+    fn read_only_index() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let index = database.create_index("products").unwrap();
+        assert!(database.set_index_read_only("products", true).unwrap());
+        assert!(!database.set_index_read_only("unknown", true).unwrap());
+        let reader = db.main_read_txn().unwrap();
+        assert!(index.main.read_only(&reader).unwrap());
+        reader.abort().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let result = additions.finalize(&mut update_writer);
+        assert_matches!(result, Err(Error::IndexReadOnly));
+        let result = index.settings_update(&mut update_writer, SettingsUpdate::default());
+        assert_matches!(result, Err(Error::IndexReadOnly));
+        update_writer.abort().unwrap();
+
+        database.create_index("products_new").unwrap();
+        assert_matches!(database.swap_indexes("products", "products_new"), Err(Error::IndexReadOnly));
+        assert_matches!(database.delete_index("products"), Err(Error::IndexReadOnly));
+
+        assert!(database.set_index_read_only("products", false).unwrap());
+        let mut update_writer = db.update_write_txn().unwrap();
+        assert!(index.settings_update(&mut update_writer, SettingsUpdate::default()).is_ok());
+        update_writer.commit().unwrap();
+        assert!(database.delete_index("products").unwrap());
+    }
+
+    #[test]
+    fn trash_and_restore_index() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let options = DatabaseOptions { trash_retention: Some(chrono::Duration::hours(1)), ..DatabaseOptions::default() };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+        let db = &database;
+
+        let index = database.create_index("products").unwrap();
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_name(&mut writer, "Products").unwrap();
+        writer.commit().unwrap();
+
+        assert!(database.trash_index("products").unwrap());
+        assert!(database.open_index("products").is_none());
+        let trashed: Vec<_> = database.trashed_indexes().unwrap().into_iter().map(|(uid, _)| uid).collect();
+        assert_eq!(trashed, vec!["products".to_string()]);
+
+        // an index created under the uid of a trashed index doesn't share its databases
+        database.create_index("products").unwrap();
+        assert_matches!(database.restore_index("products"), Err(Error::IndexAlreadyExists));
+        assert!(database.delete_index("products").unwrap());
+
+        let index = database.restore_index("products").unwrap().unwrap();
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.name(&reader).unwrap().as_deref(), Some("Products"));
+        reader.abort().unwrap();
+        assert!(database.trashed_indexes().unwrap().is_empty());
+        assert!(database.restore_index("products").unwrap().is_none());
+
+        // nothing expired yet
+        assert!(database.trash_index("products").unwrap());
+        assert_eq!(database.purge_trash().unwrap(), 0);
+    }
+
     #[test]
     fn check_number_ordering() {
         let dir = tempfile::tempdir().unwrap();
@@ -1317,4 +2077,177 @@ mod tests {
         );
         assert_matches!(iter.next(), None);
     }
+
+    #[test]
+    fn cancel_enqueued_update() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+        let index = database.create_index("test").unwrap();
+
+        // updates are pushed without notifying the update loop to keep them enqueued
+        let mut update_writer = db.update_write_txn().unwrap();
+        let first_id = update::push_clear_all(&mut update_writer, index.updates, index.updates_results).unwrap();
+        let second_id = update::push_clear_all(&mut update_writer, index.updates, index.updates_results).unwrap();
+        update_writer.commit().unwrap();
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        assert!(index.cancel_update(&mut update_writer, second_id).unwrap());
+        assert!(!index.cancel_update(&mut update_writer, second_id).unwrap());
+        assert!(!index.cancel_update(&mut update_writer, 42).unwrap());
+        update_writer.commit().unwrap();
+
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, second_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Canceled { content }) if content.canceled_at.is_some());
+        let result = index.update_status(&update_reader, first_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Enqueued { .. }));
+
+        let statuses = index.all_updates_status(&update_reader).unwrap();
+        assert_eq!(statuses.len(), 2);
+    }
+
+    #[test]
+    fn close_keeps_enqueued_updates() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+        let index = database.create_index("test").unwrap();
+
+        database.stop_updates();
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = update::push_clear_all(&mut update_writer, index.updates, index.updates_results).unwrap();
+        update_writer.commit().unwrap();
+        index.updates_notifier.send(UpdateEvent::NewUpdate).unwrap();
+
+        database.close().unwrap();
+        assert!(database.open_index("test").is_none());
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Enqueued { .. }));
+        update_reader.abort().unwrap();
+        drop(database);
+
+        // the update is processed once the database is opened again
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.open_index("test").unwrap();
+        let mut processed = false;
+        for _ in 0..100 {
+            let update_reader = database.update_read_txn().unwrap();
+            let result = index.update_status(&update_reader, update_id).unwrap();
+            update_reader.abort().unwrap();
+            if let Some(UpdateStatus::Processed { .. }) = result {
+                processed = true;
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(processed);
+    }
+
+    #[test]
+    fn warmup_index() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+        database.set_update_callback(Box::new(update_fn));
+
+        let reader = db.main_read_txn().unwrap();
+        let empty_size = index.warmup(&reader).unwrap();
+        reader.abort().unwrap();
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        let description = "a long description ".repeat(1000);
+        additions.update_document(serde_json::json!({ "id": 1, "description": description }));
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.into_iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let size = index.warmup(&reader).unwrap();
+        assert!(size > empty_size);
+        // the documents are not warmed up
+        assert!(size < index.compute_size(&reader).unwrap());
+    }
+
+    #[test]
+    fn incremental_index_size() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "the quick brown fox" }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "jumps over the lazy dog" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.size(&reader).unwrap(), index.compute_size(&reader).unwrap());
+        reader.abort().unwrap();
+
+        // the sizes of the stores follow the documents replaced and deleted
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "the quick brown fox and the cat" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_external_docid("2".to_string());
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = deletion.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.size(&reader).unwrap(), index.compute_size(&reader).unwrap());
+    }
+
+    #[test]
+    fn grow_map_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let page_size = 4096;
+        fs::write(dir.path().join("data.mdb"), vec![0; 8 * page_size]).unwrap();
+
+        // the maps are never grown without a maximum map size
+        assert_eq!(grown_map_size(dir.path(), 8 * page_size, None), 8 * page_size);
+        // doubled until the data fills less than three quarters of the map
+        assert_eq!(grown_map_size(dir.path(), 8 * page_size, Some(64 * page_size)), 16 * page_size);
+        assert_eq!(grown_map_size(dir.path(), 4 * page_size, Some(64 * page_size)), 16 * page_size);
+        // but never above the maximum map size
+        assert_eq!(grown_map_size(dir.path(), 4 * page_size, Some(12 * page_size)), 8 * page_size);
+        // a map that is not almost full is kept as is
+        assert_eq!(grown_map_size(dir.path(), 32 * page_size, Some(64 * page_size)), 32 * page_size);
+    }
 }
@@ -0,0 +1,86 @@
+//! The dates written as strings in the documents and in the filters are compared as the number
+//! of seconds since the Unix epoch, the dates without a time zone are in UTC.
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+
+const NAIVE_DATETIME_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M"];
+
+/// Returns the timestamp of a date written `2023-01-01`, `2023-01-01 10:30:00` or
+/// `2023-01-01T10:30:00+02:00`.
+pub fn parse_date(text: &str) -> Option<i64> {
+    let text = text.trim();
+    // the dates always start with the four digits of their year
+    if text.len() < 10 || !text.as_bytes()[..4].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.timestamp());
+    }
+    if let Some(datetime) = NAIVE_DATETIME_FORMATS.iter().find_map(|format| NaiveDateTime::parse_from_str(text, format).ok()) {
+        return Some(datetime.timestamp());
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().map(|date| date.and_hms(0, 0, 0).timestamp())
+}
+
+/// Returns the timestamp of a date relative to `now`, written `NOW`, `NOW-7d` or `NOW+1h`.
+/// The units are the seconds `s`, minutes `m`, hours `h`, days `d` and weeks `w`.
+pub fn parse_relative_date(text: &str, now: DateTime<Utc>) -> Option<i64> {
+    let text = text.trim();
+    if !text.starts_with("NOW") {
+        return None;
+    }
+
+    let offset = &text["NOW".len()..];
+    if offset.is_empty() {
+        return Some(now.timestamp());
+    }
+
+    let (sign, offset) = match offset.as_bytes()[0] {
+        b'+' => (1, &offset[1..]),
+        b'-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+    let unit_position = offset.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = offset.split_at(unit_position);
+    let amount: i64 = amount.parse().ok()?;
+    let duration = match unit {
+        "s" => Duration::seconds(amount),
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return None,
+    };
+
+    Some(now.timestamp() + sign * duration.num_seconds())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("2023-01-01"), Some(1_672_531_200));
+        assert_eq!(parse_date("2023-01-01 00:00:30"), Some(1_672_531_230));
+        assert_eq!(parse_date("2023-01-01T00:00:30"), Some(1_672_531_230));
+        assert_eq!(parse_date("2023-01-01T02:00:00+02:00"), Some(1_672_531_200));
+        assert_eq!(parse_date("2023-01-01T00:00:00.500Z"), Some(1_672_531_200));
+        assert_eq!(parse_date("2023"), None);
+        assert_eq!(parse_date("2023-13-01"), None);
+        assert_eq!(parse_date("hello world"), None);
+    }
+
+    #[test]
+    fn relative_dates() {
+        let now = Utc.ymd(2023, 1, 8).and_hms(0, 0, 0);
+        assert_eq!(parse_relative_date("NOW", now), Some(now.timestamp()));
+        assert_eq!(parse_relative_date("NOW-7d", now), parse_date("2023-01-01"));
+        assert_eq!(parse_relative_date("NOW+1w", now), parse_date("2023-01-15"));
+        assert_eq!(parse_relative_date("NOW-90m", now), parse_date("2023-01-07 22:30:00"));
+        assert_eq!(parse_relative_date("NOW-7y", now), None);
+        assert_eq!(parse_relative_date("NOW7d", now), None);
+        assert_eq!(parse_relative_date("2023-01-01", now), None);
+    }
+}
@@ -2,6 +2,7 @@ use crate::serde::{DeserializerError, SerializerError};
 use serde_json::Error as SerdeJsonError;
 use pest::error::Error as PestError;
 use crate::filters::Rule;
+use crate::update::DocumentError;
 use std::{error, fmt, io};
 
 pub use bincode::Error as BincodeError;
@@ -17,11 +18,22 @@ pub type MResult<T> = Result<T, Error>;
 pub enum Error {
     Bincode(bincode::Error),
     Deserializer(DeserializerError),
+    DuplicateDocumentId(String),
     FacetError(FacetError),
     FilterParseError(PestError<Rule>),
     Fst(fst::Error),
     Heed(heed::Error),
+    /// The files of a documents import could not be downloaded or parsed.
+    ImportFailed(String),
     IndexAlreadyExists,
+    IndexReadOnly,
+    /// The updates are refused while the storage of the database is almost full.
+    InsufficientStorage,
+    /// The update would make the index grow past one of its quotas.
+    IndexQuotaExceeded(String),
+    /// Some documents of an update are invalid, `errors` is bounded
+    /// while `count` is the total number of invalid documents.
+    InvalidDocuments { first: Box<Error>, errors: Vec<DocumentError>, count: usize },
     Io(io::Error),
     MaxFieldsLimitExceeded,
     MissingDocumentId,
@@ -41,9 +53,15 @@ impl ErrorCode for Error {
         match self {
             FacetError(_) => Code::Facet,
             FilterParseError(_) => Code::Filter,
+            ImportFailed(_) => Code::ImportFailed,
             IndexAlreadyExists => Code::IndexAlreadyExists,
+            IndexReadOnly => Code::IndexReadOnly,
+            InsufficientStorage => Code::InsufficientStorage,
+            IndexQuotaExceeded(_) => Code::IndexQuotaExceeded,
+            InvalidDocuments { first, .. } => first.error_code(),
             MissingPrimaryKey => Code::MissingPrimaryKey,
             MissingDocumentId => Code::MissingDocumentId,
+            DuplicateDocumentId(_) => Code::DuplicateDocumentId,
             MaxFieldsLimitExceeded => Code::MaxFieldsLimitExceeded,
             Schema(s) =>  s.error_code(),
             WordIndexMissing
@@ -145,11 +163,20 @@ impl fmt::Display for Error {
         match self {
             Bincode(e) => write!(f, "bincode error; {}", e),
             Deserializer(e) => write!(f, "deserializer error; {}", e),
+            DuplicateDocumentId(id) => write!(f, "document id {:?} is used by more than one document", id),
             FacetError(e) => write!(f, "error processing facet filter: {}", e),
             FilterParseError(e) => write!(f, "error parsing filter; {}", e),
             Fst(e) => write!(f, "fst error; {}", e),
             Heed(e) => write!(f, "heed error; {}", e),
+            ImportFailed(e) => write!(f, "the documents import failed; {}", e),
             IndexAlreadyExists => write!(f, "index already exists"),
+            IndexReadOnly => write!(f, "index is read-only, it must be made writable before being modified"),
+            InsufficientStorage => write!(f, "the storage is almost full, the updates are refused until space is freed"),
+            IndexQuotaExceeded(quota) => write!(f, "index quota exceeded; {}", quota),
+            InvalidDocuments { first, count, .. } if *count > 1 => {
+                write!(f, "{} documents are invalid, the first one because: {}", count, first)
+            }
+            InvalidDocuments { first, .. } => write!(f, "{}", first),
             Io(e) => write!(f, "{}", e),
             MaxFieldsLimitExceeded => write!(f, "maximum number of fields in a document exceeded"),
             MissingDocumentId => write!(f, "document id is missing"),
@@ -217,7 +244,7 @@ impl fmt::Display for FacetError {
             InvalidFormat(found) => write!(f, "invalid facet: {}, facets should be \"facetName:facetValue\"", found),
             AttributeNotFound(attr) => write!(f, "unknown {:?} attribute", attr),
             AttributeNotSet { found, expected } => write!(f, "`{}` is not set as a faceted attribute. available facet attributes: {}", found, expected.join(", ")),
-            InvalidDocumentAttribute(attr) => write!(f, "invalid document attribute {}, accepted types: String and [String]", attr),
+            InvalidDocumentAttribute(attr) => write!(f, "invalid document attribute {}, accepted types: String, Number and [String, Number]", attr),
             NoAttributesForFaceting => write!(f, "impossible to perform faceted search, no attributes for faceting are set"),
         }
     }
@@ -171,6 +171,8 @@ pub fn add_to_facet_map(
 ) -> Result<(), FacetError> {
     let value = match value {
         Value::String(s) => s,
+        // the numbers are faceted on their string representation
+        Value::Number(n) => n.to_string(),
         // ignore null
         Value::Null => return Ok(()),
         value => return Err(FacetError::InvalidDocumentAttribute(value.to_string())),
@@ -1,8 +1,10 @@
 use std::str::FromStr;
 use std::cmp::Ordering;
 
+use crate::date::{parse_date, parse_relative_date};
 use crate::error::Error;
 use crate::{store::Index, DocumentId, MainT};
+use chrono::Utc;
 use heed::RoTxn;
 use meilisearch_schema::{FieldId, Schema};
 use pest::error::{Error as PestError, ErrorVariant};
@@ -18,6 +20,12 @@ enum ConditionType {
     LessEqual,
     GreaterEqual,
     NotEqual,
+    Exists,
+    IsNull,
+    IsEmpty,
+    In,
+    All,
+    Contains,
 }
 
 /// We need to infer type when the filter is constructed
@@ -26,10 +34,19 @@ enum ConditionType {
 struct ConditionValue<'a> {
     string: &'a str,
     boolean: Option<bool>,
-    number: Option<Number>
+    number: Option<Number>,
+    /// The timestamp of the value when it is a date, compared to the dates and numbers of the documents.
+    date: Option<i64>,
+    /// Whether the value is a date relative to the time the filter is parsed.
+    relative: bool,
 }
 
 impl<'a> ConditionValue<'a> {
+    /// The value of the conditions only testing the presence of an attribute.
+    fn none() -> Self {
+        ConditionValue { string: "", boolean: None, number: None, date: None, relative: false }
+    }
+
     pub fn new(value: &Pair<'a, Rule>) -> Self {
         match value.as_rule() {
             Rule::string | Rule::word => {
@@ -39,8 +56,10 @@ impl<'a> ConditionValue<'a> {
                     "false" => Some(false),
                     _ => None,
                 };
-                let number = Number::from_str(value.as_str()).ok();
-                ConditionValue { string, boolean, number }
+                let relative_date = parse_relative_date(string, Utc::now());
+                let date = parse_date(string).or(relative_date);
+                let number = Number::from_str(value.as_str()).ok().or_else(|| date.map(Number::from));
+                ConditionValue { string, boolean, number, date, relative: relative_date.is_some() }
             },
             _ => unreachable!(),
         }
@@ -57,20 +76,38 @@ impl<'a> ConditionValue<'a> {
     pub fn as_bool(&self) -> Option<bool> {
         self.boolean
     }
+
+    pub fn as_date(&self) -> Option<i64> {
+        self.date
+    }
 }
 
 #[derive(Debug)]
 pub struct Condition<'a> {
     field: FieldId,
     condition: ConditionType,
-    value: ConditionValue<'a>
+    value: ConditionValue<'a>,
+    /// The values of the `IN`, `ANY` and `ALL` conditions.
+    list: Vec<ConditionValue<'a>>,
 }
 
 fn get_field_value<'a>(schema: &Schema, pair: Pair<'a, Rule>) -> Result<(FieldId, ConditionValue<'a>), Error> {
     let mut items = pair.into_inner();
     // lexing ensures that we at least have a key
-    let key = items.next().unwrap();
-    let field = schema
+    let field = get_field(schema, items.next().unwrap())?;
+    let value = ConditionValue::new(&items.next().unwrap());
+    Ok((field, value))
+}
+
+fn get_field_list<'a>(schema: &Schema, pair: Pair<'a, Rule>) -> Result<(FieldId, Vec<ConditionValue<'a>>), Error> {
+    let mut items = pair.into_inner();
+    let field = get_field(schema, items.next().unwrap())?;
+    let list = items.next().unwrap().into_inner().map(|value| ConditionValue::new(&value)).collect();
+    Ok((field, list))
+}
+
+fn get_field(schema: &Schema, key: Pair<Rule>) -> Result<FieldId, Error> {
+    schema
         .id(key.as_str())
         .ok_or_else(|| PestError::new_from_span(
                 ErrorVariant::CustomError {
@@ -80,9 +117,21 @@ fn get_field_value<'a>(schema: &Schema, pair: Pair<'a, Rule>) -> Result<(FieldId
                                  schema.names().collect::<Vec<_>>().join(", ")
                              ),
                 },
-                key.as_span()))?;
-    let value = ConditionValue::new(&items.next().unwrap());
-    Ok((field, value))
+                key.as_span()).into())
+}
+
+/// Whether a value of a document is equal to the value of a condition, the strings are compared
+/// without their case and the dates by their timestamps.
+fn value_equals(value: &Value, expected: &ConditionValue) -> bool {
+    match value {
+        Value::String(s) => match (parse_date(s), expected.as_date()) {
+            (Some(date), Some(expected)) => date == expected,
+            _ => unicase::eq(expected.as_str(), s.as_str()),
+        },
+        Value::Number(n) => expected.as_number().and_then(|expected| compare_numbers(n, expected)) == Some(Ordering::Equal),
+        Value::Bool(b) => expected.as_bool() == Some(*b),
+        _ => false,
+    }
 }
 
 // undefined behavior with big numbers
@@ -104,7 +153,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::Less;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, list: Vec::new() })
     }
 
     pub fn greater(
@@ -113,7 +162,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::Greater;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, list: Vec::new() })
     }
 
     pub fn neq(
@@ -122,7 +171,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::NotEqual;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, list: Vec::new() })
     }
 
     pub fn geq(
@@ -131,7 +180,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::GreaterEqual;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, list: Vec::new() })
     }
 
     pub fn leq(
@@ -140,7 +189,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::LessEqual;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, list: Vec::new() })
     }
 
     pub fn eq(
@@ -149,7 +198,66 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::Equal;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, list: Vec::new() })
+    }
+
+    pub fn exists(
+        item: Pair<'a, Rule>,
+        schema: &'a Schema,
+    ) -> Result<Self, Error> {
+        let field = get_field(schema, item.into_inner().next().unwrap())?;
+        let condition = ConditionType::Exists;
+        Ok(Self { field, condition, value: ConditionValue::none(), list: Vec::new() })
+    }
+
+    pub fn is_null(
+        item: Pair<'a, Rule>,
+        schema: &'a Schema,
+    ) -> Result<Self, Error> {
+        let field = get_field(schema, item.into_inner().next().unwrap())?;
+        let condition = ConditionType::IsNull;
+        Ok(Self { field, condition, value: ConditionValue::none(), list: Vec::new() })
+    }
+
+    pub fn is_empty(
+        item: Pair<'a, Rule>,
+        schema: &'a Schema,
+    ) -> Result<Self, Error> {
+        let field = get_field(schema, item.into_inner().next().unwrap())?;
+        let condition = ConditionType::IsEmpty;
+        Ok(Self { field, condition, value: ConditionValue::none(), list: Vec::new() })
+    }
+
+    pub fn is_in(
+        item: Pair<'a, Rule>,
+        schema: &'a Schema,
+    ) -> Result<Self, Error> {
+        let (field, list) = get_field_list(schema, item)?;
+        let condition = ConditionType::In;
+        Ok(Self { field, condition, value: ConditionValue::none(), list })
+    }
+
+    pub fn all(
+        item: Pair<'a, Rule>,
+        schema: &'a Schema,
+    ) -> Result<Self, Error> {
+        let (field, list) = get_field_list(schema, item)?;
+        let condition = ConditionType::All;
+        Ok(Self { field, condition, value: ConditionValue::none(), list })
+    }
+
+    pub fn contains(
+        item: Pair<'a, Rule>,
+        schema: &'a Schema,
+    ) -> Result<Self, Error> {
+        let (field, value) = get_field_value(schema, item)?;
+        let condition = ConditionType::Contains;
+        Ok(Self { field, condition, value, list: Vec::new() })
+    }
+
+    /// Whether the documents matching the condition change with the time.
+    pub fn is_relative(&self) -> bool {
+        self.value.relative || self.list.iter().any(|value| value.relative)
     }
 
     pub fn test(
@@ -158,15 +266,52 @@ impl<'a> Condition<'a> {
         index: &Index,
         document_id: DocumentId,
     ) -> Result<bool, Error> {
-        match index.document_attribute::<Value>(reader, document_id, self.field)? {
-            Some(Value::Array(values)) => Ok(values.iter().any(|v| self.match_value(Some(v)))),
-            other => Ok(self.match_value(other.as_ref())),
+        let value = index.document_attribute::<Value>(reader, document_id, self.field)?;
+        // the values of an array are its elements, a single value is an array of one element
+        let elements = match &value {
+            Some(Value::Array(values)) => values.iter().collect(),
+            Some(value) => vec![value],
+            None => Vec::new(),
+        };
+        match (&self.condition, &value) {
+            // the attributes set to null exist
+            (ConditionType::Exists, value) => Ok(value.is_some()),
+            (ConditionType::IsNull, value) => Ok(*value == Some(Value::Null)),
+            (ConditionType::IsEmpty, Some(Value::String(s))) => Ok(s.is_empty()),
+            (ConditionType::IsEmpty, Some(Value::Array(values))) => Ok(values.is_empty()),
+            (ConditionType::IsEmpty, Some(Value::Object(object))) => Ok(object.is_empty()),
+            (ConditionType::IsEmpty, _) => Ok(false),
+            (ConditionType::In, _) => Ok(elements.iter().any(|v| self.list.iter().any(|e| value_equals(v, e)))),
+            (ConditionType::All, _) => Ok(self.list.iter().all(|e| elements.iter().any(|v| value_equals(v, e)))),
+            // a string contains its substrings, an array its elements
+            (ConditionType::Contains, Some(Value::String(s))) => {
+                Ok(s.to_lowercase().contains(&self.value.as_str().to_lowercase()))
+            },
+            (ConditionType::Contains, _) => Ok(elements.iter().any(|v| value_equals(v, &self.value))),
+            (_, Some(Value::Array(values))) => Ok(values.iter().any(|v| self.match_value(Some(v)))),
+            (_, other) => Ok(self.match_value(other.as_ref())),
+        }
+    }
+
+    fn match_ordering(&self, ord: Ordering) -> bool {
+        match self.condition {
+            ConditionType::Equal => ord == Ordering::Equal,
+            ConditionType::NotEqual => ord != Ordering::Equal,
+            ConditionType::GreaterEqual => ord != Ordering::Less,
+            ConditionType::LessEqual => ord != Ordering::Greater,
+            ConditionType::Greater => ord == Ordering::Greater,
+            ConditionType::Less => ord == Ordering::Less,
+            _ => false,
         }
     }
 
     fn match_value(&self, value: Option<&Value>) -> bool {
         match value {
             Some(Value::String(s)) => {
+                // the dates are compared by their timestamps
+                if let (Some(date), Some(value)) = (parse_date(s), self.value.as_date()) {
+                    return self.match_ordering(date.cmp(&value))
+                }
                 let value = self.value.as_str();
                 match self.condition {
                     ConditionType::Equal => unicase::eq(value, &s),
@@ -177,15 +322,7 @@ impl<'a> Condition<'a> {
             Some(Value::Number(n)) => { 
                 if let Some(value) = self.value.as_number() {
                     if let Some(ord) = compare_numbers(&n, value) {
-                        let res =  match self.condition {
-                            ConditionType::Equal => ord == Ordering::Equal,
-                            ConditionType::NotEqual => ord != Ordering::Equal,
-                            ConditionType::GreaterEqual => ord != Ordering::Less,
-                            ConditionType::LessEqual => ord != Ordering::Greater,
-                            ConditionType::Greater => ord == Ordering::Greater,
-                            ConditionType::Less => ord == Ordering::Less,
-                        };
-                        return res
+                        return self.match_ordering(ord)
                     } 
                 } 
                 false
@@ -1,3 +1,17 @@
+//! The filters select the documents with conditions on their attributes. A condition on an
+//! attribute holding an array is applied to its elements:
+//!
+//! - `tags = rust` and the other comparisons match if one of the elements matches,
+//! - `tags IN [rust, search]`, or `tags ANY [rust, search]`, matches if one of the elements is
+//!   one of the values, on a single value it matches if the value is one of them,
+//! - `tags ALL [rust, search]` matches if each of the values is one of the elements,
+//! - `tags CONTAINS rust` matches if one of the elements is the value, on a string it matches
+//!   if the value is a part of the string.
+//!
+//! The facets distribution counts the documents matching the query and the facet filters for
+//! each value of an attribute, before the filters are applied. A document is counted once in
+//! the count of each of its elements, so the sum of the counts of an attribute can be greater
+//! than the number of documents found.
 mod parser;
 mod condition;
 
@@ -30,6 +44,16 @@ impl<'a> Filter<'a> {
         Self::build(lexed.next().unwrap().into_inner(), schema)
     }
 
+    /// Whether the filter contains a date relative to the time it is parsed, like `NOW-7d`,
+    /// the documents it matches change with the time.
+    pub fn is_relative(&self) -> bool {
+        match self {
+            Filter::Condition(c) => c.is_relative(),
+            Filter::Or(lhs, rhs) | Filter::And(lhs, rhs) => lhs.is_relative() || rhs.is_relative(),
+            Filter::Not(op) => op.is_relative(),
+        }
+    }
+
     pub fn test(
         &self,
         reader: &RoTxn<MainT>,
@@ -59,6 +83,12 @@ impl<'a> Filter<'a> {
                 Rule::neq => Ok(Filter::Condition(Condition::neq(pair, schema)?)),
                 Rule::geq => Ok(Filter::Condition(Condition::geq(pair, schema)?)),
                 Rule::leq => Ok(Filter::Condition(Condition::leq(pair, schema)?)),
+                Rule::exists => Ok(Filter::Condition(Condition::exists(pair, schema)?)),
+                Rule::is_null => Ok(Filter::Condition(Condition::is_null(pair, schema)?)),
+                Rule::is_empty => Ok(Filter::Condition(Condition::is_empty(pair, schema)?)),
+                Rule::is_in => Ok(Filter::Condition(Condition::is_in(pair, schema)?)),
+                Rule::all => Ok(Filter::Condition(Condition::all(pair, schema)?)),
+                Rule::contains => Ok(Filter::Condition(Condition::contains(pair, schema)?)),
                 Rule::prgm => Self::build(pair.into_inner(), schema),
                 Rule::term => Self::build(pair.into_inner(), schema),
                 Rule::not => Ok(Filter::Not(Box::new(Self::build(
@@ -91,6 +121,12 @@ mod test {
         assert!(FilterParser::parse(Rule::prgm, "NOT field=").is_err());
         assert!(FilterParser::parse(Rule::prgm, "N").is_err());
         assert!(FilterParser::parse(Rule::prgm, "(field=1").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "EXISTS").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IS").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IS true").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IN []").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field ALL rust").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field CONTAINS [rust]").is_err());
         assert!(FilterParser::parse(Rule::prgm, "(field=1))").is_err());
         assert!(FilterParser::parse(Rule::prgm, "field=1ORfield=2").is_err());
         assert!(FilterParser::parse(Rule::prgm, "field=1 ( OR field=2)").is_err());
@@ -123,5 +159,32 @@ mod test {
         assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' <= 10"#).is_ok());
         assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' != 10"#).is_ok());
         assert!(FilterParser::parse(Rule::prgm, r#"bar != 10"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"EXISTS field"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"NOT EXISTS field AND field = 1"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"field IS NULL OR field IS EMPTY"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' IS NULL"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"tags IN [rust, "full text"]"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"tags ANY [rust] AND tags ALL ['rust', search]"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"NOT tags CONTAINS rust"#).is_ok());
+    }
+
+    #[test]
+    fn relative_filters() {
+        let mut schema = Schema::with_primary_key("id");
+        for name in &["date", "owner", "tags"] {
+            schema.insert(name).unwrap();
+        }
+        let is_relative = |expr: &str| Filter::parse(expr, &schema).unwrap().is_relative();
+
+        assert!(is_relative("date > NOW-7d"));
+        assert!(is_relative("owner = 1 AND NOT date <= NOW"));
+        assert!(is_relative("owner = 1 OR tags IN [rust, "NOW+1h"]"));
+        assert!(!is_relative("date > 2023-01-01 AND owner = 1"));
+        assert!(!is_relative("tags IN [rust, search]"));
+
+        // the filter of a tenant token is combined with the filter of the search
+        let tenant = Filter::parse("owner = 1", &schema).unwrap();
+        let search = Filter::parse("date > NOW-1d", &schema).unwrap();
+        assert!(Filter::And(Box::new(tenant), Box::new(search)).is_relative());
     }
 }
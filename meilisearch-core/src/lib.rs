@@ -9,6 +9,7 @@ extern crate pest_derive;
 mod automaton;
 mod bucket_sort;
 mod database;
+mod date;
 mod distinct_map;
 mod error;
 mod filters;
@@ -20,22 +21,27 @@ mod query_words_mapper;
 mod ranked_map;
 mod raw_document;
 mod reordered_attrs;
+pub mod analysis;
 pub mod criterion;
 pub mod facets;
 pub mod raw_indexer;
 pub mod serde;
 pub mod settings;
+pub mod stop_words;
+pub mod synonyms;
 pub mod store;
 pub mod update;
 
-pub use self::database::{BoxUpdateFn, Database, DatabaseOptions, MainT, UpdateT, MainWriter, MainReader, UpdateWriter, UpdateReader};
+pub use self::bucket_sort::{CriterionProfile, SortProfile};
+pub use self::database::{BoxJournalFilterFn, BoxJournalFn, BoxUpdateFn, Database, DatabaseOptions, TrashedIndex, MainT, UpdateT, MainWriter, MainReader, UpdateWriter, UpdateReader};
 pub use self::error::{Error, HeedError, FstError, MResult, pest_error, FacetError};
+pub use heed::CompactionOption;
 pub use self::filters::Filter;
 pub use self::number::{Number, ParseNumberError};
 pub use self::ranked_map::RankedMap;
 pub use self::raw_document::RawDocument;
 pub use self::store::Index;
-pub use self::update::{EnqueuedUpdateResult, ProcessedUpdateResult, UpdateStatus, UpdateType};
+pub use self::update::{DocumentError, EnqueuedUpdateResult, ProcessedUpdateResult, UpdateStatus, UpdateType};
 pub use meilisearch_types::{DocIndex, DocumentId, Highlight};
 pub use meilisearch_schema::Schema;
 pub use query_words_mapper::QueryWordsMapper;
@@ -86,7 +92,14 @@ fn highlights_from_raw_document<'a, 'tag, 'txn>(
                     } else {
                         prefix_damerau_levenshtein(query.as_bytes(), input).1
                     };
-                    u16::try_from(len).unwrap_or(u16::max_value())
+                    // the length is in bytes of the indexed word, the highlight is in characters
+                    // of the word written in the document, that can contain stripped vowel marks
+                    if len >= input.len() {
+                        di.char_length
+                    } else {
+                        let chars = input[..len].iter().filter(|b| *b & 0xC0 != 0x80).count();
+                        u16::try_from(chars).unwrap_or(u16::max_value()).min(di.char_length)
+                    }
                 },
                 _ => di.char_length,
             };
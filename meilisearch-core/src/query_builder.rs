@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::ops::{Deref, Range};
 use std::time::Duration;
@@ -8,7 +9,8 @@ use sdset::{SetOperation, SetBuf, Set};
 
 use meilisearch_schema::FieldId;
 
-use crate::bucket_sort::{bucket_sort, bucket_sort_with_distinct, SortResult, placeholder_document_sort, facet_count};
+use crate::bucket_sort::{bucket_sort, bucket_sort_with_distinct, count_hits, SortResult, placeholder_document_sort, facet_count};
+use crate::bucket_sort::{disjunctive_facet_count, DisjunctiveCountDocids};
 use crate::database::MainT;
 use crate::facets::FacetFilter;
 use crate::distinct_map::{DistinctMap, BufferedDistinctMap};
@@ -20,11 +22,14 @@ pub struct QueryBuilder<'c, 'f, 'd, 'i> {
     criteria: Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
     filter: Option<Box<dyn Fn(DocumentId) -> bool + 'f>>,
+    placeholder_sort: Option<Box<dyn Fn(DocumentId, DocumentId) -> Ordering + 'f>>,
     distinct: Option<(Box<dyn Fn(DocumentId) -> Option<u64> + 'd>, usize)>,
     timeout: Option<Duration>,
     index: &'i store::Index,
     facet_filter: Option<FacetFilter>,
     facets: Option<Vec<(FieldId, String)>>,
+    disjunctive_facets: Option<Vec<(FieldId, String)>>,
+    pinned: Vec<DocumentId>,
 }
 
 impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
@@ -42,16 +47,31 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         self.facets = facets;
     }
 
+    /// sets facet attributes for which to return the count ignoring their own facet filters,
+    /// the counts of the other values of a selected attribute stay as if it was not selected
+    pub fn set_disjunctive_facets(&mut self, facets: Option<Vec<(FieldId, String)>>) {
+        self.disjunctive_facets = facets;
+    }
+
+    /// sets the documents returned before the ranked ones, they are counted in the number of
+    /// hits even when they don't match the query, they must match the filters
+    pub fn set_pinned(&mut self, pinned: Vec<DocumentId>) {
+        self.pinned = pinned;
+    }
+
     pub fn with_criteria(index: &'i store::Index, criteria: Criteria<'c>) -> Self {
         QueryBuilder {
             criteria,
             searchable_attrs: None,
             filter: None,
+            placeholder_sort: None,
             distinct: None,
             timeout: None,
             index,
             facet_filter: None,
             facets: None,
+            disjunctive_facets: None,
+            pinned: Vec::new(),
         }
     }
 
@@ -62,6 +82,15 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         self.filter = Some(Box::new(function))
     }
 
+    /// Sorts the documents of the searches without query before the ranking rules sorting
+    /// them, the searches with a query sort them with the criteria.
+    pub fn with_placeholder_sort<F>(&mut self, function: F)
+    where
+        F: Fn(DocumentId, DocumentId) -> Ordering + 'f,
+    {
+        self.placeholder_sort = Some(Box::new(function))
+    }
+
     pub fn with_fetch_timeout(&mut self, timeout: Duration) {
         self.timeout = Some(timeout)
     }
@@ -79,14 +108,22 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
     }
 
     /// returns the documents ids associated with a facet filter by computing the union and
-    /// intersection of the document sets
-    fn facets_docids(&self, reader: &MainReader) -> MResult<Option<SetBuf<DocumentId>>> {
+    /// intersection of the document sets, `None` if there is no facet filter
+    pub fn facets_docids(&self, reader: &MainReader) -> MResult<Option<SetBuf<DocumentId>>> {
+        self.facets_docids_excluding(reader, None)
+    }
+
+    /// returns the documents ids associated with the facet filter, ignoring the conditions on
+    /// the excluded attribute, `None` if no condition remains
+    fn facets_docids_excluding(&self, reader: &MainReader, excluded: Option<FieldId>) -> MResult<Option<SetBuf<DocumentId>>> {
         let facet_docids = match self.facet_filter {
             Some(ref facets) => {
                 let mut ands = Vec::with_capacity(facets.len());
                 let mut ors = Vec::new();
                 for f in facets.deref() {
                     match f {
+                        Either::Left(keys) if keys.iter().any(|key| Some(key.key()) == excluded) => (),
+                        Either::Right(key) if Some(key.key()) == excluded => (),
                         Either::Left(keys) => {
                             ors.reserve(keys.len());
                             for key in keys {
@@ -111,6 +148,9 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                         }
                     };
                 }
+                if ands.is_empty() {
+                    return Ok(None);
+                }
                 let ands: Vec<_> = ands.iter().map(Cow::deref).collect();
                 Some(
                     sdset::multi::OpBuilder::from_vec(ands)
@@ -132,6 +172,7 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         // value to a set of matching documents. The HashMaps are them collected in another
         // HashMap, associating each HashMap to it's field.
         let facet_count_docids = self.facet_count_docids(reader)?;
+        let disjunctive_count_docids = self.disjunctive_count_docids(reader)?;
 
         match self.distinct {
             Some((distinct, distinct_size)) => bucket_sort_with_distinct(
@@ -140,7 +181,9 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 range,
                 facets_docids,
                 facet_count_docids,
+                disjunctive_count_docids,
                 self.filter,
+                &self.pinned,
                 distinct,
                 distinct_size,
                 self.criteria,
@@ -153,7 +196,9 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 range,
                 facets_docids,
                 facet_count_docids,
+                disjunctive_count_docids,
                 self.filter,
+                &self.pinned,
                 self.criteria,
                 self.searchable_attrs,
                 self.index,
@@ -166,19 +211,25 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
             Some(docids) => {
                 // We sort the docids from facets according to the criteria set by the user
                 let mut sorted_docids = docids.clone().into_vec();
-                let mut sort_result = match self.index.main.ranked_map(reader)? {
-                    Some(ranked_map) => {
-                        placeholder_document_sort(&mut sorted_docids, self.index, reader, &ranked_map)?;
-                        self.sort_result_from_docids(&sorted_docids, range)
-                    },
-                    // if we can't perform a sort, we return documents unordered
-                    None => self.sort_result_from_docids(&docids, range),
-                };
+                // if we can't perform a sort, we return documents unordered
+                if let Some(ranked_map) = self.index.main.ranked_map(reader)? {
+                    placeholder_document_sort(&mut sorted_docids, self.index, reader, &ranked_map)?;
+                }
+                if let Some(sort) = &self.placeholder_sort {
+                    sorted_docids.sort_by(|a, b| sort(*a, *b));
+                }
+                let mut sort_result = self.sort_result_from_docids(&sorted_docids, range);
+                sort_result.nb_hits = count_hits(&docids, &self.pinned);
 
                 if let Some(f) = self.facet_count_docids(reader)? {
                     sort_result.exhaustive_facets_count = Some(true);
                     sort_result.facets = Some(facet_count(f, &docids));
                 }
+                if let Some(f) = self.disjunctive_count_docids(reader)? {
+                    sort_result.exhaustive_facets_count = Some(true);
+                    let all_docids = self.index.main.internal_docids(reader)?;
+                    sort_result.disjunctive_facets = Some(disjunctive_facet_count(f, &all_docids));
+                }
 
                 Ok(sort_result)
             },
@@ -186,7 +237,14 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 match self.index.main.sorted_document_ids_cache(reader)? {
                     // build result from cached document ids
                     Some(docids) => {
-                        let mut sort_result = self.sort_result_from_docids(&docids, range);
+                        let mut sort_result = match &self.placeholder_sort {
+                            Some(sort) => {
+                                let mut sorted_docids = docids.to_vec();
+                                sorted_docids.sort_by(|a, b| sort(*a, *b));
+                                self.sort_result_from_docids(&sorted_docids, range)
+                            }
+                            None => self.sort_result_from_docids(&docids, range),
+                        };
 
                         if let Some(f) = self.facet_count_docids(reader)? {
                             sort_result.exhaustive_facets_count = Some(true);
@@ -194,6 +252,11 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                             let document_set = SetBuf::from_dirty(Vec::from(docids));
                             sort_result.facets = Some(facet_count(f, &document_set));
                         }
+                        if let Some(f) = self.disjunctive_count_docids(reader)? {
+                            sort_result.exhaustive_facets_count = Some(true);
+                            let all_docids = self.index.main.internal_docids(reader)?;
+                            sort_result.disjunctive_facets = Some(disjunctive_facet_count(f, &all_docids));
+                        }
 
                         Ok(sort_result)
                     },
@@ -209,13 +272,7 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
             Some(ref field_ids) => {
                 let mut facet_count_map = HashMap::new();
                 for (field_id, field_name) in field_ids {
-                    let mut key_map = HashMap::new();
-                    for pair in self.index.facets.field_document_ids(reader, *field_id)? {
-                        let (facet_key, document_ids) = pair?;
-                        let value = facet_key.value();
-                        key_map.insert(value.to_string(), document_ids);
-                    }
-                    facet_count_map.insert(field_name.clone(), key_map);
+                    facet_count_map.insert(field_name.clone(), self.field_values_docids(reader, *field_id)?);
                 }
                 Ok(Some(facet_count_map))
             }
@@ -223,6 +280,30 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         }
     }
 
+    fn disjunctive_count_docids<'a>(&self, reader: &'a MainReader) -> MResult<Option<DisjunctiveCountDocids<'a>>> {
+        match self.disjunctive_facets {
+            Some(ref field_ids) => {
+                let mut facet_count_map = HashMap::new();
+                for (field_id, field_name) in field_ids {
+                    let others_docids = self.facets_docids_excluding(reader, Some(*field_id))?;
+                    facet_count_map.insert(field_name.clone(), (others_docids, self.field_values_docids(reader, *field_id)?));
+                }
+                Ok(Some(facet_count_map))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn field_values_docids<'a>(&self, reader: &'a MainReader, field_id: FieldId) -> MResult<HashMap<String, (&'a str, Cow<'a, Set<DocumentId>>)>> {
+        let mut key_map = HashMap::new();
+        for pair in self.index.facets.field_document_ids(reader, field_id)? {
+            let (facet_key, document_ids) = pair?;
+            let value = facet_key.value();
+            key_map.insert(value.to_string(), document_ids);
+        }
+        Ok(key_map)
+    }
+
     fn sort_result_from_docids(&self, docids: &[DocumentId], range: Range<usize>) -> SortResult {
         let mut sort_result = SortResult::default();
         let mut result = match self.filter {
@@ -257,6 +338,7 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
 
         sort_result.documents = result;
         sort_result.nb_hits = docids.len();
+        sort_result.profile.candidates = docids.len();
         sort_result
     }
 
@@ -285,14 +367,18 @@ mod tests {
     use sdset::SetBuf;
     use tempfile::TempDir;
 
-    use crate::automaton::normalize_str;
     use crate::bucket_sort::SimpleMatch;
     use crate::database::{Database, DatabaseOptions};
+    use crate::settings::NormalizationSettings;
     use crate::store::Index;
     use crate::DocIndex;
     use crate::Document;
     use meilisearch_schema::Schema;
 
+    fn normalize_str(string: &str) -> String {
+        NormalizationSettings::default().normalize(string)
+    }
+
     fn set_from_stream<'f, I, S>(stream: I) -> fst::Set<Vec<u8>>
     where
         I: for<'a> fst::IntoStreamer<'a, Into = S, Item = &'a [u8]>,
@@ -3,17 +3,18 @@ use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 use std::time::Instant;
-use std::{cmp, fmt, iter::once};
+use std::{cmp, fmt};
 
 use fst::{IntoStreamer, Streamer};
 use itertools::{EitherOrBoth, merge_join_by};
-use meilisearch_tokenizer::split_query_string;
+use meilisearch_tokenizer::{split_query_string_with_separators, Separators};
 use sdset::{Set, SetBuf, SetOperation};
 use log::debug;
 
 use crate::database::MainT;
 use crate::{store, DocumentId, DocIndex, MResult, FstSetCow};
-use crate::automaton::{normalize_str, build_dfa, build_prefix_dfa, build_exact_dfa};
+use crate::automaton::{build_dfa, build_prefix_dfa, build_exact_dfa, build_exact_prefix_dfa};
+use crate::settings::{NormalizationSettings, TypoToleranceSettings};
 use crate::QueryWordsMapper;
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -56,6 +57,19 @@ impl Operation {
         let kind = QueryKind::Phrase(vec![left.to_owned(), right.to_owned()]);
         Operation::Query(Query { id, prefix, exact: true, kind })
     }
+
+    /// The words of a multi-word synonym must follow each other in the documents,
+    /// they are declared in the mapper with the ids following the one of the synonym.
+    fn synonym(id: QueryId, idgen: &mut impl Iterator<Item=QueryId>, mut words: Vec<String>) -> Operation {
+        let exact = words.len() == 1;
+        let kind = if exact {
+            QueryKind::NonTolerant(words.pop().unwrap())
+        } else {
+            idgen.by_ref().take(words.len() - 1).for_each(drop);
+            QueryKind::Phrase(words)
+        };
+        Operation::Query(Query { id, prefix: false, exact, kind })
+    }
 }
 
 pub type QueryId = usize;
@@ -115,9 +129,15 @@ pub struct PostingsList {
 pub struct Context<'a> {
     pub words_set: FstSetCow<'a>,
     pub stop_words: FstSetCow<'a>,
+    pub separators: Separators,
     pub synonyms: store::Synonyms,
     pub postings_lists: store::PostingsLists,
     pub prefix_postings_lists: store::PrefixPostingsListsCache,
+    pub typo_tolerance: TypoToleranceSettings,
+    pub normalization: NormalizationSettings,
+    /// Whether some attributes are indexed as keywords, the whole query is then also
+    /// searched as a single word.
+    pub keyword_attributes: bool,
 }
 
 fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'a str) -> MResult<Option<(&'a str, &'a str)>> {
@@ -145,8 +165,8 @@ fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'
     Ok(best.map(|(_, l, r)| (l, r)))
 }
 
-fn fetch_synonyms(reader: &heed::RoTxn<MainT>, ctx: &Context, words: &[&str]) -> MResult<Vec<Vec<String>>> {
-    let words = normalize_str(&words.join(" "));
+pub(crate) fn fetch_synonyms(reader: &heed::RoTxn<MainT>, ctx: &Context, words: &[&str]) -> MResult<Vec<Vec<String>>> {
+    let words = ctx.normalization.normalize(&words.join(" "));
     let set = ctx.synonyms.synonyms_fst(reader, words.as_bytes())?;
 
     let mut strings = Vec::new();
@@ -172,7 +192,7 @@ where I: IntoIterator<Item=Operation>,
     }
 }
 
-const MAX_NGRAM: usize = 3;
+pub(crate) const MAX_NGRAM: usize = 3;
 
 pub fn create_query_tree(
     reader: &heed::RoTxn<MainT>,
@@ -180,8 +200,8 @@ pub fn create_query_tree(
     query: &str,
 ) -> MResult<(Operation, HashMap<QueryId, Range<usize>>)>
 {
-    let words = split_query_string(query).map(str::to_lowercase);
-    let words = words.filter(|w| !ctx.stop_words.contains(w));
+    let words = split_query_string_with_separators(query, &ctx.separators).map(|w| ctx.normalization.normalize_word(w));
+    let words = words.filter(|w| !w.is_empty() && !ctx.stop_words.contains(w.to_lowercase()));
     let words: Vec<_> = words.enumerate().collect();
 
     let mut mapper = QueryWordsMapper::new(words.iter().map(|(_, w)| w));
@@ -219,18 +239,9 @@ pub fn create_query_tree(
                         let synonyms = fetch_synonyms(reader, ctx, &[word])?
                             .into_iter()
                             .map(|alts| {
-                                let exact = alts.len() == 1;
                                 let id = idgen.next().unwrap();
                                 mapper.declare(range.clone(), id, &alts);
-
-                                let mut idgen = once(id).chain(&mut idgen);
-                                let iter = alts.into_iter().map(|w| {
-                                    let id = idgen.next().unwrap();
-                                    let kind = QueryKind::NonTolerant(w);
-                                    Operation::Query(Query { id, prefix: false, exact, kind })
-                                });
-
-                                create_operation(iter, Operation::And)
+                                Operation::synonym(id, &mut idgen, alts)
                             });
 
                         let original = Operation::tolerant(*id, is_last, word);
@@ -246,17 +257,9 @@ pub fn create_query_tree(
                         let words: Vec<_> = words.iter().map(|(_, s)| s.as_str()).collect();
 
                         for synonym in fetch_synonyms(reader, ctx, &words)? {
-                            let exact = synonym.len() == 1;
                             let id = idgen.next().unwrap();
                             mapper.declare(range.clone(), id, &synonym);
-
-                            let mut idgen = once(id).chain(&mut idgen);
-                            let synonym = synonym.into_iter().map(|s| {
-                                let id = idgen.next().unwrap();
-                                let kind = QueryKind::NonTolerant(s);
-                                Operation::Query(Query { id, prefix: false, exact, kind })
-                            });
-                            group_alts.push(create_operation(synonym, Operation::And));
+                            group_alts.push(Operation::synonym(id, &mut idgen, synonym));
                         }
 
                         let id = idgen.next().unwrap();
@@ -280,7 +283,18 @@ pub fn create_query_tree(
         Ok(alts)
     }
 
-    let alternatives = create_inner(reader, ctx, &mut mapper, &words)?;
+    let mut alternatives = create_inner(reader, ctx, &mut mapper, &words)?;
+
+    // the values of the keyword attributes are single words, separators included
+    if ctx.keyword_attributes && !words.is_empty() {
+        let whole = ctx.normalization.normalize_word(query.trim());
+        if words.len() > 1 || words[0].1 != whole {
+            let id = (words.len() + 1) * 100_usize.pow(MAX_NGRAM as u32 + 1);
+            mapper.declare(0..words.len(), id, &[&whole]);
+            alternatives.push(Operation::non_tolerant(id, true, &whole));
+        }
+    }
+
     let operation = Operation::Or(alternatives);
     let mapping = mapper.mapping();
 
@@ -430,15 +444,21 @@ pub fn traverse_query_tree<'o, 'txn>(
                     Cow::Owned(docids)
 
                 } else {
-                    let dfa = if *prefix { build_prefix_dfa(word) } else { build_dfa(word) };
-
-                    let byte = word.as_bytes()[0];
-                    let mut stream = if byte == u8::max_value() {
-                        ctx.words_set.search(&dfa).ge(&[byte]).into_stream()
-                    } else {
-                        ctx.words_set.search(&dfa).ge(&[byte]).lt(&[byte + 1]).into_stream()
+                    let dfa = match (*prefix, ctx.typo_tolerance.allows_typos(word)) {
+                        (true, true) => build_prefix_dfa(word),
+                        (false, true) => build_dfa(word),
+                        (true, false) => build_exact_prefix_dfa(word),
+                        (false, false) => build_exact_dfa(word),
                     };
 
+                    // the first character of the word is never corrected, the last byte
+                    // of an UTF-8 encoded character is never the max value of a byte
+                    let mut buffer = [0; 4];
+                    let first = word.chars().next().unwrap().encode_utf8(&mut buffer).as_bytes();
+                    let mut next = first.to_vec();
+                    *next.last_mut().unwrap() += 1;
+                    let mut stream = ctx.words_set.search(&dfa).ge(first).lt(&next).into_stream();
+
                     let before = Instant::now();
                     let mut results = Vec::new();
                     while let Some(input) = stream.next() {
@@ -510,36 +530,43 @@ pub fn traverse_query_tree<'o, 'txn>(
             },
             QueryKind::Phrase(words) => {
                 // TODO support prefix and non-prefix exact DFA
-                if let [first, second] = words.as_slice() {
-                    let first = ctx.postings_lists.postings_list(reader, first.as_bytes())?.unwrap_or_default();
-                    let second = ctx.postings_lists.postings_list(reader, second.as_bytes())?.unwrap_or_default();
+                let mut lists = Vec::with_capacity(words.len());
+                for word in words {
+                    lists.push(ctx.postings_lists.postings_list(reader, word.as_bytes())?.unwrap_or_default());
+                }
 
-                    let iter = merge_join_by(first.matches.as_slice(), second.matches.as_slice(), |a, b| {
+                // we keep the chains of matches where each word directly follows the previous one
+                let mut chains: Vec<Vec<DocIndex>> = match lists.first() {
+                    Some(first) => first.matches.iter().map(|m| vec![*m]).collect(),
+                    None => Vec::new(),
+                };
+                for list in lists.iter().skip(1) {
+                    let iter = merge_join_by(chains, list.matches.as_slice(), |chain, b| {
+                        let a = chain.last().unwrap();
                         let x = (a.document_id, a.attribute, (a.word_index as u32) + 1);
                         let y = (b.document_id, b.attribute, b.word_index as u32);
                         x.cmp(&y)
                     });
 
-                    let matches: Vec<_> = iter
+                    chains = iter
                         .filter_map(EitherOrBoth::both)
-                        .flat_map(|(a, b)| once(*a).chain(Some(*b)))
+                        .map(|(mut chain, b)| { chain.push(*b); chain })
                         .collect();
+                }
 
-                    let before = Instant::now();
-                    let mut docids: Vec<_> = matches.iter().map(|m| m.document_id).collect();
-                    docids.dedup();
-                    let docids = SetBuf::new(docids).unwrap();
-                    debug!("{:2$}docids construction took {:.02?}", "", before.elapsed(), depth * 2);
+                let matches: Vec<_> = chains.into_iter().flatten().collect();
 
-                    let matches = Cow::Owned(SetBuf::from_dirty(matches));
-                    let key = PostingsKey { query, input: vec![], distance: 0, is_exact: true };
-                    postings.insert(key, matches);
+                let before = Instant::now();
+                let mut docids: Vec<_> = matches.iter().map(|m| m.document_id).collect();
+                docids.dedup();
+                let docids = SetBuf::new(docids).unwrap();
+                debug!("{:2$}docids construction took {:.02?}", "", before.elapsed(), depth * 2);
 
-                    Cow::Owned(docids)
-                } else {
-                    debug!("{:2$}{:?} skipped", "", words, depth * 2);
-                    Cow::default()
-                }
+                let matches = Cow::Owned(SetBuf::from_dirty(matches));
+                let key = PostingsKey { query, input: vec![], distance: 0, is_exact: *exact };
+                postings.insert(key, matches);
+
+                Cow::Owned(docids)
             },
         };
 
@@ -4,19 +4,29 @@ use std::convert::TryFrom;
 
 use deunicode::deunicode_with_tofu;
 use meilisearch_schema::IndexedPos;
-use meilisearch_tokenizer::{is_cjk, SeqTokenizer, Token, Tokenizer};
+use meilisearch_tokenizer::{is_cjk, SeqTokenizer, Separators, Token, Tokenizer};
 use sdset::SetBuf;
 
+use crate::settings::{NormalizationSettings, TokenizerMode};
 use crate::{DocIndex, DocumentId};
 use crate::FstSetCow;
 
 const WORD_LENGTH_LIMIT: usize = 80;
 
+/// The shortest substring under which the words of the ngram attributes are indexed, in chars.
+const MIN_NGRAM_CHARS: usize = 3;
+
+/// The words of the ngram attributes longer than this, in chars, are not indexed under their substrings.
+const MAX_NGRAM_WORD_CHARS: usize = 32;
+
 type Word = Vec<u8>; // TODO make it be a SmallVec
 
 pub struct RawIndexer<A> {
     word_limit: usize, // the maximum number of indexed words
     stop_words: fst::Set<A>,
+    separators: Separators,
+    normalization: NormalizationSettings,
+    tokenizer_modes: HashMap<IndexedPos, TokenizerMode>,
     words_doc_indexes: BTreeMap<Word, Vec<DocIndex>>,
     docs_words: HashMap<DocumentId, Vec<Word>>,
 }
@@ -35,23 +45,63 @@ impl<A> RawIndexer<A> {
         RawIndexer {
             word_limit: limit,
             stop_words,
+            separators: Separators::default(),
+            normalization: NormalizationSettings::default(),
+            tokenizer_modes: HashMap::new(),
             words_doc_indexes: BTreeMap::new(),
             docs_words: HashMap::new(),
         }
     }
+
+    pub fn with_separators(self, separators: Separators) -> RawIndexer<A> {
+        RawIndexer { separators, ..self }
+    }
+
+    pub fn with_normalization(self, normalization: NormalizationSettings) -> RawIndexer<A> {
+        RawIndexer { normalization, ..self }
+    }
+
+    /// The tokenizers of the attributes that don't use the standard one.
+    pub fn with_tokenizer_modes(self, tokenizer_modes: HashMap<IndexedPos, TokenizerMode>) -> RawIndexer<A> {
+        RawIndexer { tokenizer_modes, ..self }
+    }
+
+    pub(crate) fn tokenizer_mode(&self, indexed_pos: IndexedPos) -> TokenizerMode {
+        self.tokenizer_modes.get(&indexed_pos).copied().unwrap_or_default()
+    }
+
+    /// Moves the words indexed by another indexer into this one, the indexers can fill
+    /// distinct documents or distinct fields of the same documents.
+    pub fn merge<B>(&mut self, other: RawIndexer<B>) {
+        for (word, mut indexes) in other.words_doc_indexes {
+            self.words_doc_indexes.entry(word).or_insert_with(Vec::new).append(&mut indexes);
+        }
+
+        for (id, mut words) in other.docs_words {
+            self.docs_words.entry(id).or_insert_with(Vec::new).append(&mut words);
+        }
+    }
 }
 
 impl<A: AsRef<[u8]>> RawIndexer<A> {
     pub fn index_text(&mut self, id: DocumentId, indexed_pos: IndexedPos, text: &str) -> usize {
         let mut number_of_words = 0;
 
-        for token in Tokenizer::new(text) {
+        let mode = self.tokenizer_mode(indexed_pos);
+        let tokens: Box<dyn Iterator<Item = Token> + '_> = match mode {
+            TokenizerMode::Keyword => Box::new(keyword_tokens(Some(text)).into_iter()),
+            _ => Box::new(Tokenizer::with_separators(text, &self.separators)),
+        };
+
+        for token in tokens {
             let must_continue = index_token(
                 token,
                 id,
                 indexed_pos,
+                mode,
                 self.word_limit,
                 &self.stop_words,
+                &self.normalization,
                 &mut self.words_doc_indexes,
                 &mut self.docs_words,
             );
@@ -71,13 +121,21 @@ impl<A: AsRef<[u8]>> RawIndexer<A> {
         I: IntoIterator<Item = &'s str>,
     {
         let iter = iter.into_iter();
-        for token in SeqTokenizer::new(iter) {
+        let mode = self.tokenizer_mode(indexed_pos);
+        let tokens: Box<dyn Iterator<Item = Token> + '_> = match mode {
+            TokenizerMode::Keyword => Box::new(keyword_tokens(iter).into_iter()),
+            _ => Box::new(SeqTokenizer::with_separators(iter, &self.separators)),
+        };
+
+        for token in tokens {
             let must_continue = index_token(
                 token,
                 id,
                 indexed_pos,
+                mode,
                 self.word_limit,
                 &self.stop_words,
+                &self.normalization,
                 &mut self.words_doc_indexes,
                 &mut self.docs_words,
             );
@@ -113,12 +171,15 @@ impl<A: AsRef<[u8]>> RawIndexer<A> {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn index_token<A>(
     token: Token,
     id: DocumentId,
     indexed_pos: IndexedPos,
+    mode: TokenizerMode,
     word_limit: usize,
     stop_words: &fst::Set<A>,
+    normalization: &NormalizationSettings,
     words_doc_indexes: &mut BTreeMap<Word, Vec<DocIndex>>,
     docs_words: &mut HashMap<DocumentId, Vec<Word>>,
 ) -> bool
@@ -128,37 +189,22 @@ where A: AsRef<[u8]>,
         return false;
     }
 
-    let lower = token.word.to_lowercase();
-    let token = Token {
-        word: &lower,
-        ..token
-    };
-
-    if !stop_words.contains(&token.word) {
+    // the stop words are matched regardless of the case, the highlights
+    // cover the characters of the token written in the document
+    let word = normalization.normalize_word(token.word);
+    if !stop_words.contains(word.to_lowercase()) {
         match token_to_docindex(id, indexed_pos, token) {
             Some(docindex) => {
-                let word = Vec::from(token.word);
-
-                if word.len() <= WORD_LENGTH_LIMIT {
+                let mut words = indexed_words(&word, normalization);
+                if mode == TokenizerMode::Ngram {
+                    words.extend(ngrams(&word));
+                }
+                for word in words {
                     words_doc_indexes
                         .entry(word.clone())
                         .or_insert_with(Vec::new)
                         .push(docindex);
                     docs_words.entry(id).or_insert_with(Vec::new).push(word);
-
-                    if !lower.contains(is_cjk) {
-                        let unidecoded = deunicode_with_tofu(&lower, "");
-                        if unidecoded != lower && !unidecoded.is_empty() {
-                            let word = Vec::from(unidecoded);
-                            if word.len() <= WORD_LENGTH_LIMIT {
-                                words_doc_indexes
-                                    .entry(word.clone())
-                                    .or_insert_with(Vec::new)
-                                    .push(docindex);
-                                docs_words.entry(id).or_insert_with(Vec::new).push(word);
-                            }
-                        }
-                    }
                 }
             }
             None => return false,
@@ -168,6 +214,71 @@ where A: AsRef<[u8]>,
     true
 }
 
+/// The words under which a case folded token is indexed, the token itself and, if the diacritics
+/// are folded and it contains no CJK characters, its ascii transliteration. Words that are too
+/// long are not indexed.
+pub(crate) fn indexed_words(word: &str, normalization: &NormalizationSettings) -> Vec<Word> {
+    let mut words = Vec::new();
+    if word.is_empty() || word.len() > WORD_LENGTH_LIMIT {
+        return words;
+    }
+
+    words.push(Vec::from(word));
+    if normalization.diacritic_folding && !word.contains(is_cjk) {
+        let unidecoded = deunicode_with_tofu(word, "");
+        if unidecoded != word && !unidecoded.is_empty() && unidecoded.len() <= WORD_LENGTH_LIMIT {
+            words.push(Vec::from(unidecoded));
+        }
+    }
+
+    words
+}
+
+/// The substrings of a word, the word itself excluded, under which the words of the
+/// ngram attributes are also indexed.
+fn ngrams(word: &str) -> Vec<Word> {
+    let bounds: Vec<usize> = word.char_indices().map(|(i, _)| i).chain(Some(word.len())).collect();
+    let count = bounds.len() - 1;
+    if count > MAX_NGRAM_WORD_CHARS || word.len() > WORD_LENGTH_LIMIT {
+        return Vec::new();
+    }
+
+    let mut ngrams = Vec::new();
+    for start in 0..count {
+        for end in (start + MIN_NGRAM_CHARS)..=count {
+            if end - start < count {
+                ngrams.push(Vec::from(&word[bounds[start]..bounds[end]]));
+            }
+        }
+    }
+    ngrams
+}
+
+/// The tokens of the values of a keyword attribute, each value is a single word,
+/// the values are separated as if by a hard separator.
+fn keyword_tokens<'s, I>(texts: I) -> Vec<Token<'s>>
+where I: IntoIterator<Item = &'s str>,
+{
+    let hard_space = 8;
+    let mut tokens = Vec::new();
+    let mut char_offset = 0;
+    for text in texts {
+        let word = text.trim();
+        if !word.is_empty() {
+            let leading = text.len() - text.trim_start().len();
+            let index = tokens.len();
+            tokens.push(Token {
+                word,
+                index,
+                word_index: index * hard_space,
+                char_index: char_offset + text[..leading].chars().count(),
+            });
+        }
+        char_offset += text.chars().count() + hard_space;
+    }
+    tokens
+}
+
 fn token_to_docindex(id: DocumentId, indexed_pos: IndexedPos, token: Token) -> Option<DocIndex> {
     let word_index = u16::try_from(token.word_index).ok()?;
     let char_index = u16::try_from(token.char_index).ok()?;
@@ -189,6 +300,35 @@ mod tests {
     use super::*;
     use meilisearch_schema::IndexedPos;
 
+    #[test]
+    fn merge() {
+        let texts = [
+            (DocumentId(0), IndexedPos(0), "Zut, l’aspirateur, j’ai oublié de l’éteindre !"),
+            (DocumentId(0), IndexedPos(1), "l’aspirateur est éteint"),
+            (DocumentId(1), IndexedPos(0), "j’ai oublié l’aspirateur"),
+        ];
+
+        let mut sequential = RawIndexer::new(fst::Set::default());
+        for (docid, indexed_pos, text) in &texts {
+            sequential.index_text(*docid, *indexed_pos, text);
+        }
+
+        let mut merged = RawIndexer::new(fst::Set::default());
+        for (docid, indexed_pos, text) in texts.iter().rev() {
+            let mut indexer = RawIndexer::new(fst::Set::default());
+            indexer.index_text(*docid, *indexed_pos, text);
+            merged.merge(indexer);
+        }
+
+        let sequential = sequential.build();
+        let merged = merged.build();
+        assert_eq!(sequential.words_doc_indexes, merged.words_doc_indexes);
+        assert_eq!(sequential.docs_words.len(), merged.docs_words.len());
+        for (id, words) in &sequential.docs_words {
+            assert_eq!(words.as_fst().as_bytes(), merged.docs_words[id].as_fst().as_bytes());
+        }
+    }
+
     #[test]
     fn strange_apostrophe() {
         let mut indexer = RawIndexer::new(fst::Set::default());
@@ -309,4 +449,19 @@ mod tests {
         assert!(words_doc_indexes.get(&"less".to_owned().into_bytes()).is_some());
         assert!(words_doc_indexes.get(&"more".to_owned().into_bytes()).is_none());
     }
+
+    #[test]
+    fn custom_separators() {
+        let separators = Separators::new(vec!['|'], vec!['.']);
+        let mut indexer = RawIndexer::new(fst::Set::default()).with_separators(separators);
+        indexer.index_text(DocumentId(0), IndexedPos(0), "k8s.io|helm");
+
+        let Indexed {
+            words_doc_indexes, ..
+        } = indexer.build();
+
+        assert!(words_doc_indexes.get(&b"k8s.io"[..]).is_some());
+        assert!(words_doc_indexes.get(&b"helm"[..]).is_some());
+        assert!(words_doc_indexes.get(&b"k8s"[..]).is_none());
+    }
 }
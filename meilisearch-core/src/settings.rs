@@ -2,13 +2,21 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
 use std::iter::IntoIterator;
 
+use meilisearch_tokenizer::{compose_jamo, fold_kana, fold_number, fold_traditional, is_cjk, is_vowel_mark};
 use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use once_cell::sync::Lazy;
 
+use crate::stop_words::StopWords;
+use crate::synonyms::Synonyms;
 use self::RankingRule::*;
 
 pub const DEFAULT_RANKING_RULES: [RankingRule; 6] = [Typo, Words, Proximity, Attribute, WordsPosition, Exactness];
 
+pub const DEFAULT_MAX_VALUES_PER_FACET: usize = 100;
+
+pub const DEFAULT_MAX_PROXIMITY: u16 = 8;
+
 static RANKING_RULE_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
     regex::Regex::new(r"(asc|desc)\(([a-zA-Z0-9-_]*)\)").unwrap()
 });
@@ -24,12 +32,34 @@ pub struct Settings {
     pub searchable_attributes: Option<Option<Vec<String>>>,
     #[serde(default, deserialize_with = "deserialize_some")]
     pub displayed_attributes: Option<Option<BTreeSet<String>>>,
-    #[serde(default, deserialize_with = "deserialize_some")]
+    #[serde(default, deserialize_with = "deserialize_some_stop_words")]
     pub stop_words: Option<Option<BTreeSet<String>>>,
     #[serde(default, deserialize_with = "deserialize_some")]
+    pub separator_tokens: Option<Option<BTreeSet<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub non_separator_tokens: Option<Option<BTreeSet<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub dictionary: Option<Option<BTreeSet<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some_synonyms")]
     pub synonyms: Option<Option<BTreeMap<String, Vec<String>>>>,
     #[serde(default, deserialize_with = "deserialize_some")]
     pub attributes_for_faceting: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub faceting: Option<Option<FacetingSettings>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub query_rules: Option<Option<Vec<QueryRule>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub typo_tolerance: Option<Option<TypoToleranceSettings>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub ranking_behavior: Option<Option<RankingBehavior>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub normalization: Option<Option<NormalizationSettings>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub languages: Option<Option<BTreeSet<Language>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub attribute_tokenizers: Option<Option<BTreeMap<String, TokenizerMode>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub identifier_splitting: Option<Option<bool>>,
 }
 
 // Any value that is present is considered Some value, including null.
@@ -40,6 +70,22 @@ fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
     Deserialize::deserialize(deserializer).map(Some)
 }
 
+// The stop words presets are expanded into their words.
+fn deserialize_some_stop_words<'de, D>(deserializer: D) -> Result<Option<Option<BTreeSet<String>>>, D::Error>
+    where D: Deserializer<'de>
+{
+    let stop_words: Option<StopWords> = Deserialize::deserialize(deserializer)?;
+    Ok(Some(stop_words.map(|StopWords(words)| words)))
+}
+
+// The synonyms rules are expanded into a map.
+fn deserialize_some_synonyms<'de, D>(deserializer: D) -> Result<Option<Option<BTreeMap<String, Vec<String>>>>, D::Error>
+    where D: Deserializer<'de>
+{
+    let synonyms: Option<Synonyms> = Deserialize::deserialize(deserializer)?;
+    Ok(Some(synonyms.map(|Synonyms(synonyms)| synonyms)))
+}
+
 impl Settings {
     pub fn to_update(&self) -> Result<SettingsUpdate, RankingRuleConversionError> {
         let settings = self.clone();
@@ -57,8 +103,19 @@ impl Settings {
             searchable_attributes: settings.searchable_attributes.into(),
             displayed_attributes: settings.displayed_attributes.into(),
             stop_words: settings.stop_words.into(),
+            separator_tokens: settings.separator_tokens.into(),
+            non_separator_tokens: settings.non_separator_tokens.into(),
+            dictionary: settings.dictionary.into(),
             synonyms: settings.synonyms.into(),
             attributes_for_faceting: settings.attributes_for_faceting.into(),
+            faceting: settings.faceting.into(),
+            query_rules: settings.query_rules.into(),
+            typo_tolerance: settings.typo_tolerance.into(),
+            ranking_behavior: settings.ranking_behavior.into(),
+            normalization: settings.normalization.into(),
+            languages: settings.languages.into(),
+            attribute_tokenizers: settings.attribute_tokenizers.into(),
+            identifier_splitting: settings.identifier_splitting.into(),
         })
     }
 }
@@ -70,6 +127,23 @@ pub enum UpdateState<T> {
     Nothing,
 }
 
+// The settings added after an update was enqueued are left untouched by it.
+impl<T> Default for UpdateState<T> {
+    fn default() -> UpdateState<T> {
+        UpdateState::Nothing
+    }
+}
+
+impl<T> UpdateState<T> {
+    /// Returns the state resulting of the application of `other` after this one.
+    fn then(self, other: UpdateState<T>) -> UpdateState<T> {
+        match other {
+            UpdateState::Nothing => self,
+            other => other,
+        }
+    }
+}
+
 impl <T> From<Option<Option<T>>> for UpdateState<T> {
     fn from(opt: Option<Option<T>>) -> UpdateState<T> {
         match opt {
@@ -155,6 +229,238 @@ impl RankingRule {
     }
 }
 
+/// How the values of the facets are returned in the facets distribution of the searches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct FacetingSettings {
+    /// The maximum number of values returned for each facet.
+    pub max_values_per_facet: usize,
+    /// The order of the values of each facet, `*` applies to the facets that are not listed.
+    pub sort_facet_values_by: BTreeMap<String, FacetValuesOrder>,
+}
+
+impl Default for FacetingSettings {
+    fn default() -> FacetingSettings {
+        let mut sort_facet_values_by = BTreeMap::new();
+        sort_facet_values_by.insert("*".to_string(), FacetValuesOrder::Alpha);
+        FacetingSettings { max_values_per_facet: DEFAULT_MAX_VALUES_PER_FACET, sort_facet_values_by }
+    }
+}
+
+impl FacetingSettings {
+    pub fn order(&self, facet: &str) -> FacetValuesOrder {
+        self.sort_facet_values_by
+            .get(facet)
+            .or_else(|| self.sort_facet_values_by.get("*"))
+            .copied()
+            .unwrap_or(FacetValuesOrder::Alpha)
+    }
+}
+
+/// When the words of the queries are not corrected. The first character of a word is never corrected.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct TypoToleranceSettings {
+    /// The words containing a digit, like numbers or product references, must match exactly.
+    pub disable_on_numbers: bool,
+}
+
+impl TypoToleranceSettings {
+    /// Whether this word of the query can be corrected.
+    pub fn allows_typos(&self, word: &str) -> bool {
+        !(self.disable_on_numbers && word.chars().any(|c| c.is_ascii_digit()))
+    }
+}
+
+/// How the proximity and the exactness ranking rules compare the documents.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct RankingBehavior {
+    /// The distance from which two query words are considered unrelated, `0` disables the proximity.
+    pub max_proximity: u16,
+    /// What must be equal to the query words for a document to be exact.
+    pub exactness: ExactnessMatch,
+}
+
+impl Default for RankingBehavior {
+    fn default() -> RankingBehavior {
+        RankingBehavior { max_proximity: DEFAULT_MAX_PROXIMITY, exactness: ExactnessMatch::Attribute }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExactnessMatch {
+    /// The documents having an attribute equal to a query word come first,
+    /// then the ones with the most exact words.
+    Attribute,
+    /// The documents with the most exact words come first.
+    Word,
+}
+
+/// How the words are normalized when the documents are indexed and when the queries are searched.
+/// The stop words are always matched regardless of the case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct NormalizationSettings {
+    /// Whether the words are also indexed transliterated to ascii, so that `é` matches `e`.
+    pub diacritic_folding: bool,
+    /// Whether the words are lowercased, so that `É` matches `é`.
+    pub case_folding: bool,
+    /// Whether the Arabic harakat and the Hebrew niqqud are removed from the words, so that
+    /// the vocalized words match the unvocalized ones.
+    pub strip_vowel_marks: bool,
+    /// Whether the katakana are written in hiragana, so that the words written with the same
+    /// reading match.
+    pub kana_folding: bool,
+    /// Whether the traditional Chinese characters are written in simplified Chinese, so that
+    /// a word written in both forms matches.
+    pub traditional_chinese_folding: bool,
+    /// Whether the numbers are written without thousands separators and leading zeros, with
+    /// a point as decimal separator, so that `1,000` matches `1000` and `007` matches `7`.
+    pub number_folding: bool,
+}
+
+impl Default for NormalizationSettings {
+    fn default() -> NormalizationSettings {
+        NormalizationSettings {
+            diacritic_folding: true,
+            case_folding: true,
+            strip_vowel_marks: false,
+            kana_folding: false,
+            traditional_chinese_folding: false,
+            number_folding: false,
+        }
+    }
+}
+
+impl NormalizationSettings {
+    /// Composes the Hangul jamo into syllables, lowercases the word if the case is folded, removes
+    /// its vowel marks if they are stripped and writes its katakana in hiragana and its traditional
+    /// Chinese characters in simplified Chinese and its numbers in a single way if they are folded.
+    pub fn normalize_word(&self, word: &str) -> String {
+        let word = compose_jamo(word);
+        let word = if self.case_folding { word.to_lowercase() } else { word };
+        let word = if self.strip_vowel_marks {
+            word.chars().filter(|c| !is_vowel_mark(*c)).collect()
+        } else {
+            word
+        };
+        let word = if self.kana_folding { fold_kana(&word) } else { word };
+        let word = if self.traditional_chinese_folding { fold_traditional(&word) } else { word };
+        if self.number_folding { fold_number(&word) } else { word }
+    }
+
+    /// Folds the case and the diacritics of the word, the way the synonyms are stored.
+    pub fn normalize(&self, word: &str) -> String {
+        let word = self.normalize_word(word);
+        if self.diacritic_folding && !word.contains(is_cjk) {
+            deunicode::deunicode_with_tofu(&word, "")
+        } else {
+            word
+        }
+    }
+}
+
+/// The languages of the documents that are tokenized with dedicated rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Language {
+    /// The runs of Thai characters are split into words, with a list of common words
+    /// and the words of the dictionary.
+    #[serde(rename = "th")]
+    Thai,
+    /// The runs of Japanese characters are split into words where the script changes and at
+    /// the particles, the words of the dictionary are kept whole.
+    #[serde(rename = "ja")]
+    Japanese,
+    /// The runs of Han characters, simplified or traditional, are split into words with a list
+    /// of common words and the words of the dictionary.
+    #[serde(rename = "zh")]
+    Chinese,
+    /// The runs of Hangul characters are kept together, without the particles that end them.
+    #[serde(rename = "ko")]
+    Korean,
+}
+
+/// How the values of an attribute are split into words, the attributes
+/// that are not configured use the standard tokenizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenizerMode {
+    /// The values are split into words with the separators and the languages of the index.
+    Standard,
+    /// The values are indexed whole, as a single word, for the codes and the identifiers.
+    Keyword,
+    /// The words are also indexed under their substrings, so that a part of a reference matches.
+    Ngram,
+}
+
+impl Default for TokenizerMode {
+    fn default() -> TokenizerMode {
+        TokenizerMode::Standard
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FacetValuesOrder {
+    /// In the lexicographic order of the values.
+    Alpha,
+    /// The values with the most documents first.
+    Count,
+}
+
+/// A merchandising rule applied to the searches whose query matches its condition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct QueryRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub condition: QueryRuleCondition,
+    /// The ids of the documents returned first, in this order, whatever their ranking.
+    #[serde(default)]
+    pub pinned: Vec<String>,
+    /// The ids of the documents never returned.
+    #[serde(default)]
+    pub hidden: Vec<String>,
+    /// An arbitrary payload added to the search response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banner: Option<Value>,
+}
+
+/// The queries a rule applies to. The query is lowercased and its words are separated by a
+/// single space before being compared.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryRuleCondition {
+    /// The query is this exact string.
+    Is(String),
+    /// The query contains these consecutive words.
+    Contains(String),
+    /// The query matches this case insensitive regular expression.
+    Pattern(String),
+}
+
+impl QueryRuleCondition {
+    pub fn matches(&self, query: &str) -> bool {
+        let normalize = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        let query = normalize(query);
+        match self {
+            QueryRuleCondition::Is(value) => query == normalize(value),
+            QueryRuleCondition::Contains(value) => {
+                let value = normalize(value);
+                !value.is_empty() && format!(" {} ", query).contains(&format!(" {} ", value))
+            }
+            QueryRuleCondition::Pattern(pattern) => {
+                regex::RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_or(false, |regex| regex.is_match(&query))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SettingsUpdate {
     pub ranking_rules: UpdateState<Vec<RankingRule>>,
@@ -163,8 +469,57 @@ pub struct SettingsUpdate {
     pub searchable_attributes: UpdateState<Vec<String>>,
     pub displayed_attributes: UpdateState<BTreeSet<String>>,
     pub stop_words: UpdateState<BTreeSet<String>>,
+    #[serde(default)]
+    pub separator_tokens: UpdateState<BTreeSet<String>>,
+    #[serde(default)]
+    pub non_separator_tokens: UpdateState<BTreeSet<String>>,
+    #[serde(default)]
+    pub dictionary: UpdateState<BTreeSet<String>>,
     pub synonyms: UpdateState<BTreeMap<String, Vec<String>>>,
     pub attributes_for_faceting: UpdateState<Vec<String>>,
+    #[serde(default)]
+    pub faceting: UpdateState<FacetingSettings>,
+    #[serde(default)]
+    pub query_rules: UpdateState<Vec<QueryRule>>,
+    #[serde(default)]
+    pub typo_tolerance: UpdateState<TypoToleranceSettings>,
+    #[serde(default)]
+    pub ranking_behavior: UpdateState<RankingBehavior>,
+    #[serde(default)]
+    pub normalization: UpdateState<NormalizationSettings>,
+    #[serde(default)]
+    pub languages: UpdateState<BTreeSet<Language>>,
+    #[serde(default)]
+    pub attribute_tokenizers: UpdateState<BTreeMap<String, TokenizerMode>>,
+    #[serde(default)]
+    pub identifier_splitting: UpdateState<bool>,
+}
+
+impl SettingsUpdate {
+    /// Merges two settings updates into one, `other` being applied after this one.
+    pub fn merge(self, other: SettingsUpdate) -> SettingsUpdate {
+        SettingsUpdate {
+            ranking_rules: self.ranking_rules.then(other.ranking_rules),
+            distinct_attribute: self.distinct_attribute.then(other.distinct_attribute),
+            primary_key: self.primary_key.then(other.primary_key),
+            searchable_attributes: self.searchable_attributes.then(other.searchable_attributes),
+            displayed_attributes: self.displayed_attributes.then(other.displayed_attributes),
+            stop_words: self.stop_words.then(other.stop_words),
+            separator_tokens: self.separator_tokens.then(other.separator_tokens),
+            non_separator_tokens: self.non_separator_tokens.then(other.non_separator_tokens),
+            dictionary: self.dictionary.then(other.dictionary),
+            synonyms: self.synonyms.then(other.synonyms),
+            attributes_for_faceting: self.attributes_for_faceting.then(other.attributes_for_faceting),
+            faceting: self.faceting.then(other.faceting),
+            query_rules: self.query_rules.then(other.query_rules),
+            typo_tolerance: self.typo_tolerance.then(other.typo_tolerance),
+            ranking_behavior: self.ranking_behavior.then(other.ranking_behavior),
+            normalization: self.normalization.then(other.normalization),
+            languages: self.languages.then(other.languages),
+            attribute_tokenizers: self.attribute_tokenizers.then(other.attribute_tokenizers),
+            identifier_splitting: self.identifier_splitting.then(other.identifier_splitting),
+        }
+    }
 }
 
 impl Default for SettingsUpdate {
@@ -176,8 +531,19 @@ impl Default for SettingsUpdate {
             searchable_attributes: UpdateState::Nothing,
             displayed_attributes: UpdateState::Nothing,
             stop_words: UpdateState::Nothing,
+            separator_tokens: UpdateState::Nothing,
+            non_separator_tokens: UpdateState::Nothing,
+            dictionary: UpdateState::Nothing,
             synonyms: UpdateState::Nothing,
             attributes_for_faceting: UpdateState::Nothing,
+            faceting: UpdateState::Nothing,
+            query_rules: UpdateState::Nothing,
+            typo_tolerance: UpdateState::Nothing,
+            ranking_behavior: UpdateState::Nothing,
+            normalization: UpdateState::Nothing,
+            languages: UpdateState::Nothing,
+            attribute_tokenizers: UpdateState::Nothing,
+            identifier_splitting: UpdateState::Nothing,
         }
     }
 }
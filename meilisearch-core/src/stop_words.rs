@@ -0,0 +1,81 @@
+//! The stop words lists that can be selected by name in the stop words setting.
+
+use std::collections::BTreeSet;
+
+use serde::{de, Deserialize, Deserializer};
+
+/// The names of the presets along with their words, one per line.
+const PRESETS: [(&str, &str); 6] = [
+    ("de", include_str!("stop_words/de.txt")),
+    ("en", include_str!("stop_words/en.txt")),
+    ("es", include_str!("stop_words/es.txt")),
+    ("fr", include_str!("stop_words/fr.txt")),
+    ("it", include_str!("stop_words/it.txt")),
+    ("pt", include_str!("stop_words/pt.txt")),
+];
+
+pub fn preset_names() -> impl Iterator<Item = &'static str> {
+    PRESETS.iter().map(|(name, _)| *name)
+}
+
+pub fn preset(name: &str) -> Option<BTreeSet<String>> {
+    let (_, words) = PRESETS.iter().find(|(preset, _)| *preset == name)?;
+    Some(words.lines().map(str::trim).filter(|word| !word.is_empty()).map(String::from).collect())
+}
+
+/// A stop words setting, either a list of words or the name of a preset along with the words
+/// to add to it, e.g. `{ "preset": "en", "words": ["thee"] }`. The preset is expanded when the
+/// setting is deserialized.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StopWords(pub BTreeSet<String>);
+
+impl<'de> Deserialize<'de> for StopWords {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<StopWords, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Setting {
+            Words(BTreeSet<String>),
+            Preset(Preset),
+        }
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Preset {
+            preset: String,
+            #[serde(default)]
+            words: BTreeSet<String>,
+        }
+
+        match Setting::deserialize(deserializer)? {
+            Setting::Words(words) => Ok(StopWords(words)),
+            Setting::Preset(Preset { preset: name, words }) => match preset(&name) {
+                Some(mut preset_words) => {
+                    preset_words.extend(words);
+                    Ok(StopWords(preset_words))
+                }
+                None => {
+                    let names = preset_names().collect::<Vec<_>>().join(", ");
+                    Err(de::Error::custom(format!("unknown stop words preset `{}`, expected one of {}", name, names)))
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_and_custom_words() {
+        let StopWords(words) = serde_json::from_str(r#"["thee", "thou"]"#).unwrap();
+        assert_eq!(words.len(), 2);
+
+        let StopWords(words) = serde_json::from_str(r#"{ "preset": "en", "words": ["thee"] }"#).unwrap();
+        assert!(words.contains("the") && words.contains("thee"));
+        assert_eq!(words.len(), preset("en").unwrap().len() + 1);
+
+        assert!(serde_json::from_str::<StopWords>(r#"{ "preset": "xx" }"#).is_err());
+        assert!(preset_names().all(|name| preset(name).map_or(false, |words| !words.is_empty())));
+    }
+}
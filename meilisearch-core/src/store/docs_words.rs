@@ -2,14 +2,16 @@ use std::borrow::Cow;
 
 use heed::Result as ZResult;
 use heed::types::{ByteSlice, OwnedType};
+use zerocopy::AsBytes;
 
 use crate::database::MainT;
 use crate::{DocumentId, FstSetCow};
-use super::BEU32;
+use super::{StoreSize, BEU32};
 
 #[derive(Copy, Clone)]
 pub struct DocsWords {
     pub(crate) docs_words: heed::Database<OwnedType<BEU32>, ByteSlice>,
+    pub(crate) size: StoreSize,
 }
 
 impl DocsWords {
@@ -21,16 +23,23 @@ impl DocsWords {
     ) -> ZResult<()> {
         let document_id = BEU32::new(document_id.0);
         let bytes = words.as_fst().as_bytes();
-        self.docs_words.put(writer, &document_id, bytes)
+        let store = self.docs_words;
+        self.size.track(writer, store.as_polymorph(), document_id.as_bytes(), |writer| {
+            store.put(writer, &document_id, bytes)
+        })
     }
 
     pub fn del_doc_words(self, writer: &mut heed::RwTxn<MainT>, document_id: DocumentId) -> ZResult<bool> {
         let document_id = BEU32::new(document_id.0);
-        self.docs_words.delete(writer, &document_id)
+        let store = self.docs_words;
+        self.size.track(writer, store.as_polymorph(), document_id.as_bytes(), |writer| {
+            store.delete(writer, &document_id)
+        })
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.docs_words.clear(writer)
+        self.docs_words.clear(writer)?;
+        self.size.put(writer, 0)
     }
 
     pub fn doc_words(self, reader: &heed::RoTxn<MainT>, document_id: DocumentId) -> ZResult<FstSetCow> {
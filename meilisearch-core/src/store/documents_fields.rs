@@ -1,14 +1,18 @@
+use std::ops::Bound;
+
 use heed::types::{ByteSlice, OwnedType};
 use crate::database::MainT;
 use heed::Result as ZResult;
 use meilisearch_schema::FieldId;
+use zerocopy::AsBytes;
 
-use super::DocumentFieldStoredKey;
+use super::{DocumentFieldStoredKey, StoreSize};
 use crate::DocumentId;
 
 #[derive(Copy, Clone)]
 pub struct DocumentsFields {
     pub(crate) documents_fields: heed::Database<OwnedType<DocumentFieldStoredKey>, ByteSlice>,
+    pub(crate) size: StoreSize,
 }
 
 impl DocumentsFields {
@@ -20,7 +24,8 @@ impl DocumentsFields {
         value: &[u8],
     ) -> ZResult<()> {
         let key = DocumentFieldStoredKey::new(document_id, field);
-        self.documents_fields.put(writer, &key, value)
+        let store = self.documents_fields;
+        self.size.track(writer, store.as_polymorph(), key.as_bytes(), |writer| store.put(writer, &key, value))
     }
 
     pub fn del_all_document_fields(
@@ -30,11 +35,16 @@ impl DocumentsFields {
     ) -> ZResult<usize> {
         let start = DocumentFieldStoredKey::new(document_id, FieldId::min());
         let end = DocumentFieldStoredKey::new(document_id, FieldId::max());
-        self.documents_fields.delete_range(writer, &(start..=end))
+        let store = self.documents_fields;
+        let range = (Bound::Included(start.as_bytes()), Bound::Included(end.as_bytes()));
+        self.size.track_range(writer, store.as_polymorph(), range, |writer| {
+            store.delete_range(writer, &(start..=end))
+        })
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.documents_fields.clear(writer)
+        self.documents_fields.clear(writer)?;
+        self.size.put(writer, 0)
     }
 
     pub fn document_attribute<'txn>(
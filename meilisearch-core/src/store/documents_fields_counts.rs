@@ -1,14 +1,18 @@
-use super::DocumentFieldIndexedKey;
+use std::ops::Bound;
+
+use super::{DocumentFieldIndexedKey, StoreSize};
 use crate::database::MainT;
 use crate::DocumentId;
 use heed::types::OwnedType;
 use heed::Result as ZResult;
 use meilisearch_schema::IndexedPos;
+use zerocopy::AsBytes;
 use crate::MResult;
 
 #[derive(Copy, Clone)]
 pub struct DocumentsFieldsCounts {
     pub(crate) documents_fields_counts: heed::Database<OwnedType<DocumentFieldIndexedKey>, OwnedType<u16>>,
+    pub(crate) size: StoreSize,
 }
 
 impl DocumentsFieldsCounts {
@@ -20,7 +24,8 @@ impl DocumentsFieldsCounts {
         value: u16,
     ) -> ZResult<()> {
         let key = DocumentFieldIndexedKey::new(document_id, attribute);
-        self.documents_fields_counts.put(writer, &key, &value)
+        let store = self.documents_fields_counts;
+        self.size.track(writer, store.as_polymorph(), key.as_bytes(), |writer| store.put(writer, &key, &value))
     }
 
     pub fn del_all_document_fields_counts(
@@ -30,11 +35,16 @@ impl DocumentsFieldsCounts {
     ) -> ZResult<usize> {
         let start = DocumentFieldIndexedKey::new(document_id, IndexedPos::min());
         let end = DocumentFieldIndexedKey::new(document_id, IndexedPos::max());
-        self.documents_fields_counts.delete_range(writer, &(start..=end))
+        let store = self.documents_fields_counts;
+        let range = (Bound::Included(start.as_bytes()), Bound::Included(end.as_bytes()));
+        self.size.track_range(writer, store.as_polymorph(), range, |writer| {
+            store.delete_range(writer, &(start..=end))
+        })
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.documents_fields_counts.clear(writer)
+        self.documents_fields_counts.clear(writer)?;
+        self.size.put(writer, 0)
     }
 
     pub fn document_field_count(
@@ -12,11 +12,13 @@ use crate::MResult;
 use crate::database::MainT;
 use crate::facets::FacetKey;
 use super::cow_set::CowSet;
+use super::StoreSize;
 
 /// contains facet info
 #[derive(Clone, Copy)]
 pub struct Facets {
     pub(crate) facets: heed::Database<FacetKey, FacetData>,
+    pub(crate) size: StoreSize,
 }
 
 pub struct FacetData;
@@ -59,7 +61,12 @@ impl<'a> BytesDecode<'a> for FacetData {
 impl Facets {
     // we use sdset::SetBuf to ensure the docids are sorted.
     pub fn put_facet_document_ids(&self, writer: &mut RwTxn<MainT>, facet_key: FacetKey, doc_ids: &Set<DocumentId>, facet_value: &str) -> MResult<()> {
-        Ok(self.facets.put(writer, &facet_key, &(facet_value, doc_ids))?)
+        // the facet keys always encode, an empty key only makes the put fail
+        let key_bytes = FacetKey::bytes_encode(&facet_key).unwrap_or_default();
+        let store = self.facets;
+        Ok(self.size.track(writer, store.as_polymorph(), &key_bytes, |writer| {
+            store.put(writer, &facet_key, &(facet_value, doc_ids))
+        })?)
     }
 
     pub fn field_document_ids<'txn>(&self, reader: &'txn RoTxn<MainT>, field_id: FieldId) -> MResult<RoRange<'txn, FacetKey, FacetData>> {
@@ -77,7 +84,7 @@ impl Facets {
             if let Some((_, old)) = self.facets.get(writer, &key)? {
                 let to_remove = SetBuf::from_dirty(document_ids);
                 let new = sdset::duo::OpBuilder::new(old.as_ref(), to_remove.as_set()).difference().into_set_buf();
-                self.facets.put(writer, &key, &(&name, new.as_set()))?;
+                self.put_facet_document_ids(writer, key, new.as_set(), &name)?;
             }
         }
         Ok(())
@@ -92,6 +99,7 @@ impl Facets {
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> MResult<()> {
-        Ok(self.facets.clear(writer)?)
+        self.facets.clear(writer)?;
+        Ok(self.size.put(writer, 0)?)
     }
 }
@@ -0,0 +1,56 @@
+use heed::Result as ZResult;
+use heed::types::OwnedType;
+use zerocopy::{AsBytes, FromBytes};
+
+use crate::database::MainT;
+use crate::DocumentId;
+use super::{StoreSize, BEU32};
+
+/// The location of a document, the latitude and the longitude of its `_geo` attribute in degrees.
+#[derive(Debug, Copy, Clone, PartialEq, AsBytes, FromBytes)]
+#[repr(C)]
+pub struct GeoLocation {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+/// The locations of the documents, written when they are indexed so that the searches sorting
+/// by distance only read the locations of their candidates.
+#[derive(Copy, Clone)]
+pub struct GeoLocations {
+    pub(crate) geo_locations: heed::Database<OwnedType<BEU32>, OwnedType<GeoLocation>>,
+    pub(crate) size: StoreSize,
+}
+
+impl GeoLocations {
+    pub fn put_location(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        document_id: DocumentId,
+        location: GeoLocation,
+    ) -> ZResult<()> {
+        let document_id = BEU32::new(document_id.0);
+        let store = self.geo_locations;
+        self.size.track(writer, store.as_polymorph(), document_id.as_bytes(), |writer| {
+            store.put(writer, &document_id, &location)
+        })
+    }
+
+    pub fn del_location(self, writer: &mut heed::RwTxn<MainT>, document_id: DocumentId) -> ZResult<bool> {
+        let document_id = BEU32::new(document_id.0);
+        let store = self.geo_locations;
+        self.size.track(writer, store.as_polymorph(), document_id.as_bytes(), |writer| {
+            store.delete(writer, &document_id)
+        })
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.geo_locations.clear(writer)?;
+        self.size.put(writer, 0)
+    }
+
+    pub fn location(self, reader: &heed::RoTxn<MainT>, document_id: DocumentId) -> ZResult<Option<GeoLocation>> {
+        let document_id = BEU32::new(document_id.0);
+        self.geo_locations.get(reader, &document_id)
+    }
+}
@@ -1,33 +1,49 @@
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use chrono::{DateTime, Utc};
-use heed::types::{ByteSlice, OwnedType, SerdeBincode, Str, CowSlice};
+use heed::types::{ByteSlice, OwnedType, SerdeBincode, SerdeJson, Str, CowSlice, Unit};
 use meilisearch_schema::{FieldId, Schema};
+use meilisearch_tokenizer::Separators;
 use meilisearch_types::DocumentId;
 use sdset::Set;
+use serde::{Deserialize, Serialize};
 
 use crate::database::MainT;
 use crate::{RankedMap, MResult};
-use crate::settings::RankingRule;
+use crate::settings::{FacetingSettings, Language, NormalizationSettings, QueryRule, RankingBehavior, RankingRule, TokenizerMode, TypoToleranceSettings};
 use crate::{FstSetCow, FstMapCow};
 use super::{CowSet, DocumentsIds};
 
+const ATTRIBUTE_TOKENIZERS_KEY: &str = "attribute-tokenizers";
 const ATTRIBUTES_FOR_FACETING_KEY: &str = "attributes-for-faceting";
 const CREATED_AT_KEY: &str = "created-at";
 const CUSTOMS_KEY: &str = "customs";
+const DICTIONARY_KEY: &str = "dictionary";
 const DISTINCT_ATTRIBUTE_KEY: &str = "distinct-attribute";
+const EXPERIMENT_KEY: &str = "experiment";
 const EXTERNAL_DOCIDS_KEY: &str = "external-docids";
+const FACETING_KEY: &str = "faceting";
 const FIELDS_DISTRIBUTION_KEY: &str = "fields-distribution";
+const IDENTIFIER_SPLITTING_KEY: &str = "identifier-splitting";
 const INTERNAL_DOCIDS_KEY: &str = "internal-docids";
+const LANGUAGES_KEY: &str = "languages";
 const NAME_KEY: &str = "name";
+const NON_SEPARATOR_TOKENS_KEY: &str = "non-separator-tokens";
+const NORMALIZATION_KEY: &str = "normalization";
 const NUMBER_OF_DOCUMENTS_KEY: &str = "number-of-documents";
+const QUERY_RULES_KEY: &str = "query-rules";
+const QUOTAS_KEY: &str = "quotas";
 const RANKED_MAP_KEY: &str = "ranked-map";
+const RANKING_BEHAVIOR_KEY: &str = "ranking-behavior";
 const RANKING_RULES_KEY: &str = "ranking-rules";
+const READ_ONLY_KEY: &str = "read-only";
 const SCHEMA_KEY: &str = "schema";
+const SEPARATOR_TOKENS_KEY: &str = "separator-tokens";
 const SORTED_DOCUMENT_IDS_CACHE_KEY: &str = "sorted-document-ids-cache";
 const STOP_WORDS_KEY: &str = "stop-words";
 const SYNONYMS_KEY: &str = "synonyms";
+const TYPO_TOLERANCE_KEY: &str = "typo-tolerance";
 const UPDATED_AT_KEY: &str = "updated-at";
 const WORDS_KEY: &str = "words";
 
@@ -35,6 +51,35 @@ pub type FreqsMap = BTreeMap<String, usize>;
 type SerdeFreqsMap = SerdeBincode<FreqsMap>;
 type SerdeDatetime = SerdeBincode<DateTime<Utc>>;
 
+/// The limits an index can't grow past, an update that would make the index exceed
+/// one of them fails and is not applied.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct IndexQuotas {
+    pub max_documents: Option<u64>,
+    /// The maximum size of the index in bytes, as reported by [`Index::size`](super::Index::size).
+    pub max_size: Option<u64>,
+}
+
+/// A relevance experiment, the searches carrying a user token are split between its variants.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Experiment {
+    pub name: String,
+    pub variants: Vec<ExperimentVariant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ExperimentVariant {
+    pub name: String,
+    /// The share of the users assigned to this variant, relative to the weights of the others.
+    pub weight: u32,
+    /// The ranking rules used instead of the ones of the index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ranking_rules: Option<Vec<String>>,
+}
+
 #[derive(Copy, Clone)]
 pub struct Main {
     pub(crate) main: heed::PolyDatabase,
@@ -72,6 +117,19 @@ impl Main {
         Ok(self.main.get::<_, Str, SerdeDatetime>(reader, UPDATED_AT_KEY)?)
     }
 
+    pub fn put_read_only(self, writer: &mut heed::RwTxn<MainT>, read_only: bool) -> MResult<()> {
+        if read_only {
+            self.main.put::<_, Str, Unit>(writer, READ_ONLY_KEY, &())?;
+        } else {
+            self.main.delete::<_, Str>(writer, READ_ONLY_KEY)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_only(self, reader: &heed::RoTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.get::<_, Str, Unit>(reader, READ_ONLY_KEY)?.is_some())
+    }
+
     pub fn put_internal_docids(self, writer: &mut heed::RwTxn<MainT>, ids: &sdset::Set<DocumentId>) -> MResult<()> {
         Ok(self.main.put::<_, Str, DocumentsIds>(writer, INTERNAL_DOCIDS_KEY, ids)?)
     }
@@ -234,6 +292,86 @@ impl Main {
         Ok(stop_word_list)
     }
 
+    pub fn separator_tokens(self, reader: &heed::RoTxn<MainT>) -> MResult<BTreeSet<String>> {
+        let tokens = self.main.get::<_, Str, SerdeBincode<BTreeSet<String>>>(reader, SEPARATOR_TOKENS_KEY)?;
+        Ok(tokens.unwrap_or_default())
+    }
+
+    pub fn put_separator_tokens(self, writer: &mut heed::RwTxn<MainT>, tokens: &BTreeSet<String>) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<BTreeSet<String>>>(writer, SEPARATOR_TOKENS_KEY, tokens)?)
+    }
+
+    pub fn non_separator_tokens(self, reader: &heed::RoTxn<MainT>) -> MResult<BTreeSet<String>> {
+        let tokens = self.main.get::<_, Str, SerdeBincode<BTreeSet<String>>>(reader, NON_SEPARATOR_TOKENS_KEY)?;
+        Ok(tokens.unwrap_or_default())
+    }
+
+    pub fn put_non_separator_tokens(self, writer: &mut heed::RwTxn<MainT>, tokens: &BTreeSet<String>) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<BTreeSet<String>>>(writer, NON_SEPARATOR_TOKENS_KEY, tokens)?)
+    }
+
+    pub fn dictionary(self, reader: &heed::RoTxn<MainT>) -> MResult<BTreeSet<String>> {
+        let words = self.main.get::<_, Str, SerdeBincode<BTreeSet<String>>>(reader, DICTIONARY_KEY)?;
+        Ok(words.unwrap_or_default())
+    }
+
+    pub fn put_dictionary(self, writer: &mut heed::RwTxn<MainT>, words: &BTreeSet<String>) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<BTreeSet<String>>>(writer, DICTIONARY_KEY, words)?)
+    }
+
+    pub fn query_rules(self, reader: &heed::RoTxn<MainT>) -> MResult<Vec<QueryRule>> {
+        let rules = self.main.get::<_, Str, SerdeJson<Vec<QueryRule>>>(reader, QUERY_RULES_KEY)?;
+        Ok(rules.unwrap_or_default())
+    }
+
+    // the banners are arbitrary JSON values that bincode can't deserialize
+    pub fn put_query_rules(self, writer: &mut heed::RwTxn<MainT>, rules: &[QueryRule]) -> MResult<()> {
+        if rules.is_empty() {
+            self.main.delete::<_, Str>(writer, QUERY_RULES_KEY)?;
+        } else {
+            self.main.put::<_, Str, SerdeJson<Vec<QueryRule>>>(writer, QUERY_RULES_KEY, &rules.to_vec())?;
+        }
+        Ok(())
+    }
+
+    /// The tokenization rules of the index, the tokens that are not a single character are ignored.
+    pub fn separators(self, reader: &heed::RoTxn<MainT>) -> MResult<Separators> {
+        let single_chars = |tokens: BTreeSet<String>| -> Vec<char> {
+            tokens.iter().filter_map(|token| {
+                let mut chars = token.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => None,
+                }
+            })
+            .collect()
+        };
+        let separators = single_chars(self.separator_tokens(reader)?);
+        let non_separators = single_chars(self.non_separator_tokens(reader)?);
+        let dictionary = self.dictionary(reader)?;
+        let mut separators = Separators::new(separators, non_separators).with_dictionary(dictionary);
+        let languages = self.languages(reader)?;
+        if languages.contains(&Language::Thai) {
+            separators = separators.with_thai_segmentation();
+        }
+        if languages.contains(&Language::Chinese) {
+            separators = separators.with_chinese_segmentation();
+        }
+        if languages.contains(&Language::Japanese) {
+            separators = separators.with_japanese_segmentation();
+        }
+        if languages.contains(&Language::Korean) {
+            separators = separators.with_korean_segmentation();
+        }
+        if self.identifier_splitting(reader)? {
+            separators = separators.with_identifier_splitting();
+        }
+        if self.normalization(reader)?.number_folding {
+            separators = separators.with_number_grouping();
+        }
+        Ok(separators)
+    }
+
     pub fn put_number_of_documents<F>(self, writer: &mut heed::RwTxn<MainT>, f: F) -> MResult<u64>
     where
         F: Fn(u64) -> u64,
@@ -253,6 +391,32 @@ impl Main {
         }
     }
 
+    pub fn put_quotas(self, writer: &mut heed::RwTxn<MainT>, quotas: IndexQuotas) -> MResult<()> {
+        if quotas == IndexQuotas::default() {
+            self.main.delete::<_, Str>(writer, QUOTAS_KEY)?;
+        } else {
+            self.main.put::<_, Str, SerdeBincode<IndexQuotas>>(writer, QUOTAS_KEY, &quotas)?;
+        }
+        Ok(())
+    }
+
+    pub fn quotas(self, reader: &heed::RoTxn<MainT>) -> MResult<IndexQuotas> {
+        let quotas = self.main.get::<_, Str, SerdeBincode<IndexQuotas>>(reader, QUOTAS_KEY)?;
+        Ok(quotas.unwrap_or_default())
+    }
+
+    pub fn put_experiment(self, writer: &mut heed::RwTxn<MainT>, experiment: &Experiment) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeJson<Experiment>>(writer, EXPERIMENT_KEY, experiment)?)
+    }
+
+    pub fn experiment(self, reader: &heed::RoTxn<MainT>) -> MResult<Option<Experiment>> {
+        Ok(self.main.get::<_, Str, SerdeJson<Experiment>>(reader, EXPERIMENT_KEY)?)
+    }
+
+    pub fn delete_experiment(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, EXPERIMENT_KEY)?)
+    }
+
     pub fn put_fields_distribution(
         self,
         writer: &mut heed::RwTxn<MainT>,
@@ -283,6 +447,81 @@ impl Main {
         Ok(self.main.delete::<_, Str>(writer, ATTRIBUTES_FOR_FACETING_KEY)?)
     }
 
+    pub fn faceting(self, reader: &heed::RoTxn<MainT>) -> MResult<FacetingSettings> {
+        let faceting = self.main.get::<_, Str, SerdeBincode<FacetingSettings>>(reader, FACETING_KEY)?;
+        Ok(faceting.unwrap_or_default())
+    }
+
+    pub fn put_faceting(self, writer: &mut heed::RwTxn<MainT>, faceting: &FacetingSettings) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<FacetingSettings>>(writer, FACETING_KEY, faceting)?)
+    }
+
+    pub fn delete_faceting(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, FACETING_KEY)?)
+    }
+
+    pub fn typo_tolerance(self, reader: &heed::RoTxn<MainT>) -> MResult<TypoToleranceSettings> {
+        let typo_tolerance = self.main.get::<_, Str, SerdeBincode<TypoToleranceSettings>>(reader, TYPO_TOLERANCE_KEY)?;
+        Ok(typo_tolerance.unwrap_or_default())
+    }
+
+    pub fn put_typo_tolerance(self, writer: &mut heed::RwTxn<MainT>, typo_tolerance: &TypoToleranceSettings) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<TypoToleranceSettings>>(writer, TYPO_TOLERANCE_KEY, typo_tolerance)?)
+    }
+
+    pub fn delete_typo_tolerance(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, TYPO_TOLERANCE_KEY)?)
+    }
+
+    pub fn languages(self, reader: &heed::RoTxn<MainT>) -> MResult<BTreeSet<Language>> {
+        let languages = self.main.get::<_, Str, SerdeBincode<BTreeSet<Language>>>(reader, LANGUAGES_KEY)?;
+        Ok(languages.unwrap_or_default())
+    }
+
+    pub fn put_languages(self, writer: &mut heed::RwTxn<MainT>, languages: &BTreeSet<Language>) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<BTreeSet<Language>>>(writer, LANGUAGES_KEY, languages)?)
+    }
+
+    pub fn attribute_tokenizers(self, reader: &heed::RoTxn<MainT>) -> MResult<BTreeMap<String, TokenizerMode>> {
+        let tokenizers = self.main.get::<_, Str, SerdeBincode<BTreeMap<String, TokenizerMode>>>(reader, ATTRIBUTE_TOKENIZERS_KEY)?;
+        Ok(tokenizers.unwrap_or_default())
+    }
+
+    pub fn put_attribute_tokenizers(self, writer: &mut heed::RwTxn<MainT>, tokenizers: &BTreeMap<String, TokenizerMode>) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<BTreeMap<String, TokenizerMode>>>(writer, ATTRIBUTE_TOKENIZERS_KEY, tokenizers)?)
+    }
+
+    pub fn identifier_splitting(self, reader: &heed::RoTxn<MainT>) -> MResult<bool> {
+        let splitting = self.main.get::<_, Str, SerdeBincode<bool>>(reader, IDENTIFIER_SPLITTING_KEY)?;
+        Ok(splitting.unwrap_or_default())
+    }
+
+    pub fn put_identifier_splitting(self, writer: &mut heed::RwTxn<MainT>, splitting: bool) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<bool>>(writer, IDENTIFIER_SPLITTING_KEY, &splitting)?)
+    }
+
+    pub fn normalization(self, reader: &heed::RoTxn<MainT>) -> MResult<NormalizationSettings> {
+        let normalization = self.main.get::<_, Str, SerdeBincode<NormalizationSettings>>(reader, NORMALIZATION_KEY)?;
+        Ok(normalization.unwrap_or_default())
+    }
+
+    pub fn put_normalization(self, writer: &mut heed::RwTxn<MainT>, normalization: &NormalizationSettings) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<NormalizationSettings>>(writer, NORMALIZATION_KEY, normalization)?)
+    }
+
+    pub fn ranking_behavior(self, reader: &heed::RoTxn<MainT>) -> MResult<RankingBehavior> {
+        let behavior = self.main.get::<_, Str, SerdeBincode<RankingBehavior>>(reader, RANKING_BEHAVIOR_KEY)?;
+        Ok(behavior.unwrap_or_default())
+    }
+
+    pub fn put_ranking_behavior(self, writer: &mut heed::RwTxn<MainT>, behavior: &RankingBehavior) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<RankingBehavior>>(writer, RANKING_BEHAVIOR_KEY, behavior)?)
+    }
+
+    pub fn delete_ranking_behavior(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, RANKING_BEHAVIOR_KEY)?)
+    }
+
     pub fn ranking_rules(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<Vec<RankingRule>>> {
         Ok(self.main.get::<_, Str, SerdeBincode<Vec<RankingRule>>>(reader, RANKING_RULES_KEY)?)
     }
@@ -4,10 +4,12 @@ mod documents_ids;
 mod documents_fields;
 mod documents_fields_counts;
 mod facets;
+mod geo_locations;
 mod main;
 mod postings_lists;
 mod prefix_documents_cache;
 mod prefix_postings_lists_cache;
+mod size;
 mod synonyms;
 mod updates;
 mod updates_results;
@@ -18,10 +20,12 @@ pub use self::documents_fields::{DocumentFieldsIter, DocumentsFields};
 pub use self::documents_fields_counts::{DocumentFieldsCountsIter, DocumentsFieldsCounts, DocumentsIdsIter};
 pub use self::documents_ids::{DocumentsIds, DiscoverIds};
 pub use self::facets::Facets;
-pub use self::main::Main;
+pub use self::geo_locations::{GeoLocation, GeoLocations};
+pub use self::main::{Experiment, ExperimentVariant, IndexQuotas, Main};
 pub use self::postings_lists::PostingsLists;
 pub use self::prefix_documents_cache::PrefixDocumentsCache;
 pub use self::prefix_postings_lists_cache::PrefixPostingsListsCache;
+pub use self::size::StoreSize;
 pub use self::synonyms::Synonyms;
 pub use self::updates::Updates;
 pub use self::updates_results::UpdatesResults;
@@ -29,7 +33,9 @@ pub use self::updates_results::UpdatesResults;
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::convert::TryInto;
-use std::{mem, ptr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{cmp, mem, ptr};
 
 use heed::{BytesEncode, BytesDecode};
 use meilisearch_schema::{IndexedPos, FieldId};
@@ -48,6 +54,16 @@ type BEU32 = zerocopy::U32<byteorder::BigEndian>;
 type BEU64 = zerocopy::U64<byteorder::BigEndian>;
 pub type BEU16 = zerocopy::U16<byteorder::BigEndian>;
 
+const POSTINGS_LISTS_SIZE_KEY: &str = "postings-lists-size";
+const DOCUMENTS_FIELDS_SIZE_KEY: &str = "documents-fields-size";
+const DOCUMENTS_FIELDS_COUNTS_SIZE_KEY: &str = "documents-fields-counts-size";
+const FACETS_SIZE_KEY: &str = "facets-size";
+const SYNONYMS_SIZE_KEY: &str = "synonyms-size";
+const DOCS_WORDS_SIZE_KEY: &str = "docs-words-size";
+const PREFIX_DOCUMENTS_CACHE_SIZE_KEY: &str = "prefix-documents-cache-size";
+const PREFIX_POSTINGS_LISTS_CACHE_SIZE_KEY: &str = "prefix-postings-lists-cache-size";
+const GEO_LOCATIONS_SIZE_KEY: &str = "geo-locations-size";
+
 #[derive(Debug, Copy, Clone, AsBytes, FromBytes)]
 #[repr(C)]
 pub struct DocumentFieldIndexedKey {
@@ -197,6 +213,18 @@ fn updates_name(name: &str) -> String {
     format!("store-{}-updates", name)
 }
 
+fn updates_priorities_name(name: &str) -> String {
+    format!("store-{}-updates-priorities", name)
+}
+
+fn updates_queue_name(name: &str) -> String {
+    format!("store-{}-updates-queue", name)
+}
+
+fn updates_pending_name(name: &str) -> String {
+    format!("store-{}-updates-pending", name)
+}
+
 fn updates_results_name(name: &str) -> String {
     format!("store-{}-updates-results", name)
 }
@@ -205,6 +233,10 @@ fn facets_name(name: &str) -> String {
     format!("store-{}-facets", name)
 }
 
+fn geo_locations_name(name: &str) -> String {
+    format!("store-{}-geo-locations", name)
+}
+
 #[derive(Clone)]
 pub struct Index {
     pub main: Main,
@@ -214,15 +246,109 @@ pub struct Index {
     pub facets: Facets,
     pub synonyms: Synonyms,
     pub docs_words: DocsWords,
+    pub geo_locations: GeoLocations,
     pub prefix_documents_cache: PrefixDocumentsCache,
     pub prefix_postings_lists_cache: PrefixPostingsListsCache,
 
     pub updates: Updates,
     pub updates_results: UpdatesResults,
     pub(crate) updates_notifier: UpdateEventsEmitter,
+    /// The ids of the updates currently processed by the update loop.
+    pub(crate) processing_updates: Arc<Mutex<Vec<u64>>>,
+    /// Whether the index rejects the updates, mirrors the flag stored in the main store.
+    pub(crate) read_only: Arc<AtomicBool>,
+    /// Whether the database rejects the updates of all its indexes, see [`Database::storage_full`].
+    ///
+    /// [`Database::storage_full`]: crate::Database::storage_full
+    pub(crate) storage_full: Arc<AtomicBool>,
 }
 
 impl Index {
+    /// Sums the sizes of the keys and values of the documents and search structures of the index.
+    ///
+    /// The stores are shared in the LMDB environment, this is therefore the size of the data
+    /// and not of the pages it occupies on disk.
+    pub fn compute_size(&self, reader: &heed::RoTxn<MainT>) -> MResult<u64> {
+        let mut size = size::measure(reader, &self.main.main)?;
+        for (_, store) in self.sized_stores().iter() {
+            size += size::measure(reader, store)?;
+        }
+        Ok(size)
+    }
+
+    /// Reads a byte of each page of the stores used by the searches, so that the operating system
+    /// loads them in its page cache, and returns the number of bytes of these stores. The documents
+    /// are left out, the search only reads the ones it returns.
+    pub fn warmup(&self, reader: &heed::RoTxn<MainT>) -> MResult<u64> {
+        const PAGE_SIZE: usize = 4096;
+
+        let stores = [
+            &self.main.main,
+            self.postings_lists.postings_lists.as_polymorph(),
+            self.documents_fields_counts.documents_fields_counts.as_polymorph(),
+            self.facets.facets.as_polymorph(),
+            self.synonyms.synonyms.as_polymorph(),
+            self.geo_locations.geo_locations.as_polymorph(),
+            self.prefix_documents_cache.prefix_documents_cache.as_polymorph(),
+            self.prefix_postings_lists_cache.prefix_postings_lists_cache.as_polymorph(),
+        ];
+
+        let mut size = 0;
+        for store in stores.iter() {
+            for result in store.iter::<_, heed::types::ByteSlice, heed::types::ByteSlice>(reader)? {
+                let (key, value) = result?;
+                // the values larger than a page are stored in overflow pages that are only loaded when read
+                for offset in (0..value.len()).step_by(PAGE_SIZE) {
+                    // safety: the offset is within the value, the read must not be optimized out
+                    unsafe { std::ptr::read_volatile(value.as_ptr().add(offset)) };
+                }
+                size += (key.len() + value.len()) as u64;
+            }
+        }
+
+        Ok(size)
+    }
+
+    fn sized_stores(&self) -> [(StoreSize, &heed::PolyDatabase); 9] {
+        [
+            (self.postings_lists.size, self.postings_lists.postings_lists.as_polymorph()),
+            (self.documents_fields.size, self.documents_fields.documents_fields.as_polymorph()),
+            (self.documents_fields_counts.size, self.documents_fields_counts.documents_fields_counts.as_polymorph()),
+            (self.facets.size, self.facets.facets.as_polymorph()),
+            (self.synonyms.size, self.synonyms.synonyms.as_polymorph()),
+            (self.docs_words.size, self.docs_words.docs_words.as_polymorph()),
+            (self.geo_locations.size, self.geo_locations.geo_locations.as_polymorph()),
+            (self.prefix_documents_cache.size, self.prefix_documents_cache.prefix_documents_cache.as_polymorph()),
+            (self.prefix_postings_lists_cache.size, self.prefix_postings_lists_cache.prefix_postings_lists_cache.as_polymorph()),
+        ]
+    }
+
+    /// The size of the index, the same as [`compute_size`](Index::compute_size) without reading
+    /// all the stores: their sizes are kept up to date by their writers, only the main store, which
+    /// holds a few entries, is measured. The stores of the indexes created before their sizes were
+    /// kept are measured until [`measure_unknown_sizes`](Index::measure_unknown_sizes) is called.
+    pub fn size(&self, reader: &heed::RoTxn<MainT>) -> MResult<u64> {
+        let mut size = size::measure(reader, &self.main.main)?;
+        for (store_size, store) in self.sized_stores().iter() {
+            size += match store_size.get(reader)? {
+                Some(store_size) => store_size,
+                None => size::measure(reader, store)?,
+            };
+        }
+        Ok(size)
+    }
+
+    /// Measures and stores the sizes of the stores that are not known yet, the writers then keep them up to date.
+    pub fn measure_unknown_sizes(&self, writer: &mut heed::RwTxn<MainT>) -> MResult<()> {
+        for (store_size, store) in self.sized_stores().iter() {
+            if store_size.get(writer)?.is_none() {
+                let size = size::measure(writer, store)?;
+                store_size.put(writer, size)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn document<T: de::DeserializeOwned>(
         &self,
         reader: &heed::RoTxn<MainT>,
@@ -278,21 +404,47 @@ impl Index {
         }
     }
 
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::SeqCst)
+    }
+
+    /// Returns an error if the index has been made read-only or if the storage is almost full.
+    pub fn check_writable(&self) -> MResult<()> {
+        check_writable(&self.read_only, &self.storage_full)
+    }
+
     pub fn customs_update(&self, writer: &mut heed::RwTxn<UpdateT>, customs: Vec<u8>) -> MResult<u64> {
+        self.check_writable()?;
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         Ok(update::push_customs_update(writer, self.updates, self.updates_results, customs)?)
     }
 
     pub fn settings_update(&self, writer: &mut heed::RwTxn<UpdateT>, update: SettingsUpdate) -> MResult<u64> {
+        self.check_writable()?;
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         Ok(update::push_settings_update(writer, self.updates, self.updates_results, update)?)
     }
 
+    pub fn replay_update(&self, writer: &mut heed::RwTxn<UpdateT>, update: update::Update) -> MResult<u64> {
+        self.check_writable()?;
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        update::push_update(writer, self.updates, self.updates_results, update)
+    }
+
+    /// Enqueues the import of files of documents, they are downloaded when the update is processed.
+    pub fn documents_import(&self, writer: &mut heed::RwTxn<UpdateT>, import: update::DocumentsImport) -> MResult<u64> {
+        self.check_writable()?;
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        update::push_documents_import(writer, self.updates, self.updates_results, import)
+    }
+
     pub fn documents_addition<D>(&self) -> update::DocumentsAddition<D> {
         update::DocumentsAddition::new(
             self.updates,
             self.updates_results,
             self.updates_notifier.clone(),
+            self.read_only.clone(),
+            self.storage_full.clone(),
         )
     }
 
@@ -301,6 +453,8 @@ impl Index {
             self.updates,
             self.updates_results,
             self.updates_notifier.clone(),
+            self.read_only.clone(),
+            self.storage_full.clone(),
         )
     }
 
@@ -309,10 +463,13 @@ impl Index {
             self.updates,
             self.updates_results,
             self.updates_notifier.clone(),
+            self.read_only.clone(),
+            self.storage_full.clone(),
         )
     }
 
     pub fn clear_all(&self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64> {
+        self.check_writable()?;
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         update::push_clear_all(writer, self.updates, self.updates_results)
     }
@@ -334,30 +491,42 @@ impl Index {
 
     pub fn all_updates_status(&self, reader: &heed::RoTxn<UpdateT>) -> MResult<Vec<update::UpdateStatus>> {
         let mut updates = Vec::new();
-        let mut last_update_result_id = 0;
 
-        // retrieve all updates results
-        if let Some((last_id, _)) = self.updates_results.last_update(reader)? {
+        // canceled updates have a result while older updates can still be enqueued,
+        // we must therefore look for every id up to the last known one.
+        let last_result_id = self.updates_results.last_update(reader)?.map(|(id, _)| id);
+        let last_update_id = self.updates.last_update(reader)?.map(|(id, _)| id);
+
+        if let Some(last_id) = cmp::max(last_result_id, last_update_id) {
             updates.reserve(last_id as usize);
 
             for id in 0..=last_id {
                 if let Some(update) = self.update_status(reader, id)? {
                     updates.push(update);
-                    last_update_result_id = id + 1;
                 }
             }
         }
 
-        // retrieve all enqueued updates
-        if let Some((last_id, _)) = self.updates.last_update(reader)? {
-            for id in last_update_result_id..=last_id {
-                if let Some(update) = self.update_status(reader, id)? {
-                    updates.push(update);
-                }
-            }
+        Ok(updates)
+    }
+
+    /// Deletes the result of a finished update. The result of the most recent update
+    /// is always kept as it is used to generate the next update ids.
+    pub fn delete_update_result(&self, writer: &mut heed::RwTxn<UpdateT>, update_id: u64) -> MResult<bool> {
+        let last_result_id = self.updates_results.last_update(writer)?.map(|(id, _)| id);
+        if last_result_id == Some(update_id) {
+            return Ok(false);
         }
+        Ok(self.updates_results.del_update_result(writer, update_id)?)
+    }
 
-        Ok(updates)
+    /// Cancels an update that is still enqueued, returns `false` if the update
+    /// doesn't exist, has already been processed or is being processed.
+    pub fn cancel_update(&self, writer: &mut heed::RwTxn<UpdateT>, update_id: u64) -> MResult<bool> {
+        if self.processing_updates.lock().unwrap().contains(&update_id) {
+            return Ok(false);
+        }
+        update::cancel_update(writer, self.updates, self.updates_results, update_id)
     }
 
     pub fn query_builder(&self) -> QueryBuilder {
@@ -372,11 +541,24 @@ impl Index {
     }
 }
 
+/// Returns an error if the index has been made read-only or if the database refuses the
+/// updates because its storage is almost full.
+pub(crate) fn check_writable(read_only: &AtomicBool, storage_full: &AtomicBool) -> MResult<()> {
+    if read_only.load(Ordering::SeqCst) {
+        return Err(Error::IndexReadOnly);
+    }
+    if storage_full.load(Ordering::SeqCst) {
+        return Err(Error::InsufficientStorage);
+    }
+    Ok(())
+}
+
 pub fn create(
     env: &heed::Env,
     update_env: &heed::Env,
     name: &str,
     updates_notifier: UpdateEventsEmitter,
+    storage_full: Arc<AtomicBool>,
 ) -> MResult<Index> {
     // create all the store names
     let main_name = main_name(name);
@@ -388,8 +570,11 @@ pub fn create(
     let prefix_documents_cache_name = prefix_documents_cache_name(name);
     let prefix_postings_lists_cache_name = prefix_postings_lists_cache_name(name);
     let updates_name = updates_name(name);
+    let updates_queue_name = updates_queue_name(name);
+    let updates_pending_name = updates_pending_name(name);
     let updates_results_name = updates_results_name(name);
     let facets_name = facets_name(name);
+    let geo_locations_name = geo_locations_name(name);
 
     // open all the stores
     let main = env.create_poly_database(Some(&main_name))?;
@@ -399,25 +584,47 @@ pub fn create(
     let facets = env.create_database(Some(&facets_name))?;
     let synonyms = env.create_database(Some(&synonyms_name))?;
     let docs_words = env.create_database(Some(&docs_words_name))?;
+    let geo_locations = env.create_database(Some(&geo_locations_name))?;
     let prefix_documents_cache = env.create_database(Some(&prefix_documents_cache_name))?;
     let prefix_postings_lists_cache = env.create_database(Some(&prefix_postings_lists_cache_name))?;
     let updates = update_env.create_database(Some(&updates_name))?;
+    let updates_queue = update_env.create_database(Some(&updates_queue_name))?;
+    let updates_pending = update_env.create_database(Some(&updates_pending_name))?;
     let updates_results = update_env.create_database(Some(&updates_results_name))?;
 
     Ok(Index {
         main: Main { main },
-        postings_lists: PostingsLists { postings_lists },
-        documents_fields: DocumentsFields { documents_fields },
-        documents_fields_counts: DocumentsFieldsCounts { documents_fields_counts },
-        synonyms: Synonyms { synonyms },
-        docs_words: DocsWords { docs_words },
-        prefix_postings_lists_cache: PrefixPostingsListsCache { prefix_postings_lists_cache },
-        prefix_documents_cache: PrefixDocumentsCache { prefix_documents_cache },
-        facets: Facets { facets },
-
-        updates: Updates { updates },
+        postings_lists: PostingsLists {
+            postings_lists,
+            size: StoreSize::new(main, POSTINGS_LISTS_SIZE_KEY),
+        },
+        documents_fields: DocumentsFields {
+            documents_fields,
+            size: StoreSize::new(main, DOCUMENTS_FIELDS_SIZE_KEY),
+        },
+        documents_fields_counts: DocumentsFieldsCounts {
+            documents_fields_counts,
+            size: StoreSize::new(main, DOCUMENTS_FIELDS_COUNTS_SIZE_KEY),
+        },
+        synonyms: Synonyms { synonyms, size: StoreSize::new(main, SYNONYMS_SIZE_KEY) },
+        docs_words: DocsWords { docs_words, size: StoreSize::new(main, DOCS_WORDS_SIZE_KEY) },
+        geo_locations: GeoLocations { geo_locations, size: StoreSize::new(main, GEO_LOCATIONS_SIZE_KEY) },
+        prefix_postings_lists_cache: PrefixPostingsListsCache {
+            prefix_postings_lists_cache,
+            size: StoreSize::new(main, PREFIX_POSTINGS_LISTS_CACHE_SIZE_KEY),
+        },
+        prefix_documents_cache: PrefixDocumentsCache {
+            prefix_documents_cache,
+            size: StoreSize::new(main, PREFIX_DOCUMENTS_CACHE_SIZE_KEY),
+        },
+        facets: Facets { facets, size: StoreSize::new(main, FACETS_SIZE_KEY) },
+
+        updates: Updates { updates, queue: updates_queue, pending: updates_pending },
         updates_results: UpdatesResults { updates_results },
         updates_notifier,
+        processing_updates: Arc::default(),
+        read_only: Arc::default(),
+        storage_full,
     })
 }
 
@@ -426,6 +633,7 @@ pub fn open(
     update_env: &heed::Env,
     name: &str,
     updates_notifier: UpdateEventsEmitter,
+    storage_full: Arc<AtomicBool>,
 ) -> MResult<Option<Index>> {
     // create all the store names
     let main_name = main_name(name);
@@ -438,7 +646,11 @@ pub fn open(
     let facets_name = facets_name(name);
     let prefix_postings_lists_cache_name = prefix_postings_lists_cache_name(name);
     let updates_name = updates_name(name);
+    let updates_priorities_name = updates_priorities_name(name);
+    let updates_queue_name = updates_queue_name(name);
+    let updates_pending_name = updates_pending_name(name);
     let updates_results_name = updates_results_name(name);
+    let geo_locations_name = geo_locations_name(name);
 
     // open all the stores
     let main = match env.open_poly_database(Some(&main_name))? {
@@ -465,6 +677,12 @@ pub fn open(
         Some(docs_words) => docs_words,
         None => return Ok(None),
     };
+    // indexes created before the locations were stored don't have this store yet,
+    // the locations are read once from their documents
+    let (geo_locations, missing_geo_locations) = match env.open_database(Some(&geo_locations_name))? {
+        Some(geo_locations) => (geo_locations, false),
+        None => (env.create_database(Some(&geo_locations_name))?, true),
+    };
     let prefix_documents_cache = match env.open_database(Some(&prefix_documents_cache_name))? {
         Some(prefix_documents_cache) => prefix_documents_cache,
         None => return Ok(None),
@@ -481,25 +699,83 @@ pub fn open(
         Some(updates) => updates,
         None => return Ok(None),
     };
+    // indexes created before the queue was introduced don't have this store yet,
+    // it is built from their enqueued updates
+    let (updates_queue, missing_updates_queue) = match update_env.open_database(Some(&updates_queue_name))? {
+        Some(updates_queue) => (updates_queue, false),
+        None => (update_env.create_database(Some(&updates_queue_name))?, true),
+    };
+    // the same goes for the index of the footprints of the enqueued updates
+    let (updates_pending, missing_updates_pending) = match update_env.open_database(Some(&updates_pending_name))? {
+        Some(updates_pending) => (updates_pending, false),
+        None => (update_env.create_database(Some(&updates_pending_name))?, true),
+    };
     let updates_results = match update_env.open_database(Some(&updates_results_name))? {
         Some(updates_results) => updates_results,
         None => return Ok(None),
     };
 
-    Ok(Some(Index {
+    let index = Index {
         main: Main { main },
-        postings_lists: PostingsLists { postings_lists },
-        documents_fields: DocumentsFields { documents_fields },
-        documents_fields_counts: DocumentsFieldsCounts { documents_fields_counts },
-        synonyms: Synonyms { synonyms },
-        docs_words: DocsWords { docs_words },
-        prefix_documents_cache: PrefixDocumentsCache { prefix_documents_cache },
-        facets: Facets { facets },
-        prefix_postings_lists_cache: PrefixPostingsListsCache { prefix_postings_lists_cache },
-        updates: Updates { updates },
+        postings_lists: PostingsLists {
+            postings_lists,
+            size: StoreSize::new(main, POSTINGS_LISTS_SIZE_KEY),
+        },
+        documents_fields: DocumentsFields {
+            documents_fields,
+            size: StoreSize::new(main, DOCUMENTS_FIELDS_SIZE_KEY),
+        },
+        documents_fields_counts: DocumentsFieldsCounts {
+            documents_fields_counts,
+            size: StoreSize::new(main, DOCUMENTS_FIELDS_COUNTS_SIZE_KEY),
+        },
+        synonyms: Synonyms { synonyms, size: StoreSize::new(main, SYNONYMS_SIZE_KEY) },
+        docs_words: DocsWords { docs_words, size: StoreSize::new(main, DOCS_WORDS_SIZE_KEY) },
+        geo_locations: GeoLocations { geo_locations, size: StoreSize::new(main, GEO_LOCATIONS_SIZE_KEY) },
+        prefix_documents_cache: PrefixDocumentsCache {
+            prefix_documents_cache,
+            size: StoreSize::new(main, PREFIX_DOCUMENTS_CACHE_SIZE_KEY),
+        },
+        facets: Facets { facets, size: StoreSize::new(main, FACETS_SIZE_KEY) },
+        prefix_postings_lists_cache: PrefixPostingsListsCache {
+            prefix_postings_lists_cache,
+            size: StoreSize::new(main, PREFIX_POSTINGS_LISTS_CACHE_SIZE_KEY),
+        },
+        updates: Updates { updates, queue: updates_queue, pending: updates_pending },
         updates_results: UpdatesResults { updates_results },
         updates_notifier,
-    }))
+        processing_updates: Arc::default(),
+        read_only: Arc::default(),
+        storage_full,
+    };
+
+    if missing_geo_locations {
+        let mut writer = env.typed_write_txn::<MainT>()?;
+        update::index_geo_locations(&mut writer, &index)?;
+        writer.commit()?;
+    }
+
+    if missing_updates_queue {
+        let reader = env.typed_read_txn::<MainT>()?;
+        let schema = index.main.schema(&reader)?;
+        let primary_key = schema.as_ref().and_then(|schema| schema.primary_key());
+
+        let mut update_writer = update_env.typed_write_txn::<UpdateT>()?;
+        index.updates.rebuild_queue(&mut update_writer, primary_key)?;
+        // the priorities were stored by update before the queue was introduced
+        let priorities = update_env.open_database::<heed::types::ByteSlice, heed::types::ByteSlice>(Some(&updates_priorities_name))?;
+        if let Some(priorities) = priorities {
+            priorities.clear(&mut update_writer)?;
+        }
+        update_writer.commit()?;
+        reader.abort()?;
+    } else if missing_updates_pending {
+        let mut update_writer = update_env.typed_write_txn::<UpdateT>()?;
+        index.updates.rebuild_pending(&mut update_writer)?;
+        update_writer.commit()?;
+    }
+
+    Ok(Some(index))
 }
 
 pub fn clear(
@@ -514,6 +790,7 @@ pub fn clear(
     index.documents_fields_counts.clear(writer)?;
     index.synonyms.clear(writer)?;
     index.docs_words.clear(writer)?;
+    index.geo_locations.clear(writer)?;
     index.prefix_documents_cache.clear(writer)?;
     index.prefix_postings_lists_cache.clear(writer)?;
     index.updates.clear(update_writer)?;
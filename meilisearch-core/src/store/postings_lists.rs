@@ -7,11 +7,12 @@ use slice_group_by::GroupBy;
 
 use crate::database::MainT;
 use crate::DocIndex;
-use crate::store::{Postings, PostingsCodec};
+use crate::store::{Postings, PostingsCodec, StoreSize};
 
 #[derive(Copy, Clone)]
 pub struct PostingsLists {
     pub(crate) postings_lists: heed::Database<ByteSlice, PostingsCodec>,
+    pub(crate) size: StoreSize,
 }
 
 impl PostingsLists {
@@ -26,15 +27,18 @@ impl PostingsLists {
         let matches = Cow::Borrowed(matches);
         let postings = Postings { docids, matches };
 
-        self.postings_lists.put(writer, word, &postings)
+        let store = self.postings_lists;
+        self.size.track(writer, store.as_polymorph(), word, |writer| store.put(writer, word, &postings))
     }
 
     pub fn del_postings_list(self, writer: &mut heed::RwTxn<MainT>, word: &[u8]) -> ZResult<bool> {
-        self.postings_lists.delete(writer, word)
+        let store = self.postings_lists;
+        self.size.track(writer, store.as_polymorph(), word, |writer| store.delete(writer, word))
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.postings_lists.clear(writer)
+        self.postings_lists.clear(writer)?;
+        self.size.put(writer, 0)
     }
 
     pub fn postings_list<'txn>(
@@ -4,7 +4,7 @@ use heed::types::{OwnedType, CowSlice};
 use heed::Result as ZResult;
 use zerocopy::{AsBytes, FromBytes};
 
-use super::{BEU64, BEU32};
+use super::{StoreSize, BEU64, BEU32};
 use crate::{DocumentId, Highlight};
 use crate::database::MainT;
 
@@ -29,6 +29,7 @@ impl PrefixKey {
 #[derive(Copy, Clone)]
 pub struct PrefixDocumentsCache {
     pub(crate) prefix_documents_cache: heed::Database<OwnedType<PrefixKey>, CowSlice<Highlight>>,
+    pub(crate) size: StoreSize,
 }
 
 impl PrefixDocumentsCache {
@@ -41,11 +42,13 @@ impl PrefixDocumentsCache {
         highlights: &[Highlight],
     ) -> ZResult<()> {
         let key = PrefixKey::new(prefix, index as u64, docid.0);
-        self.prefix_documents_cache.put(writer, &key, highlights)
+        let store = self.prefix_documents_cache;
+        self.size.track(writer, store.as_polymorph(), key.as_bytes(), |writer| store.put(writer, &key, highlights))
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.prefix_documents_cache.clear(writer)
+        self.prefix_documents_cache.clear(writer)?;
+        self.size.put(writer, 0)
     }
 
     pub fn prefix_documents<'txn>(
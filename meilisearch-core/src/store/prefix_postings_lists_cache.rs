@@ -7,11 +7,12 @@ use slice_group_by::GroupBy;
 
 use crate::database::MainT;
 use crate::DocIndex;
-use crate::store::{PostingsCodec, Postings};
+use crate::store::{PostingsCodec, Postings, StoreSize};
 
 #[derive(Copy, Clone)]
 pub struct PrefixPostingsListsCache {
     pub(crate) prefix_postings_lists_cache: heed::Database<OwnedType<[u8; 4]>, PostingsCodec>,
+    pub(crate) size: StoreSize,
 }
 
 impl PrefixPostingsListsCache {
@@ -27,11 +28,13 @@ impl PrefixPostingsListsCache {
         let matches = Cow::Borrowed(matches);
         let postings = Postings { docids, matches };
 
-        self.prefix_postings_lists_cache.put(writer, &prefix, &postings)
+        let store = self.prefix_postings_lists_cache;
+        self.size.track(writer, store.as_polymorph(), &prefix, |writer| store.put(writer, &prefix, &postings))
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.prefix_postings_lists_cache.clear(writer)
+        self.prefix_postings_lists_cache.clear(writer)?;
+        self.size.put(writer, 0)
     }
 
     pub fn prefix_postings_list<'txn>(
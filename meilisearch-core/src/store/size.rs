@@ -0,0 +1,92 @@
+use std::ops::Bound;
+
+use heed::types::{ByteSlice, OwnedType, Str};
+use heed::Result as ZResult;
+
+use crate::database::MainT;
+
+/// The size of the keys and values of a store, kept in the main store of the index. The writers
+/// of the store add the difference between the entries they replace and the ones they write,
+/// in the same transaction, so that the size of the index never has to be computed again.
+#[derive(Copy, Clone)]
+pub struct StoreSize {
+    main: heed::PolyDatabase,
+    key: &'static str,
+}
+
+impl StoreSize {
+    pub(crate) fn new(main: heed::PolyDatabase, key: &'static str) -> StoreSize {
+        StoreSize { main, key }
+    }
+
+    /// The size of the store, unknown for the indexes created before the sizes were kept.
+    pub fn get(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<u64>> {
+        self.main.get::<_, Str, OwnedType<u64>>(reader, self.key)
+    }
+
+    pub(crate) fn put(self, writer: &mut heed::RwTxn<MainT>, size: u64) -> ZResult<()> {
+        self.main.put::<_, Str, OwnedType<u64>>(writer, self.key, &size)
+    }
+
+    /// Adds the difference to the size of the store, an unknown size is left unknown.
+    fn add(self, writer: &mut heed::RwTxn<MainT>, delta: i64) -> ZResult<()> {
+        if delta == 0 {
+            return Ok(());
+        }
+        match self.get(writer)? {
+            Some(size) => self.put(writer, (size as i64 + delta).max(0) as u64),
+            None => Ok(()),
+        }
+    }
+
+    /// Applies the modification of the entry with the given key, accounting for the difference in size.
+    pub(crate) fn track<T, F>(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        store: &heed::PolyDatabase,
+        key: &[u8],
+        f: F,
+    ) -> ZResult<T>
+    where
+        F: FnOnce(&mut heed::RwTxn<MainT>) -> ZResult<T>,
+    {
+        let range = (Bound::Included(key), Bound::Included(key));
+        self.track_range(writer, store, range, f)
+    }
+
+    /// Applies the modification of the entries in the range of keys, accounting for the difference in size.
+    pub(crate) fn track_range<T, F>(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        store: &heed::PolyDatabase,
+        range: (Bound<&[u8]>, Bound<&[u8]>),
+        f: F,
+    ) -> ZResult<T>
+    where
+        F: FnOnce(&mut heed::RwTxn<MainT>) -> ZResult<T>,
+    {
+        let before = range_size(writer, store, range)?;
+        let result = f(writer)?;
+        let after = range_size(writer, store, range)?;
+        self.add(writer, after as i64 - before as i64)?;
+        Ok(result)
+    }
+}
+
+fn range_size(
+    reader: &heed::RoTxn<MainT>,
+    store: &heed::PolyDatabase,
+    range: (Bound<&[u8]>, Bound<&[u8]>),
+) -> ZResult<u64> {
+    let mut size = 0;
+    for result in store.range::<_, ByteSlice, ByteSlice, _>(reader, &range)? {
+        let (key, value) = result?;
+        size += (key.len() + value.len()) as u64;
+    }
+    Ok(size)
+}
+
+/// Sums the sizes of the keys and values of the store.
+pub(crate) fn measure(reader: &heed::RoTxn<MainT>, store: &heed::PolyDatabase) -> ZResult<u64> {
+    range_size(reader, store, (Bound::Unbounded, Bound::Unbounded))
+}
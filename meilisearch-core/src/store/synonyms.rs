@@ -5,10 +5,12 @@ use heed::types::ByteSlice;
 
 use crate::database::MainT;
 use crate::{FstSetCow, MResult};
+use super::StoreSize;
 
 #[derive(Copy, Clone)]
 pub struct Synonyms {
     pub(crate) synonyms: heed::Database<ByteSlice, ByteSlice>,
+    pub(crate) size: StoreSize,
 }
 
 impl Synonyms {
@@ -16,15 +18,18 @@ impl Synonyms {
     where A: AsRef<[u8]>,
     {
         let bytes = synonyms.as_fst().as_bytes();
-        self.synonyms.put(writer, word, bytes)
+        let store = self.synonyms;
+        self.size.track(writer, store.as_polymorph(), word, |writer| store.put(writer, word, bytes))
     }
 
     pub fn del_synonyms(self, writer: &mut heed::RwTxn<MainT>, word: &[u8]) -> ZResult<bool> {
-        self.synonyms.delete(writer, word)
+        let store = self.synonyms;
+        self.size.track(writer, store.as_polymorph(), word, |writer| store.delete(writer, word))
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.synonyms.clear(writer)
+        self.synonyms.clear(writer)?;
+        self.size.put(writer, 0)
     }
 
     pub(crate) fn synonyms_fst<'txn>(self, reader: &'txn heed::RoTxn<MainT>, word: &[u8]) -> ZResult<FstSetCow<'txn>> {
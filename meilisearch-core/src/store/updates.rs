@@ -1,12 +1,65 @@
+use std::collections::HashMap;
+use std::ops::Bound;
+
 use super::BEU64;
 use crate::database::UpdateT;
-use crate::update::Update;
-use heed::types::{OwnedType, SerdeJson};
+use crate::update::{Update, UpdateFootprint, UpdatePriority};
+use heed::types::{ByteSlice, OwnedType, SerdeBincode, SerdeJson, Unit};
 use heed::Result as ZResult;
+use zerocopy::{AsBytes, FromBytes};
+
+/// The lanes of the queue, in the order they are processed.
+const LANES: [UpdatePriority; 3] = [UpdatePriority::Urgent, UpdatePriority::Normal, UpdatePriority::Bulk];
+
+/// The position of an update in the queue, the updates of the highest priority
+/// lane come first and are ordered by id in their lane.
+#[derive(Debug, Copy, Clone, AsBytes, FromBytes)]
+#[repr(C)]
+pub struct QueueKey {
+    lane: u8,
+    update_id: BEU64,
+}
+
+impl QueueKey {
+    fn new(priority: UpdatePriority, update_id: u64) -> QueueKey {
+        let lane = LANES.iter().position(|p| *p == priority).unwrap_or_default() as u8;
+        QueueKey { lane, update_id: BEU64::new(update_id) }
+    }
+}
+
+/// The key of an update in the index of the pending footprints: its lane, the document it
+/// modifies, none when it modifies the whole index, and its id.
+fn pending_key(lane: u8, document_id: Option<&str>, update_id: u64) -> Vec<u8> {
+    let mut key = vec![lane];
+    match document_id {
+        Some(document_id) => {
+            key.push(1);
+            key.extend_from_slice(&(document_id.len() as u32).to_be_bytes());
+            key.extend_from_slice(document_id.as_bytes());
+        }
+        None => key.push(0),
+    }
+    key.extend_from_slice(&update_id.to_be_bytes());
+    key
+}
+
+/// The keys of the update in the index of the pending footprints, one by document it modifies.
+fn pending_keys(lane: u8, update_id: u64, footprint: &UpdateFootprint) -> Vec<Vec<u8>> {
+    match footprint {
+        UpdateFootprint::Index => vec![pending_key(lane, None, update_id)],
+        UpdateFootprint::Documents(ids) => ids.iter().map(|id| pending_key(lane, Some(id), update_id)).collect(),
+    }
+}
 
 #[derive(Copy, Clone)]
 pub struct Updates {
     pub(crate) updates: heed::Database<OwnedType<BEU64>, SerdeJson<Update>>,
+    /// The enqueued updates in the order they are processed along with what they modify,
+    /// it is a lightweight index of the queue that avoids deserializing the updates.
+    pub(crate) queue: heed::Database<OwnedType<QueueKey>, SerdeBincode<UpdateFootprint>>,
+    /// The enqueued updates by lane and by document they modify, the updates an update
+    /// depends on are found without visiting the other enqueued updates.
+    pub(crate) pending: heed::Database<ByteSlice, Unit>,
 }
 
 impl Updates {
@@ -26,40 +79,212 @@ impl Updates {
         }
     }
 
+    /// Returns the oldest update of the highest priority lane.
+    pub fn next_update(self, reader: &heed::RoTxn<UpdateT>) -> ZResult<Option<(u64, Update)>> {
+        let queue = self.queue.as_polymorph();
+        match queue.first::<_, OwnedType<QueueKey>, ByteSlice>(reader)? {
+            Some((key, _)) => {
+                let update_id = key.update_id.get();
+                Ok(self.get(reader, update_id)?.map(|update| (update_id, update)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The lane in which the update is processed, higher than its own priority when an
+    /// update of a higher priority enqueued after it depends on it.
+    pub fn lane(self, reader: &heed::RoTxn<UpdateT>, update_id: u64) -> ZResult<Option<UpdatePriority>> {
+        let queue = self.queue.as_polymorph();
+        for priority in LANES.iter() {
+            let key = QueueKey::new(*priority, update_id);
+            if queue.get::<_, OwnedType<QueueKey>, ByteSlice>(reader, &key)?.is_some() {
+                return Ok(Some(*priority));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The number of enqueued updates, counted without deserializing them.
+    pub fn count(self, reader: &heed::RoTxn<UpdateT>) -> ZResult<u64> {
+        let mut count = 0;
+        for result in self.updates.as_polymorph().iter::<_, ByteSlice, ByteSlice>(reader)? {
+            result?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
     // TODO do not trigger deserialize if possible
     pub fn get(self, reader: &heed::RoTxn<UpdateT>, update_id: u64) -> ZResult<Option<Update>> {
         let update_id = BEU64::new(update_id);
         self.updates.get(reader, &update_id)
     }
 
+    /// Returns the update as it is stored, serialized in JSON, without deserializing it.
+    pub fn get_raw<'txn>(self, reader: &'txn heed::RoTxn<UpdateT>, update_id: u64) -> ZResult<Option<&'txn [u8]>> {
+        let update_id = BEU64::new(update_id);
+        self.updates.as_polymorph().get::<_, OwnedType<BEU64>, ByteSlice>(reader, &update_id)
+    }
+
+    /// Enqueues the update in the lane of its priority, the `primary_key` identifies the
+    /// documents it adds. The updates enqueued before in a lower lane that this update
+    /// depends on, directly or through other updates, are moved to its lane so that
+    /// they are still processed before it.
     pub fn put_update(
         self,
         writer: &mut heed::RwTxn<UpdateT>,
         update_id: u64,
         update: &Update,
+        primary_key: Option<&str>,
     ) -> ZResult<()> {
-        // TODO prefer using serde_json?
-        let update_id = BEU64::new(update_id);
-        self.updates.put(writer, &update_id, update)
+        let priority = update.priority();
+        let lane = QueueKey::new(priority, 0).lane;
+        let footprint = UpdateFootprint::of(update, primary_key);
+
+        // an update depends on the updates enqueued before it that modify the same documents,
+        // the updates it depends on are promoted along with the ones they depend on
+        let mut promoted: HashMap<u64, (u8, UpdateFootprint)> = HashMap::new();
+        let mut dependents = vec![(update_id, footprint.clone())];
+        while let Some((dependent_id, dependent)) = dependents.pop() {
+            for lower_lane in lane + 1..LANES.len() as u8 {
+                let dependencies = match &dependent {
+                    UpdateFootprint::Index => {
+                        let start = QueueKey { lane: lower_lane, update_id: BEU64::new(0) };
+                        let end = QueueKey { lane: lower_lane, update_id: BEU64::new(dependent_id) };
+                        let mut ids = Vec::new();
+                        for result in self.queue.range(writer, &(start..end))? {
+                            let (key, _) = result?;
+                            ids.push(key.update_id.get());
+                        }
+                        ids
+                    }
+                    UpdateFootprint::Documents(document_ids) => {
+                        let mut ids = self.pending_updates(writer, lower_lane, None, dependent_id)?;
+                        for document_id in document_ids {
+                            ids.extend(self.pending_updates(writer, lower_lane, Some(document_id), dependent_id)?);
+                        }
+                        ids
+                    }
+                };
+
+                for dependency_id in dependencies {
+                    if promoted.contains_key(&dependency_id) {
+                        continue;
+                    }
+                    let key = QueueKey { lane: lower_lane, update_id: BEU64::new(dependency_id) };
+                    if let Some(dependency) = self.queue.get(writer, &key)? {
+                        dependents.push((dependency_id, dependency.clone()));
+                        promoted.insert(dependency_id, (lower_lane, dependency));
+                    }
+                }
+            }
+        }
+
+        for (promoted_id, (lower_lane, promoted_footprint)) in promoted {
+            self.queue.delete(writer, &QueueKey { lane: lower_lane, update_id: BEU64::new(promoted_id) })?;
+            self.del_pending(writer, lower_lane, promoted_id, &promoted_footprint)?;
+            self.queue.put(writer, &QueueKey { lane, update_id: BEU64::new(promoted_id) }, &promoted_footprint)?;
+            self.put_pending(writer, lane, promoted_id, &promoted_footprint)?;
+        }
+
+        self.queue.put(writer, &QueueKey::new(priority, update_id), &footprint)?;
+        self.put_pending(writer, lane, update_id, &footprint)?;
+        self.updates.put(writer, &BEU64::new(update_id), update)
+    }
+
+    /// The ids of the updates of the lane enqueued before `before` that modify the document,
+    /// or that modify the whole index when no document is given.
+    fn pending_updates(
+        self,
+        reader: &heed::RoTxn<UpdateT>,
+        lane: u8,
+        document_id: Option<&str>,
+        before: u64,
+    ) -> ZResult<Vec<u64>> {
+        let start = pending_key(lane, document_id, 0);
+        let end = pending_key(lane, document_id, before);
+        let range = (Bound::Included(start.as_slice()), Bound::Excluded(end.as_slice()));
+
+        let mut ids = Vec::new();
+        for result in self.pending.range(reader, &range)? {
+            let (key, ()) = result?;
+            let mut update_id = [0; 8];
+            update_id.copy_from_slice(&key[key.len() - 8..]);
+            ids.push(u64::from_be_bytes(update_id));
+        }
+        Ok(ids)
+    }
+
+    fn put_pending(self, writer: &mut heed::RwTxn<UpdateT>, lane: u8, update_id: u64, footprint: &UpdateFootprint) -> ZResult<()> {
+        for key in pending_keys(lane, update_id, footprint) {
+            self.pending.put(writer, &key, &())?;
+        }
+        Ok(())
+    }
+
+    fn del_pending(self, writer: &mut heed::RwTxn<UpdateT>, lane: u8, update_id: u64, footprint: &UpdateFootprint) -> ZResult<()> {
+        for key in pending_keys(lane, update_id, footprint) {
+            self.pending.delete(writer, &key)?;
+        }
+        Ok(())
     }
 
     pub fn del_update(self, writer: &mut heed::RwTxn<UpdateT>, update_id: u64) -> ZResult<bool> {
-        let update_id = BEU64::new(update_id);
-        self.updates.delete(writer, &update_id)
+        for priority in LANES.iter() {
+            let key = QueueKey::new(*priority, update_id);
+            if let Some(footprint) = self.queue.get(writer, &key)? {
+                self.queue.delete(writer, &key)?;
+                self.del_pending(writer, key.lane, update_id, &footprint)?;
+            }
+        }
+        self.updates.delete(writer, &BEU64::new(update_id))
     }
 
     pub fn pop_front(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<Option<(u64, Update)>> {
-        match self.first_update(writer)? {
+        match self.next_update(writer)? {
             Some((update_id, update)) => {
-                let key = BEU64::new(update_id);
-                self.updates.delete(writer, &key)?;
+                self.del_update(writer, update_id)?;
                 Ok(Some((update_id, update)))
             }
             None => Ok(None),
         }
     }
 
+    /// Enqueues again all the updates in the order they were enqueued, for the indexes
+    /// whose queue was not kept along with the updates.
+    pub(crate) fn rebuild_queue(self, writer: &mut heed::RwTxn<UpdateT>, primary_key: Option<&str>) -> ZResult<()> {
+        let mut updates = Vec::new();
+        for result in self.updates.iter(writer)? {
+            let (update_id, update) = result?;
+            updates.push((update_id.get(), update));
+        }
+
+        self.queue.clear(writer)?;
+        self.pending.clear(writer)?;
+        for (update_id, update) in updates {
+            self.put_update(writer, update_id, &update, primary_key)?;
+        }
+        Ok(())
+    }
+
+    /// Indexes again the footprints of the enqueued updates, for the indexes whose
+    /// queue was kept without this index.
+    pub(crate) fn rebuild_pending(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<()> {
+        let mut enqueued = Vec::new();
+        for result in self.queue.iter(writer)? {
+            enqueued.push(result?);
+        }
+
+        self.pending.clear(writer)?;
+        for (key, footprint) in enqueued {
+            self.put_pending(writer, key.lane, key.update_id.get(), &footprint)?;
+        }
+        Ok(())
+    }
+
     pub fn clear(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<()> {
+        self.queue.clear(writer)?;
+        self.pending.clear(writer)?;
         self.updates.clear(writer)
     }
 }
@@ -39,6 +39,11 @@ impl UpdatesResults {
         self.updates_results.get(reader, &update_id)
     }
 
+    pub fn del_update_result(self, writer: &mut heed::RwTxn<UpdateT>, update_id: u64) -> ZResult<bool> {
+        let update_id = BEU64::new(update_id);
+        self.updates_results.delete(writer, &update_id)
+    }
+
     pub fn clear(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<()> {
         self.updates_results.clear(writer)
     }
@@ -0,0 +1,85 @@
+//! The rules that can be used to declare the synonyms setting.
+use std::collections::BTreeMap;
+
+use serde::{de, Deserialize, Deserializer};
+
+/// A synonyms setting, either a map of words to the alternatives they expand to, or a list of
+/// rules. A rule is either one-way, `phone => iphone, android` expands `phone` but neither
+/// `iphone` nor `android`, or a list of equivalent words, `couch, sofa` expands each of them
+/// into the others. The rules are expanded into a map when the setting is deserialized.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Synonyms(pub BTreeMap<String, Vec<String>>);
+
+impl Synonyms {
+    pub fn from_rules<I, S>(rules: I) -> Result<Synonyms, String>
+    where I: IntoIterator<Item = S>,
+          S: AsRef<str>,
+    {
+        let mut synonyms = BTreeMap::new();
+        for rule in rules {
+            let rule = rule.as_ref();
+            let (words, alternatives) = match rule.split("=>").collect::<Vec<_>>().as_slice() {
+                [equivalents] => (split_words(equivalents), split_words(equivalents)),
+                [words, alternatives] => (split_words(words), split_words(alternatives)),
+                _ => return Err(format!("invalid synonym rule `{}`, it must contain at most one `=>`", rule)),
+            };
+
+            let is_one_way = rule.contains("=>");
+            if words.is_empty() || alternatives.is_empty() || (!is_one_way && words.len() < 2) {
+                return Err(format!("invalid synonym rule `{}`, it must contain at least two words", rule));
+            }
+
+            for word in &words {
+                let entry: &mut Vec<String> = synonyms.entry(word.clone()).or_default();
+                for alternative in alternatives.iter().filter(|a| *a != word) {
+                    if !entry.contains(alternative) {
+                        entry.push(alternative.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(Synonyms(synonyms))
+    }
+}
+
+fn split_words(words: &str) -> Vec<String> {
+    words.split(',').map(str::trim).filter(|word| !word.is_empty()).map(String::from).collect()
+}
+
+impl<'de> Deserialize<'de> for Synonyms {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Synonyms, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Setting {
+            Map(BTreeMap<String, Vec<String>>),
+            Rules(Vec<String>),
+        }
+
+        match Setting::deserialize(deserializer)? {
+            Setting::Map(synonyms) => Ok(Synonyms(synonyms)),
+            Setting::Rules(rules) => Synonyms::from_rules(rules).map_err(de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_way_and_equivalent_rules() {
+        let Synonyms(synonyms) = serde_json::from_str(r#"["phone => iphone, android", "couch, sofa"]"#).unwrap();
+        assert_eq!(synonyms["phone"], vec!["iphone", "android"]);
+        assert!(!synonyms.contains_key("iphone") && !synonyms.contains_key("android"));
+        assert_eq!(synonyms["couch"], vec!["sofa"]);
+        assert_eq!(synonyms["sofa"], vec!["couch"]);
+
+        let Synonyms(synonyms) = serde_json::from_str(r#"{ "phone": ["iphone"] }"#).unwrap();
+        assert_eq!(synonyms["phone"], vec!["iphone"]);
+
+        assert!(serde_json::from_str::<Synonyms>(r#"["sofa"]"#).is_err());
+        assert!(serde_json::from_str::<Synonyms>(r#"["phone =>"]"#).is_err());
+        assert!(serde_json::from_str::<Synonyms>(r#"["a => b => c"]"#).is_err());
+    }
+}
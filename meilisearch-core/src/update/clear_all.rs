@@ -15,6 +15,7 @@ pub fn apply_clear_all(
     index.documents_fields_counts.clear(writer)?;
     index.postings_lists.clear(writer)?;
     index.docs_words.clear(writer)?;
+    index.geo_locations.clear(writer)?;
     index.prefix_documents_cache.clear(writer)?;
     index.prefix_postings_lists_cache.clear(writer)?;
 
@@ -28,7 +29,7 @@ pub fn push_clear_all(
 ) -> MResult<u64> {
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
     let update = Update::clear_all();
-    updates_store.put_update(writer, last_update_id, &update)?;
+    updates_store.put_update(writer, last_update_id, &update, None)?;
 
     Ok(last_update_id)
 }
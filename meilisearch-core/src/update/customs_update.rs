@@ -20,7 +20,7 @@ pub fn push_customs_update(
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
 
     let update = Update::customs(customs);
-    updates_store.put_update(writer, last_update_id, &update)?;
+    updates_store.put_update(writer, last_update_id, &update, None)?;
 
     Ok(last_update_id)
 }
@@ -1,12 +1,16 @@
 use std::borrow::Cow;
 use std::collections::{HashMap, BTreeMap};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 use fst::{set::OpBuilder, SetBuilder};
 use indexmap::IndexMap;
-use meilisearch_schema::{Schema, FieldId};
+use meilisearch_schema::{FieldId, IndexedPos, Schema};
+use meilisearch_tokenizer::Separators;
 use meilisearch_types::DocumentId;
+use rayon::prelude::*;
 use sdset::{duo::Union, SetOperation};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::database::{MainT, UpdateT};
@@ -14,17 +18,43 @@ use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::facets;
 use crate::raw_indexer::RawIndexer;
 use crate::serde::Deserializer;
-use crate::store::{self, DocumentsFields, DocumentsFieldsCounts, DiscoverIds};
-use crate::update::helpers::{index_value, value_to_number, extract_document_id};
-use crate::update::{apply_documents_deletion, compute_short_prefixes, next_update_id, Update};
+use crate::settings::{NormalizationSettings, TokenizerMode};
+use crate::store::{self, DocumentsFieldsCounts, DiscoverIds};
+use crate::update::helpers::{index_value, value_to_number, value_to_geo_location, extract_document_id};
+use crate::update::{apply_documents_deletion, compute_short_prefixes, next_update_id, Update, UpdatePriority};
+use crate::update::{DocumentError, MAX_DOCUMENT_ERRORS};
 use crate::{Error, MResult, RankedMap};
 
+/// Describes how the nested objects and arrays of a partially updated document
+/// are combined with the ones of the already stored document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    /// The top-level fields of the new document replace the old ones.
+    Replace,
+    /// Nested objects are merged recursively, arrays are replaced.
+    DeepMerge,
+    /// Nested objects are merged recursively, arrays are concatenated.
+    Append,
+}
+
+impl Default for MergeStrategy {
+    fn default() -> MergeStrategy {
+        MergeStrategy::Replace
+    }
+}
+
 pub struct DocumentsAddition<D> {
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
     updates_notifier: UpdateEventsEmitter,
+    read_only: Arc<AtomicBool>,
+    storage_full: Arc<AtomicBool>,
     documents: Vec<D>,
     is_partial: bool,
+    merge_strategy: MergeStrategy,
+    priority: UpdatePriority,
+    primary_key: Option<String>,
 }
 
 impl<D> DocumentsAddition<D> {
@@ -32,13 +62,20 @@ impl<D> DocumentsAddition<D> {
         updates_store: store::Updates,
         updates_results_store: store::UpdatesResults,
         updates_notifier: UpdateEventsEmitter,
+        read_only: Arc<AtomicBool>,
+        storage_full: Arc<AtomicBool>,
     ) -> DocumentsAddition<D> {
         DocumentsAddition {
             updates_store,
             updates_results_store,
             updates_notifier,
+            read_only,
+            storage_full,
             documents: Vec::new(),
             is_partial: false,
+            merge_strategy: MergeStrategy::default(),
+            priority: UpdatePriority::default(),
+            primary_key: None,
         }
     }
 
@@ -46,16 +83,38 @@ impl<D> DocumentsAddition<D> {
         updates_store: store::Updates,
         updates_results_store: store::UpdatesResults,
         updates_notifier: UpdateEventsEmitter,
+        read_only: Arc<AtomicBool>,
+        storage_full: Arc<AtomicBool>,
     ) -> DocumentsAddition<D> {
         DocumentsAddition {
             updates_store,
             updates_results_store,
             updates_notifier,
+            read_only,
+            storage_full,
             documents: Vec::new(),
             is_partial: true,
+            merge_strategy: MergeStrategy::default(),
+            priority: UpdatePriority::default(),
+            primary_key: None,
         }
     }
 
+    /// Sets the way the nested fields of partially updated documents are merged.
+    pub fn set_merge_strategy(&mut self, merge_strategy: MergeStrategy) {
+        self.merge_strategy = merge_strategy;
+    }
+
+    pub fn set_priority(&mut self, priority: UpdatePriority) {
+        self.priority = priority;
+    }
+
+    /// Sets the primary key of the documents, the updates enqueued before that modify other
+    /// documents can then be processed after this one when it has a higher priority.
+    pub fn set_primary_key(&mut self, primary_key: String) {
+        self.primary_key = Some(primary_key);
+    }
+
     pub fn update_document(&mut self, document: D) {
         self.documents.push(document);
     }
@@ -64,6 +123,7 @@ impl<D> DocumentsAddition<D> {
     where
         D: serde::Serialize,
     {
+        store::check_writable(&self.read_only, &self.storage_full)?;
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         let update_id = push_documents_addition(
             writer,
@@ -71,6 +131,9 @@ impl<D> DocumentsAddition<D> {
             self.updates_results_store,
             self.documents,
             self.is_partial,
+            self.merge_strategy,
+            self.priority,
+            self.primary_key.as_deref(),
         )?;
         Ok(update_id)
     }
@@ -88,6 +151,9 @@ pub fn push_documents_addition<D: serde::Serialize>(
     updates_results_store: store::UpdatesResults,
     addition: Vec<D>,
     is_partial: bool,
+    merge_strategy: MergeStrategy,
+    priority: UpdatePriority,
+    primary_key: Option<&str>,
 ) -> MResult<u64> {
     let mut values = Vec::with_capacity(addition.len());
     for add in addition {
@@ -99,58 +165,127 @@ pub fn push_documents_addition<D: serde::Serialize>(
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
 
     let update = if is_partial {
-        Update::documents_partial(values)
+        Update::documents_partial(values, merge_strategy)
     } else {
         Update::documents_addition(values)
     };
+    let update = update.with_priority(priority);
 
-    updates_store.put_update(writer, last_update_id, &update)?;
+    updates_store.put_update(writer, last_update_id, &update, primary_key)?;
 
     Ok(last_update_id)
 }
 
-#[allow(clippy::too_many_arguments)]
-fn index_document<A>(
-    writer: &mut heed::RwTxn<MainT>,
-    documents_fields: DocumentsFields,
-    documents_fields_counts: DocumentsFieldsCounts,
+/// The number of values tokenized by each task of the indexing thread pool.
+const INDEXING_CHUNK_SIZE: usize = 1000;
+
+/// The number of documents read in memory at once when reindexing all the documents.
+const REINDEXING_BATCH_SIZE: usize = 10_000;
+
+/// The attribute holding the location of the documents.
+const GEO_ATTRIBUTE: &str = "_geo";
+
+fn update_ranked_map(
     ranked_map: &mut RankedMap,
-    indexer: &mut RawIndexer<A>,
     schema: &Schema,
     field_id: FieldId,
     document_id: DocumentId,
     value: &Value,
-) -> MResult<()>
-where A: AsRef<[u8]>,
-{
-    let serialized = serde_json::to_vec(value)?;
-    documents_fields.put_document_field(writer, document_id, field_id, &serialized)?;
-
-    if let Some(indexed_pos) = schema.is_indexed(field_id) {
-        let number_of_words = index_value(indexer, document_id, *indexed_pos, value);
-        if let Some(number_of_words) = number_of_words {
-            documents_fields_counts.put_document_field_count(
-                writer,
-                document_id,
-                *indexed_pos,
-                number_of_words as u16,
-            )?;
-        }
-    }
-
+) {
     if schema.is_ranked(field_id) {
         let number = value_to_number(value).unwrap_or_default();
         ranked_map.insert(document_id, field_id, number);
     }
+}
 
-    Ok(())
+/// The tokenizers of the indexed attributes configured with a specific one.
+fn indexed_tokenizer_modes(schema: &Schema, attribute_tokenizers: &BTreeMap<String, TokenizerMode>) -> HashMap<IndexedPos, TokenizerMode> {
+    attribute_tokenizers
+        .iter()
+        .filter_map(|(attribute, mode)| {
+            let indexed_pos = schema.id(attribute).and_then(|field_id| schema.is_indexed(field_id))?;
+            Some((*indexed_pos, *mode))
+        })
+        .collect()
+}
+
+/// Tokenizes the values on the rayon thread pool, each task fills its own indexer and the
+/// indexers are merged once all the values are tokenized. The number of words of each value
+/// is then written in the fields counts, the writes are all done by the calling thread.
+fn index_values<'s, A>(
+    writer: &mut heed::RwTxn<MainT>,
+    documents_fields_counts: DocumentsFieldsCounts,
+    stop_words: &'s fst::Set<A>,
+    separators: &Separators,
+    normalization: NormalizationSettings,
+    tokenizer_modes: &HashMap<IndexedPos, TokenizerMode>,
+    values: &[(DocumentId, IndexedPos, &Value)],
+) -> MResult<RawIndexer<&'s [u8]>>
+where A: AsRef<[u8]>,
+{
+    let stop_words_bytes = stop_words.as_fst().as_bytes();
+    // the bytes come from a valid fst, reading them again can't fail
+    let new_indexer = || {
+        RawIndexer::new(fst::Set::new(stop_words_bytes).unwrap())
+            .with_separators(separators.clone())
+            .with_normalization(normalization)
+            .with_tokenizer_modes(tokenizer_modes.clone())
+    };
+
+    let (indexer, fields_counts) = values
+        .par_chunks(INDEXING_CHUNK_SIZE)
+        .map(|chunk| {
+            let mut indexer = new_indexer();
+            let mut fields_counts = Vec::with_capacity(chunk.len());
+            for (document_id, indexed_pos, value) in chunk {
+                if let Some(number_of_words) = index_value(&mut indexer, *document_id, *indexed_pos, value) {
+                    fields_counts.push((*document_id, *indexed_pos, number_of_words as u16));
+                }
+            }
+            (indexer, fields_counts)
+        })
+        .reduce(
+            || (new_indexer(), Vec::new()),
+            |(mut indexer, mut fields_counts), (other, other_fields_counts)| {
+                indexer.merge(other);
+                fields_counts.extend(other_fields_counts);
+                (indexer, fields_counts)
+            },
+        );
+
+    for (document_id, indexed_pos, number_of_words) in fields_counts {
+        documents_fields_counts.put_document_field_count(writer, document_id, indexed_pos, number_of_words)?;
+    }
+
+    Ok(indexer)
+}
+
+/// Merges the old value of a field into its new value according to the given strategy.
+fn merge_values(old: Value, new: &mut Value, merge_strategy: MergeStrategy) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) if merge_strategy != MergeStrategy::Replace => {
+            for (key, value) in old {
+                match new.entry(key) {
+                    serde_json::map::Entry::Vacant(entry) => { entry.insert(value); },
+                    serde_json::map::Entry::Occupied(mut entry) => {
+                        merge_values(value, entry.get_mut(), merge_strategy)
+                    },
+                }
+            }
+        },
+        (Value::Array(mut old), Value::Array(new)) if merge_strategy == MergeStrategy::Append => {
+            old.append(new);
+            *new = old;
+        },
+        _ => (),
+    }
 }
 
 pub fn apply_addition<'a, 'b>(
     writer: &'a mut heed::RwTxn<'b, MainT>,
     index: &store::Index,
     new_documents: Vec<IndexMap<String, Value>>,
-    partial: bool
+    partial: Option<MergeStrategy>,
 ) -> MResult<()>
 {
     let mut schema = match index.main.schema(writer)? {
@@ -170,7 +305,12 @@ pub fn apply_addition<'a, 'b>(
     let mut new_external_docids = BTreeMap::new();
     let mut new_internal_docids = Vec::with_capacity(new_documents.len());
 
-    for mut document in new_documents {
+    // the invalid documents are reported all at once, not only the first one
+    let mut first_error = None;
+    let mut document_errors = Vec::new();
+    let mut invalid_documents = 0;
+
+    for (position, mut document) in new_documents.into_iter().enumerate() {
         let external_docids_get = |docid: &str| {
             match (external_docids.get(docid), new_external_docids.get(docid)) {
                 (_, Some(&id))
@@ -179,18 +319,43 @@ pub fn apply_addition<'a, 'b>(
             }
         };
 
-        let (internal_docid, external_docid) =
-            extract_document_id(
-                &primary_key,
-                &document,
-                &external_docids_get,
-                &mut available_ids,
-            )?;
+        let result = extract_document_id(
+            &primary_key,
+            &document,
+            &external_docids_get,
+            &mut available_ids,
+        );
+
+        let (internal_docid, external_docid) = match result {
+            Ok(ids) => ids,
+            Err(error) => {
+                let error = Error::from(error);
+                invalid_documents += 1;
+                if document_errors.len() < MAX_DOCUMENT_ERRORS {
+                    document_errors.push(DocumentError {
+                        index: position,
+                        document_id: match document.get(primary_key) {
+                            Some(Value::String(id)) => Some(id.clone()),
+                            Some(Value::Number(id)) => Some(id.to_string()),
+                            _ => None,
+                        },
+                        message: error.to_string(),
+                    });
+                }
+                first_error.get_or_insert(error);
+                continue;
+            }
+        };
+
+        // there is no need to go further once a document is invalid
+        if first_error.is_some() {
+            continue;
+        }
 
         new_external_docids.insert(external_docid, internal_docid.0 as u64);
         new_internal_docids.push(internal_docid);
 
-        if partial {
+        if let Some(merge_strategy) = partial {
             let mut deserializer = Deserializer {
                 document_id: internal_docid,
                 reader: writer,
@@ -202,13 +367,26 @@ pub fn apply_addition<'a, 'b>(
             let old_document = Option::<HashMap<String, Value>>::deserialize(&mut deserializer)?;
             if let Some(old_document) = old_document {
                 for (key, value) in old_document {
-                    document.entry(key).or_insert(value);
+                    match document.entry(key) {
+                        indexmap::map::Entry::Vacant(entry) => { entry.insert(value); },
+                        indexmap::map::Entry::Occupied(mut entry) => {
+                            merge_values(value, entry.get_mut(), merge_strategy)
+                        },
+                    }
                 }
             }
         }
         documents_additions.insert(internal_docid, document);
     }
 
+    if let Some(first) = first_error {
+        return Err(Error::InvalidDocuments {
+            first: Box::new(first),
+            errors: document_errors,
+            count: invalid_documents,
+        });
+    }
+
     // 2. remove the documents postings lists
     let number_of_inserted_documents = documents_additions.len();
     let documents_ids = new_external_docids.iter().map(|(id, _)| id.clone()).collect();
@@ -220,29 +398,35 @@ pub fn apply_addition<'a, 'b>(
     };
 
     let stop_words = index.main.stop_words_fst(writer)?.map_data(Cow::into_owned)?;
+    let separators = index.main.separators(writer)?;
+    let normalization = index.main.normalization(writer)?;
 
-
-    let mut indexer = RawIndexer::new(stop_words);
-
-    // For each document in this update
+    // 3. store the documents fields and collect the indexed values,
+    //    they are tokenized in parallel but written by this single writer
+    let mut values_to_index = Vec::new();
     for (document_id, document) in &documents_additions {
         // For each key-value pair in the document.
         for (attribute, value) in document {
             let field_id = schema.insert_and_index(&attribute)?;
-            index_document(
-                writer,
-                index.documents_fields,
-                index.documents_fields_counts,
-                &mut ranked_map,
-                &mut indexer,
-                &schema,
-                field_id,
-                *document_id,
-                &value,
-            )?;
+            let serialized = serde_json::to_vec(value)?;
+            index.documents_fields.put_document_field(writer, *document_id, field_id, &serialized)?;
+
+            if let Some(indexed_pos) = schema.is_indexed(field_id) {
+                values_to_index.push((*document_id, *indexed_pos, value));
+            }
+            update_ranked_map(&mut ranked_map, &schema, field_id, *document_id, value);
+
+            if attribute == GEO_ATTRIBUTE {
+                if let Some(location) = value_to_geo_location(value) {
+                    index.geo_locations.put_location(writer, *document_id, location)?;
+                }
+            }
         }
     }
 
+    let tokenizer_modes = indexed_tokenizer_modes(&schema, &index.main.attribute_tokenizers(writer)?);
+    let indexer = index_values(writer, index.documents_fields_counts, &stop_words, &separators, normalization, &tokenizer_modes, &values_to_index)?;
+
     write_documents_addition_index(
         writer,
         index,
@@ -276,8 +460,9 @@ pub fn apply_documents_partial_addition<'a, 'b>(
     writer: &'a mut heed::RwTxn<'b, MainT>,
     index: &store::Index,
     new_documents: Vec<IndexMap<String, Value>>,
+    merge_strategy: MergeStrategy,
 ) -> MResult<()> {
-    apply_addition(writer, index, new_documents, true)
+    apply_addition(writer, index, new_documents, Some(merge_strategy))
 }
 
 pub fn apply_documents_addition<'a, 'b>(
@@ -285,7 +470,7 @@ pub fn apply_documents_addition<'a, 'b>(
     index: &store::Index,
     new_documents: Vec<IndexMap<String, Value>>,
 ) -> MResult<()> {
-    apply_addition(writer, index, new_documents, false)
+    apply_addition(writer, index, new_documents, None)
 }
 
 pub fn reindex_all_documents(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
@@ -310,42 +495,56 @@ pub fn reindex_all_documents(writer: &mut heed::RwTxn<MainT>, index: &store::Ind
     index.facets.clear(writer)?;
     index.postings_lists.clear(writer)?;
     index.docs_words.clear(writer)?;
+    index.geo_locations.clear(writer)?;
 
     let stop_words = index.main
         .stop_words_fst(writer)?
         .map_data(Cow::into_owned)
         .unwrap();
+    let separators = index.main.separators(writer)?;
+    let normalization = index.main.normalization(writer)?;
+    let tokenizer_modes = indexed_tokenizer_modes(&schema, &index.main.attribute_tokenizers(writer)?);
 
+    let geo_field_id = schema.id(GEO_ATTRIBUTE);
     let number_of_inserted_documents = documents_ids_to_reindex.len();
-    let mut indexer = RawIndexer::new(stop_words);
-    let mut ram_store = HashMap::new();
+    let mut indexer = RawIndexer::new(fst::Set::new(stop_words.as_fst().as_bytes())?)
+        .with_separators(separators.clone())
+        .with_normalization(normalization)
+        .with_tokenizer_modes(tokenizer_modes.clone());
 
     if let Some(ref attributes_for_facetting) = index.main.attributes_for_faceting(writer)? {
         let facet_map = facets::facet_map_from_docids(writer, &index, &documents_ids_to_reindex, &attributes_for_facetting)?;
         index.facets.add(writer, facet_map)?;
     }
-    // ^-- https://github.com/meilisearch/MeiliSearch/pull/631#issuecomment-626624470 --v
-    for document_id in &documents_ids_to_reindex {
-        for result in index.documents_fields.document_fields(writer, *document_id)? {
-            let (field_id, bytes) = result?;
-            let value: Value = serde_json::from_slice(bytes)?;
-            ram_store.insert((document_id, field_id), value);
+
+    // 3. tokenize the documents by batches, the values of a batch are tokenized
+    //    in parallel but the fields counts are written by this single writer
+    for documents_ids in documents_ids_to_reindex.chunks(REINDEXING_BATCH_SIZE) {
+        let mut values = Vec::new();
+        for document_id in documents_ids {
+            for result in index.documents_fields.document_fields(writer, *document_id)? {
+                let (field_id, bytes) = result?;
+                let value: Value = serde_json::from_slice(bytes)?;
+                values.push((*document_id, field_id, value));
+            }
         }
 
-        // For each key-value pair in the document.
-        for ((document_id, field_id), value) in ram_store.drain() {
-            index_document(
-                writer,
-                index.documents_fields,
-                index.documents_fields_counts,
-                &mut ranked_map,
-                &mut indexer,
-                &schema,
-                field_id,
-                *document_id,
-                &value,
-            )?;
+        let mut values_to_index = Vec::new();
+        for (document_id, field_id, value) in &values {
+            if let Some(indexed_pos) = schema.is_indexed(*field_id) {
+                values_to_index.push((*document_id, *indexed_pos, value));
+            }
+            update_ranked_map(&mut ranked_map, &schema, *field_id, *document_id, value);
+
+            if Some(*field_id) == geo_field_id {
+                if let Some(location) = value_to_geo_location(value) {
+                    index.geo_locations.put_location(writer, *document_id, location)?;
+                }
+            }
         }
+
+        let batch_indexer = index_values(writer, index.documents_fields_counts, &stop_words, &separators, normalization, &tokenizer_modes, &values_to_index)?;
+        indexer.merge(batch_indexer);
     }
 
     // 4. write the new index in the main store
@@ -373,6 +572,25 @@ pub fn reindex_all_documents(writer: &mut heed::RwTxn<MainT>, index: &store::Ind
     Ok(())
 }
 
+/// Stores the locations of all the documents, for the indexes created before they were stored.
+pub fn index_geo_locations(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+    let field_id = match index.main.schema(writer)?.and_then(|schema| schema.id(GEO_ATTRIBUTE)) {
+        Some(field_id) => field_id,
+        None => return Ok(()),
+    };
+
+    let documents_ids = index.main.internal_docids(writer)?.to_vec();
+    for document_id in documents_ids {
+        if let Some(value) = index.document_attribute::<Value>(writer, document_id, field_id)? {
+            if let Some(location) = value_to_geo_location(&value) {
+                index.geo_locations.put_location(writer, document_id, location)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 pub fn write_documents_addition_index<A>(
     writer: &mut heed::RwTxn<MainT>,
     index: &store::Index,
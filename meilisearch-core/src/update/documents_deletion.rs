@@ -1,4 +1,6 @@
 use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 use fst::{SetBuilder, Streamer};
 use sdset::{duo::DifferenceByKey, SetBuf, SetOperation};
@@ -7,14 +9,17 @@ use crate::database::{MainT, UpdateT};
 use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::facets;
 use crate::store;
-use crate::update::{next_update_id, compute_short_prefixes, Update};
+use crate::update::{next_update_id, compute_short_prefixes, Update, UpdatePriority};
 use crate::{DocumentId, Error, MResult, RankedMap, MainWriter, Index};
 
 pub struct DocumentsDeletion {
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
     updates_notifier: UpdateEventsEmitter,
+    read_only: Arc<AtomicBool>,
+    storage_full: Arc<AtomicBool>,
     external_docids: Vec<String>,
+    priority: UpdatePriority,
 }
 
 impl DocumentsDeletion {
@@ -22,26 +27,37 @@ impl DocumentsDeletion {
         updates_store: store::Updates,
         updates_results_store: store::UpdatesResults,
         updates_notifier: UpdateEventsEmitter,
+        read_only: Arc<AtomicBool>,
+        storage_full: Arc<AtomicBool>,
     ) -> DocumentsDeletion {
         DocumentsDeletion {
             updates_store,
             updates_results_store,
             updates_notifier,
+            read_only,
+            storage_full,
             external_docids: Vec::new(),
+            priority: UpdatePriority::default(),
         }
     }
 
+    pub fn set_priority(&mut self, priority: UpdatePriority) {
+        self.priority = priority;
+    }
+
     pub fn delete_document_by_external_docid(&mut self, document_id: String) {
         self.external_docids.push(document_id);
     }
 
     pub fn finalize(self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64> {
+        store::check_writable(&self.read_only, &self.storage_full)?;
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         let update_id = push_documents_deletion(
             writer,
             self.updates_store,
             self.updates_results_store,
             self.external_docids,
+            self.priority,
         )?;
         Ok(update_id)
     }
@@ -58,11 +74,12 @@ pub fn push_documents_deletion(
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
     external_docids: Vec<String>,
+    priority: UpdatePriority,
 ) -> MResult<u64> {
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
 
-    let update = Update::documents_deletion(external_docids);
-    updates_store.put_update(writer, last_update_id, &update)?;
+    let update = Update::documents_deletion(external_docids).with_priority(priority);
+    updates_store.put_update(writer, last_update_id, &update, None)?;
 
     Ok(last_update_id)
 }
@@ -114,6 +131,8 @@ pub fn apply_documents_deletion(
             ranked_map.remove(id, *ranked_attr);
         }
 
+        index.geo_locations.del_location(writer, id)?;
+
         let words = index.docs_words.doc_words(writer, id)?;
         if !words.is_empty() {
             let mut stream = words.stream();
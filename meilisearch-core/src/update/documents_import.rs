@@ -0,0 +1,137 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database::{MainT, UpdateT};
+use crate::settings::SettingsUpdate;
+use crate::update::{apply_documents_addition, apply_settings_update, next_update_id, Update};
+use crate::{store, Error, MResult};
+
+/// Downloads and parses the files of an import, their settings and documents are given
+/// to the sink while the files are read. The error is reported as the cause of the failure.
+pub type BoxImportFn = Box<dyn Fn(&DocumentsImport, &mut dyn ImportSink) -> Result<(), String> + Send + Sync + 'static>;
+
+/// The files of documents to download and index, they are read by the importer
+/// registered with [`Database::set_import_callback`](crate::Database::set_import_callback).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentsImport {
+    pub url: String,
+    /// The format of the files, as named by the importer.
+    pub format: String,
+    /// Sent with the requests, it is kept until the import is processed but never reported nor journaled.
+    #[serde(default)]
+    pub authorization: Option<String>,
+    /// The primary key of the index when it has none, the importer infers it otherwise.
+    #[serde(default)]
+    pub primary_key: Option<String>,
+    /// The settings to apply before the documents are added.
+    #[serde(default)]
+    pub settings_url: Option<String>,
+}
+
+impl DocumentsImport {
+    /// The url of the documents as it is reported, without its query string
+    /// which often carries credentials, like the signature of presigned urls.
+    pub fn reported_url(&self) -> String {
+        let end = self.url.find(|c| c == '?' || c == '#').unwrap_or(self.url.len());
+        self.url[..end].to_string()
+    }
+}
+
+/// Receives the content of the files of an import while they are read. An error means
+/// that the import is failed and that the importer must stop reading.
+pub trait ImportSink {
+    /// Applies the settings of the import, they must be given before the documents.
+    fn settings(&mut self, settings: SettingsUpdate) -> Result<(), String>;
+
+    /// Adds a batch of documents, the index takes the given primary key if it has none.
+    fn documents(&mut self, primary_key: Option<&str>, documents: Vec<IndexMap<String, Value>>) -> Result<(), String>;
+}
+
+pub fn push_documents_import(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    import: DocumentsImport,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+
+    let update = Update::documents_import(import);
+    updates_store.put_update(writer, last_update_id, &update, None)?;
+
+    Ok(last_update_id)
+}
+
+/// Applies the settings and the documents of the import in the transaction, the documents
+/// are indexed batch by batch while the files are downloaded. Returns the number of documents.
+pub fn apply_documents_import<'a, 'b>(
+    writer: &'a mut heed::RwTxn<'b, MainT>,
+    index: &store::Index,
+    import: &DocumentsImport,
+    importer: Option<&BoxImportFn>,
+) -> MResult<usize> {
+    let importer = importer.ok_or_else(|| Error::ImportFailed("no importer is registered".to_string()))?;
+
+    let mut sink = IndexSink { writer, index, number: 0, error: None };
+    let result = (importer)(import, &mut sink);
+
+    match (sink.error, result) {
+        (Some(error), _) => Err(error),
+        (None, Err(message)) => Err(Error::ImportFailed(message)),
+        (None, Ok(())) => Ok(sink.number),
+    }
+}
+
+struct IndexSink<'a, 'b, 'i> {
+    writer: &'a mut heed::RwTxn<'b, MainT>,
+    index: &'i store::Index,
+    /// The number of documents added by the previous batches.
+    number: usize,
+    error: Option<Error>,
+}
+
+impl IndexSink<'_, '_, '_> {
+    /// Keeps the error to report it instead of the one of the importer.
+    fn keep(&mut self, result: MResult<()>) -> Result<(), String> {
+        result.map_err(|error| {
+            let message = error.to_string();
+            self.error = Some(error);
+            message
+        })
+    }
+}
+
+impl ImportSink for IndexSink<'_, '_, '_> {
+    fn settings(&mut self, settings: SettingsUpdate) -> Result<(), String> {
+        let result = apply_settings_update(self.writer, self.index, settings);
+        self.keep(result)
+    }
+
+    fn documents(&mut self, primary_key: Option<&str>, documents: Vec<IndexMap<String, Value>>) -> Result<(), String> {
+        let number = documents.len();
+        let offset = self.number;
+        let result = ensure_primary_key(self.writer, self.index, primary_key)
+            .and_then(|()| apply_documents_addition(self.writer, self.index, documents))
+            .map_err(|error| match error {
+                // the invalid documents are reported by their position in the file
+                Error::InvalidDocuments { first, mut errors, count } => {
+                    errors.iter_mut().for_each(|error| error.index += offset);
+                    Error::InvalidDocuments { first, errors, count }
+                }
+                error => error,
+            });
+
+        self.number += number;
+        self.keep(result)
+    }
+}
+
+fn ensure_primary_key(writer: &mut heed::RwTxn<MainT>, index: &store::Index, primary_key: Option<&str>) -> MResult<()> {
+    let mut schema = index.main.schema(writer)?.ok_or(Error::SchemaMissing)?;
+    if schema.primary_key().is_none() {
+        schema.set_primary_key(primary_key.ok_or(Error::MissingPrimaryKey)?)?;
+        index.main.put_schema(writer, &schema)?;
+    }
+    Ok(())
+}
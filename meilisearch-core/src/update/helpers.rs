@@ -7,9 +7,11 @@ use ordered_float::OrderedFloat;
 use serde_json::Value;
 
 use crate::Number;
+use crate::date::parse_date;
 use crate::raw_indexer::RawIndexer;
 use crate::serde::SerializerError;
-use crate::store::DiscoverIds;
+use crate::settings::TokenizerMode;
+use crate::store::{DiscoverIds, GeoLocation};
 
 /// Returns the number of words indexed or `None` if the type is unindexable.
 pub fn index_value<A>(
@@ -34,6 +36,12 @@ where A: AsRef<[u8]>,
         Value::String(string) => {
             Some(indexer.index_text(document_id, indexed_pos, &string))
         },
+        Value::Array(values) if indexer.tokenizer_mode(indexed_pos) == TokenizerMode::Keyword => {
+            // each value of a keyword attribute is a word of its own
+            let texts: Vec<_> = values.iter().map(value_to_string).collect();
+            indexer.index_text_seq(document_id, indexed_pos, texts.iter().map(String::as_str));
+            Some(texts.iter().filter(|text| !text.trim().is_empty()).count())
+        },
         Value::Array(_) => {
             let text = value_to_string(value);
             Some(indexer.index_text(document_id, indexed_pos, &text))
@@ -90,12 +98,26 @@ pub fn value_to_number(value: &Value) -> Option<Number> {
                 (None, None, None) => None,
             }
         },
-        Value::String(string) => Number::from_str(string).ok(),
+        // the dates are ranked by their timestamps
+        Value::String(string) => Number::from_str(string).ok().or_else(|| parse_date(string).map(Number::Signed)),
         Value::Array(_array) => None,
         Value::Object(_object) => None,
     }
 }
 
+/// The location of a `_geo` value, an object with a `lat` and a `lng` given as numbers or strings.
+pub fn value_to_geo_location(value: &Value) -> Option<GeoLocation> {
+    let coordinate = |value: &Value| match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.parse().ok(),
+        _ => None,
+    };
+
+    let lat = coordinate(value.get("lat")?)?;
+    let lng = coordinate(value.get("lng")?)?;
+    Some(GeoLocation { lat, lng })
+}
+
 /// Validates a string representation to be a correct document id and returns
 /// the corresponding id or generate a new one, this is the way we produce documents ids.
 pub fn discover_document_id<F>(
@@ -106,7 +128,7 @@ pub fn discover_document_id<F>(
 where
     F: FnOnce(&str) -> Option<u32>
 {
-    if docid.chars().all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_') {
+    if is_valid_document_id(docid) {
         match external_docids_get(docid) {
             Some(id) => Ok(DocumentId(id)),
             None => {
@@ -119,6 +141,25 @@ where
     }
 }
 
+/// Returns the string representation of a document id value if its type and format are valid.
+pub fn value_to_document_id(value: &Value) -> Result<String, SerializerError> {
+    let docid = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(string) => string.clone(),
+        _ => return Err(SerializerError::InvalidDocumentIdFormat),
+    };
+
+    if is_valid_document_id(&docid) {
+        Ok(docid)
+    } else {
+        Err(SerializerError::InvalidDocumentIdFormat)
+    }
+}
+
+fn is_valid_document_id(docid: &str) -> bool {
+    docid.chars().all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+}
+
 /// Extracts and validates the document id of a document.
 pub fn extract_document_id<F>(
     primary_key: &str,
@@ -2,14 +2,17 @@ mod clear_all;
 mod customs_update;
 mod documents_addition;
 mod documents_deletion;
+mod documents_import;
 mod settings_update;
 mod helpers;
 
 pub use self::clear_all::{apply_clear_all, push_clear_all};
 pub use self::customs_update::{apply_customs_update, push_customs_update};
-pub use self::documents_addition::{apply_documents_addition, apply_documents_partial_addition, DocumentsAddition};
+pub use self::documents_addition::{apply_documents_addition, apply_documents_partial_addition, DocumentsAddition, MergeStrategy};
+pub(crate) use self::documents_addition::index_geo_locations;
 pub use self::documents_deletion::{apply_documents_deletion, DocumentsDeletion};
-pub use self::helpers::{index_value, value_to_string, value_to_number, discover_document_id, extract_document_id};
+pub use self::documents_import::{apply_documents_import, push_documents_import, BoxImportFn, DocumentsImport, ImportSink};
+pub use self::helpers::{index_value, value_to_string, value_to_number, value_to_geo_location, value_to_document_id, discover_document_id, extract_document_id};
 pub use self::settings_update::{apply_settings_update, push_settings_update};
 
 use std::cmp;
@@ -19,7 +22,7 @@ use chrono::{DateTime, Utc};
 use fst::{IntoStreamer, Streamer};
 use heed::Result as ZResult;
 use indexmap::IndexMap;
-use log::debug;
+use log::{debug, warn};
 use sdset::Set;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -27,21 +30,55 @@ use serde_json::Value;
 use meilisearch_error::ErrorCode;
 use meilisearch_types::DocumentId;
 
-use crate::{store, MResult, RankedMap};
+use crate::{store, Error, MResult, RankedMap};
 use crate::database::{MainT, UpdateT};
 use crate::settings::SettingsUpdate;
 
+/// The lane in which an update is enqueued, the updates of the highest priority
+/// lane are processed first and in the order they were enqueued. An update never
+/// overtakes the ones enqueued before it that modify the same documents, these are
+/// moved to its lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdatePriority {
+    Bulk,
+    Normal,
+    Urgent,
+}
+
+impl Default for UpdatePriority {
+    fn default() -> UpdatePriority {
+        UpdatePriority::Normal
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Update {
     data: UpdateData,
     enqueued_at: DateTime<Utc>,
+    #[serde(default)]
+    priority: UpdatePriority,
 }
 
 impl Update {
+    pub fn priority(&self) -> UpdatePriority {
+        self.priority
+    }
+
+    pub fn data(&self) -> &UpdateData {
+        &self.data
+    }
+
+    fn with_priority(mut self, priority: UpdatePriority) -> Update {
+        self.priority = priority;
+        self
+    }
+
     fn clear_all() -> Update {
         Update {
             data: UpdateData::ClearAll,
             enqueued_at: Utc::now(),
+            priority: UpdatePriority::default(),
         }
     }
 
@@ -49,6 +86,7 @@ impl Update {
         Update {
             data: UpdateData::Customs(data),
             enqueued_at: Utc::now(),
+            priority: UpdatePriority::default(),
         }
     }
 
@@ -56,13 +94,15 @@ impl Update {
         Update {
             data: UpdateData::DocumentsAddition(documents),
             enqueued_at: Utc::now(),
+            priority: UpdatePriority::default(),
         }
     }
 
-    fn documents_partial(documents: Vec<IndexMap<String, Value>>) -> Update {
+    fn documents_partial(documents: Vec<IndexMap<String, Value>>, merge_strategy: MergeStrategy) -> Update {
         Update {
-            data: UpdateData::DocumentsPartial(documents),
+            data: UpdateData::DocumentsPartial { documents, merge_strategy },
             enqueued_at: Utc::now(),
+            priority: UpdatePriority::default(),
         }
     }
 
@@ -70,13 +110,32 @@ impl Update {
         Update {
             data: UpdateData::DocumentsDeletion(data),
             enqueued_at: Utc::now(),
+            priority: UpdatePriority::default(),
+        }
+    }
+
+    fn documents_import(import: DocumentsImport) -> Update {
+        Update {
+            data: UpdateData::DocumentsImport(import),
+            enqueued_at: Utc::now(),
+            priority: UpdatePriority::default(),
+        }
+    }
+
+    /// The update without the credentials it must not leave the updates store with.
+    pub fn without_credentials(&self) -> Update {
+        let mut update = self.clone();
+        if let UpdateData::DocumentsImport(import) = &mut update.data {
+            import.authorization = None;
         }
+        update
     }
 
     fn settings(data: SettingsUpdate) -> Update {
         Update {
             data: UpdateData::Settings(Box::new(data)),
             enqueued_at: Utc::now(),
+            priority: UpdatePriority::default(),
         }
     }
 }
@@ -86,9 +145,14 @@ pub enum UpdateData {
     ClearAll,
     Customs(Vec<u8>),
     DocumentsAddition(Vec<IndexMap<String, Value>>),
-    DocumentsPartial(Vec<IndexMap<String, Value>>),
+    DocumentsPartial {
+        documents: Vec<IndexMap<String, Value>>,
+        #[serde(default)]
+        merge_strategy: MergeStrategy,
+    },
     DocumentsDeletion(Vec<String>),
-    Settings(Box<SettingsUpdate>)
+    Settings(Box<SettingsUpdate>),
+    DocumentsImport(DocumentsImport),
 }
 
 impl UpdateData {
@@ -99,8 +163,8 @@ impl UpdateData {
             UpdateData::DocumentsAddition(addition) => UpdateType::DocumentsAddition {
                 number: addition.len(),
             },
-            UpdateData::DocumentsPartial(addition) => UpdateType::DocumentsPartial {
-                number: addition.len(),
+            UpdateData::DocumentsPartial { documents, .. } => UpdateType::DocumentsPartial {
+                number: documents.len(),
             },
             UpdateData::DocumentsDeletion(deletion) => UpdateType::DocumentsDeletion {
                 number: deletion.len(),
@@ -108,6 +172,66 @@ impl UpdateData {
             UpdateData::Settings(update) => UpdateType::Settings {
                 settings: update.clone(),
             },
+            UpdateData::DocumentsImport(import) => UpdateType::DocumentsImport {
+                url: import.reported_url(),
+                number: None,
+            },
+        }
+    }
+}
+
+/// What an enqueued update modifies. The updates modifying the same things are processed
+/// in the order they were enqueued, whatever their priorities.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UpdateFootprint {
+    /// The update can modify any document or the settings of the index.
+    Index,
+    /// The update only modifies the documents with these sorted ids.
+    Documents(Vec<String>),
+}
+
+impl UpdateFootprint {
+    /// The footprint of the update, the documents it adds are only known by their
+    /// primary key, the additions modify the whole index when it is not known.
+    pub fn of(update: &Update, primary_key: Option<&str>) -> UpdateFootprint {
+        let ids: Option<Vec<String>> = match (&update.data, primary_key) {
+            (UpdateData::DocumentsDeletion(ids), _) => Some(ids.clone()),
+            (UpdateData::DocumentsAddition(documents), Some(primary_key))
+            | (UpdateData::DocumentsPartial { documents, .. }, Some(primary_key)) => {
+                documents.iter().map(|document| match document.get(primary_key) {
+                    Some(Value::Number(number)) => Some(number.to_string()),
+                    Some(Value::String(string)) => Some(string.clone()),
+                    _ => None,
+                }).collect()
+            }
+            _ => None,
+        };
+
+        match ids {
+            Some(mut ids) => {
+                ids.sort_unstable();
+                ids.dedup();
+                UpdateFootprint::Documents(ids)
+            }
+            None => UpdateFootprint::Index,
+        }
+    }
+
+    /// Whether the two updates modify the same documents or the whole index.
+    pub fn overlaps(&self, other: &UpdateFootprint) -> bool {
+        match (self, other) {
+            (UpdateFootprint::Documents(ids), UpdateFootprint::Documents(other_ids)) => {
+                let (mut ids, mut other_ids) = (ids.iter().peekable(), other_ids.iter().peekable());
+                while let (Some(id), Some(other_id)) = (ids.peek(), other_ids.peek()) {
+                    match id.cmp(other_id) {
+                        cmp::Ordering::Less => { ids.next(); },
+                        cmp::Ordering::Greater => { other_ids.next(); },
+                        cmp::Ordering::Equal => return true,
+                    }
+                }
+                false
+            }
+            _ => true,
         }
     }
 }
@@ -121,6 +245,27 @@ pub enum UpdateType {
     DocumentsPartial { number: usize },
     DocumentsDeletion { number: usize },
     Settings { settings: Box<SettingsUpdate> },
+    /// The number of documents is known once the import is processed.
+    DocumentsImport {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        number: Option<usize>,
+    },
+}
+
+impl UpdateType {
+    /// The name of the update type, as used in the serialized `type` object.
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpdateType::ClearAll => "ClearAll",
+            UpdateType::Customs => "Customs",
+            UpdateType::DocumentsAddition { .. } => "DocumentsAddition",
+            UpdateType::DocumentsPartial { .. } => "DocumentsPartial",
+            UpdateType::DocumentsDeletion { .. } => "DocumentsDeletion",
+            UpdateType::Settings { .. } => "Settings",
+            UpdateType::DocumentsImport { .. } => "DocumentsImport",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,6 +285,24 @@ pub struct ProcessedUpdateResult {
     pub duration: f64, // in seconds
     pub enqueued_at: DateTime<Utc>,
     pub processed_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canceled_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub document_errors: Vec<DocumentError>,
+}
+
+/// The maximum number of per-document errors reported in an update result.
+pub const MAX_DOCUMENT_ERRORS: usize = 100;
+
+/// The reason why a document of an update has been rejected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentError {
+    /// The position of the document in the update payload.
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<String>,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -166,6 +329,67 @@ pub enum UpdateStatus {
         #[serde(flatten)]
         content: ProcessedUpdateResult,
     },
+    Canceled {
+        #[serde(flatten)]
+        content: ProcessedUpdateResult,
+    },
+}
+
+impl UpdateStatus {
+    pub fn update_id(&self) -> u64 {
+        match self {
+            UpdateStatus::Enqueued { content } => content.update_id,
+            UpdateStatus::Failed { content }
+            | UpdateStatus::Processed { content }
+            | UpdateStatus::Canceled { content } => content.update_id,
+        }
+    }
+
+    pub fn update_type(&self) -> &UpdateType {
+        match self {
+            UpdateStatus::Enqueued { content } => &content.update_type,
+            UpdateStatus::Failed { content }
+            | UpdateStatus::Processed { content }
+            | UpdateStatus::Canceled { content } => &content.update_type,
+        }
+    }
+
+    pub fn enqueued_at(&self) -> DateTime<Utc> {
+        match self {
+            UpdateStatus::Enqueued { content } => content.enqueued_at,
+            UpdateStatus::Failed { content }
+            | UpdateStatus::Processed { content }
+            | UpdateStatus::Canceled { content } => content.enqueued_at,
+        }
+    }
+
+    pub fn processed_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            UpdateStatus::Enqueued { .. } => None,
+            UpdateStatus::Failed { content }
+            | UpdateStatus::Processed { content }
+            | UpdateStatus::Canceled { content } => Some(content.processed_at),
+        }
+    }
+
+    /// The time spent processing the update, canceled updates have not been processed.
+    pub fn duration(&self) -> Option<f64> {
+        match self {
+            UpdateStatus::Enqueued { .. } | UpdateStatus::Canceled { .. } => None,
+            UpdateStatus::Failed { content }
+            | UpdateStatus::Processed { content } => Some(content.duration),
+        }
+    }
+
+    /// The name of the status, as used in the serialized `status` field.
+    pub fn status(&self) -> &'static str {
+        match self {
+            UpdateStatus::Enqueued { .. } => "enqueued",
+            UpdateStatus::Failed { .. } => "failed",
+            UpdateStatus::Processed { .. } => "processed",
+            UpdateStatus::Canceled { .. } => "canceled",
+        }
+    }
 }
 
 pub fn update_status(
@@ -176,7 +400,9 @@ pub fn update_status(
 ) -> MResult<Option<UpdateStatus>> {
     match updates_results_store.update_result(update_reader, update_id)? {
         Some(result) => {
-            if result.error.is_some() {
+            if result.canceled_at.is_some() {
+                Ok(Some(UpdateStatus::Canceled { content: result }))
+            } else if result.error.is_some() {
                 Ok(Some(UpdateStatus::Failed { content: result }))
             } else {
                 Ok(Some(UpdateStatus::Processed { content: result }))
@@ -195,6 +421,55 @@ pub fn update_status(
     }
 }
 
+/// Removes an enqueued update from the queue and stores a canceled result in its place.
+/// The caller must make sure that the update is not being processed.
+pub fn cancel_update(
+    update_writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    update_id: u64,
+) -> MResult<bool> {
+    let update = match updates_store.get(update_writer, update_id)? {
+        Some(update) => update,
+        None => return Ok(false),
+    };
+
+    let now = Utc::now();
+    let result = ProcessedUpdateResult {
+        update_id,
+        update_type: update.data.update_type(),
+        error: None,
+        error_code: None,
+        error_type: None,
+        error_link: None,
+        duration: 0.0,
+        enqueued_at: update.enqueued_at,
+        processed_at: now,
+        canceled_at: Some(now),
+        document_errors: Vec::new(),
+    };
+
+    updates_store.del_update(update_writer, update_id)?;
+    updates_results_store.put_update_result(update_writer, update_id, &result)?;
+
+    Ok(true)
+}
+
+/// Enqueues an update that has already been applied once, e.g. when replaying a journal, it is
+/// enqueued with the normal priority to be applied in the same order as before.
+pub fn push_update(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    update: Update,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+    let update = Update { enqueued_at: Utc::now(), ..update }.with_priority(UpdatePriority::default());
+    updates_store.put_update(writer, last_update_id, &update, None)?;
+
+    Ok(last_update_id)
+}
+
 pub fn next_update_id(
     update_writer: &mut heed::RwTxn<UpdateT>,
     updates_store: store::Updates,
@@ -217,10 +492,14 @@ pub fn update_task<'a, 'b>(
     index: &store::Index,
     update_id: u64,
     update: Update,
+    importer: Option<&BoxImportFn>,
 ) -> MResult<ProcessedUpdateResult> {
     debug!("Processing update number {}", update_id);
 
-    let Update { enqueued_at, data } = update;
+    let Update { enqueued_at, data, .. } = update;
+
+    // the updates that can only shrink the index are always accepted, even over quota
+    let shrinks = matches!(data, UpdateData::ClearAll | UpdateData::DocumentsDeletion(_));
 
     let (update_type, result, duration) = match data {
         UpdateData::ClearAll => {
@@ -250,14 +529,14 @@ pub fn update_task<'a, 'b>(
 
             (update_type, result, start.elapsed())
         }
-        UpdateData::DocumentsPartial(documents) => {
+        UpdateData::DocumentsPartial { documents, merge_strategy } => {
             let start = Instant::now();
 
             let update_type = UpdateType::DocumentsPartial {
                 number: documents.len(),
             };
 
-            let result = apply_documents_partial_addition(writer, index, documents);
+            let result = apply_documents_partial_addition(writer, index, documents, merge_strategy);
 
             (update_type, result, start.elapsed())
         }
@@ -287,13 +566,35 @@ pub fn update_task<'a, 'b>(
 
             (update_type, result, start.elapsed())
         }
+        UpdateData::DocumentsImport(import) => {
+            let start = Instant::now();
+
+            let result = apply_documents_import(writer, index, &import, importer);
+
+            let update_type = UpdateType::DocumentsImport {
+                url: import.reported_url(),
+                number: result.as_ref().ok().copied(),
+            };
+
+            (update_type, result.map(drop), start.elapsed())
+        }
     };
 
+    let result = result.and_then(|()| {
+        let size = index_size(writer, index);
+        if shrinks { Ok(()) } else { check_quotas(writer, index, size) }
+    });
+
     debug!(
         "Processed update number {} {:?} {:?}",
         update_id, update_type, result
     );
 
+    let document_errors = match &result {
+        Err(Error::InvalidDocuments { errors, .. }) => errors.clone(),
+        _ => Vec::new(),
+    };
+
     let status = ProcessedUpdateResult {
         update_id,
         update_type,
@@ -304,11 +605,126 @@ pub fn update_task<'a, 'b>(
         duration: duration.as_secs_f64(),
         enqueued_at,
         processed_at: Utc::now(),
+        canceled_at: None,
+        document_errors,
     };
 
     Ok(status)
 }
 
+/// Returns the size of the index once an update is applied, the sizes of its stores are kept up to date
+/// by their writers but the indexes created before that must be measured once. The update doesn't fail
+/// if the size can't be known, the size quota is then not checked.
+fn index_size(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> Option<u64> {
+    match index.measure_unknown_sizes(writer).and_then(|()| index.size(writer)) {
+        Ok(size) => Some(size),
+        Err(e) => {
+            warn!("Impossible to compute the size of the index; {}", e);
+            None
+        }
+    }
+}
+
+/// Returns an error if the index, once the update applied, is over one of its quotas.
+fn check_quotas(writer: &heed::RwTxn<MainT>, index: &store::Index, size: Option<u64>) -> MResult<()> {
+    let quotas = index.main.quotas(writer)?;
+
+    if let Some(max_documents) = quotas.max_documents {
+        let number_of_documents = index.main.number_of_documents(writer)?;
+        if number_of_documents > max_documents {
+            let message = format!("the index would contain {} documents, the limit is {}", number_of_documents, max_documents);
+            return Err(Error::IndexQuotaExceeded(message));
+        }
+    }
+
+    if let (Some(max_size), Some(size)) = (quotas.max_size, size) {
+        if size > max_size {
+            let message = format!("the index would weigh {} bytes, the limit is {}", size, max_size);
+            return Err(Error::IndexQuotaExceeded(message));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the settings updates that directly follow the given settings update
+/// in the same priority lane, these can be applied along with it in one go.
+pub fn coalescable_settings_updates(
+    update_reader: &heed::RoTxn<UpdateT>,
+    updates_store: store::Updates,
+    update_id: u64,
+    update: &Update,
+) -> MResult<Vec<(u64, Update)>> {
+    let mut updates = Vec::new();
+    if let UpdateData::Settings(_) = update.data {
+        let lane = updates_store.lane(update_reader, update_id)?;
+        let mut next_id = update_id + 1;
+        while let Some(next) = updates_store.get(update_reader, next_id)? {
+            let same_lane = updates_store.lane(update_reader, next_id)? == lane;
+            match next.data {
+                UpdateData::Settings(_) if same_lane => {
+                    updates.push((next_id, next));
+                    next_id += 1;
+                }
+                _ => break,
+            }
+        }
+    }
+    Ok(updates)
+}
+
+/// Merges the given settings updates into a single one and applies it, the
+/// documents are therefore reindexed only once for all of these updates.
+///
+/// Returns an error if the merged update can't be applied, in which case the
+/// caller must abort the transaction and process the updates one by one.
+pub fn coalesced_settings_task<'a, 'b>(
+    writer: &'a mut heed::RwTxn<'b, MainT>,
+    index: &store::Index,
+    updates: Vec<(u64, Update)>,
+) -> MResult<Vec<ProcessedUpdateResult>> {
+    let ids: Vec<_> = updates.iter().map(|(id, _)| *id).collect();
+    debug!("Processing coalesced settings updates {:?}", ids);
+
+    let start = Instant::now();
+
+    let mut merged = SettingsUpdate::default();
+    let mut infos = Vec::with_capacity(updates.len());
+    for (update_id, update) in updates {
+        if let UpdateData::Settings(settings) = update.data {
+            merged = merged.merge((*settings).clone());
+            let update_type = UpdateType::Settings { settings };
+            infos.push((update_id, update_type, update.enqueued_at));
+        }
+    }
+
+    apply_settings_update(writer, index, merged)?;
+    let size = index_size(writer, index);
+    check_quotas(writer, index, size)?;
+
+    let duration = start.elapsed().as_secs_f64();
+    let processed_at = Utc::now();
+
+    let statuses = infos
+        .into_iter()
+        .map(|(update_id, update_type, enqueued_at)| ProcessedUpdateResult {
+            update_id,
+            update_type,
+            error: None,
+            error_code: None,
+            error_type: None,
+            error_link: None,
+            duration,
+            enqueued_at,
+            processed_at,
+            canceled_at: None,
+            document_errors: Vec::new(),
+        })
+        .collect();
+
+    Ok(statuses)
+}
+
 fn compute_short_prefixes<A>(
     writer: &mut heed::RwTxn<MainT>,
     words_fst: &fst::Set<A>,
@@ -382,3 +798,236 @@ fn cache_document_ids_sorted(
     crate::bucket_sort::placeholder_document_sort(document_ids, index, writer, ranked_map)?;
     index.main.put_sorted_document_ids_cache(writer, &document_ids)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, DatabaseOptions};
+
+    #[test]
+    fn updates_are_processed_by_priority() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        // updates are put without notifying the update loop to keep them enqueued
+        let mut writer = database.update_write_txn().unwrap();
+        let updates = [UpdatePriority::Bulk, UpdatePriority::Normal, UpdatePriority::Urgent, UpdatePriority::Normal];
+        for (id, priority) in updates.iter().enumerate() {
+            let update = Update::documents_deletion(vec![id.to_string()]).with_priority(*priority);
+            index.updates.put_update(&mut writer, id as u64, &update, None).unwrap();
+        }
+
+        let mut order = Vec::new();
+        while let Some((id, _)) = index.updates.next_update(&writer).unwrap() {
+            index.updates.del_update(&mut writer, id).unwrap();
+            order.push(id);
+        }
+
+        assert_eq!(order, vec![2, 1, 3, 0]);
+    }
+
+    #[test]
+    fn updates_are_processed_after_the_ones_they_depend_on() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let addition = |id: u64| {
+            let document: IndexMap<String, Value> = vec![("id".to_string(), Value::from(id))].into_iter().collect();
+            Update::documents_addition(vec![document])
+        };
+        let deletion = |id: &str| Update::documents_deletion(vec![id.to_string()]);
+
+        let mut writer = database.update_write_txn().unwrap();
+        let updates = vec![
+            addition(1).with_priority(UpdatePriority::Normal),
+            deletion("2").with_priority(UpdatePriority::Bulk),
+            deletion("3").with_priority(UpdatePriority::Normal),
+            // the deletion of the document 1 must not overtake its addition
+            deletion("1").with_priority(UpdatePriority::Urgent),
+            deletion("4").with_priority(UpdatePriority::Bulk),
+            Update::clear_all().with_priority(UpdatePriority::Normal),
+            // the clear all depends on all the updates before it
+            deletion("6").with_priority(UpdatePriority::Urgent),
+        ];
+
+        let mut order = Vec::new();
+        for (id, update) in updates.iter().enumerate() {
+            index.updates.put_update(&mut writer, id as u64, update, Some("id")).unwrap();
+            if id == 3 {
+                while let Some((id, _)) = index.updates.next_update(&writer).unwrap() {
+                    index.updates.del_update(&mut writer, id).unwrap();
+                    order.push(id);
+                }
+            }
+        }
+        assert_eq!(order, vec![0, 3, 2, 1]);
+
+        assert_eq!(index.updates.lane(&writer, 4).unwrap(), Some(UpdatePriority::Urgent));
+        order.clear();
+        while let Some((id, _)) = index.updates.next_update(&writer).unwrap() {
+            index.updates.del_update(&mut writer, id).unwrap();
+            order.push(id);
+        }
+        assert_eq!(order, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn footprints_overlap_on_the_same_documents() {
+        let deletion = |ids: &[&str]| Update::documents_deletion(ids.iter().map(|id| id.to_string()).collect());
+        let footprint = |ids: &[&str]| UpdateFootprint::of(&deletion(ids), None);
+
+        assert_eq!(footprint(&["b", "a", "b"]), UpdateFootprint::Documents(vec!["a".to_string(), "b".to_string()]));
+        assert!(footprint(&["a", "c"]).overlaps(&footprint(&["b", "c"])));
+        assert!(!footprint(&["a", "c"]).overlaps(&footprint(&["b", "d"])));
+        assert!(UpdateFootprint::Index.overlaps(&footprint(&[])));
+
+        // the documents added are unknown without the primary key
+        let document: IndexMap<String, Value> = vec![("id".to_string(), Value::from(1))].into_iter().collect();
+        let addition = Update::documents_addition(vec![document]);
+        assert_eq!(UpdateFootprint::of(&addition, None), UpdateFootprint::Index);
+        assert_eq!(UpdateFootprint::of(&addition, Some("id")), UpdateFootprint::Documents(vec!["1".to_string()]));
+    }
+
+    #[test]
+    fn consecutive_settings_updates_are_coalesced() {
+        use std::collections::BTreeSet;
+        use crate::settings::UpdateState;
+
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let stop_words = |words: &[&str]| {
+            let words: BTreeSet<_> = words.iter().map(|w| w.to_string()).collect();
+            Update::settings(SettingsUpdate { stop_words: UpdateState::Update(words), ..SettingsUpdate::default() })
+        };
+
+        let mut writer = database.update_write_txn().unwrap();
+        let updates = vec![
+            stop_words(&["a"]),
+            stop_words(&["b", "c"]),
+            Update::settings(SettingsUpdate::default()),
+            Update::clear_all(),
+            stop_words(&["d"]),
+        ];
+        for (id, update) in updates.iter().enumerate() {
+            index.updates.put_update(&mut writer, id as u64, update, None).unwrap();
+        }
+
+        let (update_id, update) = index.updates.next_update(&writer).unwrap().unwrap();
+        let following = coalescable_settings_updates(&writer, index.updates, update_id, &update).unwrap();
+        let ids: Vec<_> = following.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        writer.abort().unwrap();
+
+        let mut updates = vec![(update_id, update)];
+        updates.extend(following);
+
+        let mut writer = database.main_write_txn().unwrap();
+        let statuses = coalesced_settings_task(&mut writer, &index, updates).unwrap();
+        writer.commit().unwrap();
+
+        let ids: Vec<_> = statuses.iter().map(|status| status.update_id).collect();
+        assert_eq!(ids, vec![0, 1, 2]);
+        assert!(statuses.iter().all(|status| status.error.is_none()));
+
+        let reader = database.main_read_txn().unwrap();
+        let stop_words = index.main.stop_words(&reader).unwrap();
+        assert_eq!(stop_words, vec!["b".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn updates_exceeding_the_quotas_fail() {
+        use meilisearch_schema::Schema;
+        use crate::store::IndexQuotas;
+
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let mut writer = database.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        index.main.put_quotas(&mut writer, IndexQuotas { max_documents: Some(1), max_size: None }).unwrap();
+        writer.commit().unwrap();
+
+        let documents = |ids: &[u64]| {
+            let documents = ids.iter().map(|id| {
+                let mut document = IndexMap::new();
+                document.insert("id".to_string(), Value::from(*id));
+                document
+            });
+            Update::documents_addition(documents.collect())
+        };
+
+        let mut writer = database.main_write_txn().unwrap();
+        let status = update_task(&mut writer, &index, 0, documents(&[1, 2]), None).unwrap();
+        assert_eq!(status.error_code.as_deref(), Some("index_quota_exceeded"));
+        writer.abort().unwrap();
+
+        let mut writer = database.main_write_txn().unwrap();
+        let status = update_task(&mut writer, &index, 1, documents(&[1]), None).unwrap();
+        assert!(status.error.is_none());
+        writer.commit().unwrap();
+
+        // an index over quota can still be emptied
+        let mut writer = database.main_write_txn().unwrap();
+        index.main.put_quotas(&mut writer, IndexQuotas { max_documents: Some(1), max_size: Some(0) }).unwrap();
+        let status = update_task(&mut writer, &index, 2, Update::documents_deletion(vec!["1".to_string()]), None).unwrap();
+        assert!(status.error.is_none());
+        writer.commit().unwrap();
+    }
+
+    #[test]
+    fn imports_add_the_documents_by_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        // the importer gives the documents of the url by batches of two, "1" is invalid in the second batch
+        let importer: BoxImportFn = Box::new(|import: &DocumentsImport, sink: &mut dyn ImportSink| {
+            let ids: Vec<Value> = serde_json::from_str(&import.url).map_err(|e| e.to_string())?;
+            for batch in ids.chunks(2) {
+                let documents = batch.iter().map(|id| {
+                    let mut document = IndexMap::new();
+                    document.insert("id".to_string(), id.clone());
+                    document
+                });
+                sink.documents(Some("id"), documents.collect())?;
+            }
+            Ok(())
+        });
+
+        let import = |url: &str| Update::documents_import(DocumentsImport {
+            url: url.to_string(),
+            format: "json".to_string(),
+            authorization: Some("Bearer secret".to_string()),
+            primary_key: None,
+            settings_url: None,
+        });
+
+        let mut writer = database.main_write_txn().unwrap();
+        let status = update_task(&mut writer, &index, 0, import("[1, 2, 3]"), None).unwrap();
+        assert_eq!(status.error_code.as_deref(), Some("import_failed"));
+        writer.abort().unwrap();
+
+        let mut writer = database.main_write_txn().unwrap();
+        let status = update_task(&mut writer, &index, 1, import("[1, 2, 3]"), Some(&importer)).unwrap();
+        assert!(status.error.is_none());
+        assert!(matches!(status.update_type, UpdateType::DocumentsImport { number: Some(3), .. }));
+        assert_eq!(index.main.number_of_documents(&writer).unwrap(), 3);
+        writer.commit().unwrap();
+
+        // the invalid documents are reported by their position in the file
+        let mut writer = database.main_write_txn().unwrap();
+        let status = update_task(&mut writer, &index, 2, import(r#"[4, 5, 6, "not valid"]"#), Some(&importer)).unwrap();
+        assert_eq!(status.document_errors.len(), 1);
+        assert_eq!(status.document_errors[0].index, 3);
+        writer.abort().unwrap();
+
+        // the credentials are not part of the update once it leaves the store
+        let update = import("[1]").without_credentials();
+        assert!(matches!(update.data(), UpdateData::DocumentsImport(DocumentsImport { authorization: None, .. })));
+    }
+}
@@ -3,11 +3,14 @@ use std::collections::{BTreeMap, BTreeSet};
 use heed::Result as ZResult;
 use fst::{set::OpBuilder, SetBuilder};
 use sdset::SetBuf;
+use meilisearch_tokenizer::{split_query_string_with_separators, Separators};
 use meilisearch_schema::Schema;
+use serde_json::Value;
 
 use crate::database::{MainT, UpdateT};
-use crate::settings::{UpdateState, SettingsUpdate, RankingRule};
+use crate::settings::{NormalizationSettings, UpdateState, SettingsUpdate, RankingRule};
 use crate::update::documents_addition::reindex_all_documents;
+use crate::update::helpers::value_to_document_id;
 use crate::update::{next_update_id, Update};
 use crate::{store, MResult, Error};
 
@@ -20,7 +23,7 @@ pub fn push_settings_update(
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
 
     let update = Update::settings(settings);
-    updates_store.put_update(writer, last_update_id, &update)?;
+    updates_store.put_update(writer, last_update_id, &update, None)?;
 
     Ok(last_update_id)
 }
@@ -42,6 +45,13 @@ pub fn apply_settings_update(
         }
     };
 
+    if let UpdateState::Update(primary_key) = &settings.primary_key {
+        if schema.primary_key() != Some(primary_key.as_str()) {
+            apply_primary_key_update(writer, index, &mut schema, primary_key)?;
+            must_reindex = true;
+        }
+    }
+
     match settings.ranking_rules {
         UpdateState::Update(v) => {
             let ranked_field: Vec<&str> = v.iter().filter_map(RankingRule::field).collect();
@@ -125,9 +135,138 @@ pub fn apply_settings_update(
         UpdateState::Nothing => (),
     }
 
+    let separator_tokens = match settings.separator_tokens {
+        UpdateState::Update(tokens) => Some(tokens),
+        UpdateState::Clear => Some(BTreeSet::new()),
+        UpdateState::Nothing => None,
+    };
+    if let Some(tokens) = separator_tokens {
+        if index.main.separator_tokens(writer)? != tokens {
+            index.main.put_separator_tokens(writer, &tokens)?;
+            must_reindex = true;
+        }
+    }
+
+    let non_separator_tokens = match settings.non_separator_tokens {
+        UpdateState::Update(tokens) => Some(tokens),
+        UpdateState::Clear => Some(BTreeSet::new()),
+        UpdateState::Nothing => None,
+    };
+    if let Some(tokens) = non_separator_tokens {
+        if index.main.non_separator_tokens(writer)? != tokens {
+            index.main.put_non_separator_tokens(writer, &tokens)?;
+            must_reindex = true;
+        }
+    }
+
+    let dictionary = match settings.dictionary {
+        UpdateState::Update(words) => Some(words),
+        UpdateState::Clear => Some(BTreeSet::new()),
+        UpdateState::Nothing => None,
+    };
+    if let Some(words) = dictionary {
+        if index.main.dictionary(writer)? != words {
+            index.main.put_dictionary(writer, &words)?;
+            must_reindex = true;
+        }
+    }
+
+    let languages = match settings.languages {
+        UpdateState::Update(languages) => Some(languages),
+        UpdateState::Clear => Some(BTreeSet::new()),
+        UpdateState::Nothing => None,
+    };
+    // the synonyms are split into words with the rules of the languages
+    let mut synonyms_outdated = false;
+    if let Some(languages) = languages {
+        if index.main.languages(writer)? != languages {
+            index.main.put_languages(writer, &languages)?;
+            synonyms_outdated = true;
+            must_reindex = true;
+        }
+    }
+
+    let identifier_splitting = match settings.identifier_splitting {
+        UpdateState::Update(splitting) => Some(splitting),
+        UpdateState::Clear => Some(false),
+        UpdateState::Nothing => None,
+    };
+    if let Some(splitting) = identifier_splitting {
+        if index.main.identifier_splitting(writer)? != splitting {
+            index.main.put_identifier_splitting(writer, splitting)?;
+            synonyms_outdated = true;
+            must_reindex = true;
+        }
+    }
+
+    let attribute_tokenizers = match settings.attribute_tokenizers {
+        UpdateState::Update(tokenizers) => Some(tokenizers),
+        UpdateState::Clear => Some(BTreeMap::new()),
+        UpdateState::Nothing => None,
+    };
+    if let Some(tokenizers) = attribute_tokenizers {
+        if index.main.attribute_tokenizers(writer)? != tokenizers {
+            index.main.put_attribute_tokenizers(writer, &tokenizers)?;
+            must_reindex = true;
+        }
+    }
+
+    let normalization = match settings.normalization {
+        UpdateState::Update(normalization) => Some(normalization),
+        UpdateState::Clear => Some(NormalizationSettings::default()),
+        UpdateState::Nothing => None,
+    };
+    if let Some(normalization) = normalization {
+        if index.main.normalization(writer)? != normalization {
+            index.main.put_normalization(writer, &normalization)?;
+            synonyms_outdated = true;
+            must_reindex = true;
+        }
+    }
+
     match settings.synonyms {
         UpdateState::Update(synonyms) => apply_synonyms_update(writer, index, synonyms)?,
         UpdateState::Clear => apply_synonyms_update(writer, index, BTreeMap::new())?,
+        // the stored synonyms are normalized again, the case and the diacritics
+        // they lost can't be restored, they must be sent again to be preserved
+        UpdateState::Nothing if synonyms_outdated => {
+            let mut synonyms = BTreeMap::new();
+            for word in index.main.synonyms(writer)? {
+                let alternatives = index.synonyms.synonyms(writer, word.as_bytes())?;
+                synonyms.insert(word, alternatives);
+            }
+            apply_synonyms_update(writer, index, synonyms)?;
+        }
+        UpdateState::Nothing => (),
+    }
+
+    match settings.faceting {
+        UpdateState::Update(faceting) => index.main.put_faceting(writer, &faceting)?,
+        UpdateState::Clear => {
+            index.main.delete_faceting(writer)?;
+        },
+        UpdateState::Nothing => (),
+    }
+
+    match settings.typo_tolerance {
+        UpdateState::Update(typo_tolerance) => index.main.put_typo_tolerance(writer, &typo_tolerance)?,
+        UpdateState::Clear => {
+            index.main.delete_typo_tolerance(writer)?;
+        },
+        UpdateState::Nothing => (),
+    }
+
+    match settings.ranking_behavior {
+        UpdateState::Update(behavior) => index.main.put_ranking_behavior(writer, &behavior)?,
+        UpdateState::Clear => {
+            index.main.delete_ranking_behavior(writer)?;
+        },
+        UpdateState::Nothing => (),
+    }
+
+    match settings.query_rules {
+        UpdateState::Update(rules) => index.main.put_query_rules(writer, &rules)?,
+        UpdateState::Clear => index.main.put_query_rules(writer, &[])?,
         UpdateState::Nothing => (),
     }
 
@@ -138,6 +277,33 @@ pub fn apply_settings_update(
     Ok(())
 }
 
+/// Changes the primary key of an index that may already contain documents. The documents
+/// keep their internal ids, only the external ids mapping is rebuilt from the new primary key.
+fn apply_primary_key_update(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    schema: &mut Schema,
+    primary_key: &str,
+) -> MResult<()> {
+    let field_id = schema.change_primary_key(primary_key)?;
+
+    let internal_docids = index.main.internal_docids(writer)?.to_vec();
+    let mut new_external_docids = BTreeMap::new();
+    for docid in internal_docids {
+        let value: Value = index
+            .document_attribute(writer, docid, field_id)?
+            .ok_or(Error::MissingDocumentId)?;
+        let external_docid = value_to_document_id(&value)?;
+
+        if new_external_docids.insert(external_docid.clone(), docid.0 as u64).is_some() {
+            return Err(Error::DuplicateDocumentId(external_docid));
+        }
+    }
+
+    let new_external_docids = fst::Map::from_iter(new_external_docids)?;
+    index.main.put_external_docids(writer, &new_external_docids)
+}
+
 fn apply_attributes_for_faceting_update(
     writer: &mut heed::RwTxn<MainT>,
     index: &store::Index,
@@ -290,13 +456,28 @@ pub fn apply_synonyms_update(
     let main_store = index.main;
     let synonyms_store = index.synonyms;
 
+    // the synonyms are looked up with the normalized words of the query,
+    // multi-word synonyms are stored with their words separated by a single space
+    let separators = main_store.separators(writer)?;
+    let normalization = main_store.normalization(writer)?;
+    let mut normalized: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (word, alternatives) in synonyms {
+        let word = normalize_synonym(&word, &separators, &normalization);
+        if word.is_empty() { continue }
+
+        let alternatives = alternatives.iter()
+            .map(|alternative| normalize_synonym(alternative, &separators, &normalization))
+            .filter(|alternative| !alternative.is_empty() && *alternative != word);
+        normalized.entry(word).or_default().extend(alternatives);
+    }
+
     let mut synonyms_builder = SetBuilder::memory();
     synonyms_store.clear(writer)?;
-    for (word, alternatives) in synonyms.clone() {
+    for (word, alternatives) in normalized {
+        if alternatives.is_empty() { continue }
         synonyms_builder.insert(&word)?;
 
         let alternatives = {
-            let alternatives = SetBuf::from_dirty(alternatives);
             let mut alternatives_builder = SetBuilder::memory();
             alternatives_builder.extend_iter(alternatives)?;
             alternatives_builder.into_set()
@@ -311,3 +492,10 @@ pub fn apply_synonyms_update(
 
     Ok(())
 }
+
+fn normalize_synonym(synonym: &str, separators: &Separators, normalization: &NormalizationSettings) -> String {
+    let words: Vec<_> = split_query_string_with_separators(synonym, separators)
+        .map(|word| normalization.normalize(word))
+        .collect();
+    words.join(" ")
+}
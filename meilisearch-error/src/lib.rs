@@ -50,6 +50,8 @@ pub enum Code {
     CreateIndex,
     IndexAlreadyExists,
     IndexNotFound,
+    IndexQuotaExceeded,
+    IndexReadOnly,
     InvalidIndexUid,
     OpenIndex,
 
@@ -60,6 +62,7 @@ pub enum Code {
 
     MaxFieldsLimitExceeded,
     MissingDocumentId,
+    DuplicateDocumentId,
 
     Facet,
     Filter,
@@ -79,6 +82,14 @@ pub enum Code {
 
     DumpAlreadyInProgress,
     DumpProcessFailed,
+
+    ImportFailed,
+
+    TooManyRequests,
+    OriginNotAllowed,
+    ShuttingDown,
+    FeatureNotEnabled,
+    InsufficientStorage,
 }
 
 impl Code {
@@ -94,6 +105,10 @@ impl Code {
             IndexAlreadyExists => ErrCode::invalid("index_already_exists", StatusCode::BAD_REQUEST),
             // thrown when requesting an unexisting index
             IndexNotFound => ErrCode::invalid("index_not_found", StatusCode::NOT_FOUND),
+            // thrown when an update would make an index grow past its quotas
+            IndexQuotaExceeded => ErrCode::invalid("index_quota_exceeded", StatusCode::PAYLOAD_TOO_LARGE),
+            // thrown when modifying an index that has been made read-only
+            IndexReadOnly => ErrCode::invalid("index_read_only", StatusCode::CONFLICT),
             InvalidIndexUid => ErrCode::invalid("invalid_index_uid", StatusCode::BAD_REQUEST),
             OpenIndex => ErrCode::internal("index_not_accessible", StatusCode::INTERNAL_SERVER_ERROR),
 
@@ -107,6 +122,7 @@ impl Code {
             // invalid document
             MaxFieldsLimitExceeded => ErrCode::invalid("max_fields_limit_exceeded", StatusCode::BAD_REQUEST),
             MissingDocumentId => ErrCode::invalid("missing_document_id", StatusCode::BAD_REQUEST),
+            DuplicateDocumentId => ErrCode::invalid("duplicate_document_id", StatusCode::BAD_REQUEST),
 
             // error related to facets
             Facet => ErrCode::invalid("invalid_facet", StatusCode::BAD_REQUEST),
@@ -129,6 +145,20 @@ impl Code {
             // error related to dump
             DumpAlreadyInProgress => ErrCode::invalid("dump_already_in_progress", StatusCode::CONFLICT),
             DumpProcessFailed => ErrCode::internal("dump_process_failed", StatusCode::INTERNAL_SERVER_ERROR),
+
+            // error related to documents import
+            ImportFailed => ErrCode::invalid("import_failed", StatusCode::BAD_REQUEST),
+
+            // thrown when an API key exceeds its rate limit or its indexing quota
+            TooManyRequests => ErrCode::invalid("too_many_requests", StatusCode::TOO_MANY_REQUESTS),
+            // thrown when an origin restricted to the search routes calls another route
+            OriginNotAllowed => ErrCode::authentication("origin_not_allowed", StatusCode::FORBIDDEN),
+            // thrown when a write is sent while the server is shutting down
+            ShuttingDown => ErrCode::internal("shutting_down", StatusCode::SERVICE_UNAVAILABLE),
+            // thrown when calling a route of an experimental feature that is disabled
+            FeatureNotEnabled => ErrCode::invalid("feature_not_enabled", StatusCode::BAD_REQUEST),
+            // thrown when a write is sent while the disk or a map is almost full
+            InsufficientStorage => ErrCode::internal("insufficient_storage", StatusCode::INSUFFICIENT_STORAGE),
         }
     }
 
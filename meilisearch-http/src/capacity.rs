@@ -0,0 +1,241 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use serde::Serialize;
+
+/// The utilization ratios above which a warning is logged, the last one is logged as an error.
+const THRESHOLDS: [f64; 3] = [0.80, 0.90, 0.95];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapCapacity {
+    pub name: &'static str,
+    /// The size of the LMDB data file. LMDB never shrinks it, the pages freed by deletions are
+    /// reused, so it is the high watermark of the pages used.
+    pub used_bytes: u64,
+    pub map_size_bytes: u64,
+    pub utilization: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskCapacity {
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+    pub utilization: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capacity {
+    pub maps: Vec<MapCapacity>,
+    /// The capacity of the file system holding the database, unknown on some platforms.
+    pub disk: Option<DiskCapacity>,
+    /// Whether the writes are refused because the disk or a map is almost full.
+    pub safety_mode: bool,
+}
+
+/// Measures how full the LMDB maps and the disk are, and warns when they fill up.
+pub struct CapacityMonitor {
+    db_path: PathBuf,
+    main_map_size: u64,
+    update_map_size: u64,
+    /// The size up to which the maps are grown when the database is opened.
+    max_map_size: Option<u64>,
+    /// The number of thresholds crossed by each of the maps and by the disk when last checked.
+    levels: Mutex<Vec<(&'static str, usize)>>,
+    /// The free space, in bytes, below which the safety mode is enabled.
+    safety_min_free_space: Option<u64>,
+    /// Shared with the database, whose indexes refuse the updates while it is set.
+    safety_mode: Arc<AtomicBool>,
+}
+
+impl CapacityMonitor {
+    pub fn new(
+        db_path: impl AsRef<Path>,
+        main_map_size: usize,
+        update_map_size: usize,
+        max_map_size: Option<usize>,
+        safety_min_free_space: Option<u64>,
+        safety_mode: Arc<AtomicBool>,
+    ) -> CapacityMonitor {
+        CapacityMonitor {
+            db_path: db_path.as_ref().to_path_buf(),
+            main_map_size: main_map_size as u64,
+            update_map_size: update_map_size as u64,
+            max_map_size: max_map_size.map(|size| size as u64),
+            levels: Mutex::new(Vec::new()),
+            safety_min_free_space,
+            safety_mode,
+        }
+    }
+
+    /// Whether the writes must be refused, as of the last check.
+    pub fn safety_mode(&self) -> bool {
+        self.safety_mode.load(Ordering::SeqCst)
+    }
+
+    pub fn measure(&self) -> Capacity {
+        let maps = vec![
+            map_capacity("main", &self.db_path.join("main"), self.main_map_size),
+            map_capacity("update", &self.db_path.join("update"), self.update_map_size),
+        ];
+        Capacity { maps, disk: disk_capacity(&self.db_path), safety_mode: self.safety_mode() }
+    }
+
+    /// Measures the capacity and logs when a threshold is crossed, once per threshold. A warning
+    /// is logged again if the utilization drops below a threshold and crosses it another time.
+    /// The safety mode is enabled or disabled according to the free space.
+    pub fn check(&self) -> Capacity {
+        let mut capacity = self.measure();
+        capacity.safety_mode = self.update_safety_mode(&capacity);
+
+        let mut resources: Vec<_> = capacity.maps.iter()
+            .map(|map| {
+                let can_grow = self.max_map_size.map_or(false, |max| map.map_size_bytes.saturating_mul(2) <= max);
+                let advice = match map.name {
+                    _ if can_grow => "restart MeiliSearch to grow the map",
+                    "main" => "raise the map size with --max-mdb-size",
+                    _ => "raise the map size with --max-udb-size",
+                };
+                (map.name, map.utilization, advice)
+            })
+            .collect();
+        if let Some(disk) = &capacity.disk {
+            resources.push(("disk", disk.utilization, "free some disk space"));
+        }
+
+        let mut levels = self.levels.lock().unwrap();
+        for (name, utilization, advice) in resources {
+            let level = THRESHOLDS.iter().filter(|threshold| utilization >= **threshold).count();
+            let previous = match levels.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, previous)) => std::mem::replace(previous, level),
+                None => {
+                    levels.push((name, level));
+                    0
+                }
+            };
+
+            let percent = utilization * 100.0;
+            if level > previous && level == THRESHOLDS.len() {
+                error!("the {} storage is {:.1}% full, the writes will soon fail: {}", name, percent, advice);
+            } else if level > previous {
+                warn!("the {} storage is {:.1}% full: {}", name, percent, advice);
+            } else if level < previous && level == 0 {
+                info!("the {} storage is back to {:.1}% full", name, percent);
+            }
+        }
+
+        capacity
+    }
+
+    fn update_safety_mode(&self, capacity: &Capacity) -> bool {
+        let min_free_space = match self.safety_min_free_space {
+            Some(min_free_space) => min_free_space,
+            None => return false,
+        };
+
+        let mut free_spaces: Vec<_> = capacity.maps.iter()
+            .map(|map| (map.name, map.map_size_bytes.saturating_sub(map.used_bytes)))
+            .collect();
+        if let Some(disk) = &capacity.disk {
+            free_spaces.push(("disk", disk.available_bytes));
+        }
+
+        let full = free_spaces.iter().find(|(_, free_space)| *free_space < min_free_space);
+        let was_enabled = self.safety_mode.swap(full.is_some(), Ordering::SeqCst);
+        match full {
+            Some((name, free_space)) if !was_enabled => {
+                error!("only {} bytes are free in the {} storage, the writes are refused until space is freed", free_space, name);
+            }
+            None if was_enabled => info!("enough space is free again, the writes are accepted"),
+            _ => (),
+        }
+
+        full.is_some()
+    }
+}
+
+/// Checks the capacity every interval, for the safety mode to be enabled even when the disk
+/// is filled by another process.
+pub fn schedule_capacity_checks(monitor: Arc<CapacityMonitor>, interval: Duration) {
+    thread::spawn(move || loop {
+        monitor.check();
+        thread::sleep(interval);
+    });
+}
+
+fn map_capacity(name: &'static str, env_path: &Path, map_size: u64) -> MapCapacity {
+    let used_bytes = fs::metadata(env_path.join("data.mdb")).map(|m| m.len()).unwrap_or(0);
+    let utilization = if map_size == 0 { 0.0 } else { used_bytes as f64 / map_size as f64 };
+    MapCapacity { name, used_bytes, map_size_bytes: map_size, utilization }
+}
+
+#[cfg(unix)]
+fn disk_capacity(path: &Path) -> Option<DiskCapacity> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // safety: the path is a valid C string and statvfs only writes in the given struct
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+
+    let fragment_size = stat.f_frsize as u64;
+    let total_bytes = stat.f_blocks as u64 * fragment_size;
+    // the blocks reserved to root are not available to MeiliSearch
+    let available_bytes = stat.f_bavail as u64 * fragment_size;
+    let utilization = if total_bytes == 0 { 0.0 } else { 1.0 - available_bytes as f64 / total_bytes as f64 };
+
+    Some(DiskCapacity { available_bytes, total_bytes, utilization })
+}
+
+#[cfg(not(unix))]
+fn disk_capacity(_path: &Path) -> Option<DiskCapacity> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_utilization() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("main")).unwrap();
+        fs::write(dir.path().join("main/data.mdb"), vec![0; 900]).unwrap();
+
+        let monitor = CapacityMonitor::new(dir.path(), 1000, 1000, None, None, Arc::default());
+        let capacity = monitor.check();
+        assert_eq!(capacity.maps[0].used_bytes, 900);
+        assert!((capacity.maps[0].utilization - 0.9).abs() < f64::EPSILON);
+        // the update environment doesn't exist yet
+        assert_eq!(capacity.maps[1].used_bytes, 0);
+
+        let levels = monitor.levels.lock().unwrap();
+        assert!(levels.contains(&("main", 2)));
+        assert!(levels.contains(&("update", 0)));
+    }
+
+    #[test]
+    fn safety_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("main")).unwrap();
+        fs::write(dir.path().join("main/data.mdb"), vec![0; 900]).unwrap();
+
+        let monitor = CapacityMonitor::new(dir.path(), 1000, 1000, None, Some(200), Arc::default());
+        assert!(monitor.check().safety_mode);
+        assert!(monitor.safety_mode());
+
+        fs::write(dir.path().join("main/data.mdb"), vec![0; 700]).unwrap();
+        assert!(!monitor.check().safety_mode);
+        assert!(!monitor.safety_mode());
+    }
+}
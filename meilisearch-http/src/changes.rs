@@ -0,0 +1,103 @@
+use std::sync::Mutex;
+
+use futures::channel::mpsc;
+
+use crate::routes::task::Task;
+
+/// The number of changes buffered for a subscriber, a subscriber lagging further behind is
+/// disconnected so that a slow client can't make the server buffer the changes indefinitely.
+const SUBSCRIBER_CAPACITY: usize = 1024;
+
+/// Sends the updates applied to the indexes to the clients subscribed to their changes.
+#[derive(Default)]
+pub struct ChangeFeed {
+    subscribers: Mutex<Vec<(String, mpsc::Sender<Task>)>>,
+}
+
+impl ChangeFeed {
+    pub fn subscribe(&self, index_uid: &str) -> mpsc::Receiver<Task> {
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_CAPACITY);
+        self.subscribers.lock().unwrap().push((index_uid.to_string(), sender));
+        receiver
+    }
+
+    /// Sends the task to the subscribers of its index, the subscribers that are gone or
+    /// lagging are removed, the latter can resume from the last change they received.
+    pub fn publish(&self, task: &Task) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        for (index_uid, mut sender) in std::mem::take(&mut *subscribers) {
+            let keep = if index_uid == task.index_uid {
+                sender.try_send(task.clone()).is_ok()
+            } else {
+                !sender.is_closed()
+            };
+            if keep {
+                subscribers.push((index_uid, sender));
+            }
+        }
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use meilisearch_core::update::UpdateType;
+    use meilisearch_core::{ProcessedUpdateResult, UpdateStatus};
+
+    use super::*;
+
+    fn task(index_uid: &str, update_id: u64) -> Task {
+        let content = ProcessedUpdateResult {
+            update_id,
+            update_type: UpdateType::ClearAll,
+            error: None,
+            error_type: None,
+            error_code: None,
+            error_link: None,
+            duration: 0.0,
+            enqueued_at: Utc::now(),
+            processed_at: Utc::now(),
+            canceled_at: None,
+            document_errors: Vec::new(),
+        };
+        Task::new(index_uid, UpdateStatus::Processed { content })
+    }
+
+    #[test]
+    fn publish_to_the_subscribers_of_the_index() {
+        let feed = ChangeFeed::default();
+        let mut movies = feed.subscribe("movies");
+        let mut series = feed.subscribe("series");
+
+        feed.publish(&task("movies", 1));
+        assert_eq!(movies.try_next().unwrap().unwrap().status.update_id(), 1);
+        assert!(series.try_next().is_err());
+
+        // the subscribers that are gone are removed
+        drop(series);
+        feed.publish(&task("movies", 2));
+        assert_eq!(feed.subscribers(), 1);
+    }
+
+    #[test]
+    fn lagging_subscribers_are_disconnected() {
+        let feed = ChangeFeed::default();
+        let mut receiver = feed.subscribe("movies");
+
+        for update_id in 0..=SUBSCRIBER_CAPACITY as u64 + 1 {
+            feed.publish(&task("movies", update_id));
+        }
+        assert_eq!(feed.subscribers(), 0);
+
+        // the buffered changes can still be read before the end of the stream
+        let mut received = 0;
+        while let Ok(Some(_)) = receiver.try_next() {
+            received += 1;
+        }
+        assert!(received >= SUBSCRIBER_CAPACITY);
+    }
+}
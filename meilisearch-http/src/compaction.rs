@@ -0,0 +1,219 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+
+use crate::error::Error;
+use crate::Data;
+
+/// The directory of the database where the compacted copy waits to be installed.
+const COMPACTED_DIR: &str = "compacted";
+const MANIFEST_FILE: &str = "compaction.json";
+
+/// LMDB writes the id of the last transaction in one of the two meta pages, at the beginning of
+/// the data file, at every commit. They are within this prefix for all the page sizes.
+const FINGERPRINT_LEN: u64 = 128 * 1024;
+
+static COMPACTION_LOCK: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+static COMPACTION_INFO: Lazy<Mutex<Option<CompactionInfo>>> = Lazy::new(Mutex::default);
+
+/// The fingerprints of the environments when they were copied, the copies are only installed
+/// if the environments were not modified since.
+#[derive(Serialize, Deserialize)]
+struct CompactionManifest {
+    main: String,
+    update: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionReport {
+    pub main_size: u64,
+    pub compacted_main_size: u64,
+    pub update_size: u64,
+    pub compacted_update_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStatus {
+    Processing,
+    Done,
+    Failed,
+}
+
+/// The state of the last compaction started since MeiliSearch was launched.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionInfo {
+    pub status: CompactionStatus,
+    /// The sizes before and after the compaction once it is done.
+    #[serde(flatten)]
+    pub report: Option<CompactionReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CompactionInfo {
+    pub fn get_current() -> Option<CompactionInfo> {
+        COMPACTION_INFO.lock().unwrap().clone()
+    }
+}
+
+/// Starts a compaction in the background, see [`compact`]. Its progress and its report
+/// are given by [`CompactionInfo::get_current`].
+pub fn start_compaction(data: &Data) -> Result<CompactionInfo, Error> {
+    let info = CompactionInfo { status: CompactionStatus::Processing, report: None, error: None };
+    {
+        let mut current = COMPACTION_INFO.lock().unwrap();
+        if current.as_ref().map_or(false, |info| info.status == CompactionStatus::Processing) {
+            return Err(Error::bad_request("a compaction is already in progress"));
+        }
+        *current = Some(info.clone());
+    }
+
+    let data = data.clone();
+    thread::spawn(move || {
+        let info = match compact(&data) {
+            Ok(report) => CompactionInfo { status: CompactionStatus::Done, report: Some(report), error: None },
+            Err(e) => {
+                error!("The compaction of the database failed; {}", e);
+                CompactionInfo { status: CompactionStatus::Failed, report: None, error: Some(e.to_string()) }
+            }
+        };
+        *COMPACTION_INFO.lock().unwrap() = Some(info);
+    });
+
+    Ok(info)
+}
+
+/// Writes a compacted copy of the database while it keeps serving requests, the copy replaces the
+/// database the next time MeiliSearch starts if no update was applied in the meantime.
+pub fn compact(data: &Data) -> Result<CompactionReport, Error> {
+    let _guard = COMPACTION_LOCK.try_lock().map_err(|_| Error::bad_request("a compaction is already in progress"))?;
+
+    let db_path = Path::new(&data.db_path);
+    // the fingerprints are computed before the copy starts, any commit made
+    // after, even during the copy, prevents the copy from being installed
+    let manifest = CompactionManifest {
+        main: fingerprint(&db_path.join("main"))?,
+        update: fingerprint(&db_path.join("update"))?,
+    };
+
+    let tmp_dir = TempDir::new_in(db_path)?;
+    data.db.copy_and_compact_to_path(tmp_dir.path())?;
+    fs::write(tmp_dir.path().join(MANIFEST_FILE), serde_json::to_vec(&manifest)?)?;
+
+    let report = CompactionReport {
+        main_size: data_file_size(&db_path.join("main"))?,
+        compacted_main_size: data_file_size(&tmp_dir.path().join("main"))?,
+        update_size: data_file_size(&db_path.join("update"))?,
+        compacted_update_size: data_file_size(&tmp_dir.path().join("update"))?,
+    };
+
+    // a previous copy is outdated by this one
+    let compacted_path = db_path.join(COMPACTED_DIR);
+    if compacted_path.exists() {
+        fs::remove_dir_all(&compacted_path)?;
+    }
+    fs::rename(tmp_dir.into_path(), &compacted_path)?;
+
+    info!(
+        "The database has been compacted from {} to {} bytes, the copy will be installed at the next start",
+        report.main_size + report.update_size,
+        report.compacted_main_size + report.compacted_update_size,
+    );
+
+    Ok(report)
+}
+
+/// Replaces the environments of the database by their compacted copy, if there is one and they were
+/// not modified since it was written. Must be called before the database is opened.
+pub fn install_compacted(db_path: &Path) -> Result<(), Error> {
+    let compacted_path = db_path.join(COMPACTED_DIR);
+    let manifest = match fs::read(compacted_path.join(MANIFEST_FILE)) {
+        Ok(bytes) => serde_json::from_slice::<CompactionManifest>(&bytes)?,
+        // the copy is incomplete, the compaction was interrupted
+        Err(_) if compacted_path.exists() => return Ok(fs::remove_dir_all(&compacted_path)?),
+        Err(_) => return Ok(()),
+    };
+
+    // the environments are installed one after the other, if the first one is installed but not the
+    // second one, the second one is discarded at the next start, which is harmless as the copies
+    // contain the same entries as the originals
+    for (name, expected) in &[("main", &manifest.main), ("update", &manifest.update)] {
+        let env_path = db_path.join(name);
+        if fingerprint(&env_path)? == **expected {
+            fs::rename(compacted_path.join(name).join("data.mdb"), env_path.join("data.mdb"))?;
+            info!("The compacted copy of the {} environment has been installed", name);
+        } else {
+            warn!("The {} environment was modified after it was compacted, its compacted copy is discarded", name);
+        }
+    }
+
+    fs::remove_dir_all(&compacted_path)?;
+    Ok(())
+}
+
+/// The digest of the beginning of the data file of the environment, it changes at every commit.
+fn fingerprint(env_path: &Path) -> Result<String, Error> {
+    let mut prefix = Vec::new();
+    File::open(env_path.join("data.mdb"))?.take(FINGERPRINT_LEN).read_to_end(&mut prefix)?;
+    Ok(format!("{:x}", Sha256::digest(&prefix)))
+}
+
+fn data_file_size(env_path: &Path) -> Result<u64, Error> {
+    Ok(fs::metadata(env_path.join("data.mdb"))?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_env(dir: &Path, name: &str, content: &[u8]) {
+        fs::create_dir_all(dir.join(name)).unwrap();
+        fs::write(dir.join(name).join("data.mdb"), content).unwrap();
+    }
+
+    #[test]
+    fn install_unmodified_environments() {
+        let dir = tempfile::tempdir().unwrap();
+        write_env(dir.path(), "main", b"main");
+        write_env(dir.path(), "update", b"update");
+
+        let compacted = dir.path().join(COMPACTED_DIR);
+        write_env(&compacted, "main", b"compacted main");
+        write_env(&compacted, "update", b"compacted update");
+        let manifest = CompactionManifest {
+            main: fingerprint(&dir.path().join("main")).unwrap(),
+            update: fingerprint(&dir.path().join("update")).unwrap(),
+        };
+        fs::write(compacted.join(MANIFEST_FILE), serde_json::to_vec(&manifest).unwrap()).unwrap();
+
+        // the update environment is modified after the compaction
+        write_env(dir.path(), "update", b"update modified");
+
+        install_compacted(dir.path()).unwrap();
+        assert_eq!(fs::read(dir.path().join("main/data.mdb")).unwrap(), b"compacted main");
+        assert_eq!(fs::read(dir.path().join("update/data.mdb")).unwrap(), b"update modified");
+        assert!(!compacted.exists());
+    }
+
+    #[test]
+    fn discard_interrupted_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        write_env(dir.path(), "main", b"main");
+        write_env(&dir.path().join(COMPACTED_DIR), "main", b"compacted main");
+
+        install_compacted(dir.path()).unwrap();
+        assert_eq!(fs::read(dir.path().join("main/data.mdb")).unwrap(), b"main");
+        assert!(!dir.path().join(COMPACTED_DIR).exists());
+    }
+}
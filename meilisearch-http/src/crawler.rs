@@ -0,0 +1,421 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::Read;
+use std::thread;
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use log::{info, warn};
+use regex::Regex;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::helpers::{html, import, xml};
+use crate::routes::document::{enqueue_documents_deletion, ensure_primary_key};
+use crate::routes::index::open_or_create_index;
+use crate::Data;
+
+/// The hash of the content of the pages indexed, by document id.
+type Pages = BTreeMap<String, String>;
+
+const USER_AGENT: &str = concat!("MeiliSearch-Crawler/", env!("CARGO_PKG_VERSION"));
+const PRIMARY_KEY: &str = "id";
+pub const DEFAULT_MAX_PAGES: usize = 1000;
+/// The delay between two requests, not to overload the website.
+const CRAWL_DELAY: Duration = Duration::from_millis(100);
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// The pages and sitemaps larger than this are truncated.
+const MAX_RESPONSE_SIZE: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CrawlReport {
+    pub pages: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+/// Crawls a website and indexes its pages, each page is a document with its url, title, description,
+/// lang, headings and content. The urls are sitemaps, sitemap indexes or pages from which the links
+/// to the pages of the same websites are followed, the pages listed by sitemaps are not followed.
+///
+/// Only the pages that changed since the previous crawl are sent to the index, and the pages
+/// that disappeared are deleted once the website was entirely crawled without errors.
+/// The rules of the robots.txt files and of the robots meta tags are respected.
+pub fn crawl(data: &Data, index_uid: &str, urls: &[String], max_pages: usize) -> Result<CrawlReport, Error> {
+    let source = format!("web:{}", index_uid);
+    let previous: Pages = data.db.main_read(|reader| data.db.ingestion_offsets(reader, &source))?.unwrap_or_default();
+
+    let origins: HashSet<_> = urls.iter().filter_map(|url| origin(url)).map(str::to_string).collect();
+    let mut robots: HashMap<String, Robots> = HashMap::new();
+    let mut queue: VecDeque<(String, bool)> = urls.iter().map(|url| (strip_fragment(url).to_string(), true)).collect();
+    let mut seen = HashSet::new();
+    let mut pages = Pages::new();
+    let mut documents = Vec::new();
+    let mut errors = 0;
+
+    while let Some((url, follow)) = queue.pop_front() {
+        if pages.len() >= max_pages {
+            break;
+        }
+        let url_origin = match origin(&url) {
+            Some(url_origin) => url_origin.to_string(),
+            None => continue,
+        };
+        if !seen.insert(url.clone()) {
+            continue;
+        }
+
+        let robots = robots.entry(url_origin.clone()).or_insert_with(|| Robots::fetch(&url_origin));
+        if !robots.allows(&url[url_origin.len()..]) {
+            continue;
+        }
+
+        thread::sleep(CRAWL_DELAY);
+        let (content_type, body) = match fetch(&url) {
+            Ok(Some(response)) => response,
+            // the pages that no longer exist are deleted from the index
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("The page {} could not be crawled: {}", url, e);
+                errors += 1;
+                continue;
+            }
+        };
+
+        // the sitemaps can only list the pages of the websites being crawled
+        if is_sitemap(&body) {
+            let locations = xml::elements(&body, "loc").into_iter().map(xml::unescape);
+            let locations = locations.map(|location| strip_fragment(&location).to_string());
+            queue.extend(locations.filter(|location| is_crawled(&origins, location)).map(|location| (location, false)));
+            continue;
+        }
+        if !content_type.starts_with("text/html") {
+            continue;
+        }
+
+        let page = html::extract(&body);
+        if follow && !page.nofollow {
+            let links = page.links.iter().filter_map(|link| resolve(&url, link));
+            queue.extend(links.filter(|link| is_crawled(&origins, link)).map(|link| (link, true)));
+        }
+        if page.noindex {
+            continue;
+        }
+
+        // the pages reachable from several urls are indexed once, under their canonical url
+        let url = match page.canonical.as_ref().and_then(|canonical| resolve(&url, canonical)) {
+            Some(canonical) if origin(&canonical) == Some(url_origin.as_str()) => canonical,
+            _ => url,
+        };
+        seen.insert(url.clone());
+
+        let id = document_id(&url);
+        let document = json!({
+            "id": id,
+            "url": url,
+            "title": page.title,
+            "description": page.description,
+            "lang": page.lang,
+            "headings": page.headings,
+            "content": page.content,
+        });
+        let hash = hex(&Sha256::digest(document.to_string().as_bytes()));
+        if previous.get(&id) != Some(&hash) {
+            documents.push(document);
+        }
+        pages.insert(id, hash);
+    }
+
+    let complete = queue.is_empty() && errors == 0;
+    let index = open_or_create_index(data, index_uid)?;
+    let mut report = CrawlReport { pages: pages.len(), updated: documents.len(), deleted: 0 };
+
+    if !documents.is_empty() {
+        let documents: Vec<IndexMap<String, Value>> = documents.into_iter().filter_map(|document| serde_json::from_value(document).ok()).collect();
+        let primary_key = ensure_primary_key(data, &index, Some(&PRIMARY_KEY.to_string()), documents.first())
+            .map_err(|e| Error::internal(format!("the primary key of the index {} can't be set: {}", index_uid, e)))?;
+        let mut addition = index.documents_addition();
+        addition.set_primary_key(primary_key);
+        addition.extend(documents);
+        data.db.update_write(|writer| addition.finalize(writer))?;
+    }
+
+    // an incomplete crawl can't tell which pages disappeared, the pages not crawled are kept
+    let pages = if complete {
+        let deleted: Vec<_> = previous.keys().filter(|id| !pages.contains_key(*id)).map(|id| Value::String(id.clone())).collect();
+        if !deleted.is_empty() {
+            report.deleted = deleted.len();
+            enqueue_documents_deletion(data, &index, None, deleted)
+                .map_err(|e| Error::internal(format!("the deleted pages can't be removed from the index {}: {}", index_uid, e)))?;
+        }
+        pages
+    } else {
+        let mut merged = previous;
+        merged.extend(pages);
+        merged
+    };
+    data.db.main_write(|writer| data.db.put_ingestion_offsets(writer, &source, &pages))?;
+
+    info!("{} pages crawled for the index {}, {} updated and {} deleted", report.pages, index_uid, report.updated, report.deleted);
+    Ok(report)
+}
+
+/// Fetches the url, returns its content type and body, or `None` when it doesn't exist.
+/// The url and the redirections must target public hosts, as for the imports.
+fn fetch(url: &str) -> Result<Option<(String, String)>, Error> {
+    let response = import::get_public(url.to_string(), None, |request| {
+        request
+            .timeout_connect(TIMEOUT.as_millis() as u64)
+            .timeout_read(TIMEOUT.as_millis() as u64)
+            .set("User-Agent", USER_AGENT);
+    })
+    .map_err(|e| Error::internal(format!("the website is unreachable: {}", e)))?;
+
+    match response.status() {
+        404 | 410 => return Ok(None),
+        status if !response.ok() => return Err(Error::internal(format!("the website responded with status {}", status))),
+        _ => (),
+    }
+
+    let content_type = response.content_type().to_lowercase();
+    let mut body = Vec::new();
+    response.into_reader().take(MAX_RESPONSE_SIZE).read_to_end(&mut body)?;
+    Ok(Some((content_type, String::from_utf8_lossy(&body).into_owned())))
+}
+
+/// Whether the url belongs to one of the websites being crawled, the origins of the urls it started from.
+fn is_crawled(origins: &HashSet<String>, url: &str) -> bool {
+    origin(url).map_or(false, |origin| origins.contains(origin))
+}
+
+fn is_sitemap(body: &str) -> bool {
+    let start = body.get(..1024).unwrap_or(body);
+    start.contains("<urlset") || start.contains("<sitemapindex")
+}
+
+/// The document ids can't contain the characters of the urls.
+fn document_id(url: &str) -> String {
+    hex(&Sha256::digest(url.as_bytes())[..16])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The scheme, host and port of an http or https url.
+pub(crate) fn origin(url: &str) -> Option<&str> {
+    let scheme_end = url.find("://")?;
+    if !matches!(&url[..scheme_end], "http" | "https") {
+        return None;
+    }
+    let host_end = url[scheme_end + 3..].find(|c| c == '/' || c == '?' || c == '#').map_or(url.len(), |pos| pos + scheme_end + 3);
+    Some(&url[..host_end]).filter(|origin| origin.len() > scheme_end + 3)
+}
+
+fn strip_fragment(url: &str) -> &str {
+    url.split('#').next().unwrap_or(url)
+}
+
+/// Resolves a link of a page into an absolute url without fragment, the links
+/// to other schemes than http and https are ignored.
+pub(crate) fn resolve(base: &str, link: &str) -> Option<String> {
+    let link = strip_fragment(link.trim());
+    if link.is_empty() {
+        return Some(base.to_string());
+    }
+    if link.starts_with("http://") || link.starts_with("https://") {
+        return origin(link).map(|_| link.to_string());
+    }
+    let has_scheme = link.find(|c| c == ':' || c == '/' || c == '?').map_or(false, |pos| {
+        pos > 0 && link[pos..].starts_with(':') && link[..pos].chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    if has_scheme {
+        return None;
+    }
+
+    let base_origin = origin(base)?;
+    let scheme = &base[..base.find("://")?];
+    if link.starts_with("//") {
+        return Some(format!("{}:{}", scheme, link));
+    }
+
+    let base_path = base[base_origin.len()..].split('?').next().unwrap_or("");
+    let path = if link.starts_with('/') {
+        link.to_string()
+    } else if link.starts_with('?') {
+        format!("{}{}", if base_path.is_empty() { "/" } else { base_path }, link)
+    } else {
+        let directory = base_path.rfind('/').map_or("/", |pos| &base_path[..pos + 1]);
+        format!("{}{}", directory, link)
+    };
+
+    Some(format!("{}{}", base_origin, normalize_path(&path)))
+}
+
+/// Removes the `.` and `..` segments of the path, its query is left untouched.
+fn normalize_path(path: &str) -> String {
+    let (path, query) = match path.find('?') {
+        Some(pos) => (&path[..pos], &path[pos..]),
+        None => (path, ""),
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    let mut parts = path.split('/').skip(1).peekable();
+    while let Some(segment) = parts.next() {
+        match segment {
+            "." => (),
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+        // a path ending with a dot segment designates a directory
+        if parts.peek().is_none() && (segment == "." || segment == "..") {
+            segments.push("");
+        }
+    }
+
+    format!("/{}{}", segments.join("/"), query)
+}
+
+/// The rules of a robots.txt file that apply to the crawler, either those of
+/// its own group or those of the group of all the crawlers.
+#[derive(Default)]
+struct Robots {
+    /// Whether the rule allows or disallows the paths it matches, the longest matching rule applies.
+    rules: Vec<(bool, usize, Regex)>,
+}
+
+impl Robots {
+    /// The websites without robots.txt file can be entirely crawled.
+    fn fetch(origin: &str) -> Robots {
+        match fetch(&format!("{}/robots.txt", origin)) {
+            Ok(Some((_, body))) => Robots::parse(&body),
+            _ => Robots::default(),
+        }
+    }
+
+    fn parse(robots: &str) -> Robots {
+        let mut groups: Vec<(Vec<String>, Vec<(bool, String)>)> = Vec::new();
+        let mut in_agents = false;
+
+        for line in robots.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let pos = match line.find(':') {
+                Some(pos) => pos,
+                None => continue,
+            };
+            let (field, value) = (line[..pos].trim().to_lowercase(), line[pos + 1..].trim());
+            match field.as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push((Vec::new(), Vec::new()));
+                        in_agents = true;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.0.push(value.to_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    in_agents = false;
+                    // an empty disallow rule allows everything
+                    if let (Some(group), false) = (groups.last_mut(), value.is_empty()) {
+                        group.1.push((field == "allow", value.to_string()));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let group = groups.iter().find(|(agents, _)| agents.iter().any(|agent| agent.contains("meilisearch")))
+            .or_else(|| groups.iter().find(|(agents, _)| agents.iter().any(|agent| agent == "*")));
+
+        let rules = group.map_or(Vec::new(), |(_, rules)| {
+            rules.iter().filter_map(|(allow, pattern)| Some((*allow, pattern.len(), rule_regex(pattern)?))).collect()
+        });
+        Robots { rules }
+    }
+
+    fn allows(&self, path: &str) -> bool {
+        let path = if path.is_empty() { "/" } else { path };
+        self.rules
+            .iter()
+            .filter(|(_, _, regex)| regex.is_match(path))
+            .max_by_key(|(allow, len, _)| (*len, *allow))
+            .map_or(true, |(allow, _, _)| *allow)
+    }
+}
+
+/// The rules are prefixes of the paths, in which `*` matches any sequence
+/// of characters and a final `$` matches the end of the path.
+fn rule_regex(pattern: &str) -> Option<Regex> {
+    let anchored = pattern.ends_with('$');
+    let pattern = pattern.trim_end_matches('$');
+    let parts: Vec<_> = pattern.split('*').map(regex::escape).collect();
+    Regex::new(&format!("^{}{}", parts.join(".*"), if anchored { "$" } else { "" })).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_links() {
+        let base = "https://example.com/movies/carol?ref=home";
+        assert_eq!(resolve(base, "/series").unwrap(), "https://example.com/series");
+        assert_eq!(resolve(base, "wonder-woman#cast").unwrap(), "https://example.com/movies/wonder-woman");
+        assert_eq!(resolve(base, "../people/./cate").unwrap(), "https://example.com/people/cate");
+        assert_eq!(resolve(base, "..").unwrap(), "https://example.com/");
+        assert_eq!(resolve(base, "?page=2").unwrap(), "https://example.com/movies/carol?page=2");
+        assert_eq!(resolve(base, "//cdn.example.com/a").unwrap(), "https://cdn.example.com/a");
+        assert_eq!(resolve(base, "http://other.com").unwrap(), "http://other.com");
+        assert_eq!(resolve("https://example.com", "movies").unwrap(), "https://example.com/movies");
+        assert_eq!(resolve(base, "mailto:contact@example.com"), None);
+        assert_eq!(resolve(base, "javascript:void(0)"), None);
+    }
+
+    #[test]
+    fn origins() {
+        assert_eq!(origin("https://example.com:8080/movies?a=1"), Some("https://example.com:8080"));
+        assert_eq!(origin("http://example.com"), Some("http://example.com"));
+        assert_eq!(origin("ftp://example.com/movies"), None);
+        assert_eq!(origin("https:///movies"), None);
+
+        let origins = vec!["https://example.com".to_string()].into_iter().collect();
+        assert!(is_crawled(&origins, "https://example.com/movies"));
+        assert!(!is_crawled(&origins, "http://example.com/movies"));
+        assert!(!is_crawled(&origins, "http://169.254.169.254/latest/meta-data/"));
+    }
+
+    #[test]
+    fn robots_rules() {
+        let robots = Robots::parse(
+            "User-agent: Googlebot\n\
+             Disallow: /\n\
+             \n\
+             User-agent: *\n\
+             User-agent: Bingbot\n\
+             Disallow: /private # the private pages\n\
+             Allow: /private/public\n\
+             Disallow: /*.pdf$\n\
+             Disallow:\n",
+        );
+        assert!(robots.allows("/movies"));
+        assert!(!robots.allows("/private/movies"));
+        assert!(robots.allows("/private/public/movies"));
+        assert!(!robots.allows("/files/carol.pdf"));
+        assert!(robots.allows("/files/carol.pdf?page=2"));
+
+        let robots = Robots::parse("User-agent: *\nDisallow: /\n\nUser-agent: MeiliSearch-Crawler\nDisallow: /private\n");
+        assert!(robots.allows("/movies"));
+        assert!(!robots.allows("/private"));
+    }
+
+    #[test]
+    fn detect_sitemaps() {
+        assert!(is_sitemap(r#"<?xml version="1.0"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#));
+        assert!(is_sitemap("<sitemapindex>"));
+        assert!(!is_sitemap("<!DOCTYPE html><html>"));
+        assert_eq!(document_id("https://example.com/").len(), 32);
+    }
+}
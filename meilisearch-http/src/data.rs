@@ -1,13 +1,38 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::ops::Deref;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
+use log::{error, warn};
 use meilisearch_core::{Database, DatabaseOptions};
 use sha2::Digest;
 
+use crate::capacity::CapacityMonitor;
+use crate::changes::ChangeFeed;
+use crate::compaction;
+use crate::features::FeatureStore;
+use crate::filter_cache::FilterCache;
+use crate::firehose::Firehose;
+use crate::helpers::access_log::AccessLogWriter;
+use crate::helpers::payload_limit::PayloadLimits;
+use crate::imports;
 use crate::index_update_callback;
+use crate::journal::UpdateJournal;
+use crate::keys::KeyStore;
 use crate::option::Opt;
+use crate::rate_limit::RateLimiter;
+use crate::scheduler::{Job, Scheduler};
+use crate::secrets::resolve_secret;
+use crate::storage::{storage_from_url, Storage};
+use crate::search_analytics::SearchAnalytics;
+use crate::search_limiter::SearchLimiter;
+use crate::slow_query::SlowQueryLog;
+use crate::task_metrics::TaskMetrics;
+use crate::telemetry::Tracer;
+use crate::webhook::WebhookNotifier;
 
 #[derive(Clone)]
 pub struct Data {
@@ -28,9 +53,50 @@ pub struct DataInner {
     pub db_path: String,
     pub dumps_folder: PathBuf,
     pub dump_batch_size: usize,
-    pub api_keys: ApiKeys,
+    pub api_keys: Arc<RwLock<ApiKeys>>,
     pub server_pid: u32,
-    pub http_payload_size_limit: usize,
+    /// The maximum sizes of the request payloads, by class of routes.
+    pub payload_limits: PayloadLimits,
+    pub webhook_notifier: Option<WebhookNotifier>,
+    pub scheduler: Arc<Scheduler>,
+    pub keys: Arc<KeyStore>,
+    /// The uids of the scoped API keys associated to the names of the client certificates.
+    pub client_certificate_keys: HashMap<String, String>,
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Where the snapshots are copied once created, besides the snapshot directory.
+    pub snapshot_storage: Option<Arc<dyn Storage>>,
+    /// Where the dumps are copied once created, besides the dumps folder.
+    pub dump_storage: Option<Arc<dyn Storage>>,
+    /// Records the updates applied to the indexes, to replay them on top of a snapshot.
+    pub journal: Option<Arc<UpdateJournal>>,
+    /// Exports the traces of the requests and of the updates to an OpenTelemetry collector.
+    pub tracer: Option<Tracer>,
+    /// Set when the server is shutting down, the writes are refused from then on.
+    pub shutting_down: Arc<AtomicBool>,
+    /// The options the server runs with, the reloadable ones are updated on reload.
+    pub config: Arc<Mutex<Opt>>,
+    /// The interval between two scheduled snapshots, in seconds.
+    pub snapshot_interval_sec: Arc<AtomicU64>,
+    /// Logs the searches slower than the configured threshold.
+    pub slow_queries: Option<Arc<SlowQueryLog>>,
+    /// Aggregates the searches made on each index, unless disabled.
+    pub search_analytics: Option<Arc<SearchAnalytics>>,
+    /// Bounds the number of searches executed at once, if enabled.
+    pub search_limiter: Option<Arc<SearchLimiter>>,
+    /// The documents matching the filters used frequently, unless disabled.
+    pub filter_cache: Option<Arc<FilterCache>>,
+    /// Measures how full the LMDB maps and the disk are.
+    pub capacity: Arc<CapacityMonitor>,
+    /// Where the access logs are written, if enabled.
+    pub access_log: Option<Arc<AccessLogWriter>>,
+    /// Sends the applied updates to the clients following the changes of the indexes.
+    pub change_feed: Arc<ChangeFeed>,
+    /// Forwards the changes made to the documents to the targets configured on the indexes.
+    pub firehose: Arc<Firehose>,
+    /// The experimental features enabled on this instance.
+    pub features: Arc<FeatureStore>,
+    /// Counts the processed tasks for the statistics and the metrics.
+    pub task_metrics: Arc<TaskMetrics>,
 }
 
 #[derive(Clone)]
@@ -57,24 +123,118 @@ impl ApiKeys {
     }
 }
 
+fn primary_key(db: &Database, index_uid: &str) -> Option<String> {
+    let index = db.open_index(index_uid)?;
+    let reader = db.main_read_txn().ok()?;
+    let schema = index.main.schema(&reader).ok()??;
+    schema.primary_key().map(ToString::to_string)
+}
+
 impl Data {
     pub fn new(opt: Opt) -> Result<Data, Box<dyn Error>> {
         let db_path = opt.db_path.clone();
         let dumps_folder = opt.dumps_folder.clone();
         let dump_batch_size = opt.dump_batch_size;
         let server_pid = std::process::id();
+        let config = Arc::new(Mutex::new(opt.clone()));
 
         let db_opt = DatabaseOptions {
             main_map_size: opt.max_mdb_size,
             update_map_size: opt.max_udb_size,
+            max_map_size: opt.max_map_size,
+            trash_retention: opt.index_trash_retention_sec.map(|secs| {
+                chrono::Duration::from_std(std::time::Duration::from_secs(secs)).unwrap_or_else(|_| chrono::Duration::max_value())
+            }),
         };
 
-        let http_payload_size_limit = opt.http_payload_size_limit;
+        let payload_limits = PayloadLimits::from_opt(&opt);
 
+        compaction::install_compacted(Path::new(&opt.db_path))?;
         let db = Arc::new(Database::open_or_create(opt.db_path, db_opt)?);
 
+        let master_key = opt.master_key.as_deref().map(resolve_secret).transpose()?;
+        let webhook_secret = opt.webhook_secret.as_deref().map(resolve_secret).transpose()?;
+
+        // the webhook secret signs the payloads of the webhooks and of the firehose targets, the
+        // master key is never used instead, their receivers could authenticate as the master key
+        if webhook_secret.is_none() && !opt.webhook_urls.is_empty() {
+            warn!("No webhook secret was provided, the webhook payloads are sent unsigned");
+        }
+
+        let webhook_notifier = if opt.webhook_urls.is_empty() {
+            None
+        } else {
+            Some(WebhookNotifier::spawn(opt.webhook_urls, webhook_secret.clone()))
+        };
+
+        let keys = KeyStore::load(&db)?;
+        let features = Arc::new(FeatureStore::load(&db)?);
+
+        let dead_letter_path = match opt.firehose_dead_letter_path {
+            Some(path) => path,
+            None => Path::new(&db_path).with_file_name("firehose-dead-letters.jsonl"),
+        };
+        let firehose = Arc::new(Firehose::spawn(&db, webhook_secret, dead_letter_path)?);
+
+        let scheduler = Scheduler::new(opt.snapshot_path.clone(), opt.snapshot_retention, opt.snapshot_incremental);
+        for schedule in &opt.schedules {
+            let (job, cron) = Job::parse_with_schedule(schedule)?;
+            scheduler.add_configured(job, cron)?;
+        }
+
+        let snapshot_storage = opt.snapshot_storage.as_deref().map(storage_from_url).transpose()?.map(Arc::from);
+        let dump_storage = opt.dump_storage.as_deref().map(storage_from_url).transpose()?.map(Arc::from);
+
+        let journal = opt.update_journal_path.as_deref().map(UpdateJournal::open).transpose()?.map(Arc::new);
+
+        let tracer = opt.otlp_endpoint.as_deref().map(|endpoint| Tracer::spawn(endpoint, opt.otlp_service_name.clone()));
+
+        let slow_queries = opt.slow_query_threshold_ms.map(|threshold| {
+            Arc::new(SlowQueryLog::new(Duration::from_millis(threshold), opt.slow_query_log_size))
+        });
+
+        let snapshot_interval_sec = Arc::new(AtomicU64::new(opt.snapshot_interval_sec.unwrap_or(86400)));
+
+        let search_analytics = if opt.no_search_analytics { None } else { Some(Arc::new(SearchAnalytics::default())) };
+
+        let search_limiter = opt.max_concurrent_searches.map(|max| {
+            Arc::new(SearchLimiter::new(max, Duration::from_millis(opt.search_queue_timeout_ms)))
+        });
+
+        let filter_cache = opt.filter_cache_size.map(|size| Arc::new(FilterCache::new(size)));
+
+        let (main_map_size, update_map_size) = db.map_sizes();
+        // the indexes refuse the updates in safety mode, whether they come from the routes or not
+        let capacity = Arc::new(CapacityMonitor::new(
+            &db_path,
+            main_map_size,
+            update_map_size,
+            opt.max_map_size,
+            opt.safety_mode_min_free_space,
+            db.storage_full(),
+        ));
+
+        let access_log = match &opt.access_log_path {
+            Some(path) => {
+                let writer = AccessLogWriter::open(path, opt.access_log_format, opt.access_log_max_size, opt.access_log_max_files)?;
+                Some(Arc::new(writer))
+            }
+            None => None,
+        };
+
+        let mut client_certificate_keys = HashMap::new();
+        for mapping in &opt.ssl_client_keys {
+            match mapping.find('=') {
+                Some(pos) => {
+                    let (name, key_uid) = (mapping[..pos].trim(), mapping[pos + 1..].trim());
+                    client_certificate_keys.insert(name.to_string(), key_uid.to_string());
+                }
+                None => return Err(format!("the client key {:?} must be of the form name=keyUid", mapping).into()),
+            }
+        }
+
         let mut api_keys = ApiKeys {
-            master: opt.master_key,
+            master: master_key,
             private: None,
             public: None,
         };
@@ -86,9 +246,31 @@ impl Data {
             db_path,
             dumps_folder,
             dump_batch_size,
-            api_keys,
+            api_keys: Arc::new(RwLock::new(api_keys)),
             server_pid,
-            http_payload_size_limit,
+            payload_limits,
+            webhook_notifier,
+            scheduler: Arc::new(scheduler),
+            keys: Arc::new(keys),
+            client_certificate_keys,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            snapshot_storage,
+            dump_storage,
+            journal,
+            tracer,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            config,
+            snapshot_interval_sec,
+            slow_queries,
+            search_analytics,
+            search_limiter,
+            filter_cache,
+            capacity,
+            access_log,
+            change_feed: Arc::new(ChangeFeed::default()),
+            firehose,
+            features,
+            task_metrics: Arc::new(TaskMetrics::default()),
         };
 
         let data = Data {
@@ -100,6 +282,30 @@ impl Data {
             index_update_callback(&index_uid, &callback_context, status);
         }));
 
+        db.set_import_callback(imports::importer(data.clone()));
+
+        // the firehose targets can be configured at any time, the core asks which indexes
+        // need their applied updates before copying them
+        let journal = data.journal.clone();
+        let firehose = data.firehose.clone();
+        let filter_firehose = data.firehose.clone();
+        let journaled = journal.is_some();
+        let journal_db = db.clone();
+        db.set_journal_callback(
+            Box::new(move |index_uid| journaled || filter_firehose.has_targets(index_uid)),
+            Box::new(move |index_uid, update, status| {
+                let primary_key = primary_key(&journal_db, index_uid);
+                if let Some(journal) = &journal {
+                    if let Err(e) = journal.append(index_uid, primary_key.as_deref(), update, status.processed_at) {
+                        error!("Cannot journal the update {} of the index {}; {}", status.update_id, index_uid, e);
+                    }
+                }
+                if firehose.has_targets(index_uid) {
+                    firehose.notify(index_uid, primary_key, update, status);
+                }
+            }),
+        );
+
         Ok(data)
     }
 }
@@ -1,11 +1,12 @@
-use std::fs::{create_dir_all, File};
+use std::fs::{self, create_dir_all, File};
 use std::io::prelude::*;
+use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::thread;
 
 use actix_web::web;
-use chrono::offset::Utc;
+use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
 use log::{error, info};
 use meilisearch_core::{MainWriter, MainReader, UpdateReader};
@@ -18,6 +19,7 @@ use tempfile::TempDir;
 use crate::Data;
 use crate::error::Error;
 use crate::helpers::compression;
+use crate::keys::ApiKey;
 use crate::routes::index;
 use crate::routes::index::IndexResponse;
 
@@ -92,10 +94,25 @@ fn settings_to_path(settings: &Settings, folder_path: &Path) -> Result<(), Error
     Ok(())
 }
 
+/// Extract the API keys from `keys.json` file present at provided `folder_path`,
+/// the dumps created before the keys were exported don't contain this file.
+fn keys_from_path(folder_path: &Path) -> Result<Vec<ApiKey>, Error> {
+    let path = folder_path.join("keys.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let file = File::open(path)?;
+    let reader = std::io::BufReader::new(file);
+    let keys = serde_json::from_reader(reader)?;
+
+    Ok(keys)
+}
+
 /// Import settings and documents of a dump with version `DumpVersion::V1` in specified index.
 fn import_index_v1(
     data: &Data,
-    dumps_folder: &Path,
+    index_path: &Path,
     index_uid: &str,
     document_batch_size: usize,
     write_txn: &mut MainWriter,
@@ -107,9 +124,6 @@ fn import_index_v1(
         .open_index(index_uid)
         .ok_or(Error::index_not_found(index_uid))?;
 
-    // index folder path in  dump folder
-    let index_path = &dumps_folder.join(index_uid);
-
     // extract `settings.json` file and import content
     let settings = settings_from_path(&index_path)?;
     let settings = settings.to_update().map_err(|_e| Error::dump_failed())?;
@@ -174,6 +188,11 @@ pub fn import_dump(
         DumpVersion::V1 => import_index_v1,
     };
 
+    // the uids name the folders of the indexes in the dump
+    for index in metadata.indexes.iter() {
+        check_archived_index_uid(&index.uid)?;
+    }
+
     // remove indexes which have same `uid` than indexes to import and create empty indexes
     let existing_index_uids = data.db.indexes_uids();
     for index in metadata.indexes.iter() {
@@ -186,15 +205,93 @@ pub fn import_dump(
     // import each indexes content
     data.db.main_write::<_, _, Error>(|mut writer| {
         for index in metadata.indexes {
-            import_index(&data, tmp_dir_path, &index.uid, document_batch_size, &mut writer)?;
+            import_index(&data, &tmp_dir_path.join(&index.uid), &index.uid, document_batch_size, &mut writer)?;
         }
         Ok(())
     })?;
 
+    // import the API keys, they replace the keys with the same uid
+    let keys = keys_from_path(&tmp_dir_path)?;
+    data.keys.import(&data.db, keys)?;
+
     info!("Dump importation from {:?} succeed", dump_path);
     Ok(())
 }
 
+/// Exports the settings and the documents of an index in a tar.gz archive written to the writer,
+/// the archive has the layout of a dump containing only this index and no API keys.
+pub fn export_index<W: Write>(data: &web::Data<Data>, index_uid: &str, writer: W) -> Result<W, Error> {
+    let main_reader = data.db.main_read_txn()?;
+
+    let index = crate::routes::index::list_indexes_sync(data, &main_reader)
+        .map_err(|e| Error::Internal(e.to_string()))?
+        .into_iter()
+        .find(|index| index.uid == index_uid)
+        .ok_or(Error::index_not_found(index_uid))?;
+
+    let tmp_dir = TempDir::new()?;
+    let tmp_dir_path = tmp_dir.path();
+    let index_path = tmp_dir_path.join(index_uid);
+    create_dir_all(&index_path)?;
+
+    dump_metadata(data, tmp_dir_path, vec![index])?;
+    dump_index_settings(data, &main_reader, &index_path, index_uid)?;
+    dump_index_documents(data, &main_reader, &index_path, index_uid)?;
+
+    compression::write_tar_gz(tmp_dir_path, writer)
+}
+
+/// Returns an error if the uid of an index of an archive is not a valid index uid,
+/// the uid names the folder of the index and must not be a path out of the archive.
+fn check_archived_index_uid(index_uid: &str) -> Result<(), Error> {
+    if index::is_valid_index_uid(index_uid) {
+        Ok(())
+    } else {
+        Err(Error::bad_request(format!("invalid index archive: the index uid {:?} is invalid", index_uid)))
+    }
+}
+
+/// Restores the index exported in the archive, or the index of a dump containing a single index,
+/// under the given uid. The index must not exist, the other indexes and the API keys are left untouched.
+pub fn restore_index<R: Read>(data: &Data, archive: R, index_uid: &str) -> Result<IndexResponse, Error> {
+    let tmp_dir = TempDir::new()?;
+    let tmp_dir_path = tmp_dir.path();
+    compression::read_tar_gz(archive, tmp_dir_path)
+        .map_err(|e| Error::bad_request(format!("invalid index archive: {}", e)))?;
+
+    let metadata = DumpMetadata::from_path(tmp_dir_path)
+        .map_err(|e| Error::bad_request(format!("invalid index archive: {}", e)))?;
+    let import_index = match metadata.dump_version {
+        DumpVersion::V1 => import_index_v1,
+    };
+
+    let mut indexes = metadata.indexes;
+    if indexes.len() != 1 {
+        return Err(Error::bad_request("the archive must contain a single index"));
+    }
+    let exported = indexes.remove(0);
+    check_archived_index_uid(&exported.uid)?;
+
+    // an index named after its uid is named after its new uid
+    let name = if exported.name == exported.uid { index_uid.to_string() } else { exported.name };
+    let index = index::create_index_sync(&data.db, index_uid.to_string(), name, exported.primary_key)?;
+
+    let result = data.db.main_write::<_, _, Error>(|mut writer| {
+        import_index(data, &tmp_dir_path.join(&exported.uid), index_uid, data.dump_batch_size, &mut writer)
+    });
+
+    // the index is created in its own transaction, it is removed when its content cannot be imported
+    if let Err(e) = result {
+        if let Err(e) = data.db.delete_index(index_uid) {
+            error!("Cannot remove the index {} after a failed restoration; {}", index_uid, e);
+        }
+        return Err(e);
+    }
+
+    info!("Index {} restored from the archive of the index {}", index_uid, exported.uid);
+    Ok(index)
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum DumpStatus {
@@ -247,6 +344,55 @@ pub fn compressed_dumps_folder(dumps_folder: &Path, dump_uid: &str) -> PathBuf {
     dumps_folder.join(format!("{}.tar.gz", dump_uid))
 }
 
+/// A dump of the dumps folder, as listed by the dumps API.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpFile {
+    pub uid: String,
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Lists the dumps of the dumps folder, from the oldest to the most recent, but the one being created.
+pub fn list_dumps(dumps_folder: &Path) -> Result<Vec<DumpFile>, Error> {
+    let mut dumps = Vec::new();
+    if !dumps_folder.exists() {
+        return Ok(dumps);
+    }
+
+    for entry in fs::read_dir(dumps_folder)? {
+        let path = entry?.path();
+        let uid = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.ends_with(".tar.gz") => name.trim_end_matches(".tar.gz").to_string(),
+            _ => continue,
+        };
+        if is_dump_in_progress(&uid) {
+            continue;
+        }
+        let metadata = fs::metadata(&path)?;
+        dumps.push(DumpFile { uid, size: metadata.len(), created_at: DateTime::from(metadata.modified()?) });
+    }
+
+    // the uids are the creation dates of the dumps
+    dumps.sort_by(|a, b| a.uid.cmp(&b.uid));
+    Ok(dumps)
+}
+
+/// Returns the path of the dump with this uid, if it exists and is complete.
+pub fn dump_path(dumps_folder: &Path, dump_uid: &str) -> Option<PathBuf> {
+    let is_valid_uid = !dump_uid.is_empty() && !dump_uid.starts_with('.') && !dump_uid.contains(|c| c == '/' || c == '\\');
+    let path = compressed_dumps_folder(dumps_folder, dump_uid);
+    if is_valid_uid && !is_dump_in_progress(dump_uid) && path.exists() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+pub fn is_dump_in_progress(dump_uid: &str) -> bool {
+    DumpInfo::get_current().map_or(false, |info| info.uid == dump_uid && info.dump_already_in_progress())
+}
+
 /// Write metadata in dump
 fn dump_metadata(data: &web::Data<Data>, folder_path: &Path, indexes: Vec<IndexResponse>) -> Result<(), Error> {
     let (db_major, db_minor, db_patch) = data.db.version();
@@ -255,6 +401,14 @@ fn dump_metadata(data: &web::Data<Data>, folder_path: &Path, indexes: Vec<IndexR
     metadata.to_path(folder_path)
 }
 
+/// Export the API keys in dump, only their hashes are known
+fn dump_keys(data: &web::Data<Data>, folder_path: &Path) -> Result<(), Error> {
+    let file = File::create(folder_path.join("keys.json"))?;
+    serde_json::to_writer(file, &data.keys.all())?;
+
+    Ok(())
+}
+
 /// Export settings of provided index in dump
 fn dump_index_settings(data: &web::Data<Data>, reader: &MainReader, folder_path: &Path, index_uid: &str) -> Result<(), Error> {
     let settings = crate::routes::setting::get_all_sync(data, reader, index_uid)?;
@@ -277,23 +431,18 @@ fn dump_index_updates(data: &web::Data<Data>, reader: &UpdateReader, folder_path
     Ok(())
 }
 
-/// Export documents of provided index in dump
+/// Export documents of provided index in dump, the documents are written one after the other
 fn dump_index_documents(data: &web::Data<Data>, reader: &MainReader, folder_path: &Path, index_uid: &str) -> Result<(), Error> {
+    let index = data.db.open_index(index_uid).ok_or(Error::index_not_found(index_uid))?;
     let documents_path = folder_path.join("documents.jsonl");
-    let file = File::create(documents_path)?;
-    let dump_batch_size = data.dump_batch_size;
+    let mut file = BufWriter::new(File::create(documents_path)?);
 
-    let mut offset = 0;
-    loop {
-        let documents = crate::routes::document::get_all_documents_sync(data, reader, index_uid, offset, dump_batch_size, None)?;
-        if documents.is_empty() { break; } else { offset += dump_batch_size; }
-
-        for document in documents {
-            serde_json::to_writer(&file, &document)?;
-            writeln!(&file)?;
-        }
-    }
+    crate::routes::document::for_each_document(&index, reader, 0, usize::MAX, None, |document| {
+        serde_json::to_writer(&mut file, &document)?;
+        Ok(writeln!(file)?)
+    })?;
 
+    file.flush()?;
     Ok(())
 }
 
@@ -350,6 +499,12 @@ fn dump_process(data: web::Data<Data>, dumps_folder: PathBuf, dump_info: DumpInf
         return ;
     }
 
+    // export API keys
+    if let Err(e) = dump_keys(&data, &tmp_dir_path) {
+        fail_dump_process(dump_info, "generating API keys", e);
+        return ;
+    }
+
     // export settings, updates and documents for each indexes
     for index in indexes {
         let index_path = tmp_dir_path.join(&index.uid);
@@ -380,11 +535,22 @@ fn dump_process(data: web::Data<Data>, dumps_folder: PathBuf, dump_info: DumpInf
     }
 
     // compress dump in a file named `{dump_uid}.tar.gz` in `dumps_folder`
-    if let Err(e) = crate::helpers::compression::to_tar_gz(&tmp_dir_path, &compressed_dumps_folder(&dumps_folder, &dump_info.uid)) {
+    let dump_path = compressed_dumps_folder(&dumps_folder, &dump_info.uid);
+    if let Err(e) = crate::helpers::compression::to_tar_gz(&tmp_dir_path, &dump_path) {
         fail_dump_process(dump_info, "compressing dump", e);
         return ;
     }
 
+    // copy the dump to the dump storage, if any
+    if let Some(storage) = &data.dump_storage {
+        let name = format!("{}.tar.gz", dump_info.uid);
+        if let Err(e) = storage.store(&dump_path, &name) {
+            fail_dump_process(dump_info, "copying dump to the storage", e);
+            return ;
+        }
+        info!("Dump copied to {}", storage.location(&name));
+    }
+
     // update dump info to `done`
     let resume = DumpInfo::new(
         dump_info.uid,
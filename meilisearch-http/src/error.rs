@@ -3,12 +3,14 @@ use std::fmt;
 
 use actix_http::ResponseBuilder;
 use actix_web as aweb;
-use actix_web::error::{JsonPayloadError, QueryPayloadError};
+use actix_web::error::{JsonPayloadError, PayloadError, QueryPayloadError};
 use actix_web::http::StatusCode;
 use serde_json::json;
 
 use meilisearch_error::{ErrorCode, Code};
 
+use crate::rate_limit::Exceeded;
+
 #[derive(Debug)]
 pub struct ResponseError {
     inner: Box<dyn ErrorCode>,
@@ -42,6 +44,7 @@ pub enum Error {
     DocumentNotFound(String),
     IndexNotFound(String),
     IndexAlreadyExists(String),
+    IndexReadOnly,
     Internal(String),
     InvalidIndexUid,
     InvalidToken(String),
@@ -55,6 +58,12 @@ pub enum Error {
     UnsupportedMediaType,
     DumpAlreadyInProgress,
     DumpProcessFailed,
+    ImportFailed(String),
+    TooManyRequests(String),
+    OriginNotAllowed(String),
+    ShuttingDown,
+    FeatureNotEnabled(String),
+    InsufficientStorage,
 }
 
 impl error::Error for Error {}
@@ -69,6 +78,7 @@ impl ErrorCode for Error {
             DocumentNotFound(_) => Code::DocumentNotFound,
             IndexNotFound(_) => Code::IndexNotFound,
             IndexAlreadyExists(_) => Code::IndexAlreadyExists,
+            IndexReadOnly => Code::IndexReadOnly,
             Internal(_) => Code::Internal,
             InvalidIndexUid => Code::InvalidIndexUid,
             InvalidToken(_) => Code::InvalidToken,
@@ -82,6 +92,12 @@ impl ErrorCode for Error {
             UnsupportedMediaType => Code::UnsupportedMediaType,
             DumpAlreadyInProgress => Code::DumpAlreadyInProgress,
             DumpProcessFailed => Code::DumpProcessFailed,
+            ImportFailed(_) => Code::ImportFailed,
+            TooManyRequests(_) => Code::TooManyRequests,
+            OriginNotAllowed(_) => Code::OriginNotAllowed,
+            ShuttingDown => Code::ShuttingDown,
+            FeatureNotEnabled(_) => Code::FeatureNotEnabled,
+            InsufficientStorage => Code::InsufficientStorage,
         }
     }
 }
@@ -192,6 +208,10 @@ impl Error {
     pub fn dump_failed() -> Error {
         Error::DumpProcessFailed
     }
+
+    pub fn import_failed(err: impl fmt::Display) -> Error {
+        Error::ImportFailed(err.to_string())
+    }
 }
 
 impl fmt::Display for Error {
@@ -203,6 +223,7 @@ impl fmt::Display for Error {
             Self::DocumentNotFound(document_id) => write!(f, "Document with id {} not found", document_id),
             Self::IndexNotFound(index_uid) => write!(f, "Index {} not found", index_uid),
             Self::IndexAlreadyExists(index_uid) => write!(f, "Index {} already exists", index_uid),
+            Self::IndexReadOnly => f.write_str("Index is read-only, it must be made writable before being modified"),
             Self::Internal(err) => f.write_str(err),
             Self::InvalidIndexUid => f.write_str("Index must have a valid uid; Index uid can be of type integer or string only composed of alphanumeric characters, hyphens (-) and underscores (_)."),
             Self::InvalidToken(err) => write!(f, "Invalid API key: {}", err),
@@ -216,6 +237,12 @@ impl fmt::Display for Error {
             Self::UnsupportedMediaType => f.write_str("Unsupported media type"),
             Self::DumpAlreadyInProgress => f.write_str("Another dump is already in progress"),
             Self::DumpProcessFailed => f.write_str("Dump process failed"),
+            Self::ImportFailed(err) => write!(f, "Impossible to import documents; {}", err),
+            Self::TooManyRequests(err) => write!(f, "Too many requests; {}", err),
+            Self::OriginNotAllowed(origin) => write!(f, "The origin {} is only allowed to call the search routes", origin),
+            Self::ShuttingDown => f.write_str("Server is shutting down, the writes are not accepted anymore"),
+            Self::InsufficientStorage => f.write_str("The storage is almost full, the writes are refused until space is freed"),
+            Self::FeatureNotEnabled(feature) => write!(f, "The experimental feature {} is not enabled, it can be enabled on the /experimental-features route", feature),
         }
     }
 }
@@ -261,7 +288,12 @@ impl From<actix_http::Error> for Error {
 
 impl From<meilisearch_core::Error> for Error {
     fn from(err: meilisearch_core::Error) -> Error {
-        Error::Internal(err.to_string())
+        match err {
+            meilisearch_core::Error::IndexReadOnly => Error::IndexReadOnly,
+            meilisearch_core::Error::InsufficientStorage => Error::InsufficientStorage,
+            meilisearch_core::Error::ImportFailed(err) => Error::ImportFailed(err),
+            err => Error::Internal(err.to_string()),
+        }
     }
 }
 
@@ -283,6 +315,11 @@ impl From<JsonPayloadError> for Error {
             JsonPayloadError::Deserialize(err) => Error::BadRequest(format!("Invalid JSON: {}", err)),
             JsonPayloadError::Overflow => Error::PayloadTooLarge,
             JsonPayloadError::ContentType => Error::UnsupportedMediaType,
+            JsonPayloadError::Payload(PayloadError::Overflow) => Error::PayloadTooLarge,
+            // the indexing quota of the key is exceeded while the payload is received
+            JsonPayloadError::Payload(PayloadError::Io(err)) if err.get_ref().map_or(false, |err| err.is::<Exceeded>()) => {
+                Error::TooManyRequests(err.to_string())
+            }
             JsonPayloadError::Payload(err) => Error::BadRequest(format!("Problem while decoding the request: {}", err)),
         }
     }
@@ -0,0 +1,68 @@
+use std::sync::RwLock;
+
+use meilisearch_core::{Database, MResult};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// The features that can be trialed on an instance, they are disabled until enabled on the
+/// `/experimental-features` route and can be toggled without restarting.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentalFeatures {
+    /// The Prometheus metrics served on `/metrics`.
+    #[serde(default)]
+    pub metrics: bool,
+    /// The Elasticsearch-compatible search routes under `/es-compat`.
+    #[serde(default)]
+    pub es_compat: bool,
+}
+
+/// The features to enable or disable, the ones that are not given are left unchanged.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FeaturesUpdate {
+    pub metrics: Option<bool>,
+    pub es_compat: Option<bool>,
+}
+
+/// The experimental features of the instance, kept in memory and persisted in the database.
+pub struct FeatureStore {
+    features: RwLock<ExperimentalFeatures>,
+}
+
+impl FeatureStore {
+    pub fn load(db: &Database) -> MResult<FeatureStore> {
+        let reader = db.main_read_txn()?;
+        let features = db.experimental_features::<ExperimentalFeatures>(&reader)?.unwrap_or_default();
+        Ok(FeatureStore { features: RwLock::new(features) })
+    }
+
+    pub fn get(&self) -> ExperimentalFeatures {
+        *self.features.read().unwrap()
+    }
+
+    pub fn update(&self, db: &Database, update: FeaturesUpdate) -> Result<ExperimentalFeatures, Error> {
+        let mut features = self.features.write().unwrap();
+        let mut new_features = *features;
+        if let Some(metrics) = update.metrics {
+            new_features.metrics = metrics;
+        }
+        if let Some(es_compat) = update.es_compat {
+            new_features.es_compat = es_compat;
+        }
+
+        db.main_write::<_, _, Error>(|writer| Ok(db.put_experimental_features(writer, &new_features)?))?;
+        *features = new_features;
+        Ok(new_features)
+    }
+
+    /// Returns an error naming the feature if it is not enabled.
+    pub fn ensure_enabled(&self, name: &str, enabled: impl Fn(&ExperimentalFeatures) -> bool) -> Result<(), Error> {
+        if enabled(&self.features.read().unwrap()) {
+            Ok(())
+        } else {
+            Err(Error::FeatureNotEnabled(name.to_string()))
+        }
+    }
+}
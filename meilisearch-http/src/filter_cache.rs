@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use meilisearch_core::DocumentId;
+
+/// The number of times a filter must be used on an index before its documents are cached, so that
+/// the filters used once don't evict the frequent ones.
+const MIN_USES: u64 = 2;
+
+/// The memory used by an entry besides its key and its documents.
+const ENTRY_OVERHEAD: usize = mem::size_of::<(String, String)>() + mem::size_of::<Entry>();
+
+/// A set of document ids, stored as a bitmap when the ids are dense and as a sorted list otherwise.
+#[derive(Debug, PartialEq)]
+pub enum DocumentIds {
+    Sorted(Vec<u32>),
+    Bitmap(Vec<u64>),
+}
+
+impl DocumentIds {
+    pub fn contains(&self, id: DocumentId) -> bool {
+        match self {
+            DocumentIds::Sorted(ids) => ids.binary_search(&id.0).is_ok(),
+            DocumentIds::Bitmap(words) => {
+                let word = words.get(id.0 as usize / 64).copied().unwrap_or(0);
+                word & (1u64 << (id.0 % 64)) != 0
+            }
+        }
+    }
+
+    /// The memory used by the ids, in bytes.
+    pub fn memory(&self) -> usize {
+        match self {
+            DocumentIds::Sorted(ids) => ids.len() * mem::size_of::<u32>(),
+            DocumentIds::Bitmap(words) => words.len() * mem::size_of::<u64>(),
+        }
+    }
+}
+
+impl FromIterator<DocumentId> for DocumentIds {
+    fn from_iter<I: IntoIterator<Item = DocumentId>>(iter: I) -> DocumentIds {
+        let mut ids: Vec<u32> = iter.into_iter().map(|id| id.0).collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let bitmap_words = ids.last().map_or(0, |max| *max as usize / 64 + 1);
+        if bitmap_words * mem::size_of::<u64>() < ids.len() * mem::size_of::<u32>() {
+            let mut words = vec![0u64; bitmap_words];
+            for id in ids {
+                words[id as usize / 64] |= 1u64 << (id % 64);
+            }
+            DocumentIds::Bitmap(words)
+        } else {
+            ids.shrink_to_fit();
+            DocumentIds::Sorted(ids)
+        }
+    }
+}
+
+/// The documents matching the filters frequently used in the searches, by index. The documents
+/// of a filter are valid until the index is updated, the update date of the index is the
+/// watermark compared to the one of the cached documents. The least recently used filters
+/// are evicted once the entries use more than the maximum memory.
+pub struct FilterCache {
+    max_memory: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<(String, String), Entry>,
+    clock: u64,
+}
+
+struct Entry {
+    watermark: Option<DateTime<Utc>>,
+    uses: u64,
+    last_used: u64,
+    documents: Option<Arc<DocumentIds>>,
+}
+
+impl Entry {
+    fn memory(&self, key: &(String, String)) -> usize {
+        ENTRY_OVERHEAD + key.0.len() + key.1.len() + self.documents.as_ref().map_or(0, |documents| documents.memory())
+    }
+}
+
+impl FilterCache {
+    pub fn new(max_memory: usize) -> FilterCache {
+        FilterCache { max_memory, inner: Mutex::default() }
+    }
+
+    /// Returns the documents of this filter, computed by `compute` the first time the filter is
+    /// used often enough. Returns `None` while the filter is not used often enough to be cached.
+    pub fn get_or_compute<F, E>(
+        &self,
+        index_uid: &str,
+        filter: &str,
+        watermark: Option<DateTime<Utc>>,
+        compute: F,
+    ) -> Result<Option<Arc<DocumentIds>>, E>
+    where F: FnOnce() -> Result<DocumentIds, E>,
+    {
+        let key = (index_uid.to_string(), filter.to_string());
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.clock += 1;
+            let clock = inner.clock;
+
+            let entry = inner.entries.entry(key.clone()).or_insert(Entry { watermark, uses: 0, last_used: clock, documents: None });
+            if entry.watermark != watermark {
+                *entry = Entry { watermark, uses: 0, last_used: clock, documents: None };
+            }
+            entry.uses += 1;
+            entry.last_used = clock;
+
+            if let Some(documents) = &entry.documents {
+                return Ok(Some(documents.clone()));
+            }
+            if entry.uses < MIN_USES {
+                inner.evict(self.max_memory);
+                return Ok(None);
+            }
+        }
+
+        // the other searches are not blocked while the documents are computed
+        let documents = Arc::new(compute()?);
+
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(entry) = inner.entries.get_mut(&key) {
+            if entry.watermark == watermark {
+                entry.documents = Some(documents.clone());
+            }
+        }
+        inner.evict(self.max_memory);
+
+        Ok(Some(documents))
+    }
+
+    /// The number of filters whose documents are cached.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.values().filter(|entry| entry.documents.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The memory used by the entries, in bytes.
+    pub fn memory(&self) -> usize {
+        self.inner.lock().unwrap().memory()
+    }
+}
+
+impl Inner {
+    fn memory(&self) -> usize {
+        self.entries.iter().map(|(key, entry)| entry.memory(key)).sum()
+    }
+
+    fn evict(&mut self, max_memory: usize) {
+        let mut memory = self.memory();
+        while memory > max_memory {
+            let oldest = self.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone());
+            match oldest.and_then(|key| self.entries.remove_entry(&key)) {
+                Some((key, entry)) => memory -= entry.memory(&key),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[u32]) -> DocumentIds {
+        ids.iter().map(|id| DocumentId(*id)).collect()
+    }
+
+    #[test]
+    fn frequent_filters_are_cached_until_the_update() {
+        let cache = FilterCache::new(1024 * 1024);
+        let watermark = Some(Utc::now());
+        let compute = || Ok::<_, ()>(ids(&[1]));
+
+        assert_eq!(cache.get_or_compute("movies", "a = 1", watermark, compute), Ok(None));
+        assert!(cache.get_or_compute("movies", "a = 1", watermark, compute).unwrap().is_some());
+        assert_eq!(cache.len(), 1);
+
+        // the cached documents are returned without computing them again
+        let documents = cache.get_or_compute("movies", "a = 1", watermark, || Err(())).unwrap();
+        assert!(documents.unwrap().contains(DocumentId(1)));
+
+        // the documents are forgotten once the index is updated
+        let updated = Some(Utc::now() + chrono::Duration::seconds(1));
+        assert_eq!(cache.get_or_compute("movies", "a = 1", updated, compute), Ok(None));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn least_recently_used_filters_are_evicted_over_the_memory() {
+        let documents: Vec<u32> = (0..1000).map(|id| id * 100).collect();
+        let max_memory = 2 * (ids(&documents).memory() + ENTRY_OVERHEAD + 100);
+        let cache = FilterCache::new(max_memory);
+        let compute = || Ok::<_, ()>(ids(&documents));
+
+        for filter in &["a = 1", "a = 2", "a = 1", "a = 2", "a = 3", "a = 3"] {
+            cache.get_or_compute("movies", filter, None, compute).unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.memory() <= max_memory);
+        assert_eq!(cache.get_or_compute("movies", "a = 1", None, || Err(())), Ok(None));
+    }
+
+    #[test]
+    fn dense_ids_are_stored_as_bitmaps() {
+        let dense = ids(&(0..1000).collect::<Vec<_>>());
+        assert!(matches!(dense, DocumentIds::Bitmap(_)));
+        assert_eq!(dense.memory(), 16 * 8);
+
+        let sparse = ids(&[5, 1_000_000, 5]);
+        assert_eq!(sparse, DocumentIds::Sorted(vec![5, 1_000_000]));
+
+        for set in &[dense, sparse] {
+            assert!(set.contains(DocumentId(5)));
+            assert!(!set.contains(DocumentId(1001)));
+            assert!(!set.contains(DocumentId(u32::max_value())));
+        }
+    }
+}
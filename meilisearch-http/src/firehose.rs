@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use chrono::{DateTime, Utc};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use indexmap::IndexMap;
+use log::{error, warn};
+use meilisearch_core::update::{self, Update, UpdateData};
+use meilisearch_core::{Database, MResult, ProcessedUpdateResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::webhook::send;
+
+/// The number of events sent in a single request when the target doesn't specify it.
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+fn default_batch_size() -> usize {
+    DEFAULT_BATCH_SIZE
+}
+
+/// An endpoint receiving the changes made to the documents of an index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FirehoseTarget {
+    pub url: String,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+}
+
+impl FirehoseTarget {
+    pub fn validate(&self) -> Result<(), Error> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(Error::bad_parameter("url", format!("{:?} is not an HTTP URL", self.url)));
+        }
+        if self.batch_size == 0 {
+            return Err(Error::bad_parameter("batchSize", "must be greater than zero"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventType {
+    /// The document has been added or replaced.
+    Added,
+    /// Some fields of the document have been added or replaced.
+    Updated,
+    Deleted,
+    /// All the documents of the index have been deleted.
+    Cleared,
+}
+
+/// A change made to the documents of an index, the events of an update are sent in the order
+/// of the documents of the update.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentEvent {
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    pub index_uid: String,
+    pub update_id: u64,
+    pub processed_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<String>,
+    /// The document, or its fields that have been updated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<IndexMap<String, Value>>,
+}
+
+/// The events of an update applied to an index, none for the updates of the settings and the imports.
+pub fn document_events(index_uid: &str, primary_key: Option<&str>, update_id: u64, processed_at: DateTime<Utc>, update: &Update) -> Vec<DocumentEvent> {
+    let event = |event_type, document_id, document| DocumentEvent {
+        event_type,
+        index_uid: index_uid.to_string(),
+        update_id,
+        processed_at,
+        document_id,
+        document,
+    };
+    let id = |document: &IndexMap<String, Value>| {
+        primary_key.and_then(|key| document.get(key)).map(update::value_to_string)
+    };
+
+    match update.data() {
+        UpdateData::DocumentsAddition(documents) => documents
+            .iter()
+            .map(|document| event(EventType::Added, id(document), Some(document.clone())))
+            .collect(),
+        UpdateData::DocumentsPartial { documents, .. } => documents
+            .iter()
+            .map(|document| event(EventType::Updated, id(document), Some(document.clone())))
+            .collect(),
+        UpdateData::DocumentsDeletion(ids) => ids
+            .iter()
+            .map(|document_id| event(EventType::Deleted, Some(document_id.clone()), None))
+            .collect(),
+        UpdateData::ClearAll => vec![event(EventType::Cleared, None, None)],
+        // the imported documents are read from their files and are not part of the update
+        UpdateData::Customs(_) | UpdateData::Settings(_) | UpdateData::DocumentsImport(_) => Vec::new(),
+    }
+}
+
+struct AppliedUpdate {
+    index_uid: String,
+    primary_key: Option<String>,
+    update_id: u64,
+    processed_at: DateTime<Utc>,
+    /// The update as it was stored, it is deserialized by the forwarding thread.
+    update: Vec<u8>,
+}
+
+type Targets = Arc<RwLock<HashMap<String, Vec<FirehoseTarget>>>>;
+
+/// Forwards the changes made to the documents of the indexes to their targets from a dedicated
+/// thread, by batches of JSON events. The batches a target failed to receive after the retries
+/// are appended to the dead letter file, one JSON object per line, to be replayed by hand.
+pub struct Firehose {
+    targets: Targets,
+    sender: Sender<AppliedUpdate>,
+}
+
+impl Firehose {
+    pub fn spawn(db: &Database, secret: Option<String>, dead_letter_path: PathBuf) -> MResult<Firehose> {
+        let reader = db.main_read_txn()?;
+        let targets = db.firehoses::<HashMap<String, Vec<FirehoseTarget>>>(&reader)?.unwrap_or_default();
+        drop(reader);
+
+        let targets = Arc::new(RwLock::new(targets));
+        let (sender, receiver) = unbounded();
+        let thread_targets = targets.clone();
+        thread::Builder::new()
+            .name("firehose".to_string())
+            .spawn(move || forward(receiver, thread_targets, secret, dead_letter_path))?;
+
+        Ok(Firehose { targets, sender })
+    }
+
+    pub fn targets(&self, index_uid: &str) -> Vec<FirehoseTarget> {
+        self.targets.read().unwrap().get(index_uid).cloned().unwrap_or_default()
+    }
+
+    pub fn has_targets(&self, index_uid: &str) -> bool {
+        self.targets.read().unwrap().contains_key(index_uid)
+    }
+
+    /// Replaces the targets of the index, no target removes the firehose of the index.
+    pub fn set_targets(&self, db: &Database, index_uid: &str, targets: Vec<FirehoseTarget>) -> Result<(), Error> {
+        let mut all_targets = self.targets.write().unwrap();
+        let mut new_targets = all_targets.clone();
+        if targets.is_empty() {
+            new_targets.remove(index_uid);
+        } else {
+            new_targets.insert(index_uid.to_string(), targets);
+        }
+        db.main_write::<_, _, Error>(|writer| Ok(db.put_firehoses(writer, &new_targets)?))?;
+        *all_targets = new_targets;
+        Ok(())
+    }
+
+    pub fn notify(&self, index_uid: &str, primary_key: Option<String>, update: &[u8], status: &ProcessedUpdateResult) {
+        let update = AppliedUpdate {
+            index_uid: index_uid.to_string(),
+            primary_key,
+            update_id: status.update_id,
+            processed_at: status.processed_at,
+            update: update.to_vec(),
+        };
+        if self.sender.send(update).is_err() {
+            error!("The firehose thread is not running anymore");
+        }
+    }
+}
+
+/// Sends the events of the updates, the updates applied while a batch was being sent are
+/// forwarded together so that a burst of small updates doesn't result in a burst of requests.
+fn forward(receiver: Receiver<AppliedUpdate>, targets: Targets, secret: Option<String>, dead_letter_path: PathBuf) {
+    while let Ok(first) = receiver.recv() {
+        let mut events: Vec<(String, Vec<DocumentEvent>)> = Vec::new();
+        for applied in std::iter::once(first).chain(receiver.try_iter()) {
+            let update: Update = match serde_json::from_slice(&applied.update) {
+                Ok(update) => update,
+                Err(e) => {
+                    error!("Cannot read the update {} of the index {}; {}", applied.update_id, applied.index_uid, e);
+                    continue;
+                }
+            };
+            let update_events = document_events(
+                &applied.index_uid,
+                applied.primary_key.as_deref(),
+                applied.update_id,
+                applied.processed_at,
+                &update,
+            );
+            match events.iter_mut().find(|(index_uid, _)| *index_uid == applied.index_uid) {
+                Some((_, index_events)) => index_events.extend(update_events),
+                None => events.push((applied.index_uid, update_events)),
+            }
+        }
+
+        for (index_uid, events) in events.iter().filter(|(_, events)| !events.is_empty()) {
+            let index_targets = targets.read().unwrap().get(index_uid).cloned().unwrap_or_default();
+            for target in index_targets {
+                for batch in events.chunks(target.batch_size) {
+                    let payload = match serde_json::to_string(batch) {
+                        Ok(payload) => payload,
+                        Err(e) => {
+                            error!("Impossible to serialize the firehose events: {}", e);
+                            continue;
+                        }
+                    };
+                    if !send(&target.url, &payload, secret.as_deref()) {
+                        warn!("The firehose target {} didn't receive {} events of the index {}", target.url, batch.len(), index_uid);
+                        if let Err(e) = write_dead_letter(&dead_letter_path, &target.url, batch) {
+                            error!("Cannot write the dead letter of the firehose target {}; {}", target.url, e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeadLetter<'a> {
+    url: &'a str,
+    failed_at: DateTime<Utc>,
+    events: &'a [DocumentEvent],
+}
+
+fn write_dead_letter(path: &Path, url: &str, events: &[DocumentEvent]) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(&DeadLetter { url, failed_at: Utc::now(), events })?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn update(data: Value) -> Update {
+        serde_json::from_value(json!({ "data": data, "enqueued_at": Utc::now() })).unwrap()
+    }
+
+    #[test]
+    fn events_of_the_updates() {
+        let now = Utc::now();
+        let addition = update(json!({ "DocumentsAddition": [{ "id": 1, "title": "Carol" }, { "id": "2", "title": "Mad Max" }] }));
+        let events = document_events("movies", Some("id"), 3, now, &addition);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, EventType::Added);
+        assert_eq!(events[0].document_id.as_deref(), Some("1"));
+        assert_eq!(events[1].document_id.as_deref(), Some("2"));
+        assert_eq!(
+            serde_json::to_value(&events[0]).unwrap(),
+            json!({
+                "type": "added",
+                "indexUid": "movies",
+                "updateId": 3,
+                "processedAt": now,
+                "documentId": "1",
+                "document": { "id": 1, "title": "Carol" },
+            }),
+        );
+
+        let deletion = update(json!({ "DocumentsDeletion": ["1", "2"] }));
+        let events = document_events("movies", Some("id"), 4, now, &deletion);
+        assert_eq!(events.iter().map(|e| e.event_type).collect::<Vec<_>>(), vec![EventType::Deleted; 2]);
+        assert!(events.iter().all(|e| e.document.is_none()));
+
+        let clear = update(json!("ClearAll"));
+        assert_eq!(document_events("movies", None, 5, now, &clear)[0].event_type, EventType::Cleared);
+    }
+
+    #[test]
+    fn dead_letters_are_appended() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dead-letters.jsonl");
+        let update = update(json!({ "DocumentsDeletion": ["1"] }));
+        let events = document_events("movies", Some("id"), 1, Utc::now(), &update);
+
+        write_dead_letter(&path, "http://localhost:1/", &events).unwrap();
+        write_dead_letter(&path, "http://localhost:2/", &events).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let letters: Vec<Value> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(letters[1]["url"], "http://localhost:2/");
+        assert_eq!(letters[1]["events"][0]["documentId"], "1");
+    }
+}
@@ -0,0 +1,337 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use actix_service::{Service, Transform};
+use actix_web::dev::{BodySize, MessageBody, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{REFERER, USER_AGENT};
+use actix_web::{web, HttpMessage, ResponseError};
+use chrono::{DateTime, Local, SecondsFormat};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use log::error;
+use serde::Serialize;
+
+use crate::helpers::logging::{ApiKeyUid, RequestId};
+use crate::Data;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    /// The NCSA common log format followed by the duration in microseconds.
+    Common,
+    /// The NCSA combined log format followed by the duration in microseconds.
+    Combined,
+    /// One JSON object per line.
+    Json,
+}
+
+impl Default for AccessLogFormat {
+    fn default() -> AccessLogFormat {
+        AccessLogFormat::Combined
+    }
+}
+
+impl FromStr for AccessLogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AccessLogFormat, String> {
+        match s {
+            "common" => Ok(AccessLogFormat::Common),
+            "combined" => Ok(AccessLogFormat::Combined),
+            "json" => Ok(AccessLogFormat::Json),
+            _ => Err(format!("{:?} is not an access log format, it must be common, combined or json", s)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AccessLogEntry<'a> {
+    timestamp: String,
+    client_ip: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key_uid: Option<&'a str>,
+    method: &'a str,
+    path: &'a str,
+    /// The pattern of the route that handled the request, e.g. `/indexes/{index_uid}/search`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    route: Option<&'a str>,
+    http_version: &'a str,
+    status: u16,
+    /// The size of the body, before compression, unknown for the streamed bodies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes: Option<u64>,
+    duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    referer: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_agent: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<&'a str>,
+}
+
+impl AccessLogEntry<'_> {
+    fn format(&self, format: AccessLogFormat, date: DateTime<Local>) -> String {
+        let common = || {
+            format!(
+                "{} - {} [{}] \"{} {} {}\" {} {}",
+                self.client_ip,
+                self.api_key_uid.unwrap_or("-"),
+                date.format("%d/%b/%Y:%H:%M:%S %z"),
+                self.method,
+                self.path,
+                self.http_version,
+                self.status,
+                self.bytes.map_or_else(|| "-".to_string(), |bytes| bytes.to_string()),
+            )
+        };
+        let duration_us = (self.duration_ms * 1000.0).round() as u64;
+
+        match format {
+            AccessLogFormat::Common => format!("{} {}", common(), duration_us),
+            AccessLogFormat::Combined => format!(
+                "{} \"{}\" \"{}\" {}",
+                common(),
+                escape(self.referer.unwrap_or("-")),
+                escape(self.user_agent.unwrap_or("-")),
+                duration_us,
+            ),
+            AccessLogFormat::Json => serde_json::to_string(self).unwrap_or_default(),
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Writes the access logs to a file, the file is rotated once it reaches the maximum size.
+pub struct AccessLogWriter {
+    format: AccessLogFormat,
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: Mutex<(File, u64)>,
+}
+
+impl AccessLogWriter {
+    /// Opens the access log file, when it is larger than `max_size` it is renamed with a `.1`
+    /// suffix, the previous ones are shifted and only `max_files` rotated files are kept.
+    pub fn open(path: impl AsRef<Path>, format: AccessLogFormat, max_size: u64, max_files: usize) -> io::Result<AccessLogWriter> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(AccessLogWriter { format, path, max_size, max_files, file: Mutex::new((file, size)) })
+    }
+
+    fn write(&self, line: &str) -> io::Result<()> {
+        let mut guard = self.file.lock().unwrap();
+        let (file, size) = &mut *guard;
+
+        if *size > 0 && *size + line.len() as u64 + 1 > self.max_size {
+            self.rotate()?;
+            *file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            *size = 0;
+        }
+
+        writeln!(file, "{}", line)?;
+        *size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn rotate(&self) -> io::Result<()> {
+        let rotated = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", n));
+            PathBuf::from(name)
+        };
+
+        if self.max_files == 0 {
+            return fs::remove_file(&self.path);
+        }
+        for n in (1..self.max_files).rev() {
+            if rotated(n).exists() {
+                fs::rename(rotated(n), rotated(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(1))
+    }
+}
+
+/// Writes an access log line for each request, when an access log file is configured.
+pub struct AccessLog;
+
+impl<S, B> Transform<S> for AccessLog
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type InitError = ();
+    type Transform = AccessLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AccessLogMiddleware { service })
+    }
+}
+
+pub struct AccessLogMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service for AccessLogMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let writer: Arc<AccessLogWriter> = match req.app_data::<web::Data<Data>>().and_then(|data| data.access_log.clone()) {
+            Some(writer) => writer,
+            None => return self.service.call(req).boxed_local(),
+        };
+
+        let start = Instant::now();
+        let date = Local::now();
+        let method = req.method().to_string();
+        let path = req.uri().to_string();
+        let http_version = format!("{:?}", req.version());
+        let client_ip = req.connection_info().realip_remote_addr().map(|addr| {
+            // the peer address comes with its port, the forwarded ones don't
+            addr.parse::<SocketAddr>().map_or_else(|_| addr.to_string(), |addr| addr.ip().to_string())
+        });
+        let header = |name| req.headers().get(name).and_then(|value| value.to_str().ok()).map(ToString::to_string);
+        let referer = header(REFERER);
+        let user_agent = header(USER_AGENT);
+
+        self.service.call(req).map(move |result| {
+            let mut entry = AccessLogEntry {
+                timestamp: date.to_rfc3339_opts(SecondsFormat::Millis, true),
+                client_ip: client_ip.as_deref().unwrap_or("-"),
+                api_key_uid: None,
+                method: &method,
+                path: &path,
+                route: None,
+                http_version: &http_version,
+                status: 0,
+                bytes: None,
+                duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+                referer: referer.as_deref(),
+                user_agent: user_agent.as_deref(),
+                request_id: None,
+            };
+
+            let line = match &result {
+                Ok(res) => {
+                    let request = res.request();
+                    let extensions = request.extensions();
+                    let route = request.match_pattern();
+                    entry.status = res.status().as_u16();
+                    entry.bytes = match res.response().body().size() {
+                        BodySize::Sized(size) => Some(size as u64),
+                        BodySize::Sized64(size) => Some(size),
+                        BodySize::Empty | BodySize::None => Some(0),
+                        BodySize::Stream => None,
+                    };
+                    entry.route = route.as_deref();
+                    entry.api_key_uid = extensions.get::<ApiKeyUid>().map(|key| key.0.as_str());
+                    entry.request_id = extensions.get::<RequestId>().map(|id| id.0.as_str());
+                    entry.format(writer.format, date)
+                }
+                Err(e) => {
+                    entry.status = e.as_response_error().status_code().as_u16();
+                    entry.format(writer.format, date)
+                }
+            };
+
+            if let Err(e) = writer.write(&line) {
+                error!("could not write the access log: {}", e);
+            }
+
+            result
+        })
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn entry() -> AccessLogEntry<'static> {
+        AccessLogEntry {
+            timestamp: String::new(),
+            client_ip: "127.0.0.1",
+            api_key_uid: Some("master"),
+            method: "GET",
+            path: "/indexes/movies/search?q=joy",
+            route: Some("/indexes/{index_uid}/search"),
+            http_version: "HTTP/1.1",
+            status: 200,
+            bytes: Some(512),
+            duration_ms: 1.25,
+            referer: None,
+            user_agent: Some("curl/7.68.0"),
+            request_id: None,
+        }
+    }
+
+    #[test]
+    fn formats() {
+        let date = Local.ymd(2020, 10, 10).and_hms(13, 55, 36);
+        let offset = date.format("%z");
+
+        assert_eq!(
+            entry().format(AccessLogFormat::Common, date),
+            format!("127.0.0.1 - master [10/Oct/2020:13:55:36 {}] \"GET /indexes/movies/search?q=joy HTTP/1.1\" 200 512 1250", offset),
+        );
+        assert_eq!(
+            entry().format(AccessLogFormat::Combined, date),
+            format!("127.0.0.1 - master [10/Oct/2020:13:55:36 {}] \"GET /indexes/movies/search?q=joy HTTP/1.1\" 200 512 \"-\" \"curl/7.68.0\" 1250", offset),
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&entry().format(AccessLogFormat::Json, date)).unwrap();
+        assert_eq!(json["route"], "/indexes/{index_uid}/search");
+        assert_eq!(json["apiKeyUid"], "master");
+        assert!(json.get("referer").is_none());
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("access.log");
+        let writer = AccessLogWriter::open(&path, AccessLogFormat::Common, 10, 2).unwrap();
+
+        for line in &["first", "second", "third", "fourth"] {
+            writer.write(line).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(fs::read_to_string(dir.path().join("access.log.1")).unwrap(), "third\n");
+        assert_eq!(fs::read_to_string(dir.path().join("access.log.2")).unwrap(), "second\n");
+        assert!(!dir.path().join("access.log.3").exists());
+    }
+}
@@ -1,22 +1,121 @@
 use std::cell::RefCell;
+use std::io;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
+use actix_http::error::PayloadError;
+use actix_http::Payload;
 use actix_service::{Service, Transform};
-use actix_web::{dev::ServiceRequest, dev::ServiceResponse, web};
-use futures::future::{err, ok, Future, Ready};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, RETRY_AFTER};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, web, HttpMessage, HttpRequest};
+use futures::future::{err, ok, Future, Ready, TryFutureExt};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
 
 use crate::error::{Error, ResponseError};
+use crate::helpers::client_certificate::ClientIdentity;
+use crate::helpers::logging::ApiKeyUid;
+use crate::keys::{derive_key, ApiKey, IndexScope};
+use crate::rate_limit::{Allowance, Exceeded};
+use crate::routes::grpc::{GRPC_SERVICE_PATH, INDEX_UID_METADATA};
+use crate::routes::task::parse_task_uid;
+use crate::tenant_token::{self, IndexSearchRules, API_KEY_PREFIX_LENGTH};
+use crate::data::ApiKeys;
 use crate::Data;
 
+/// The actions an API key can be allowed to perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    #[serde(rename = "*")]
+    All,
+    #[serde(rename = "search")]
+    Search,
+    #[serde(rename = "documents.get")]
+    DocumentsGet,
+    #[serde(rename = "documents.add")]
+    DocumentsAdd,
+    #[serde(rename = "documents.delete")]
+    DocumentsDelete,
+    #[serde(rename = "indexes.get")]
+    IndexesGet,
+    #[serde(rename = "indexes.create")]
+    IndexesCreate,
+    #[serde(rename = "indexes.update")]
+    IndexesUpdate,
+    #[serde(rename = "indexes.delete")]
+    IndexesDelete,
+    #[serde(rename = "indexes.export")]
+    IndexesExport,
+    #[serde(rename = "indexes.restore")]
+    IndexesRestore,
+    #[serde(rename = "settings.get")]
+    SettingsGet,
+    #[serde(rename = "settings.update")]
+    SettingsUpdate,
+    #[serde(rename = "tasks.get")]
+    TasksGet,
+    #[serde(rename = "tasks.cancel")]
+    TasksCancel,
+    #[serde(rename = "tasks.delete")]
+    TasksDelete,
+    #[serde(rename = "tasks.schedule")]
+    TasksSchedule,
+    #[serde(rename = "stats.get")]
+    StatsGet,
+    #[serde(rename = "dumps.create")]
+    DumpsCreate,
+    #[serde(rename = "dumps.get")]
+    DumpsGet,
+    #[serde(rename = "dumps.delete")]
+    DumpsDelete,
+    #[serde(rename = "snapshots.get")]
+    SnapshotsGet,
+    #[serde(rename = "snapshots.delete")]
+    SnapshotsDelete,
+    #[serde(rename = "health.update")]
+    HealthUpdate,
+    #[serde(rename = "version")]
+    Version,
+}
+
+impl Action {
+    /// The actions the public key is allowed to perform, the private key is allowed to perform all of them.
+    fn is_public(self) -> bool {
+        matches!(self, Action::Search | Action::DocumentsGet)
+    }
+}
+
 #[derive(Clone)]
 pub enum Authentication {
-    Public,
-    Private,
+    /// The route performs an action that the master key, the private key, the public key
+    /// for the public actions, and the scoped keys allowing this action can perform.
+    Action(Action),
+    /// Same as `Action` for a route that doesn't target a specific index but the indexes named
+    /// in its query or its body, or that lists the indexes or their tasks. The scoped keys are
+    /// accepted whatever their index patterns, the route checks the indexes against their [`IndexScope`].
+    IndexesAction(Action),
+    /// The route can only be called with the master key.
     Admin,
 }
 
+impl Authentication {
+    fn action(&self) -> Option<Action> {
+        match *self {
+            Authentication::Action(action) | Authentication::IndexesAction(action) => Some(action),
+            Authentication::Admin => None,
+        }
+    }
+
+    fn allows(&self, key: &ApiKey, index_uid: Option<&str>) -> bool {
+        match *self {
+            Authentication::Action(action) => key.allows(action, index_uid),
+            Authentication::IndexesAction(action) => key.allows_action(action),
+            Authentication::Admin => false,
+        }
+    }
+}
+
 impl<S: 'static, B> Transform<S> for Authentication
 where
     S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
@@ -59,45 +158,233 @@ where
         self.service.poll_ready(cx)
     }
 
-    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
         let mut svc = self.service.clone();
         // This unwrap is left because this error should never appear. If that's the case, then
         // it means that actix-web has an issue or someone changes the type `Data`.
-        let data = req.app_data::<web::Data<Data>>().unwrap();
+        let data = req.app_data::<web::Data<Data>>().unwrap().clone();
 
-        if data.api_keys.master.is_none() {
+        let api_keys = data.api_keys.read().unwrap().clone();
+        if api_keys.master.is_none() {
             return Box::pin(svc.call(req));
         }
 
+        // the index targeted by the request, if it can be known from the path,
+        // the gRPC calls name it in their metadata as their path can't contain it,
+        // the metadata is ignored by the other routes as nothing checks it matches what they access
+        let index_uid = match req.match_info().get("index_uid") {
+            Some(index_uid) => Some(index_uid.to_string()),
+            None if req.path().starts_with(GRPC_SERVICE_PATH) => req.headers().get(INDEX_UID_METADATA)
+                .and_then(|uid| uid.to_str().ok())
+                .map(str::to_string),
+            None => req.match_info().get("task_uid")
+                .and_then(|uid| parse_task_uid(uid).ok())
+                .map(|(index_uid, _)| index_uid.to_string()),
+        };
+
         let auth_header = match req.headers().get("X-Meili-API-Key") {
             Some(auth) => match auth.to_str() {
                 Ok(auth) => auth,
                 Err(_) => return Box::pin(err(ResponseError::from(Error::MissingAuthorizationHeader).into())),
             },
             None => {
-                return Box::pin(err(ResponseError::from(Error::MissingAuthorizationHeader).into()));
+                // clients authenticated with a certificate are given the scopes of the key mapped to it
+                let key = req.extensions().get::<ClientIdentity>()
+                    .and_then(|identity| client_certificate_key(&data, identity, &self.acl, index_uid.as_deref()));
+
+                return match (self.acl.action(), key) {
+                    (Some(action), Some(key)) => {
+                        let limit = check_rate_limit(&data, &mut req, &key, action);
+                        req.extensions_mut().insert(key.scope());
+                        req.extensions_mut().insert(ApiKeyUid(key.uid));
+                        call_rate_limited(&mut svc, req, limit)
+                    }
+                    _ => Box::pin(err(ResponseError::from(Error::MissingAuthorizationHeader).into())),
+                };
             }
         };
 
-        let authenticated = match self.acl {
-            Authentication::Admin => data.api_keys.master.as_deref() == Some(auth_header),
-            Authentication::Private => {
-                data.api_keys.master.as_deref() == Some(auth_header)
-                    || data.api_keys.private.as_deref() == Some(auth_header)
-            }
-            Authentication::Public => {
-                data.api_keys.master.as_deref() == Some(auth_header)
-                    || data.api_keys.private.as_deref() == Some(auth_header)
-                    || data.api_keys.public.as_deref() == Some(auth_header)
+        // tenant tokens embed the rules that restrict the searches made with them
+        if tenant_token::is_tenant_token(auth_header) {
+            return match tenant_token_rules(&data, &api_keys, auth_header, &self.acl, index_uid.as_deref()) {
+                Some(rules) => {
+                    req.extensions_mut().insert(rules);
+                    Box::pin(svc.call(req))
+                }
+                None => Box::pin(err(
+                    ResponseError::from(Error::InvalidToken(auth_header.to_string())).into()
+                )),
+            };
+        }
+
+        let is_master = api_keys.master.as_deref() == Some(auth_header);
+        let authenticated = match self.acl.action() {
+            None => is_master,
+            Some(action) => {
+                is_master
+                    || api_keys.private.as_deref() == Some(auth_header)
+                    || (action.is_public() && api_keys.public.as_deref() == Some(auth_header))
             }
         };
 
         if authenticated {
-            Box::pin(svc.call(req))
-        } else {
-            Box::pin(err(
-                ResponseError::from(Error::InvalidToken(auth_header.to_string())).into()
-            ))
+            let name = if is_master {
+                "master"
+            } else if api_keys.private.as_deref() == Some(auth_header) {
+                "private"
+            } else {
+                "public"
+            };
+            req.extensions_mut().insert(ApiKeyUid(name.to_string()));
+            return Box::pin(svc.call(req));
+        }
+
+        // the scoped keys are the only ones with rate limits
+        if let Some(action) = self.acl.action() {
+            if let Some(key) = data.keys.find(auth_header).filter(|key| self.acl.allows(key, index_uid.as_deref())) {
+                let limit = check_rate_limit(&data, &mut req, &key, action);
+                req.extensions_mut().insert(key.scope());
+                req.extensions_mut().insert(ApiKeyUid(key.uid));
+                return call_rate_limited(&mut svc, req, limit);
+            }
         }
+
+        Box::pin(err(
+            ResponseError::from(Error::InvalidToken(auth_header.to_string())).into()
+        ))
+    }
+}
+
+/// Calls the service if the limits of the key aren't exceeded, the state of the requests
+/// quota is sent in the `X-RateLimit-Limit` and `X-RateLimit-Remaining` headers.
+fn call_rate_limited<S, B>(
+    svc: &mut S,
+    req: ServiceRequest,
+    limit: Result<Option<Allowance>, Exceeded>,
+) -> Pin<Box<dyn Future<Output = Result<ServiceResponse<B>, actix_web::Error>>>>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    match limit {
+        Ok(allowance) => Box::pin(svc.call(req).map_ok(move |mut res| {
+            if let Some(allowance) = allowance {
+                insert_allowance_headers(res.headers_mut(), allowance);
+            }
+            res
+        })),
+        Err(exceeded) => {
+            let mut res = req.error_response(ResponseError::from(Error::TooManyRequests(exceeded.message)));
+            let retry_after = exceeded.retry_after.as_secs() + u64::from(exceeded.retry_after.subsec_nanos() > 0);
+            res.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after));
+            if let Some(allowance) = exceeded.allowance {
+                insert_allowance_headers(res.headers_mut(), allowance);
+            }
+            Box::pin(ok(res))
+        }
+    }
+}
+
+fn insert_allowance_headers(headers: &mut HeaderMap, allowance: Allowance) {
+    headers.insert(HeaderName::from_static("x-ratelimit-limit"), HeaderValue::from(allowance.limit));
+    headers.insert(HeaderName::from_static("x-ratelimit-remaining"), HeaderValue::from(allowance.remaining));
+}
+
+/// Consumes the quotas of the key. The indexing quota is consumed up front by the payloads sent
+/// with a content length, the other payloads consume it as they are received and are cut short
+/// once it is exceeded.
+fn check_rate_limit(
+    data: &Data,
+    req: &mut ServiceRequest,
+    key: &ApiKey,
+    action: Action,
+) -> Result<Option<Allowance>, Exceeded> {
+    let content_length = req.headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse().ok());
+
+    let limit = data.rate_limiter.check(key, action, content_length.unwrap_or(0))?;
+
+    if content_length.is_none() && data.rate_limiter.limits_indexing_bytes(key, action) {
+        let rate_limiter = data.rate_limiter.clone();
+        let key = key.clone();
+        let payload = req.take_payload().map(move |chunk| {
+            let chunk = chunk?;
+            rate_limiter.take_indexing_bytes(&key, chunk.len() as u64)
+                .map_err(|exceeded| PayloadError::Io(io::Error::new(io::ErrorKind::Other, exceeded)))?;
+            Ok(chunk)
+        });
+        req.set_payload(Payload::Stream(Box::pin(payload)));
+    }
+
+    Ok(limit)
+}
+
+/// Returns the first key, mapped to one of the names of the client certificate, allowing the action.
+fn client_certificate_key(
+    data: &Data,
+    identity: &ClientIdentity,
+    acl: &Authentication,
+    index_uid: Option<&str>,
+) -> Option<ApiKey> {
+    identity.names.iter()
+        .filter_map(|name| data.client_certificate_keys.get(name))
+        .filter_map(|key_uid| data.keys.find_by_uid(key_uid))
+        .find(|key| acl.allows(key, index_uid))
+}
+
+/// Whether the request can access the index, only the requests authenticated
+/// with a scoped key are restricted to the indexes of its [`IndexScope`].
+pub fn can_access_index(req: &HttpRequest, index_uid: &str) -> bool {
+    req.extensions().get::<IndexScope>().map_or(true, |scope| scope.allows(index_uid))
+}
+
+/// Returns an error when the request can't access the index, see [`can_access_index`].
+pub fn check_index_access(req: &HttpRequest, index_uid: &str) -> Result<(), Error> {
+    if can_access_index(req, index_uid) {
+        Ok(())
+    } else {
+        Err(Error::InvalidToken(format!("the key can't access the index {}", index_uid)))
+    }
+}
+
+/// Returns the search rules of the index if the tenant token is valid, is signed with
+/// a key allowed to search the index and is used for a search on an index it covers.
+fn tenant_token_rules(
+    data: &Data,
+    api_keys: &ApiKeys,
+    token: &str,
+    acl: &Authentication,
+    index_uid: Option<&str>,
+) -> Option<IndexSearchRules> {
+    let index_uid = match (acl, index_uid) {
+        (Authentication::Action(Action::Search), Some(index_uid)) => index_uid,
+        _ => return None,
+    };
+
+    let claims = tenant_token::decode_unverified(token).ok()?;
+    let rules = claims.search_rules.index_rules(index_uid)?;
+    let prefix = &claims.api_key_prefix;
+    if prefix.len() != API_KEY_PREFIX_LENGTH {
+        return None;
+    }
+
+    let signed_by_default_key = api_keys.private.iter()
+        .chain(&api_keys.public)
+        .any(|key| key.starts_with(prefix.as_str()) && tenant_token::verify(token, key));
+
+    // only the current key is derived, the tokens signed with a rotated key are invalid
+    let signed_by_scoped_key = || data.keys.all().iter().any(|key| {
+        key.hashed.prefix == *prefix
+            && key.allows(Action::Search, Some(index_uid))
+            && tenant_token::verify(token, &derive_key(api_keys.master.as_deref(), &key.uid, key.generation))
+    });
+
+    if signed_by_default_key || signed_by_scoped_key() {
+        Some(rules)
+    } else {
+        None
     }
 }
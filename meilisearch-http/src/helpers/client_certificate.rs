@@ -0,0 +1,177 @@
+use std::any::Any;
+
+use actix_rt::net::TcpStream;
+use actix_tls::rustls::{Session, TlsStream};
+
+/// The names of the certificate a client authenticated with: the common name
+/// of its subject followed by its DNS, email and URI alternative names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    pub names: Vec<String>,
+}
+
+/// Extracts the identity of the client from a TLS connection, if it presented a certificate.
+/// The certificate has already been verified against the client authentication roots.
+pub fn client_identity(connection: &dyn Any) -> Option<ClientIdentity> {
+    let stream = connection.downcast_ref::<TlsStream<TcpStream>>()?;
+    let (_, session) = stream.get_ref();
+    let certificates = session.get_peer_certificates()?;
+    let names = certificate_names(&certificates.first()?.0)?;
+    Some(ClientIdentity { names })
+}
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const OID: u8 = 0x06;
+const BOOLEAN: u8 = 0x01;
+const OCTET_STRING: u8 = 0x04;
+const VERSION: u8 = 0xa0;
+const EXTENSIONS: u8 = 0xa3;
+
+const COMMON_NAME_OID: &[u8] = &[0x55, 0x04, 0x03];
+const SUBJECT_ALT_NAME_OID: &[u8] = &[0x55, 0x1d, 0x11];
+
+const RFC822_NAME: u8 = 0x81;
+const DNS_NAME: u8 = 0x82;
+const URI: u8 = 0x86;
+
+/// Reads a DER encoded element and returns its tag, its content and the remaining bytes.
+fn read_tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, data) = data.split_first()?;
+    let (&first, data) = data.split_first()?;
+
+    let (len, data) = if first < 0x80 {
+        (first as usize, data)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || data.len() < count {
+            return None;
+        }
+        let len = data[..count].iter().fold(0usize, |len, b| len << 8 | *b as usize);
+        (len, &data[count..])
+    };
+
+    if data.len() < len {
+        return None;
+    }
+    Some((tag, &data[..len], &data[len..]))
+}
+
+/// Iterates over the elements of a DER encoded sequence or set content.
+fn elements(mut data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    std::iter::from_fn(move || {
+        let (tag, content, rest) = read_tlv(data)?;
+        data = rest;
+        Some((tag, content))
+    })
+}
+
+/// Returns the common name and the alternative names of a DER encoded X.509 certificate.
+pub fn certificate_names(der: &[u8]) -> Option<Vec<String>> {
+    let (tag, certificate, _) = read_tlv(der)?;
+    if tag != SEQUENCE {
+        return None;
+    }
+    let (tag, tbs, _) = read_tlv(certificate)?;
+    if tag != SEQUENCE {
+        return None;
+    }
+
+    let mut fields = elements(tbs).peekable();
+    if fields.peek().map(|(tag, _)| *tag) == Some(VERSION) {
+        fields.next();
+    }
+
+    // serial number, signature algorithm, issuer, validity and then the subject
+    let (tag, subject) = fields.nth(4)?;
+    if tag != SEQUENCE {
+        return None;
+    }
+
+    let mut names = Vec::new();
+    for (_, set) in elements(subject).filter(|(tag, _)| *tag == SET) {
+        for (_, attribute) in elements(set).filter(|(tag, _)| *tag == SEQUENCE) {
+            let mut parts = elements(attribute);
+            if let (Some((OID, COMMON_NAME_OID)), Some((_, value))) = (parts.next(), parts.next()) {
+                names.push(String::from_utf8_lossy(value).into_owned());
+            }
+        }
+    }
+
+    let extensions = fields.find(|(tag, _)| *tag == EXTENSIONS).and_then(|(_, content)| read_tlv(content));
+    if let Some((SEQUENCE, extensions, _)) = extensions {
+        for (_, extension) in elements(extensions).filter(|(tag, _)| *tag == SEQUENCE) {
+            let mut parts = elements(extension).filter(|(tag, _)| *tag != BOOLEAN);
+            if let (Some((OID, SUBJECT_ALT_NAME_OID)), Some((OCTET_STRING, value))) = (parts.next(), parts.next()) {
+                if let Some((SEQUENCE, general_names, _)) = read_tlv(value) {
+                    for (tag, name) in elements(general_names) {
+                        if tag == RFC822_NAME || tag == DNS_NAME || tag == URI {
+                            names.push(String::from_utf8_lossy(name).into_owned());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Some(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut output = vec![tag];
+        if content.len() < 0x80 {
+            output.push(content.len() as u8);
+        } else {
+            output.push(0x82);
+            output.extend_from_slice(&(content.len() as u16).to_be_bytes());
+        }
+        output.extend_from_slice(content);
+        output
+    }
+
+    fn concat(parts: &[Vec<u8>]) -> Vec<u8> {
+        parts.concat()
+    }
+
+    #[test]
+    fn extract_certificate_names() {
+        let name = |cn: &str| tlv(SEQUENCE, &tlv(SET, &tlv(SEQUENCE, &concat(&[
+            tlv(OID, COMMON_NAME_OID),
+            tlv(0x0c, cn.as_bytes()),
+        ]))));
+
+        let san = tlv(SEQUENCE, &concat(&[
+            tlv(OID, SUBJECT_ALT_NAME_OID),
+            tlv(BOOLEAN, &[0xff]),
+            tlv(OCTET_STRING, &tlv(SEQUENCE, &concat(&[
+                tlv(DNS_NAME, b"search.internal"),
+                tlv(RFC822_NAME, b"ops@example.com"),
+            ]))),
+        ]));
+
+        let tbs = tlv(SEQUENCE, &concat(&[
+            tlv(VERSION, &tlv(0x02, &[2])),
+            tlv(0x02, &[1; 16]),
+            tlv(SEQUENCE, &tlv(OID, &[0x2a, 0x86, 0x48])),
+            name("ca"),
+            tlv(SEQUENCE, &[0; 200]),
+            name("search-service"),
+            tlv(SEQUENCE, &[0; 300]),
+            tlv(EXTENSIONS, &tlv(SEQUENCE, &san)),
+        ]));
+        let certificate = tlv(SEQUENCE, &concat(&[
+            tbs,
+            tlv(SEQUENCE, &tlv(OID, &[0x2a, 0x86, 0x48])),
+            tlv(0x03, &[0; 64]),
+        ]));
+
+        let names = certificate_names(&certificate).unwrap();
+        assert_eq!(names, vec!["search-service", "search.internal", "ops@example.com"]);
+
+        assert_eq!(certificate_names(&certificate[..100]), None);
+    }
+}
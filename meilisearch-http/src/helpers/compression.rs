@@ -2,6 +2,7 @@ use flate2::Compression;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use std::fs::{create_dir_all, File};
+use std::io::{Read, Write};
 use std::path::Path;
 use tar::{Builder, Archive};
 
@@ -9,17 +10,27 @@ use crate::error::Error;
 
 pub fn to_tar_gz(src: &Path, dest: &Path) -> Result<(), Error> {
     let f = File::create(dest)?;
-    let gz_encoder = GzEncoder::new(f, Compression::default());
+    write_tar_gz(src, f)?;
+    Ok(())
+}
+
+/// Writes the content of the `src` directory as a tar.gz archive, returns the writer once the archive is complete.
+pub fn write_tar_gz<W: Write>(src: &Path, writer: W) -> Result<W, Error> {
+    let gz_encoder = GzEncoder::new(writer, Compression::default());
     let mut tar_encoder = Builder::new(gz_encoder);
     tar_encoder.append_dir_all(".", src)?;
     let gz_encoder = tar_encoder.into_inner()?;
-    gz_encoder.finish()?;
-    Ok(())
+    Ok(gz_encoder.finish()?)
 }
 
 pub fn from_tar_gz(src: &Path, dest: &Path) -> Result<(), Error> {
     let f = File::open(src)?;
-    let gz = GzDecoder::new(f);
+    read_tar_gz(f, dest)
+}
+
+/// Unpacks the tar.gz archive read from the reader in the `dest` directory.
+pub fn read_tar_gz<R: Read>(reader: R, dest: &Path) -> Result<(), Error> {
+    let gz = GzDecoder::new(reader);
     let mut ar = Archive::new(gz);
     create_dir_all(dest)?;
     ar.unpack(dest)?;
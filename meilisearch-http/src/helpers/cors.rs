@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_cors::{Cors, CorsFactory};
+use actix_http::Error;
+use actix_service::{Service, Transform};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::ORIGIN;
+use futures::future::{ok, Either, Ready};
+
+use crate::error::{self, ResponseError};
+use crate::option::Opt;
+
+/// Whether any origin is allowed, either because no origin is configured or because of a `*` origin.
+fn allows_any_origin(opt: &Opt) -> bool {
+    let mut origins = opt.cors_allowed_origins.iter().chain(&opt.cors_search_origins).peekable();
+    origins.peek().is_none() || origins.any(|origin| origin == "*")
+}
+
+/// Refuses the options allowing the requests with credentials from any origin.
+pub fn check_cors(opt: &Opt) -> Result<(), String> {
+    if opt.cors_allow_credentials && allows_any_origin(opt) {
+        return Err("The cross-origin requests can't include credentials when any origin is allowed, \
+            MEILI_CORS_ALLOW_CREDENTIALS requires the allowed origins to be specified"
+            .to_string());
+    }
+    Ok(())
+}
+
+/// Builds the CORS middleware from the options, any origin is allowed when none is configured.
+/// Only the search origins are allowed when they are the only ones configured, the requests
+/// they send to the other routes are refused by [`SearchOnlyOrigins`].
+pub fn create_cors(opt: &Opt) -> CorsFactory {
+    let mut cors = Cors::new();
+
+    if allows_any_origin(opt) {
+        cors = cors.send_wildcard();
+    } else {
+        for origin in opt.cors_allowed_origins.iter().chain(&opt.cors_search_origins) {
+            cors = cors.allowed_origin(origin);
+        }
+    }
+
+    if !opt.cors_allowed_methods.is_empty() {
+        cors = cors.allowed_methods(opt.cors_allowed_methods.iter().map(String::as_str));
+    }
+
+    if opt.cors_allow_credentials {
+        cors = cors.supports_credentials();
+    }
+
+    cors.allowed_headers(opt.cors_allowed_headers.iter().map(String::as_str))
+        .max_age(opt.cors_max_age)
+        .finish()
+}
+
+/// Whether the path is the one of a search route, e.g. `/indexes/movies/search`.
+pub(crate) fn is_search_route(path: &str) -> bool {
+    let mut parts = path.trim_matches('/').split('/');
+    matches!(
+        (parts.next(), parts.next(), parts.next(), parts.next()),
+        (Some("indexes"), Some(_), Some("search"), None)
+    )
+}
+
+/// Refuses the requests, and their preflight requests, sent from the origins that are
+/// only allowed to call the search routes, to any other route.
+pub struct SearchOnlyOrigins {
+    origins: Rc<HashSet<String>>,
+}
+
+impl SearchOnlyOrigins {
+    pub fn new(origins: &[String]) -> SearchOnlyOrigins {
+        SearchOnlyOrigins { origins: Rc::new(origins.iter().cloned().collect()) }
+    }
+}
+
+impl<S, B> Transform<S> for SearchOnlyOrigins
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = SearchOnlyOriginsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(SearchOnlyOriginsMiddleware { service, origins: self.origins.clone() })
+    }
+}
+
+pub struct SearchOnlyOriginsMiddleware<S> {
+    service: S,
+    origins: Rc<HashSet<String>>,
+}
+
+impl<S, B> Service for SearchOnlyOriginsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let origin = req.headers().get(ORIGIN).and_then(|origin| origin.to_str().ok());
+        match origin {
+            Some(origin) if self.origins.contains(origin) && !is_search_route(req.path()) => {
+                let error = ResponseError::from(error::Error::OriginNotAllowed(origin.to_string()));
+                Either::Right(ok(req.error_response(error)))
+            }
+            _ => Either::Left(self.service.call(req)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_routes() {
+        assert!(is_search_route("/indexes/movies/search"));
+        assert!(is_search_route("/indexes/movies/search/"));
+        assert!(!is_search_route("/indexes/movies"));
+        assert!(!is_search_route("/indexes/movies/documents"));
+        assert!(!is_search_route("/indexes/movies/search/settings"));
+        assert!(!is_search_route("/keys"));
+    }
+
+    #[test]
+    fn credentials_require_the_origins() {
+        let mut opt = Opt { cors_allow_credentials: true, ..Opt::default() };
+        assert!(check_cors(&opt).is_err());
+
+        opt.cors_search_origins = vec!["https://search.example.com".to_string()];
+        assert!(check_cors(&opt).is_ok());
+
+        opt.cors_allowed_origins = vec!["*".to_string()];
+        assert!(check_cors(&opt).is_err());
+
+        opt.cors_allow_credentials = false;
+        assert!(check_cors(&opt).is_ok());
+    }
+
+    #[test]
+    fn search_origins_are_not_a_wildcard() {
+        let mut opt = Opt::default();
+        assert!(allows_any_origin(&opt));
+
+        opt.cors_search_origins = vec!["https://search.example.com".to_string()];
+        assert!(!allows_any_origin(&opt));
+    }
+}
@@ -0,0 +1,45 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use actix_http::body::SizedStream;
+use actix_web::http::header::CONTENT_DISPOSITION;
+use actix_web::{web, HttpResponse};
+use bytes::Bytes;
+
+use crate::error::{Error, ResponseError};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Streams the file as an attachment with the given name, the file is read on the blocking thread pool.
+pub fn file_response(path: &Path, name: &str) -> Result<HttpResponse, ResponseError> {
+    let file = File::open(path).map_err(|_| Error::not_found(format!("{} does not exist", name)))?;
+    open_file_response(file, name)
+}
+
+/// Streams the open file, rewound to its start, as an attachment with the given name.
+pub fn open_file_response(file: File, name: &str) -> Result<HttpResponse, ResponseError> {
+    let length = file.metadata().map_err(Error::from)?.len();
+
+    let stream = futures::stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let result = web::block(move || {
+            let mut chunk = vec![0; CHUNK_SIZE];
+            let read = file.read(&mut chunk)?;
+            chunk.truncate(read);
+            Ok::<_, std::io::Error>((file, chunk))
+        })
+        .await;
+
+        match result {
+            Ok((_, chunk)) if chunk.is_empty() => None,
+            Ok((file, chunk)) => Some((Ok(Bytes::from(chunk)), Some(file))),
+            Err(e) => Some((Err(actix_http::Error::from(ResponseError::from(Error::Internal(e.to_string())))), None)),
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/gzip")
+        .header(CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", name))
+        .body(SizedStream::new(length, Box::pin(stream))))
+}
@@ -0,0 +1,40 @@
+use sha2::{Digest, Sha256};
+
+/// Computes the HMAC-SHA256 of the message, as defined in the RFC 2104.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.input(block.iter().map(|b| b ^ 0x36).collect::<Vec<_>>());
+    inner.input(message);
+
+    let mut outer = Sha256::new();
+    outer.input(block.iter().map(|b| b ^ 0x5c).collect::<Vec<_>>());
+    outer.input(inner.result());
+
+    outer.result().to_vec()
+}
+
+/// Compares two byte strings in a time that only depends on their length.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_4231_test_case_2() {
+        let hmac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = hmac.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+}
@@ -0,0 +1,355 @@
+/// The content of a web page, as it is indexed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Page {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub lang: Option<String>,
+    pub canonical: Option<String>,
+    /// The h1, h2 and h3 headings of the content.
+    pub headings: Vec<String>,
+    pub content: String,
+    /// The targets of the links of the page, as written in the page.
+    pub links: Vec<String>,
+    /// The robots meta tag of the page asks not to index it.
+    pub noindex: bool,
+    /// The robots meta tag of the page asks not to follow its links.
+    pub nofollow: bool,
+}
+
+/// The elements whose content is not text.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "textarea"];
+/// The elements that don't contain the content of a page, wherever they are.
+const BOILERPLATE_ELEMENTS: &[&str] = &["nav", "aside", "form", "button", "select", "svg", "iframe"];
+/// The elements that don't contain the content of a page outside of its main content.
+const PAGE_BOILERPLATE_ELEMENTS: &[&str] = &["header", "footer"];
+const MAIN_ELEMENTS: &[&str] = &["main", "article"];
+const HEADING_ELEMENTS: &[&str] = &["h1", "h2", "h3"];
+
+#[derive(Default)]
+struct Extractor {
+    page: Page,
+    title: Option<String>,
+    body: String,
+    main: String,
+    heading: Option<String>,
+    boilerplate_depth: usize,
+    main_depth: usize,
+}
+
+impl Extractor {
+    fn open(&mut self, name: &str, attributes: &[(String, String)]) {
+        let attribute = |name: &str| attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+
+        match name {
+            "title" if self.page.title.is_none() => self.title = Some(String::new()),
+            "html" => self.page.lang = attribute("lang").map(str::to_string),
+            "meta" => {
+                let name = attribute("name").or_else(|| attribute("property")).unwrap_or("").to_lowercase();
+                let content = attribute("content").unwrap_or("");
+                match name.as_str() {
+                    "description" | "og:description" if self.page.description.is_none() => {
+                        self.page.description = Some(collapse_whitespace(content));
+                    }
+                    "robots" => {
+                        let content = content.to_lowercase();
+                        self.page.noindex |= content.contains("noindex") || content.contains("none");
+                        self.page.nofollow |= content.contains("nofollow") || content.contains("none");
+                    }
+                    _ => (),
+                }
+            }
+            "link" if attribute("rel").map_or(false, |rel| rel.eq_ignore_ascii_case("canonical")) => {
+                self.page.canonical = attribute("href").map(str::to_string);
+            }
+            "a" => {
+                let nofollow = attribute("rel").map_or(false, |rel| rel.to_lowercase().contains("nofollow"));
+                if let (Some(href), false) = (attribute("href"), nofollow) {
+                    self.page.links.push(href.to_string());
+                }
+            }
+            _ if BOILERPLATE_ELEMENTS.contains(&name) => self.boilerplate_depth += 1,
+            _ if PAGE_BOILERPLATE_ELEMENTS.contains(&name) && self.main_depth == 0 => self.boilerplate_depth += 1,
+            _ if MAIN_ELEMENTS.contains(&name) => self.main_depth += 1,
+            _ if HEADING_ELEMENTS.contains(&name) => self.heading = Some(String::new()),
+            _ => (),
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "title" => {
+                if let Some(title) = self.title.take() {
+                    self.page.title = Some(collapse_whitespace(&title)).filter(|title| !title.is_empty());
+                }
+            }
+            _ if BOILERPLATE_ELEMENTS.contains(&name) => self.boilerplate_depth = self.boilerplate_depth.saturating_sub(1),
+            _ if PAGE_BOILERPLATE_ELEMENTS.contains(&name) && self.main_depth == 0 => {
+                self.boilerplate_depth = self.boilerplate_depth.saturating_sub(1);
+            }
+            _ if MAIN_ELEMENTS.contains(&name) => self.main_depth = self.main_depth.saturating_sub(1),
+            _ if HEADING_ELEMENTS.contains(&name) => {
+                if let Some(heading) = self.heading.take() {
+                    let heading = collapse_whitespace(&heading);
+                    if !heading.is_empty() && self.boilerplate_depth == 0 {
+                        self.page.headings.push(heading);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let text = decode_entities(text);
+        if let Some(title) = &mut self.title {
+            title.push_str(&text);
+            return;
+        }
+        if self.boilerplate_depth > 0 {
+            return;
+        }
+
+        // the tags separate the words, the whitespaces are collapsed at the end
+        self.body.push(' ');
+        self.body.push_str(&text);
+        if self.main_depth > 0 {
+            self.main.push(' ');
+            self.main.push_str(&text);
+        }
+        if let Some(heading) = &mut self.heading {
+            heading.push(' ');
+            heading.push_str(&text);
+        }
+    }
+
+    fn finish(mut self) -> Page {
+        let main = collapse_whitespace(&self.main);
+        self.page.content = if main.is_empty() { collapse_whitespace(&self.body) } else { main };
+        self.page
+    }
+}
+
+/// Extracts the content of a page following the rules of the readability tools: the scripts,
+/// the navigation, the forms, and the headers and footers of the page are ignored, and the
+/// content of the `main` and `article` elements is preferred to the whole body.
+pub fn extract(html: &str) -> Page {
+    let mut extractor = Extractor::default();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+
+        let is_tag = rest.starts_with('<')
+            && rest[1..].chars().next().map_or(false, |c| c.is_ascii_alphabetic() || c == '/' || c == '!' || c == '?');
+        if !is_tag {
+            let end = rest[1..].find('<').map_or(rest.len(), |pos| pos + 1);
+            extractor.text(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let end = match tag_end(rest) {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+        if tag.starts_with('/') {
+            extractor.close(&tag_name(&tag[1..]));
+            continue;
+        }
+
+        let name = tag_name(tag);
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            // the content of the raw text elements is skipped up to their closing tag
+            let closing = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&closing) {
+                Some(pos) => rest[pos..].find('>').map_or("", |end| &rest[pos + end + 1..]),
+                None => "",
+            };
+            continue;
+        }
+
+        extractor.open(&name, &attributes(&tag[name.len()..]));
+        if tag.ends_with('/') {
+            extractor.close(&name);
+        }
+    }
+
+    extractor.finish()
+}
+
+/// The position of the end of the tag, the `>` in the quoted attribute values are skipped.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '>') => return Some(i),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            _ => (),
+        }
+    }
+    None
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("").to_ascii_lowercase()
+}
+
+fn attributes(s: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut chars = s.trim_end_matches('/').chars().peekable();
+
+    loop {
+        while chars.peek().map_or(false, |c| c.is_whitespace() || *c == '/') {
+            chars.next();
+        }
+        let mut name = String::new();
+        while let Some(c) = chars.peek().copied().filter(|c| !c.is_whitespace() && *c != '=') {
+            name.push(c);
+            chars.next();
+        }
+        if name.is_empty() {
+            return attributes;
+        }
+
+        while chars.peek().map_or(false, |c| c.is_whitespace()) {
+            chars.next();
+        }
+        let mut value = String::new();
+        if chars.peek() == Some(&'=') {
+            chars.next();
+            while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                chars.next();
+            }
+            match chars.peek().copied() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    chars.next();
+                    value.extend(chars.by_ref().take_while(|c| *c != quote));
+                }
+                _ => {
+                    while let Some(c) = chars.peek().copied().filter(|c| !c.is_whitespace()) {
+                        value.push(c);
+                        chars.next();
+                    }
+                }
+            }
+        }
+        attributes.push((name.to_ascii_lowercase(), decode_entities(&value)));
+    }
+}
+
+/// Decodes the character references, the named ones are limited to the most common.
+pub fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..].find(';').filter(|end| *end <= 10).map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                u32::from_str_radix(&entity[2..], 16).ok().and_then(std::char::from_u32)
+            }
+            _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        });
+
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_pages() {
+        let html = r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <title>Carol &amp; Therese</title>
+                <meta name="description" content="A love story in New York">
+                <link rel="canonical" href="https://example.com/movies/carol">
+                <style>body { color: red; }</style>
+                <script>if (a < b) { document.write("<p>hidden</p>"); }</script>
+            </head>
+            <body>
+                <header><a href="/">Home</a></header>
+                <nav><a href="/movies">Movies</a></nav>
+                <!-- <p>a comment</p> -->
+                <main>
+                    <article>
+                        <header><h1>Carol</h1></header>
+                        <p>Set in 1950s New York, a department-store clerk who dreams of a better life
+                        falls for an older, married woman &#8212; <a href="/people/1" rel="nofollow">Cate</a>.</p>
+                        <h2 class='cast'>Cast</h2>
+                        <p>1 &lt; 2 <a href="../movies/wonder-woman?ref=carol">Wonder Woman</a><br/></p>
+                    </article>
+                </main>
+                <footer>Copyright</footer>
+            </body>
+            </html>"#;
+
+        let page = extract(html);
+        assert_eq!(page.title.as_deref(), Some("Carol & Therese"));
+        assert_eq!(page.description.as_deref(), Some("A love story in New York"));
+        assert_eq!(page.lang.as_deref(), Some("en"));
+        assert_eq!(page.canonical.as_deref(), Some("https://example.com/movies/carol"));
+        assert_eq!(page.headings, vec!["Carol", "Cast"]);
+        assert_eq!(
+            page.content,
+            "Carol Set in 1950s New York, a department-store clerk who dreams of a better life \
+             falls for an older, married woman \u{2014} Cate . Cast 1 < 2 Wonder Woman",
+        );
+        assert_eq!(page.links, vec!["/", "/movies", "../movies/wonder-woman?ref=carol"]);
+        assert!(!page.noindex && !page.nofollow);
+    }
+
+    #[test]
+    fn extract_the_body_without_main_content() {
+        let page = extract(r#"<meta name=robots content="noindex, nofollow"><body><nav>Menu</nav><div>Hello <b>world</b></div></body>"#);
+        assert_eq!(page.content, "Hello world");
+        assert!(page.noindex && page.nofollow);
+        assert_eq!(page.title, None);
+    }
+
+    #[test]
+    fn decode_character_references() {
+        assert_eq!(decode_entities("a &amp; b &#x41;&#66; &unknown; &"), "a & b AB &unknown; &");
+    }
+}
@@ -0,0 +1,394 @@
+use std::env;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use actix_service::{Service, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, HttpMessage, ResponseError};
+use chrono::{SecondsFormat, Utc};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use log::{log, Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The target of the events describing the requests, see [`RequestEvent`].
+pub const REQUEST_TARGET: &str = "meilisearch_http::request";
+
+/// The target of the events describing the processed updates, see [`UpdateEvent`].
+pub const UPDATE_TARGET: &str = "meilisearch_http::update";
+
+/// The target of the events describing the slow searches, see [`SlowQuery`](crate::slow_query::SlowQuery).
+pub const SLOW_QUERY_TARGET: &str = "meilisearch_http::slow_query";
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+static LOG_FORMAT: OnceCell<LogFormat> = OnceCell::new();
+
+/// The logger and the filters it was built with, replaced when the filters are changed.
+static LOGGER: Lazy<RwLock<Option<(env_logger::Logger, String)>>> = Lazy::new(|| RwLock::new(None));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines.
+    Text,
+    /// One JSON object per line, the events carry their fields as attributes of the object.
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> LogFormat {
+        LogFormat::Text
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LogFormat, String> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("{:?} is not a log format, it must be text or json", s)),
+        }
+    }
+}
+
+/// Initializes the logger, the level of the logs is given by the `RUST_LOG` environment variable
+/// and can be changed afterward with [`set_log_filters`]. Does nothing if a logger is already set.
+pub fn init_logger(format: LogFormat) {
+    let filters = env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
+    let logger = build_logger(format, &filters);
+    let max_level = logger.filter();
+
+    if log::set_boxed_logger(Box::new(ReloadableLogger)).is_ok() {
+        *LOGGER.write().unwrap() = Some((logger, filters));
+        log::set_max_level(max_level);
+        let _ = LOG_FORMAT.set(format);
+    }
+}
+
+/// The filters of the logs, in the `RUST_LOG` syntax, `None` if the logs are not handled by
+/// the logger of MeiliSearch.
+pub fn log_filters() -> Option<String> {
+    LOGGER.read().unwrap().as_ref().map(|(_, filters)| filters.clone())
+}
+
+/// Replaces the filters of the logs, e.g. `info,meilisearch_core=debug`, without restarting.
+pub fn set_log_filters(filters: &str) -> Result<(), String> {
+    validate_filters(filters)?;
+
+    let mut current = LOGGER.write().unwrap();
+    if current.is_none() {
+        return Err("the logs are not handled by MeiliSearch, their level can't be changed".to_string());
+    }
+
+    let format = LOG_FORMAT.get().copied().unwrap_or_default();
+    let logger = build_logger(format, filters);
+    log::set_max_level(logger.filter());
+    *current = Some((logger, filters.to_string()));
+
+    Ok(())
+}
+
+/// env_logger ignores the invalid directives, they are rejected here instead.
+fn validate_filters(filters: &str) -> Result<(), String> {
+    // the optional regex that filters the messages is checked by env_logger
+    let directives = filters.splitn(2, '/').next().unwrap_or_default();
+    for directive in directives.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let mut parts = directive.splitn(2, '=');
+        let (target, level) = (parts.next().unwrap_or_default(), parts.next());
+        let valid = match level {
+            Some(level) => !target.is_empty() && level.parse::<LevelFilter>().is_ok(),
+            // a lone level or a target logged at every level
+            None => !target.is_empty(),
+        };
+        if !valid {
+            return Err(format!("{:?} is not a valid log directive", directive));
+        }
+    }
+    Ok(())
+}
+
+/// Forwards the logs to the current logger, for its filters to be replaceable.
+struct ReloadableLogger;
+
+impl Log for ReloadableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        match LOGGER.read().unwrap().as_ref() {
+            Some((logger, _)) => logger.enabled(metadata),
+            None => false,
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some((logger, _)) = LOGGER.read().unwrap().as_ref() {
+            logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some((logger, _)) = LOGGER.read().unwrap().as_ref() {
+            logger.flush();
+        }
+    }
+}
+
+fn build_logger(format: LogFormat, filters: &str) -> env_logger::Logger {
+    let mut builder = env_logger::Builder::new();
+    builder.parse_filters(filters);
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let mut object = Map::new();
+            object.insert("timestamp".into(), Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true).into());
+            object.insert("level".into(), record.level().to_string().into());
+            object.insert("target".into(), record.target().into());
+
+            // the events are logged as JSON objects, their fields are merged in the log entry
+            let message = record.args().to_string();
+            match serde_json::from_str::<Map<String, Value>>(&message) {
+                Ok(fields) if is_event_target(record.target()) => object.extend(fields),
+                _ => {
+                    object.insert("message".into(), message.into());
+                }
+            }
+
+            writeln!(buf, "{}", Value::Object(object))
+        });
+    }
+    builder.build()
+}
+
+fn is_event_target(target: &str) -> bool {
+    target == REQUEST_TARGET || target == UPDATE_TARGET || target == SLOW_QUERY_TARGET
+}
+
+/// Logs the event, as a JSON object when the logs are in the JSON format.
+pub fn log_event<E: Serialize + fmt::Display>(level: Level, target: &str, event: &E) {
+    match LOG_FORMAT.get() {
+        Some(LogFormat::Json) => match serde_json::to_string(event) {
+            Ok(event) => log!(target: target, level, "{}", event),
+            Err(_) => log!(target: target, level, "{}", event),
+        },
+        _ => log!(target: target, level, "{}", event),
+    }
+}
+
+/// The id of the request, received in the `X-Request-Id` header or generated, it is sent back
+/// in the `X-Request-Id` header of the response.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// The uid of the API key the request is authenticated with, or the name of the master,
+/// private or public key. Set by the authentication middleware.
+#[derive(Debug, Clone)]
+pub struct ApiKeyUid(pub String);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestEvent<'a> {
+    pub message: &'static str,
+    pub request_id: &'a str,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub status: u16,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_uid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_uid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_addr: Option<&'a str>,
+}
+
+impl fmt::Display for RequestEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} \"{} {}\" {} {:.3}ms request_id={}",
+            self.peer_addr.unwrap_or("-"),
+            self.method,
+            self.path,
+            self.status,
+            self.latency_ms,
+            self.request_id,
+        )?;
+        if let Some(index_uid) = self.index_uid {
+            write!(f, " index_uid={}", index_uid)?;
+        }
+        if let Some(api_key_uid) = self.api_key_uid {
+            write!(f, " api_key_uid={}", api_key_uid)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateEvent<'a> {
+    pub message: &'static str,
+    pub index_uid: &'a str,
+    pub update_id: u64,
+    #[serde(rename = "type")]
+    pub update_type: &'static str,
+    pub status: &'static str,
+    pub duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<&'a str>,
+}
+
+impl fmt::Display for UpdateEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "update {} of index {} {} in {:.3}ms type={}",
+            self.update_id, self.index_uid, self.status, self.duration_ms, self.update_type,
+        )?;
+        if let Some(error_code) = self.error_code {
+            write!(f, " error_code={}", error_code)?;
+        }
+        Ok(())
+    }
+}
+
+/// Logs each request once answered, with its id, its status, its latency, the index it targets
+/// and the API key it is authenticated with.
+pub struct RequestLogger;
+
+impl<S, B> Transform<S> for RequestLogger
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type InitError = ();
+    type Transform = RequestLoggerMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestLoggerMiddleware { service })
+    }
+}
+
+pub struct RequestLoggerMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service for RequestLoggerMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+
+        // the id given by a proxy is kept to correlate its logs with ours
+        let request_id = req.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= 128)
+            .map(ToString::to_string)
+            .unwrap_or_else(|| format!("{:032x}", rand::random::<u128>()));
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+        let peer_addr = req.connection_info().realip_remote_addr().map(ToString::to_string);
+
+        self.service.call(req).map(move |result| {
+            let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+            let mut event = RequestEvent {
+                message: "request",
+                request_id: &request_id,
+                method: &method,
+                path: &path,
+                status: 0,
+                latency_ms,
+                index_uid: None,
+                api_key_uid: None,
+                peer_addr: peer_addr.as_deref(),
+            };
+
+            match result {
+                Ok(mut res) => {
+                    if let Ok(value) = HeaderValue::from_str(&request_id) {
+                        res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+                    }
+                    let request = res.request().clone();
+                    let extensions = request.extensions();
+                    event.status = res.status().as_u16();
+                    event.index_uid = request.match_info().get("index_uid");
+                    event.api_key_uid = extensions.get::<ApiKeyUid>().map(|key| key.0.as_str());
+                    log_event(Level::Info, REQUEST_TARGET, &event);
+                    Ok(res)
+                }
+                Err(e) => {
+                    event.status = e.as_response_error().status_code().as_u16();
+                    log_event(Level::Info, REQUEST_TARGET, &event);
+                    Err(e)
+                }
+            }
+        })
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_formats() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("logfmt".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn log_filters_validation() {
+        assert!(validate_filters("info").is_ok());
+        assert!(validate_filters("info,meilisearch_core=trace,heed").is_ok());
+        assert!(validate_filters("debug/search").is_ok());
+        assert!(validate_filters("meilisearch_core=loud").is_err());
+        assert!(validate_filters("=debug").is_err());
+    }
+
+    #[test]
+    fn request_events() {
+        let event = RequestEvent {
+            message: "request",
+            request_id: "42",
+            method: "POST",
+            path: "/indexes/movies/search",
+            status: 200,
+            latency_ms: 1.5,
+            index_uid: Some("movies"),
+            api_key_uid: None,
+            peer_addr: None,
+        };
+        assert_eq!(event.to_string(), "- \"POST /indexes/movies/search\" 200 1.500ms request_id=42 index_uid=movies");
+
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["requestId"], "42");
+        assert_eq!(value["latencyMs"], 1.5);
+        assert!(value.get("apiKeyUid").is_none());
+    }
+}
@@ -1,15 +1,15 @@
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use indexmap::IndexMap;
 use log::error;
 use meilisearch_core::{Filter, MainReader};
-use meilisearch_core::facets::FacetFilter;
-use meilisearch_core::criterion::*;
-use meilisearch_core::settings::RankingRule;
-use meilisearch_core::{Highlight, Index, RankedMap};
+use meilisearch_core::facets::{FacetFilter, FacetKey};
+use meilisearch_core::criterion::{self, *};
+use meilisearch_core::settings::{FacetValuesOrder, FacetingSettings, RankingRule, DEFAULT_RANKING_RULES};
+use meilisearch_core::{DocumentId, Highlight, Index, RankedMap, SortProfile};
 use meilisearch_schema::{FieldId, Schema};
 use meilisearch_tokenizer::is_cjk;
 use serde::{Deserialize, Serialize};
@@ -18,6 +18,8 @@ use siphasher::sip::SipHasher;
 use slice_group_by::GroupBy;
 
 use crate::error::{Error, ResponseError};
+use crate::filter_cache::FilterCache;
+use crate::routes::experiment::ExperimentAssignment;
 
 pub trait IndexSearchExt {
     fn new_search(&self, query: Option<String>) -> SearchBuilder;
@@ -34,9 +36,18 @@ impl IndexSearchExt for Index {
             attributes_to_retrieve: None,
             attributes_to_highlight: None,
             filters: None,
+            tenant_filter: None,
             matches: false,
             facet_filters: None,
             facets: None,
+            disjunctive_facets: None,
+            facet_stats: None,
+            facet_ranges: None,
+            ranking_rules: None,
+            boost: None,
+            sort: None,
+            filter_cache: None,
+            profile: false,
         }
     }
 }
@@ -50,9 +61,18 @@ pub struct SearchBuilder<'a> {
     attributes_to_retrieve: Option<HashSet<String>>,
     attributes_to_highlight: Option<HashSet<String>>,
     filters: Option<String>,
+    tenant_filter: Option<String>,
     matches: bool,
     facet_filters: Option<FacetFilter>,
-    facets: Option<Vec<(FieldId, String)>>
+    facets: Option<Vec<(FieldId, String)>>,
+    disjunctive_facets: Option<Vec<(FieldId, String)>>,
+    facet_stats: Option<Vec<(FieldId, String)>>,
+    facet_ranges: Option<Vec<((FieldId, String), Vec<f64>)>>,
+    ranking_rules: Option<Vec<RankingRule>>,
+    boost: Option<Vec<((FieldId, String), f64)>>,
+    sort: Option<Vec<SortRule>>,
+    filter_cache: Option<(&'a FilterCache, String)>,
+    profile: bool,
 }
 
 impl<'a> SearchBuilder<'a> {
@@ -97,6 +117,13 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    /// The filter of the tenant token the search is made with. It is parsed apart from the
+    /// filters of the search and both must match, the filters can't change its meaning.
+    pub fn tenant_filter(&mut self, value: String) -> &SearchBuilder {
+        self.tenant_filter = Some(value);
+        self
+    }
+
     pub fn get_matches(&mut self) -> &SearchBuilder {
         self.matches = true;
         self
@@ -107,7 +134,59 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    /// Returns the number of matching documents for each value of these facets, ignoring the facet
+    /// filters on the facet itself, so that the other values of a selected facet keep their counts.
+    pub fn add_disjunctive_facets(&mut self, facets: Vec<(FieldId, String)>) -> &SearchBuilder {
+        self.disjunctive_facets = Some(facets);
+        self
+    }
+
+    /// Returns the minimum and maximum numeric values of these facets among the matching documents.
+    pub fn add_facet_stats(&mut self, facets: Vec<(FieldId, String)>) -> &SearchBuilder {
+        self.facet_stats = Some(facets);
+        self
+    }
+
+    /// Returns the number of matching documents in the buckets delimited by the given edges.
+    pub fn add_facet_ranges(&mut self, ranges: Vec<((FieldId, String), Vec<f64>)>) -> &SearchBuilder {
+        self.facet_ranges = Some(ranges);
+        self
+    }
+
+    /// Ranks the documents with these rules instead of the ranking rules of the index.
+    pub fn ranking_rules(&mut self, rules: Vec<RankingRule>) -> &SearchBuilder {
+        self.ranking_rules = Some(rules);
+        self
+    }
+
+    /// Multiplies the score of the documents having these facet values, the documents are
+    /// sorted by their score after the ranking rules of the index.
+    pub fn boost(&mut self, boost: Vec<((FieldId, String), f64)>) -> &SearchBuilder {
+        self.boost = Some(boost);
+        self
+    }
+
+    /// Sorts the documents with these rules before the ranking rules, which order the documents
+    /// the rules consider equal.
+    pub fn sort(&mut self, rules: Vec<SortRule>) -> &SearchBuilder {
+        self.sort = Some(rules);
+        self
+    }
+
+    /// Reuses the documents matching the filters of the previous searches made on this index.
+    pub fn filter_cache(&mut self, cache: &'a FilterCache, index_uid: &str) -> &SearchBuilder {
+        self.filter_cache = Some((cache, index_uid.to_string()));
+        self
+    }
+
+    /// Returns the time spent in each phase of the search along with the results.
+    pub fn profile(&mut self) -> &SearchBuilder {
+        self.profile = true;
+        self
+    }
+
     pub fn search(self, reader: &MainReader) -> Result<SearchResult, ResponseError> {
+        let before_preparation = Instant::now();
         let schema = self
             .index
             .main
@@ -115,6 +194,7 @@ impl<'a> SearchBuilder<'a> {
             .ok_or(Error::internal("missing schema"))?;
 
         let ranked_map = self.index.main.ranked_map(reader)?.unwrap_or_default();
+        let faceting = self.index.main.faceting(reader)?;
 
         // Change criteria
         let mut query_builder = match self.get_criteria(reader, &ranked_map, &schema)? {
@@ -122,20 +202,64 @@ impl<'a> SearchBuilder<'a> {
             None => self.index.query_builder(),
         };
 
-        if let Some(filter_expression) = &self.filters {
-            let filter = Filter::parse(filter_expression, &schema)?;
-            let index = &self.index;
-            query_builder.with_filter(move |id| {
-                let reader = &reader;
-                let filter = &filter;
-                match filter.test(reader, index, id) {
-                    Ok(res) => res,
-                    Err(e) => {
-                        log::warn!("unexpected error during filtering: {}", e);
-                        false
+        // the searches without query are not sorted by the criteria
+        let sort_comparators = self.sort_comparators(reader, &ranked_map, &schema)?;
+        if !sort_comparators.is_empty() {
+            query_builder.with_placeholder_sort(move |a, b| {
+                sort_comparators.iter().map(|compare| compare(a, b)).find(|o| *o != Ordering::Equal).unwrap_or(Ordering::Equal)
+            });
+        }
+
+        let tenant_filter = match &self.tenant_filter {
+            Some(filter_expression) => Some(Filter::parse(filter_expression, &schema)?),
+            None => None,
+        };
+        let filter = match &self.filters {
+            Some(filter_expression) => Some(Filter::parse(filter_expression, &schema)?),
+            None => None,
+        };
+        let filter = match (tenant_filter, filter) {
+            (Some(tenant_filter), Some(filter)) => Some(Filter::And(Box::new(tenant_filter), Box::new(filter))),
+            (tenant_filter, filter) => tenant_filter.or(filter),
+        };
+        let is_filtered = filter.is_some();
+        let index = &self.index;
+        let cached_documents = match (&self.filter_cache, &filter) {
+            // the documents of the filters relative to the current date are not kept, the filter
+            // is the combination of the tenant and search filters, either of them can be relative
+            (Some((cache, index_uid)), Some(filter)) if !filter.is_relative() => {
+                let watermark = index.main.updated_at(reader)?;
+                let filter_expression = format!("{:?} {:?}", self.tenant_filter, self.filters);
+                cache.get_or_compute(index_uid, &filter_expression, watermark, || {
+                    let mut documents = Vec::new();
+                    for id in index.main.internal_docids(reader)?.iter() {
+                        if filter.test(reader, index, *id)? {
+                            documents.push(*id);
+                        }
                     }
+                    Ok::<_, ResponseError>(documents.into_iter().collect())
+                })?
+            }
+            _ => None,
+        };
+        let matches_filter = move |id: DocumentId| match (&cached_documents, &filter) {
+            (Some(documents), _) => documents.contains(id),
+            (None, Some(filter)) => match filter.test(reader, index, id) {
+                Ok(res) => res,
+                Err(e) => {
+                    log::warn!("unexpected error during filtering: {}", e);
+                    false
                 }
-            });
+            },
+            (None, None) => true,
+        };
+
+        let rules = QueryRulesEffect::new(reader, self.index, self.query.as_deref().unwrap_or_default())?;
+        // the pinned documents are returned first, they must match the filters too
+        let mut pinned: Vec<DocumentId> = rules.pinned.iter().copied().filter(|id| matches_filter(*id)).collect();
+        if is_filtered || !rules.excluded.is_empty() {
+            let excluded = rules.excluded;
+            query_builder.with_filter(move |id| !excluded.contains(&id) && matches_filter(id));
         }
 
         if let Some(field) = self.index.main.distinct_attribute(reader)? {
@@ -153,10 +277,32 @@ impl<'a> SearchBuilder<'a> {
         }
 
         query_builder.set_facet_filter(self.facet_filters);
-        query_builder.set_facets(self.facets);
+        // the pinned documents must match the facet filters as well, they are counted once in the hits
+        if !pinned.is_empty() {
+            if let Some(facet_docids) = query_builder.facets_docids(reader)? {
+                pinned.retain(|id| facet_docids.binary_search(id).is_ok());
+            }
+            query_builder.set_pinned(pinned.clone());
+        }
+        // the stats and the ranges are computed from the counts of the facet values
+        let mut counted_facets: Vec<(FieldId, String)> = Vec::new();
+        let ranges_facets = self.facet_ranges.iter().flatten().map(|(facet, _)| facet);
+        for facet in self.facets.iter().chain(self.facet_stats.iter()).flatten().chain(ranges_facets) {
+            if !counted_facets.contains(facet) {
+                counted_facets.push(facet.clone());
+            }
+        }
+        if !counted_facets.is_empty() {
+            query_builder.set_facets(Some(counted_facets));
+        }
+        query_builder.set_disjunctive_facets(self.disjunctive_facets.clone());
 
+        let preparation = before_preparation.elapsed();
         let start = Instant::now();
-        let result = query_builder.query(reader, self.query.as_deref(), self.offset..(self.offset + self.limit));
+        // the pinned documents come before the ranked ones in the pages
+        let pinned_page = &pinned[self.offset.min(pinned.len())..(self.offset + self.limit).min(pinned.len())];
+        let ranked_range = self.offset.saturating_sub(pinned.len())..(self.offset + self.limit).saturating_sub(pinned.len());
+        let result = query_builder.query(reader, self.query.as_deref(), ranked_range);
         let search_result = result.map_err(Error::search_documents)?;
         let time_ms = start.elapsed().as_millis() as usize;
 
@@ -187,12 +333,15 @@ impl<'a> SearchBuilder<'a> {
             },
         }
 
+        let before_formatting = Instant::now();
         let mut hits = Vec::with_capacity(self.limit);
-        for doc in search_result.documents {
+        let pinned_documents = pinned_page.iter().map(|id| (*id, Vec::new()));
+        let ranked_documents = search_result.documents.iter().map(|doc| (doc.id, doc.highlights.clone()));
+        for (id, highlights) in pinned_documents.chain(ranked_documents) {
             let mut document: IndexMap<String, Value> = self
                 .index
-                .document(reader, Some(&all_attributes), doc.id)
-                .map_err(|e| Error::retrieve_document(doc.id.0, e))?
+                .document(reader, Some(&all_attributes), id)
+                .map_err(|e| Error::retrieve_document(id.0, e))?
                 .ok_or(Error::internal(
                     "Impossible to retrieve the document; Corrupted data",
                 ))?;
@@ -202,7 +351,7 @@ impl<'a> SearchBuilder<'a> {
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect();
 
-            let mut matches = doc.highlights.clone();
+            let mut matches = highlights;
 
             // Crops fields if needed
             if let Some(fields) = &self.attributes_to_crop {
@@ -238,6 +387,29 @@ impl<'a> SearchBuilder<'a> {
             hits.push(hit);
         }
 
+        let profile = if self.profile {
+            Some(SearchProfile::new(preparation, &search_result.profile, before_formatting.elapsed()))
+        } else {
+            None
+        };
+
+        let counts = search_result.facets.unwrap_or_default();
+        let facet_stats = self.facet_stats.map(|facets| {
+            facets.iter().filter_map(|(_, name)| Some((name.clone(), FacetStats::new(counts.get(name)?)?))).collect()
+        });
+        let facet_ranges = self.facet_ranges.map(|ranges| {
+            ranges.iter().map(|((_, name), edges)| (name.clone(), FacetRange::buckets(counts.get(name), edges))).collect()
+        });
+        let facets_distribution = self.facets.map(|facets| {
+            let distribution = facets.iter().map(|(_, name)| (name.clone(), counts.get(name).cloned().unwrap_or_default())).collect();
+            sort_facets_distribution(distribution, &faceting)
+        });
+        let disjunctive_counts = search_result.disjunctive_facets.unwrap_or_default();
+        let disjunctive_facets_distribution = self.disjunctive_facets.map(|facets| {
+            let distribution = facets.iter().map(|(_, name)| (name.clone(), disjunctive_counts.get(name).cloned().unwrap_or_default())).collect();
+            sort_facets_distribution(distribution, &faceting)
+        });
+
         let results = SearchResult {
             hits,
             offset: self.offset,
@@ -246,8 +418,14 @@ impl<'a> SearchBuilder<'a> {
             exhaustive_nb_hits: search_result.exhaustive_nb_hit,
             processing_time_ms: time_ms,
             query: self.query.unwrap_or_default(),
-            facets_distribution: search_result.facets,
+            facets_distribution,
+            disjunctive_facets_distribution,
+            facet_stats,
+            facet_ranges,
             exhaustive_facets_count: search_result.exhaustive_facets_count,
+            profile,
+            banners: rules.banners,
+            experiment: None,
         };
 
         Ok(results)
@@ -255,14 +433,33 @@ impl<'a> SearchBuilder<'a> {
 
     pub fn get_criteria(
         &self,
-        reader: &MainReader,
+        reader: &'a MainReader,
         ranked_map: &'a RankedMap,
         schema: &Schema,
     ) -> Result<Option<Criteria<'a>>, ResponseError> {
-        let ranking_rules = self.index.main.ranking_rules(reader)?;
+        let ranking_rules = match &self.ranking_rules {
+            Some(rules) => Some(rules.clone()),
+            None => self.index.main.ranking_rules(reader)?,
+        };
+        // the boost and the sort are applied along the default rules when the index has none
+        let ranking_rules = match ranking_rules {
+            None if self.boost.is_some() || self.sort.is_some() => Some(DEFAULT_RANKING_RULES.to_vec()),
+            rules => rules,
+        };
 
         if let Some(ranking_rules) = ranking_rules {
             let mut builder = CriteriaBuilder::with_capacity(7 + ranking_rules.len());
+            for rule in self.sort.iter().flatten() {
+                match rule {
+                    SortRule::GeoPoint { lat, lng, ascending: true } => {
+                        builder.push(GeoDistance::nearest_first(reader, self.index.geo_locations, (*lat, *lng)))
+                    }
+                    SortRule::GeoPoint { lat, lng, ascending: false } => {
+                        builder.push(GeoDistance::farthest_first(reader, self.index.geo_locations, (*lat, *lng)))
+                    }
+                    SortRule::Attribute { name, ascending } => builder.push(sort_by_attribute(ranked_map, schema, name, *ascending)?),
+                }
+            }
             for rule in ranking_rules {
                 match rule {
                     RankingRule::Typo => builder.push(Typo),
@@ -285,12 +482,81 @@ impl<'a> SearchBuilder<'a> {
                     }
                 }
             }
-            builder.push(DocumentId);
+            if let Some(boost) = &self.boost {
+                builder.push(Boost::new(self.boost_scores(reader, boost)?));
+            }
+            builder.push(criterion::DocumentId);
             return Ok(Some(builder.build()));
         }
 
         Ok(None)
     }
+
+    /// The comparisons of the documents by the sort rules, to sort the documents of the searches
+    /// without query.
+    fn sort_comparators<'s>(
+        &self,
+        reader: &'s MainReader,
+        ranked_map: &'s RankedMap,
+        schema: &Schema,
+    ) -> Result<Vec<Box<dyn Fn(DocumentId, DocumentId) -> Ordering + 's>>, ResponseError> {
+        let mut comparators: Vec<Box<dyn Fn(DocumentId, DocumentId) -> Ordering + 's>> = Vec::new();
+        for rule in self.sort.iter().flatten() {
+            match rule {
+                SortRule::GeoPoint { lat, lng, ascending } => {
+                    let (locations, point) = (self.index.geo_locations, (*lat, *lng));
+                    let criterion = if *ascending {
+                        GeoDistance::nearest_first(reader, locations, point)
+                    } else {
+                        GeoDistance::farthest_first(reader, locations, point)
+                    };
+                    comparators.push(Box::new(move |a, b| criterion.compare(a, b)));
+                }
+                SortRule::Attribute { name, ascending } => {
+                    let criterion = sort_by_attribute(ranked_map, schema, name, *ascending)?;
+                    comparators.push(Box::new(move |a, b| criterion.compare(a, b)));
+                }
+            }
+        }
+        Ok(comparators)
+    }
+
+    /// The product of the multipliers of the boosted facet values of each document.
+    fn boost_scores(
+        &self,
+        reader: &MainReader,
+        boost: &[((FieldId, String), f64)],
+    ) -> Result<HashMap<DocumentId, f64>, ResponseError> {
+        let mut scores = HashMap::new();
+        for ((field_id, value), multiplier) in boost {
+            let key = FacetKey::new(*field_id, value.clone());
+            if let Some((_, document_ids)) = self.index.facets.facet_document_ids(reader, &key)? {
+                for id in document_ids.iter() {
+                    *scores.entry(*id).or_insert(1.0) *= multiplier;
+                }
+            }
+        }
+        Ok(scores)
+    }
+}
+
+/// The criterion sorting the documents by an attribute, which must be used in an asc or desc ranking rule.
+fn sort_by_attribute<'r>(ranked_map: &'r RankedMap, schema: &Schema, name: &str, ascending: bool) -> Result<SortByAttr<'r>, ResponseError> {
+    let criterion = if ascending {
+        SortByAttr::lower_is_better(ranked_map, schema, name)
+    } else {
+        SortByAttr::higher_is_better(ranked_map, schema, name)
+    };
+    criterion.map_err(|e| Error::bad_parameter("sort", format!("{}: {}", name, e)).into())
+}
+
+/// A rule sorting the documents of a search before its ranking rules.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortRule {
+    /// Sorts the documents by the distance of their `_geo` location to a point.
+    GeoPoint { lat: f64, lng: f64, ascending: bool },
+    /// Sorts the documents by an attribute used in an asc or desc ranking rule.
+    Attribute { name: String, ascending: bool },
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -339,9 +605,191 @@ pub struct SearchResult {
     pub processing_time_ms: usize,
     pub query: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub facets_distribution: Option<HashMap<String, HashMap<String, usize>>>,
+    pub facets_distribution: Option<HashMap<String, IndexMap<String, usize>>>,
+    /// The counts of the values of the facets ignoring the facet filters on their own facet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disjunctive_facets_distribution: Option<HashMap<String, IndexMap<String, usize>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_stats: Option<HashMap<String, FacetStats>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_ranges: Option<HashMap<String, Vec<FacetRange>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exhaustive_facets_count: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<SearchProfile>,
+    /// The banners of the query rules matching the query.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub banners: Vec<Value>,
+    /// The experiment variant the search was made with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<ExperimentAssignment>,
+}
+
+/// The minimum and maximum numeric values of a facet among the matching documents, the values
+/// that are not numbers are ignored.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FacetStats {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl FacetStats {
+    fn new(counts: &HashMap<String, usize>) -> Option<FacetStats> {
+        let mut values = counts
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .filter_map(|(value, _)| value.parse::<f64>().ok());
+        let first = values.next()?;
+        Some(values.fold(FacetStats { min: first, max: first }, |stats, value| {
+            FacetStats { min: stats.min.min(value), max: stats.max.max(value) }
+        }))
+    }
+}
+
+/// A bucket of the numeric values of a facet, the last bucket includes its upper edge. A document
+/// is counted once for each of its values, in the bucket of the value.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FacetRange {
+    pub from: f64,
+    pub to: f64,
+    pub count: usize,
+}
+
+impl FacetRange {
+    fn buckets(counts: Option<&HashMap<String, usize>>, edges: &[f64]) -> Vec<FacetRange> {
+        let mut buckets: Vec<_> = edges.windows(2).map(|pair| FacetRange { from: pair[0], to: pair[1], count: 0 }).collect();
+        let values = counts.into_iter().flatten().filter_map(|(value, count)| Some((value.parse::<f64>().ok()?, *count)));
+        for (value, count) in values {
+            let last = buckets.len() - 1;
+            let bucket = buckets.iter().position(|b| b.from <= value && value < b.to)
+                .or_else(|| if value == buckets[last].to { Some(last) } else { None });
+            if let Some(bucket) = bucket {
+                buckets[bucket].count += count;
+            }
+        }
+        buckets
+    }
+}
+
+/// Orders the values of each facet as configured in the faceting settings of the index and keeps
+/// at most the configured maximum number of values.
+fn sort_facets_distribution(
+    facets: HashMap<String, HashMap<String, usize>>,
+    faceting: &FacetingSettings,
+) -> HashMap<String, IndexMap<String, usize>> {
+    facets
+        .into_iter()
+        .map(|(facet, values)| {
+            let mut values: Vec<_> = values.into_iter().collect();
+            match faceting.order(&facet) {
+                FacetValuesOrder::Alpha => values.sort_by(|(a, _), (b, _)| a.cmp(b)),
+                FacetValuesOrder::Count => values.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b))),
+            }
+            values.truncate(faceting.max_values_per_facet);
+            (facet, values.into_iter().collect())
+        })
+        .collect()
+}
+
+/// The changes the query rules of an index make to the results of a query.
+struct QueryRulesEffect {
+    pinned: Vec<DocumentId>,
+    /// The pinned and the hidden documents, removed from the ranked results.
+    excluded: HashSet<DocumentId>,
+    banners: Vec<Value>,
+}
+
+impl QueryRulesEffect {
+    fn new(reader: &MainReader, index: &Index, query: &str) -> Result<QueryRulesEffect, ResponseError> {
+        let mut effect = QueryRulesEffect { pinned: Vec::new(), excluded: HashSet::new(), banners: Vec::new() };
+        let mut hidden = HashSet::new();
+
+        for rule in index.main.query_rules(reader)? {
+            if !rule.condition.matches(query) {
+                continue;
+            }
+            for external_id in &rule.hidden {
+                if let Some(id) = index.main.external_to_internal_docid(reader, external_id)? {
+                    hidden.insert(id);
+                }
+            }
+            for external_id in &rule.pinned {
+                // the ids of the documents that don't exist are ignored
+                if let Some(id) = index.main.external_to_internal_docid(reader, external_id)? {
+                    if !effect.pinned.contains(&id) {
+                        effect.pinned.push(id);
+                    }
+                }
+            }
+            effect.banners.extend(rule.banner);
+        }
+
+        // a document pinned by one rule and hidden by another one is hidden
+        effect.pinned.retain(|id| !hidden.contains(id));
+        effect.excluded = hidden;
+        effect.excluded.extend(effect.pinned.iter().copied());
+
+        Ok(effect)
+    }
+}
+
+/// The time spent in each phase of a search, in milliseconds, and the number of documents
+/// each phase went through.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchProfile {
+    /// Reading the settings and parsing the filters.
+    pub preparation_ms: f64,
+    /// Tokenizing the query and building its query tree.
+    pub query_tree_ms: f64,
+    /// Fetching the postings lists of the query words and applying the facet filters.
+    pub candidates_retrieval_ms: f64,
+    pub facets_count_ms: f64,
+    /// Grouping the matches of the candidates by document.
+    pub candidates_building_ms: f64,
+    pub ranking_rules: Vec<RankingRuleProfile>,
+    /// Filtering, deduplicating and building the documents of the requested range.
+    pub documents_building_ms: f64,
+    /// Retrieving, cropping and highlighting the hits.
+    pub formatting_ms: f64,
+    pub postings_lists: usize,
+    pub candidates: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingRuleProfile {
+    pub name: String,
+    pub duration_ms: f64,
+    /// The documents sorted by the rule, it stops once the requested range is sorted.
+    pub sorted_documents: usize,
+    /// The number of buckets of equivalent documents the rule produced.
+    pub buckets: usize,
+}
+
+impl SearchProfile {
+    fn new(preparation: Duration, profile: &SortProfile, formatting: Duration) -> SearchProfile {
+        let as_ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let ranking_rules = profile.criteria.iter().map(|criterion| RankingRuleProfile {
+            name: criterion.name.clone(),
+            duration_ms: as_ms(criterion.duration),
+            sorted_documents: criterion.sorted_documents,
+            buckets: criterion.buckets,
+        });
+
+        SearchProfile {
+            preparation_ms: as_ms(preparation),
+            query_tree_ms: as_ms(profile.query_tree),
+            candidates_retrieval_ms: as_ms(profile.candidates_retrieval),
+            facets_count_ms: as_ms(profile.facets_count),
+            candidates_building_ms: as_ms(profile.candidates_building),
+            ranking_rules: ranking_rules.collect(),
+            documents_building_ms: as_ms(profile.documents_building),
+            formatting_ms: as_ms(formatting),
+            postings_lists: profile.postings_lists,
+            candidates: profile.candidates,
+        }
+    }
 }
 
 /// returns the start index and the length on the crop.
@@ -481,37 +929,89 @@ fn calculate_highlights(
 
     for (attribute, matches) in matches.iter() {
         if attributes_to_highlight.contains(attribute) {
-            if let Some(Value::String(value)) = document.get(attribute) {
-                let value: Vec<_> = value.chars().collect();
-                let mut highlighted_value = String::new();
-                let mut index = 0;
-
-                let longest_matches = matches
-                    .linear_group_by_key(|m| m.start)
-                    .map(|group| group.last().unwrap())
-                    .filter(move |m| m.start >= index);
-
-                for m in longest_matches {
-                    let before = value.get(index..m.start);
-                    let highlighted = value.get(m.start..(m.start + m.length));
-                    if let (Some(before), Some(highlighted)) = (before, highlighted) {
-                        highlighted_value.extend(before);
-                        highlighted_value.push_str("<em>");
-                        highlighted_value.extend(highlighted);
-                        highlighted_value.push_str("</em>");
-                        index = m.start + m.length;
-                    } else {
-                        error!("value: {:?}; index: {:?}, match: {:?}", value, index, m);
-                    }
-                }
-                highlighted_value.extend(value[index..].iter());
-                highlight_result.insert(attribute.to_string(), Value::String(highlighted_value));
-            };
+            if let Some(value) = document.get(attribute) {
+                let highlighted_value = highlight_value(value, matches, &mut 0);
+                highlight_result.insert(attribute.to_string(), highlighted_value);
+            }
         }
     }
     highlight_result
 }
 
+/// Highlights the strings of a value in place. The arrays and the objects are indexed as a single
+/// text in which the values are separated by `. ` and the keys precede the values, the positions
+/// of the matches are relative to this text.
+fn highlight_value(value: &Value, matches: &[MatchPosition], offset: &mut usize) -> Value {
+    const SEPARATOR_LEN: usize = 2;
+
+    match value {
+        Value::String(text) => {
+            let start = *offset;
+            let end = start + text.chars().count();
+            *offset = end;
+
+            let matches: Vec<_> = matches.iter()
+                .filter(|m| m.start >= start && m.start + m.length <= end)
+                .map(|m| MatchPosition { start: m.start - start, length: m.length })
+                .collect();
+            Value::String(highlight_text(text, &matches))
+        }
+        Value::Array(values) => {
+            let values = values.iter().map(|value| {
+                let value = highlight_value(value, matches, offset);
+                *offset += SEPARATOR_LEN;
+                value
+            });
+            Value::Array(values.collect())
+        }
+        Value::Object(object) => {
+            let object = object.iter().map(|(key, value)| {
+                *offset += key.chars().count() + SEPARATOR_LEN;
+                let value = highlight_value(value, matches, offset);
+                *offset += SEPARATOR_LEN;
+                (key.clone(), value)
+            });
+            Value::Object(object.collect())
+        }
+        Value::Number(number) => {
+            *offset += number.to_string().chars().count();
+            value.clone()
+        }
+        Value::Bool(boolean) => {
+            *offset += boolean.to_string().chars().count();
+            value.clone()
+        }
+        Value::Null => Value::Null,
+    }
+}
+
+fn highlight_text(text: &str, matches: &[MatchPosition]) -> String {
+    let value: Vec<_> = text.chars().collect();
+    let mut highlighted_value = String::new();
+    let mut index = 0;
+
+    let longest_matches = matches
+        .linear_group_by_key(|m| m.start)
+        .map(|group| group.last().unwrap())
+        .filter(move |m| m.start >= index);
+
+    for m in longest_matches {
+        let before = value.get(index..m.start);
+        let highlighted = value.get(m.start..(m.start + m.length));
+        if let (Some(before), Some(highlighted)) = (before, highlighted) {
+            highlighted_value.extend(before);
+            highlighted_value.push_str("<em>");
+            highlighted_value.extend(highlighted);
+            highlighted_value.push_str("</em>");
+            index = m.start + m.length;
+        } else {
+            error!("value: {:?}; index: {:?}, match: {:?}", value, index, m);
+        }
+    }
+    highlighted_value.extend(value[index..].iter());
+    highlighted_value
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -649,4 +1149,27 @@ mod tests {
 
         assert_eq!(result, result_expected);
     }
+
+    #[test]
+    fn highlight_nested_values() {
+        let data = r#"{
+            "tags": ["red fox", 12, "fox"],
+            "author": { "name": "fox mulder", "age": 40 }
+        }"#;
+
+        let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
+        let mut attributes_to_highlight = HashSet::new();
+        attributes_to_highlight.insert("tags".to_string());
+        attributes_to_highlight.insert("author".to_string());
+
+        // the values are indexed as "red fox. 12. fox. " and "name. fox mulder. age. 40. "
+        let mut matches = HashMap::new();
+        matches.insert("tags".to_string(), vec![MatchPosition { start: 4, length: 3 }, MatchPosition { start: 13, length: 3 }]);
+        matches.insert("author".to_string(), vec![MatchPosition { start: 6, length: 3 }]);
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight);
+
+        assert_eq!(result["tags"], serde_json::json!(["red <em>fox</em>", 12, "<em>fox</em>"]));
+        assert_eq!(result["author"], serde_json::json!({ "name": "<em>fox</em> mulder", "age": 40 }));
+    }
 }
@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufRead;
+
+use indexmap::IndexMap;
+use meilisearch_core::settings::{Settings, DEFAULT_RANKING_RULES};
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+use crate::helpers::import;
+
+type Document = IndexMap<String, Value>;
+
+/// The attribute in which the `_id` of the Elasticsearch documents is stored.
+pub const ELASTICSEARCH_ID: &str = "id";
+
+/// The attribute Algolia identifies its records with.
+pub const ALGOLIA_ID: &str = "objectID";
+
+/// The attributes Algolia adds to the records it returns, they are not part of the records.
+const ALGOLIA_RESPONSE_ATTRIBUTES: [&str; 3] = ["_highlightResult", "_snippetResult", "_rankingInfo"];
+
+fn invalid(message: impl std::fmt::Display) -> Error {
+    Error::import_failed(message)
+}
+
+/// Parses an Algolia export, either a JSON array of records or one record per line,
+/// and calls `f` with each of the records.
+pub fn algolia_documents(mut reader: impl BufRead, mut f: impl FnMut(Document) -> Result<(), Error>) -> Result<(), Error> {
+    let add = |mut record: Document| {
+        for attribute in &ALGOLIA_RESPONSE_ATTRIBUTES {
+            record.remove(*attribute);
+        }
+        f(record)
+    };
+
+    if import::starts_with_array(&mut reader)? {
+        import::json_documents(reader, add)
+    } else {
+        import::ndjson_documents(reader, add)
+    }
+}
+
+/// Parses an Elasticsearch bulk file, where each action is followed by its document, or an export
+/// of hits with one `{ "_id": ..., "_source": ... }` object per line. The `_id` of a document is
+/// stored in its `id` attribute when it doesn't already have one, the deletions are ignored.
+pub fn elasticsearch_documents(reader: impl BufRead, mut f: impl FnMut(Document) -> Result<(), Error>) -> Result<(), Error> {
+    // the action waiting for its document: whether it is an update, and the id of the document
+    let mut pending: Option<(bool, Option<Value>)> = None;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(Error::import_failed)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut object: Map<String, Value> = serde_json::from_str(&line)
+            .map_err(|e| invalid(format!("invalid JSON at line {}: {}", i + 1, e)))?;
+
+        if let Some((is_update, id)) = pending.take() {
+            let document = if is_update { object.remove("doc") } else { Some(Value::Object(object)) };
+            match document {
+                Some(Value::Object(document)) => f(with_id(document, id))?,
+                _ => return Err(invalid(format!("the update at line {} has no doc", i + 1))),
+            }
+            continue;
+        }
+
+        let action = ["index", "create", "update", "delete"].iter().find(|action| object.contains_key(**action));
+        match action {
+            Some(&"delete") => (),
+            Some(action) => {
+                let id = object[*action].get("_id").cloned();
+                pending = Some((*action == "update", id));
+            }
+            None => match object.remove("_source") {
+                Some(Value::Object(source)) => f(with_id(source, object.remove("_id")))?,
+                _ => f(object.into_iter().collect())?,
+            },
+        }
+    }
+
+    if pending.is_some() {
+        return Err(invalid("the last action of the bulk file has no document"));
+    }
+
+    Ok(())
+}
+
+fn with_id(source: Map<String, Value>, id: Option<Value>) -> Document {
+    let mut document = Document::new();
+    if let Some(id) = id {
+        if !source.contains_key(ELASTICSEARCH_ID) {
+            document.insert(ELASTICSEARCH_ID.to_string(), id);
+        }
+    }
+    document.extend(source);
+    document
+}
+
+/// Converts the settings of an Algolia index, or the configuration export of the dashboard which
+/// contains the settings and the synonyms.
+pub fn algolia_settings(export: &Value) -> Result<Settings, Error> {
+    let settings = export.get("settings").unwrap_or(export);
+    if !settings.is_object() {
+        return Err(invalid("the Algolia settings must be a JSON object"));
+    }
+
+    let mut result = Settings::default();
+
+    if let Some(attributes) = string_array(&settings["searchableAttributes"]) {
+        // `unordered(title)` is searched like `title`, `title,subtitle` have the same priority
+        let attributes = attributes
+            .iter()
+            .flat_map(|attribute| unwrap_modifier(attribute).split(','))
+            .map(|attribute| attribute.trim().to_string())
+            .filter(|attribute| !attribute.is_empty())
+            .collect();
+        result.searchable_attributes = Some(Some(attributes));
+    }
+
+    if let Some(attributes) = string_array(&settings["attributesForFaceting"]) {
+        let attributes = attributes.iter().map(|attribute| unwrap_modifier(attribute).to_string()).collect();
+        result.attributes_for_faceting = Some(Some(attributes));
+    }
+
+    // the custom ranking of Algolia breaks the ties left by its default criteria
+    if let Some(custom_ranking) = string_array(&settings["customRanking"]) {
+        let mut rules: Vec<String> = DEFAULT_RANKING_RULES.iter().map(ToString::to_string).collect();
+        rules.extend(custom_ranking);
+        result.ranking_rules = Some(Some(rules));
+    }
+
+    if let Some(attribute) = settings["attributeForDistinct"].as_str() {
+        result.distinct_attribute = Some(Some(attribute.to_string()));
+    }
+
+    if let Some(attributes) = string_array(&settings["attributesToRetrieve"]) {
+        if !attributes.iter().any(|attribute| attribute == "*") {
+            result.displayed_attributes = Some(Some(attributes.into_iter().collect()));
+        }
+    }
+
+    if let Some(synonyms) = export["synonyms"].as_array() {
+        let mut result_synonyms = BTreeMap::new();
+        for synonym in synonyms {
+            match synonym["type"].as_str() {
+                Some("synonym") => {
+                    let words = string_array(&synonym["synonyms"]).unwrap_or_default();
+                    add_equivalent_synonyms(&mut result_synonyms, &words);
+                }
+                Some("oneWaySynonym") => {
+                    if let Some(input) = synonym["input"].as_str() {
+                        let words = string_array(&synonym["synonyms"]).unwrap_or_default();
+                        add_synonyms(&mut result_synonyms, input, &words);
+                    }
+                }
+                // the alternative corrections and placeholders have no equivalent
+                _ => (),
+            }
+        }
+        result.synonyms = Some(Some(result_synonyms));
+    }
+
+    Ok(result)
+}
+
+/// Removes the modifier of an Algolia attribute, e.g. `filterOnly(genre)` or `unordered(title)`.
+fn unwrap_modifier(attribute: &str) -> &str {
+    match (attribute.find('('), attribute.ends_with(')')) {
+        (Some(start), true) => &attribute[start + 1..attribute.len() - 1],
+        _ => attribute,
+    }
+}
+
+/// Converts the mapping and the analysis settings of an Elasticsearch index, as returned by the
+/// `GET /<index>` route or as given to create the index. The `text` fields are the searchable
+/// attributes, the `keyword` fields the attributes for faceting, and the synonym and stop filters
+/// give the synonyms and stop words.
+pub fn elasticsearch_settings(export: &Value) -> Result<Settings, Error> {
+    // the `GET /<index>` route returns the index in an object keyed by its name
+    let index = match export.as_object() {
+        Some(object) if object.len() == 1 && !object.contains_key("mappings") && !object.contains_key("settings") => {
+            object.values().next().unwrap()
+        }
+        Some(_) => export,
+        None => return Err(invalid("the Elasticsearch mapping must be a JSON object")),
+    };
+
+    let mut result = Settings::default();
+
+    if let Some(properties) = index["mappings"]["properties"].as_object() {
+        let mut searchable = Vec::new();
+        let mut faceted = Vec::new();
+        for (name, property) in properties {
+            if property["index"] == Value::Bool(false) {
+                continue;
+            }
+            match property["type"].as_str() {
+                Some("text") | Some("search_as_you_type") => searchable.push(name.clone()),
+                Some("keyword") | Some("constant_keyword") => faceted.push(name.clone()),
+                _ => (),
+            }
+        }
+        if !searchable.is_empty() {
+            result.searchable_attributes = Some(Some(searchable));
+        }
+        if !faceted.is_empty() {
+            result.attributes_for_faceting = Some(Some(faceted));
+        }
+    }
+
+    let settings = &index["settings"];
+    let analysis = if settings["index"]["analysis"].is_object() { &settings["index"]["analysis"] } else { &settings["analysis"] };
+
+    let mut synonyms = BTreeMap::new();
+    let mut stop_words = BTreeSet::new();
+    if let Some(filters) = analysis["filter"].as_object() {
+        for filter in filters.values() {
+            match filter["type"].as_str() {
+                Some("synonym") | Some("synonym_graph") => {
+                    for rule in string_array(&filter["synonyms"]).unwrap_or_default() {
+                        add_solr_synonyms(&mut synonyms, &rule);
+                    }
+                }
+                Some("stop") => {
+                    // the predefined lists, e.g. `_english_`, have no equivalent
+                    stop_words.extend(string_array(&filter["stopwords"]).unwrap_or_default());
+                }
+                _ => (),
+            }
+        }
+    }
+    if !synonyms.is_empty() {
+        result.synonyms = Some(Some(synonyms));
+    }
+    if !stop_words.is_empty() {
+        result.stop_words = Some(Some(stop_words));
+    }
+
+    Ok(result)
+}
+
+/// Adds a synonym rule in the Solr format used by Elasticsearch, `a, b, c` for equivalent
+/// words or `a, b => c, d` for words replaced by others.
+fn add_solr_synonyms(synonyms: &mut BTreeMap<String, Vec<String>>, rule: &str) {
+    let split = |words: &str| -> Vec<String> {
+        words.split(',').map(|word| word.trim().to_string()).filter(|word| !word.is_empty()).collect()
+    };
+
+    match rule.find("=>") {
+        Some(pos) => {
+            let replacements = split(&rule[pos + 2..]);
+            for input in split(&rule[..pos]) {
+                add_synonyms(synonyms, &input, &replacements);
+            }
+        }
+        None => add_equivalent_synonyms(synonyms, &split(rule)),
+    }
+}
+
+fn add_equivalent_synonyms(synonyms: &mut BTreeMap<String, Vec<String>>, words: &[String]) {
+    for word in words {
+        let others: Vec<_> = words.iter().filter(|other| *other != word).cloned().collect();
+        add_synonyms(synonyms, word, &others);
+    }
+}
+
+fn add_synonyms(synonyms: &mut BTreeMap<String, Vec<String>>, input: &str, words: &[String]) {
+    let entry = synonyms.entry(input.to_string()).or_insert_with(Vec::new);
+    for word in words {
+        if word != input && !entry.contains(word) {
+            entry.push(word.clone());
+        }
+    }
+}
+
+fn string_array(value: &Value) -> Option<Vec<String>> {
+    value.as_array().map(|values| values.iter().filter_map(Value::as_str).map(ToString::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::helpers::import::ImportFormat;
+
+    #[test]
+    fn algolia_records() {
+        let export = r#"[{ "objectID": "1", "title": "Carol", "_highlightResult": {} }]"#;
+        let documents = import::read_documents(export.as_bytes(), ImportFormat::Algolia).unwrap();
+        assert_eq!(documents[0].keys().collect::<Vec<_>>(), vec!["objectID", "title"]);
+
+        let export = "{ \"objectID\": \"1\" }\n{ \"objectID\": \"2\" }\n";
+        assert_eq!(import::read_documents(export.as_bytes(), ImportFormat::Algolia).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn elasticsearch_bulk() {
+        let bulk = r#"{ "index": { "_index": "movies", "_id": "1" } }
+{ "title": "Carol" }
+{ "delete": { "_index": "movies", "_id": "2" } }
+{ "update": { "_index": "movies", "_id": "3" } }
+{ "doc": { "title": "Joy" } }
+{ "_index": "movies", "_id": "4", "_source": { "title": "Heat" } }
+"#;
+        let documents = import::read_documents(bulk.as_bytes(), ImportFormat::Elasticsearch).unwrap();
+        let ids: Vec<_> = documents.iter().map(|document| document["id"].clone()).collect();
+        assert_eq!(ids, vec![json!("1"), json!("3"), json!("4")]);
+        assert_eq!(documents[1]["title"], "Joy");
+
+        let bulk = r#"{ "index": { "_id": "1" } }"#;
+        assert!(import::read_documents(bulk.as_bytes(), ImportFormat::Elasticsearch).is_err());
+    }
+
+    #[test]
+    fn convert_algolia_settings() {
+        let export = json!({
+            "settings": {
+                "searchableAttributes": ["title,alternative_title", "unordered(overview)"],
+                "attributesForFaceting": ["filterOnly(genre)", "director"],
+                "customRanking": ["desc(popularity)"],
+                "attributeForDistinct": "movie_id",
+            },
+            "synonyms": [
+                { "objectID": "1", "type": "synonym", "synonyms": ["movie", "film"] },
+                { "objectID": "2", "type": "oneWaySynonym", "input": "sf", "synonyms": ["science fiction"] },
+            ],
+        });
+        let settings = algolia_settings(&export).unwrap();
+        assert_eq!(settings.searchable_attributes, Some(Some(vec!["title".into(), "alternative_title".into(), "overview".into()])));
+        assert_eq!(settings.attributes_for_faceting, Some(Some(vec!["genre".into(), "director".into()])));
+        assert_eq!(settings.ranking_rules.unwrap().unwrap().last().unwrap(), "desc(popularity)");
+        assert_eq!(settings.distinct_attribute, Some(Some("movie_id".into())));
+
+        let synonyms = settings.synonyms.unwrap().unwrap();
+        assert_eq!(synonyms["movie"], vec!["film".to_string()]);
+        assert_eq!(synonyms["sf"], vec!["science fiction".to_string()]);
+        assert!(!synonyms.contains_key("science fiction"));
+    }
+
+    #[test]
+    fn convert_elasticsearch_settings() {
+        let export = json!({
+            "movies": {
+                "mappings": { "properties": {
+                    "title": { "type": "text" },
+                    "genre": { "type": "keyword" },
+                    "poster": { "type": "text", "index": false },
+                    "release_date": { "type": "date" },
+                } },
+                "settings": { "index": { "analysis": { "filter": {
+                    "movie_synonyms": { "type": "synonym", "synonyms": ["movie, film", "sf => science fiction"] },
+                    "movie_stop": { "type": "stop", "stopwords": ["the", "a"] },
+                } } } },
+            },
+        });
+        let settings = elasticsearch_settings(&export).unwrap();
+        assert_eq!(settings.searchable_attributes, Some(Some(vec!["title".into()])));
+        assert_eq!(settings.attributes_for_faceting, Some(Some(vec!["genre".into()])));
+
+        let synonyms = settings.synonyms.unwrap().unwrap();
+        assert_eq!(synonyms["film"], vec!["movie".to_string()]);
+        assert_eq!(synonyms["sf"], vec!["science fiction".to_string()]);
+        assert_eq!(settings.stop_words.unwrap().unwrap().len(), 2);
+    }
+}
@@ -2,6 +2,21 @@ pub mod authentication;
 pub mod meilisearch;
 pub mod normalize_path;
 pub mod compression;
+pub mod import;
+pub mod migration;
+pub mod hmac;
+pub mod client_certificate;
+pub mod cors;
+pub mod download;
+pub mod logging;
+pub mod access_log;
+pub mod streaming;
+pub mod protobuf;
+pub mod xml;
+pub mod html;
+pub mod shutdown;
+pub mod payload_limit;
+pub mod write_guard;
 
-pub use authentication::Authentication;
+pub use authentication::{Action, Authentication};
 pub use normalize_path::NormalizePath;
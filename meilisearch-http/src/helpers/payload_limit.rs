@@ -0,0 +1,149 @@
+use std::task::{Context, Poll};
+
+use actix_http::error::PayloadError;
+use actix_http::{Error, Payload};
+use actix_service::{Service, Transform};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::CONTENT_LENGTH;
+use futures::future::{ok, Either, Ready};
+use futures::StreamExt;
+
+use crate::error::{self, ResponseError};
+use crate::helpers::cors::is_search_route;
+use crate::option::Opt;
+
+/// The default limit of the index archives, see [`PayloadLimits::restore`].
+const DEFAULT_RESTORE_LIMIT: usize = 10 * 1024 * 1024 * 1024;
+
+/// The maximum sizes of the request payloads, by class of routes.
+#[derive(Debug, Clone, Copy)]
+pub struct PayloadLimits {
+    pub default: usize,
+    pub documents: usize,
+    pub search: usize,
+    /// The limit of the index archives, which are not read by the extractors but written to disk.
+    pub restore: usize,
+}
+
+impl PayloadLimits {
+    pub fn from_opt(opt: &Opt) -> PayloadLimits {
+        let default = opt.http_payload_size_limit;
+        PayloadLimits {
+            default,
+            documents: opt.http_documents_payload_size_limit.unwrap_or(default),
+            search: opt.http_search_payload_size_limit.unwrap_or(default),
+            restore: opt.http_restore_payload_size_limit.unwrap_or(DEFAULT_RESTORE_LIMIT),
+        }
+    }
+
+    /// The largest limit of the routes read by the extractors, they are configured with it
+    /// and the routes are checked against their own limit by [`PayloadSizeLimit`].
+    pub fn max(&self) -> usize {
+        self.default.max(self.documents).max(self.search)
+    }
+
+    /// The limit of the route.
+    fn route_limit(&self, path: &str) -> usize {
+        let parts: Vec<_> = path.trim_matches('/').split('/').collect();
+        match parts.as_slice() {
+            ["indexes", _, "restore"] => self.restore,
+            ["indexes", _, "documents", ..] | ["meilisearch.v1.Meilisearch", "AddDocuments"] => self.documents,
+            ["es-compat", _, "_search"] | ["meilisearch.v1.Meilisearch", "Search"] => self.search,
+            _ if is_search_route(path) => self.search,
+            _ => self.default,
+        }
+    }
+}
+
+/// Refuses the payloads larger than the limit of their route class, the payloads without
+/// a content length are checked as they are received.
+pub struct PayloadSizeLimit {
+    limits: PayloadLimits,
+}
+
+impl PayloadSizeLimit {
+    pub fn new(limits: PayloadLimits) -> PayloadSizeLimit {
+        PayloadSizeLimit { limits }
+    }
+}
+
+impl<S, B> Transform<S> for PayloadSizeLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = PayloadSizeLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(PayloadSizeLimitMiddleware { service, limits: self.limits })
+    }
+}
+
+pub struct PayloadSizeLimitMiddleware<S> {
+    service: S,
+    limits: PayloadLimits,
+}
+
+impl<S, B> Service for PayloadSizeLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let limit = self.limits.route_limit(req.path());
+
+        let content_length = req.headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse::<usize>().ok());
+        if content_length.map_or(false, |length| length > limit) {
+            let error = ResponseError::from(error::Error::PayloadTooLarge);
+            return Either::Right(ok(req.error_response(error)));
+        }
+
+        let mut received = 0;
+        let payload = req.take_payload().map(move |chunk| {
+            let chunk = chunk?;
+            received += chunk.len();
+            if received > limit {
+                return Err(PayloadError::Overflow);
+            }
+            Ok(chunk)
+        });
+        req.set_payload(Payload::Stream(Box::pin(payload)));
+
+        Either::Left(self.service.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_limits() {
+        let limits = PayloadLimits { default: 10, documents: 100, search: 1, restore: 1000 };
+        assert_eq!(limits.max(), 100);
+        assert_eq!(limits.route_limit("/indexes/movies/documents"), 100);
+        assert_eq!(limits.route_limit("/indexes/movies/documents/delete-batch"), 100);
+        assert_eq!(limits.route_limit("/meilisearch.v1.Meilisearch/AddDocuments"), 100);
+        assert_eq!(limits.route_limit("/indexes/movies/search"), 1);
+        assert_eq!(limits.route_limit("/es-compat/movies/_search"), 1);
+        assert_eq!(limits.route_limit("/indexes/movies/settings"), 10);
+        assert_eq!(limits.route_limit("/indexes/movies/restore"), 1000);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::error::Error;
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LENGTH_DELIMITED: u8 = 2;
+const FIXED32: u8 = 5;
+
+/// The value of a field of a protobuf message, as encoded on the wire.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Field<'a> {
+    pub fn as_u64(self) -> Result<u64, Error> {
+        match self {
+            Field::Varint(value) => Ok(value),
+            _ => Err(Error::bad_request("a field was expected to be an integer")),
+        }
+    }
+
+    pub fn as_bool(self) -> Result<bool, Error> {
+        self.as_u64().map(|value| value != 0)
+    }
+
+    pub fn as_str(self) -> Result<&'a str, Error> {
+        match self {
+            Field::Bytes(bytes) => std::str::from_utf8(bytes).map_err(|_| Error::bad_request("a string field is not valid UTF-8")),
+            _ => Err(Error::bad_request("a field was expected to be a string")),
+        }
+    }
+}
+
+/// Reads the fields of a message in the order they are encoded, with their field number.
+/// The fields unknown to the reader are to be skipped, as required by the protobuf evolution rules.
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Decoder<'a> {
+        Decoder { buf }
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for (i, byte) in self.buf.iter().enumerate().take(10) {
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                self.buf = &self.buf[i + 1..];
+                return Ok(value);
+            }
+        }
+        Err(Error::bad_request("the message contains an invalid varint"))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.buf.len() < len {
+            return Err(Error::bad_request("the message is truncated"));
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    fn field(&mut self) -> Result<(u32, Field<'a>), Error> {
+        let key = self.varint()?;
+        let number = (key >> 3) as u32;
+        let field = match (key & 0x7) as u8 {
+            VARINT => Field::Varint(self.varint()?),
+            FIXED64 => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(self.take(8)?);
+                Field::Fixed64(u64::from_le_bytes(bytes))
+            }
+            LENGTH_DELIMITED => {
+                let len = self.varint()? as usize;
+                Field::Bytes(self.take(len)?)
+            }
+            FIXED32 => {
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(self.take(4)?);
+                Field::Fixed32(u32::from_le_bytes(bytes))
+            }
+            wire_type => return Err(Error::bad_request(format!("the wire type {} is not supported", wire_type))),
+        };
+        Ok((number, field))
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = Result<(u32, Field<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            self.buf = &[];
+        }
+        Some(field)
+    }
+}
+
+/// Writes the fields of a message, the fields set to their default value are omitted as in proto3.
+#[derive(Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn key(&mut self, number: u32, wire_type: u8) {
+        self.varint(u64::from(number) << 3 | u64::from(wire_type));
+    }
+
+    pub fn uint64(&mut self, number: u32, value: u64) -> &mut Self {
+        if value != 0 {
+            self.key(number, VARINT);
+            self.varint(value);
+        }
+        self
+    }
+
+    pub fn bool(&mut self, number: u32, value: bool) -> &mut Self {
+        self.uint64(number, value as u64)
+    }
+
+    pub fn double(&mut self, number: u32, value: f64) -> &mut Self {
+        if value != 0.0 {
+            self.key(number, FIXED64);
+            self.buf.extend_from_slice(&value.to_le_bytes());
+        }
+        self
+    }
+
+    pub fn string(&mut self, number: u32, value: &str) -> &mut Self {
+        if !value.is_empty() {
+            self.repeated_string(number, Some(value));
+        }
+        self
+    }
+
+    /// The values of a repeated field are all written, the empty ones included.
+    pub fn repeated_string<'a>(&mut self, number: u32, values: impl IntoIterator<Item = &'a str>) -> &mut Self {
+        for value in values {
+            self.key(number, LENGTH_DELIMITED);
+            self.varint(value.len() as u64);
+            self.buf.extend_from_slice(value.as_bytes());
+        }
+        self
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        let mut encoder = Encoder::default();
+        encoder
+            .uint64(1, 300)
+            .string(2, "movies")
+            .repeated_string(3, vec!["", "title"])
+            .bool(4, true)
+            .double(5, 1.5)
+            .uint64(6, 0)
+            .string(7, "");
+        let bytes = encoder.into_bytes();
+        assert_eq!(&bytes[..3], &[0x08, 0xac, 0x02]);
+
+        let fields: Vec<_> = Decoder::new(&bytes).collect::<Result<_, _>>().unwrap();
+        assert_eq!(fields, vec![
+            (1, Field::Varint(300)),
+            (2, Field::Bytes(b"movies")),
+            (3, Field::Bytes(b"")),
+            (3, Field::Bytes(b"title")),
+            (4, Field::Varint(1)),
+            (5, Field::Fixed64(1.5f64.to_bits())),
+        ]);
+    }
+
+    #[test]
+    fn invalid_messages() {
+        // a string announcing more bytes than the message contains
+        assert!(Decoder::new(&[0x12, 0x05, b'a']).any(|field| field.is_err()));
+        // a varint that never ends
+        assert!(Decoder::new(&[0x08, 0xff, 0xff]).any(|field| field.is_err()));
+        // the deprecated groups
+        assert!(Decoder::new(&[0x0b]).any(|field| field.is_err()));
+        assert!(Field::Varint(1).as_str().is_err());
+    }
+}
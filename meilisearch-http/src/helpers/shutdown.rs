@@ -0,0 +1,15 @@
+use std::sync::atomic::Ordering;
+
+use log::info;
+
+use crate::{systemd, Data};
+
+/// Starts shutting down the server: the writes are refused and the update loops stop once
+/// their current update is processed. The enqueued updates are kept for the next start.
+pub fn begin_shutdown(data: &Data) {
+    if !data.shutting_down.swap(true, Ordering::SeqCst) {
+        info!("Shutting down, the writes are refused from now on");
+        systemd::notify_or_warn("STOPPING=1");
+        data.db.stop_updates();
+    }
+}
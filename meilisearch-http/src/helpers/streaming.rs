@@ -0,0 +1,230 @@
+use std::io::Write;
+
+use actix_web::http::header::{ACCEPT, CONTENT_ENCODING};
+use actix_web::{HttpRequest, HttpResponse};
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::SinkExt;
+use log::{debug, error};
+use serde::Serialize;
+
+use crate::error::{Error, ResponseError};
+
+/// The number of bytes serialized before they are sent as a chunk.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// The number of chunks serialized ahead of the client, it bounds the memory used by a response.
+const CHANNEL_CAPACITY: usize = 4;
+
+type Chunk = Result<Bytes, actix_http::Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    JsonArray,
+    /// One JSON value per line.
+    Ndjson,
+}
+
+impl StreamFormat {
+    /// NDJSON when the client accepts it, a JSON array otherwise.
+    pub fn from_request(req: &HttpRequest) -> StreamFormat {
+        let accept = req.headers().get(ACCEPT).and_then(|value| value.to_str().ok()).unwrap_or("");
+        if accept.split(',').any(|mime| mime.trim().starts_with("application/x-ndjson")) {
+            StreamFormat::Ndjson
+        } else {
+            StreamFormat::JsonArray
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            StreamFormat::JsonArray => "application/json",
+            StreamFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// Serializes the values of a streamed response and sends them by chunks,
+/// it waits for the client to receive the previous chunks before sending more.
+pub struct StreamWriter {
+    format: StreamFormat,
+    sender: mpsc::Sender<Chunk>,
+    buffer: Vec<u8>,
+    /// Compresses the chunks when the response is gzipped.
+    encoder: Option<GzEncoder<Vec<u8>>>,
+    count: usize,
+    disconnected: bool,
+}
+
+impl StreamWriter {
+    fn new(format: StreamFormat, sender: mpsc::Sender<Chunk>, gzip: bool) -> StreamWriter {
+        let encoder = if gzip { Some(GzEncoder::new(Vec::new(), Compression::default())) } else { None };
+        StreamWriter { format, sender, buffer: Vec::new(), encoder, count: 0, disconnected: false }
+    }
+
+    /// Appends the value to the response, fails when the client disconnected.
+    pub fn write<T: Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        match self.format {
+            StreamFormat::JsonArray => self.buffer.push(if self.count == 0 { b'[' } else { b',' }),
+            StreamFormat::Ndjson => (),
+        }
+        serde_json::to_writer(&mut self.buffer, value)?;
+        if self.format == StreamFormat::Ndjson {
+            self.buffer.push(b'\n');
+        }
+        self.count += 1;
+
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        let chunk = match &mut self.encoder {
+            Some(encoder) => {
+                encoder.write_all(&self.buffer)?;
+                self.buffer.clear();
+                std::mem::take(encoder.get_mut())
+            }
+            None => std::mem::take(&mut self.buffer),
+        };
+        self.send(chunk)
+    }
+
+    fn send(&mut self, chunk: Vec<u8>) -> Result<(), Error> {
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(chunk);
+        if block_on(self.sender.send(Ok(chunk))).is_err() {
+            self.disconnected = true;
+            return Err(Error::internal("the client disconnected"));
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if self.format == StreamFormat::JsonArray {
+            self.buffer.extend_from_slice(if self.count == 0 { b"[]" } else { b"]" });
+        }
+        self.flush()?;
+        match self.encoder.take() {
+            Some(encoder) => {
+                let trailer = encoder.finish()?;
+                self.send(trailer)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Responds with the values written by `produce`, which runs on its own thread. The response is
+/// sent while the values are produced, an error raised by `produce` interrupts the response.
+pub fn streaming_response<F>(format: StreamFormat, produce: F) -> Result<HttpResponse, ResponseError>
+where
+    F: FnOnce(&mut StreamWriter) -> Result<(), Error> + Send + 'static,
+{
+    stream(format, false, produce)
+}
+
+/// Same as `streaming_response` but the response is a gzip file, it is meant to be saved as is
+/// and isn't decompressed by the clients, unlike the responses compressed on the fly.
+pub fn gzip_streaming_response<F>(format: StreamFormat, produce: F) -> Result<HttpResponse, ResponseError>
+where
+    F: FnOnce(&mut StreamWriter) -> Result<(), Error> + Send + 'static,
+{
+    stream(format, true, produce)
+}
+
+fn stream<F>(format: StreamFormat, gzip: bool, produce: F) -> Result<HttpResponse, ResponseError>
+where
+    F: FnOnce(&mut StreamWriter) -> Result<(), Error> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+
+    std::thread::Builder::new()
+        .name("response-stream".to_string())
+        .spawn(move || {
+            let mut writer = StreamWriter::new(format, sender, gzip);
+            match produce(&mut writer).and_then(|()| writer.finish()) {
+                Ok(()) => (),
+                Err(_) if writer.disconnected => debug!("the client disconnected before the end of the response"),
+                Err(e) => {
+                    error!("the streamed response has been interrupted: {}", e);
+                    // the client notices the truncated response as the connection is aborted
+                    let error = actix_http::Error::from(ResponseError::from(e));
+                    let _ = block_on(writer.sender.send(Err(error)));
+                }
+            }
+        })
+        .map_err(Error::from)?;
+
+    if gzip {
+        // the identity encoding prevents the compression middleware from compressing it again
+        Ok(HttpResponse::Ok()
+            .content_type("application/gzip")
+            .header(CONTENT_ENCODING, "identity")
+            .streaming(receiver))
+    } else {
+        Ok(HttpResponse::Ok().content_type(format.content_type()).streaming(receiver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use super::*;
+
+    fn collect_bytes(format: StreamFormat, gzip: bool, values: Vec<serde_json::Value>) -> Vec<Vec<u8>> {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let handle = std::thread::spawn(move || {
+            let mut writer = StreamWriter::new(format, sender, gzip);
+            values.iter().try_for_each(|value| writer.write(value)).and_then(|()| writer.finish()).unwrap();
+        });
+        let chunks: Vec<_> = block_on(receiver.collect());
+        handle.join().unwrap();
+        chunks.into_iter().map(|chunk| chunk.unwrap().to_vec()).collect()
+    }
+
+    fn collect(format: StreamFormat, values: Vec<serde_json::Value>) -> Vec<String> {
+        collect_bytes(format, false, values).into_iter().map(|chunk| String::from_utf8(chunk).unwrap()).collect()
+    }
+
+    #[test]
+    fn formats() {
+        let values = vec![json!({ "id": 1 }), json!({ "id": 2 })];
+        assert_eq!(collect(StreamFormat::JsonArray, values.clone()).concat(), r#"[{"id":1},{"id":2}]"#);
+        assert_eq!(collect(StreamFormat::Ndjson, values).concat(), "{\"id\":1}\n{\"id\":2}\n");
+        assert_eq!(collect(StreamFormat::JsonArray, Vec::new()).concat(), "[]");
+        assert_eq!(collect(StreamFormat::Ndjson, Vec::new()).concat(), "");
+    }
+
+    #[test]
+    fn large_responses_are_chunked() {
+        let values: Vec<_> = (0..10_000).map(|id| json!({ "id": id, "title": "a movie title" })).collect();
+        let chunks = collect(StreamFormat::JsonArray, values);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.len() < 2 * CHUNK_SIZE));
+
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&chunks.concat()).unwrap();
+        assert_eq!(parsed.len(), 10_000);
+    }
+
+    #[test]
+    fn gzipped_responses() {
+        use std::io::Read;
+
+        let values: Vec<_> = (0..10_000).map(|id| json!({ "id": id, "title": "a movie title" })).collect();
+        let compressed = collect_bytes(StreamFormat::Ndjson, true, values).concat();
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed.lines().count(), 10_000);
+        assert_eq!(decompressed.lines().next(), Some(r#"{"id":0,"title":"a movie title"}"#));
+    }
+}
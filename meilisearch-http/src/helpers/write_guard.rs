@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use actix_http::Error;
+use actix_service::{Service, Transform};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use futures::future::{ok, Either, Ready};
+
+use crate::capacity::CapacityMonitor;
+use crate::error::{self, ResponseError};
+use crate::helpers::cors::is_search_route;
+use crate::Data;
+
+/// Whether the request only reads the database, searches are sent with POST too.
+fn is_read_request(req: &ServiceRequest) -> bool {
+    matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) || is_search_route(req.path())
+}
+
+/// Refuses the requests that would modify the database once the server is shutting down or
+/// when the storage is almost full, the reads are still served.
+pub struct WriteGuard {
+    shutting_down: Arc<AtomicBool>,
+    capacity: Arc<CapacityMonitor>,
+}
+
+impl WriteGuard {
+    pub fn new(data: &Data) -> WriteGuard {
+        WriteGuard { shutting_down: data.shutting_down.clone(), capacity: data.capacity.clone() }
+    }
+}
+
+impl<S, B> Transform<S> for WriteGuard
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = WriteGuardMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(WriteGuardMiddleware {
+            service,
+            shutting_down: self.shutting_down.clone(),
+            capacity: self.capacity.clone(),
+        })
+    }
+}
+
+pub struct WriteGuardMiddleware<S> {
+    service: S,
+    shutting_down: Arc<AtomicBool>,
+    capacity: Arc<CapacityMonitor>,
+}
+
+impl<S, B> Service for WriteGuardMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if is_read_request(&req) {
+            return Either::Left(self.service.call(req));
+        }
+
+        let error = if self.shutting_down.load(Ordering::SeqCst) {
+            error::Error::ShuttingDown
+        } else if self.capacity.safety_mode() {
+            error::Error::InsufficientStorage
+        } else {
+            return Either::Left(self.service.call(req));
+        };
+        Either::Right(ok(req.error_response(ResponseError::from(error))))
+    }
+}
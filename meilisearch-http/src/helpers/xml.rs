@@ -0,0 +1,44 @@
+/// The content of the elements named `tag`, in the order they appear. The documents read with it,
+/// like the S3 listings and the sitemaps, are simple enough not to require an XML parser.
+pub fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        match rest.find(&close) {
+            Some(end) => {
+                elements.push(&rest[..end]);
+                rest = &rest[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    elements
+}
+
+/// Replaces the predefined entities of XML, the content of the CDATA sections is kept as is.
+pub fn unescape(s: &str) -> String {
+    let s = s.trim();
+    if s.starts_with("<![CDATA[") && s.ends_with("]]>") {
+        return s["<![CDATA[".len()..s.len() - "]]>".len()].to_string();
+    }
+    s.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_elements() {
+        let xml = "<urlset><url><loc>https://example.com/?a=1&amp;b=2</loc></url><url><loc><![CDATA[https://example.com/<b>]]></loc></url><url><loc>";
+        let locations: Vec<_> = elements(xml, "loc").into_iter().map(unescape).collect();
+        assert_eq!(locations, vec!["https://example.com/?a=1&b=2", "https://example.com/<b>"]);
+        assert_eq!(elements(xml, "url").len(), 2);
+    }
+}
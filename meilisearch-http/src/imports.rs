@@ -0,0 +1,99 @@
+use std::mem;
+
+use indexmap::IndexMap;
+use meilisearch_core::update::{BoxImportFn, DocumentsImport, ImportSink};
+use meilisearch_core::Index;
+use serde_json::Value;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::import::{self, ImportFormat};
+use crate::routes::document::find_primary_key;
+use crate::Data;
+
+type Document = IndexMap<String, Value>;
+
+/// The number of documents indexed at once while the files of an import are read.
+const IMPORT_BATCH_SIZE: usize = 10_000;
+
+/// The files to download and index.
+#[derive(Debug, Clone)]
+pub struct ImportRequest {
+    pub url: String,
+    pub format: ImportFormat,
+    pub authorization: Option<String>,
+    pub primary_key: Option<String>,
+    /// The settings to apply before the documents are added, in the format of the documents.
+    pub settings_url: Option<String>,
+}
+
+/// Enqueues the import as an update of the index and returns its id, the files
+/// are downloaded and indexed by the update loop of the index, see [`importer`].
+pub fn enqueue(data: &Data, index: &Index, request: ImportRequest) -> Result<u64, ResponseError> {
+    let import = DocumentsImport {
+        url: request.url,
+        format: request.format.name().to_string(),
+        authorization: request.authorization,
+        primary_key: request.primary_key,
+        settings_url: request.settings_url,
+    };
+    Ok(data.db.update_write(|writer| index.documents_import(writer, import))?)
+}
+
+/// The importer of the database: it downloads the settings and the documents of the imports,
+/// the files can't be larger than the payloads of the documents routes. The documents are
+/// given to the index by batches while the file is parsed.
+pub fn importer(data: Data) -> BoxImportFn {
+    Box::new(move |import: &DocumentsImport, sink: &mut dyn ImportSink| {
+        run_import(&data, import, sink).map_err(|e| e.to_string())
+    })
+}
+
+fn run_import(data: &Data, import: &DocumentsImport, sink: &mut dyn ImportSink) -> Result<(), Error> {
+    let format = ImportFormat::from_name(&import.format)
+        .ok_or_else(|| Error::import_failed(format!("the format {} is not supported", import.format)))?;
+
+    let max_size = data.payload_limits.documents as u64;
+    let authorization = import.authorization.as_deref();
+    let s3_buckets = data.config.lock().unwrap().import_s3_buckets.clone();
+
+    // the settings are applied first so that the documents are indexed with them
+    if let Some(settings_url) = &import.settings_url {
+        let settings = import::fetch_settings(settings_url, format, authorization, max_size, &s3_buckets)?;
+        let settings = settings.to_update().map_err(Error::bad_request)?;
+        sink.settings(settings).map_err(Error::import_failed)?;
+    }
+
+    let primary_key = import.primary_key.clone().or_else(|| format.primary_key().map(String::from));
+    let mut batches = Batches { sink, primary_key, documents: Vec::new() };
+    import::fetch_documents(&import.url, format, authorization, max_size, &s3_buckets, |document| batches.push(document))?;
+    batches.flush()
+}
+
+/// Gives the documents to the sink by batches, the primary key is infered from the
+/// first document when the import doesn't specify it.
+struct Batches<'a> {
+    sink: &'a mut dyn ImportSink,
+    primary_key: Option<String>,
+    documents: Vec<Document>,
+}
+
+impl Batches<'_> {
+    fn push(&mut self, document: Document) -> Result<(), Error> {
+        self.documents.push(document);
+        if self.documents.len() >= IMPORT_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.documents.is_empty() {
+            return Ok(());
+        }
+        let documents = mem::take(&mut self.documents);
+        if self.primary_key.is_none() {
+            self.primary_key = documents.first().and_then(find_primary_key);
+        }
+        self.sink.documents(self.primary_key.as_deref(), documents).map_err(Error::import_failed)
+    }
+}
@@ -0,0 +1,265 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use meilisearch_core::update::Update;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::error::Error;
+use crate::routes::index::create_index_sync;
+use crate::Data;
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// An update successfully applied to an index, as recorded in the journal.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub index_uid: String,
+    /// The primary key of the index when the update was applied, used to create the index when replaying.
+    pub primary_key: Option<String>,
+    pub processed_at: DateTime<Utc>,
+    pub update: Update,
+}
+
+#[derive(Deserialize)]
+struct EntrySeq {
+    seq: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalEntryRef<'a> {
+    seq: u64,
+    index_uid: &'a str,
+    primary_key: Option<&'a str>,
+    processed_at: DateTime<Utc>,
+    /// The update as it was stored, written as is.
+    update: &'a RawValue,
+}
+
+/// The point up to which a journal is replayed, the sequence number of an entry or a date.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ReplayTarget {
+    Seq(u64),
+    Date(DateTime<Utc>),
+}
+
+impl ReplayTarget {
+    fn includes(&self, entry: &JournalEntry) -> bool {
+        match self {
+            ReplayTarget::Seq(seq) => entry.seq <= *seq,
+            ReplayTarget::Date(date) => entry.processed_at <= *date,
+        }
+    }
+}
+
+impl FromStr for ReplayTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ReplayTarget, String> {
+        if let Ok(seq) = s.parse() {
+            return Ok(ReplayTarget::Seq(seq));
+        }
+        DateTime::parse_from_rfc3339(s)
+            .map(|date| ReplayTarget::Date(date.with_timezone(&Utc)))
+            .map_err(|_| format!("{:?} is neither a journal sequence number nor a RFC 3339 date", s))
+    }
+}
+
+/// Appends the updates applied to the indexes to the `journal.jsonl` file of the journal directory,
+/// one JSON entry per line, to be able to replay them on top of a snapshot.
+pub struct UpdateJournal {
+    dir: PathBuf,
+    /// The sequence number of the next entry and the journal file.
+    file: Mutex<(u64, File)>,
+}
+
+impl UpdateJournal {
+    pub fn open(dir: &Path) -> Result<UpdateJournal, Error> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(JOURNAL_FILE);
+        let file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+
+        let mut valid_len = 0;
+        let mut next_seq = 0;
+        let mut reader = BufReader::new(&file);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            // the complete entries always end with a newline
+            if read == 0 || line.last() != Some(&b'\n') {
+                break;
+            }
+            valid_len += read as u64;
+            if let Ok(entry) = serde_json::from_slice::<EntrySeq>(&line) {
+                next_seq = entry.seq + 1;
+            }
+        }
+
+        if valid_len < file.metadata()?.len() {
+            warn!("Removing the last entry of the journal, truncated by a crash while it was written");
+            file.set_len(valid_len)?;
+        }
+
+        Ok(UpdateJournal { dir: dir.to_path_buf(), file: Mutex::new((next_seq, file)) })
+    }
+
+    /// Appends an update, given serialized in JSON, it is borrowed and written without being parsed again.
+    pub fn append(&self, index_uid: &str, primary_key: Option<&str>, update: &[u8], processed_at: DateTime<Utc>) -> Result<(), Error> {
+        let update: &RawValue = serde_json::from_slice(update)?;
+        let mut guard = self.file.lock().unwrap();
+        let (next_seq, file) = &mut *guard;
+
+        let entry = JournalEntryRef { seq: *next_seq, index_uid, primary_key, processed_at, update };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()?;
+
+        *next_seq += 1;
+        Ok(())
+    }
+
+    /// Moves the entries after the target to a `discarded-<date>.jsonl` file, they are kept for
+    /// inspection but never replayed again, and returns the remaining entries.
+    fn discard_after(&self, target: ReplayTarget) -> Result<Vec<JournalEntry>, Error> {
+        let mut guard = self.file.lock().unwrap();
+        let path = self.dir.join(JOURNAL_FILE);
+
+        let (kept, discarded): (Vec<_>, Vec<_>) = read_entries(&path)?
+            .into_iter()
+            .partition(|entry| target.includes(entry));
+
+        if !discarded.is_empty() {
+            let date = Utc::now().format("%Y%m%d-%H%M%S%3f");
+            let discarded_path = self.dir.join(format!("discarded-{}.jsonl", date));
+            write_entries(&discarded_path, &discarded)?;
+
+            let tmp_path = self.dir.join(format!("{}.tmp", JOURNAL_FILE));
+            write_entries(&tmp_path, &kept)?;
+            fs::rename(&tmp_path, &path)?;
+
+            let file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+            *guard = (kept.last().map_or(0, |entry| entry.seq + 1), file);
+            info!("{} journal entries after the replay target moved to {:?}", discarded.len(), discarded_path);
+        }
+
+        Ok(kept)
+    }
+}
+
+fn read_entries(path: &Path) -> Result<Vec<JournalEntry>, Error> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if !line.is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+
+    Ok(entries)
+}
+
+fn write_entries(path: &Path, entries: &[JournalEntry]) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for entry in entries {
+        serde_json::to_writer(&mut writer, entry)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.into_inner().map_err(|e| Error::Internal(e.to_string()))?.sync_all()?;
+    Ok(())
+}
+
+/// Enqueues again the journaled updates applied after the last update of the database, up to the target,
+/// the database being restored from a snapshot. Replayed updates are applied again like any other update.
+/// The entries after the target are discarded from the journal. Returns the number of replayed updates.
+pub fn replay_journal(data: &Data, journal: &UpdateJournal, target: ReplayTarget) -> Result<usize, Error> {
+    let since = {
+        let reader = data.db.main_read_txn()?;
+        data.db.last_update(&reader)?
+    };
+
+    let mut replayed = 0;
+    for entry in journal.discard_after(target)? {
+        // the last update date of the database is set after the update is applied, an update
+        // applied at the same date is already in the snapshot but applying it again is harmless
+        if since.map_or(false, |since| entry.processed_at < since) {
+            continue;
+        }
+
+        let index = match data.db.open_index(&entry.index_uid) {
+            Some(index) => index,
+            None => {
+                let uid = entry.index_uid.clone();
+                create_index_sync(&data.db, uid.clone(), uid, entry.primary_key.clone())?;
+                data.db.open_index(&entry.index_uid)
+                    .ok_or_else(|| Error::Internal(format!("cannot create the index {}", entry.index_uid)))?
+            }
+        };
+
+        data.db.update_write::<_, _, Error>(|writer| Ok(index.replay_update(writer, entry.update)?))?;
+        replayed += 1;
+    }
+
+    Ok(replayed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_targets() {
+        assert_eq!("42".parse::<ReplayTarget>().unwrap(), ReplayTarget::Seq(42));
+        let date = DateTime::parse_from_rfc3339("2020-10-16T10:00:00+02:00").unwrap().with_timezone(&Utc);
+        assert_eq!("2020-10-16T10:00:00+02:00".parse::<ReplayTarget>().unwrap(), ReplayTarget::Date(date));
+        assert!("yesterday".parse::<ReplayTarget>().is_err());
+    }
+
+    #[test]
+    fn truncated_last_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(JOURNAL_FILE);
+        fs::write(&path, "{\"seq\":0,\"indexUid\":\"movies\",\"primaryKey\":null,\"processedAt\":\"2020-10-16T08:00:00Z\",\"update\":{\"data\":\"ClearAll\",\"enqueued_at\":\"2020-10-16T08:00:00Z\"}}\n{\"seq\":1,\"indexU").unwrap();
+
+        let journal = UpdateJournal::open(dir.path()).unwrap();
+        assert_eq!(read_entries(&path).unwrap().len(), 1);
+        assert_eq!(journal.file.lock().unwrap().0, 1);
+
+        let target = ReplayTarget::Date(Utc::now());
+        let kept = journal.discard_after(target).unwrap();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].index_uid, "movies");
+
+        let kept = journal.discard_after(ReplayTarget::Seq(0)).unwrap();
+        assert_eq!(kept.len(), 1);
+    }
+
+    #[test]
+    fn append_raw_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = UpdateJournal::open(dir.path()).unwrap();
+
+        let update = br#"{"data":{"DocumentsAddition":[{"id":1,"title":"Carol"}]},"enqueued_at":"2020-10-16T08:00:00Z"}"#;
+        journal.append("movies", Some("id"), update, Utc::now()).unwrap();
+        assert!(journal.append("movies", Some("id"), b"{\"data\"", Utc::now()).is_err());
+
+        let entries = read_entries(&dir.path().join(JOURNAL_FILE)).unwrap();
+        assert_eq!(entries.len(), 1);
+        let expected: Update = serde_json::from_slice(update).unwrap();
+        assert_eq!(serde_json::to_vec(&entries[0].update).unwrap(), serde_json::to_vec(&expected).unwrap());
+    }
+}
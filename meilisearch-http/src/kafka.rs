@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::routes::document::ensure_primary_key;
+use crate::routes::index::{is_valid_index_uid, open_or_create_index};
+use crate::Data;
+
+type Document = IndexMap<String, Value>;
+
+/// The next offset to consume of each partition of each topic.
+type Offsets = BTreeMap<String, BTreeMap<i32, u64>>;
+
+/// The name under which the offsets are stored in the database.
+const SOURCE: &str = "kafka";
+const CONTENT_TYPE: &str = "application/vnd.kafka.v2+json";
+const RECORDS_CONTENT_TYPE: &str = "application/vnd.kafka.json.v2+json";
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a fetch of the records waits for new records.
+const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct KafkaConfig {
+    /// The URL of the Kafka REST proxy.
+    pub rest_url: String,
+    pub topics: Vec<String>,
+    pub consumer_group: String,
+    pub batch_size: usize,
+    pub batch_timeout: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    topic: String,
+    key: Option<Value>,
+    value: Value,
+    partition: i32,
+    offset: u64,
+}
+
+/// The documents consumed since the last flush, grouped by index.
+#[derive(Default)]
+struct Batch {
+    documents: IndexMap<String, Vec<Document>>,
+    len: usize,
+    offsets: Offsets,
+    started_at: Option<Instant>,
+}
+
+impl Batch {
+    /// Adds the documents of the record to the batch, the record is skipped if it is invalid.
+    fn push(&mut self, record: Record) {
+        self.started_at.get_or_insert_with(Instant::now);
+        self.offsets.entry(record.topic.clone()).or_default().insert(record.partition, record.offset + 1);
+
+        match route(record.key.as_ref(), record.value) {
+            Ok((index_uid, documents)) => {
+                self.len += documents.len();
+                self.documents.entry(index_uid).or_default().extend(documents);
+            }
+            Err(e) => warn!(
+                "The Kafka record {} of the partition {} of {} is skipped: {}",
+                record.offset, record.partition, record.topic, e,
+            ),
+        }
+    }
+
+    fn is_full(&self, config: &KafkaConfig) -> bool {
+        self.len >= config.batch_size
+            || self.started_at.map_or(false, |started_at| started_at.elapsed() >= config.batch_timeout)
+    }
+}
+
+/// Reads the index uid from the key of the record and the documents from its value,
+/// either a document or an array of documents.
+fn route(key: Option<&Value>, value: Value) -> Result<(String, Vec<Document>), String> {
+    let index_uid = match key {
+        Some(Value::String(uid)) if is_valid_index_uid(uid) => uid.clone(),
+        Some(key) => return Err(format!("the key {} is not a valid index uid", key)),
+        None => return Err("the record has no key, it must be the uid of an index".to_string()),
+    };
+
+    let documents = match value {
+        Value::Array(values) => values.into_iter().map(serde_json::from_value).collect(),
+        value => serde_json::from_value(value).map(|document| vec![document]),
+    };
+
+    match documents {
+        Ok(documents) => Ok((index_uid, documents)),
+        Err(_) => Err("the value must be a document or an array of documents".to_string()),
+    }
+}
+
+/// Consumes the records of the Kafka topics through a Kafka REST proxy from a dedicated thread.
+/// The records are keyed by the uid of the index their documents are added to, the documents
+/// are enqueued by batches and the offsets reached are stored in the database once enqueued.
+pub fn spawn_consumer(data: Data, config: KafkaConfig) {
+    thread::spawn(move || loop {
+        if let Err(e) = consume(&data, &config) {
+            error!("The Kafka consumer stopped: {}, it is restarted in {:?}", e, RETRY_DELAY);
+        }
+        thread::sleep(RETRY_DELAY);
+    });
+}
+
+fn consume(data: &Data, config: &KafkaConfig) -> Result<(), Error> {
+    let mut offsets: Offsets = data.db.main_read(|reader| data.db.ingestion_offsets(reader, SOURCE))?.unwrap_or_default();
+    let consumer = Consumer::create(config)?;
+
+    let result = consumer.seek(config, &offsets).and_then(|()| {
+        info!("The Kafka consumer {} consumes the topics {:?}", consumer.base_uri, config.topics);
+
+        let mut batch = Batch::default();
+        loop {
+            for record in consumer.records()? {
+                batch.push(record);
+            }
+
+            if batch.is_full(config) {
+                let batch = std::mem::take(&mut batch);
+                flush(data, batch.documents)?;
+
+                for (topic, partitions) in batch.offsets {
+                    offsets.entry(topic).or_default().extend(partitions);
+                }
+                data.db.main_write(|writer| data.db.put_ingestion_offsets(writer, SOURCE, &offsets))?;
+            }
+        }
+    });
+
+    consumer.delete();
+    result
+}
+
+/// Enqueues an addition of the documents of each index, the indexes that don't exist are created.
+/// The offsets are stored after the updates are enqueued, the documents of a batch interrupted
+/// in between are consumed again, which replaces them by themselves.
+fn flush(data: &Data, documents: IndexMap<String, Vec<Document>>) -> Result<(), Error> {
+    for (index_uid, documents) in documents {
+        let index = open_or_create_index(data, &index_uid)?;
+
+        if let Err(e) = ensure_primary_key(data, &index, None, documents.first()) {
+            error!("{} documents consumed from Kafka are skipped, the primary key of the index {} is unknown: {}", documents.len(), index_uid, e);
+            continue;
+        }
+
+        let mut addition = index.documents_addition();
+        addition.extend(documents);
+        data.db.update_write(|writer| addition.finalize(writer))?;
+    }
+
+    Ok(())
+}
+
+/// A consumer instance of the Kafka REST proxy, the partitions of the topics
+/// are assigned to it so that it reads them from the offsets stored in the database.
+struct Consumer {
+    base_uri: String,
+}
+
+impl Consumer {
+    fn create(config: &KafkaConfig) -> Result<Consumer, Error> {
+        let url = format!("{}/consumers/{}", config.rest_url.trim_end_matches('/'), config.consumer_group);
+        let body = json!({
+            "name": format!("meilisearch-{:016x}", rand::random::<u64>()),
+            "format": "json",
+            "auto.offset.reset": "earliest",
+            "auto.commit.enable": "false",
+        });
+        let response = call(ureq::post(&url), Some(body))?;
+        let base_uri = response["base_uri"].as_str().ok_or_else(|| Error::internal("the Kafka REST proxy returned no consumer"))?;
+        Ok(Consumer { base_uri: base_uri.to_string() })
+    }
+
+    /// Assigns all the partitions of the topics to the consumer and moves it to the stored offsets,
+    /// the partitions without offset are read from their beginning.
+    fn seek(&self, config: &KafkaConfig, offsets: &Offsets) -> Result<(), Error> {
+        let mut partitions = Vec::new();
+        let mut positions = Vec::new();
+        for topic in &config.topics {
+            let url = format!("{}/topics/{}/partitions", config.rest_url.trim_end_matches('/'), topic);
+            let response = call(ureq::get(&url), None)?;
+            for partition in response.as_array().into_iter().flatten().filter_map(|p| p["partition"].as_i64()) {
+                partitions.push(json!({ "topic": topic, "partition": partition }));
+                if let Some(offset) = offsets.get(topic).and_then(|p| p.get(&(partition as i32))) {
+                    positions.push(json!({ "topic": topic, "partition": partition, "offset": offset }));
+                }
+            }
+        }
+
+        call(ureq::post(&format!("{}/assignments", self.base_uri)), Some(json!({ "partitions": partitions })))?;
+        if !positions.is_empty() {
+            call(ureq::post(&format!("{}/positions", self.base_uri)), Some(json!({ "offsets": positions })))?;
+        }
+        Ok(())
+    }
+
+    fn records(&self) -> Result<Vec<Record>, Error> {
+        let url = format!("{}/records?timeout={}", self.base_uri, POLL_TIMEOUT.as_millis());
+        let mut request = ureq::get(&url);
+        request.set("Accept", RECORDS_CONTENT_TYPE);
+        Ok(serde_json::from_value(call(request, None)?)?)
+    }
+
+    /// Deletes the consumer instance, the proxy would otherwise keep it until it expires.
+    fn delete(self) {
+        if let Err(e) = call(ureq::delete(&self.base_uri), None) {
+            warn!("The Kafka consumer {} could not be deleted: {}", self.base_uri, e);
+        }
+    }
+}
+
+fn call(mut request: ureq::Request, body: Option<Value>) -> Result<Value, Error> {
+    request
+        .timeout_connect(TIMEOUT.as_millis() as u64)
+        .timeout_read(TIMEOUT.as_millis() as u64);
+
+    let response = match body {
+        Some(body) => request.set("Content-Type", CONTENT_TYPE).send_json(body),
+        None => request.call(),
+    };
+    if let Some(err) = response.synthetic_error() {
+        return Err(Error::internal(format!("the Kafka REST proxy is unreachable: {}", err)));
+    }
+    if !response.ok() {
+        let status = response.status();
+        let body = response.into_string().unwrap_or_default();
+        return Err(Error::internal(format!("the Kafka REST proxy responded with status {}: {}", status, body)));
+    }
+    if response.status() == 204 {
+        return Ok(Value::Null);
+    }
+
+    Ok(response.into_json()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: Value, value: Value, offset: u64) -> Record {
+        Record { topic: "movies".to_string(), key: Some(key), value, partition: 0, offset }
+    }
+
+    #[test]
+    fn route_records() {
+        let (uid, documents) = route(Some(&json!("movies")), json!({ "id": 1 })).unwrap();
+        assert_eq!(uid, "movies");
+        assert_eq!(documents.len(), 1);
+
+        let (_, documents) = route(Some(&json!("movies")), json!([{ "id": 1 }, { "id": 2 }])).unwrap();
+        assert_eq!(documents.len(), 2);
+
+        assert!(route(None, json!({ "id": 1 })).is_err());
+        assert!(route(Some(&json!("not an uid")), json!({ "id": 1 })).is_err());
+        assert!(route(Some(&json!("movies")), json!(42)).is_err());
+    }
+
+    #[test]
+    fn invalid_records_advance_the_offsets() {
+        let config = KafkaConfig {
+            rest_url: String::new(),
+            topics: vec!["movies".to_string()],
+            consumer_group: String::new(),
+            batch_size: 3,
+            batch_timeout: Duration::from_secs(60),
+        };
+
+        let mut batch = Batch::default();
+        batch.push(record(json!("movies"), json!([{ "id": 1 }, { "id": 2 }]), 10));
+        batch.push(record(json!("movies"), json!("invalid"), 11));
+        assert!(!batch.is_full(&config));
+        assert_eq!(batch.offsets["movies"][&0], 12);
+
+        batch.push(record(json!("series"), json!({ "id": 1 }), 12));
+        assert!(batch.is_full(&config));
+        assert_eq!(batch.documents.keys().collect::<Vec<_>>(), vec!["movies", "series"]);
+        assert_eq!(batch.offsets["movies"][&0], 13);
+    }
+}
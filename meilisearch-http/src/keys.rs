@@ -0,0 +1,412 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Duration, Utc};
+use meilisearch_core::{Database, MResult};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::helpers::hmac::{constant_time_eq, hmac_sha256};
+use crate::helpers::Action;
+use crate::tenant_token::API_KEY_PREFIX_LENGTH;
+use crate::rate_limit::RateLimit;
+
+/// An API key restricted to some actions on the indexes matching some patterns.
+/// Only a salted hash of the key is stored, the key itself is derived from the master key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    /// Identifies the key without revealing it, the keys stored before
+    /// the introduction of the uids are given a new one when loaded.
+    #[serde(default = "generate_uid")]
+    pub uid: String,
+    /// The key itself, only present in the keys stored before they were hashed.
+    #[serde(default, skip_serializing)]
+    key: Option<String>,
+    #[serde(flatten)]
+    pub hashed: HashedKey,
+    /// Incremented each time the key is rotated, it is used to derive the key.
+    #[serde(default)]
+    pub generation: u32,
+    /// The key replaced by the last rotation, it remains valid until the end of the grace period.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<PreviousKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub actions: Vec<Action>,
+    /// Patterns of the index uids this key gives access to, `*` matches any sequence of characters.
+    pub indexes: Vec<String>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashedKey {
+    /// The first characters of the key, they identify the key that signed a tenant token.
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub salt: String,
+    /// The SHA256 of the salt followed by the key, in hexadecimal.
+    #[serde(default)]
+    pub hash: String,
+}
+
+impl HashedKey {
+    fn new(key: &str) -> HashedKey {
+        let salt = random_string(16);
+        let hash = salted_hash(&salt, key);
+        let prefix = key.chars().take(API_KEY_PREFIX_LENGTH).collect();
+        HashedKey { prefix, salt, hash }
+    }
+
+    fn is(&self, key: &str) -> bool {
+        constant_time_eq(salted_hash(&self.salt, key).as_bytes(), self.hash.as_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviousKey {
+    #[serde(flatten)]
+    pub hashed: HashedKey,
+    pub valid_until: DateTime<Utc>,
+}
+
+fn salted_hash(salt: &str, key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(salt.as_bytes());
+    hasher.input(key.as_bytes());
+    format!("{:x}", hasher.result())
+}
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(len).collect()
+}
+
+pub fn generate_uid() -> String {
+    random_string(16)
+}
+
+/// Derives the key of the given generation from the master key, the key doesn't need to be stored
+/// to verify the tenant tokens it signs. Without a master key the keys are simply random.
+pub fn derive_key(master_key: Option<&str>, uid: &str, generation: u32) -> String {
+    match master_key {
+        Some(master_key) => {
+            let message = format!("{}:{}", uid, generation);
+            hmac_sha256(master_key.as_bytes(), message.as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect()
+        }
+        None => random_string(64),
+    }
+}
+
+impl ApiKey {
+    pub fn new(
+        master_key: Option<&str>,
+        description: Option<String>,
+        actions: Vec<Action>,
+        indexes: Vec<String>,
+        expires_at: Option<DateTime<Utc>>,
+        rate_limit: Option<RateLimit>,
+    ) -> (ApiKey, String) {
+        let uid = generate_uid();
+        let key = derive_key(master_key, &uid, 0);
+        let api_key = ApiKey {
+            uid,
+            key: None,
+            hashed: HashedKey::new(&key),
+            generation: 0,
+            previous: None,
+            description,
+            actions,
+            indexes,
+            expires_at,
+            rate_limit,
+            created_at: Utc::now(),
+            updated_at: None,
+        };
+        (api_key, key)
+    }
+
+    /// Replaces the key stored before the keys were hashed by its hash.
+    fn hash_plain_key(&mut self) -> bool {
+        match self.key.take() {
+            Some(key) => {
+                self.hashed = HashedKey::new(&key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the key is the current one or the previous one, during its grace period.
+    pub fn is(&self, key: &str) -> bool {
+        self.hashed.is(key)
+            || self.previous.as_ref().map_or(false, |previous| {
+                previous.valid_until > Utc::now() && previous.hashed.is(key)
+            })
+    }
+
+    /// Whether this key allows the action on the index. The requests that don't target a
+    /// specific index, i.e. of which the index uid isn't known, can reach any index and
+    /// are only allowed to the keys of which a pattern is `*`.
+    pub fn allows(&self, action: Action, index_uid: Option<&str>) -> bool {
+        let index_allowed = match index_uid {
+            Some(uid) => self.scope().allows(uid),
+            None => self.indexes.iter().any(|pattern| pattern == "*"),
+        };
+
+        self.allows_action(action) && index_allowed
+    }
+
+    /// Whether this key allows the action, whatever the index.
+    pub fn allows_action(&self, action: Action) -> bool {
+        let not_expired = self.expires_at.map_or(true, |date| date > Utc::now());
+        let action_allowed = self.actions.iter().any(|a| *a == Action::All || *a == action);
+        not_expired && action_allowed
+    }
+
+    pub fn scope(&self) -> IndexScope {
+        IndexScope(self.indexes.clone())
+    }
+}
+
+/// The index patterns of the scoped key that authenticated a request, the routes that
+/// don't target a specific index use it to check the index uids they are given and to
+/// filter what they list. The requests authenticated with another key have no scope.
+#[derive(Debug, Clone)]
+pub struct IndexScope(Vec<String>);
+
+impl IndexScope {
+    pub fn allows(&self, index_uid: &str) -> bool {
+        self.0.iter().any(|pattern| matches_pattern(pattern, index_uid))
+    }
+}
+
+/// Matches an index uid against a glob pattern in which `*` matches any sequence of characters.
+pub fn matches_pattern(pattern: &str, uid: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !uid.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &uid[first.len()..];
+    let parts: Vec<_> = parts.collect();
+    let last = match parts.last() {
+        Some(last) => *last,
+        // there is no `*` in the pattern
+        None => return rest.is_empty(),
+    };
+
+    for part in &parts[..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+/// Keeps the scoped API keys in memory, every modification is written to the database.
+pub struct KeyStore {
+    keys: RwLock<Vec<ApiKey>>,
+}
+
+impl KeyStore {
+    pub fn load(db: &Database) -> MResult<KeyStore> {
+        let reader = db.main_read_txn()?;
+        let mut keys = db.api_keys::<Vec<ApiKey>>(&reader)?.unwrap_or_default();
+        drop(reader);
+
+        let mut hashed_plain_keys = false;
+        for key in &mut keys {
+            hashed_plain_keys |= key.hash_plain_key();
+        }
+        if hashed_plain_keys {
+            db.main_write(|writer| db.put_api_keys(writer, &keys))?;
+        }
+
+        Ok(KeyStore { keys: RwLock::new(keys) })
+    }
+
+    pub fn find(&self, key: &str) -> Option<ApiKey> {
+        self.keys.read().unwrap().iter().find(|k| k.is(key)).cloned()
+    }
+
+    pub fn all(&self) -> Vec<ApiKey> {
+        self.keys.read().unwrap().clone()
+    }
+
+    pub fn insert(&self, db: &Database, mut key: ApiKey) -> Result<(), Error> {
+        key.hash_plain_key();
+
+        let mut keys = self.keys.write().unwrap();
+        if keys.iter().any(|k| k.uid == key.uid) {
+            return Err(Error::bad_request(format!("the key {:?} already exists", key.uid)));
+        }
+
+        let mut new_keys = keys.clone();
+        new_keys.push(key);
+        db.main_write::<_, _, Error>(|writer| Ok(db.put_api_keys(writer, &new_keys)?))?;
+        *keys = new_keys;
+
+        Ok(())
+    }
+
+    pub fn find_by_uid(&self, uid: &str) -> Option<ApiKey> {
+        self.keys.read().unwrap().iter().find(|k| k.uid == uid).cloned()
+    }
+
+    /// Applies the modification to the key with the given uid and returns the modified key.
+    pub fn update<F>(&self, db: &Database, uid: &str, f: F) -> Result<Option<ApiKey>, Error>
+    where
+        F: FnOnce(&mut ApiKey),
+    {
+        let mut keys = self.keys.write().unwrap();
+        let mut new_keys = keys.clone();
+        let key = match new_keys.iter_mut().find(|k| k.uid == uid) {
+            Some(key) => {
+                f(key);
+                key.updated_at = Some(Utc::now());
+                key.clone()
+            }
+            None => return Ok(None),
+        };
+
+        db.main_write::<_, _, Error>(|writer| Ok(db.put_api_keys(writer, &new_keys)?))?;
+        *keys = new_keys;
+
+        Ok(Some(key))
+    }
+
+    /// Replaces the key with the given uid by a new one, the previous key remains valid during
+    /// the grace period but the tenant tokens it signed are immediately invalid.
+    /// Returns the modified key along with the new key.
+    pub fn rotate(
+        &self,
+        db: &Database,
+        master_key: Option<&str>,
+        uid: &str,
+        grace_period: Duration,
+    ) -> Result<Option<(ApiKey, String)>, Error> {
+        let mut new_key = String::new();
+        let updated = self.update(db, uid, |key| {
+            key.generation += 1;
+            new_key = derive_key(master_key, &key.uid, key.generation);
+            let previous = std::mem::replace(&mut key.hashed, HashedKey::new(&new_key));
+            key.previous = Some(PreviousKey { hashed: previous, valid_until: Utc::now() + grace_period });
+        })?;
+
+        Ok(updated.map(|key| (key, new_key)))
+    }
+
+    /// Adds the keys imported from a dump, they replace the keys with the same uid.
+    pub fn import(&self, db: &Database, imported: Vec<ApiKey>) -> Result<(), Error> {
+        if imported.is_empty() {
+            return Ok(());
+        }
+
+        let mut keys = self.keys.write().unwrap();
+        let mut new_keys: Vec<_> = keys.iter()
+            .filter(|k| imported.iter().all(|i| i.uid != k.uid))
+            .cloned()
+            .collect();
+        for mut key in imported {
+            key.hash_plain_key();
+            new_keys.push(key);
+        }
+
+        db.main_write::<_, _, Error>(|writer| Ok(db.put_api_keys(writer, &new_keys)?))?;
+        *keys = new_keys;
+
+        Ok(())
+    }
+
+    pub fn remove(&self, db: &Database, uid: &str) -> Result<bool, Error> {
+        let mut keys = self.keys.write().unwrap();
+        if !keys.iter().any(|k| k.uid == uid) {
+            return Ok(false);
+        }
+
+        let new_keys: Vec<_> = keys.iter().filter(|k| k.uid != uid).cloned().collect();
+        db.main_write::<_, _, Error>(|writer| Ok(db.put_api_keys(writer, &new_keys)?))?;
+        *keys = new_keys;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_patterns() {
+        assert!(matches_pattern("*", "movies"));
+        assert!(matches_pattern("movies", "movies"));
+        assert!(!matches_pattern("movies", "movies_fr"));
+        assert!(matches_pattern("movies_*", "movies_fr"));
+        assert!(!matches_pattern("movies_*", "books_fr"));
+        assert!(matches_pattern("*_fr", "movies_fr"));
+        assert!(matches_pattern("tenant-*-movies", "tenant-42-movies"));
+        assert!(!matches_pattern("tenant-*-movies", "tenant-42-books"));
+        assert!(!matches_pattern("a*a", "a"));
+    }
+
+    #[test]
+    fn index_scopes() {
+        let (key, _) = ApiKey::new(None, None, vec![Action::TasksGet], vec!["movies*".to_string()], None, None);
+        assert!(key.allows(Action::TasksGet, Some("movies_fr")));
+        assert!(!key.allows(Action::TasksGet, Some("books")));
+        assert!(!key.allows(Action::TasksCancel, Some("movies_fr")));
+        // the routes that don't target an index require a key covering all of them
+        assert!(!key.allows(Action::TasksGet, None));
+        assert!(key.allows_action(Action::TasksGet));
+
+        let (key, _) = ApiKey::new(None, None, vec![Action::All], vec!["*".to_string()], None, None);
+        assert!(key.allows(Action::TasksGet, None));
+        assert!(key.scope().allows("books"));
+    }
+
+    #[test]
+    fn hashed_keys() {
+        let (mut api_key, key) = ApiKey::new(Some("masterKey"), None, vec![Action::Search], vec!["*".to_string()], None, None);
+        assert_eq!(key, derive_key(Some("masterKey"), &api_key.uid, 0));
+        assert_eq!(api_key.hashed.prefix, &key[..API_KEY_PREFIX_LENGTH]);
+        assert_ne!(api_key.hashed.hash, key);
+        assert!(api_key.is(&key));
+        assert!(!api_key.is("anotherKey"));
+
+        // the keys stored before they were hashed are hashed when loaded
+        let mut stored: ApiKey = serde_json::from_value(serde_json::json!({
+            "key": "plainKey",
+            "actions": ["*"],
+            "indexes": ["*"],
+            "createdAt": Utc::now(),
+        })).unwrap();
+        assert!(stored.hash_plain_key());
+        assert!(stored.is("plainKey"));
+        assert!(!serde_json::to_string(&stored).unwrap().contains("plainKey"));
+
+        // the previous key remains valid during its grace period
+        let previous = std::mem::replace(&mut api_key.hashed, HashedKey::new("newKey"));
+        api_key.previous = Some(PreviousKey { hashed: previous, valid_until: Utc::now() + Duration::hours(1) });
+        assert!(api_key.is("newKey"));
+        assert!(api_key.is(&key));
+        api_key.previous.as_mut().unwrap().valid_until = Utc::now() - Duration::seconds(1);
+        assert!(!api_key.is(&key));
+    }
+}
@@ -9,6 +9,33 @@ pub mod routes;
 pub mod analytics;
 pub mod snapshot;
 pub mod dump;
+pub mod webhook;
+pub mod firehose;
+pub mod scheduler;
+pub mod keys;
+pub mod tenant_token;
+pub mod rate_limit;
+pub mod secrets;
+pub mod storage;
+pub mod journal;
+pub mod telemetry;
+pub mod slow_query;
+pub mod search_analytics;
+pub mod search_limiter;
+pub mod filter_cache;
+pub mod capacity;
+pub mod changes;
+pub mod compaction;
+pub mod warmup;
+pub mod kafka;
+pub mod postgres;
+pub mod s3;
+pub mod crawler;
+pub mod imports;
+pub mod reload;
+pub mod features;
+pub mod systemd;
+pub mod task_metrics;
 
 use actix_http::Error;
 use actix_service::ServiceFactory;
@@ -16,11 +43,14 @@ use actix_web::{dev, web, App};
 use chrono::Utc;
 use log::error;
 
-use meilisearch_core::{Index, MainWriter, ProcessedUpdateResult};
+use meilisearch_core::{Index, MainWriter, ProcessedUpdateResult, UpdateStatus};
 
 pub use option::Opt;
 pub use self::data::Data;
 use self::error::{payload_error_handler, ResponseError};
+use self::helpers::logging;
+use self::helpers::payload_limit::PayloadSizeLimit;
+use self::helpers::write_guard::WriteGuard;
 
 pub fn create_app(
     data: &Data,
@@ -38,10 +68,11 @@ pub fn create_app(
         .data(data.clone())
         .app_data(
             web::JsonConfig::default()
-                .limit(data.http_payload_size_limit)
+                .limit(data.payload_limits.max())
                 .content_type(|_mime| true) // Accept all mime types
                 .error_handler(|err, _req| payload_error_handler(err).into()),
         )
+        .app_data(web::PayloadConfig::new(data.payload_limits.max()))
         .app_data(
             web::QueryConfig::default()
             .error_handler(|err, _req| payload_error_handler(err).into())
@@ -53,11 +84,29 @@ pub fn create_app(
         .configure(routes::search::services)
         .configure(routes::setting::services)
         .configure(routes::stop_words::services)
+        .configure(routes::separator_tokens::services)
+        .configure(routes::dictionary::services)
+        .configure(routes::query_rules::services)
         .configure(routes::synonym::services)
+        .configure(routes::experiment::services)
         .configure(routes::health::services)
         .configure(routes::stats::services)
         .configure(routes::key::services)
         .configure(routes::dump::services)
+        .configure(routes::snapshot::services)
+        .configure(routes::changes::services)
+        .configure(routes::compaction::services)
+        .configure(routes::schedule::services)
+        .configure(routes::task::services)
+        .configure(routes::debug::services)
+        .configure(routes::config::services)
+        .configure(routes::features::services)
+        .configure(routes::grpc::services)
+        .configure(routes::es_compat::services)
+        .configure(routes::export::services)
+        .configure(routes::firehose::services)
+        .wrap(PayloadSizeLimit::new(data.payload_limits))
+        .wrap(WriteGuard::new(data))
 }
 
 pub fn index_update_callback_txn(index: Index, index_uid: &str, data: &Data, mut writer: &mut MainWriter) -> Result<(), String> {
@@ -77,7 +126,43 @@ pub fn index_update_callback_txn(index: Index, index_uid: &str, data: &Data, mut
 }
 
 pub fn index_update_callback(index_uid: &str, data: &Data, status: ProcessedUpdateResult) {
-    if status.error.is_some() {
+    let failed = status.error.is_some();
+
+    data.task_metrics.record(index_uid, &status);
+
+    if let Some(tracer) = &data.tracer {
+        tracer.update_processed(index_uid, &status);
+    }
+
+    logging::log_event(log::Level::Info, logging::UPDATE_TARGET, &logging::UpdateEvent {
+        message: "update",
+        index_uid,
+        update_id: status.update_id,
+        update_type: status.update_type.name(),
+        status: if failed { "failed" } else { "processed" },
+        duration_ms: status.duration * 1000.0,
+        error_code: status.error_code.as_deref(),
+    });
+
+    let task = if failed {
+        routes::task::Task::new(index_uid, UpdateStatus::Failed { content: status })
+    } else {
+        routes::task::Task::new(index_uid, UpdateStatus::Processed { content: status })
+    };
+
+    // the failed updates left the index untouched
+    if !failed {
+        data.change_feed.publish(&task);
+    }
+
+    if let Some(notifier) = &data.webhook_notifier {
+        notifier.notify(task);
+    }
+
+    // the updates are what fill the maps, warn before they are full
+    data.capacity.check();
+
+    if failed {
         return;
     }
 
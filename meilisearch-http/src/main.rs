@@ -1,12 +1,18 @@
+use std::time::Duration;
 use std::{env, thread};
 
-use actix_cors::Cors;
+use actix_http::KeepAlive;
 use actix_web::{middleware, HttpServer};
+use log::info;
 use main_error::MainError;
-use meilisearch_http::helpers::NormalizePath;
+use meilisearch_http::helpers::access_log::AccessLog;
+use meilisearch_http::helpers::cors::{check_cors, create_cors, SearchOnlyOrigins};
+use meilisearch_http::helpers::logging::{self, LogFormat, RequestLogger};
+use meilisearch_http::helpers::{client_certificate, shutdown, NormalizePath};
 use meilisearch_http::{create_app, index_update_callback, Data, Opt};
-use structopt::StructOpt;
-use meilisearch_http::{snapshot, dump};
+use meilisearch_http::{capacity, snapshot, dump, journal, kafka, postgres, reload, scheduler, secrets, systemd, warmup};
+use meilisearch_http::routes::{index, task};
+use meilisearch_http::telemetry::RequestTracing;
 
 mod analytics;
 
@@ -16,7 +22,12 @@ static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 #[actix_web::main]
 async fn main() -> Result<(), MainError> {
-    let opt = Opt::from_args();
+    let opt = Opt::from_args_and_config_file()?;
+
+    if opt.print_config {
+        print!("{}", opt.to_config_file()?);
+        return Ok(());
+    }
 
     #[cfg(all(not(debug_assertions), feature = "sentry"))]
     let _sentry = sentry::init((
@@ -45,19 +56,35 @@ async fn main() -> Result<(), MainError> {
                 sentry::integrations::panic::register_panic_handler(); // TODO: This shouldn't be needed when upgrading to sentry 0.19.0. These integrations are turned on by default when using `sentry::init`.
                 sentry::integrations::env_logger::init(None, Default::default());
             }
+
+            // does nothing if the logs are already sent to Sentry
+            if opt.log_format == LogFormat::Json {
+                logging::init_logger(opt.log_format);
+            }
         }
         "development" => {
-            env_logger::from_env(env_logger::Env::default().default_filter_or("info")).init();
+            logging::init_logger(opt.log_format);
         }
         _ => unreachable!(),
     }
 
+    check_cors(&opt)?;
+
+    if let (Some(filters), Some(_)) = (&opt.log_level, logging::log_filters()) {
+        logging::set_log_filters(filters)?;
+    }
+
     if let Some(path) = &opt.load_from_snapshot {
         snapshot::load_snapshot(&opt.db_path, path, opt.ignore_snapshot_if_db_exists, opt.ignore_missing_snapshot)?;
     }
 
     let data = Data::new(opt.clone())?;
 
+    if let (Some(journal), Some(target)) = (&data.journal, opt.replay_journal_until) {
+        let replayed = journal::replay_journal(&data, journal, target)?;
+        info!("{} journaled updates replayed up to {:?}", replayed, target);
+    }
+
     if !opt.no_analytics {
         let analytics_data = data.clone();
         let analytics_opt = opt.clone();
@@ -74,38 +101,147 @@ async fn main() -> Result<(), MainError> {
         dump::import_dump(&data, path, opt.dump_batch_size)?;
     }
 
+    if !opt.warmup_indexes.is_empty() {
+        warmup::warmup_indexes(&data, &opt.warmup_indexes)?;
+    }
+
     if let Some(path) = &opt.snapshot_path {
-        snapshot::schedule_snapshot(data.clone(), &path, opt.snapshot_interval_sec.unwrap_or(86400))?;
+        snapshot::schedule_snapshot(
+            data.clone(),
+            &path,
+            opt.snapshot_retention,
+            opt.snapshot_incremental,
+        )?;
+    }
+
+    if let Some(days) = opt.task_retention_days {
+        task::schedule_tasks_retention(data.clone(), Duration::from_secs(days.saturating_mul(24 * 3600)));
+    }
+
+    if opt.index_trash_retention_sec.is_some() {
+        index::schedule_trash_purge(data.clone());
+    }
+
+    scheduler::spawn_scheduler(data.clone());
+
+    if opt.safety_mode_min_free_space.is_some() {
+        capacity::schedule_capacity_checks(data.capacity.clone(), Duration::from_secs(10));
+    }
+
+    if let Some(path) = &opt.config_file {
+        reload::watch_config_file(data.clone(), path.clone());
+    }
+
+    if let Some(rest_url) = &opt.kafka_rest_url {
+        let config = kafka::KafkaConfig {
+            rest_url: rest_url.clone(),
+            topics: opt.kafka_topics.clone(),
+            consumer_group: opt.kafka_consumer_group.clone(),
+            batch_size: opt.kafka_batch_size,
+            batch_timeout: Duration::from_millis(opt.kafka_batch_timeout_ms),
+        };
+        kafka::spawn_consumer(data.clone(), config);
+    }
+
+    if let Some(path) = &opt.postgres_cdc_config {
+        let config: postgres::CdcConfig = serde_json::from_slice(&std::fs::read(path)?)?;
+        config.validate()?;
+        postgres::spawn_sync(data.clone(), config);
+    }
+
+    if let (Some(master_key), Some(interval)) = (&opt.master_key, opt.secrets_refresh_interval_sec) {
+        if secrets::is_secret_reference(master_key) {
+            secrets::schedule_master_key_rotation(data.clone(), master_key.clone(), Duration::from_secs(interval));
+        }
     }
 
     print_launch_resume(&opt, &data);
 
-    let http_server = HttpServer::new(move || {
+    #[cfg(unix)]
+    spawn_shutdown_listener(data.clone());
+
+    let shutdown_data = data.clone();
+    let cors_opt = opt.clone();
+    let mut http_server = HttpServer::new(move || {
         create_app(&data)
-            .wrap(
-                Cors::new()
-                    .send_wildcard()
-                    .allowed_headers(vec!["content-type", "x-meili-api-key"])
-                    .max_age(86_400) // 24h
-                    .finish(),
-            )
-            .wrap(middleware::Logger::default())
+            .wrap(create_cors(&cors_opt))
+            .wrap(SearchOnlyOrigins::new(&cors_opt.cors_search_origins))
+            .wrap(RequestTracing)
+            .wrap(RequestLogger)
+            .wrap(AccessLog)
             .wrap(middleware::Compress::default())
             .wrap(NormalizePath)
-    });
+    })
+    .on_connect(|connection, extensions| {
+        if let Some(identity) = client_certificate::client_identity(connection) {
+            extensions.insert(identity);
+        }
+    })
+    .shutdown_timeout(opt.shutdown_timeout_sec);
 
-    if let Some(config) = opt.get_ssl_config()? {
-        http_server
-            .bind_rustls(opt.http_addr, config)?
-            .run()
-            .await?;
-    } else {
-        http_server.bind(opt.http_addr)?.run().await?;
+    // the defaults of actix-web are kept for the options that are not set
+    if let Some(workers) = opt.http_workers {
+        http_server = http_server.workers(workers);
+    }
+    if let Some(max) = opt.http_max_connections {
+        http_server = http_server.max_connections(max);
+    }
+    if let Some(max) = opt.http_max_connection_rate {
+        http_server = http_server.max_connection_rate(max);
+    }
+    if let Some(backlog) = opt.http_backlog {
+        http_server = http_server.backlog(backlog);
+    }
+    if let Some(secs) = opt.http_keep_alive_sec {
+        http_server = http_server.keep_alive(if secs == 0 { KeepAlive::Disabled } else { KeepAlive::Timeout(secs) });
+    }
+    if let Some(ms) = opt.http_client_timeout_ms {
+        http_server = http_server.client_timeout(ms);
     }
+    if let Some(ms) = opt.http_client_shutdown_ms {
+        http_server = http_server.client_shutdown(ms);
+    }
+
+    let server = match opt.get_ssl_config()? {
+        Some(config) => http_server.bind_rustls(opt.http_addr, config)?.run(),
+        None => http_server.bind(opt.http_addr)?.run(),
+    };
+
+    // the database is opened, the journal replayed and the server listening
+    systemd::notify_or_warn("READY=1");
+    if let Some(interval) = systemd::watchdog_interval() {
+        systemd::spawn_watchdog(shutdown_data.clone(), interval);
+    }
+
+    server.await?;
+
+    // the server stopped, the update being processed is committed before exiting
+    shutdown::begin_shutdown(&shutdown_data);
+    shutdown_data.db.close()?;
+    info!("Shutdown complete");
 
     Ok(())
 }
 
+/// Refuses the writes as soon as a termination signal is received, while the HTTP server
+/// finishes the requests in progress.
+#[cfg(unix)]
+fn spawn_shutdown_listener(data: Data) {
+    use actix_rt::signal::unix::{signal, SignalKind};
+
+    actix_rt::spawn(async move {
+        let (mut terminate, mut interrupt) = match (signal(SignalKind::terminate()), signal(SignalKind::interrupt())) {
+            (Ok(terminate), Ok(interrupt)) => (terminate, interrupt),
+            _ => return,
+        };
+        tokio::select! {
+            _ = terminate.recv() => (),
+            _ = interrupt.recv() => (),
+        }
+        shutdown::begin_shutdown(&data);
+    });
+}
+
 pub fn print_launch_resume(opt: &Opt, data: &Data) {
     let ascii_name = r#"
 888b     d888          d8b 888 d8b  .d8888b.                                    888
@@ -154,7 +290,7 @@ pub fn print_launch_resume(opt: &Opt, data: &Data) {
 
     eprintln!();
 
-    if data.api_keys.master.is_some() {
+    if data.api_keys.read().unwrap().master.is_some() {
         eprintln!("A Master Key has been set. Requests to MeiliSearch won't be authorized unless you provide an authentication key.");
     } else {
         eprintln!("No master key found; The server will accept unidentified requests. \
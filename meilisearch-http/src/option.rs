@@ -1,18 +1,34 @@
-use std::{error, fs};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::{env, error, fs};
 use std::io::{BufReader, Read};
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
 use rustls::{
     AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, NoClientAuth,
     RootCertStore,
 };
+use once_cell::sync::Lazy;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
 use structopt::StructOpt;
 
-const POSSIBLE_ENV: [&str; 2] = ["development", "production"];
+use crate::helpers::access_log::AccessLogFormat;
+use crate::helpers::logging::LogFormat;
+use crate::journal::ReplayTarget;
+use crate::secrets::resolve_secret;
 
-#[derive(Debug, Default, Clone, StructOpt)]
+const POSSIBLE_ENV: [&str; 2] = ["development", "production"];
+const POSSIBLE_LOG_FORMATS: [&str; 2] = ["text", "json"];
+const POSSIBLE_ACCESS_LOG_FORMATS: [&str; 3] = ["common", "combined", "json"];
+/// The environment variables set from the configuration file, as opposed to the ones of the environment.
+static EXPORTED_VARS: Lazy<Mutex<Vec<String>>> = Lazy::new(Mutex::default);
+/// The options whose values are separated by semicolons instead of commas.
+const SEMICOLON_DELIMITED_OPTIONS: [&str; 2] = ["ssl_client_keys", "schedules"];
+
+#[derive(Debug, Default, Clone, StructOpt, Serialize)]
 pub struct Opt {
     /// The destination where the database must be created.
     #[structopt(long, env = "MEILI_DB_PATH", default_value = "./data.ms")]
@@ -22,10 +38,19 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_HTTP_ADDR", default_value = "127.0.0.1:7700")]
     pub http_addr: String,
 
-    /// The master key allowing you to do everything on the server.
+    /// The master key allowing you to do everything on the server. It can also be read from
+    /// a file with `file:<path>`, from another environment variable with `env:<VARIABLE>`,
+    /// or fetched from Vault with `vault:<url>#<field>` using the `VAULT_TOKEN` variable.
     #[structopt(long, env = "MEILI_MASTER_KEY")]
+    #[serde(serialize_with = "serialize_secret")]
     pub master_key: Option<String>,
 
+    /// Resolves the master key again every interval, in seconds, when it is read from a file,
+    /// an environment variable or Vault. The private and public keys change along with it, the
+    /// rotation is refused while scoped API keys exist as they are derived from the master key.
+    #[structopt(long, requires = "master-key", env = "MEILI_SECRETS_REFRESH_INTERVAL_SEC")]
+    pub secrets_refresh_interval_sec: Option<u64>,
+
     /// The Sentry DSN to use for error reporting. This defaults to the MeiliSearch Sentry project.
     /// You can disable sentry all together using the `--no-sentry` flag or `MEILI_NO_SENTRY` environment variable.
     #[cfg(all(not(debug_assertions), feature = "sentry"))]
@@ -44,6 +69,76 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_ENV", default_value = "development", possible_values = &POSSIBLE_ENV)]
     pub env: String,
 
+    /// The format of the logs: `text`, or `json` to write one JSON object per line. In the JSON format,
+    /// the request and update events carry their fields, e.g. the request id, the index uid, the API key
+    /// uid and the latency, as attributes of the object. The logs are also enabled in production.
+    #[structopt(long, env = "MEILI_LOG_FORMAT", default_value = "text", possible_values = &POSSIBLE_LOG_FORMATS)]
+    pub log_format: LogFormat,
+
+    /// The filters of the logs in the `RUST_LOG` syntax, e.g. `info,meilisearch_core=debug`.
+    /// Defaults to the `RUST_LOG` environment variable, or `info`.
+    #[structopt(long, env = "MEILI_LOG_LEVEL")]
+    pub log_level: Option<String>,
+
+    /// Writes a line for each request to this file: the client IP, the API key uid, the route,
+    /// the status, the size of the body and the duration.
+    #[structopt(long, env = "MEILI_ACCESS_LOG_PATH", parse(from_os_str))]
+    pub access_log_path: Option<PathBuf>,
+
+    /// The format of the access logs: the NCSA `common` or `combined` log formats, followed by the
+    /// duration in microseconds, or `json` to write one JSON object per line.
+    #[structopt(long, env = "MEILI_ACCESS_LOG_FORMAT", default_value = "combined", possible_values = &POSSIBLE_ACCESS_LOG_FORMATS)]
+    pub access_log_format: AccessLogFormat,
+
+    /// The size, in bytes, above which the access log file is rotated.
+    #[structopt(long, env = "MEILI_ACCESS_LOG_MAX_SIZE", default_value = "104857600")] // 100MiB
+    pub access_log_max_size: u64,
+
+    /// The number of rotated access log files kept, named with a `.1`, `.2`... suffix.
+    #[structopt(long, env = "MEILI_ACCESS_LOG_MAX_FILES", default_value = "5")]
+    pub access_log_max_files: usize,
+
+    /// The OpenTelemetry collector to which the traces of the requests and of the updates are exported,
+    /// with the OTLP/HTTP protocol, e.g. `http://localhost:4318`. The `traceparent` header of the
+    /// requests is honored. No trace is exported if this option is not specified.
+    #[structopt(long, env = "MEILI_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+
+    /// The service name of the exported traces.
+    #[structopt(long, requires = "otlp-endpoint", env = "MEILI_OTLP_SERVICE_NAME", default_value = "meilisearch")]
+    pub otlp_service_name: String,
+
+    /// Logs, as warnings, the searches that take at least this number of milliseconds, with their
+    /// parameters, their number of hits and the time spent in each phase of the search.
+    #[structopt(long, env = "MEILI_SLOW_QUERY_THRESHOLD_MS")]
+    pub slow_query_threshold_ms: Option<u64>,
+
+    /// Keeps this number of the most recent slow searches in memory, they are listed by the
+    /// `GET /debug/slow-queries` route.
+    #[structopt(long, requires = "slow-query-threshold-ms", env = "MEILI_SLOW_QUERY_LOG_SIZE", default_value = "0")]
+    pub slow_query_log_size: usize,
+
+    /// The maximum number of searches executed at once, the other searches are queued and the API
+    /// keys are served in turn. Setting it below the number of HTTP workers keeps workers free for
+    /// the other routes during a burst of searches. The searches are not limited by default.
+    #[structopt(long, env = "MEILI_MAX_CONCURRENT_SEARCHES")]
+    pub max_concurrent_searches: Option<usize>,
+
+    /// The number of milliseconds a search can wait in the queue before being refused.
+    #[structopt(long, requires = "max-concurrent-searches", env = "MEILI_SEARCH_QUEUE_TIMEOUT_MS", default_value = "5000")]
+    pub search_queue_timeout_ms: u64,
+
+    /// Do not aggregate the searches made on the indexes, the query counts, top queries and
+    /// latencies listed by the `GET /indexes/{index_uid}/analytics` route.
+    #[structopt(long, env = "MEILI_NO_SEARCH_ANALYTICS")]
+    pub no_search_analytics: bool,
+
+    /// The memory, in bytes, used to cache the documents matching the filters used frequently
+    /// in the searches, the cache is disabled when not set. The documents of a filter are cached
+    /// until the index is updated, the least recently used filters are evicted first.
+    #[structopt(long, env = "MEILI_FILTER_CACHE_SIZE")]
+    pub filter_cache_size: Option<usize>,
+
     /// Do not send analytics to Meili.
     #[structopt(long, env = "MEILI_NO_ANALYTICS")]
     pub no_analytics: bool,
@@ -56,10 +151,75 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_MAX_UDB_SIZE", default_value = "107374182400")] // 100GB
     pub max_udb_size: usize,
 
+    /// The size, in bytes, up to which the lmdb maps are doubled when they are found almost full
+    /// at launch. The maps are not grown while MeiliSearch runs, a warning asks to restart it
+    /// when one of them fills up. By default the maps are never grown.
+    #[structopt(long, env = "MEILI_MAX_MAP_SIZE")]
+    pub max_map_size: Option<usize>,
+
+    /// Switches to a read-only safety mode when the free space of the disk or of a lmdb map drops
+    /// below this number of bytes: the writes are refused and the searches are still served.
+    /// The writes are accepted again once there is enough free space. Disabled by default.
+    #[structopt(long, env = "MEILI_SAFETY_MODE_MIN_FREE_SPACE")]
+    pub safety_mode_min_free_space: Option<u64>,
+
     /// The maximum size, in bytes, of accepted JSON payloads
     #[structopt(long, env = "MEILI_HTTP_PAYLOAD_SIZE_LIMIT", default_value = "10485760")] // 10MB
     pub http_payload_size_limit: usize,
 
+    /// The maximum size, in bytes, of the payloads sent to the documents routes, e.g. large document
+    /// additions. Defaults to `--http-payload-size-limit`.
+    #[structopt(long, env = "MEILI_HTTP_DOCUMENTS_PAYLOAD_SIZE_LIMIT")]
+    pub http_documents_payload_size_limit: Option<usize>,
+
+    /// The maximum size, in bytes, of the payloads sent to the search routes.
+    /// Defaults to `--http-payload-size-limit`.
+    #[structopt(long, env = "MEILI_HTTP_SEARCH_PAYLOAD_SIZE_LIMIT")]
+    pub http_search_payload_size_limit: Option<usize>,
+
+    /// The maximum size, in bytes, of the index archives sent to the restore route.
+    /// Defaults to 10GiB, the archives are written to disk as they are received.
+    #[structopt(long, env = "MEILI_HTTP_RESTORE_PAYLOAD_SIZE_LIMIT")]
+    pub http_restore_payload_size_limit: Option<usize>,
+
+    /// The number of threads handling the HTTP requests, defaults to the number of CPUs.
+    #[structopt(long, env = "MEILI_HTTP_WORKERS")]
+    pub http_workers: Option<usize>,
+
+    /// The maximum number of connections handled at once by each worker, the new connections wait
+    /// in the backlog afterward. Defaults to 25000.
+    #[structopt(long, env = "MEILI_HTTP_MAX_CONNECTIONS")]
+    pub http_max_connections: Option<usize>,
+
+    /// The maximum number of TLS handshakes made at once by each worker. Defaults to 256.
+    #[structopt(long, env = "MEILI_HTTP_MAX_CONNECTION_RATE")]
+    pub http_max_connection_rate: Option<usize>,
+
+    /// The maximum number of connections waiting to be accepted. Defaults to 2048.
+    #[structopt(long, env = "MEILI_HTTP_BACKLOG")]
+    pub http_backlog: Option<u32>,
+
+    /// How long an idle connection is kept open, in seconds, 0 disables the keep-alive.
+    /// Defaults to 5 seconds.
+    #[structopt(long, env = "MEILI_HTTP_KEEP_ALIVE_SEC")]
+    pub http_keep_alive_sec: Option<usize>,
+
+    /// How long a client has to send the headers of its request, in milliseconds, 0 disables the
+    /// timeout. It doesn't apply to the body, a large upload can take longer. Defaults to 5000.
+    #[structopt(long, env = "MEILI_HTTP_CLIENT_TIMEOUT_MS")]
+    pub http_client_timeout_ms: Option<u64>,
+
+    /// How long a client has to close its connection once the response is sent, in milliseconds,
+    /// 0 disables the timeout. Defaults to 5000.
+    #[structopt(long, env = "MEILI_HTTP_CLIENT_SHUTDOWN_MS")]
+    pub http_client_shutdown_ms: Option<u64>,
+
+    /// On SIGTERM or SIGINT, the writes are refused and the requests in progress are given this
+    /// number of seconds to complete. The update being processed is then committed before exiting,
+    /// the other enqueued updates are processed on the next start.
+    #[structopt(long, env = "MEILI_SHUTDOWN_TIMEOUT_SEC", default_value = "30")]
+    pub shutdown_timeout_sec: u64,
+
     /// Read server certificates from CERTFILE.
     /// This should contain PEM-format certificates
     /// in the right order (the first certificate should
@@ -72,6 +232,12 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_SSL_KEY_PATH", parse(from_os_str))]
     pub ssl_key_path: Option<PathBuf>,
 
+    /// The PEM content of the private key, used instead of the KEYFILE. Like the master key,
+    /// it can be read from another environment variable or fetched from Vault.
+    #[structopt(long, env = "MEILI_SSL_KEY", conflicts_with = "ssl-key-path")]
+    #[serde(serialize_with = "serialize_secret")]
+    pub ssl_key: Option<String>,
+
     /// Enable client authentication, and accept certificates
     /// signed by those roots provided in CERTFILE.
     #[structopt(long, env = "MEILI_SSL_AUTH_PATH", parse(from_os_str))]
@@ -86,6 +252,13 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_SSL_REQUIRE_AUTH")]
     pub ssl_require_auth: bool,
 
+    /// Maps the names of the client certificates to scoped API keys, separated by semicolons,
+    /// of the form `name=keyUid` where the name is the common name or an alternative name
+    /// of the certificate, e.g. `search-service=3PmVkUvHqW8Xk0ZJ`. Clients authenticated with
+    /// such a certificate don't need to send the `X-Meili-API-Key` header.
+    #[structopt(long, env = "MEILI_SSL_CLIENT_KEYS", use_delimiter = true, value_delimiter = ";")]
+    pub ssl_client_keys: Vec<String>,
+
     /// SSL support session resumption
     #[structopt(long, env = "MEILI_SSL_RESUMPTION")]
     pub ssl_resumption: bool,
@@ -94,7 +267,34 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_SSL_TICKETS")]
     pub ssl_tickets: bool,
 
-    /// Defines the path of the snapshot file to import.
+    /// The origins, separated by commas, allowed to call the API from a browser.
+    /// Any origin is allowed if neither these origins nor the search origins are specified.
+    #[structopt(long, env = "MEILI_CORS_ALLOWED_ORIGINS", use_delimiter = true)]
+    pub cors_allowed_origins: Vec<String>,
+
+    /// The origins, separated by commas, only allowed to call the search routes from a browser,
+    /// the requests they send to any other route are refused.
+    #[structopt(long, env = "MEILI_CORS_SEARCH_ORIGINS", use_delimiter = true)]
+    pub cors_search_origins: Vec<String>,
+
+    /// The methods, separated by commas, allowed in the cross-origin requests. Any method is allowed if none is specified.
+    #[structopt(long, env = "MEILI_CORS_ALLOWED_METHODS", use_delimiter = true)]
+    pub cors_allowed_methods: Vec<String>,
+
+    /// The headers, separated by commas, allowed in the cross-origin requests.
+    #[structopt(long, env = "MEILI_CORS_ALLOWED_HEADERS", use_delimiter = true, default_value = "content-type,x-meili-api-key")]
+    pub cors_allowed_headers: Vec<String>,
+
+    /// Allow the cross-origin requests to include credentials, it requires the allowed origins to be
+    /// specified, the server refuses to start when any origin is allowed.
+    #[structopt(long, env = "MEILI_CORS_ALLOW_CREDENTIALS")]
+    pub cors_allow_credentials: bool,
+
+    /// The number of seconds the browsers can cache the responses of the preflight requests.
+    #[structopt(long, env = "MEILI_CORS_MAX_AGE", default_value = "86400")] // 24h
+    pub cors_max_age: usize,
+
+    /// Defines the path of the snapshot file, or incremental snapshot, to import.
     /// This option will, by default, stop the process if a database already exist or if no snapshot exists at
     /// the given path. If this option is not specified no snapshot is imported.
     #[structopt(long, env = "MEILI_LOAD_FROM_SNAPSHOT")]
@@ -116,10 +316,41 @@ pub struct Opt {
     #[structopt(long, requires = "snapshot-path", env = "MEILI_SNAPSHOT_INTERVAL_SEC")]
     pub snapshot_interval_sec: Option<u64>,
 
+    /// Keeps this number of snapshots, the name of each snapshot then contains its creation date.
+    /// By default, each snapshot replaces the previous one.
+    #[structopt(long, requires = "snapshot-path", env = "MEILI_SNAPSHOT_RETENTION")]
+    pub snapshot_retention: Option<usize>,
+
+    /// Creates incremental snapshots in the `<db>.incremental` directory of the snapshot path, only the chunks
+    /// of the database that changed since the previous snapshot are stored. Such a snapshot is loaded by giving
+    /// this directory, or the manifest of one of its snapshots, to `load-from-snapshot`.
+    #[structopt(long, requires = "snapshot-path", env = "MEILI_SNAPSHOT_INCREMENTAL")]
+    pub snapshot_incremental: bool,
+
+    /// Copies each snapshot to a storage once created: a local directory or an `s3://<bucket>/<prefix>`,
+    /// `gs://<bucket>/<prefix>` or `azure://<account>/<container>/<prefix>` url.
+    #[structopt(long, env = "MEILI_SNAPSHOT_STORAGE")]
+    pub snapshot_storage: Option<String>,
+
+    /// Records the updates applied to the indexes in this directory, to be able to restore the database
+    /// to a point in time by replaying them on top of a snapshot.
+    #[structopt(long, env = "MEILI_UPDATE_JOURNAL_PATH")]
+    pub update_journal_path: Option<PathBuf>,
+
+    /// Once the snapshot is loaded, replays the journaled updates applied after it up to this journal
+    /// sequence number or RFC 3339 date, e.g. just before a bad update. The later updates are discarded
+    /// from the journal.
+    #[structopt(long, requires_all = &["load-from-snapshot", "update-journal-path"], env = "MEILI_REPLAY_JOURNAL_UNTIL")]
+    pub replay_journal_until: Option<ReplayTarget>,
+
     /// Folder where dumps are created when the dump route is called.
     #[structopt(long, env = "MEILI_DUMPS_FOLDER", default_value = "dumps/")]
     pub dumps_folder: PathBuf,
 
+    /// Copies each dump to a storage once created, see `snapshot-storage` for the supported storages.
+    #[structopt(long, env = "MEILI_DUMP_STORAGE")]
+    pub dump_storage: Option<String>,
+
     /// Import a dump from the specified path, must be a `.tar.gz` file.
     #[structopt(long, env = "MEILI_IMPORT_DUMP", conflicts_with = "load-from-snapshot")]
     pub import_dump: Option<PathBuf>,
@@ -127,11 +358,150 @@ pub struct Opt {
     /// The batch size used in the importation process, the bigger it is the faster the dump is created.
     #[structopt(long, env = "MEILI_DUMP_BATCH_SIZE", default_value = "1024")]
     pub dump_batch_size: usize,
+
+    /// Defines the number of days finished tasks are kept before being automatically deleted.
+    /// If this option is not specified tasks are kept forever.
+    #[structopt(long, env = "MEILI_TASK_RETENTION_DAYS")]
+    pub task_retention_days: Option<u64>,
+
+    /// Defines the number of seconds a deleted index is kept in the trash, it can be restored with
+    /// `POST /indexes/{uid}/restore` meanwhile. If this option is not specified the indexes are
+    /// deleted right away.
+    #[structopt(long, env = "MEILI_INDEX_TRASH_RETENTION_SEC")]
+    pub index_trash_retention_sec: Option<u64>,
+
+    /// The buckets, separated by commas, from which `POST /indexes/{uid}/documents/import` can import
+    /// files with an `s3://` url. They are downloaded with the AWS credentials of the server, the
+    /// other buckets are refused. No file can be imported from S3 if this option is not specified.
+    #[structopt(long, env = "MEILI_IMPORT_S3_BUCKETS", use_delimiter = true)]
+    pub import_s3_buckets: Vec<String>,
+
+    /// URLs, separated by commas, that receive a JSON payload each time a task is processed or failed.
+    #[structopt(long, env = "MEILI_WEBHOOK_URLS", use_delimiter = true)]
+    pub webhook_urls: Vec<String>,
+
+    /// The secret used to sign the webhook payloads and the events sent to the firehose targets of
+    /// the indexes. The signature of `{timestamp}.{payload}` is sent in the `X-Meili-Signature` header
+    /// and the timestamp, in seconds, in the `X-Meili-Timestamp` header. The payloads are sent
+    /// unsigned when it is not set. Like the master key, it can be read from a file, another
+    /// environment variable or Vault.
+    #[structopt(long, env = "MEILI_WEBHOOK_SECRET")]
+    #[serde(serialize_with = "serialize_secret")]
+    pub webhook_secret: Option<String>,
+
+    /// The file to which the batches of document events the firehose targets of the indexes failed
+    /// to receive are appended, one JSON object per line. Defaults to `firehose-dead-letters.jsonl`
+    /// next to the database.
+    #[structopt(long, env = "MEILI_FIREHOSE_DEAD_LETTER_PATH")]
+    pub firehose_dead_letter_path: Option<PathBuf>,
+
+    /// The uids of the indexes, separated by commas, whose search structures are loaded in memory
+    /// before the server starts, or `*` for all the indexes. The first searches made on them after
+    /// a restart or a snapshot installation then don't have to wait for the disk.
+    #[structopt(long, env = "MEILI_WARMUP_INDEXES", use_delimiter = true)]
+    pub warmup_indexes: Vec<String>,
+
+    /// The URL of a Kafka REST proxy through which the documents of the Kafka topics are consumed.
+    /// The key of each record is the uid of the index its documents are added to, and its value
+    /// is a document or an array of documents.
+    #[structopt(long, requires = "kafka-topics", env = "MEILI_KAFKA_REST_URL")]
+    pub kafka_rest_url: Option<String>,
+
+    /// The Kafka topics, separated by commas, from which the documents are consumed.
+    #[structopt(long, requires = "kafka-rest-url", env = "MEILI_KAFKA_TOPICS", use_delimiter = true)]
+    pub kafka_topics: Vec<String>,
+
+    /// The Kafka consumer group of the consumer.
+    #[structopt(long, env = "MEILI_KAFKA_CONSUMER_GROUP", default_value = "meilisearch")]
+    pub kafka_consumer_group: String,
+
+    /// The number of documents consumed before they are enqueued as an update.
+    #[structopt(long, env = "MEILI_KAFKA_BATCH_SIZE", default_value = "1000")]
+    pub kafka_batch_size: usize,
+
+    /// The maximum time, in milliseconds, the consumed documents wait before they are enqueued.
+    #[structopt(long, env = "MEILI_KAFKA_BATCH_TIMEOUT_MS", default_value = "1000")]
+    pub kafka_batch_timeout_ms: u64,
+
+    /// A JSON file describing a Postgres database and how its tables are mapped to indexes. The rows
+    /// are copied in the indexes, which are then kept in sync through a logical replication slot
+    /// decoded by the wal2json plugin.
+    #[structopt(long, env = "MEILI_POSTGRES_CDC_CONFIG", parse(from_os_str))]
+    pub postgres_cdc_config: Option<PathBuf>,
+
+    /// Recurring tasks, separated by semicolons, of the form `job=cron` where the job is `snapshot`,
+    /// `dump`, `documentsExpiration:<attribute>`, `s3Crawl:<indexUid>:s3://<bucket>/<prefix>` or
+    /// `webCrawl:<indexUid>:<url>,<url>`, e.g. `snapshot=0 3 * * *`.
+    #[structopt(long, env = "MEILI_SCHEDULES", use_delimiter = true, value_delimiter = ";")]
+    pub schedules: Vec<String>,
+
+    /// A TOML or YAML file, told apart by its extension, setting the options above by their names,
+    /// e.g. `db_path = "./data.ms"`. The options given in the environment or on the command line
+    /// take precedence over the ones of the file.
+    #[structopt(long, env = "MEILI_CONFIG_FILE", parse(from_os_str))]
+    #[serde(skip)]
+    pub config_file: Option<PathBuf>,
+
+    /// Prints the effective configuration, merged from the configuration file, the environment and
+    /// the command line, in TOML and exits. The secrets are hidden.
+    #[structopt(long)]
+    #[serde(skip)]
+    pub print_config: bool,
 }
 
 impl Opt {
+    /// Parses the options from the command line and the environment, then completes them with
+    /// the options of the configuration file if there is one.
+    pub fn from_args_and_config_file() -> Result<Opt, Box<dyn error::Error>> {
+        Opt::parse_with_config_file(|| Ok(Opt::from_args()))
+    }
+
+    /// Parses the options again, the changes made to the configuration file are taken into account.
+    /// Unlike at startup, invalid options are returned as an error instead of exiting.
+    pub fn reload() -> Result<Opt, Box<dyn error::Error>> {
+        Opt::parse_with_config_file(|| Ok(Opt::from_iter_safe(env::args_os())?))
+    }
+
+    fn parse_with_config_file<F>(parse: F) -> Result<Opt, Box<dyn error::Error>>
+    where
+        F: Fn() -> Result<Opt, Box<dyn error::Error>>,
+    {
+        // the options of a previous read of the file must not shadow the ones of the file
+        let mut exported = EXPORTED_VARS.lock().unwrap();
+        for var in exported.drain(..) {
+            env::remove_var(var);
+        }
+
+        let opt = parse()?;
+        match &opt.config_file {
+            Some(path) => {
+                // the environment variables are read back by structopt, with a lower precedence
+                // than the command line arguments
+                for (var, value) in config_file_env_vars(path)? {
+                    if env::var_os(&var).is_none() {
+                        env::set_var(&var, value);
+                        exported.push(var);
+                    }
+                }
+                parse()
+            }
+            None => Ok(opt),
+        }
+    }
+
+    /// The effective configuration in TOML, in the format of the configuration files.
+    pub fn to_config_file(&self) -> Result<String, Box<dyn error::Error>> {
+        Ok(toml::to_string(self)?)
+    }
+
     pub fn get_ssl_config(&self) -> Result<Option<rustls::ServerConfig>, Box<dyn error::Error>> {
-        if let (Some(cert_path), Some(key_path)) = (&self.ssl_cert_path, &self.ssl_key_path) {
+        let private_key = match (&self.ssl_key_path, &self.ssl_key) {
+            (Some(key_path), _) => Some(fs::read(key_path).map_err(|_| "cannot open private key file")?),
+            (None, Some(key)) => Some(resolve_secret(key)?.into_bytes()),
+            (None, None) => None,
+        };
+
+        if let (Some(cert_path), Some(private_key)) = (&self.ssl_cert_path, private_key) {
             let client_auth = match &self.ssl_auth_path {
                 Some(auth_path) => {
                     let roots = load_certs(auth_path.to_path_buf())?;
@@ -152,7 +522,7 @@ impl Opt {
             config.key_log = Arc::new(rustls::KeyLogFile::new());
 
             let certs = load_certs(cert_path.to_path_buf())?;
-            let privkey = load_private_key(key_path.to_path_buf())?;
+            let privkey = load_private_key(&private_key)?;
             let ocsp = load_ocsp(&self.ssl_ocsp_path)?;
             config
                 .set_single_cert_with_ocsp_and_sct(certs, privkey, ocsp, vec![])
@@ -173,23 +543,72 @@ impl Opt {
     }
 }
 
+fn serialize_secret<S: Serializer>(secret: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    secret.as_ref().map(|_| "<hidden>").serialize(serializer)
+}
+
+/// The name of the environment variable an option of the configuration file is read from.
+fn env_var_name(option: &str) -> String {
+    match option {
+        "sentry_dsn" => "SENTRY_DSN".to_string(),
+        option => format!("MEILI_{}", option.to_uppercase()),
+    }
+}
+
+/// Reads the options of a configuration file as the environment variables that set them.
+fn config_file_env_vars(path: &Path) -> Result<Vec<(String, String)>, Box<dyn error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read the configuration file {}: {}", path.display(), e))?;
+    let options: BTreeMap<String, Value> = match path.extension().and_then(OsStr::to_str) {
+        Some("toml") => toml::from_str(&content)?,
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&content)?,
+        _ => return Err(format!("the configuration file {} must be a .toml, .yaml or .yml file", path.display()).into()),
+    };
+
+    let known_options = serde_json::to_value(Opt::default())?;
+    let to_string = |option: &str, value: &Value| match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Bool(_) | Value::Number(_) => Ok(value.to_string()),
+        _ => Err(format!("invalid value for the option {} of the configuration file: {}", option, value)),
+    };
+
+    let mut vars = Vec::new();
+    for (option, value) in options {
+        let option = option.replace('-', "_");
+        if known_options.get(&option).is_none() {
+            return Err(format!("unknown option {} in the configuration file", option).into());
+        }
+
+        let value = match value {
+            // the flags are raised by the presence of their variable
+            Value::Null | Value::Bool(false) => continue,
+            Value::Array(values) => {
+                let delimiter = if SEMICOLON_DELIMITED_OPTIONS.contains(&option.as_str()) { ";" } else { "," };
+                let values: Result<Vec<_>, _> = values.iter().map(|value| to_string(&option, value)).collect();
+                values?.join(delimiter)
+            }
+            value => to_string(&option, &value)?,
+        };
+        vars.push((env_var_name(&option), value));
+    }
+
+    Ok(vars)
+}
+
 fn load_certs(filename: PathBuf) -> Result<Vec<rustls::Certificate>, Box<dyn error::Error>> {
     let certfile = fs::File::open(filename).map_err(|_| "cannot open certificate file")?;
     let mut reader = BufReader::new(certfile);
     Ok(certs(&mut reader).map_err(|_| "cannot read certificate file")?)
 }
 
-fn load_private_key(filename: PathBuf) -> Result<rustls::PrivateKey, Box<dyn error::Error>> {
+fn load_private_key(pem: &[u8]) -> Result<rustls::PrivateKey, Box<dyn error::Error>> {
     let rsa_keys = {
-        let keyfile =
-            fs::File::open(filename.clone()).map_err(|_| "cannot open private key file")?;
-        let mut reader = BufReader::new(keyfile);
+        let mut reader = pem;
         rsa_private_keys(&mut reader).map_err(|_| "file contains invalid rsa private key")?
     };
 
     let pkcs8_keys = {
-        let keyfile = fs::File::open(filename).map_err(|_| "cannot open private key file")?;
-        let mut reader = BufReader::new(keyfile);
+        let mut reader = pem;
         pkcs8_private_keys(&mut reader)
             .map_err(|_| "file contains invalid pkcs8 private key (encrypted keys not supported)")?
     };
@@ -215,3 +634,57 @@ fn load_ocsp(filename: &Option<PathBuf>) -> Result<Vec<u8>, Box<dyn error::Error
 
     Ok(ret)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_file_options() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("config.toml");
+        let toml = r#"
+            db_path = "/var/lib/meilisearch"
+            max-mdb-size = 1073741824
+            no_analytics = true
+            snapshot_incremental = false
+            schedules = ["snapshot=0 3 * * *", "dump=0 4 * * 0"]
+            warmup_indexes = ["movies", "books"]
+        "#;
+        fs::write(&path, toml).unwrap();
+        let vars = config_file_env_vars(&path).unwrap();
+        assert_eq!(vars, vec![
+            ("MEILI_DB_PATH".to_string(), "/var/lib/meilisearch".to_string()),
+            ("MEILI_MAX_MDB_SIZE".to_string(), "1073741824".to_string()),
+            ("MEILI_NO_ANALYTICS".to_string(), "true".to_string()),
+            ("MEILI_SCHEDULES".to_string(), "snapshot=0 3 * * *;dump=0 4 * * 0".to_string()),
+            ("MEILI_WARMUP_INDEXES".to_string(), "movies,books".to_string()),
+        ]);
+
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, "http_addr: 0.0.0.0:7700\nlog_format: json\n").unwrap();
+        let vars = config_file_env_vars(&path).unwrap();
+        assert_eq!(vars, vec![
+            ("MEILI_HTTP_ADDR".to_string(), "0.0.0.0:7700".to_string()),
+            ("MEILI_LOG_FORMAT".to_string(), "json".to_string()),
+        ]);
+
+        fs::write(&path, "unknown_option: 1\n").unwrap();
+        assert!(config_file_env_vars(&path).is_err());
+        fs::write(&path, "config_file: other.yaml\n").unwrap();
+        assert!(config_file_env_vars(&path).is_err());
+
+        let path = dir.path().join("config.ini");
+        fs::write(&path, "db_path = ./data.ms\n").unwrap();
+        assert!(config_file_env_vars(&path).is_err());
+    }
+
+    #[test]
+    fn secrets_are_hidden_from_the_printed_config() {
+        let opt = Opt { master_key: Some("masterKey".to_string()), ..Opt::default() };
+        let config = opt.to_config_file().unwrap();
+        assert!(config.contains("master_key = \"<hidden>\""));
+        assert!(!config.contains("masterKey"));
+    }
+}
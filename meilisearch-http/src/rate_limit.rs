@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::Action;
+use crate::keys::ApiKey;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+/// The lowest rate of requests, one request per day.
+const MIN_REQUESTS_PER_SECOND: f64 = 1.0 / SECONDS_PER_DAY;
+
+/// The limits of the requests made with an API key.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RateLimit {
+    /// The sustained number of requests per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_second: Option<f64>,
+    /// The number of requests that can be made at once, defaults to the requests per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+    /// The number of bytes of documents that can be sent for indexing per day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexing_bytes_per_day: Option<u64>,
+}
+
+impl RateLimit {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.requests_per_second.map_or(false, |rps| !rps.is_finite() || rps < MIN_REQUESTS_PER_SECOND) {
+            return Err("the requests per second must be at least one request per day".to_string());
+        }
+        if self.burst == Some(0) {
+            return Err("the burst must be a positive number".to_string());
+        }
+        if self.indexing_bytes_per_day == Some(0) {
+            return Err("the indexing bytes per day must be a positive number".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A bucket containing at most `capacity` tokens and refilled with `rate` tokens per second.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, rate: f64, now: Instant) -> TokenBucket {
+        TokenBucket { capacity, rate, tokens: capacity, last_refill: now }
+    }
+
+    /// Takes the tokens from the bucket, or returns the time to wait before they are available.
+    fn take(&mut self, amount: f64, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= amount {
+            self.tokens -= amount;
+            Ok(())
+        } else {
+            // the wait never exceeds a day as the rates are at least the capacity per day,
+            // it is clamped all the same as a huge wait can't be represented
+            let wait = (amount - self.tokens) / self.rate;
+            Err(Duration::from_secs_f64(if wait.is_finite() { wait.min(SECONDS_PER_DAY) } else { SECONDS_PER_DAY }))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Quota {
+    Requests,
+    IndexingBytes,
+}
+
+/// The state of the requests quota, sent back in the `X-RateLimit-*` headers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Allowance {
+    pub limit: u64,
+    pub remaining: u64,
+}
+
+/// Why a request was refused and when it can be retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exceeded {
+    pub message: String,
+    pub retry_after: Duration,
+    pub allowance: Option<Allowance>,
+}
+
+impl fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Exceeded {}
+
+/// Keeps the token buckets of the API keys, the quota already used is kept when the limits
+/// of a key are modified and the buckets of a key are removed along with it.
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<(String, Quota), TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Consumes a request, and the size of the payload when documents are sent for indexing,
+    /// from the quotas of the key. Returns the requests allowance if the key is limited.
+    pub fn check(&self, key: &ApiKey, action: Action, payload_size: u64) -> Result<Option<Allowance>, Exceeded> {
+        self.check_at(key, action, payload_size, Instant::now())
+    }
+
+    /// Whether the payloads sent with the key for the action consume its indexing quota.
+    pub fn limits_indexing_bytes(&self, key: &ApiKey, action: Action) -> bool {
+        action == Action::DocumentsAdd && key.rate_limit.as_ref().map_or(false, |limit| limit.indexing_bytes_per_day.is_some())
+    }
+
+    /// Consumes the bytes from the indexing quota of the key, used for the payloads
+    /// whose size is only known as they are received.
+    pub fn take_indexing_bytes(&self, key: &ApiKey, size: u64) -> Result<(), Exceeded> {
+        let mut buckets = self.buckets.lock().unwrap();
+        take_indexing_bytes(&mut buckets, key, size, None, Instant::now())
+    }
+
+    /// Removes the buckets of a deleted key.
+    pub fn remove(&self, key_uid: &str) {
+        self.buckets.lock().unwrap().retain(|(uid, _), _| uid != key_uid);
+    }
+
+    fn check_at(
+        &self,
+        key: &ApiKey,
+        action: Action,
+        payload_size: u64,
+        now: Instant,
+    ) -> Result<Option<Allowance>, Exceeded> {
+        let limit = match &key.rate_limit {
+            Some(limit) => limit,
+            None => return Ok(None),
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let mut allowance = None;
+        if let Some(rps) = limit.requests_per_second {
+            let capacity = limit.burst.map_or(rps.ceil(), f64::from);
+            let bucket = quota_bucket(&mut buckets, &key.uid, Quota::Requests, capacity, rps, now);
+            let result = bucket.take(1.0, now);
+            allowance = Some(Allowance { limit: capacity as u64, remaining: bucket.tokens as u64 });
+
+            if let Err(retry_after) = result {
+                return Err(Exceeded {
+                    message: format!("the key {} is limited to {} requests per second", key.uid, rps),
+                    retry_after,
+                    allowance,
+                });
+            }
+        }
+
+        if action == Action::DocumentsAdd {
+            take_indexing_bytes(&mut buckets, key, payload_size, allowance, now)?;
+        }
+
+        Ok(allowance)
+    }
+}
+
+fn take_indexing_bytes(
+    buckets: &mut HashMap<(String, Quota), TokenBucket>,
+    key: &ApiKey,
+    size: u64,
+    allowance: Option<Allowance>,
+    now: Instant,
+) -> Result<(), Exceeded> {
+    let per_day = match key.rate_limit.as_ref().and_then(|limit| limit.indexing_bytes_per_day) {
+        Some(per_day) => per_day,
+        None => return Ok(()),
+    };
+
+    let capacity = per_day as f64;
+    if size as f64 > capacity {
+        return Err(Exceeded {
+            message: format!("the payload is larger than the {} bytes the key {} can index per day", per_day, key.uid),
+            retry_after: Duration::from_secs(SECONDS_PER_DAY as u64),
+            allowance,
+        });
+    }
+
+    let bucket = quota_bucket(buckets, &key.uid, Quota::IndexingBytes, capacity, capacity / SECONDS_PER_DAY, now);
+    bucket.take(size as f64, now).map_err(|retry_after| Exceeded {
+        message: format!("the key {} exceeded its quota of {} indexed bytes per day", key.uid, per_day),
+        retry_after,
+        allowance,
+    })
+}
+
+/// Returns the bucket of the key quota, the bucket is resized when the limits of the key
+/// changed but the tokens already taken remain taken.
+fn quota_bucket<'a>(
+    buckets: &'a mut HashMap<(String, Quota), TokenBucket>,
+    key_uid: &str,
+    quota: Quota,
+    capacity: f64,
+    rate: f64,
+    now: Instant,
+) -> &'a mut TokenBucket {
+    let bucket = buckets
+        .entry((key_uid.to_string(), quota))
+        .or_insert_with(|| TokenBucket::new(capacity, rate, now));
+    if bucket.capacity != capacity || bucket.rate != rate {
+        // the bucket is refilled at its previous rate until now
+        let _ = bucket.take(0.0, now);
+        let used = bucket.capacity - bucket.tokens;
+        *bucket = TokenBucket::new(capacity, rate, now);
+        bucket.tokens = (capacity - used).max(0.0);
+    }
+    bucket
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited_key(rate_limit: RateLimit) -> ApiKey {
+        let (mut key, _) = ApiKey::new(None, None, vec![Action::All], vec!["*".to_string()], None, Some(rate_limit));
+        key.uid = "limited".to_string();
+        key
+    }
+
+    #[test]
+    fn requests_per_second() {
+        let limiter = RateLimiter::default();
+        let key = limited_key(RateLimit { requests_per_second: Some(2.0), burst: Some(3), ..RateLimit::default() });
+        let now = Instant::now();
+
+        for remaining in (0..3).rev() {
+            let allowance = limiter.check_at(&key, Action::Search, 0, now).unwrap();
+            assert_eq!(allowance, Some(Allowance { limit: 3, remaining }));
+        }
+
+        let exceeded = limiter.check_at(&key, Action::Search, 0, now).unwrap_err();
+        assert_eq!(exceeded.retry_after, Duration::from_millis(500));
+
+        // a token is refilled every half second
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.check_at(&key, Action::Search, 0, later).is_ok());
+        assert!(limiter.check_at(&key, Action::Search, 0, later).is_err());
+
+        // keys without limits are never refused
+        let mut unlimited = key.clone();
+        unlimited.rate_limit = None;
+        assert_eq!(limiter.check_at(&unlimited, Action::Search, 0, later), Ok(None));
+    }
+
+    #[test]
+    fn tiny_rates() {
+        let tiny = RateLimit { requests_per_second: Some(1e-20), ..RateLimit::default() };
+        assert!(tiny.validate().is_err());
+        assert!(RateLimit { requests_per_second: Some(1.0 / 86_400.0), ..RateLimit::default() }.validate().is_ok());
+
+        // the keys stored before the rates were bounded are refused for a day at most
+        let limiter = RateLimiter::default();
+        let key = limited_key(tiny);
+        let now = Instant::now();
+        assert!(limiter.check_at(&key, Action::Search, 0, now).is_ok());
+        let exceeded = limiter.check_at(&key, Action::Search, 0, now).unwrap_err();
+        assert_eq!(exceeded.retry_after, Duration::from_secs(86_400));
+    }
+
+    #[test]
+    fn indexing_bytes_per_day() {
+        let limiter = RateLimiter::default();
+        let key = limited_key(RateLimit { indexing_bytes_per_day: Some(1000), ..RateLimit::default() });
+        let now = Instant::now();
+
+        assert!(limiter.check_at(&key, Action::DocumentsAdd, 600, now).is_ok());
+        // only the documents additions consume the quota
+        assert!(limiter.check_at(&key, Action::Search, 600, now).is_ok());
+        assert!(limiter.check_at(&key, Action::DocumentsAdd, 600, now).is_err());
+        assert!(limiter.check_at(&key, Action::DocumentsAdd, 400, now).is_ok());
+        assert!(limiter.check_at(&key, Action::DocumentsAdd, 2000, now).is_err());
+
+        // modifying the limits of the key keeps the quota already used
+        let key = limited_key(RateLimit { indexing_bytes_per_day: Some(2000), ..RateLimit::default() });
+        assert!(limiter.check_at(&key, Action::DocumentsAdd, 2000, now).is_err());
+        assert!(limiter.check_at(&key, Action::DocumentsAdd, 1000, now).is_ok());
+        assert!(limiter.check_at(&key, Action::DocumentsAdd, 1, now).is_err());
+
+        // deleting the key removes its quota
+        limiter.remove(&key.uid);
+        assert!(limiter.check_at(&key, Action::DocumentsAdd, 2000, now).is_ok());
+    }
+
+    #[test]
+    fn indexing_bytes_taken_as_received() {
+        let limiter = RateLimiter::default();
+        let key = limited_key(RateLimit { indexing_bytes_per_day: Some(1000), ..RateLimit::default() });
+        assert!(limiter.limits_indexing_bytes(&key, Action::DocumentsAdd));
+        assert!(!limiter.limits_indexing_bytes(&key, Action::Search));
+
+        assert!(limiter.check(&key, Action::DocumentsAdd, 0).is_ok());
+        assert!(limiter.take_indexing_bytes(&key, 600).is_ok());
+        assert!(limiter.take_indexing_bytes(&key, 600).is_err());
+    }
+}
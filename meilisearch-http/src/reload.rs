@@ -0,0 +1,147 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::{error, info, warn};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::helpers::logging;
+use crate::option::Opt;
+use crate::scheduler::Job;
+use crate::Data;
+
+/// The options applied without restarting when the configuration is reloaded.
+const RELOADABLE_OPTIONS: [&str; 6] = [
+    "log_level",
+    "slow_query_threshold_ms",
+    "max_concurrent_searches",
+    "search_queue_timeout_ms",
+    "schedules",
+    "snapshot_interval_sec",
+];
+
+/// The options that changed since the configuration was last loaded.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadReport {
+    /// The options whose new value is used from now on.
+    pub applied: Vec<String>,
+    /// The options whose new value is ignored until the server is restarted.
+    pub requires_restart: Vec<String>,
+}
+
+/// Reads the options again from the command line, the environment and the configuration file,
+/// and applies the ones that can be changed while the server is running.
+pub fn reload_config(data: &Data) -> Result<ReloadReport, Error> {
+    let new_opt = Opt::reload().map_err(Error::bad_request)?;
+    let mut current = data.config.lock().unwrap();
+
+    let old_values = serde_json::to_value(&*current).map_err(Error::internal)?;
+    let new_values = serde_json::to_value(&new_opt).map_err(Error::internal)?;
+    let changed: Vec<&String> = match (&old_values, &new_values) {
+        (Value::Object(old), Value::Object(new)) => new.iter()
+            .filter(|(name, value)| old.get(*name) != Some(value))
+            .map(|(name, _)| name)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut report = ReloadReport::default();
+    for name in changed {
+        if !RELOADABLE_OPTIONS.contains(&name.as_str()) {
+            report.requires_restart.push(name.clone());
+            continue;
+        }
+
+        match name.as_str() {
+            // the logs sent to Sentry are not filtered by MeiliSearch
+            "log_level" if logging::log_filters().is_none() => {
+                report.requires_restart.push(name.clone());
+                continue;
+            }
+            "log_level" => {
+                let filters = match &new_opt.log_level {
+                    Some(filters) => filters.clone(),
+                    None => env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string()),
+                };
+                logging::set_log_filters(&filters).map_err(Error::bad_request)?;
+                current.log_level = new_opt.log_level.clone();
+            }
+            "slow_query_threshold_ms" => match (&data.slow_queries, new_opt.slow_query_threshold_ms) {
+                (Some(slow_queries), Some(threshold)) => {
+                    slow_queries.set_threshold(Duration::from_millis(threshold));
+                    current.slow_query_threshold_ms = new_opt.slow_query_threshold_ms;
+                }
+                // the slow queries log is only created at startup
+                _ => {
+                    report.requires_restart.push(name.clone());
+                    continue;
+                }
+            },
+            "max_concurrent_searches" | "search_queue_timeout_ms" => {
+                match (&data.search_limiter, new_opt.max_concurrent_searches) {
+                    (Some(limiter), Some(max)) => {
+                        limiter.set_limits(max, Duration::from_millis(new_opt.search_queue_timeout_ms));
+                        current.max_concurrent_searches = new_opt.max_concurrent_searches;
+                        current.search_queue_timeout_ms = new_opt.search_queue_timeout_ms;
+                    }
+                    // the searches are only limited if they were at startup
+                    _ => {
+                        report.requires_restart.push(name.clone());
+                        continue;
+                    }
+                }
+            }
+            "schedules" => {
+                let mut tasks = Vec::with_capacity(new_opt.schedules.len());
+                for schedule in &new_opt.schedules {
+                    tasks.push(Job::parse_with_schedule(schedule).map_err(Error::bad_request)?);
+                }
+                data.scheduler.replace_configured(tasks)?;
+                current.schedules = new_opt.schedules.clone();
+            }
+            "snapshot_interval_sec" => {
+                let interval = new_opt.snapshot_interval_sec.unwrap_or(86400);
+                data.snapshot_interval_sec.store(interval, Ordering::Relaxed);
+                current.snapshot_interval_sec = new_opt.snapshot_interval_sec;
+            }
+            _ => unreachable!(),
+        }
+        report.applied.push(name.clone());
+    }
+
+    if !report.applied.is_empty() {
+        info!("Configuration reloaded, options applied: {}", report.applied.join(", "));
+    }
+    if !report.requires_restart.is_empty() {
+        warn!("Options changed that require a restart: {}", report.requires_restart.join(", "));
+    }
+
+    Ok(report)
+}
+
+/// Reloads the configuration every time the configuration file is modified.
+pub fn watch_config_file(data: Data, path: PathBuf) {
+    let modified_at = |path: &PathBuf| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+
+    thread::spawn(move || {
+        let mut last_modified: Option<SystemTime> = modified_at(&path);
+        loop {
+            thread::sleep(Duration::from_secs(5));
+            let modified = modified_at(&path);
+            if modified == last_modified {
+                continue;
+            }
+
+            last_modified = modified;
+            if let Err(e) = reload_config(&data) {
+                error!("Unsuccessful reload of the configuration file {}: {}", path.display(), e);
+            }
+        }
+    });
+}
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use actix_web::http::header::CACHE_CONTROL;
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use bytes::Bytes;
+use futures::future::ready;
+use futures::{stream, StreamExt};
+use meilisearch_core::{Index, UpdateReader, UpdateStatus};
+use serde::Deserialize;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::routes::task::Task;
+use crate::routes::IndexParam;
+use crate::Data;
+
+/// The header sent by the clients of server-sent events when they reconnect.
+const LAST_EVENT_ID: &str = "Last-Event-ID";
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_changes);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ChangesQuery {
+    /// The update id of the last change received, the changes applied after it are sent first.
+    after: Option<u64>,
+}
+
+/// Streams the updates applied to the index as server-sent events, the id of each event is the
+/// update id. A client resumes the stream with the `after` parameter or the `Last-Event-ID` header.
+#[get("/indexes/{index_uid}/changes", wrap = "Authentication::Action(Action::TasksGet)")]
+async fn get_changes(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<ChangesQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let cursor = match params.after {
+        Some(update_id) => Some(update_id),
+        None => match req.headers().get(LAST_EVENT_ID) {
+            Some(value) => Some(
+                value.to_str().ok().and_then(|id| id.parse().ok())
+                    .ok_or_else(|| Error::bad_request("the Last-Event-ID header must be an update id"))?,
+            ),
+            None => None,
+        },
+    };
+
+    // the subscription starts before the history is read so that no change is missed in between
+    let receiver = data.change_feed.subscribe(&path.index_uid);
+    let history = match cursor {
+        Some(update_id) => {
+            let reader = data.db.update_read_txn()?;
+            changes_after(&index, &reader, &path.index_uid, update_id)?
+        }
+        None => Vec::new(),
+    };
+
+    // the changes of the history may also be received from the subscription
+    let mut sent: HashSet<u64> = history.iter().map(|task| task.status.update_id()).collect();
+    let live = receiver.filter(move |task| ready(!sent.remove(&task.status.update_id())));
+    let events = stream::iter(history)
+        .chain(live)
+        .map(|task| Ok::<_, actix_web::Error>(event(&task)));
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header(CACHE_CONTROL, "no-cache")
+        .streaming(events))
+}
+
+/// The updates processed after the given one, in the order they were applied. The updates are
+/// not applied in the order of their ids as they can be enqueued with a higher priority.
+fn changes_after(index: &Index, reader: &UpdateReader, index_uid: &str, update_id: u64) -> Result<Vec<Task>, ResponseError> {
+    let mut processed: Vec<_> = index
+        .all_updates_status(reader)?
+        .into_iter()
+        .filter_map(|status| match status {
+            UpdateStatus::Processed { content } => Some(content),
+            _ => None,
+        })
+        .collect();
+
+    let cursor = processed
+        .iter()
+        .find(|content| content.update_id == update_id)
+        .map(|content| (content.processed_at, content.update_id))
+        .ok_or_else(|| Error::bad_parameter("after", "the update is not a processed update of the index, it may have been deleted"))?;
+
+    processed.retain(|content| (content.processed_at, content.update_id) > cursor);
+    processed.sort_by_key(|content| (content.processed_at, content.update_id));
+
+    Ok(processed
+        .into_iter()
+        .map(|content| Task::new(index_uid, UpdateStatus::Processed { content }))
+        .collect())
+}
+
+fn event(task: &Task) -> Bytes {
+    let data = serde_json::to_string(task).unwrap_or_default();
+    Bytes::from(format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        task.status.update_id(),
+        task.status.update_type().name(),
+        data,
+    ))
+}
@@ -0,0 +1,22 @@
+use actix_web::{get, post, web, HttpResponse};
+
+use crate::compaction::{self, CompactionInfo};
+use crate::error::{Error, ResponseError};
+use crate::helpers::Authentication;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(compact_database).service(get_compaction_status);
+}
+
+#[post("/compact", wrap = "Authentication::Admin")]
+async fn compact_database(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let info = compaction::start_compaction(&data)?;
+    Ok(HttpResponse::Accepted().json(info))
+}
+
+#[get("/compact/status", wrap = "Authentication::Admin")]
+async fn get_compaction_status() -> Result<HttpResponse, ResponseError> {
+    let info = CompactionInfo::get_current().ok_or_else(|| Error::not_found("no compaction has been started"))?;
+    Ok(HttpResponse::Ok().json(info))
+}
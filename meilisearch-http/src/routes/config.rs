@@ -0,0 +1,18 @@
+use actix_web::{post, web, HttpResponse};
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::reload::reload_config;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(reload);
+}
+
+/// Reloads the configuration file and the environment, returns the options that were applied
+/// and the ones that require a restart.
+#[post("/config/reload", wrap = "Authentication::Admin")]
+async fn reload(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let report = reload_config(&data)?;
+    Ok(HttpResponse::Ok().json(report))
+}
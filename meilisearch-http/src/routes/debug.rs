@@ -0,0 +1,69 @@
+use actix_web::{get, post, put, web, HttpResponse};
+use meilisearch_core::analysis::analyze;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::logging;
+use crate::helpers::{Action, Authentication};
+use crate::routes::IndexParam;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_slow_queries)
+        .service(get_log_level)
+        .service(update_log_level)
+        .service(analyze_text);
+}
+
+#[get("/debug/slow-queries", wrap = "Authentication::Admin")]
+async fn get_slow_queries(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let entries = data.slow_queries.as_ref().map(|log| log.entries()).unwrap_or_default();
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct LogLevel {
+    /// The filters of the logs in the `RUST_LOG` syntax, e.g. `info,meilisearch_core=debug`.
+    filters: String,
+}
+
+#[get("/debug/log-level", wrap = "Authentication::Admin")]
+async fn get_log_level() -> Result<HttpResponse, ResponseError> {
+    let filters = logging::log_filters()
+        .ok_or(Error::bad_request("the logs are not handled by MeiliSearch"))?;
+    Ok(HttpResponse::Ok().json(LogLevel { filters }))
+}
+
+#[put("/debug/log-level", wrap = "Authentication::Admin")]
+async fn update_log_level(body: web::Json<LogLevel>) -> Result<HttpResponse, ResponseError> {
+    logging::set_log_filters(&body.filters).map_err(Error::bad_request)?;
+    Ok(HttpResponse::Ok().json(LogLevel { filters: body.into_inner().filters }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct AnalyzeBody {
+    text: String,
+}
+
+/// Shows how a text is tokenized, normalized, filtered by the stop words and expanded with the
+/// synonyms, both when it is indexed and when it is searched, under the settings of the index.
+#[post(
+    "/indexes/{index_uid}/debug/analyze",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn analyze_text(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<AnalyzeBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let analysis = analyze(&reader, &index, &body.text)?;
+
+    Ok(HttpResponse::Ok().json(analysis))
+}
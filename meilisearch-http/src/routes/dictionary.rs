@@ -0,0 +1,88 @@
+use actix_web::{web, HttpResponse};
+use actix_web::{delete, get, post};
+use meilisearch_core::settings::{SettingsUpdate, UpdateState};
+use std::collections::BTreeSet;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::routes::{IndexParam, IndexUpdateResponse};
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get).service(update).service(delete);
+}
+
+pub fn check_words(words: &BTreeSet<String>) -> Result<(), Error> {
+    match words.iter().find(|word| word.trim().is_empty()) {
+        Some(word) => Err(Error::bad_parameter("dictionary", format!("{:?} is not a word", word))),
+        None => Ok(()),
+    }
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/dictionary",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let dictionary = index.main.dictionary(&reader)?;
+
+    Ok(HttpResponse::Ok().json(dictionary))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/dictionary",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<BTreeSet<String>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let words = body.into_inner();
+    check_words(&words)?;
+
+    let settings = SettingsUpdate {
+        dictionary: UpdateState::Update(words),
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/dictionary",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        dictionary: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
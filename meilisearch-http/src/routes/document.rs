@@ -1,19 +1,27 @@
-use std::collections::{BTreeSet, HashSet};
+use std::collections::HashSet;
 
 use actix_web::{delete, get, post, put};
-use actix_web::{web, HttpResponse};
+use actix_web::error::BlockingError;
+use actix_web::{web, HttpRequest, HttpResponse};
 use indexmap::IndexMap;
-use meilisearch_core::{update, MainReader};
+use meilisearch_core::{update, Index, MainReader};
+use meilisearch_core::update::{MergeStrategy, UpdatePriority};
 use serde_json::Value;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::Data;
 use crate::error::{Error, ResponseError};
-use crate::helpers::Authentication;
+use crate::helpers::{Action, Authentication};
+use crate::helpers::import::{self, ImportFormat};
+use crate::helpers::streaming::{streaming_response, StreamFormat};
+use crate::imports::{self, ImportRequest};
 use crate::routes::{IndexParam, IndexUpdateResponse};
 
 type Document = IndexMap<String, Value>;
 
+/// The maximum number of documents that can be requested in a single fetch call.
+const MAX_FETCH_DOCUMENTS: usize = 1000;
+
 #[derive(Deserialize)]
 struct DocumentParam {
     index_uid: String,
@@ -24,15 +32,17 @@ pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(get_document)
         .service(delete_document)
         .service(get_all_documents)
+        .service(fetch_documents)
         .service(add_documents)
         .service(update_documents)
+        .service(import_documents)
         .service(delete_documents)
         .service(clear_all_documents);
 }
 
 #[get(
     "/indexes/{index_uid}/documents/{document_id}",
-    wrap = "Authentication::Public"
+    wrap = "Authentication::Action(Action::DocumentsGet)"
 )]
 async fn get_document(
     data: web::Data<Data>,
@@ -58,11 +68,12 @@ async fn get_document(
 
 #[delete(
     "/indexes/{index_uid}/documents/{document_id}",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::DocumentsDelete)"
 )]
 async fn delete_document(
     data: web::Data<Data>,
     path: web::Path<DocumentParam>,
+    params: web::Query<DeleteDocumentsQuery>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -70,6 +81,9 @@ async fn delete_document(
         .ok_or(Error::index_not_found(&path.index_uid))?;
 
     let mut documents_deletion = index.documents_deletion();
+    if let Some(priority) = params.priority {
+        documents_deletion.set_priority(priority);
+    }
     documents_deletion.delete_document_by_external_docid(path.document_id.clone());
 
     let update_id = data.db.update_write(|w| documents_deletion.finalize(w))?;
@@ -85,66 +99,116 @@ struct BrowseQuery {
     attributes_to_retrieve: Option<String>,
 }
 
-pub fn get_all_documents_sync(
-    data: &web::Data<Data>,
+/// Calls `f` with the documents of the index one after the other, in the order of their internal ids,
+/// the documents are read lazily so that the whole index can be browsed with a bounded memory.
+pub fn for_each_document(
+    index: &Index,
     reader: &MainReader,
-    index_uid: &str,
     offset: usize,
     limit: usize,
-    attributes_to_retrieve: Option<&String>
-) -> Result<Vec<Document>, Error> {
-    let index = data
-        .db
-        .open_index(index_uid)
-        .ok_or(Error::index_not_found(index_uid))?;
-
-
-    let documents_ids: Result<BTreeSet<_>, _> = index
+    attributes_to_retrieve: Option<&HashSet<&str>>,
+    mut f: impl FnMut(Document) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let documents_ids = index
         .documents_fields_counts
         .documents_ids(reader)?
         .skip(offset)
-        .take(limit)
-        .collect();
+        .take(limit);
 
-    let attributes: Option<HashSet<&str>> = attributes_to_retrieve
-        .map(|a| a.split(',').collect());
-
-    let mut documents = Vec::new();
-    for document_id in documents_ids? {
-        if let Ok(Some(document)) =
-            index.document::<Document>(reader, attributes.as_ref(), document_id)
-        {
-            documents.push(document);
+    for document_id in documents_ids {
+        if let Ok(Some(document)) = index.document::<Document>(reader, attributes_to_retrieve, document_id?) {
+            f(document)?;
         }
     }
 
-    Ok(documents)
+    Ok(())
 }
 
-#[get("/indexes/{index_uid}/documents", wrap = "Authentication::Public")]
+/// Streams the documents as a JSON array, or as NDJSON when the client accepts it,
+/// the documents are serialized while they are sent.
+#[get("/indexes/{index_uid}/documents", wrap = "Authentication::Action(Action::DocumentsGet)")]
 async fn get_all_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<BrowseQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
     let offset = params.offset.unwrap_or(0);
     let limit = params.limit.unwrap_or(20);
-    let index_uid = &path.index_uid;
+    let attributes_to_retrieve = params.into_inner().attributes_to_retrieve;
+
+    streaming_response(StreamFormat::from_request(&req), move |writer| {
+        let reader = data.db.main_read_txn()?;
+        let attributes: Option<HashSet<&str>> = attributes_to_retrieve.as_ref().map(|a| a.split(',').collect());
+        for_each_document(&index, &reader, offset, limit, attributes.as_ref(), |document| writer.write(&document))
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct FetchDocumentsBody {
+    ids: Vec<Value>,
+    attributes_to_retrieve: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FetchDocumentsResponse {
+    results: Vec<Document>,
+    missing: Vec<String>,
+}
+
+#[post(
+    "/indexes/{index_uid}/documents/fetch",
+    wrap = "Authentication::Action(Action::DocumentsGet)"
+)]
+async fn fetch_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<FetchDocumentsBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let body = body.into_inner();
+    if body.ids.len() > MAX_FETCH_DOCUMENTS {
+        return Err(Error::bad_request(
+            format!("at most {} documents can be fetched at once", MAX_FETCH_DOCUMENTS),
+        ).into());
+    }
+
+    let attributes: Option<HashSet<&str>> = body.attributes_to_retrieve
+        .as_ref()
+        .map(|a| a.iter().map(String::as_str).collect());
+
     let reader = data.db.main_read_txn()?;
-    
-    let documents = get_all_documents_sync(
-        &data,
-        &reader,
-        index_uid,
-        offset,
-        limit,
-        params.attributes_to_retrieve.as_ref()
-    )?;
-
-    Ok(HttpResponse::Ok().json(documents))
+
+    let mut results = Vec::new();
+    let mut missing = Vec::new();
+    for document_id in &body.ids {
+        let document_id = update::value_to_string(document_id);
+        let document = match index.main.external_to_internal_docid(&reader, &document_id)? {
+            Some(internal_id) => index.document::<Document>(&reader, attributes.as_ref(), internal_id)?,
+            None => None,
+        };
+
+        match document {
+            Some(document) => results.push(document),
+            None => missing.push(document_id),
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(FetchDocumentsResponse { results, missing }))
 }
 
-fn find_primary_key(document: &IndexMap<String, Value>) -> Option<String> {
+pub(crate) fn find_primary_key(document: &IndexMap<String, Value>) -> Option<String> {
     for key in document.keys() {
         if key.to_lowercase().contains("id") {
             return Some(key.to_string());
@@ -153,10 +217,152 @@ fn find_primary_key(document: &IndexMap<String, Value>) -> Option<String> {
     None
 }
 
-#[derive(Deserialize)]
+/// Sets the primary key of the index if it doesn't have one yet, either from the one
+/// given by the user or by infering it from the first document, and returns it.
+pub(crate) fn ensure_primary_key(
+    data: &Data,
+    index: &Index,
+    primary_key: Option<&String>,
+    first_document: Option<&Document>,
+) -> Result<String, ResponseError> {
+    index.check_writable()?;
+    let reader = data.db.main_read_txn()?;
+
+    let mut schema = index
+        .main
+        .schema(&reader)?
+        .ok_or(meilisearch_core::Error::SchemaMissing)?;
+
+    if let Some(primary_key) = schema.primary_key() {
+        return Ok(primary_key.to_string());
+    }
+
+    let id = match primary_key {
+        Some(id) => id.to_string(),
+        None => first_document
+            .and_then(find_primary_key)
+            .ok_or(meilisearch_core::Error::MissingPrimaryKey)?
+    };
+
+    schema
+        .set_primary_key(&id)
+        .map_err(Error::bad_request)?;
+
+    data.db.main_write(|w| index.main.put_schema(w, &schema))?;
+
+    Ok(id)
+}
+
+#[derive(Default, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
-struct UpdateDocumentsQuery {
+pub(crate) struct UpdateDocumentsQuery {
+    pub(crate) primary_key: Option<String>,
+    pub(crate) merge_strategy: Option<MergeStrategy>,
+    pub(crate) dry_run: Option<bool>,
+    pub(crate) priority: Option<UpdatePriority>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentValidationError {
+    index: usize,
+    document_id: Option<String>,
+    message: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DryRunReport {
+    number_of_documents: usize,
     primary_key: Option<String>,
+    errors: Vec<DocumentValidationError>,
+}
+
+/// Checks the documents against the current state of the index without modifying it:
+/// every document must contain a valid document id, faceted attributes must be strings
+/// or arrays of strings, and attributes used in custom ranking rules must be numbers.
+fn validate_documents(
+    data: &web::Data<Data>,
+    index: &Index,
+    primary_key: Option<&String>,
+    documents: &[Document],
+) -> Result<DryRunReport, ResponseError> {
+    let reader = data.db.main_read_txn()?;
+
+    let schema = index.main.schema(&reader)?;
+    let primary_key = match schema.as_ref().and_then(|s| s.primary_key()) {
+        Some(primary_key) => Some(primary_key.to_string()),
+        None => primary_key.cloned().or_else(|| documents.first().and_then(find_primary_key)),
+    };
+
+    let faceted_attributes: Vec<String> = match (&schema, index.main.attributes_for_faceting(&reader)?) {
+        (Some(schema), Some(attributes)) => attributes
+            .iter()
+            .filter_map(|id| schema.name(*id).map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let ranked_attributes: Vec<String> = index.main
+        .ranking_rules(&reader)?
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|rule| rule.field().map(str::to_string))
+        .collect();
+
+    let mut errors = Vec::new();
+    for (i, document) in documents.iter().enumerate() {
+        let mut push_error = |document_id: &Option<String>, message: String| {
+            errors.push(DocumentValidationError { index: i, document_id: document_id.clone(), message });
+        };
+
+        let document_id = match &primary_key {
+            Some(primary_key) => match document.get(primary_key) {
+                Some(value) => match update::value_to_document_id(value) {
+                    Ok(document_id) => Some(document_id),
+                    Err(e) => {
+                        push_error(&None, e.to_string());
+                        None
+                    }
+                },
+                None => {
+                    push_error(&None, format!("missing primary key {:?}", primary_key));
+                    None
+                }
+            },
+            None => {
+                push_error(&None, meilisearch_core::Error::MissingPrimaryKey.to_string());
+                None
+            }
+        };
+
+        for attribute in &faceted_attributes {
+            let values = match document.get(attribute) {
+                Some(Value::Array(values)) => values.iter().collect(),
+                Some(value) => vec![value],
+                None => Vec::new(),
+            };
+            if values.iter().any(|v| !matches!(v, Value::String(_) | Value::Null)) {
+                push_error(&document_id, format!(
+                    "faceted attribute {:?} must be a string or an array of strings",
+                    attribute,
+                ));
+            }
+        }
+
+        for attribute in &ranked_attributes {
+            if let Some(value) = document.get(attribute) {
+                if !value.is_null() && update::value_to_number(value).is_none() {
+                    push_error(&document_id, format!(
+                        "attribute {:?} is used in a ranking rule and must be a number or a date",
+                        attribute,
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(DryRunReport { number_of_documents: documents.len(), primary_key, errors })
 }
 
 async fn update_multiple_documents(
@@ -171,45 +377,56 @@ async fn update_multiple_documents(
         .open_index(&path.index_uid)
         .ok_or(Error::index_not_found(&path.index_uid))?;
 
-    let reader = data.db.main_read_txn()?;
-
-    let mut schema = index
-        .main
-        .schema(&reader)?
-        .ok_or(meilisearch_core::Error::SchemaMissing)?;
+    if params.dry_run.unwrap_or(false) {
+        let report = validate_documents(&data, &index, params.primary_key.as_ref(), &body)?;
+        return Ok(HttpResponse::Ok().json(report));
+    }
 
-    if schema.primary_key().is_none() {
-        let id = match &params.primary_key {
-            Some(id) => id.to_string(),
-            None => body
-                .first()
-                .and_then(find_primary_key)
-                .ok_or(meilisearch_core::Error::MissingPrimaryKey)?
-        };
+    let update_id = enqueue_documents(&data, &index, &params, body.into_inner(), is_partial)?;
 
-        schema
-            .set_primary_key(&id)
-            .map_err(Error::bad_request)?;
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
 
-        data.db.main_write(|w| index.main.put_schema(w, &schema))?;
-    }
+/// Enqueues the addition, or the partial update when `is_partial`, of the documents
+/// and returns the id of the update.
+pub(crate) fn enqueue_documents(
+    data: &Data,
+    index: &Index,
+    params: &UpdateDocumentsQuery,
+    documents: Vec<Document>,
+    is_partial: bool,
+) -> Result<u64, ResponseError> {
+    let primary_key = ensure_primary_key(data, index, params.primary_key.as_ref(), documents.first())?;
 
     let mut document_addition = if is_partial {
-        index.documents_partial_addition()
+        let mut addition = index.documents_partial_addition();
+        if let Some(merge_strategy) = params.merge_strategy {
+            addition.set_merge_strategy(merge_strategy);
+        }
+        addition
     } else {
+        if params.merge_strategy.is_some() {
+            return Err(Error::bad_parameter(
+                "mergeStrategy",
+                "only available for partial documents updates",
+            ).into());
+        }
         index.documents_addition()
     };
 
-    for document in body.into_inner() {
-        document_addition.update_document(document);
+    if let Some(priority) = params.priority {
+        document_addition.set_priority(priority);
     }
+    document_addition.set_primary_key(primary_key);
 
-    let update_id = data.db.update_write(|w| document_addition.finalize(w))?;
+    for document in documents {
+        document_addition.update_document(document);
+    }
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(data.db.update_write(|w| document_addition.finalize(w))?)
 }
 
-#[post("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
+#[post("/indexes/{index_uid}/documents", wrap = "Authentication::Action(Action::DocumentsAdd)")]
 async fn add_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -219,7 +436,7 @@ async fn add_documents(
     update_multiple_documents(data, path, params, body, false).await
 }
 
-#[put("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
+#[put("/indexes/{index_uid}/documents", wrap = "Authentication::Action(Action::DocumentsAdd)")]
 async fn update_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -229,13 +446,66 @@ async fn update_documents(
     update_multiple_documents(data, path, params, body, true).await
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ImportDocumentsBody {
+    url: String,
+    #[serde(default)]
+    format: ImportFormat,
+    authorization: Option<String>,
+    primary_key: Option<String>,
+    /// The settings to apply before the documents are added, in the format of the documents.
+    settings_url: Option<String>,
+}
+
+/// Enqueues the import of the files as an update of the index, they are downloaded when it is
+/// processed. The urls are checked before the import is enqueued.
+#[post("/indexes/{index_uid}/documents/import", wrap = "Authentication::Action(Action::DocumentsAdd)")]
+async fn import_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<ImportDocumentsBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data.db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let body = body.into_inner();
+    let urls = vec![Some(body.url.clone()), body.settings_url.clone()];
+    let s3_buckets = data.config.lock().unwrap().import_s3_buckets.clone();
+    web::block(move || urls.iter().flatten().try_for_each(|url| import::check_url(url, &s3_buckets)))
+        .await
+        .map_err(|e| match e {
+            BlockingError::Error(e) => e,
+            BlockingError::Canceled => Error::internal("documents import was canceled"),
+        })?;
+
+    let request = ImportRequest {
+        url: body.url,
+        format: body.format,
+        authorization: body.authorization,
+        primary_key: body.primary_key,
+        settings_url: body.settings_url,
+    };
+    let update_id = imports::enqueue(&data, &index, request)?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct DeleteDocumentsQuery {
+    priority: Option<UpdatePriority>,
+}
+
 #[post(
     "/indexes/{index_uid}/documents/delete-batch",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::DocumentsDelete)"
 )]
 async fn delete_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    params: web::Query<DeleteDocumentsQuery>,
     body: web::Json<Vec<Value>>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -243,20 +513,32 @@ async fn delete_documents(
         .open_index(&path.index_uid)
         .ok_or(Error::index_not_found(&path.index_uid))?;
 
+    let update_id = enqueue_documents_deletion(&data, &index, params.priority, body.into_inner())?;
 
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+/// Enqueues the deletion of the documents and returns the id of the update.
+pub(crate) fn enqueue_documents_deletion(
+    data: &Data,
+    index: &Index,
+    priority: Option<UpdatePriority>,
+    document_ids: Vec<Value>,
+) -> Result<u64, ResponseError> {
     let mut documents_deletion = index.documents_deletion();
+    if let Some(priority) = priority {
+        documents_deletion.set_priority(priority);
+    }
 
-    for document_id in body.into_inner() {
+    for document_id in document_ids {
         let document_id = update::value_to_string(&document_id);
         documents_deletion.delete_document_by_external_docid(document_id);
     }
 
-    let update_id = data.db.update_write(|w| documents_deletion.finalize(w))?;
-
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(data.db.update_write(|w| documents_deletion.finalize(w))?)
 }
 
-#[delete("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
+#[delete("/indexes/{index_uid}/documents", wrap = "Authentication::Action(Action::DocumentsDelete)")]
 async fn clear_all_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -1,21 +1,40 @@
 use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
 use std::path::Path;
 
-use actix_web::{get, post};
+use actix_web::error::{BlockingError, PayloadError};
+use actix_web::{delete, get, post};
 use actix_web::{HttpResponse, web};
+use chrono::Utc;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 
-use crate::dump::{DumpInfo, DumpStatus, compressed_dumps_folder, init_dump_process};
+use crate::dump::{self, DumpInfo, DumpStatus, compressed_dumps_folder, dump_path, init_dump_process, is_dump_in_progress, list_dumps};
 use crate::Data;
 use crate::error::{Error, ResponseError};
-use crate::helpers::Authentication;
+use crate::helpers::download::{file_response, open_file_response};
+use crate::helpers::{Action, Authentication};
+use crate::routes::IndexParam;
+use crate::routes::index::{is_valid_index_uid, restore_trashed_index_sync};
 
 pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(trigger_dump)
-        .service(get_dump_status);
+        .service(get_dumps)
+        .service(get_dump_status)
+        .service(download_dump)
+        .service(delete_dump)
+        .service(export_index)
+        .service(restore_index);
 }
 
-#[post("/dumps", wrap = "Authentication::Private")]
+fn blocking_error(e: BlockingError<Error>) -> Error {
+    match e {
+        BlockingError::Error(e) => e,
+        BlockingError::Canceled => Error::internal("the operation was canceled"),
+    }
+}
+
+#[post("/dumps", wrap = "Authentication::Action(Action::DumpsCreate)")]
 async fn trigger_dump(
     data: web::Data<Data>,
 ) -> Result<HttpResponse, ResponseError> {
@@ -37,7 +56,7 @@ struct DumpParam {
     dump_uid: String,
 }
 
-#[get("/dumps/{dump_uid}/status", wrap = "Authentication::Private")]
+#[get("/dumps/{dump_uid}/status", wrap = "Authentication::Action(Action::DumpsGet)")]
 async fn get_dump_status(
     data: web::Data<Data>,
     path: web::Path<DumpParam>,
@@ -62,3 +81,105 @@ async fn get_dump_status(
         Err(Error::not_found("dump does not exist").into())
     }
 }
+
+#[get("/dumps", wrap = "Authentication::Action(Action::DumpsGet)")]
+async fn get_dumps(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let dumps = list_dumps(Path::new(&data.dumps_folder))?;
+    Ok(HttpResponse::Ok().json(dumps))
+}
+
+#[get("/dumps/{dump_uid}/download", wrap = "Authentication::Action(Action::DumpsGet)")]
+async fn download_dump(
+    data: web::Data<Data>,
+    path: web::Path<DumpParam>,
+) -> Result<HttpResponse, ResponseError> {
+    match dump_path(Path::new(&data.dumps_folder), &path.dump_uid) {
+        Some(dump_path) => file_response(&dump_path, &format!("{}.tar.gz", path.dump_uid)),
+        None => Err(Error::not_found("dump does not exist").into()),
+    }
+}
+
+#[delete("/dumps/{dump_uid}", wrap = "Authentication::Action(Action::DumpsDelete)")]
+async fn delete_dump(
+    data: web::Data<Data>,
+    path: web::Path<DumpParam>,
+) -> Result<HttpResponse, ResponseError> {
+    if is_dump_in_progress(&path.dump_uid) {
+        return Err(Error::dump_conflict().into());
+    }
+
+    match dump_path(Path::new(&data.dumps_folder), &path.dump_uid) {
+        Some(dump_path) => {
+            std::fs::remove_file(dump_path).map_err(Error::from)?;
+            Ok(HttpResponse::NoContent().finish())
+        }
+        None => Err(Error::not_found("dump does not exist").into()),
+    }
+}
+
+#[get("/indexes/{index_uid}/export", wrap = "Authentication::Action(Action::IndexesExport)")]
+async fn export_index(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index_uid = path.index_uid.clone();
+    let archive = {
+        let data = data.clone();
+        let index_uid = index_uid.clone();
+        web::block(move || {
+            // the archive is written to an anonymous file, removed once it has been sent
+            let mut file = dump::export_index(&data, &index_uid, tempfile::tempfile()?)?;
+            file.seek(SeekFrom::Start(0))?;
+            Ok::<_, Error>(file)
+        })
+        .await
+        .map_err(blocking_error)?
+    };
+
+    let name = format!("{}-{}.tar.gz", index_uid, Utc::now().format("%Y%m%d-%H%M%S"));
+    open_file_response(archive, &name)
+}
+
+#[post("/indexes/{index_uid}/restore", wrap = "Authentication::Action(Action::IndexesRestore)")]
+async fn restore_index(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    mut payload: web::Payload,
+) -> Result<HttpResponse, ResponseError> {
+    let index_uid = path.index_uid.clone();
+    if !is_valid_index_uid(&index_uid) {
+        return Err(Error::InvalidIndexUid.into());
+    }
+    if data.db.open_index(&index_uid).is_some() {
+        return Err(Error::IndexAlreadyExists(index_uid).into());
+    }
+
+    // the archive can be larger than the memory, it is written to an anonymous file as it is received
+    let mut file = web::block(|| tempfile::tempfile().map_err(Error::from)).await.map_err(blocking_error)?;
+    let mut received = 0;
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| match e {
+            PayloadError::Overflow => Error::PayloadTooLarge,
+            e => Error::bad_request(format!("Problem while receiving the archive: {}", e)),
+        })?;
+        received += chunk.len();
+        file = web::block(move || file.write_all(&chunk).map(|_| file).map_err(Error::from))
+            .await
+            .map_err(blocking_error)?;
+    }
+
+    let index = web::block(move || {
+        // without an archive the index is brought back from the trash
+        if received == 0 {
+            if let Some(index) = restore_trashed_index_sync(&data, &index_uid)? {
+                return Ok(index);
+            }
+        }
+        file.seek(SeekFrom::Start(0))?;
+        dump::restore_index(&data, file, &index_uid)
+    })
+    .await
+    .map_err(blocking_error)?;
+
+    Ok(HttpResponse::Created().json(index))
+}
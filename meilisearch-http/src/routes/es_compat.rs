@@ -0,0 +1,479 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::routes::search::{search_index, SearchQueryPost};
+use crate::routes::IndexParam;
+use crate::Data;
+
+/// The number of buckets of a terms aggregation, when not given.
+const DEFAULT_BUCKETS: usize = 10;
+/// The parameters of the search DSL that are accepted without changing the results.
+const IGNORED_PARAMETERS: &[&str] = &["track_total_hits", "timeout", "explain", "version"];
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(search_with_get).service(search_with_post);
+}
+
+#[derive(Deserialize)]
+struct UriSearchQuery {
+    q: Option<String>,
+    from: Option<usize>,
+    size: Option<usize>,
+}
+
+/// The body is optional, the searches can also be made with the parameters of the URI search.
+#[get("/es-compat/{index_uid}/_search", wrap = "Authentication::Action(Action::Search)")]
+async fn search_with_get(
+    req: HttpRequest,
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<UriSearchQuery>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ResponseError> {
+    es_search(req, data, &path.index_uid, params.into_inner(), &body).await
+}
+
+#[post("/es-compat/{index_uid}/_search", wrap = "Authentication::Action(Action::Search)")]
+async fn search_with_post(
+    req: HttpRequest,
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<UriSearchQuery>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ResponseError> {
+    es_search(req, data, &path.index_uid, params.into_inner(), &body).await
+}
+
+/// Translates an Elasticsearch search into a native search and its results into the response
+/// of Elasticsearch. The hits have no score, the engine ranks them without computing one.
+async fn es_search(
+    req: HttpRequest,
+    data: web::Data<Data>,
+    index_uid: &str,
+    params: UriSearchQuery,
+    body: &[u8],
+) -> Result<HttpResponse, ResponseError> {
+    data.features.ensure_enabled("esCompat", |features| features.es_compat)?;
+
+    let body: Value = if body.iter().all(u8::is_ascii_whitespace) {
+        json!({})
+    } else {
+        serde_json::from_slice(body).map_err(|e| Error::bad_request(format!("invalid search body: {}", e)))?
+    };
+
+    let mut search = translate_search(&body)?;
+    if let Some(q) = params.q {
+        search.query.q = Some(match search.query.q.take() {
+            Some(words) => format!("{} {}", q, words),
+            None => q,
+        });
+    }
+    search.query.offset = params.from.or(search.query.offset);
+    search.query.limit = params.size.or(search.query.limit);
+
+    let primary_key = {
+        let index = data.db.open_index(index_uid).ok_or(Error::index_not_found(index_uid))?;
+        let reader = data.db.main_read_txn()?;
+        index.main.schema(&reader)?.and_then(|schema| schema.primary_key().map(str::to_string))
+    };
+
+    // the primary key is always retrieved to be the id of the hits
+    if let (Some(attributes), Some(primary_key)) = (&mut search.query.attributes_to_retrieve, &primary_key) {
+        if !attributes.contains(primary_key) {
+            attributes.push(primary_key.clone());
+        }
+    }
+
+    let result = search_index(&req, data, index_uid, search.query.into()).await?;
+
+    let hits: Vec<_> = result.hits.into_iter().map(|hit| {
+        let id = primary_key.as_ref().and_then(|key| hit.document.get(key)).map(|id| match id {
+            Value::String(id) => id.clone(),
+            id => id.to_string(),
+        });
+        let mut source = hit.document;
+        if let (Some(includes), Some(primary_key)) = (&search.source, &primary_key) {
+            if !includes.contains(primary_key) {
+                source.shift_remove(primary_key);
+            }
+        }
+
+        let mut hit = json!({ "_index": index_uid, "_id": id, "_score": null });
+        if search.with_source {
+            hit["_source"] = json!(source);
+        }
+        hit
+    }).collect();
+
+    let mut response = json!({
+        "took": result.processing_time_ms,
+        "timed_out": false,
+        "_shards": { "total": 1, "successful": 1, "skipped": 0, "failed": 0 },
+        "hits": {
+            "total": {
+                "value": result.nb_hits,
+                "relation": if result.exhaustive_nb_hits { "eq" } else { "gte" },
+            },
+            "max_score": null,
+            "hits": hits,
+        },
+    });
+
+    if !search.aggregations.is_empty() {
+        let distribution = result.facets_distribution.unwrap_or_default();
+        let aggregations: Map<String, Value> = search.aggregations.into_iter().map(|(name, field, size)| {
+            let mut buckets: Vec<_> = distribution.get(&field).into_iter().flatten().collect();
+            buckets.sort_by(|(a_key, a_count), (b_key, b_count)| b_count.cmp(a_count).then_with(|| a_key.cmp(b_key)));
+            let sum_other_doc_count: usize = buckets.iter().skip(size).map(|(_, count)| **count).sum();
+            let buckets: Vec<_> = buckets.into_iter().take(size).map(|(key, count)| json!({ "key": key, "doc_count": count })).collect();
+            (name, json!({ "doc_count_error_upper_bound": 0, "sum_other_doc_count": sum_other_doc_count, "buckets": buckets }))
+        }).collect();
+        response["aggregations"] = Value::Object(aggregations);
+    }
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// A search of the Elasticsearch DSL translated into a native search.
+#[derive(Default)]
+struct Search {
+    query: SearchQueryPost,
+    /// The attributes of the `_source` requested, all when `None`.
+    source: Option<Vec<String>>,
+    with_source: bool,
+    /// The name, field and number of buckets of the terms aggregations.
+    aggregations: Vec<(String, String, usize)>,
+}
+
+fn unsupported(what: impl std::fmt::Display) -> Error {
+    Error::bad_request(format!("{} is not supported by the Elasticsearch compatibility layer", what))
+}
+
+fn translate_search(body: &Value) -> Result<Search, Error> {
+    let body = body.as_object().ok_or_else(|| Error::bad_request("the search body must be an object"))?;
+    let mut search = Search { with_source: true, ..Search::default() };
+
+    for (parameter, value) in body {
+        match parameter.as_str() {
+            "query" => {
+                let clause = translate_query(value)?;
+                search.query.q = Some(clause.words.join(" ")).filter(|q| !q.is_empty());
+                search.query.filters = clause.filter;
+            }
+            "from" => search.query.offset = Some(as_usize(value, "from")?),
+            "size" => search.query.limit = Some(as_usize(value, "size")?),
+            "_source" => match value {
+                Value::Bool(with_source) => search.with_source = *with_source,
+                Value::String(_) | Value::Array(_) => search.source = Some(strings(value, "_source")?),
+                Value::Object(source) => match source.get("includes") {
+                    Some(includes) => search.source = Some(strings(includes, "_source.includes")?),
+                    None if source.is_empty() => (),
+                    None => return Err(unsupported("_source without includes")),
+                },
+                _ => return Err(Error::bad_parameter("_source", "must be a boolean, a string, an array or an object")),
+            },
+            "sort" => {
+                let by_score = match value {
+                    Value::String(field) => field == "_score",
+                    Value::Array(fields) => fields.iter().all(|field| *field == "_score" || field.get("_score").is_some()),
+                    _ => false,
+                };
+                if !by_score {
+                    return Err(unsupported("sorting on other fields than the _score, use ranking rules instead,"));
+                }
+            }
+            "aggs" | "aggregations" => {
+                let aggregations = value.as_object().ok_or_else(|| Error::bad_parameter(parameter, "must be an object"))?;
+                for (name, aggregation) in aggregations {
+                    let terms = aggregation.get("terms").ok_or_else(|| unsupported(format!("the aggregation {:?}, only terms aggregations are", name)))?;
+                    let field = terms.get("field").and_then(Value::as_str).ok_or_else(|| Error::bad_parameter("terms", "must have a field"))?;
+                    let size = terms.get("size").map(|size| as_usize(size, "size")).transpose()?.unwrap_or(DEFAULT_BUCKETS);
+                    search.aggregations.push((name.clone(), field.to_string(), size));
+                }
+            }
+            parameter if IGNORED_PARAMETERS.contains(&parameter) => (),
+            parameter => return Err(unsupported(format!("the parameter {:?}", parameter))),
+        }
+    }
+
+    if let Some(source) = &search.source {
+        search.query.attributes_to_retrieve = Some(source.clone());
+    }
+    if !search.aggregations.is_empty() {
+        search.query.facets_distribution = Some(search.aggregations.iter().map(|(_, field, _)| field.clone()).collect());
+    }
+
+    Ok(search)
+}
+
+/// The translation of a query clause: the words searched and the filter of the documents.
+#[derive(Debug, Default, PartialEq)]
+struct Clause {
+    words: Vec<String>,
+    filter: Option<String>,
+}
+
+impl Clause {
+    fn filter(filter: String) -> Clause {
+        Clause { words: Vec::new(), filter: Some(filter) }
+    }
+
+    /// The clauses of the filter context can't search words.
+    fn into_filter(self, context: &str) -> Result<Option<String>, Error> {
+        if self.words.is_empty() {
+            Ok(self.filter)
+        } else {
+            Err(unsupported(format!("full-text queries in the {} context", context)))
+        }
+    }
+}
+
+fn translate_query(query: &Value) -> Result<Clause, Error> {
+    let (name, params) = match query.as_object() {
+        Some(query) if query.len() == 1 => query.iter().next().unwrap(),
+        _ => return Err(Error::bad_request("a query clause must be an object with a single query")),
+    };
+
+    match name.as_str() {
+        "match_all" => Ok(Clause::default()),
+        "match" | "match_phrase" | "match_phrase_prefix" | "match_bool_prefix" => {
+            let (_, value) = single_field(params, name)?;
+            let text = match value {
+                Value::Object(options) => options.get("query").ok_or_else(|| Error::bad_parameter(name, "must have a query"))?,
+                value => value,
+            };
+            Ok(Clause { words: vec![scalar_text(text, name)?], filter: None })
+        }
+        "multi_match" | "query_string" | "simple_query_string" => {
+            let text = params.get("query").ok_or_else(|| Error::bad_parameter(name, "must have a query"))?;
+            Ok(Clause { words: vec![scalar_text(text, name)?], filter: None })
+        }
+        "term" => {
+            let (field, value) = single_field(params, name)?;
+            let value = match value {
+                Value::Object(options) => options.get("value").ok_or_else(|| Error::bad_parameter(name, "must have a value"))?,
+                value => value,
+            };
+            Ok(Clause::filter(condition(field, "=", value)?))
+        }
+        "terms" => {
+            let (field, values) = single_field(params, name)?;
+            let values = values.as_array().ok_or_else(|| Error::bad_parameter(name, "must be an array of values"))?;
+            let conditions = values.iter().map(|value| condition(field, "=", value)).collect::<Result<Vec<_>, _>>()?;
+            match combine(conditions, "OR") {
+                Some(filter) => Ok(Clause::filter(filter)),
+                None => Err(Error::bad_parameter(name, "must contain at least one value")),
+            }
+        }
+        "range" => {
+            let (field, bounds) = single_field(params, name)?;
+            let bounds = bounds.as_object().ok_or_else(|| Error::bad_parameter(name, "must be an object of bounds"))?;
+            let mut conditions = Vec::new();
+            for (bound, value) in bounds {
+                let operator = match bound.as_str() {
+                    "gt" => ">",
+                    "gte" => ">=",
+                    "lt" => "<",
+                    "lte" => "<=",
+                    bound => return Err(unsupported(format!("the range parameter {:?}", bound))),
+                };
+                if !value.is_number() {
+                    return Err(Error::bad_parameter(name, "the bounds of the ranges must be numbers"));
+                }
+                conditions.push(condition(field, operator, value)?);
+            }
+            combine(conditions, "AND").map(Clause::filter).ok_or_else(|| Error::bad_parameter(name, "must have a bound"))
+        }
+        "bool" => translate_bool(params),
+        name => Err(unsupported(format!("the {:?} query", name))),
+    }
+}
+
+/// The must clauses are required, as well as the should clauses when there are neither must nor
+/// filter clauses. The optional should clauses don't change the results and are ignored.
+fn translate_bool(params: &Value) -> Result<Clause, Error> {
+    let params = params.as_object().ok_or_else(|| Error::bad_parameter("bool", "must be an object"))?;
+    let clauses = |occurrence: &str| -> Result<Vec<Clause>, Error> {
+        match params.get(occurrence) {
+            Some(Value::Array(queries)) => queries.iter().map(translate_query).collect(),
+            Some(query) => Ok(vec![translate_query(query)?]),
+            None => Ok(Vec::new()),
+        }
+    };
+
+    for occurrence in params.keys() {
+        if !matches!(occurrence.as_str(), "must" | "filter" | "should" | "must_not" | "minimum_should_match" | "boost") {
+            return Err(unsupported(format!("the bool parameter {:?}", occurrence)));
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut filters = Vec::new();
+
+    let must = clauses("must")?;
+    let filter = clauses("filter")?;
+    let should_required = match params.get("minimum_should_match") {
+        Some(minimum) => *minimum != 0 && *minimum != "0",
+        None => must.is_empty() && filter.is_empty(),
+    };
+
+    for clause in must {
+        words.extend(clause.words);
+        filters.extend(clause.filter);
+    }
+    for clause in filter {
+        filters.extend(clause.into_filter("filter")?);
+    }
+    for clause in clauses("must_not")? {
+        if let Some(filter) = clause.into_filter("must_not")? {
+            filters.push(format!("NOT ({})", filter));
+        }
+    }
+
+    if should_required {
+        let mut should_filters = Vec::new();
+        for clause in clauses("should")? {
+            words.extend(clause.words);
+            should_filters.extend(clause.filter);
+        }
+        filters.extend(combine(should_filters, "OR"));
+    }
+
+    Ok(Clause { words, filter: combine(filters, "AND") })
+}
+
+fn combine(filters: Vec<String>, operator: &str) -> Option<String> {
+    match filters.len() {
+        0 => None,
+        1 => filters.into_iter().next(),
+        _ => Some(filters.iter().map(|filter| format!("({})", filter)).collect::<Vec<_>>().join(&format!(" {} ", operator))),
+    }
+}
+
+fn single_field<'a>(params: &'a Value, query: &str) -> Result<(&'a str, &'a Value), Error> {
+    match params.as_object() {
+        Some(fields) if fields.len() == 1 => {
+            let (field, value) = fields.iter().next().unwrap();
+            Ok((field.as_str(), value))
+        }
+        _ => Err(Error::bad_parameter(query, "must target a single field")),
+    }
+}
+
+fn condition(field: &str, operator: &str, value: &Value) -> Result<String, Error> {
+    let value = match value {
+        Value::String(s) => quote(s),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        _ => return Err(Error::bad_parameter(field, "the values of the filters must be strings, numbers or booleans")),
+    };
+    Ok(format!("{} {} {}", key(field), operator, value))
+}
+
+/// The attributes made of other characters than letters, digits, `_`, `-` and `.` are quoted.
+fn key(field: &str) -> String {
+    if !field.is_empty() && field.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.') {
+        field.to_string()
+    } else {
+        quote(field)
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn scalar_text(value: &Value, query: &str) -> Result<String, Error> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(Error::bad_parameter(query, "the text searched must be a string")),
+    }
+}
+
+fn as_usize(value: &Value, parameter: &str) -> Result<usize, Error> {
+    value.as_u64().map(|n| n as usize).ok_or_else(|| Error::bad_parameter(parameter, "must be a positive integer"))
+}
+
+fn strings(value: &Value, parameter: &str) -> Result<Vec<String>, Error> {
+    match value {
+        Value::String(s) => Ok(vec![s.clone()]),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| value.as_str().map(str::to_string).ok_or_else(|| Error::bad_parameter(parameter, "must contain strings")))
+            .collect(),
+        _ => Err(Error::bad_parameter(parameter, "must be a string or an array of strings")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_queries() {
+        let clause = translate_query(&json!({
+            "bool": {
+                "must": [{ "match": { "title": "wonder woman" } }],
+                "filter": [
+                    { "term": { "genre": "Action \"hero\"" } },
+                    { "range": { "release_year": { "gte": 2000, "lt": 2020 } } },
+                    { "terms": { "director name": ["Jenkins", 42] } },
+                ],
+                "must_not": { "term": { "adult": { "value": true } } },
+                "should": [{ "match": { "overview": "amazon" } }],
+            }
+        })).unwrap();
+
+        assert_eq!(clause.words, vec!["wonder woman"]);
+        assert_eq!(
+            clause.filter.unwrap(),
+            "(genre = \"Action \\\"hero\\\"\") AND ((release_year >= 2000) AND (release_year < 2020)) \
+             AND ((\"director name\" = \"Jenkins\") OR (\"director name\" = 42)) AND (NOT (adult = true))",
+        );
+    }
+
+    #[test]
+    fn should_clauses_are_required_alone() {
+        let clause = translate_query(&json!({
+            "bool": { "should": [{ "term": { "genre": "Drama" } }, { "term": { "genre": "Romance" } }] }
+        })).unwrap();
+        assert_eq!(clause.filter.unwrap(), "(genre = \"Drama\") OR (genre = \"Romance\")");
+
+        let clause = translate_query(&json!({ "match_all": {} })).unwrap();
+        assert_eq!(clause, Clause::default());
+    }
+
+    #[test]
+    fn unsupported_queries() {
+        assert!(translate_query(&json!({ "fuzzy": { "title": "wonder" } })).is_err());
+        assert!(translate_query(&json!({ "bool": { "filter": { "match": { "title": "wonder" } } } })).is_err());
+        assert!(translate_query(&json!({ "range": { "released": { "gte": "now-1d" } } })).is_err());
+        assert!(translate_query(&json!({ "term": { "a": 1 }, "match": { "b": 2 } })).is_err());
+        assert!(translate_search(&json!({ "sort": [{ "release_year": "desc" }] })).is_err());
+        assert!(translate_search(&json!({ "sort": ["_score"], "track_total_hits": true })).is_ok());
+        assert!(translate_search(&json!({ "highlight": {} })).is_err());
+    }
+
+    #[test]
+    fn translate_searches() {
+        let search = translate_search(&json!({
+            "query": { "multi_match": { "query": "carol", "fields": ["title^2", "overview"] } },
+            "from": 10,
+            "size": 5,
+            "_source": { "includes": ["title"] },
+            "aggs": { "genres": { "terms": { "field": "genre", "size": 3 } } },
+        })).unwrap();
+
+        assert_eq!(search.query.q.as_deref(), Some("carol"));
+        assert_eq!(search.query.offset, Some(10));
+        assert_eq!(search.query.limit, Some(5));
+        assert_eq!(search.query.attributes_to_retrieve, Some(vec!["title".to_string()]));
+        assert_eq!(search.query.facets_distribution, Some(vec!["genre".to_string()]));
+        assert_eq!(search.aggregations, vec![("genres".to_string(), "genre".to_string(), 3)]);
+    }
+}
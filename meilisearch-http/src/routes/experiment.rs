@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use actix_web::{delete, get, put};
+use actix_web::{web, HttpResponse};
+use meilisearch_core::settings::RankingRule;
+use meilisearch_core::store::{Experiment, ExperimentVariant};
+use meilisearch_schema::Schema;
+use serde::Serialize;
+use siphasher::sip::SipHasher;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::routes::IndexParam;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_experiment)
+        .service(update_experiment)
+        .service(delete_experiment);
+}
+
+/// The variant of an experiment a search was made with, returned along with the results.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentAssignment {
+    pub name: String,
+    pub variant: String,
+}
+
+/// Assigns the user to a variant of the experiment, a user is always assigned to the same
+/// variant as long as the experiment is not modified.
+pub fn assign_variant<'a>(experiment: &'a Experiment, user_token: &str) -> Option<&'a ExperimentVariant> {
+    let total: u64 = experiment.variants.iter().map(|variant| variant.weight as u64).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut hasher = SipHasher::new();
+    (&experiment.name, user_token).hash(&mut hasher);
+    let mut bucket = hasher.finish() % total;
+    for variant in &experiment.variants {
+        if bucket < variant.weight as u64 {
+            return Some(variant);
+        }
+        bucket -= variant.weight as u64;
+    }
+    None
+}
+
+fn check_experiment(experiment: &Experiment, schema: Option<&Schema>) -> Result<(), Error> {
+    let invalid = |message: String| Err(Error::bad_parameter("experiment", message));
+
+    if experiment.variants.len() < 2 {
+        return invalid("an experiment must have at least two variants".to_string());
+    }
+    if experiment.variants.iter().all(|variant| variant.weight == 0) {
+        return invalid("at least one variant must have a weight".to_string());
+    }
+
+    let mut names = HashSet::new();
+    for variant in &experiment.variants {
+        if !names.insert(&variant.name) {
+            return invalid(format!("the variant name {:?} is used twice", variant.name));
+        }
+        let rules = match &variant.ranking_rules {
+            Some(rules) => RankingRule::try_from_iter(rules).map_err(Error::bad_request)?,
+            None => continue,
+        };
+        // the values of an attribute are only sorted if the ranking rules of the index use it
+        let ranked = schema.map(Schema::ranked_name).unwrap_or_default();
+        if let Some(field) = rules.iter().filter_map(RankingRule::field).find(|field| !ranked.contains(field)) {
+            return invalid(format!("{} must be used by the ranking rules of the index to be used by a variant", field));
+        }
+    }
+
+    Ok(())
+}
+
+#[get("/indexes/{index_uid}/experiment", wrap = "Authentication::Action(Action::SettingsGet)")]
+async fn get_experiment(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+    let experiment = index.main.experiment(&reader)?;
+
+    Ok(HttpResponse::Ok().json(experiment))
+}
+
+/// Starts an experiment on the index, replacing the running one. Modifying an experiment
+/// may assign the users to other variants.
+#[put("/indexes/{index_uid}/experiment", wrap = "Authentication::Action(Action::SettingsUpdate)")]
+async fn update_experiment(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Experiment>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let experiment = body.into_inner();
+    data.db.main_write::<_, _, ResponseError>(|writer| {
+        let schema = index.main.schema(writer)?;
+        check_experiment(&experiment, schema.as_ref())?;
+        index.main.put_experiment(writer, &experiment)?;
+        Ok(())
+    })?;
+
+    Ok(HttpResponse::Ok().json(experiment))
+}
+
+#[delete("/indexes/{index_uid}/experiment", wrap = "Authentication::Action(Action::SettingsUpdate)")]
+async fn delete_experiment(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    data.db.main_write::<_, _, ResponseError>(|writer| {
+        index.main.delete_experiment(writer)?;
+        Ok(())
+    })?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
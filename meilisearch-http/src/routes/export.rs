@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+
+use actix_web::{post, web, HttpResponse};
+use indexmap::IndexMap;
+use meilisearch_core::{DocumentId, Filter};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::helpers::streaming::{gzip_streaming_response, streaming_response, StreamFormat, StreamWriter};
+use crate::routes::IndexParam;
+use crate::Data;
+
+type Document = IndexMap<String, Value>;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(export_documents);
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ExportBody {
+    filters: Option<String>,
+    attributes_to_retrieve: Option<Vec<String>>,
+    /// The primary key of the last document received, the export resumes after it.
+    cursor: Option<Value>,
+    limit: Option<usize>,
+    #[serde(default)]
+    gzip: bool,
+}
+
+/// Streams the documents matching the filters as NDJSON, in the order in which they are stored.
+/// The primary key is always exported so that an interrupted export can be resumed with the
+/// primary key of the last document received as the cursor.
+#[post("/indexes/{index_uid}/export", wrap = "Authentication::Action(Action::DocumentsGet)")]
+async fn export_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: Option<web::Json<ExportBody>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let body = body.map(web::Json::into_inner).unwrap_or_default();
+
+    // the parameters are checked before the response starts, the errors couldn't be reported after
+    let start = {
+        let reader = data.db.main_read_txn()?;
+        let schema = index.main.schema(&reader)?;
+        if let (Some(filters), Some(schema)) = (&body.filters, &schema) {
+            Filter::parse(filters, schema)?;
+        }
+        match &body.cursor {
+            Some(cursor) => {
+                let cursor = meilisearch_core::update::value_to_string(cursor);
+                let internal_id = index.main
+                    .external_to_internal_docid(&reader, &cursor)?
+                    .ok_or_else(|| Error::bad_parameter("cursor", format!("the document {:?} doesn't exist anymore", cursor)))?;
+                Some(internal_id)
+            }
+            None => None,
+        }
+    };
+
+    let gzip = body.gzip;
+    let export = move |writer: &mut StreamWriter| -> Result<(), Error> {
+        let reader = data.db.main_read_txn()?;
+        let schema = match index.main.schema(&reader)? {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+        let filter = body.filters.as_ref().map(|filters| Filter::parse(filters, &schema)).transpose()?;
+        let attributes: Option<HashSet<&str>> = body.attributes_to_retrieve.as_ref().map(|attributes| {
+            attributes.iter().map(String::as_str).chain(schema.primary_key()).collect()
+        });
+
+        let mut remaining = body.limit.unwrap_or(usize::MAX);
+        for document_id in index.documents_fields_counts.documents_ids(&reader)? {
+            let document_id: DocumentId = document_id?;
+            if remaining == 0 {
+                break;
+            }
+            if start.map_or(false, |start| document_id <= start) {
+                continue;
+            }
+            if let Some(filter) = &filter {
+                if !filter.test(&reader, &index, document_id)? {
+                    continue;
+                }
+            }
+            if let Some(document) = index.document::<Document>(&reader, attributes.as_ref(), document_id)? {
+                writer.write(&document)?;
+                remaining -= 1;
+            }
+        }
+
+        Ok(())
+    };
+
+    if gzip {
+        gzip_streaming_response(StreamFormat::Ndjson, export)
+    } else {
+        streaming_response(StreamFormat::Ndjson, export)
+    }
+}
@@ -0,0 +1,24 @@
+use actix_web::{get, patch, web, HttpResponse};
+
+use crate::error::ResponseError;
+use crate::features::FeaturesUpdate;
+use crate::helpers::Authentication;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_features).service(update_features);
+}
+
+#[get("/experimental-features", wrap = "Authentication::Admin")]
+async fn get_features(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    Ok(HttpResponse::Ok().json(data.features.get()))
+}
+
+#[patch("/experimental-features", wrap = "Authentication::Admin")]
+async fn update_features(
+    data: web::Data<Data>,
+    body: web::Json<FeaturesUpdate>,
+) -> Result<HttpResponse, ResponseError> {
+    let features = data.features.update(&data.db, body.into_inner())?;
+    Ok(HttpResponse::Ok().json(features))
+}
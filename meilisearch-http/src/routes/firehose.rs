@@ -0,0 +1,53 @@
+use actix_web::{delete, get, put};
+use actix_web::{web, HttpResponse};
+
+use crate::Data;
+use crate::error::{Error, ResponseError};
+use crate::firehose::FirehoseTarget;
+use crate::helpers::Authentication;
+use crate::routes::IndexParam;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_firehose)
+        .service(update_firehose)
+        .service(delete_firehose);
+}
+
+// the targets receive all the documents of the index, only the master key can configure them
+
+#[get("/indexes/{index_uid}/firehose", wrap = "Authentication::Admin")]
+async fn get_firehose(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    data.db.open_index(&path.index_uid).ok_or(Error::index_not_found(&path.index_uid))?;
+    Ok(HttpResponse::Ok().json(data.firehose.targets(&path.index_uid)))
+}
+
+/// Replaces the targets that receive the changes made to the documents of the index.
+#[put("/indexes/{index_uid}/firehose", wrap = "Authentication::Admin")]
+async fn update_firehose(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Vec<FirehoseTarget>>,
+) -> Result<HttpResponse, ResponseError> {
+    data.db.open_index(&path.index_uid).ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let targets = body.into_inner();
+    for target in &targets {
+        target.validate()?;
+    }
+    data.firehose.set_targets(&data.db, &path.index_uid, targets.clone())?;
+
+    Ok(HttpResponse::Ok().json(targets))
+}
+
+#[delete("/indexes/{index_uid}/firehose", wrap = "Authentication::Admin")]
+async fn delete_firehose(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    data.db.open_index(&path.index_uid).ok_or(Error::index_not_found(&path.index_uid))?;
+    data.firehose.set_targets(&data.db, &path.index_uid, Vec::new())?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,365 @@
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::StatusCode;
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use indexmap::IndexMap;
+use meilisearch_core::UpdateStatus;
+use serde_json::Value;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::protobuf::{Decoder, Encoder};
+use crate::helpers::authentication::can_access_index;
+use crate::helpers::{Action, Authentication};
+use crate::routes::document::{enqueue_documents, enqueue_documents_deletion, UpdateDocumentsQuery};
+use crate::routes::search::{search_index, SearchQueryPost};
+use crate::routes::task::{find_task, parse_task_uid, task_uid, Task};
+use crate::telemetry;
+use crate::Data;
+
+/// The metadata naming the index targeted by a call, the API keys restricted to some indexes
+/// are checked against it.
+pub const INDEX_UID_METADATA: &str = "x-meili-index-uid";
+/// The path of the methods of the service, the only routes reading the index uid metadata.
+pub const GRPC_SERVICE_PATH: &str = "/meilisearch.v1.Meilisearch/";
+
+const GRPC_WEB: &str = "application/grpc-web";
+const GRPC_WEB_PROTO: &str = "application/grpc-web+proto";
+/// The flag of the frame carrying the trailers, the status of the call.
+const TRAILERS_FLAG: u8 = 0x80;
+const COMPRESSED_FLAG: u8 = 0x01;
+
+// the gRPC status codes
+const OK: u32 = 0;
+const INVALID_ARGUMENT: u32 = 3;
+const NOT_FOUND: u32 = 5;
+const ALREADY_EXISTS: u32 = 6;
+const PERMISSION_DENIED: u32 = 7;
+const RESOURCE_EXHAUSTED: u32 = 8;
+const UNIMPLEMENTED: u32 = 12;
+const INTERNAL: u32 = 13;
+const UNAVAILABLE: u32 = 14;
+const UNAUTHENTICATED: u32 = 16;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(search)
+        .service(add_documents)
+        .service(delete_documents)
+        .service(get_task);
+}
+
+/// The status a call ends with, when it failed.
+#[derive(Debug, PartialEq)]
+struct Status {
+    code: u32,
+    message: String,
+}
+
+impl Status {
+    fn new(code: u32, message: impl Into<String>) -> Status {
+        Status { code, message: message.into() }
+    }
+}
+
+impl From<ResponseError> for Status {
+    fn from(error: ResponseError) -> Status {
+        let code = match actix_web::ResponseError::status_code(&error) {
+            StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE => INVALID_ARGUMENT,
+            StatusCode::UNAUTHORIZED => UNAUTHENTICATED,
+            StatusCode::FORBIDDEN => PERMISSION_DENIED,
+            StatusCode::NOT_FOUND => NOT_FOUND,
+            StatusCode::CONFLICT => ALREADY_EXISTS,
+            StatusCode::TOO_MANY_REQUESTS => RESOURCE_EXHAUSTED,
+            StatusCode::SERVICE_UNAVAILABLE => UNAVAILABLE,
+            _ => INTERNAL,
+        };
+        Status::new(code, error.to_string())
+    }
+}
+
+impl From<Error> for Status {
+    fn from(error: Error) -> Status {
+        Status::from(ResponseError::from(error))
+    }
+}
+
+impl From<serde_json::Error> for Status {
+    fn from(error: serde_json::Error) -> Status {
+        Status::new(INVALID_ARGUMENT, error.to_string())
+    }
+}
+
+/// The binary gRPC-Web format is served, the base64 text format of the browsers without binary
+/// support is not.
+fn is_grpc_web(req: &HttpRequest) -> bool {
+    let content_type = req.headers().get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or("");
+    content_type == GRPC_WEB || content_type.starts_with(GRPC_WEB_PROTO)
+}
+
+/// Returns the message of the single data frame of a unary call.
+fn unframe(body: &[u8]) -> Result<&[u8], Status> {
+    if body.len() < 5 {
+        return Err(Status::new(INVALID_ARGUMENT, "the request does not contain a message"));
+    }
+    if body[0] & COMPRESSED_FLAG != 0 {
+        return Err(Status::new(UNIMPLEMENTED, "the compressed messages are not supported"));
+    }
+    let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+    body.get(5..5 + len).ok_or_else(|| Status::new(INVALID_ARGUMENT, "the message is truncated"))
+}
+
+fn frame(flag: u8, payload: &[u8], out: &mut Vec<u8>) {
+    out.push(flag);
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(payload);
+}
+
+/// The message of the status is percent-encoded as it is sent as a header.
+fn percent_encode(message: &str) -> String {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        match byte {
+            b' '..=b'~' if byte != b'%' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// gRPC-Web responds with a status of 200 and sends the status of the call in a trailers
+/// frame following the message, the status is the only frame of the failed calls.
+fn reply(result: Result<Vec<u8>, Status>) -> HttpResponse {
+    let mut body = Vec::new();
+    let status = match result {
+        Ok(message) => {
+            frame(0, &message, &mut body);
+            Status::new(OK, "")
+        }
+        Err(status) => status,
+    };
+
+    let mut trailers = format!("grpc-status:{}\r\n", status.code);
+    if !status.message.is_empty() {
+        trailers.push_str(&format!("grpc-message:{}\r\n", percent_encode(&status.message)));
+    }
+    frame(TRAILERS_FLAG, trailers.as_bytes(), &mut body);
+
+    HttpResponse::Ok().content_type(GRPC_WEB_PROTO).body(body)
+}
+
+/// Checks that the index of the message is the one named in the metadata, which the API key was
+/// checked against, and that the scoped key that authenticated the call can access it.
+/// The metadata is required when the server is protected by a master key.
+fn check_index(req: &HttpRequest, data: &Data, index_uid: &str) -> Result<(), Status> {
+    if !can_access_index(req, index_uid) {
+        return Err(Status::new(PERMISSION_DENIED, format!("the API key can't access the index {}", index_uid)));
+    }
+
+    let named = req.headers().get(INDEX_UID_METADATA).and_then(|value| value.to_str().ok());
+    match named {
+        Some(named) if named == index_uid => Ok(()),
+        Some(named) => Err(Status::new(INVALID_ARGUMENT, format!(
+            "the call targets the index {} but the {} metadata names the index {}",
+            index_uid, INDEX_UID_METADATA, named,
+        ))),
+        None if data.api_keys.read().unwrap().master.is_some() => Err(Status::new(PERMISSION_DENIED, format!(
+            "the calls must name the index they target in the {} metadata",
+            INDEX_UID_METADATA,
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn parse_json_objects(values: Vec<String>) -> Result<Vec<IndexMap<String, Value>>, Status> {
+    values.iter().map(|value| Ok(serde_json::from_str(value)?)).collect()
+}
+
+fn update_response(index_uid: &str, update_id: u64) -> Vec<u8> {
+    // as for the REST API, the update is linked to the trace of the call
+    telemetry::record_enqueued_update(update_id);
+
+    let mut encoder = Encoder::default();
+    encoder.uint64(1, update_id).string(2, &task_uid(index_uid, update_id));
+    encoder.into_bytes()
+}
+
+#[post("/meilisearch.v1.Meilisearch/Search", wrap = "Authentication::Action(Action::Search)")]
+async fn search(req: HttpRequest, data: web::Data<Data>, body: web::Bytes) -> HttpResponse {
+    if !is_grpc_web(&req) {
+        return HttpResponse::UnsupportedMediaType().finish();
+    }
+
+    let result = async {
+        let mut index_uid = String::new();
+        let mut query = SearchQueryPost::default();
+        for field in Decoder::new(unframe(&body)?) {
+            let (number, value) = field?;
+            let push = |list: &mut Option<Vec<String>>| value.as_str().map(|s| list.get_or_insert_with(Vec::new).push(s.to_string()));
+            match number {
+                1 => index_uid = value.as_str()?.to_string(),
+                2 => query.q = Some(value.as_str()?.to_string()),
+                3 => query.offset = Some(value.as_u64()? as usize),
+                4 => query.limit = Some(value.as_u64()? as usize).filter(|limit| *limit != 0),
+                5 => push(&mut query.attributes_to_retrieve)?,
+                6 => push(&mut query.attributes_to_crop)?,
+                7 => query.crop_length = Some(value.as_u64()? as usize).filter(|length| *length != 0),
+                8 => push(&mut query.attributes_to_highlight)?,
+                9 => query.filters = Some(value.as_str()?.to_string()).filter(|filters| !filters.is_empty()),
+                10 => query.matches = Some(value.as_bool()?),
+                11 => query.facet_filters = Some(serde_json::from_str(value.as_str()?)?),
+                12 => push(&mut query.facets_distribution)?,
+                _ => (),
+            }
+        }
+        check_index(&req, &data, &index_uid)?;
+
+        let result = search_index(&req, data, &index_uid, query.into()).await?;
+        let hits = result.hits.iter().map(serde_json::to_string).collect::<Result<Vec<_>, _>>().map_err(Error::from)?;
+        let facets_distribution = match &result.facets_distribution {
+            Some(distribution) => serde_json::to_string(distribution).map_err(Error::from)?,
+            None => String::new(),
+        };
+
+        let mut encoder = Encoder::default();
+        encoder
+            .repeated_string(1, hits.iter().map(String::as_str))
+            .uint64(2, result.offset as u64)
+            .uint64(3, result.limit as u64)
+            .uint64(4, result.nb_hits as u64)
+            .bool(5, result.exhaustive_nb_hits)
+            .uint64(6, result.processing_time_ms as u64)
+            .string(7, &result.query)
+            .string(8, &facets_distribution)
+            .bool(9, result.exhaustive_facets_count.unwrap_or(false));
+        Ok::<_, Status>(encoder.into_bytes())
+    }
+    .await;
+
+    reply(result)
+}
+
+#[post("/meilisearch.v1.Meilisearch/AddDocuments", wrap = "Authentication::Action(Action::DocumentsAdd)")]
+async fn add_documents(req: HttpRequest, data: web::Data<Data>, body: web::Bytes) -> HttpResponse {
+    if !is_grpc_web(&req) {
+        return HttpResponse::UnsupportedMediaType().finish();
+    }
+
+    let result = (|| -> Result<Vec<u8>, Status> {
+        let mut index_uid = String::new();
+        let mut documents = Vec::new();
+        let mut params = UpdateDocumentsQuery::default();
+        let mut partial = false;
+        for field in Decoder::new(unframe(&body)?) {
+            let (number, value) = field?;
+            match number {
+                1 => index_uid = value.as_str()?.to_string(),
+                2 => documents.push(value.as_str()?.to_string()),
+                3 => params.primary_key = Some(value.as_str()?.to_string()).filter(|key| !key.is_empty()),
+                4 => partial = value.as_bool()?,
+                _ => (),
+            }
+        }
+        check_index(&req, &data, &index_uid)?;
+
+        let index = data.db.open_index(&index_uid).ok_or(Error::index_not_found(&index_uid))?;
+        let update_id = enqueue_documents(&data, &index, &params, parse_json_objects(documents)?, partial)?;
+        Ok(update_response(&index_uid, update_id))
+    })();
+
+    reply(result)
+}
+
+#[post("/meilisearch.v1.Meilisearch/DeleteDocuments", wrap = "Authentication::Action(Action::DocumentsDelete)")]
+async fn delete_documents(req: HttpRequest, data: web::Data<Data>, body: web::Bytes) -> HttpResponse {
+    if !is_grpc_web(&req) {
+        return HttpResponse::UnsupportedMediaType().finish();
+    }
+
+    let result = (|| -> Result<Vec<u8>, Status> {
+        let mut index_uid = String::new();
+        let mut document_ids = Vec::new();
+        for field in Decoder::new(unframe(&body)?) {
+            let (number, value) = field?;
+            match number {
+                1 => index_uid = value.as_str()?.to_string(),
+                2 => document_ids.push(Value::String(value.as_str()?.to_string())),
+                _ => (),
+            }
+        }
+        check_index(&req, &data, &index_uid)?;
+
+        let index = data.db.open_index(&index_uid).ok_or(Error::index_not_found(&index_uid))?;
+        let update_id = enqueue_documents_deletion(&data, &index, None, document_ids)?;
+        Ok(update_response(&index_uid, update_id))
+    })();
+
+    reply(result)
+}
+
+#[post("/meilisearch.v1.Meilisearch/GetTask", wrap = "Authentication::Action(Action::TasksGet)")]
+async fn get_task(req: HttpRequest, data: web::Data<Data>, body: web::Bytes) -> HttpResponse {
+    if !is_grpc_web(&req) {
+        return HttpResponse::UnsupportedMediaType().finish();
+    }
+
+    let result = (|| -> Result<Vec<u8>, Status> {
+        let mut uid = String::new();
+        for field in Decoder::new(unframe(&body)?) {
+            let (number, value) = field?;
+            if number == 1 {
+                uid = value.as_str()?.to_string();
+            }
+        }
+        let (index_uid, _) = parse_task_uid(&uid)?;
+        check_index(&req, &data, index_uid)?;
+
+        Ok(encode_task(&find_task(&data, &uid)?))
+    })();
+
+    reply(result)
+}
+
+fn encode_task(task: &Task) -> Vec<u8> {
+    let (status, error) = match &task.status {
+        UpdateStatus::Enqueued { .. } => ("enqueued", None),
+        UpdateStatus::Processed { .. } => ("processed", None),
+        UpdateStatus::Failed { content } => ("failed", content.error.as_deref()),
+        UpdateStatus::Canceled { .. } => ("canceled", None),
+    };
+
+    let mut encoder = Encoder::default();
+    encoder
+        .string(1, &task.uid)
+        .string(2, &task.index_uid)
+        .uint64(3, task.status.update_id())
+        .string(4, task.status.update_type().name())
+        .string(5, status)
+        .string(6, error.unwrap_or(""))
+        .string(7, &task.status.enqueued_at().to_rfc3339())
+        .string(8, &task.status.processed_at().map(|date| date.to_rfc3339()).unwrap_or_default())
+        .double(9, task.status.duration().unwrap_or(0.0));
+    encoder.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framing() {
+        let mut body = Vec::new();
+        frame(0, b"message", &mut body);
+        assert_eq!(&body[..5], &[0, 0, 0, 0, 7]);
+        assert_eq!(unframe(&body), Ok(&b"message"[..]));
+
+        assert_eq!(unframe(&body[..8]).unwrap_err().code, INVALID_ARGUMENT);
+        assert_eq!(unframe(&[COMPRESSED_FLAG, 0, 0, 0, 0]).unwrap_err().code, UNIMPLEMENTED);
+        assert_eq!(unframe(&[]).unwrap_err().code, INVALID_ARGUMENT);
+    }
+
+    #[test]
+    fn status_of_errors() {
+        assert_eq!(Status::from(Error::index_not_found("movies")).code, NOT_FOUND);
+        assert_eq!(Status::from(Error::bad_request("invalid")).code, INVALID_ARGUMENT);
+        assert_eq!(Status::from(Error::internal("failure")).code, INTERNAL);
+        assert_eq!(percent_encode("100% sûr\n"), "100%25 s%C3%BBr%0A");
+    }
+}
@@ -1,9 +1,12 @@
+use std::sync::atomic::Ordering;
+
 use actix_web::{web, HttpResponse};
 use actix_web::{get, put};
 use serde::Deserialize;
+use serde_json::json;
 
 use crate::error::{Error, ResponseError};
-use crate::helpers::Authentication;
+use crate::helpers::{Action, Authentication};
 use crate::Data;
 
 pub fn services(cfg: &mut web::ServiceConfig) {
@@ -12,10 +15,18 @@ pub fn services(cfg: &mut web::ServiceConfig) {
 
 #[get("/health")]
 async fn get_health(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    // lets the load balancers stop sending requests to this server
+    if data.shutting_down.load(Ordering::SeqCst) {
+        return Err(Error::ShuttingDown.into());
+    }
     let reader = data.db.main_read_txn()?;
     if let Ok(Some(_)) = data.db.get_health(&reader) {
         return Err(Error::Maintenance.into());
     }
+    // the searches are still served, only the writes are refused
+    if data.capacity.safety_mode() {
+        return Ok(HttpResponse::Ok().json(json!({ "status": "safetyMode", "message": Error::InsufficientStorage.to_string() })));
+    }
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -34,7 +45,7 @@ struct HealthBody {
     health: bool,
 }
 
-#[put("/health", wrap = "Authentication::Private")]
+#[put("/health", wrap = "Authentication::Action(Action::HealthUpdate)")]
 async fn change_healthyness(
     data: web::Data<Data>,
     body: web::Json<HealthBody>,
@@ -1,27 +1,44 @@
+use std::thread;
+use std::time::Duration;
+
 use actix_web::{delete, get, post, put};
-use actix_web::{web, HttpResponse};
+use actix_web::error::BlockingError;
+use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::{DateTime, Utc};
-use log::error;
-use meilisearch_core::{Database, MainReader, UpdateReader};
+use log::{error, info};
+use meilisearch_core::{Database, Index, MainReader, UpdateReader, UpdateWriter};
+use meilisearch_core::settings::{SettingsUpdate, UpdateState};
+use meilisearch_core::store::IndexQuotas;
 use meilisearch_core::update::UpdateStatus;
 use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 
 use crate::Data;
 use crate::error::{Error, ResponseError};
-use crate::helpers::Authentication;
-use crate::routes::IndexParam;
+use crate::helpers::{Action, Authentication};
+use crate::helpers::authentication::{can_access_index, check_index_access};
+use crate::routes::{IndexParam, IndexUpdateResponse};
 
 pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(list_indexes)
         .service(get_index)
         .service(create_index)
+        .service(clone_index)
         .service(update_index)
         .service(delete_index)
+        .service(swap_indexes)
+        .service(get_read_only)
+        .service(update_read_only)
+        .service(get_quotas)
+        .service(update_quotas)
         .service(get_update_status)
         .service(get_all_updates_status);
 }
 
+pub fn is_valid_index_uid(uid: &str) -> bool {
+    uid.chars().all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+}
+
 fn generate_uid() -> String {
     let mut rng = rand::thread_rng();
     let sample = b"abcdefghijklmnopqrstuvwxyz0123456789";
@@ -92,15 +109,16 @@ pub fn list_indexes_sync(data: &web::Data<Data>, reader: &MainReader) -> Result<
     Ok(indexes)
 }
 
-#[get("/indexes", wrap = "Authentication::Private")]
-async fn list_indexes(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+#[get("/indexes", wrap = "Authentication::IndexesAction(Action::IndexesGet)")]
+async fn list_indexes(data: web::Data<Data>, req: HttpRequest) -> Result<HttpResponse, ResponseError> {
     let reader = data.db.main_read_txn()?;
-    let indexes = list_indexes_sync(&data, &reader)?;
+    let mut indexes = list_indexes_sync(&data, &reader)?;
+    indexes.retain(|index| can_access_index(&req, &index.uid));
 
     Ok(HttpResponse::Ok().json(indexes))
 }
 
-#[get("/indexes/{index_uid}", wrap = "Authentication::Private")]
+#[get("/indexes/{index_uid}", wrap = "Authentication::Action(Action::IndexesGet)")]
 async fn get_index(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -202,9 +220,52 @@ pub fn create_index_sync(
     Ok(index_response)
 }
 
-#[post("/indexes", wrap = "Authentication::Private")]
+/// Opens the index, it is created when it doesn't exist yet. Used by the sources
+/// that feed the indexes named in their data.
+/// Brings back an index from the trash, returns none if there is no index in the trash with this uid.
+pub fn restore_trashed_index_sync(data: &Data, uid: &str) -> Result<Option<IndexResponse>, Error> {
+    let index = match data.db.restore_index(uid)? {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+
+    let reader = data.db.main_read_txn()?;
+    let name = index.main.name(&reader)?.unwrap_or_else(|| uid.to_string());
+    let created_at = index.main.created_at(&reader)?.ok_or_else(|| Error::internal("Impossible to get the create date of an index"))?;
+    let updated_at = index.main.updated_at(&reader)?.ok_or_else(|| Error::internal("Impossible to get the last update date of an index"))?;
+    let primary_key = index.main.schema(&reader)?.and_then(|schema| schema.primary_key().map(ToOwned::to_owned));
+
+    Ok(Some(IndexResponse { name, uid: uid.to_string(), created_at, updated_at, primary_key }))
+}
+
+/// Purges the indexes that have been in the trash for longer than the trash retention, every hour.
+pub fn schedule_trash_purge(data: Data) {
+    thread::spawn(move || loop {
+        match data.db.purge_trash() {
+            Ok(0) => (),
+            Ok(purged) => info!("{} indexes purged from the trash", purged),
+            Err(e) => error!("Unsuccessful purge of the trash: {}", e),
+        }
+        thread::sleep(Duration::from_secs(3600)); // one hour
+    });
+}
+
+pub fn open_or_create_index(data: &Data, uid: &str) -> Result<Index, Error> {
+    if let Some(index) = data.db.open_index(uid) {
+        return Ok(index);
+    }
+
+    match create_index_sync(&data.db, uid.to_string(), uid.to_string(), None) {
+        Ok(_) | Err(Error::IndexAlreadyExists(_)) => (),
+        Err(e) => return Err(e),
+    }
+    data.db.open_index(uid).ok_or(Error::index_not_found(uid))
+}
+
+#[post("/indexes", wrap = "Authentication::IndexesAction(Action::IndexesCreate)")]
 async fn create_index(
     data: web::Data<Data>,
+    req: HttpRequest,
     body: web::Json<IndexCreateRequest>,
 ) -> Result<HttpResponse, ResponseError> {
     if let (None, None) = (body.name.clone(), body.uid.clone()) {
@@ -215,10 +276,7 @@ async fn create_index(
 
     let uid = match &body.uid {
         Some(uid) => {
-            if uid
-                .chars()
-                .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
-            {
+            if is_valid_index_uid(uid) {
                 uid.to_owned()
             } else {
                 return Err(Error::InvalidIndexUid.into());
@@ -232,6 +290,8 @@ async fn create_index(
         },
     };
 
+    check_index_access(&req, &uid)?;
+
     let name = body.name.as_ref().unwrap_or(&uid).to_string();
 
     let index_response = create_index_sync(&data.db, uid, name, body.primary_key.clone())?;
@@ -239,6 +299,111 @@ async fn create_index(
     Ok(HttpResponse::Created().json(index_response))
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct CloneIndexRequest {
+    uid: String,
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CloneIndexResponse {
+    #[serde(flatten)]
+    index: IndexResponse,
+    /// The last update of the copy, the copy is complete once it is processed.
+    update_id: u64,
+}
+
+/// Creates an index with the settings and the documents of another one. The settings and the
+/// documents are enqueued as updates of the new index, by batches, and indexed in the background.
+#[post("/indexes/{index_uid}/clone", wrap = "Authentication::Action(Action::IndexesCreate)")]
+async fn clone_index(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<CloneIndexRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    let source = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let CloneIndexRequest { uid, name } = body.into_inner();
+    if !is_valid_index_uid(&uid) {
+        return Err(Error::InvalidIndexUid.into());
+    }
+    if data.db.open_index(&uid).is_some() {
+        return Err(Error::IndexAlreadyExists(uid).into());
+    }
+
+    let source_uid = path.index_uid.clone();
+    let response = web::block(move || {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                // an index named after its uid is named after its new uid
+                let reader = data.db.main_read_txn()?;
+                let source_name = source.main.name(&reader)?.unwrap_or_default();
+                if source_name == source_uid { uid.clone() } else { source_name }
+            }
+        };
+        let primary_key = {
+            let reader = data.db.main_read_txn()?;
+            source.main.schema(&reader)?.and_then(|schema| schema.primary_key().map(str::to_string))
+        };
+        let index_response = create_index_sync(&data.db, uid.clone(), name, primary_key)?;
+        let index = data.db.open_index(&uid).ok_or(Error::index_not_found(&uid))?;
+
+        let batch_size = data.dump_batch_size.max(1);
+        let result = data.db.update_write::<_, _, Error>(|writer| {
+            // the documents and the settings are read from the same version of the index
+            let reader = data.db.main_read_txn()?;
+            let settings = crate::routes::setting::get_all_sync(&data, &reader, &source_uid)?
+                .to_update()
+                .map_err(Error::bad_request)?;
+            let mut update_id = index.settings_update(writer, settings)?;
+
+            let enqueue = |writer: &mut UpdateWriter, batch: Vec<_>| -> Result<u64, Error> {
+                let mut addition = index.documents_addition();
+                addition.extend(batch);
+                Ok(addition.finalize(writer)?)
+            };
+            let mut batch = Vec::with_capacity(batch_size);
+            crate::routes::document::for_each_document(&source, &reader, 0, usize::MAX, None, |document| {
+                batch.push(document);
+                if batch.len() == batch_size {
+                    let full = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+                    update_id = enqueue(&mut *writer, full)?;
+                }
+                Ok(())
+            })?;
+            if !batch.is_empty() {
+                update_id = enqueue(writer, batch)?;
+            }
+            Ok(update_id)
+        });
+
+        // the index is created in its own transaction, it is removed when the copy cannot be enqueued
+        match result {
+            Ok(update_id) => Ok(CloneIndexResponse { index: index_response, update_id }),
+            Err(e) => {
+                if let Err(e) = data.db.delete_index(&uid) {
+                    error!("Cannot remove the index {} after a failed copy; {}", uid, e);
+                }
+                Err(e)
+            }
+        }
+    })
+    .await
+    .map_err(|e| match e {
+        BlockingError::Error(e) => e,
+        BlockingError::Canceled => Error::internal("the copy of the index was canceled"),
+    })?;
+
+    crate::telemetry::record_enqueued_update(response.update_id);
+    Ok(HttpResponse::Accepted().json(response))
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct UpdateIndexRequest {
@@ -256,7 +421,7 @@ struct UpdateIndexResponse {
     primary_key: Option<String>,
 }
 
-#[put("/indexes/{index_uid}", wrap = "Authentication::Private")]
+#[put("/indexes/{index_uid}", wrap = "Authentication::Action(Action::IndexesUpdate)")]
 async fn update_index(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -266,22 +431,41 @@ async fn update_index(
         .db
         .open_index(&path.index_uid)
         .ok_or(Error::index_not_found(&path.index_uid))?;
+    index.check_writable()?;
 
-    data.db.main_write::<_, _, ResponseError>(|writer| {
+    let new_primary_key = data.db.main_write::<_, _, ResponseError>(|writer| {
         if let Some(name) = &body.name {
             index.main.put_name(writer, name)?;
         }
 
+        let mut new_primary_key = None;
         if let Some(id) = body.primary_key.clone() {
             if let Some(mut schema) = index.main.schema(writer)? {
-                schema.set_primary_key(&id)?;
-                index.main.put_schema(writer, &schema)?;
+                match schema.primary_key() {
+                    None => {
+                        schema.set_primary_key(&id)?;
+                        index.main.put_schema(writer, &schema)?;
+                    }
+                    Some(primary_key) if primary_key != id => new_primary_key = Some(id),
+                    Some(_) => (),
+                }
             }
         }
         index.main.put_updated_at(writer)?;
-        Ok(())
+        Ok(new_primary_key)
     })?;
 
+    // Changing the primary key of an index requires rebuilding its documents ids,
+    // this is done asynchronously by the update process.
+    if let Some(primary_key) = new_primary_key {
+        let settings = SettingsUpdate {
+            primary_key: UpdateState::Update(primary_key),
+            ..SettingsUpdate::default()
+        };
+        let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+        return Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)));
+    }
+
     let reader = data.db.main_read_txn()?;
     let name = index.main.name(&reader)?.ok_or(Error::internal(
             "Impossible to get the name of an index",
@@ -318,18 +502,132 @@ async fn update_index(
     Ok(HttpResponse::Ok().json(index_response))
 }
 
-#[delete("/indexes/{index_uid}", wrap = "Authentication::Private")]
+#[delete("/indexes/{index_uid}", wrap = "Authentication::Action(Action::IndexesDelete)")]
 async fn delete_index(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
 ) -> Result<HttpResponse, ResponseError> {
-    if data.db.delete_index(&path.index_uid)? {
+    if data.db.trash_index(&path.index_uid)? {
+        if let Some(search_analytics) = &data.search_analytics {
+            search_analytics.remove(&path.index_uid);
+        }
+        if data.firehose.has_targets(&path.index_uid) {
+            data.firehose.set_targets(&data.db, &path.index_uid, Vec::new())?;
+        }
         Ok(HttpResponse::NoContent().finish())
     } else {
         Err(Error::index_not_found(&path.index_uid).into())
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct SwapIndexesRequest {
+    indexes: (String, String),
+}
+
+/// Exchanges the uids of two indexes, a new version of an index can be built under another uid
+/// and be swapped in once ready: the searches are made on one index or the other, never on a mix.
+#[post("/swap-indexes", wrap = "Authentication::Admin")]
+async fn swap_indexes(
+    data: web::Data<Data>,
+    body: web::Json<SwapIndexesRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    let (lhs, rhs) = &body.indexes;
+    for uid in &[lhs, rhs] {
+        if data.db.open_index(uid).is_none() {
+            return Err(Error::index_not_found(uid).into());
+        }
+    }
+
+    if !data.db.swap_indexes(lhs, rhs)? {
+        // one of the indexes has been deleted in the meantime
+        return Err(Error::index_not_found(format!("{} or {}", lhs, rhs)).into());
+    }
+
+    let reader = data.db.main_read_txn()?;
+    let indexes: Vec<_> = list_indexes_sync(&data, &reader)?
+        .into_iter()
+        .filter(|index| index.uid == *lhs || index.uid == *rhs)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(indexes))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ReadOnlyBody {
+    read_only: bool,
+}
+
+// an index key could otherwise make writable an index kept read-only on purpose
+
+#[get("/indexes/{index_uid}/read-only", wrap = "Authentication::Admin")]
+async fn get_read_only(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    Ok(HttpResponse::Ok().json(ReadOnlyBody { read_only: index.is_read_only() }))
+}
+
+/// Makes the index reject the changes to its documents and settings, or accept them again.
+/// The index is still searchable while read-only.
+#[put("/indexes/{index_uid}/read-only", wrap = "Authentication::Admin")]
+async fn update_read_only(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<ReadOnlyBody>,
+) -> Result<HttpResponse, ResponseError> {
+    if !data.db.set_index_read_only(&path.index_uid, body.read_only)? {
+        return Err(Error::index_not_found(&path.index_uid).into());
+    }
+
+    Ok(HttpResponse::Ok().json(body.into_inner()))
+}
+
+#[get("/indexes/{index_uid}/quotas", wrap = "Authentication::Admin")]
+async fn get_quotas(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+    let quotas = index.main.quotas(&reader)?;
+
+    Ok(HttpResponse::Ok().json(quotas))
+}
+
+/// Limits the number of documents and the size of the index, the updates that would make the
+/// index exceed them fail. A missing limit removes it, lowering a limit doesn't shrink the index.
+#[put("/indexes/{index_uid}/quotas", wrap = "Authentication::Admin")]
+async fn update_quotas(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<IndexQuotas>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let quotas = body.into_inner();
+    data.db.main_write::<_, _, ResponseError>(|writer| {
+        index.main.put_quotas(writer, quotas)?;
+        Ok(())
+    })?;
+
+    Ok(HttpResponse::Ok().json(quotas))
+}
+
 #[derive(Deserialize)]
 struct UpdateParam {
     index_uid: String,
@@ -338,7 +636,7 @@ struct UpdateParam {
 
 #[get(
     "/indexes/{index_uid}/updates/{update_id}",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::TasksGet)"
 )]
 async fn get_update_status(
     data: web::Data<Data>,
@@ -374,7 +672,7 @@ pub fn get_all_updates_status_sync(
     Ok(index.all_updates_status(reader)?)
 }
 
-#[get("/indexes/{index_uid}/updates", wrap = "Authentication::Private")]
+#[get("/indexes/{index_uid}/updates", wrap = "Authentication::Action(Action::TasksGet)")]
 async fn get_all_updates_status(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -1,26 +1,234 @@
 use actix_web::web;
 use actix_web::HttpResponse;
-use actix_web::get;
-use serde::Serialize;
+use actix_web::{delete, get, patch, post};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::helpers::Authentication;
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::keys::ApiKey;
+use crate::rate_limit::RateLimit;
 use crate::Data;
 
 pub fn services(cfg: &mut web::ServiceConfig) {
-    cfg.service(list);
+    cfg.service(list)
+        .service(create_key)
+        .service(get_key)
+        .service(update_key)
+        .service(rotate_key)
+        .service(delete_key);
+}
+
+/// The grace period during which a rotated key remains valid when none is specified.
+const DEFAULT_GRACE_PERIOD_SEC: i64 = 3600;
+
+/// A key as returned by the API, the key itself is only known when it is created or rotated.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyView {
+    uid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    prefix: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    actions: Vec<Action>,
+    indexes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_limit: Option<RateLimit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_key_valid_until: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl KeyView {
+    fn new(api_key: ApiKey, key: Option<String>) -> KeyView {
+        KeyView {
+            uid: api_key.uid,
+            key,
+            prefix: api_key.hashed.prefix,
+            description: api_key.description,
+            actions: api_key.actions,
+            indexes: api_key.indexes,
+            expires_at: api_key.expires_at,
+            rate_limit: api_key.rate_limit,
+            previous_key_valid_until: api_key.previous
+                .map(|previous| previous.valid_until)
+                .filter(|valid_until| *valid_until > Utc::now()),
+            created_at: api_key.created_at,
+            updated_at: api_key.updated_at,
+        }
+    }
 }
 
 #[derive(Serialize)]
 struct KeysResponse {
     private: Option<String>,
     public: Option<String>,
+    results: Vec<KeyView>,
 }
 
 #[get("/keys", wrap = "Authentication::Admin")]
 async fn list(data: web::Data<Data>) -> HttpResponse {
-    let api_keys = data.api_keys.clone();
+    let api_keys = data.api_keys.read().unwrap().clone();
     HttpResponse::Ok().json(KeysResponse {
         private: api_keys.private,
         public: api_keys.public,
+        results: data.keys.all().into_iter().map(|key| KeyView::new(key, None)).collect(),
     })
 }
+
+fn validate_scopes(actions: &[Action], indexes: &[String], rate_limit: Option<&RateLimit>) -> Result<(), Error> {
+    if actions.is_empty() {
+        return Err(Error::bad_request("a key must be allowed to perform at least one action"));
+    }
+    if indexes.is_empty() {
+        return Err(Error::bad_request("a key must give access to at least one index pattern"));
+    }
+    if let Some(rate_limit) = rate_limit {
+        rate_limit.validate().map_err(Error::bad_request)?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct CreateKeyBody {
+    description: Option<String>,
+    actions: Vec<Action>,
+    indexes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+    rate_limit: Option<RateLimit>,
+}
+
+#[post("/keys", wrap = "Authentication::Admin")]
+async fn create_key(
+    data: web::Data<Data>,
+    body: web::Json<CreateKeyBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let CreateKeyBody { description, actions, indexes, expires_at, rate_limit } = body.into_inner();
+    validate_scopes(&actions, &indexes, rate_limit.as_ref())?;
+
+    let master_key = data.api_keys.read().unwrap().master.clone();
+    let (api_key, key) = ApiKey::new(master_key.as_deref(), description, actions, indexes, expires_at, rate_limit);
+
+    data.keys.insert(&data.db, api_key.clone())?;
+
+    Ok(HttpResponse::Created().json(KeyView::new(api_key, Some(key))))
+}
+
+#[derive(Deserialize)]
+struct KeyParam {
+    key_uid: String,
+}
+
+#[get("/keys/{key_uid}", wrap = "Authentication::Admin")]
+async fn get_key(
+    data: web::Data<Data>,
+    path: web::Path<KeyParam>,
+) -> Result<HttpResponse, ResponseError> {
+    match data.keys.find_by_uid(&path.key_uid) {
+        Some(key) => Ok(HttpResponse::Ok().json(KeyView::new(key, None))),
+        None => Err(Error::NotFound(format!("Key {}", path.key_uid)).into()),
+    }
+}
+
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where T: Deserialize<'de>,
+          D: Deserializer<'de>
+{
+    Deserialize::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct UpdateKeyBody {
+    #[serde(default, deserialize_with = "deserialize_some")]
+    description: Option<Option<String>>,
+    actions: Option<Vec<Action>>,
+    indexes: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    expires_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    rate_limit: Option<Option<RateLimit>>,
+}
+
+/// Updates the given fields of a key, the key itself and its uid can't be modified.
+#[patch("/keys/{key_uid}", wrap = "Authentication::Admin")]
+async fn update_key(
+    data: web::Data<Data>,
+    path: web::Path<KeyParam>,
+    body: web::Json<UpdateKeyBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let body = body.into_inner();
+    if let Some(current) = data.keys.find_by_uid(&path.key_uid) {
+        let actions = body.actions.as_ref().unwrap_or(&current.actions);
+        let indexes = body.indexes.as_ref().unwrap_or(&current.indexes);
+        let rate_limit = body.rate_limit.as_ref().unwrap_or(&current.rate_limit);
+        validate_scopes(actions, indexes, rate_limit.as_ref())?;
+    }
+
+    let updated = data.keys.update(&data.db, &path.key_uid, |key| {
+        if let Some(description) = body.description {
+            key.description = description;
+        }
+        if let Some(actions) = body.actions {
+            key.actions = actions;
+        }
+        if let Some(indexes) = body.indexes {
+            key.indexes = indexes;
+        }
+        if let Some(expires_at) = body.expires_at {
+            key.expires_at = expires_at;
+        }
+        if let Some(rate_limit) = body.rate_limit {
+            key.rate_limit = rate_limit;
+        }
+    })?;
+
+    match updated {
+        Some(key) => Ok(HttpResponse::Ok().json(KeyView::new(key, None))),
+        None => Err(Error::NotFound(format!("Key {}", path.key_uid)).into()),
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RotateKeyBody {
+    grace_period_sec: Option<u32>,
+}
+
+/// Replaces the key by a new one, the previous key remains valid during the grace
+/// period but the tenant tokens it signed are invalid as soon as it is rotated.
+#[post("/keys/{key_uid}/rotate", wrap = "Authentication::Admin")]
+async fn rotate_key(
+    data: web::Data<Data>,
+    path: web::Path<KeyParam>,
+    body: Option<web::Json<RotateKeyBody>>,
+) -> Result<HttpResponse, ResponseError> {
+    let body = body.map(web::Json::into_inner).unwrap_or_default();
+    let grace_period = body.grace_period_sec.map_or(DEFAULT_GRACE_PERIOD_SEC, i64::from);
+    let master_key = data.api_keys.read().unwrap().master.clone();
+
+    let rotated = data.keys.rotate(&data.db, master_key.as_deref(), &path.key_uid, Duration::seconds(grace_period))?;
+    match rotated {
+        Some((api_key, key)) => Ok(HttpResponse::Ok().json(KeyView::new(api_key, Some(key)))),
+        None => Err(Error::NotFound(format!("Key {}", path.key_uid)).into()),
+    }
+}
+
+#[delete("/keys/{key_uid}", wrap = "Authentication::Admin")]
+async fn delete_key(
+    data: web::Data<Data>,
+    path: web::Path<KeyParam>,
+) -> Result<HttpResponse, ResponseError> {
+    if data.keys.remove(&data.db, &path.key_uid)? {
+        data.rate_limiter.remove(&path.key_uid);
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(Error::NotFound(format!("Key {}", path.key_uid)).into())
+    }
+}
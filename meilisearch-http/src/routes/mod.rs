@@ -1,16 +1,32 @@
 use actix_web::{get, HttpResponse};
 use serde::{Deserialize, Serialize};
 
+pub mod changes;
+pub mod compaction;
+pub mod config;
+pub mod debug;
+pub mod dictionary;
 pub mod document;
+pub mod experiment;
+pub mod grpc;
 pub mod health;
 pub mod index;
 pub mod key;
+pub mod query_rules;
+pub mod schedule;
 pub mod search;
+pub mod separator_tokens;
 pub mod setting;
+pub mod snapshot;
 pub mod stats;
 pub mod stop_words;
 pub mod synonym;
+pub mod task;
 pub mod dump;
+pub mod es_compat;
+pub mod export;
+pub mod features;
+pub mod firehose;
 
 #[derive(Deserialize)]
 pub struct IndexParam {
@@ -24,7 +40,9 @@ pub struct IndexUpdateResponse {
 }
 
 impl IndexUpdateResponse {
+    /// The update is also recorded as enqueued by the request, to link it to the trace of the request.
     pub fn with_id(update_id: u64) -> Self {
+        crate::telemetry::record_enqueued_update(update_id);
         Self { update_id }
     }
 }
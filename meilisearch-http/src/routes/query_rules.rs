@@ -0,0 +1,101 @@
+use actix_web::{web, HttpResponse};
+use actix_web::{delete, get, post};
+use meilisearch_core::settings::{QueryRule, QueryRuleCondition, SettingsUpdate, UpdateState};
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::routes::{IndexParam, IndexUpdateResponse};
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get).service(update).service(delete);
+}
+
+pub fn check_rules(rules: &[QueryRule]) -> Result<(), Error> {
+    for rule in rules {
+        match &rule.condition {
+            QueryRuleCondition::Pattern(pattern) => {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    return Err(Error::bad_parameter("queryRules", format!("invalid pattern {:?}: {}", pattern, e)));
+                }
+            }
+            QueryRuleCondition::Is(_) => (),
+            QueryRuleCondition::Contains(value) if value.trim().is_empty() => {
+                return Err(Error::bad_parameter("queryRules", "the contains condition can't be empty"));
+            }
+            QueryRuleCondition::Contains(_) => (),
+        }
+        if rule.pinned.is_empty() && rule.hidden.is_empty() && rule.banner.is_none() {
+            return Err(Error::bad_parameter("queryRules", "a rule must pin, hide or add a banner"));
+        }
+    }
+    Ok(())
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/query-rules",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let rules = index.main.query_rules(&reader)?;
+
+    Ok(HttpResponse::Ok().json(rules))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/query-rules",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Vec<QueryRule>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let rules = body.into_inner();
+    check_rules(&rules)?;
+
+    let settings = SettingsUpdate {
+        query_rules: UpdateState::Update(rules),
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/query-rules",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        query_rules: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
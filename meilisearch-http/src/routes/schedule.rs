@@ -0,0 +1,54 @@
+use actix_web::{delete, get, post};
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+
+use crate::Data;
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::scheduler::Job;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_scheduled_tasks)
+        .service(create_scheduled_task)
+        .service(delete_scheduled_task);
+}
+
+/// Lists the recurring tasks along with the history of their most recent runs.
+#[get("/tasks/scheduled", wrap = "Authentication::Action(Action::TasksGet)")]
+async fn get_scheduled_tasks(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    Ok(HttpResponse::Ok().json(data.scheduler.tasks()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ScheduledTaskBody {
+    job: Job,
+    cron: String,
+}
+
+#[post("/tasks/scheduled", wrap = "Authentication::Action(Action::TasksSchedule)")]
+async fn create_scheduled_task(
+    data: web::Data<Data>,
+    body: web::Json<ScheduledTaskBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let ScheduledTaskBody { job, cron } = body.into_inner();
+    let task = data.scheduler.add(job, cron)?;
+    Ok(HttpResponse::Created().json(task))
+}
+
+#[derive(Deserialize)]
+struct ScheduledTaskParam {
+    id: u64,
+}
+
+#[delete("/tasks/scheduled/{id}", wrap = "Authentication::Action(Action::TasksSchedule)")]
+async fn delete_scheduled_task(
+    data: web::Data<Data>,
+    path: web::Path<ScheduledTaskParam>,
+) -> Result<HttpResponse, ResponseError> {
+    if data.scheduler.remove(path.id) {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(Error::NotFound(format!("Scheduled task {}", path.id)).into())
+    }
+}
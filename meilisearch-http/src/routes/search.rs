@@ -1,17 +1,25 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
 
-use actix_web::{get, post, web, HttpResponse};
+use actix_web::{get, post, web, HttpMessage, HttpRequest, HttpResponse};
+use chrono::Utc;
 use log::warn;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 
 use crate::error::{Error, FacetCountError, ResponseError};
-use crate::helpers::meilisearch::{IndexSearchExt, SearchResult};
-use crate::helpers::Authentication;
+use crate::helpers::meilisearch::{IndexSearchExt, SearchResult, SortRule};
+use crate::helpers::logging::ApiKeyUid;
+use crate::helpers::{Action, Authentication};
 use crate::routes::IndexParam;
+use crate::routes::experiment::{assign_variant, ExperimentAssignment};
+use crate::search_limiter::SearchPermit;
+use crate::slow_query::{SearchTimings, SlowQuery};
+use crate::tenant_token::IndexSearchRules;
 use crate::Data;
 
 use meilisearch_core::facets::FacetFilter;
+use meilisearch_core::settings::RankingRule;
 use meilisearch_schema::{FieldId, Schema};
 
 pub fn services(cfg: &mut web::ServiceConfig) {
@@ -32,32 +40,50 @@ pub struct SearchQuery {
     matches: Option<bool>,
     facet_filters: Option<String>,
     facets_distribution: Option<String>,
+    disjunctive_facets_distribution: Option<String>,
+    facet_stats: Option<String>,
+    facet_ranges: Option<String>,
+    boost: Option<String>,
+    sort: Option<String>,
+    profile: Option<bool>,
+    user_token: Option<String>,
+    /// The filter of the tenant token the search is made with, never given by the client.
+    #[serde(skip)]
+    tenant_filter: Option<String>,
 }
 
-#[get("/indexes/{index_uid}/search", wrap = "Authentication::Public")]
+#[get("/indexes/{index_uid}/search", wrap = "Authentication::Action(Action::Search)")]
 async fn search_with_url_query(
+    req: HttpRequest,
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<SearchQuery>,
 ) -> Result<HttpResponse, ResponseError> {
-    let search_result = params.search(&path.index_uid, data)?;
+    let search_result = search_index(&req, data, &path.index_uid, params.into_inner()).await?;
     Ok(HttpResponse::Ok().json(search_result))
 }
 
-#[derive(Deserialize)]
+#[derive(Default, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct SearchQueryPost {
-    q: Option<String>,
-    offset: Option<usize>,
-    limit: Option<usize>,
-    attributes_to_retrieve: Option<Vec<String>>,
-    attributes_to_crop: Option<Vec<String>>,
-    crop_length: Option<usize>,
-    attributes_to_highlight: Option<Vec<String>>,
-    filters: Option<String>,
-    matches: Option<bool>,
-    facet_filters: Option<Value>,
-    facets_distribution: Option<Vec<String>>,
+    pub(crate) q: Option<String>,
+    pub(crate) offset: Option<usize>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) attributes_to_retrieve: Option<Vec<String>>,
+    pub(crate) attributes_to_crop: Option<Vec<String>>,
+    pub(crate) crop_length: Option<usize>,
+    pub(crate) attributes_to_highlight: Option<Vec<String>>,
+    pub(crate) filters: Option<String>,
+    pub(crate) matches: Option<bool>,
+    pub(crate) facet_filters: Option<Value>,
+    pub(crate) facets_distribution: Option<Vec<String>>,
+    pub(crate) disjunctive_facets_distribution: Option<Vec<String>>,
+    pub(crate) facet_stats: Option<Vec<String>>,
+    pub(crate) facet_ranges: Option<BTreeMap<String, Vec<f64>>>,
+    pub(crate) boost: Option<BTreeMap<String, BTreeMap<String, f64>>>,
+    pub(crate) sort: Option<Vec<String>>,
+    pub(crate) profile: Option<bool>,
+    pub(crate) user_token: Option<String>,
 }
 
 impl From<SearchQueryPost> for SearchQuery {
@@ -74,27 +100,72 @@ impl From<SearchQueryPost> for SearchQuery {
             matches: other.matches,
             facet_filters: other.facet_filters.map(|f| f.to_string()),
             facets_distribution: other.facets_distribution.map(|f| format!("{:?}", f)),
+            disjunctive_facets_distribution: other.disjunctive_facets_distribution.map(|f| format!("{:?}", f)),
+            facet_stats: other.facet_stats.map(|f| format!("{:?}", f)),
+            facet_ranges: other.facet_ranges.map(|ranges| json!(ranges).to_string()),
+            boost: other.boost.map(|boost| json!(boost).to_string()),
+            sort: other.sort.map(|sort| json!(sort).to_string()),
+            profile: other.profile,
+            user_token: other.user_token,
+            tenant_filter: None,
         }
     }
 }
 
-#[post("/indexes/{index_uid}/search", wrap = "Authentication::Public")]
+#[post("/indexes/{index_uid}/search", wrap = "Authentication::Action(Action::Search)")]
 async fn search_with_post(
+    req: HttpRequest,
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Json<SearchQueryPost>,
 ) -> Result<HttpResponse, ResponseError> {
-    let query: SearchQuery = params.0.into();
-    let search_result = query.search(&path.index_uid, data)?;
+    let search_result = search_index(&req, data, &path.index_uid, params.0.into()).await?;
     Ok(HttpResponse::Ok().json(search_result))
 }
 
+/// Searches the index on behalf of the request, within the rules of its tenant token
+/// and the limit of concurrent searches.
+pub(crate) async fn search_index(
+    req: &HttpRequest,
+    data: web::Data<Data>,
+    index_uid: &str,
+    mut query: SearchQuery,
+) -> Result<SearchResult, ResponseError> {
+    query.restrict(req.extensions().get::<IndexSearchRules>());
+    let _permit = search_permit(req, &data).await?;
+    query.search(index_uid, data)
+}
+
+/// Waits for the search to be allowed to run when the number of concurrent searches is limited,
+/// the searches are refused once they waited longer than the queue timeout.
+async fn search_permit(req: &HttpRequest, data: &Data) -> Result<Option<SearchPermit>, ResponseError> {
+    let limiter = match &data.search_limiter {
+        Some(limiter) => limiter.clone(),
+        None => return Ok(None),
+    };
+
+    let key = req.extensions().get::<ApiKeyUid>().map(|key| key.0.clone()).unwrap_or_default();
+    match limiter.acquire(&key).await {
+        Some(permit) => Ok(Some(permit)),
+        None => Err(Error::TooManyRequests(format!(
+            "the search waited more than {}ms for the other searches to finish",
+            limiter.queue_timeout().as_millis(),
+        )).into()),
+    }
+}
+
 impl SearchQuery {
+    /// Restricts the search to the documents matching the filter of the tenant token, if any.
+    fn restrict(&mut self, rules: Option<&IndexSearchRules>) {
+        self.tenant_filter = rules.and_then(|rules| rules.filter.clone());
+    }
+
     fn search(
         &self,
         index_uid: &str,
         data: web::Data<Data>,
     ) -> Result<SearchResult, ResponseError> {
+        let start = Instant::now();
         let index = data
             .db
             .open_index(index_uid)
@@ -157,14 +228,37 @@ impl SearchQuery {
             )?);
         }
 
-        if let Some(facets) = &self.facets_distribution {
-            match index.main.attributes_for_faceting(&reader)? {
-                Some(ref attrs) => {
-                    let field_ids = prepare_facet_list(&facets, &schema, attrs)?;
-                    search_builder.add_facets(field_ids);
-                }
-                None => return Err(FacetCountError::NoFacetSet.into()),
+        if self.facets_distribution.is_some()
+            || self.disjunctive_facets_distribution.is_some()
+            || self.facet_stats.is_some()
+            || self.facet_ranges.is_some()
+        {
+            let attrs = index
+                .main
+                .attributes_for_faceting(&reader)?
+                .ok_or(FacetCountError::NoFacetSet)?;
+
+            if let Some(facets) = &self.facets_distribution {
+                search_builder.add_facets(prepare_facet_list(&facets, &schema, &attrs)?);
+            }
+            if let Some(facets) = &self.disjunctive_facets_distribution {
+                search_builder.add_disjunctive_facets(prepare_facet_list(&facets, &schema, &attrs)?);
+            }
+            if let Some(facets) = &self.facet_stats {
+                search_builder.add_facet_stats(prepare_facet_list(&facets, &schema, &attrs)?);
             }
+            if let Some(ranges) = &self.facet_ranges {
+                search_builder.add_facet_ranges(prepare_facet_ranges(ranges, &schema, &attrs)?);
+            }
+        }
+
+        if let Some(boost) = &self.boost {
+            let attrs = index.main.attributes_for_faceting(&reader)?.ok_or(FacetCountError::NoFacetSet)?;
+            search_builder.boost(prepare_boost(boost, &schema, &attrs)?);
+        }
+
+        if let Some(sort) = &self.sort {
+            search_builder.sort(prepare_sort(sort, &schema)?);
         }
 
         if let Some(attributes_to_crop) = &self.attributes_to_crop {
@@ -214,17 +308,174 @@ impl SearchQuery {
             search_builder.attributes_to_highlight(final_attributes);
         }
 
+        if let Some(filter) = &self.tenant_filter {
+            search_builder.tenant_filter(filter.to_string());
+        }
         if let Some(filters) = &self.filters {
             search_builder.filters(filters.to_string());
         }
+        if self.filters.is_some() || self.tenant_filter.is_some() {
+            if let Some(filter_cache) = &data.filter_cache {
+                search_builder.filter_cache(filter_cache, index_uid);
+            }
+        }
 
         if let Some(matches) = self.matches {
             if matches {
                 search_builder.get_matches();
             }
         }
-        search_builder.search(&reader)
+
+        if self.profile == Some(true) {
+            search_builder.profile();
+        }
+
+        let experiment = match (&self.user_token, index.main.experiment(&reader)?) {
+            (Some(user_token), Some(experiment)) => {
+                assign_variant(&experiment, user_token).map(|variant| (experiment.name.clone(), variant.clone()))
+            }
+            _ => None,
+        };
+        if let Some((_, variant)) = &experiment {
+            if let Some(rules) = &variant.ranking_rules {
+                search_builder.ranking_rules(RankingRule::try_from_iter(rules).map_err(Error::bad_request)?);
+            }
+        }
+
+        let prepared = start.elapsed();
+        let mut result = search_builder.search(&reader)?;
+        result.experiment = experiment.map(|(name, variant)| ExperimentAssignment { name, variant: variant.name });
+
+        if let Some(search_analytics) = &data.search_analytics {
+            search_analytics.record(index_uid, self.q.as_deref(), result.nb_hits, start.elapsed());
+        }
+
+        if let Some(slow_queries) = &data.slow_queries {
+            let total = start.elapsed();
+            if slow_queries.is_slow(total) {
+                slow_queries.record(self.slow_query(index_uid, &result, prepared, total));
+            }
+        }
+
+        Ok(result)
     }
+
+    fn slow_query(&self, index_uid: &str, result: &SearchResult, prepared: Duration, total: Duration) -> SlowQuery {
+        let as_ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let query_ms = result.processing_time_ms as f64;
+        let timings = SearchTimings {
+            total_ms: as_ms(total),
+            prepare_ms: as_ms(prepared),
+            query_ms,
+            format_ms: (as_ms(total) - as_ms(prepared) - query_ms).max(0.0),
+        };
+
+        SlowQuery {
+            message: "slow search",
+            index_uid: index_uid.to_string(),
+            query: self.q.clone(),
+            filters: self.filters.clone(),
+            facet_filters: self.facet_filters.clone(),
+            offset: result.offset,
+            limit: result.limit,
+            nb_hits: result.nb_hits,
+            timings,
+            date: Utc::now(),
+        }
+    }
+}
+
+/// Parses the incoming string into the bucket edges of the attributes for which to return the
+/// number of documents in each bucket. The edges of an attribute must be in ascending order.
+fn prepare_facet_ranges(
+    ranges: &str,
+    schema: &Schema,
+    facet_attrs: &[FieldId],
+) -> Result<Vec<((FieldId, String), Vec<f64>)>, ResponseError> {
+    let ranges: BTreeMap<String, Vec<f64>> = serde_json::from_str(ranges)
+        .map_err(|e| Error::bad_parameter("facetRanges", e))?;
+
+    let mut prepared = Vec::with_capacity(ranges.len());
+    for (facet, edges) in ranges {
+        let field_id = match schema.id(&facet) {
+            Some(id) if facet_attrs.contains(&id) => id,
+            Some(_) => return Err(FacetCountError::AttributeNotSet(facet).into()),
+            None => continue,
+        };
+        if edges.len() < 2 || edges.windows(2).any(|pair| pair[0] >= pair[1]) {
+            let message = format!("the edges of {} must be at least two numbers in ascending order", facet);
+            return Err(Error::bad_parameter("facetRanges", message).into());
+        }
+        prepared.push(((field_id, facet), edges));
+    }
+
+    Ok(prepared)
+}
+
+/// Parses the multipliers of the facet values, e.g. `{ "brand": { "apple": 2 } }`, the boosted
+/// attributes must be set as facets.
+fn prepare_boost(
+    boost: &str,
+    schema: &Schema,
+    facet_attrs: &[FieldId],
+) -> Result<Vec<((FieldId, String), f64)>, ResponseError> {
+    let boost: BTreeMap<String, BTreeMap<String, f64>> = serde_json::from_str(boost)
+        .map_err(|e| Error::bad_parameter("boost", e))?;
+
+    let mut prepared = Vec::new();
+    for (facet, multipliers) in boost {
+        let field_id = match schema.id(&facet) {
+            Some(id) if facet_attrs.contains(&id) => id,
+            Some(_) => return Err(FacetCountError::AttributeNotSet(facet).into()),
+            None => continue,
+        };
+        for (value, multiplier) in multipliers {
+            if !multiplier.is_finite() || multiplier <= 0.0 {
+                let message = format!("the multiplier of {} in {} must be a positive number", value, facet);
+                return Err(Error::bad_parameter("boost", message).into());
+            }
+            prepared.push(((field_id, value), multiplier));
+        }
+    }
+
+    Ok(prepared)
+}
+
+/// Parses the incoming string into the rules sorting the documents, written `attribute:asc` for
+/// an attribute used in an asc or desc ranking rule or `_geoPoint(lat,lng):asc` to sort them by
+/// their distance to a point.
+fn prepare_sort(sort: &str, schema: &Schema) -> Result<Vec<SortRule>, ResponseError> {
+    let sort: Vec<String> = serde_json::from_str(sort).map_err(|e| Error::bad_parameter("sort", e))?;
+
+    let mut prepared = Vec::with_capacity(sort.len());
+    for rule in sort {
+        let invalid = |message: String| -> ResponseError { Error::bad_parameter("sort", message).into() };
+        let (criterion, ascending) = match rule.rfind(':').map(|i| (&rule[..i], &rule[i + 1..])) {
+            Some((criterion, "asc")) => (criterion, true),
+            Some((criterion, "desc")) => (criterion, false),
+            _ => return Err(invalid(format!("{} must end with :asc or :desc", rule))),
+        };
+
+        if criterion.starts_with("_geoPoint(") && criterion.ends_with(')') {
+            let point = &criterion["_geoPoint(".len()..criterion.len() - 1];
+            let coordinates: Vec<f64> = point.split(',').filter_map(|c| c.trim().parse().ok()).collect();
+            match coordinates[..] {
+                [lat, lng] if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) => {
+                    prepared.push(SortRule::GeoPoint { lat, lng, ascending });
+                }
+                _ => return Err(invalid(format!("{} must be a latitude and a longitude in degrees", criterion))),
+            }
+        } else {
+            match schema.id(criterion) {
+                Some(field_id) if schema.is_ranked(field_id) => {
+                    prepared.push(SortRule::Attribute { name: criterion.to_string(), ascending });
+                }
+                _ => return Err(invalid(format!("attribute {} must be used in an asc or desc ranking rule to be sorted", criterion))),
+            }
+        }
+    }
+
+    Ok(prepared)
 }
 
 /// Parses the incoming string into an array of attributes for which to return a count. It returns
@@ -0,0 +1,141 @@
+use actix_web::{web, HttpResponse};
+use actix_web::{delete, get, post};
+use meilisearch_core::settings::{SettingsUpdate, UpdateState};
+use std::collections::BTreeSet;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::routes::{IndexParam, IndexUpdateResponse};
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_separators)
+        .service(update_separators)
+        .service(delete_separators)
+        .service(get_non_separators)
+        .service(update_non_separators)
+        .service(delete_non_separators);
+}
+
+/// The tokenizer classifies the text character by character, a token must be a single character.
+pub fn check_tokens(name: &str, tokens: &BTreeSet<String>) -> Result<(), Error> {
+    match tokens.iter().find(|token| token.chars().count() != 1) {
+        Some(token) => Err(Error::bad_parameter(name, format!("{:?} is not a single character", token))),
+        None => Ok(()),
+    }
+}
+
+fn enqueue(data: &Data, index_uid: &str, settings: SettingsUpdate) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(index_uid)
+        .ok_or(Error::index_not_found(index_uid))?;
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/separator-tokens",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_separators(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let tokens = index.main.separator_tokens(&reader)?;
+
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/separator-tokens",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_separators(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<BTreeSet<String>>,
+) -> Result<HttpResponse, ResponseError> {
+    let tokens = body.into_inner();
+    check_tokens("separatorTokens", &tokens)?;
+
+    let settings = SettingsUpdate {
+        separator_tokens: UpdateState::Update(tokens),
+        ..SettingsUpdate::default()
+    };
+    enqueue(&data, &path.index_uid, settings)
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/separator-tokens",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_separators(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let settings = SettingsUpdate {
+        separator_tokens: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+    enqueue(&data, &path.index_uid, settings)
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/non-separator-tokens",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_non_separators(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let tokens = index.main.non_separator_tokens(&reader)?;
+
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/non-separator-tokens",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_non_separators(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<BTreeSet<String>>,
+) -> Result<HttpResponse, ResponseError> {
+    let tokens = body.into_inner();
+    check_tokens("nonSeparatorTokens", &tokens)?;
+
+    let settings = SettingsUpdate {
+        non_separator_tokens: UpdateState::Update(tokens),
+        ..SettingsUpdate::default()
+    };
+    enqueue(&data, &path.index_uid, settings)
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/non-separator-tokens",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_non_separators(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let settings = SettingsUpdate {
+        non_separator_tokens: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+    enqueue(&data, &path.index_uid, settings)
+}
@@ -1,18 +1,24 @@
 use std::collections::{BTreeMap, BTreeSet};
 
-use actix_web::{delete, get, post};
+use actix_web::{delete, get, post, put};
 use actix_web::{web, HttpResponse};
-use meilisearch_core::{MainReader, UpdateWriter};
-use meilisearch_core::settings::{Settings, SettingsUpdate, UpdateState, DEFAULT_RANKING_RULES};
+use meilisearch_core::{MainReader, UpdateStatus, UpdateType, UpdateWriter};
+use meilisearch_core::settings::{FacetingSettings, Language, NormalizationSettings, RankingBehavior, Settings, SettingsUpdate, TokenizerMode, TypoToleranceSettings, UpdateState, DEFAULT_RANKING_RULES};
 use meilisearch_schema::Schema;
+use serde::Serialize;
+use serde_json::{Map, Value};
 
 use crate::Data;
 use crate::error::{Error, ResponseError};
-use crate::helpers::Authentication;
+use crate::helpers::{Action, Authentication};
 use crate::routes::{IndexParam, IndexUpdateResponse};
+use crate::routes::dictionary::check_words;
+use crate::routes::query_rules::check_rules;
+use crate::routes::separator_tokens::check_tokens;
 
 pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(update_all)
+        .service(replace_all)
         .service(get_all)
         .service(delete_all)
         .service(get_rules)
@@ -29,7 +35,28 @@ pub fn services(cfg: &mut web::ServiceConfig) {
         .service(delete_displayed)
         .service(get_attributes_for_faceting)
         .service(delete_attributes_for_faceting)
-        .service(update_attributes_for_faceting);
+        .service(update_attributes_for_faceting)
+        .service(get_faceting)
+        .service(update_faceting)
+        .service(delete_faceting)
+        .service(get_typo_tolerance)
+        .service(update_typo_tolerance)
+        .service(delete_typo_tolerance)
+        .service(get_ranking_behavior)
+        .service(update_ranking_behavior)
+        .service(delete_ranking_behavior)
+        .service(get_normalization)
+        .service(update_normalization)
+        .service(delete_normalization)
+        .service(get_languages)
+        .service(update_languages)
+        .service(delete_languages)
+        .service(get_attribute_tokenizers)
+        .service(update_attribute_tokenizers)
+        .service(delete_attribute_tokenizers)
+        .service(get_identifier_splitting)
+        .service(update_identifier_splitting)
+        .service(delete_identifier_splitting);
 }
 
 pub fn update_all_settings_txn(
@@ -47,16 +74,15 @@ pub fn update_all_settings_txn(
     Ok(update_id)
 }
 
-#[post("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
+#[post("/indexes/{index_uid}/settings", wrap = "Authentication::Action(Action::SettingsUpdate)")]
 async fn update_all(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     body: web::Json<Settings>,
 ) -> Result<HttpResponse, ResponseError> {
-    let settings = body
-        .into_inner()
-        .to_update()
-        .map_err(Error::bad_request)?;
+    let settings = body.into_inner();
+    check_settings(&settings)?;
+    let settings = settings.to_update().map_err(Error::bad_request)?;
 
     let update_id = data.db.update_write::<_, _, Error>(|writer| {
         update_all_settings_txn(&data, settings, &path.index_uid, writer)
@@ -65,6 +91,166 @@ async fn update_all(
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
 }
 
+fn check_settings(settings: &Settings) -> Result<(), Error> {
+    if let Some(Some(tokens)) = &settings.separator_tokens {
+        check_tokens("separatorTokens", tokens)?;
+    }
+    if let Some(Some(tokens)) = &settings.non_separator_tokens {
+        check_tokens("nonSeparatorTokens", tokens)?;
+    }
+    if let Some(Some(words)) = &settings.dictionary {
+        check_words(words)?;
+    }
+    if let Some(Some(rules)) = &settings.query_rules {
+        check_rules(rules)?;
+    }
+    Ok(())
+}
+
+/// The settings of an index that has never been configured, as returned by `get_all_sync`.
+fn default_settings() -> Settings {
+    let wildcard = || ["*"].iter().map(|s| s.to_string()).collect();
+    Settings {
+        ranking_rules: Some(Some(DEFAULT_RANKING_RULES.iter().map(|r| r.to_string()).collect())),
+        distinct_attribute: Some(None),
+        searchable_attributes: Some(Some(wildcard())),
+        displayed_attributes: Some(Some(wildcard())),
+        stop_words: Some(Some(BTreeSet::new())),
+        separator_tokens: Some(Some(BTreeSet::new())),
+        non_separator_tokens: Some(Some(BTreeSet::new())),
+        dictionary: Some(Some(BTreeSet::new())),
+        synonyms: Some(Some(BTreeMap::new())),
+        attributes_for_faceting: Some(Some(Vec::new())),
+        faceting: Some(Some(FacetingSettings::default())),
+        query_rules: Some(Some(Vec::new())),
+        typo_tolerance: Some(Some(TypoToleranceSettings::default())),
+        ranking_behavior: Some(Some(RankingBehavior::default())),
+        normalization: Some(Some(NormalizationSettings::default())),
+        languages: Some(Some(BTreeSet::new())),
+        attribute_tokenizers: Some(Some(BTreeMap::new())),
+        identifier_splitting: Some(Some(false)),
+    }
+}
+
+/// Returns the settings as a JSON object in which the missing settings are replaced by their
+/// default and the attributes lists containing a wildcard are a single wildcard, so that two
+/// settings resulting in the same configuration are equal.
+fn normalized_settings(settings: &Settings) -> Result<Map<String, Value>, Error> {
+    let defaults = match serde_json::to_value(default_settings())? {
+        Value::Object(defaults) => defaults,
+        _ => return Err(Error::internal("settings must be serialized as an object")),
+    };
+    let mut settings = match serde_json::to_value(settings)? {
+        Value::Object(settings) => settings,
+        _ => return Err(Error::internal("settings must be serialized as an object")),
+    };
+
+    for (name, value) in settings.iter_mut() {
+        if value.is_null() {
+            *value = defaults.get(name).cloned().unwrap_or(Value::Null);
+        }
+        if name == "searchableAttributes" || name == "displayedAttributes" {
+            let is_wildcard = value.as_array().map_or(false, |attributes| {
+                attributes.is_empty() || attributes.iter().any(|attribute| attribute == "*")
+            });
+            if is_wildcard {
+                *value = Value::from(vec!["*"]);
+            }
+        }
+    }
+
+    Ok(settings)
+}
+
+/// The names of the settings modified by an update, as named in the settings object.
+fn updated_settings(update: &SettingsUpdate) -> Result<Vec<String>, Error> {
+    let update = match serde_json::to_value(update)? {
+        Value::Object(update) => update,
+        _ => return Err(Error::internal("settings updates must be serialized as an object")),
+    };
+
+    let names = update
+        .into_iter()
+        .filter(|(_, state)| state != "Nothing")
+        .map(|(name, _)| {
+            let mut words = name.split('_');
+            let first = words.next().unwrap_or_default().to_string();
+            words.fold(first, |mut name, word| {
+                let mut chars = word.chars();
+                name.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                name.push_str(chars.as_str());
+                name
+            })
+        })
+        .collect();
+
+    Ok(names)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplaceSettingsResponse {
+    #[serde(flatten)]
+    update: IndexUpdateResponse,
+    /// The settings that differ from the current ones.
+    changes: Vec<String>,
+}
+
+/// Replaces all the settings of the index, the settings missing from the body are reset.
+/// Only the settings that differ from the current ones are part of the enqueued update, along
+/// with the ones that are modified by the settings updates that have not been processed yet.
+#[put("/indexes/{index_uid}/settings", wrap = "Authentication::Action(Action::SettingsUpdate)")]
+async fn replace_all(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Settings>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = body.into_inner();
+    check_settings(&settings)?;
+    settings.to_update().map_err(Error::bad_request)?;
+    let target = normalized_settings(&settings)?;
+    let submitted = serde_json::to_value(&settings)?;
+
+    let (update_id, changes) = data.db.update_write::<_, _, Error>(|writer| {
+        let mut pending = BTreeSet::new();
+        for status in index.all_updates_status(writer)? {
+            if let UpdateStatus::Enqueued { content } = status {
+                if let UpdateType::Settings { settings } = content.update_type {
+                    pending.extend(updated_settings(&settings)?);
+                }
+            }
+        }
+
+        let reader = data.db.main_read_txn()?;
+        let current = normalized_settings(&get_all_sync(&data, &reader, &path.index_uid)?)?;
+        let changes: Vec<String> = target
+            .iter()
+            .filter(|(name, value)| current.get(name.as_str()) != Some(*value))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        // the settings absent from the body are null, which resets them
+        let update: Map<String, Value> = target
+            .keys()
+            .filter(|name| changes.contains(*name) || pending.contains(*name))
+            .map(|name| (name.clone(), submitted.get(name).cloned().unwrap_or(Value::Null)))
+            .collect();
+        let update: Settings = serde_json::from_value(Value::Object(update))?;
+        let update = update.to_update().map_err(Error::bad_request)?;
+
+        let update_id = index.settings_update(writer, update)?;
+        Ok((update_id, changes))
+    })?;
+
+    let response = ReplaceSettingsResponse { update: IndexUpdateResponse::with_id(update_id), changes };
+    Ok(HttpResponse::Accepted().json(response))
+}
+
 pub fn get_all_sync(data: &web::Data<Data>, reader: &MainReader, index_uid: &str) -> Result<Settings, Error> {
     let index = data
         .db
@@ -77,6 +263,18 @@ pub fn get_all_sync(data: &web::Data<Data>, reader: &MainReader, index_uid: &str
         .into_iter()
         .collect();
 
+    let separator_tokens = index.main.separator_tokens(reader)?;
+    let non_separator_tokens = index.main.non_separator_tokens(reader)?;
+    let dictionary = index.main.dictionary(reader)?;
+    let faceting = index.main.faceting(reader)?;
+    let query_rules = index.main.query_rules(reader)?;
+    let typo_tolerance = index.main.typo_tolerance(reader)?;
+    let ranking_behavior = index.main.ranking_behavior(reader)?;
+    let normalization = index.main.normalization(reader)?;
+    let languages = index.main.languages(reader)?;
+    let attribute_tokenizers = index.main.attribute_tokenizers(reader)?;
+    let identifier_splitting = index.main.identifier_splitting(reader)?;
+
     let synonyms_list = index.main.synonyms(reader)?;
 
     let mut synonyms = BTreeMap::new();
@@ -122,12 +320,23 @@ pub fn get_all_sync(data: &web::Data<Data>, reader: &MainReader, index_uid: &str
         searchable_attributes: Some(searchable_attributes),
         displayed_attributes: Some(displayed_attributes),
         stop_words: Some(Some(stop_words)),
+        separator_tokens: Some(Some(separator_tokens)),
+        non_separator_tokens: Some(Some(non_separator_tokens)),
+        dictionary: Some(Some(dictionary)),
         synonyms: Some(Some(synonyms)),
         attributes_for_faceting: Some(Some(attributes_for_faceting)),
+        faceting: Some(Some(faceting)),
+        query_rules: Some(Some(query_rules)),
+        typo_tolerance: Some(Some(typo_tolerance)),
+        ranking_behavior: Some(Some(ranking_behavior)),
+        normalization: Some(Some(normalization)),
+        languages: Some(Some(languages)),
+        attribute_tokenizers: Some(Some(attribute_tokenizers)),
+        identifier_splitting: Some(Some(identifier_splitting)),
     })
 }
 
-#[get("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
+#[get("/indexes/{index_uid}/settings", wrap = "Authentication::Action(Action::SettingsGet)")]
 async fn get_all(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -138,7 +347,7 @@ async fn get_all(
     Ok(HttpResponse::Ok().json(settings))
 }
 
-#[delete("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
+#[delete("/indexes/{index_uid}/settings", wrap = "Authentication::Action(Action::SettingsUpdate)")]
 async fn delete_all(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -155,8 +364,19 @@ async fn delete_all(
         searchable_attributes: UpdateState::Clear,
         displayed_attributes: UpdateState::Clear,
         stop_words: UpdateState::Clear,
+        separator_tokens: UpdateState::Clear,
+        non_separator_tokens: UpdateState::Clear,
+        dictionary: UpdateState::Clear,
         synonyms: UpdateState::Clear,
         attributes_for_faceting: UpdateState::Clear,
+        faceting: UpdateState::Clear,
+        query_rules: UpdateState::Clear,
+        typo_tolerance: UpdateState::Clear,
+        ranking_behavior: UpdateState::Clear,
+        normalization: UpdateState::Clear,
+        languages: UpdateState::Clear,
+        attribute_tokenizers: UpdateState::Clear,
+        identifier_splitting: UpdateState::Clear,
     };
 
     let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
@@ -166,7 +386,7 @@ async fn delete_all(
 
 #[get(
     "/indexes/{index_uid}/settings/ranking-rules",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsGet)"
 )]
 async fn get_rules(
     data: web::Data<Data>,
@@ -191,7 +411,7 @@ async fn get_rules(
 
 #[post(
     "/indexes/{index_uid}/settings/ranking-rules",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn update_rules(
     data: web::Data<Data>,
@@ -216,7 +436,7 @@ async fn update_rules(
 
 #[delete(
     "/indexes/{index_uid}/settings/ranking-rules",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn delete_rules(
     data: web::Data<Data>,
@@ -239,7 +459,7 @@ async fn delete_rules(
 
 #[get(
     "/indexes/{index_uid}/settings/distinct-attribute",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsGet)"
 )]
 async fn get_distinct(
     data: web::Data<Data>,
@@ -262,7 +482,7 @@ async fn get_distinct(
 
 #[post(
     "/indexes/{index_uid}/settings/distinct-attribute",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn update_distinct(
     data: web::Data<Data>,
@@ -287,7 +507,7 @@ async fn update_distinct(
 
 #[delete(
     "/indexes/{index_uid}/settings/distinct-attribute",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn delete_distinct(
     data: web::Data<Data>,
@@ -310,7 +530,7 @@ async fn delete_distinct(
 
 #[get(
     "/indexes/{index_uid}/settings/searchable-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsGet)"
 )]
 async fn get_searchable(
     data: web::Data<Data>,
@@ -330,7 +550,7 @@ async fn get_searchable(
 
 #[post(
     "/indexes/{index_uid}/settings/searchable-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn update_searchable(
     data: web::Data<Data>,
@@ -356,7 +576,7 @@ async fn update_searchable(
 
 #[delete(
     "/indexes/{index_uid}/settings/searchable-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn delete_searchable(
     data: web::Data<Data>,
@@ -379,7 +599,7 @@ async fn delete_searchable(
 
 #[get(
     "/indexes/{index_uid}/settings/displayed-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsGet)"
 )]
 async fn get_displayed(
     data: web::Data<Data>,
@@ -400,7 +620,7 @@ async fn get_displayed(
 
 #[post(
     "/indexes/{index_uid}/settings/displayed-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn update_displayed(
     data: web::Data<Data>,
@@ -425,7 +645,7 @@ async fn update_displayed(
 
 #[delete(
     "/indexes/{index_uid}/settings/displayed-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn delete_displayed(
     data: web::Data<Data>,
@@ -448,7 +668,7 @@ async fn delete_displayed(
 
 #[get(
     "/indexes/{index_uid}/settings/attributes-for-faceting",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsGet)"
 )]
 async fn get_attributes_for_faceting(
     data: web::Data<Data>,
@@ -482,7 +702,7 @@ async fn get_attributes_for_faceting(
 
 #[post(
     "/indexes/{index_uid}/settings/attributes-for-faceting",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn update_attributes_for_faceting(
     data: web::Data<Data>,
@@ -507,7 +727,7 @@ async fn update_attributes_for_faceting(
 
 #[delete(
     "/indexes/{index_uid}/settings/attributes-for-faceting",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn delete_attributes_for_faceting(
     data: web::Data<Data>,
@@ -528,6 +748,468 @@ async fn delete_attributes_for_faceting(
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
 }
 
+#[get(
+    "/indexes/{index_uid}/settings/faceting",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_faceting(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let faceting = index.main.faceting(&reader)?;
+
+    Ok(HttpResponse::Ok().json(faceting))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/faceting",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_faceting(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<FacetingSettings>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        faceting: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let settings = settings.to_update().map_err(Error::bad_request)?;
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/faceting",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_faceting(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        faceting: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/typo-tolerance",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_typo_tolerance(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let typo_tolerance = index.main.typo_tolerance(&reader)?;
+
+    Ok(HttpResponse::Ok().json(typo_tolerance))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/typo-tolerance",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_typo_tolerance(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<TypoToleranceSettings>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        typo_tolerance: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let settings = settings.to_update().map_err(Error::bad_request)?;
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/typo-tolerance",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_typo_tolerance(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        typo_tolerance: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/ranking-behavior",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_ranking_behavior(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let ranking_behavior = index.main.ranking_behavior(&reader)?;
+
+    Ok(HttpResponse::Ok().json(ranking_behavior))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/ranking-behavior",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_ranking_behavior(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<RankingBehavior>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        ranking_behavior: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let settings = settings.to_update().map_err(Error::bad_request)?;
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/ranking-behavior",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_ranking_behavior(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        ranking_behavior: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/normalization",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_normalization(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let normalization = index.main.normalization(&reader)?;
+
+    Ok(HttpResponse::Ok().json(normalization))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/normalization",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_normalization(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<NormalizationSettings>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        normalization: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let settings = settings.to_update().map_err(Error::bad_request)?;
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/normalization",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_normalization(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        normalization: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/languages",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_languages(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let languages = index.main.languages(&reader)?;
+
+    Ok(HttpResponse::Ok().json(languages))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/languages",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_languages(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<BTreeSet<Language>>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        languages: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let settings = settings.to_update().map_err(Error::bad_request)?;
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/languages",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_languages(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        languages: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/attribute-tokenizers",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_attribute_tokenizers(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let attribute_tokenizers = index.main.attribute_tokenizers(&reader)?;
+
+    Ok(HttpResponse::Ok().json(attribute_tokenizers))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/attribute-tokenizers",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_attribute_tokenizers(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<BTreeMap<String, TokenizerMode>>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        attribute_tokenizers: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let settings = settings.to_update().map_err(Error::bad_request)?;
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/attribute-tokenizers",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_attribute_tokenizers(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        attribute_tokenizers: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/identifier-splitting",
+    wrap = "Authentication::Action(Action::SettingsGet)"
+)]
+async fn get_identifier_splitting(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let identifier_splitting = index.main.identifier_splitting(&reader)?;
+
+    Ok(HttpResponse::Ok().json(identifier_splitting))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/identifier-splitting",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn update_identifier_splitting(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<bool>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        identifier_splitting: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let settings = settings.to_update().map_err(Error::bad_request)?;
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/identifier-splitting",
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
+)]
+async fn delete_identifier_splitting(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        identifier_splitting: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
 fn get_indexed_attributes(schema: &Schema) -> Vec<String> {
     if schema.is_indexed_all() {
         ["*"].iter().map(|s| s.to_string()).collect()
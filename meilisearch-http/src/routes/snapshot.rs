@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use actix_web::{delete, get};
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::download::file_response;
+use crate::helpers::{Action, Authentication};
+use crate::snapshot::{delete_snapshot, list_snapshots, snapshot_archive_path};
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_snapshots)
+        .service(download_snapshot)
+        .service(remove_snapshot);
+}
+
+#[derive(Deserialize)]
+struct SnapshotParam {
+    snapshot_id: String,
+}
+
+fn snapshot_dir(data: &Data) -> Result<&Path, Error> {
+    data.scheduler.snapshot_dir().ok_or_else(|| Error::not_found("no snapshot path is configured"))
+}
+
+#[get("/snapshots", wrap = "Authentication::Action(Action::SnapshotsGet)")]
+async fn get_snapshots(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let snapshots = list_snapshots(&data, snapshot_dir(&data)?)?;
+    Ok(HttpResponse::Ok().json(snapshots))
+}
+
+#[get("/snapshots/{snapshot_id}/download", wrap = "Authentication::Action(Action::SnapshotsGet)")]
+async fn download_snapshot(
+    data: web::Data<Data>,
+    path: web::Path<SnapshotParam>,
+) -> Result<HttpResponse, ResponseError> {
+    match snapshot_archive_path(&data, snapshot_dir(&data)?, &path.snapshot_id)? {
+        Some(archive_path) => file_response(&archive_path, &format!("{}.tar.gz", path.snapshot_id)),
+        None => Err(Error::not_found("snapshot does not exist").into()),
+    }
+}
+
+#[delete("/snapshots/{snapshot_id}", wrap = "Authentication::Action(Action::SnapshotsDelete)")]
+async fn remove_snapshot(
+    data: web::Data<Data>,
+    path: web::Path<SnapshotParam>,
+) -> Result<HttpResponse, ResponseError> {
+    if delete_snapshot(&data, snapshot_dir(&data)?, &path.snapshot_id)? {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(Error::not_found("snapshot does not exist").into())
+    }
+}
@@ -1,21 +1,27 @@
 use std::collections::{HashMap, BTreeMap};
+use std::fmt::Write;
 
 use actix_web::web;
 use actix_web::HttpResponse;
 use actix_web::get;
 use chrono::{DateTime, Utc};
 use log::error;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
 use crate::error::{Error, ResponseError};
-use crate::helpers::Authentication;
+use crate::helpers::{Action, Authentication};
+use crate::capacity::Capacity;
 use crate::routes::IndexParam;
+use crate::search_analytics::Window;
 use crate::Data;
 
 pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(index_stats)
+        .service(get_index_analytics)
         .service(get_stats)
+        .service(get_tasks_stats)
+        .service(get_metrics)
         .service(get_version);
 }
 
@@ -25,9 +31,11 @@ struct IndexStatsResponse {
     number_of_documents: u64,
     is_indexing: bool,
     fields_distribution: BTreeMap<String, usize>,
+    /// The size, in bytes, of the documents and search structures of the index.
+    size: u64,
 }
 
-#[get("/indexes/{index_uid}/stats", wrap = "Authentication::Private")]
+#[get("/indexes/{index_uid}/stats", wrap = "Authentication::Action(Action::StatsGet)")]
 async fn index_stats(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -43,6 +51,8 @@ async fn index_stats(
 
     let fields_distribution = index.main.fields_distribution(&reader)?.unwrap_or_default();
 
+    let size = index.size(&reader)?;
+
     let update_reader = data.db.update_read_txn()?;
 
     let is_indexing =
@@ -55,18 +65,51 @@ async fn index_stats(
         number_of_documents,
         is_indexing,
         fields_distribution,
+        size,
     }))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct AnalyticsQuery {
+    window: Option<String>,
+    limit: Option<usize>,
+}
+
+#[get("/indexes/{index_uid}/analytics", wrap = "Authentication::Action(Action::StatsGet)")]
+async fn get_index_analytics(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<AnalyticsQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    data.db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let search_analytics = data
+        .search_analytics
+        .as_ref()
+        .ok_or(Error::bad_request("the search analytics are disabled"))?;
+
+    let window = match &params.window {
+        Some(window) => window.parse::<Window>().map_err(|e| Error::bad_parameter("window", e))?,
+        None => Window::default(),
+    };
+    let limit = params.limit.unwrap_or(10);
+
+    Ok(HttpResponse::Ok().json(search_analytics.report(&path.index_uid, window, limit)))
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct StatsResult {
     database_size: u64,
     last_update: Option<DateTime<Utc>>,
     indexes: HashMap<String, IndexStatsResponse>,
+    capacity: Capacity,
 }
 
-#[get("/stats", wrap = "Authentication::Private")]
+#[get("/stats", wrap = "Authentication::Action(Action::StatsGet)")]
 async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
     let mut index_list = HashMap::new();
 
@@ -82,6 +125,8 @@ async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError>
 
                 let fields_distribution = index.main.fields_distribution(&reader)?.unwrap_or_default();
 
+                let size = index.size(&reader)?;
+
                 let is_indexing = data.db.is_indexing(&update_reader, &index_uid)?.ok_or(
                     Error::internal("Impossible to know if the database is indexing"),
                 )?;
@@ -90,6 +135,7 @@ async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError>
                     number_of_documents,
                     is_indexing,
                     fields_distribution,
+                    size,
                 };
                 index_list.insert(index_uid, response);
             }
@@ -113,9 +159,160 @@ async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError>
         database_size,
         last_update,
         indexes: index_list,
+        capacity: data.capacity.check(),
     }))
 }
 
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskTypeStats {
+    processed: u64,
+    total_duration: f64,
+    average_duration: f64,
+    max_duration: f64,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexTasksStats {
+    queue_depth: u64,
+    indexed_documents: u64,
+    indexing_duration: f64,
+    documents_per_second: f64,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TasksStats {
+    queue_depth: u64,
+    by_status: BTreeMap<&'static str, u64>,
+    by_type: BTreeMap<&'static str, TaskTypeStats>,
+    indexes: BTreeMap<String, IndexTasksStats>,
+}
+
+/// The tasks statistics, the queue depths are counted from the enqueued updates and the other
+/// statistics are counted as the tasks are processed, since the server started.
+fn tasks_stats(data: &Data) -> Result<TasksStats, ResponseError> {
+    let reader = data.db.update_read_txn()?;
+    let counters = data.task_metrics.counters();
+    let mut stats = TasksStats::default();
+
+    for index_uid in data.db.indexes_uids() {
+        let mut index_stats = IndexTasksStats::default();
+        if let Some(index) = data.db.open_index(&index_uid) {
+            index_stats.queue_depth = index.updates.count(&reader)?;
+            stats.queue_depth += index_stats.queue_depth;
+        }
+        stats.indexes.insert(index_uid, index_stats);
+    }
+
+    stats.by_status.insert("enqueued", stats.queue_depth);
+    stats.by_status.insert("processed", counters.processed);
+    stats.by_status.insert("failed", counters.failed);
+
+    for (update_type, type_counters) in counters.by_type {
+        stats.by_type.insert(update_type, TaskTypeStats {
+            processed: type_counters.processed,
+            total_duration: type_counters.total_duration,
+            average_duration: type_counters.total_duration / type_counters.processed as f64,
+            max_duration: type_counters.max_duration,
+        });
+    }
+
+    for (index_uid, index_counters) in counters.indexes {
+        let index_stats = stats.indexes.entry(index_uid).or_default();
+        index_stats.indexed_documents = index_counters.indexed_documents;
+        index_stats.indexing_duration = index_counters.indexing_duration;
+        if index_stats.indexing_duration > 0.0 {
+            index_stats.documents_per_second = index_stats.indexed_documents as f64 / index_stats.indexing_duration;
+        }
+    }
+
+    Ok(stats)
+}
+
+#[get("/stats/tasks", wrap = "Authentication::Action(Action::StatsGet)")]
+async fn get_tasks_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    Ok(HttpResponse::Ok().json(tasks_stats(&data)?))
+}
+
+/// Exposes the tasks statistics in the Prometheus text format.
+#[get("/metrics", wrap = "Authentication::Action(Action::StatsGet)")]
+async fn get_metrics(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    data.features.ensure_enabled("metrics", |features| features.metrics)?;
+    let stats = tasks_stats(&data)?;
+    let mut body = String::new();
+
+    // writing into a String never fails
+    let _ = writeln!(body, "# TYPE meilisearch_tasks_queue_depth gauge");
+    let _ = writeln!(body, "meilisearch_tasks_queue_depth {}", stats.queue_depth);
+
+    let _ = writeln!(body, "# TYPE meilisearch_tasks_total counter");
+    for status in &["processed", "failed"] {
+        let _ = writeln!(body, "meilisearch_tasks_total{{status=\"{}\"}} {}", status, stats.by_status[status]);
+    }
+
+    let _ = writeln!(body, "# TYPE meilisearch_task_duration_seconds summary");
+    for (update_type, type_stats) in &stats.by_type {
+        let _ = writeln!(body, "meilisearch_task_duration_seconds_sum{{type=\"{}\"}} {}", update_type, type_stats.total_duration);
+        let _ = writeln!(body, "meilisearch_task_duration_seconds_count{{type=\"{}\"}} {}", update_type, type_stats.processed);
+    }
+
+    let _ = writeln!(body, "# TYPE meilisearch_task_duration_seconds_max gauge");
+    for (update_type, type_stats) in &stats.by_type {
+        let _ = writeln!(body, "meilisearch_task_duration_seconds_max{{type=\"{}\"}} {}", update_type, type_stats.max_duration);
+    }
+
+    let _ = writeln!(body, "# TYPE meilisearch_index_queue_depth gauge");
+    for (index_uid, index_stats) in &stats.indexes {
+        let _ = writeln!(body, "meilisearch_index_queue_depth{{index=\"{}\"}} {}", index_uid, index_stats.queue_depth);
+    }
+
+    let _ = writeln!(body, "# TYPE meilisearch_index_indexed_documents counter");
+    for (index_uid, index_stats) in &stats.indexes {
+        let _ = writeln!(body, "meilisearch_index_indexed_documents{{index=\"{}\"}} {}", index_uid, index_stats.indexed_documents);
+    }
+
+    let _ = writeln!(body, "# TYPE meilisearch_index_indexing_seconds counter");
+    for (index_uid, index_stats) in &stats.indexes {
+        let _ = writeln!(body, "meilisearch_index_indexing_seconds{{index=\"{}\"}} {}", index_uid, index_stats.indexing_duration);
+    }
+
+    if let Some(limiter) = &data.search_limiter {
+        let (running, queued) = limiter.usage();
+        let _ = writeln!(body, "# TYPE meilisearch_searches_running gauge");
+        let _ = writeln!(body, "meilisearch_searches_running {}", running);
+        let _ = writeln!(body, "# TYPE meilisearch_searches_queued gauge");
+        let _ = writeln!(body, "meilisearch_searches_queued {}", queued);
+    }
+
+    let capacity = data.capacity.check();
+
+    let _ = writeln!(body, "# TYPE meilisearch_lmdb_map_size_bytes gauge");
+    for map in &capacity.maps {
+        let _ = writeln!(body, "meilisearch_lmdb_map_size_bytes{{env=\"{}\"}} {}", map.name, map.map_size_bytes);
+    }
+
+    let _ = writeln!(body, "# TYPE meilisearch_lmdb_used_bytes gauge");
+    for map in &capacity.maps {
+        let _ = writeln!(body, "meilisearch_lmdb_used_bytes{{env=\"{}\"}} {}", map.name, map.used_bytes);
+    }
+
+    let _ = writeln!(body, "# TYPE meilisearch_safety_mode gauge");
+    let _ = writeln!(body, "meilisearch_safety_mode {}", capacity.safety_mode as u8);
+
+    if let Some(disk) = &capacity.disk {
+        let _ = writeln!(body, "# TYPE meilisearch_disk_available_bytes gauge");
+        let _ = writeln!(body, "meilisearch_disk_available_bytes {}", disk.available_bytes);
+        let _ = writeln!(body, "# TYPE meilisearch_disk_total_bytes gauge");
+        let _ = writeln!(body, "meilisearch_disk_total_bytes {}", disk.total_bytes);
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body))
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct VersionResponse {
@@ -124,7 +321,7 @@ struct VersionResponse {
     pkg_version: String,
 }
 
-#[get("/version", wrap = "Authentication::Private")]
+#[get("/version", wrap = "Authentication::Action(Action::Version)")]
 async fn get_version() -> HttpResponse {
     HttpResponse::Ok().json(VersionResponse {
         commit_sha: env!("VERGEN_SHA").to_string(),
@@ -1,10 +1,10 @@
 use actix_web::{web, HttpResponse};
 use actix_web::{delete, get, post};
 use meilisearch_core::settings::{SettingsUpdate, UpdateState};
-use std::collections::BTreeSet;
+use meilisearch_core::stop_words::StopWords;
 
 use crate::error::{Error, ResponseError};
-use crate::helpers::Authentication;
+use crate::helpers::{Action, Authentication};
 use crate::routes::{IndexParam, IndexUpdateResponse};
 use crate::Data;
 
@@ -14,7 +14,7 @@ pub fn services(cfg: &mut web::ServiceConfig) {
 
 #[get(
     "/indexes/{index_uid}/settings/stop-words",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsGet)"
 )]
 async fn get(
     data: web::Data<Data>,
@@ -32,12 +32,12 @@ async fn get(
 
 #[post(
     "/indexes/{index_uid}/settings/stop-words",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn update(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
-    body: web::Json<BTreeSet<String>>,
+    body: web::Json<StopWords>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -45,7 +45,7 @@ async fn update(
         .ok_or(Error::index_not_found(&path.index_uid))?;
 
     let settings = SettingsUpdate {
-        stop_words: UpdateState::Update(body.into_inner()),
+        stop_words: UpdateState::Update(body.into_inner().0),
         ..SettingsUpdate::default()
     };
 
@@ -56,7 +56,7 @@ async fn update(
 
 #[delete(
     "/indexes/{index_uid}/settings/stop-words",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn delete(
     data: web::Data<Data>,
@@ -1,12 +1,11 @@
-use std::collections::BTreeMap;
-
 use actix_web::{web, HttpResponse};
 use actix_web::{delete, get, post};
 use indexmap::IndexMap;
 use meilisearch_core::settings::{SettingsUpdate, UpdateState};
+use meilisearch_core::synonyms::Synonyms;
 
 use crate::error::{Error, ResponseError};
-use crate::helpers::Authentication;
+use crate::helpers::{Action, Authentication};
 use crate::routes::{IndexParam, IndexUpdateResponse};
 use crate::Data;
 
@@ -16,7 +15,7 @@ pub fn services(cfg: &mut web::ServiceConfig) {
 
 #[get(
     "/indexes/{index_uid}/settings/synonyms",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsGet)"
 )]
 async fn get(
     data: web::Data<Data>,
@@ -43,12 +42,12 @@ async fn get(
 
 #[post(
     "/indexes/{index_uid}/settings/synonyms",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn update(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
-    body: web::Json<BTreeMap<String, Vec<String>>>,
+    body: web::Json<Synonyms>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -56,7 +55,7 @@ async fn update(
         .ok_or(Error::index_not_found(&path.index_uid))?;
 
     let settings = SettingsUpdate {
-        synonyms: UpdateState::Update(body.into_inner()),
+        synonyms: UpdateState::Update(body.into_inner().0),
         ..SettingsUpdate::default()
     };
 
@@ -67,7 +66,7 @@ async fn update(
 
 #[delete(
     "/indexes/{index_uid}/settings/synonyms",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Action(Action::SettingsUpdate)"
 )]
 async fn delete(
     data: web::Data<Data>,
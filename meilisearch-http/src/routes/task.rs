@@ -0,0 +1,304 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actix_web::{delete, get, post};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use meilisearch_core::{UpdateReader, UpdateStatus};
+use serde::{Deserialize, Serialize};
+
+use crate::Data;
+use crate::error::{Error, ResponseError};
+use crate::helpers::{Action, Authentication};
+use crate::helpers::authentication::{can_access_index, check_index_access};
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_tasks)
+        .service(delete_tasks)
+        .service(get_task)
+        .service(wait_task)
+        .service(cancel_tasks);
+}
+
+/// An update of any index, identified by the uid of its index and its update id.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub uid: String,
+    pub index_uid: String,
+    #[serde(flatten)]
+    pub status: UpdateStatus,
+}
+
+impl Task {
+    pub fn new(index_uid: &str, status: UpdateStatus) -> Task {
+        Task {
+            uid: task_uid(index_uid, status.update_id()),
+            index_uid: index_uid.to_string(),
+            status,
+        }
+    }
+}
+
+/// Index uids can't contain a colon, which makes it a non ambiguous separator.
+pub fn task_uid(index_uid: &str, update_id: u64) -> String {
+    format!("{}:{}", index_uid, update_id)
+}
+
+pub fn parse_task_uid(uid: &str) -> Result<(&str, u64), Error> {
+    let invalid = || Error::bad_parameter("uid", "a task uid must be of the form indexUid:updateId");
+    let pos = uid.rfind(':').ok_or_else(invalid)?;
+    let update_id = uid[pos + 1..].parse().map_err(|_| invalid())?;
+    Ok((&uid[..pos], update_id))
+}
+
+/// Returns the tasks of every index, the most recently enqueued first.
+pub fn all_tasks(data: &Data, reader: &UpdateReader) -> Result<Vec<Task>, ResponseError> {
+    let mut tasks = Vec::new();
+    for index_uid in data.db.indexes_uids() {
+        if let Some(index) = data.db.open_index(&index_uid) {
+            for status in index.all_updates_status(reader)? {
+                tasks.push(Task::new(&index_uid, status));
+            }
+        }
+    }
+
+    tasks.sort_by(|a, b| {
+        b.status.enqueued_at().cmp(&a.status.enqueued_at())
+            .then_with(|| b.uid.cmp(&a.uid))
+    });
+
+    Ok(tasks)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct TasksQuery {
+    index_uid: Option<String>,
+    #[serde(rename = "type")]
+    update_type: Option<String>,
+    status: Option<String>,
+    after_enqueued_at: Option<DateTime<Utc>>,
+    before_enqueued_at: Option<DateTime<Utc>>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+fn matches_list(list: &Option<String>, value: &str) -> bool {
+    match list {
+        Some(list) => list.split(',').any(|v| v.trim() == value),
+        None => true,
+    }
+}
+
+impl TasksQuery {
+    fn matches(&self, task: &Task) -> bool {
+        let enqueued_at = task.status.enqueued_at();
+        matches_list(&self.index_uid, &task.index_uid)
+            && matches_list(&self.update_type, task.status.update_type().name())
+            && matches_list(&self.status, task.status.status())
+            && self.after_enqueued_at.map_or(true, |date| enqueued_at >= date)
+            && self.before_enqueued_at.map_or(true, |date| enqueued_at <= date)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TasksResponse {
+    results: Vec<Task>,
+    total: usize,
+    offset: usize,
+    limit: usize,
+}
+
+#[get("/tasks", wrap = "Authentication::IndexesAction(Action::TasksGet)")]
+async fn get_tasks(
+    data: web::Data<Data>,
+    req: HttpRequest,
+    params: web::Query<TasksQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let offset = params.offset.unwrap_or(0);
+    let limit = params.limit.unwrap_or(20);
+
+    let reader = data.db.update_read_txn()?;
+    let tasks: Vec<_> = all_tasks(&data, &reader)?
+        .into_iter()
+        .filter(|task| params.matches(task) && can_access_index(&req, &task.index_uid))
+        .collect();
+
+    let total = tasks.len();
+    let results = tasks.into_iter().skip(offset).take(limit).collect();
+
+    Ok(HttpResponse::Ok().json(TasksResponse { results, total, offset, limit }))
+}
+
+/// Deletes the finished tasks matching the predicate and returns the number of deleted tasks.
+pub fn delete_finished_tasks<F>(data: &Data, predicate: F) -> Result<usize, ResponseError>
+where
+    F: Fn(&Task) -> bool,
+{
+    data.db.update_write(|writer| {
+        let mut deleted = 0;
+        for task in all_tasks(data, writer)? {
+            if task.status.processed_at().is_none() || !predicate(&task) {
+                continue;
+            }
+            if let Some(index) = data.db.open_index(&task.index_uid) {
+                if index.delete_update_result(writer, task.status.update_id())? {
+                    deleted += 1;
+                }
+            }
+        }
+        Ok(deleted)
+    })
+}
+
+/// Periodically deletes the tasks that have been processed for longer than the retention period.
+pub fn schedule_tasks_retention(data: Data, retention: Duration) {
+    let retention = chrono::Duration::from_std(retention).unwrap_or_else(|_| chrono::Duration::max_value());
+    thread::spawn(move || loop {
+        let limit = Utc::now().checked_sub_signed(retention);
+        let is_expired = |task: &Task| match (task.status.processed_at(), limit) {
+            (Some(processed_at), Some(limit)) => processed_at < limit,
+            _ => false,
+        };
+        match delete_finished_tasks(&data, is_expired) {
+            Ok(0) => (),
+            Ok(deleted) => info!("{} tasks deleted by the retention policy", deleted),
+            Err(e) => error!("Unsuccessful tasks retention: {}", e),
+        }
+        thread::sleep(Duration::from_secs(3600)); // one hour
+    });
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteTasksResponse {
+    deleted_tasks: usize,
+}
+
+/// Deletes the finished tasks matching the filters, `offset` and `limit` are ignored.
+#[delete("/tasks", wrap = "Authentication::IndexesAction(Action::TasksDelete)")]
+async fn delete_tasks(
+    data: web::Data<Data>,
+    req: HttpRequest,
+    params: web::Query<TasksQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let deleted_tasks = delete_finished_tasks(&data, |task| {
+        params.matches(task) && can_access_index(&req, &task.index_uid)
+    })?;
+    Ok(HttpResponse::Ok().json(DeleteTasksResponse { deleted_tasks }))
+}
+
+#[derive(Deserialize)]
+struct TaskParam {
+    task_uid: String,
+}
+
+pub(crate) fn find_task(data: &Data, task_uid: &str) -> Result<Task, ResponseError> {
+    let (index_uid, update_id) = parse_task_uid(task_uid)?;
+
+    let index = data
+        .db
+        .open_index(index_uid)
+        .ok_or(Error::index_not_found(index_uid))?;
+
+    let reader = data.db.update_read_txn()?;
+
+    match index.update_status(&reader, update_id)? {
+        Some(status) => Ok(Task::new(index_uid, status)),
+        None => Err(Error::NotFound(format!("Task {}", task_uid)).into()),
+    }
+}
+
+#[get("/tasks/{task_uid}", wrap = "Authentication::Action(Action::TasksGet)")]
+async fn get_task(
+    data: web::Data<Data>,
+    path: web::Path<TaskParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let task = find_task(&data, &path.task_uid)?;
+    Ok(HttpResponse::Ok().json(task))
+}
+
+const DEFAULT_WAIT_TIMEOUT_MS: u64 = 5_000;
+const MAX_WAIT_TIMEOUT_MS: u64 = 60_000;
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct WaitTaskQuery {
+    timeout_ms: Option<u64>,
+}
+
+/// Waits for the task to be processed, failed or canceled and returns it. The task
+/// is returned as is when the timeout elapses, it is up to the client to check its status.
+#[get("/tasks/{task_uid}/wait", wrap = "Authentication::Action(Action::TasksGet)")]
+async fn wait_task(
+    data: web::Data<Data>,
+    path: web::Path<TaskParam>,
+    params: web::Query<WaitTaskQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let timeout_ms = params.timeout_ms.unwrap_or(DEFAULT_WAIT_TIMEOUT_MS);
+    if timeout_ms > MAX_WAIT_TIMEOUT_MS {
+        let message = format!("the timeout can't be greater than {}ms", MAX_WAIT_TIMEOUT_MS);
+        return Err(Error::bad_parameter("timeoutMs", message).into());
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    loop {
+        let task = find_task(&data, &path.task_uid)?;
+        if task.status.processed_at().is_some() || Instant::now() >= deadline {
+            return Ok(HttpResponse::Ok().json(task));
+        }
+        actix_rt::time::delay_for(WAIT_POLL_INTERVAL).await;
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct CancelTasksQuery {
+    uids: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelTasksResponse {
+    canceled: Vec<String>,
+    not_canceled: Vec<String>,
+}
+
+/// Cancels enqueued tasks, tasks that are being processed or are already
+/// processed can't be canceled and are reported as such.
+#[post("/tasks/cancel", wrap = "Authentication::IndexesAction(Action::TasksCancel)")]
+async fn cancel_tasks(
+    data: web::Data<Data>,
+    req: HttpRequest,
+    params: web::Query<CancelTasksQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let mut tasks = Vec::new();
+    for uid in params.uids.split(',').map(str::trim).filter(|uid| !uid.is_empty()) {
+        let (index_uid, update_id) = parse_task_uid(uid)?;
+        check_index_access(&req, index_uid)?;
+        let index = data
+            .db
+            .open_index(index_uid)
+            .ok_or(Error::index_not_found(index_uid))?;
+        tasks.push((uid.to_string(), index, update_id));
+    }
+
+    let mut canceled = Vec::new();
+    let mut not_canceled = Vec::new();
+    data.db.update_write::<_, _, ResponseError>(|writer| {
+        for (uid, index, update_id) in tasks {
+            if index.cancel_update(writer, update_id)? {
+                canceled.push(uid);
+            } else {
+                not_canceled.push(uid);
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(HttpResponse::Ok().json(CancelTasksResponse { canceled, not_canceled }))
+}
@@ -0,0 +1,518 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use actix_web::web;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use log::{error, info};
+use meilisearch_core::update;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Data;
+use crate::crawler;
+use crate::dump::init_dump_process;
+use crate::error::Error;
+use crate::routes::index::is_valid_index_uid;
+use crate::s3::{self, S3Location};
+use crate::snapshot::create_retained_snapshot;
+
+/// The number of runs kept in the history of each scheduled task.
+const MAX_RUNS_HISTORY: usize = 20;
+
+/// A cron expression made of five fields: minute, hour, day of month, month and day of week.
+/// Each field accepts `*`, values, ranges (`1-5`), steps (`*/15`, `0-30/10`) and lists of these.
+/// A date matches when it matches every field, days of week go from 0 (sunday) to 6.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<CronSchedule, String> {
+        let fields: Vec<_> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("the cron expression {:?} must have exactly 5 fields", expression));
+        }
+
+        Ok(CronSchedule {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays: parse_field(fields[4], 0, 6)?,
+        })
+    }
+
+    pub fn matches(&self, date: &DateTime<Utc>) -> bool {
+        let is_set = |mask: u64, value: u32| mask & (1 << value) != 0;
+        is_set(self.minutes, date.minute())
+            && is_set(self.hours, date.hour())
+            && is_set(self.days, date.day())
+            && is_set(self.months, date.month())
+            && is_set(self.weekdays, date.weekday().num_days_from_sunday())
+    }
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let invalid = || format!("invalid cron field {:?}, values must be between {} and {}", field, min, max);
+    let parse_value = |value: &str| match value.parse::<u32>() {
+        Ok(value) if value >= min && value <= max => Ok(value),
+        _ => Err(invalid()),
+    };
+
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.find('/') {
+            Some(pos) => match part[pos + 1..].parse::<u32>() {
+                Ok(step) if step > 0 => (&part[..pos], step),
+                _ => return Err(invalid()),
+            },
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some(pos) = range.find('-') {
+            (parse_value(&range[..pos])?, parse_value(&range[pos + 1..])?)
+        } else {
+            let value = parse_value(range)?;
+            (value, value)
+        };
+
+        if start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+/// An operation that can be run periodically by the scheduler.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Job {
+    Snapshot,
+    Dump,
+    /// Deletes the documents of which the attribute is a date in the past, either
+    /// as a unix timestamp in seconds or as an RFC 3339 string.
+    #[serde(rename_all = "camelCase")]
+    DocumentsExpiration { attribute: String },
+    /// Indexes the objects of an S3 prefix, `s3://bucket/prefix`, that changed since the previous run.
+    #[serde(rename_all = "camelCase")]
+    S3Crawl {
+        index_uid: String,
+        url: String,
+        #[serde(default)]
+        primary_key: Option<String>,
+    },
+    /// Crawls a website from its sitemaps or from seed pages and indexes its pages.
+    #[serde(rename_all = "camelCase")]
+    WebCrawl {
+        index_uid: String,
+        urls: Vec<String>,
+        #[serde(default)]
+        max_pages: Option<usize>,
+    },
+}
+
+impl Job {
+    /// Parses a job and its schedule from the command line, e.g. `snapshot=0 3 * * *`
+    /// or `documentsExpiration:expiresAt=*/10 * * * *` or `s3Crawl:movies:s3://datasets/movies/=0 * * * *`
+    /// or `webCrawl:docs:https://example.com/sitemap.xml,https://example.com/blog=0 3 * * *`.
+    /// The schedule follows the last `=`, the urls can contain some.
+    pub fn parse_with_schedule(s: &str) -> Result<(Job, String), String> {
+        let pos = s.rfind('=').ok_or_else(|| format!("the schedule {:?} must be of the form job=cron", s))?;
+        let (name, cron) = (s[..pos].trim(), s[pos + 1..].trim());
+
+        let job = match name {
+            "snapshot" => Job::Snapshot,
+            "dump" => Job::Dump,
+            _ if name.starts_with("documentsExpiration:") => {
+                let attribute = name["documentsExpiration:".len()..].to_string();
+                Job::DocumentsExpiration { attribute }
+            }
+            _ if name.starts_with("s3Crawl:") => {
+                let rest = &name["s3Crawl:".len()..];
+                let pos = rest.find(':').ok_or_else(|| format!("the job {:?} must be of the form s3Crawl:indexUid:url", name))?;
+                Job::S3Crawl { index_uid: rest[..pos].to_string(), url: rest[pos + 1..].to_string(), primary_key: None }
+            }
+            _ if name.starts_with("webCrawl:") => {
+                let rest = &name["webCrawl:".len()..];
+                let pos = rest.find(':').ok_or_else(|| format!("the job {:?} must be of the form webCrawl:indexUid:url,url", name))?;
+                let urls = rest[pos + 1..].split(',').map(|url| url.trim().to_string()).collect();
+                Job::WebCrawl { index_uid: rest[..pos].to_string(), urls, max_pages: None }
+            }
+            _ => return Err(format!("unknown scheduled job {:?}", name)),
+        };
+
+        Ok((job, cron.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRun {
+    pub status: RunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTask {
+    pub id: u64,
+    pub job: Job,
+    pub cron: String,
+    #[serde(skip)]
+    schedule: CronSchedule,
+    /// Whether the task comes from the configuration, as opposed to the API.
+    #[serde(skip)]
+    configured: bool,
+    /// The most recent runs, the last one first.
+    pub runs: VecDeque<JobRun>,
+}
+
+/// Keeps the scheduled tasks, those declared on the command line and those added through
+/// the API. The tasks added through the API are not persisted and are lost on restart.
+pub struct Scheduler {
+    tasks: Mutex<(u64, Vec<ScheduledTask>)>,
+    snapshot_dir: Option<PathBuf>,
+    snapshot_retention: Option<usize>,
+    snapshot_incremental: bool,
+}
+
+impl Scheduler {
+    pub fn new(snapshot_dir: Option<PathBuf>, snapshot_retention: Option<usize>, snapshot_incremental: bool) -> Scheduler {
+        Scheduler {
+            tasks: Mutex::new((0, Vec::new())),
+            snapshot_dir,
+            snapshot_retention,
+            snapshot_incremental,
+        }
+    }
+
+    /// The directory where the snapshots are created, if any.
+    pub fn snapshot_dir(&self) -> Option<&Path> {
+        self.snapshot_dir.as_deref()
+    }
+
+    pub fn add(&self, job: Job, cron: String) -> Result<ScheduledTask, Error> {
+        self.add_task(job, cron, false)
+    }
+
+    /// Adds a task declared in the configuration, it is replaced when the configuration is reloaded.
+    pub fn add_configured(&self, job: Job, cron: String) -> Result<ScheduledTask, Error> {
+        self.add_task(job, cron, true)
+    }
+
+    /// Replaces the tasks declared in the configuration, the tasks that are unchanged keep their
+    /// id and their runs. Nothing is replaced if one of the new tasks is invalid.
+    pub fn replace_configured(&self, tasks: Vec<(Job, String)>) -> Result<(), Error> {
+        let mut schedules = Vec::with_capacity(tasks.len());
+        for (job, cron) in &tasks {
+            schedules.push(validate(job, cron)?);
+        }
+
+        let mut guard = self.tasks.lock().unwrap();
+        let (next_id, current) = &mut *guard;
+        current.retain(|task| !task.configured || tasks.iter().any(|(job, cron)| task.job == *job && task.cron == *cron));
+
+        for ((job, cron), schedule) in tasks.into_iter().zip(schedules) {
+            if !current.iter().any(|task| task.configured && task.job == job && task.cron == cron) {
+                current.push(ScheduledTask { id: *next_id, job, cron, schedule, configured: true, runs: VecDeque::new() });
+                *next_id += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_task(&self, job: Job, cron: String, configured: bool) -> Result<ScheduledTask, Error> {
+        let schedule = validate(&job, &cron)?;
+
+        let mut guard = self.tasks.lock().unwrap();
+        let (next_id, tasks) = &mut *guard;
+        let task = ScheduledTask { id: *next_id, job, cron, schedule, configured, runs: VecDeque::new() };
+        *next_id += 1;
+        tasks.push(task.clone());
+
+        Ok(task)
+    }
+
+    pub fn remove(&self, id: u64) -> bool {
+        let mut guard = self.tasks.lock().unwrap();
+        let tasks = &mut guard.1;
+        let len = tasks.len();
+        tasks.retain(|task| task.id != id);
+        tasks.len() != len
+    }
+
+    pub fn tasks(&self) -> Vec<ScheduledTask> {
+        self.tasks.lock().unwrap().1.clone()
+    }
+
+    fn record_run(&self, id: u64, run: JobRun) {
+        let mut guard = self.tasks.lock().unwrap();
+        if let Some(task) = guard.1.iter_mut().find(|task| task.id == id) {
+            task.runs.push_front(run);
+            task.runs.truncate(MAX_RUNS_HISTORY);
+        }
+    }
+}
+
+/// Checks that a task can be scheduled, returns its parsed schedule.
+fn validate(job: &Job, cron: &str) -> Result<CronSchedule, Error> {
+    let schedule = CronSchedule::parse(cron).map_err(Error::bad_request)?;
+    if let Job::DocumentsExpiration { attribute } = job {
+        if attribute.is_empty() {
+            return Err(Error::bad_request("the documents expiration attribute can't be empty"));
+        }
+    }
+    if let Job::S3Crawl { index_uid, url, .. } = job {
+        if !is_valid_index_uid(index_uid) {
+            return Err(Error::InvalidIndexUid);
+        }
+        S3Location::parse(url)?;
+    }
+    if let Job::WebCrawl { index_uid, urls, max_pages } = job {
+        if !is_valid_index_uid(index_uid) {
+            return Err(Error::InvalidIndexUid);
+        }
+        if urls.is_empty() || urls.iter().any(|url| !url.starts_with("http://") && !url.starts_with("https://")) {
+            return Err(Error::bad_parameter("urls", "the urls to crawl must be http or https urls"));
+        }
+        if *max_pages == Some(0) {
+            return Err(Error::bad_parameter("maxPages", "at least one page must be crawled"));
+        }
+    }
+
+    Ok(schedule)
+}
+
+/// Checks the scheduled tasks at the beginning of every minute and runs the ones that are due,
+/// one after the other.
+pub fn spawn_scheduler(data: Data) {
+    thread::spawn(move || loop {
+        let now = Utc::now();
+        let elapsed = Duration::new(u64::from(now.second()), now.nanosecond() % 1_000_000_000);
+        thread::sleep(Duration::from_secs(60).checked_sub(elapsed).unwrap_or_default());
+
+        let now = Utc::now();
+        let due: Vec<_> = data.scheduler.tasks()
+            .into_iter()
+            .filter(|task| task.schedule.matches(&now))
+            .collect();
+
+        for task in due {
+            let started_at = Utc::now();
+            let result = run_job(&data, &task.job);
+            let finished_at = Utc::now();
+
+            let run = match result {
+                Ok(details) => {
+                    info!("Scheduled task {} ({:?}) succeeded: {}", task.id, task.job, details);
+                    JobRun { status: RunStatus::Succeeded, details: Some(details), error: None, started_at, finished_at }
+                }
+                Err(e) => {
+                    error!("Scheduled task {} ({:?}) failed: {}", task.id, task.job, e);
+                    JobRun { status: RunStatus::Failed, details: None, error: Some(e.to_string()), started_at, finished_at }
+                }
+            };
+            data.scheduler.record_run(task.id, run);
+        }
+    });
+}
+
+fn run_job(data: &Data, job: &Job) -> Result<String, Error> {
+    match job {
+        Job::Snapshot => {
+            let snapshot_dir = data.scheduler.snapshot_dir.as_ref()
+                .ok_or_else(|| Error::Internal("no snapshot path is configured".to_string()))?;
+            let snapshot_path = create_retained_snapshot(
+                data,
+                snapshot_dir,
+                data.scheduler.snapshot_retention,
+                data.scheduler.snapshot_incremental,
+            )?;
+            Ok(format!("snapshot created at {:?}", snapshot_path))
+        }
+        Job::Dump => {
+            let info = init_dump_process(&web::Data::new(data.clone()), &data.dumps_folder)?;
+            Ok(format!("dump {} started", info.uid))
+        }
+        Job::DocumentsExpiration { attribute } => {
+            let deleted = expire_documents(data, attribute)?;
+            Ok(format!("{} expired documents enqueued for deletion", deleted))
+        }
+        Job::S3Crawl { index_uid, url, primary_key } => {
+            let indexed = s3::crawl(data, index_uid, url, primary_key.as_ref())?;
+            Ok(format!("{} changed objects enqueued for indexing", indexed))
+        }
+        Job::WebCrawl { index_uid, urls, max_pages } => {
+            let report = crawler::crawl(data, index_uid, urls, max_pages.unwrap_or(crawler::DEFAULT_MAX_PAGES))?;
+            Ok(format!("{} pages crawled, {} updated and {} deleted", report.pages, report.updated, report.deleted))
+        }
+    }
+}
+
+fn is_expired(value: &Value, now: &DateTime<Utc>) -> bool {
+    match value {
+        Value::Number(timestamp) => timestamp.as_f64().map_or(false, |t| t <= now.timestamp() as f64),
+        Value::String(date) => DateTime::parse_from_rfc3339(date).map_or(false, |date| date.with_timezone(&Utc) <= *now),
+        _ => false,
+    }
+}
+
+/// Enqueues the deletion of the expired documents of every index
+/// and returns the number of documents to be deleted.
+fn expire_documents(data: &Data, attribute: &str) -> Result<usize, Error> {
+    let now = Utc::now();
+    let mut total = 0;
+
+    for index_uid in data.db.indexes_uids() {
+        let index = match data.db.open_index(&index_uid) {
+            Some(index) => index,
+            None => continue,
+        };
+
+        let reader = data.db.main_read_txn()?;
+        let schema = match index.main.schema(&reader)? {
+            Some(schema) => schema,
+            None => continue,
+        };
+        let primary_key = match schema.primary_key() {
+            Some(primary_key) if schema.id(attribute).is_some() => primary_key,
+            _ => continue,
+        };
+
+        let attributes: HashSet<&str> = [primary_key, attribute].iter().cloned().collect();
+        let mut expired = Vec::new();
+        for document_id in index.documents_fields_counts.documents_ids(&reader)? {
+            let document_id = document_id?;
+            let document = index.document::<serde_json::Map<String, Value>>(&reader, Some(&attributes), document_id)?;
+            if let Some(document) = document {
+                match (document.get(primary_key), document.get(attribute)) {
+                    (Some(id), Some(value)) if is_expired(value, &now) => {
+                        expired.push(update::value_to_string(id));
+                    }
+                    _ => (),
+                }
+            }
+        }
+        drop(reader);
+
+        if !expired.is_empty() {
+            total += expired.len();
+            let mut documents_deletion = index.documents_deletion();
+            for document_id in expired {
+                documents_deletion.delete_document_by_external_docid(document_id);
+            }
+            data.db.update_write(|w| documents_deletion.finalize(w))?;
+        }
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn parse_and_match_cron_expressions() {
+        // a tuesday
+        let date = Utc.ymd(2020, 9, 15).and_hms(3, 30, 0);
+
+        assert!(CronSchedule::parse("* * * * *").unwrap().matches(&date));
+        assert!(CronSchedule::parse("30 3 * * *").unwrap().matches(&date));
+        assert!(CronSchedule::parse("*/15 0-6 15 9 2").unwrap().matches(&date));
+        assert!(CronSchedule::parse("0,30 3 * * 1-5").unwrap().matches(&date));
+        assert!(!CronSchedule::parse("0 3 * * *").unwrap().matches(&date));
+        assert!(!CronSchedule::parse("30 3 * * 0,6").unwrap().matches(&date));
+        assert!(!CronSchedule::parse("*/20 * * * *").unwrap().matches(&date));
+
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+        assert!(CronSchedule::parse("a * * * *").is_err());
+    }
+
+    #[test]
+    fn parse_jobs_with_schedule() {
+        assert_eq!(
+            Job::parse_with_schedule("snapshot=0 3 * * *").unwrap(),
+            (Job::Snapshot, "0 3 * * *".to_string()),
+        );
+        assert_eq!(
+            Job::parse_with_schedule("documentsExpiration:expiresAt=*/10 * * * *").unwrap(),
+            (Job::DocumentsExpiration { attribute: "expiresAt".to_string() }, "*/10 * * * *".to_string()),
+        );
+        assert_eq!(
+            Job::parse_with_schedule("s3Crawl:movies:s3://datasets/movies/=0 * * * *").unwrap(),
+            (
+                Job::S3Crawl { index_uid: "movies".to_string(), url: "s3://datasets/movies/".to_string(), primary_key: None },
+                "0 * * * *".to_string(),
+            ),
+        );
+        assert!(Job::parse_with_schedule("s3Crawl:movies=0 * * * *").is_err());
+        assert_eq!(
+            Job::parse_with_schedule("webCrawl:docs:https://example.com/sitemap.xml,https://example.com/?page=1=0 3 * * *").unwrap(),
+            (
+                Job::WebCrawl {
+                    index_uid: "docs".to_string(),
+                    urls: vec!["https://example.com/sitemap.xml".to_string(), "https://example.com/?page=1".to_string()],
+                    max_pages: None,
+                },
+                "0 3 * * *".to_string(),
+            ),
+        );
+        assert!(Job::parse_with_schedule("compaction=0 3 * * *").is_err());
+        assert!(Job::parse_with_schedule("dump").is_err());
+    }
+
+    #[test]
+    fn replace_configured_tasks() {
+        let scheduler = Scheduler::new(None, None, false);
+        let api_task = scheduler.add(Job::Snapshot, "0 3 * * *".to_string()).unwrap();
+        let kept = scheduler.add_configured(Job::Dump, "0 4 * * *".to_string()).unwrap();
+        scheduler.add_configured(Job::Snapshot, "0 5 * * *".to_string()).unwrap();
+
+        // an invalid task leaves the tasks untouched
+        let invalid = vec![(Job::Dump, "0 4 * * *".to_string()), (Job::Snapshot, "61 * * * *".to_string())];
+        assert!(scheduler.replace_configured(invalid).is_err());
+        assert_eq!(scheduler.tasks().len(), 3);
+
+        let tasks = vec![(Job::Dump, "0 4 * * *".to_string()), (Job::Snapshot, "0 6 * * *".to_string())];
+        scheduler.replace_configured(tasks).unwrap();
+        let ids: Vec<_> = scheduler.tasks().iter().map(|task| (task.id, task.cron.clone())).collect();
+        assert_eq!(ids, vec![
+            (api_task.id, "0 3 * * *".to_string()),
+            (kept.id, "0 4 * * *".to_string()),
+            (3, "0 6 * * *".to_string()),
+        ]);
+    }
+}
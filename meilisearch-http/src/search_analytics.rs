@@ -0,0 +1,351 @@
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+
+/// The number of distinct queries counted in a bucket, the other queries are only counted in
+/// the totals so that a flood of distinct queries can't exhaust the memory.
+const MAX_DISTINCT_QUERIES: usize = 1000;
+
+/// The upper bounds, in milliseconds, of the buckets of the latency histograms.
+const LATENCY_BOUNDS_MS: [f64; 16] = [
+    1.0, 2.0, 3.0, 5.0, 7.5, 10.0, 15.0, 20.0, 30.0, 50.0, 75.0, 100.0, 200.0, 500.0, 1000.0, f64::INFINITY,
+];
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+
+/// The period over which the analytics of an index are aggregated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Hour,
+    Day,
+    Week,
+}
+
+impl Window {
+    fn seconds(self) -> i64 {
+        match self {
+            Window::Hour => HOUR,
+            Window::Day => 24 * HOUR,
+            Window::Week => 7 * 24 * HOUR,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Window::Hour => "1h",
+            Window::Day => "24h",
+            Window::Week => "7d",
+        }
+    }
+}
+
+impl Default for Window {
+    fn default() -> Window {
+        Window::Day
+    }
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Window, String> {
+        match s {
+            "1h" => Ok(Window::Hour),
+            "24h" => Ok(Window::Day),
+            "7d" => Ok(Window::Week),
+            _ => Err(format!("{:?} is not a window, it must be one of 1h, 24h or 7d", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryCount {
+    pub query: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyPercentiles {
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsReport {
+    pub window: &'static str,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub total_queries: u64,
+    /// The searches without a query string.
+    pub placeholder_queries: u64,
+    pub zero_result_queries: u64,
+    pub zero_result_rate: f64,
+    /// The percentiles are the upper bounds of the histogram buckets they fall in.
+    pub latency_ms: LatencyPercentiles,
+    pub top_queries: Vec<QueryCount>,
+    pub top_zero_result_queries: Vec<QueryCount>,
+}
+
+#[derive(Default)]
+struct Bucket {
+    start: i64,
+    queries: u64,
+    placeholder_queries: u64,
+    zero_result_queries: u64,
+    latencies: [u64; LATENCY_BOUNDS_MS.len()],
+    top_queries: HashMap<String, u64>,
+    top_zero_result_queries: HashMap<String, u64>,
+}
+
+impl Bucket {
+    fn new(start: i64) -> Bucket {
+        Bucket { start, ..Bucket::default() }
+    }
+
+    fn record(&mut self, query: Option<&str>, nb_hits: usize, latency_ms: f64) {
+        self.queries += 1;
+        if nb_hits == 0 {
+            self.zero_result_queries += 1;
+        }
+        let slot = LATENCY_BOUNDS_MS.iter().position(|bound| latency_ms <= *bound).unwrap_or(LATENCY_BOUNDS_MS.len() - 1);
+        self.latencies[slot] += 1;
+
+        match query {
+            Some(query) => {
+                increment(&mut self.top_queries, query);
+                if nb_hits == 0 {
+                    increment(&mut self.top_zero_result_queries, query);
+                }
+            }
+            None => self.placeholder_queries += 1,
+        }
+    }
+}
+
+fn increment(counts: &mut HashMap<String, u64>, query: &str) {
+    match counts.get_mut(query) {
+        Some(count) => *count += 1,
+        None if counts.len() < MAX_DISTINCT_QUERIES => {
+            counts.insert(query.to_string(), 1);
+        }
+        None => (),
+    }
+}
+
+/// Consecutive buckets of the same width, the oldest ones are dropped past the capacity.
+struct Tier {
+    width: i64,
+    capacity: usize,
+    buckets: VecDeque<Bucket>,
+}
+
+impl Tier {
+    fn new(width: i64, capacity: usize) -> Tier {
+        Tier { width, capacity, buckets: VecDeque::new() }
+    }
+
+    fn bucket_mut(&mut self, now: i64) -> &mut Bucket {
+        let start = now - now.rem_euclid(self.width);
+        if self.buckets.back().map_or(true, |bucket| bucket.start < start) {
+            self.buckets.push_back(Bucket::new(start));
+            while self.buckets.len() > self.capacity {
+                self.buckets.pop_front();
+            }
+        }
+        self.buckets.back_mut().unwrap()
+    }
+
+    /// The buckets that overlap the period starting at `since`.
+    fn since(&self, since: i64) -> impl Iterator<Item = &Bucket> {
+        let width = self.width;
+        self.buckets.iter().filter(move |bucket| bucket.start + width > since)
+    }
+}
+
+/// The analytics of an index, minute buckets for the last hour and hour buckets for the last week.
+struct IndexAnalytics {
+    minutes: Tier,
+    hours: Tier,
+}
+
+impl IndexAnalytics {
+    fn new() -> IndexAnalytics {
+        IndexAnalytics {
+            minutes: Tier::new(MINUTE, (Window::Hour.seconds() / MINUTE) as usize + 1),
+            hours: Tier::new(HOUR, (Window::Week.seconds() / HOUR) as usize + 1),
+        }
+    }
+
+    fn report(&self, now: i64, window: Window, limit: usize) -> AnalyticsReport {
+        let since = now - window.seconds();
+        let tier = match window {
+            Window::Hour => &self.minutes,
+            Window::Day | Window::Week => &self.hours,
+        };
+
+        let mut total_queries = 0;
+        let mut placeholder_queries = 0;
+        let mut zero_result_queries = 0;
+        let mut latencies = [0; LATENCY_BOUNDS_MS.len()];
+        let mut top_queries = HashMap::new();
+        let mut top_zero_result_queries = HashMap::new();
+
+        for bucket in tier.since(since) {
+            total_queries += bucket.queries;
+            placeholder_queries += bucket.placeholder_queries;
+            zero_result_queries += bucket.zero_result_queries;
+            for (total, count) in latencies.iter_mut().zip(bucket.latencies.iter()) {
+                *total += count;
+            }
+            for (query, count) in &bucket.top_queries {
+                *top_queries.entry(query.as_str()).or_insert(0) += count;
+            }
+            for (query, count) in &bucket.top_zero_result_queries {
+                *top_zero_result_queries.entry(query.as_str()).or_insert(0) += count;
+            }
+        }
+
+        let zero_result_rate = if total_queries == 0 { 0.0 } else { zero_result_queries as f64 / total_queries as f64 };
+
+        AnalyticsReport {
+            window: window.name(),
+            from: Utc.timestamp(since, 0),
+            to: Utc.timestamp(now, 0),
+            total_queries,
+            placeholder_queries,
+            zero_result_queries,
+            zero_result_rate,
+            latency_ms: LatencyPercentiles {
+                p50: percentile(&latencies, 0.50),
+                p90: percentile(&latencies, 0.90),
+                p99: percentile(&latencies, 0.99),
+            },
+            top_queries: top(top_queries, limit),
+            top_zero_result_queries: top(top_zero_result_queries, limit),
+        }
+    }
+}
+
+fn percentile(latencies: &[u64], quantile: f64) -> Option<f64> {
+    let total: u64 = latencies.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let rank = (total as f64 * quantile).ceil().max(1.0) as u64;
+    let mut seen = 0;
+    for (count, bound) in latencies.iter().zip(LATENCY_BOUNDS_MS.iter()) {
+        seen += count;
+        if seen >= rank {
+            // the last bucket has no upper bound, it is reported as the previous one
+            return Some(if bound.is_finite() { *bound } else { LATENCY_BOUNDS_MS[LATENCY_BOUNDS_MS.len() - 2] });
+        }
+    }
+    None
+}
+
+fn top(counts: HashMap<&str, u64>, limit: usize) -> Vec<QueryCount> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_unstable_by(|(qa, ca), (qb, cb)| cb.cmp(ca).then_with(|| qa.cmp(qb)));
+    counts.into_iter().take(limit).map(|(query, count)| QueryCount { query: query.to_string(), count }).collect()
+}
+
+/// Aggregates the searches made on each index over rolling windows, in memory.
+#[derive(Default)]
+pub struct SearchAnalytics {
+    indexes: Mutex<HashMap<String, IndexAnalytics>>,
+}
+
+impl SearchAnalytics {
+    pub fn record(&self, index_uid: &str, query: Option<&str>, nb_hits: usize, latency: Duration) {
+        self.record_at(Utc::now().timestamp(), index_uid, query, nb_hits, latency)
+    }
+
+    /// The analytics of the index over the window, the top lists hold at most `limit` queries.
+    pub fn report(&self, index_uid: &str, window: Window, limit: usize) -> AnalyticsReport {
+        self.report_at(Utc::now().timestamp(), index_uid, window, limit)
+    }
+
+    /// Forgets the analytics of a deleted index.
+    pub fn remove(&self, index_uid: &str) {
+        self.indexes.lock().unwrap().remove(index_uid);
+    }
+
+    fn record_at(&self, now: i64, index_uid: &str, query: Option<&str>, nb_hits: usize, latency: Duration) {
+        // the queries are counted case insensitively, the empty ones are placeholder searches
+        let query = query.map(|q| q.trim().to_lowercase()).filter(|q| !q.is_empty());
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+
+        let mut indexes = self.indexes.lock().unwrap();
+        let analytics = indexes.entry(index_uid.to_string()).or_insert_with(IndexAnalytics::new);
+        analytics.minutes.bucket_mut(now).record(query.as_deref(), nb_hits, latency_ms);
+        analytics.hours.bucket_mut(now).record(query.as_deref(), nb_hits, latency_ms);
+    }
+
+    fn report_at(&self, now: i64, index_uid: &str, window: Window, limit: usize) -> AnalyticsReport {
+        let indexes = self.indexes.lock().unwrap();
+        match indexes.get(index_uid) {
+            Some(analytics) => analytics.report(now, window, limit),
+            // an index without searches yet has empty analytics
+            None => IndexAnalytics::new().report(now, window, limit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_600_000_000;
+
+    #[test]
+    fn windows() {
+        assert_eq!("1h".parse::<Window>().unwrap(), Window::Hour);
+        assert_eq!("7d".parse::<Window>().unwrap(), Window::Week);
+        assert!("2h".parse::<Window>().is_err());
+    }
+
+    #[test]
+    fn aggregate_over_windows() {
+        let analytics = SearchAnalytics::default();
+        let ms = Duration::from_millis;
+
+        // two hours ago, only in the day window
+        analytics.record_at(NOW - 2 * HOUR, "movies", Some("Batman"), 3, ms(4));
+        analytics.record_at(NOW - 10, "movies", Some("batman "), 5, ms(4));
+        analytics.record_at(NOW - 5, "movies", Some("starwarz"), 0, ms(40));
+        analytics.record_at(NOW, "movies", None, 100, ms(1));
+        analytics.record_at(NOW, "books", Some("dune"), 1, ms(1));
+
+        let hour = analytics.report_at(NOW, "movies", Window::Hour, 10);
+        assert_eq!(hour.total_queries, 3);
+        assert_eq!(hour.placeholder_queries, 1);
+        assert_eq!(hour.zero_result_queries, 1);
+        assert_eq!(hour.top_queries, vec![
+            QueryCount { query: "batman".into(), count: 1 },
+            QueryCount { query: "starwarz".into(), count: 1 },
+        ]);
+        assert_eq!(hour.top_zero_result_queries, vec![QueryCount { query: "starwarz".into(), count: 1 }]);
+        assert_eq!(hour.latency_ms.p50, Some(5.0));
+        assert_eq!(hour.latency_ms.p99, Some(50.0));
+
+        let day = analytics.report_at(NOW, "movies", Window::Day, 1);
+        assert_eq!(day.total_queries, 4);
+        assert_eq!(day.top_queries, vec![QueryCount { query: "batman".into(), count: 2 }]);
+
+        let week = analytics.report_at(NOW + 8 * 24 * HOUR, "movies", Window::Week, 10);
+        assert_eq!(week.total_queries, 0);
+        assert_eq!(week.latency_ms.p50, None);
+
+        analytics.remove("movies");
+        assert_eq!(analytics.report_at(NOW, "movies", Window::Day, 10).total_queries, 0);
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::channel::oneshot;
+
+/// Bounds the number of searches executed at once, the other searches wait in a queue where
+/// the keys are served in turn, this way a key sending a burst of searches can't starve the
+/// others. The searches waiting longer than the queue timeout are refused.
+pub struct SearchLimiter {
+    /// The queue timeout in microseconds, it can be changed while searches are waiting.
+    queue_timeout: AtomicU64,
+    state: Mutex<State>,
+}
+
+struct State {
+    max_concurrent_searches: usize,
+    running: usize,
+    /// The searches waiting for a permit, grouped by key, in the order the keys are served.
+    queues: VecDeque<(String, VecDeque<oneshot::Sender<SearchPermit>>)>,
+}
+
+/// Allows a search to run, the next search in the queue is started when it is dropped.
+pub struct SearchPermit {
+    limiter: Option<Arc<SearchLimiter>>,
+}
+
+impl Drop for SearchPermit {
+    fn drop(&mut self) {
+        if let Some(limiter) = self.limiter.take() {
+            limiter.release();
+        }
+    }
+}
+
+impl SearchLimiter {
+    pub fn new(max_concurrent_searches: usize, queue_timeout: Duration) -> SearchLimiter {
+        let state = State { max_concurrent_searches, running: 0, queues: VecDeque::new() };
+        SearchLimiter { queue_timeout: AtomicU64::new(queue_timeout.as_micros() as u64), state: Mutex::new(state) }
+    }
+
+    pub fn queue_timeout(&self) -> Duration {
+        Duration::from_micros(self.queue_timeout.load(Ordering::Relaxed))
+    }
+
+    /// Changes the limits, the waiting searches are started right away if more searches can run
+    /// at once. The searches already waiting keep the timeout they started waiting with.
+    pub fn set_limits(self: &Arc<Self>, max_concurrent_searches: usize, queue_timeout: Duration) {
+        self.queue_timeout.store(queue_timeout.as_micros() as u64, Ordering::Relaxed);
+
+        let mut state = self.state.lock().unwrap();
+        state.max_concurrent_searches = max_concurrent_searches;
+        while state.running < state.max_concurrent_searches && self.send_next_permit(&mut state) {
+            state.running += 1;
+        }
+    }
+
+    /// Waits for a permit to run a search made with the given key, returns `None`
+    /// if the search waited longer than the queue timeout.
+    pub async fn acquire(self: &Arc<Self>, key: &str) -> Option<SearchPermit> {
+        let receiver = match self.try_acquire(key) {
+            Ok(permit) => return Some(permit),
+            Err(receiver) => receiver,
+        };
+
+        // a permit sent after the timeout is dropped with the receiver and given to the next search
+        match actix_rt::time::timeout(self.queue_timeout(), receiver).await {
+            Ok(Ok(permit)) => Some(permit),
+            _ => None,
+        }
+    }
+
+    /// Returns a permit if a search can run right away, enqueues the search otherwise.
+    fn try_acquire(self: &Arc<Self>, key: &str) -> Result<SearchPermit, oneshot::Receiver<SearchPermit>> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.running < state.max_concurrent_searches && state.queues.is_empty() {
+            state.running += 1;
+            return Ok(SearchPermit { limiter: Some(self.clone()) });
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        match state.queues.iter_mut().find(|(k, _)| k == key) {
+            Some((_, queue)) => {
+                // forget the searches of the key that already timed out
+                queue.retain(|sender| !sender.is_canceled());
+                queue.push_back(sender);
+            }
+            None => state.queues.push_back((key.to_string(), VecDeque::from(vec![sender]))),
+        }
+
+        Err(receiver)
+    }
+
+    /// Gives the permit of a finished search to the first search of the next key in turn,
+    /// unless the limit has been lowered below the number of running searches.
+    fn release(self: &Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+
+        if state.running > state.max_concurrent_searches || !self.send_next_permit(&mut state) {
+            state.running -= 1;
+        }
+    }
+
+    /// Sends a permit to the first search of the next key in turn, returns false if no search is waiting.
+    fn send_next_permit(self: &Arc<Self>, state: &mut State) -> bool {
+        while let Some((key, mut queue)) = state.queues.pop_front() {
+            let sender = queue.pop_front();
+            if !queue.is_empty() {
+                state.queues.push_back((key, queue));
+            }
+
+            if let Some(sender) = sender {
+                match sender.send(SearchPermit { limiter: Some(self.clone()) }) {
+                    Ok(()) => return true,
+                    // the search timed out, dropping this permit would release it again
+                    Err(mut permit) => drop(permit.limiter.take()),
+                }
+            }
+        }
+
+        false
+    }
+
+    /// The number of searches running and the number of searches waiting in the queue.
+    pub fn usage(&self) -> (usize, usize) {
+        let state = self.state.lock().unwrap();
+        let queued = state.queues.iter().flat_map(|(_, queue)| queue).filter(|sender| !sender.is_canceled()).count();
+        (state.running, queued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_served_in_turn() {
+        let limiter = Arc::new(SearchLimiter::new(1, Duration::from_secs(1)));
+
+        let permit = limiter.try_acquire("a").ok().unwrap();
+        let mut a1 = limiter.try_acquire("a").err().unwrap();
+        let mut a2 = limiter.try_acquire("a").err().unwrap();
+        let mut b1 = limiter.try_acquire("b").err().unwrap();
+        assert_eq!(limiter.usage(), (1, 3));
+
+        drop(permit);
+        let permit = a1.try_recv().unwrap().unwrap();
+        assert!(b1.try_recv().unwrap().is_none());
+
+        // the key b is served before the second search of the key a
+        drop(permit);
+        let permit = b1.try_recv().unwrap().unwrap();
+        assert!(a2.try_recv().unwrap().is_none());
+
+        drop(permit);
+        let permit = a2.try_recv().unwrap().unwrap();
+        drop(permit);
+        assert_eq!(limiter.usage(), (0, 0));
+    }
+
+    #[test]
+    fn timed_out_searches_are_skipped() {
+        let limiter = Arc::new(SearchLimiter::new(1, Duration::from_secs(1)));
+
+        let permit = limiter.try_acquire("a").ok().unwrap();
+        let timed_out = limiter.try_acquire("a").err().unwrap();
+        let mut b1 = limiter.try_acquire("b").err().unwrap();
+        drop(timed_out);
+
+        drop(permit);
+        let permit = b1.try_recv().unwrap().unwrap();
+        drop(permit);
+        assert_eq!(limiter.usage(), (0, 0));
+        assert!(limiter.try_acquire("c").is_ok());
+    }
+
+    #[test]
+    fn limits_can_be_changed() {
+        let limiter = Arc::new(SearchLimiter::new(1, Duration::from_secs(1)));
+
+        let permit = limiter.try_acquire("a").ok().unwrap();
+        let mut b1 = limiter.try_acquire("b").err().unwrap();
+
+        // the waiting search is started as soon as the limit is raised
+        limiter.set_limits(2, Duration::from_secs(2));
+        assert_eq!(limiter.queue_timeout(), Duration::from_secs(2));
+        let b1 = b1.try_recv().unwrap().unwrap();
+        assert_eq!(limiter.usage(), (2, 0));
+
+        // the finished searches are not replaced until the limit is reached again
+        limiter.set_limits(1, Duration::from_secs(2));
+        let mut c1 = limiter.try_acquire("c").err().unwrap();
+        drop(permit);
+        assert!(c1.try_recv().unwrap().is_none());
+        drop(b1);
+        let permit = c1.try_recv().unwrap().unwrap();
+        assert_eq!(limiter.usage(), (1, 0));
+        drop(permit);
+        assert_eq!(limiter.usage(), (0, 0));
+    }
+}
@@ -0,0 +1,185 @@
+use std::time::Duration;
+use std::{env, fs, thread};
+
+use log::{error, info};
+use serde_json::Value;
+
+use crate::data::ApiKeys;
+use crate::Data;
+
+const VAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resolves a secret given as a reference instead of a plain value:
+///  - `file:<path>` reads the secret from a file, the trailing newline is ignored,
+///  - `env:<VARIABLE>` reads the secret from another environment variable,
+///  - `vault:<url>#<field>` fetches the field of a Vault secret, authenticated
+///    with the token of the `VAULT_TOKEN` environment variable.
+///
+/// Any other value is considered to be the secret itself.
+pub fn resolve_secret(value: &str) -> Result<String, String> {
+    if let Some(path) = strip_prefix(value, "file:") {
+        fs::read_to_string(path)
+            .map(|secret| secret.trim_end_matches(&['\r', '\n'][..]).to_string())
+            .map_err(|e| format!("cannot read the secret file {}; {}", path, e))
+    } else if let Some(variable) = strip_prefix(value, "env:") {
+        env::var(variable).map_err(|e| format!("cannot read the secret variable {}; {}", variable, e))
+    } else if let Some(location) = strip_prefix(value, "vault:") {
+        fetch_vault_secret(location)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Whether the value is a reference to a secret that can change over time.
+pub fn is_secret_reference(value: &str) -> bool {
+    ["file:", "env:", "vault:"].iter().any(|prefix| value.starts_with(prefix))
+}
+
+fn strip_prefix<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    if value.starts_with(prefix) {
+        Some(&value[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+fn fetch_vault_secret(location: &str) -> Result<String, String> {
+    let pos = location.rfind('#').ok_or("a vault secret must be of the form vault:<url>#<field>")?;
+    let (url, field) = (&location[..pos], &location[pos + 1..]);
+    let token = env::var("VAULT_TOKEN").map_err(|_| "the VAULT_TOKEN environment variable must be set")?;
+
+    let mut request = ureq::get(url);
+    request
+        .set("X-Vault-Token", &token)
+        .timeout_connect(VAULT_TIMEOUT.as_millis() as u64)
+        .timeout_read(VAULT_TIMEOUT.as_millis() as u64);
+
+    let response = request.call();
+    if let Some(err) = response.synthetic_error() {
+        return Err(format!("cannot fetch the vault secret {}; {}", url, err));
+    }
+    if !response.ok() {
+        return Err(format!("cannot fetch the vault secret {}; the server responded with status {}", url, response.status()));
+    }
+
+    let body = response.into_string().map_err(|e| e.to_string())?;
+    let body: Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    vault_field(&body, field).ok_or_else(|| format!("the vault secret {} has no field {}", url, field))
+}
+
+/// Finds the field in the secret of a Vault response, secrets of the version 2 of
+/// the key-value engine are nested in a second `data` object.
+fn vault_field(body: &Value, field: &str) -> Option<String> {
+    let data = &body["data"];
+    data["data"][field]
+        .as_str()
+        .or_else(|| data[field].as_str())
+        .map(ToString::to_string)
+}
+
+/// Resolves the master key reference every interval, the private and public keys
+/// are derived again from the new master key when it changes.
+///
+/// The scoped API keys are derived from the master key and only their hash is stored, they
+/// can't be derived again without handing out new keys. The rotation is therefore refused
+/// while scoped keys exist, the current master key is kept until they are deleted.
+pub fn schedule_master_key_rotation(data: Data, reference: String, interval: Duration) {
+    thread::spawn(move || {
+        let mut refused: Option<String> = None;
+        loop {
+            thread::sleep(interval);
+
+            let master = match resolve_secret(&reference) {
+                Ok(master) => master,
+                Err(e) => {
+                    error!("Cannot refresh the master key; {}", e);
+                    continue;
+                }
+            };
+
+            let mut api_keys = data.api_keys.write().unwrap();
+            match rotate_master_key(&mut api_keys, master.clone(), data.keys.all().len()) {
+                Ok(true) => info!("The master key has been rotated"),
+                Ok(false) => (),
+                // the refusal is only logged once for a given master key
+                Err(e) if refused.as_deref() != Some(master.as_str()) => {
+                    error!("Cannot rotate the master key; {}", e);
+                    refused = Some(master);
+                }
+                Err(_) => (),
+            }
+        }
+    });
+}
+
+/// Replaces the master key and the keys derived from it, returns whether it changed.
+fn rotate_master_key(api_keys: &mut ApiKeys, master: String, scoped_keys: usize) -> Result<bool, String> {
+    if api_keys.master.as_deref() == Some(master.as_str()) {
+        return Ok(false);
+    }
+    if scoped_keys > 0 {
+        return Err(format!(
+            "{} scoped API keys and the tenant tokens they sign are derived from the current master key, \
+             delete them before rotating it",
+            scoped_keys,
+        ));
+    }
+
+    let mut new_keys = ApiKeys { master: Some(master), private: None, public: None };
+    new_keys.generate_missing_api_keys();
+    *api_keys = new_keys;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn resolve_secrets() {
+        assert_eq!(resolve_secret("plainSecret").unwrap(), "plainSecret");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("master-key");
+        fs::write(&path, "secretFromFile\n").unwrap();
+        let reference = format!("file:{}", path.display());
+        assert!(is_secret_reference(&reference));
+        assert_eq!(resolve_secret(&reference).unwrap(), "secretFromFile");
+        assert!(resolve_secret("file:/does/not/exist").is_err());
+
+        env::set_var("MEILI_TEST_SECRET", "secretFromEnv");
+        assert_eq!(resolve_secret("env:MEILI_TEST_SECRET").unwrap(), "secretFromEnv");
+        assert!(resolve_secret("env:MEILI_TEST_MISSING_SECRET").is_err());
+
+        assert!(resolve_secret("vault:https://vault.example.com/v1/secret/data/meili").is_err());
+    }
+
+    #[test]
+    fn vault_fields() {
+        let kv2 = json!({ "data": { "data": { "masterKey": "kv2" }, "metadata": {} } });
+        assert_eq!(vault_field(&kv2, "masterKey").as_deref(), Some("kv2"));
+
+        let kv1 = json!({ "data": { "masterKey": "kv1" } });
+        assert_eq!(vault_field(&kv1, "masterKey").as_deref(), Some("kv1"));
+
+        assert_eq!(vault_field(&kv1, "other"), None);
+    }
+
+    #[test]
+    fn rotation_is_refused_with_scoped_keys() {
+        let mut api_keys = ApiKeys { master: Some("oldMaster".to_string()), private: None, public: None };
+        api_keys.generate_missing_api_keys();
+        let private = api_keys.private.clone();
+
+        assert_eq!(rotate_master_key(&mut api_keys, "oldMaster".to_string(), 0), Ok(false));
+        assert!(rotate_master_key(&mut api_keys, "newMaster".to_string(), 2).is_err());
+        assert_eq!(api_keys.master.as_deref(), Some("oldMaster"));
+        assert_eq!(api_keys.private, private);
+
+        assert_eq!(rotate_master_key(&mut api_keys, "newMaster".to_string(), 0), Ok(true));
+        assert_eq!(api_keys.master.as_deref(), Some("newMaster"));
+        assert_ne!(api_keys.private, private);
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use log::Level;
+use serde::Serialize;
+
+use crate::helpers::logging::{log_event, SLOW_QUERY_TARGET};
+
+/// The time spent in each phase of a search, in milliseconds.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchTimings {
+    pub total_ms: f64,
+    /// Opening the index and parsing the parameters.
+    pub prepare_ms: f64,
+    /// Finding and ranking the documents.
+    pub query_ms: f64,
+    /// Retrieving, cropping and highlighting the hits.
+    pub format_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlowQuery {
+    pub message: &'static str,
+    pub index_uid: String,
+    pub query: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filters: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_filters: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+    pub nb_hits: usize,
+    pub timings: SearchTimings,
+    pub date: DateTime<Utc>,
+}
+
+impl fmt::Display for SlowQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "slow search on index {} in {:.3}ms (prepare {:.3}ms, query {:.3}ms, format {:.3}ms) q={:?} nb_hits={}",
+            self.index_uid,
+            self.timings.total_ms,
+            self.timings.prepare_ms,
+            self.timings.query_ms,
+            self.timings.format_ms,
+            self.query.as_deref().unwrap_or(""),
+            self.nb_hits,
+        )?;
+        if let Some(filters) = &self.filters {
+            write!(f, " filters={:?}", filters)?;
+        }
+        if let Some(facet_filters) = &self.facet_filters {
+            write!(f, " facet_filters={}", facet_filters)?;
+        }
+        Ok(())
+    }
+}
+
+/// Logs the searches slower than the threshold and keeps the most recent ones in memory.
+pub struct SlowQueryLog {
+    /// The threshold in microseconds, it can be changed while the searches are logged.
+    threshold: AtomicU64,
+    capacity: usize,
+    entries: Mutex<VecDeque<SlowQuery>>,
+}
+
+impl SlowQueryLog {
+    /// Creates a log of the searches slower than the threshold, the `capacity` most recent
+    /// ones are kept in memory, none if the capacity is zero.
+    pub fn new(threshold: Duration, capacity: usize) -> SlowQueryLog {
+        let threshold = AtomicU64::new(threshold.as_micros() as u64);
+        SlowQueryLog { threshold, capacity, entries: Mutex::new(VecDeque::with_capacity(capacity)) }
+    }
+
+    pub fn is_slow(&self, duration: Duration) -> bool {
+        duration.as_micros() as u64 >= self.threshold.load(Ordering::Relaxed)
+    }
+
+    pub fn set_threshold(&self, threshold: Duration) {
+        self.threshold.store(threshold.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record(&self, query: SlowQuery) {
+        log_event(Level::Warn, SLOW_QUERY_TARGET, &query);
+
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(query);
+    }
+
+    /// The slow searches kept in memory, from the most recent to the oldest.
+    pub fn entries(&self) -> Vec<SlowQuery> {
+        self.entries.lock().unwrap().iter().rev().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slow_query(q: &str) -> SlowQuery {
+        SlowQuery {
+            message: "slow search",
+            index_uid: "movies".to_string(),
+            query: Some(q.to_string()),
+            filters: None,
+            facet_filters: None,
+            offset: 0,
+            limit: 20,
+            nb_hits: 0,
+            timings: SearchTimings { total_ms: 120.0, prepare_ms: 1.0, query_ms: 110.0, format_ms: 9.0 },
+            date: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let log = SlowQueryLog::new(Duration::from_millis(100), 2);
+        assert!(log.is_slow(Duration::from_millis(100)));
+        assert!(!log.is_slow(Duration::from_millis(99)));
+        log.set_threshold(Duration::from_millis(50));
+        assert!(log.is_slow(Duration::from_millis(99)));
+
+        log.record(slow_query("a"));
+        log.record(slow_query("b"));
+        log.record(slow_query("c"));
+
+        let queries: Vec<_> = log.entries().into_iter().filter_map(|entry| entry.query).collect();
+        assert_eq!(queries, vec!["c", "b"]);
+
+        let log = SlowQueryLog::new(Duration::from_millis(100), 0);
+        log.record(slow_query("a"));
+        assert!(log.entries().is_empty());
+    }
+}
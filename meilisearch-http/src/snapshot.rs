@@ -2,13 +2,35 @@ use crate::Data;
 use crate::error::Error;
 use crate::helpers::compression;
 
-use log::error;
-use std::fs::create_dir_all;
-use std::path::Path;
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use log::{error, info, warn};
+use meilisearch_core::CompactionOption;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, create_dir_all, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
 use std::thread;
-use std::time::{Duration};
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
 
+/// The size of the chunks of the incremental snapshots, a multiple of the LMDB page size.
+const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// The suffix of the ids of the incremental snapshots in the snapshots API.
+const INCREMENTAL_SUFFIX: &str = ".incremental";
+
+// The chunks written by an incremental snapshot are not used by any manifest until it is written,
+// they must not be removed by the concurrent creation or deletion of another snapshot.
+static INCREMENTAL_LOCK: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+
 pub fn load_snapshot(
     db_path: &str,
     snapshot_path: &Path,
@@ -18,7 +40,7 @@ pub fn load_snapshot(
     let db_path = Path::new(db_path);
 
     if !db_path.exists() && snapshot_path.exists() {
-        compression::from_tar_gz(snapshot_path, db_path)
+        install_snapshot(snapshot_path, db_path)
     } else if db_path.exists() && !ignore_snapshot_if_db_exists {
         Err(Error::Internal(format!("database already exists at {:?}", db_path)))
     } else if !snapshot_path.exists() && !ignore_missing_snapshot {
@@ -28,32 +50,621 @@ pub fn load_snapshot(
     }
 }
 
+/// Unpacks the snapshot in a temporary directory next to the database, which is only moved in place
+/// once the content of the snapshot has been verified, a corrupted snapshot never creates the database.
+fn install_snapshot(snapshot_path: &Path, db_path: &Path) -> Result<(), Error> {
+    let parent = db_path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    create_dir_all(parent)?;
+    let tmp_dir = TempDir::new_in(parent)?;
+
+    if is_incremental_snapshot(snapshot_path) {
+        restore_incremental_snapshot(snapshot_path, tmp_dir.path())?;
+    } else {
+        match read_archive_manifest(snapshot_path)? {
+            Some(manifest) => {
+                // checks the archive before unpacking anything and the unpacked files after
+                verify_file(snapshot_path, &manifest.archive)?;
+                compression::from_tar_gz(snapshot_path, tmp_dir.path())?;
+                for file in &manifest.files {
+                    verify_file(&tmp_dir.path().join(&file.path), file)?;
+                }
+            }
+            None => {
+                warn!("The snapshot {:?} has no manifest, its integrity can't be verified", snapshot_path);
+                compression::from_tar_gz(snapshot_path, tmp_dir.path())?;
+            }
+        }
+    }
+
+    fs::rename(tmp_dir.path(), db_path)?;
+    Ok(())
+}
+
 pub fn create_snapshot(data: &Data, snapshot_path: &Path) -> Result<(), Error> {
     let tmp_dir = TempDir::new()?;
 
+    // the copy is made from a read transaction, the updates keep being applied while it is written
     data.db.copy_and_compact_to_path(tmp_dir.path())?;
 
-    compression::to_tar_gz(tmp_dir.path(), snapshot_path).map_err(|e| Error::Internal(format!("something went wrong during snapshot compression: {}", e)))
+    // the archive only replaces the previous one once it is durable, a crash while
+    // the snapshot is written never leaves a truncated snapshot in place of a valid one
+    let tmp_archive_path = tmp_path(snapshot_path);
+    compression::to_tar_gz(tmp_dir.path(), &tmp_archive_path).map_err(|e| Error::Internal(format!("something went wrong during snapshot compression: {}", e)))?;
+    sync_file(&tmp_archive_path)?;
+
+    // the previous manifest doesn't describe the new archive
+    let manifest_path = archive_manifest_path(snapshot_path);
+    if manifest_path.exists() {
+        fs::remove_file(&manifest_path)?;
+    }
+    fs::rename(&tmp_archive_path, snapshot_path)?;
+    write_archive_manifest(tmp_dir.path(), snapshot_path)?;
+
+    sync_dir(snapshot_path.parent().unwrap_or(Path::new(".")))
+}
+
+/// The path a file is written to before being renamed in place, once complete.
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    PathBuf::from(tmp_path)
+}
+
+/// Flushes the content of the file to the disk.
+fn sync_file(path: &Path) -> Result<(), Error> {
+    File::open(path)?.sync_all()?;
+    Ok(())
+}
+
+/// Flushes the entries of the directory to the disk, e.g. the files renamed in it.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> Result<(), Error> {
+    File::open(path)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Writes the file durably, it is written next to its path and then renamed.
+fn write_durably(path: &Path, content: &[u8]) -> Result<(), Error> {
+    let tmp_path = tmp_path(path);
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Lists the files of a snapshot archive, and the archive itself, with their size and SHA-256.
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveManifest {
+    archive: ArchiveFile,
+    files: Vec<ArchiveFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+/// The manifest of a snapshot archive is written next to it, e.g. `data.ms.tar.gz.manifest.json`.
+pub fn archive_manifest_path(archive_path: &Path) -> PathBuf {
+    let mut path = archive_path.as_os_str().to_owned();
+    path.push(".manifest.json");
+    PathBuf::from(path)
+}
+
+fn write_archive_manifest(src: &Path, archive_path: &Path) -> Result<PathBuf, Error> {
+    let mut paths = Vec::new();
+    list_files(src, &mut paths)?;
+    paths.sort();
+
+    let mut files = Vec::new();
+    for path in paths {
+        files.push(digest_file(&path, relative_path(src, &path)?)?);
+    }
+    let name = archive_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let archive = digest_file(archive_path, name)?;
+
+    let manifest_path = archive_manifest_path(archive_path);
+    write_durably(&manifest_path, &serde_json::to_vec(&ArchiveManifest { archive, files })?)?;
+    Ok(manifest_path)
+}
+
+fn read_archive_manifest(archive_path: &Path) -> Result<Option<ArchiveManifest>, Error> {
+    let manifest_path = archive_manifest_path(archive_path);
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+    let manifest = fs::read(manifest_path)?;
+    Ok(Some(serde_json::from_slice(&manifest)?))
+}
+
+fn digest_file(path: &Path, name: String) -> Result<ArchiveFile, Error> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.input(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok(ArchiveFile { path: name, size, sha256: format!("{:x}", hasher.result()) })
+}
+
+fn verify_file(path: &Path, expected: &ArchiveFile) -> Result<(), Error> {
+    if !path.exists() {
+        return Err(Error::Internal(format!("the snapshot file {} is missing", expected.path)));
+    }
+    let actual = digest_file(path, expected.path.clone())?;
+    if actual.size != expected.size || actual.sha256 != expected.sha256 {
+        return Err(Error::Internal(format!(
+            "the snapshot file {} is corrupted, expected {} bytes with the SHA-256 {} but found {} bytes with the SHA-256 {}",
+            expected.path, expected.size, expected.sha256, actual.size, actual.sha256,
+        )));
+    }
+    Ok(())
 }
 
-pub fn schedule_snapshot(data: Data, snapshot_dir: &Path, time_gap_s: u64) -> Result<(), Error> {
+/// The path relative to the directory, with slashes as separators.
+fn relative_path(dir: &Path, path: &Path) -> Result<String, Error> {
+    let relative = path.strip_prefix(dir).map_err(|e| Error::Internal(e.to_string()))?;
+    Ok(relative.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+fn db_name(data: &Data) -> Result<&str, Error> {
+    let db_name = Path::new(&data.db_path).file_name().ok_or_else(|| Error::Internal("invalid database name".to_string()))?;
+    Ok(db_name.to_str().unwrap_or("data.ms"))
+}
+
+/// Returns the path of the snapshot file in the snapshot directory, creating the directory if needed.
+pub fn snapshot_file_path(data: &Data, snapshot_dir: &Path) -> Result<PathBuf, Error> {
     if snapshot_dir.file_name().is_none() { 
         return Err(Error::Internal("invalid snapshot file path".to_string()));
     }
-    let db_name = Path::new(&data.db_path).file_name().ok_or_else(|| Error::Internal("invalid database name".to_string()))?;
+    let db_name = db_name(data)?;
     create_dir_all(snapshot_dir)?;
-    let snapshot_path = snapshot_dir.join(format!("{}.tar.gz", db_name.to_str().unwrap_or("data.ms")));
-    
-    thread::spawn(move || loop { 
-        thread::sleep(Duration::from_secs(time_gap_s));
-        if let Err(e) = create_snapshot(&data, &snapshot_path) {
-            error!("Unsuccessful snapshot creation: {}", e);
+    Ok(snapshot_dir.join(format!("{}.tar.gz", db_name)))
+}
+
+/// Creates a snapshot in the snapshot directory. Without retention the snapshot replaces the previous one,
+/// otherwise its name contains its creation date and only the `retention` most recent snapshots are kept.
+/// An incremental snapshot only stores the chunks of the database that changed since the previous ones.
+/// The snapshot is then copied to the snapshot storage, if any.
+pub fn create_retained_snapshot(
+    data: &Data,
+    snapshot_dir: &Path,
+    retention: Option<usize>,
+    incremental: bool,
+) -> Result<PathBuf, Error> {
+    let (snapshot_path, files) = if incremental {
+        create_incremental_snapshot(data, snapshot_dir, retention)?
+    } else {
+        let snapshot_path = create_local_snapshot(data, snapshot_dir, retention)?;
+        // the manifest is copied last, a storage never holds a manifest without its snapshot
+        let manifest_path = archive_manifest_path(&snapshot_path);
+        (snapshot_path.clone(), vec![snapshot_path, manifest_path])
+    };
+
+    if let Some(storage) = &data.snapshot_storage {
+        for file in &files {
+            let name = file.strip_prefix(snapshot_dir).unwrap_or(file);
+            let name = name.to_string_lossy().replace('\\', "/");
+            storage.store(file, &name)?;
+        }
+        info!("Snapshot copied to {}", storage.location(""));
+    }
+
+    Ok(snapshot_path)
+}
+
+fn create_local_snapshot(data: &Data, snapshot_dir: &Path, retention: Option<usize>) -> Result<PathBuf, Error> {
+    let snapshot_path = snapshot_file_path(data, snapshot_dir)?;
+
+    match retention {
+        None => {
+            create_snapshot(data, &snapshot_path)?;
+            Ok(snapshot_path)
+        }
+        Some(retention) => {
+            let db_name = db_name(data)?;
+            let date = Utc::now().format("%Y%m%d-%H%M%S%3f");
+            let snapshot_path = snapshot_dir.join(format!("{}-{}.tar.gz", db_name, date));
+            create_snapshot(data, &snapshot_path)?;
+            prune_snapshots(snapshot_dir, db_name, retention.max(1))?;
+            Ok(snapshot_path)
+        }
+    }
+}
+
+/// Removes the dated snapshots of the database but the `retention` most recent ones.
+fn prune_snapshots(snapshot_dir: &Path, db_name: &str, retention: usize) -> Result<(), Error> {
+    let prefix = format!("{}-", db_name);
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(snapshot_dir)? {
+        let path = entry?.path();
+        let is_dated_snapshot = path.file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.starts_with(&prefix) && name.ends_with(".tar.gz"));
+        if is_dated_snapshot {
+            snapshots.push(path);
+        }
+    }
+
+    // the dates in the names sort the snapshots chronologically
+    snapshots.sort();
+    let outdated = snapshots.len().saturating_sub(retention);
+    for path in &snapshots[..outdated] {
+        fs::remove_file(path)?;
+        let manifest_path = archive_manifest_path(path);
+        if manifest_path.exists() {
+            fs::remove_file(manifest_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates a snapshot every `data.snapshot_interval_sec` seconds, the interval can be changed
+/// while waiting for the next snapshot.
+pub fn schedule_snapshot(
+    data: Data,
+    snapshot_dir: &Path,
+    retention: Option<usize>,
+    incremental: bool,
+) -> Result<(), Error> {
+    // fails early if the snapshot directory can't be used
+    snapshot_file_path(&data, snapshot_dir)?;
+    let snapshot_dir = snapshot_dir.to_path_buf();
+
+    thread::spawn(move || {
+        let mut last_snapshot = Instant::now();
+        loop {
+            let interval = Duration::from_secs(data.snapshot_interval_sec.load(Ordering::Relaxed));
+            let elapsed = last_snapshot.elapsed();
+            if elapsed < interval {
+                // wakes up regularly to take a new interval into account
+                thread::sleep((interval - elapsed).min(Duration::from_secs(60)));
+                continue;
+            }
+
+            last_snapshot = Instant::now();
+            if let Err(e) = create_retained_snapshot(&data, &snapshot_dir, retention, incremental) {
+                error!("Unsuccessful snapshot creation: {}", e);
+            }
         }
     });
 
     Ok(())
 }
 
+/// A snapshot of the snapshot directory, as listed by the snapshots API.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub incremental: bool,
+    /// The size of the archive, or of the chunks of an incremental snapshot, which can be shared with other snapshots.
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Lists the snapshots of the database in the snapshot directory, from the oldest to the most recent.
+pub fn list_snapshots(data: &Data, snapshot_dir: &Path) -> Result<Vec<SnapshotInfo>, Error> {
+    let db_name = db_name(data)?;
+    let mut snapshots = Vec::new();
+    if !snapshot_dir.exists() {
+        return Ok(snapshots);
+    }
+
+    for entry in fs::read_dir(snapshot_dir)? {
+        let path = entry?.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if is_archive_name(name, db_name) => name,
+            _ => continue,
+        };
+        let metadata = fs::metadata(&path)?;
+        snapshots.push(SnapshotInfo {
+            id: name.trim_end_matches(".tar.gz").to_string(),
+            incremental: false,
+            size: metadata.len(),
+            created_at: modified_at(&metadata)?,
+        });
+    }
+
+    let incremental_dir = snapshot_dir.join(format!("{}.incremental", db_name));
+    if incremental_dir.join("manifests").exists() {
+        for manifest_path in list_manifests(&incremental_dir)? {
+            let manifest = read_manifest(&manifest_path)?;
+            let chunks: HashSet<_> = manifest.files.iter().flat_map(|file| &file.chunks).collect();
+            let size = chunks.into_iter()
+                .map(|hash| fs::metadata(incremental_dir.join("chunks").join(hash)).map_or(0, |metadata| metadata.len()))
+                .sum();
+            let stem = manifest_path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            snapshots.push(SnapshotInfo {
+                id: format!("{}{}", stem, INCREMENTAL_SUFFIX),
+                incremental: true,
+                size,
+                created_at: modified_at(&fs::metadata(&manifest_path)?)?,
+            });
+        }
+    }
+
+    snapshots.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(snapshots)
+}
+
+/// Whether the file name is the one of a snapshot archive of the database, dated or not.
+fn is_archive_name(name: &str, db_name: &str) -> bool {
+    name == format!("{}.tar.gz", db_name) || (name.starts_with(&format!("{}-", db_name)) && name.ends_with(".tar.gz"))
+}
+
+fn modified_at(metadata: &fs::Metadata) -> Result<DateTime<Utc>, Error> {
+    Ok(DateTime::from(metadata.modified()?))
+}
+
+/// Returns the path of the snapshot archive with this id, the incremental snapshots are made
+/// of several files and can't be downloaded.
+pub fn snapshot_archive_path(data: &Data, snapshot_dir: &Path, id: &str) -> Result<Option<PathBuf>, Error> {
+    if id.ends_with(INCREMENTAL_SUFFIX) {
+        return Err(Error::bad_request("an incremental snapshot is made of several files and can't be downloaded"));
+    }
+    let name = format!("{}.tar.gz", id);
+    if !is_valid_id(id) || !is_archive_name(&name, db_name(data)?) {
+        return Ok(None);
+    }
+    let path = snapshot_dir.join(name);
+    Ok(if path.exists() { Some(path) } else { None })
+}
+
+/// Deletes the snapshot with this id, the chunks of an incremental snapshot that are not used by
+/// the other snapshots are deleted with it. Returns whether the snapshot existed.
+pub fn delete_snapshot(data: &Data, snapshot_dir: &Path, id: &str) -> Result<bool, Error> {
+    if !is_valid_id(id) {
+        return Ok(false);
+    }
+
+    if id.ends_with(INCREMENTAL_SUFFIX) {
+        let incremental_dir = snapshot_dir.join(format!("{}.incremental", db_name(data)?));
+        let manifest_path = incremental_dir.join("manifests").join(format!("{}.json", id.trim_end_matches(INCREMENTAL_SUFFIX)));
+
+        let _lock = INCREMENTAL_LOCK.lock().unwrap();
+        if !manifest_path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(&manifest_path)?;
+        remove_unused_chunks(&incremental_dir, &list_manifests(&incremental_dir)?)?;
+        Ok(true)
+    } else {
+        match snapshot_archive_path(data, snapshot_dir, id)? {
+            Some(path) => {
+                fs::remove_file(&path)?;
+                let manifest_path = archive_manifest_path(&path);
+                if manifest_path.exists() {
+                    fs::remove_file(manifest_path)?;
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// The ids are file names, they can't be used to reach another directory.
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && !id.starts_with('.') && !id.contains(|c| c == '/' || c == '\\')
+}
+
+/// Lists the files of an incremental snapshot and the chunks their content is made of.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotManifest {
+    files: Vec<SnapshotFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotFile {
+    path: String,
+    size: u64,
+    chunks: Vec<String>,
+}
+
+/// An incremental snapshot is either the directory of the incremental snapshots, in which case
+/// the most recent one is used, or the manifest of one of them.
+fn is_incremental_snapshot(snapshot_path: &Path) -> bool {
+    snapshot_path.is_dir() || snapshot_path.extension().map_or(false, |ext| ext == "json")
+}
+
+/// Creates an incremental snapshot in the `{db_name}.incremental` directory of the snapshot directory.
+/// The database is copied without compaction, the unchanged pages then stay at the same place and
+/// the chunks that are identical to those of the previous snapshots are not stored again.
+/// Returns the path of the manifest of the snapshot and the paths of the files it created.
+fn create_incremental_snapshot(data: &Data, snapshot_dir: &Path, retention: Option<usize>) -> Result<(PathBuf, Vec<PathBuf>), Error> {
+    let db_name = db_name(data)?;
+    let incremental_dir = snapshot_dir.join(format!("{}.incremental", db_name));
+    let _lock = INCREMENTAL_LOCK.lock().unwrap();
+
+    let tmp_dir = TempDir::new()?;
+    data.db.copy_to_path(tmp_dir.path(), CompactionOption::Disabled)?;
+
+    let date = Utc::now().format("%Y%m%d-%H%M%S%3f");
+    let manifest_name = format!("{}-{}.json", db_name, date);
+    let created = store_incremental_snapshot(tmp_dir.path(), &incremental_dir, &manifest_name)?;
+    // without retention the snapshot replaces the previous one, like the full snapshots
+    prune_incremental_snapshots(&incremental_dir, retention.unwrap_or(1).max(1))?;
+
+    let manifest_path = incremental_dir.join("manifests").join(manifest_name);
+    Ok((manifest_path, created))
+}
+
+/// Splits the files of the source directory in chunks stored in the `chunks` directory under their
+/// SHA-256 and writes their list in the manifest, the manifest is written last.
+fn store_incremental_snapshot(src: &Path, incremental_dir: &Path, manifest_name: &str) -> Result<Vec<PathBuf>, Error> {
+    let chunks_dir = incremental_dir.join("chunks");
+    let manifests_dir = incremental_dir.join("manifests");
+    create_dir_all(&chunks_dir)?;
+    create_dir_all(&manifests_dir)?;
+
+    let mut paths = Vec::new();
+    list_files(src, &mut paths)?;
+    paths.sort();
+
+    let mut created = Vec::new();
+    let mut files = Vec::new();
+    for path in paths {
+        let mut file = File::open(&path)?;
+        let mut size = 0;
+        let mut chunks = Vec::new();
+
+        loop {
+            let mut chunk = Vec::new();
+            (&mut file).take(CHUNK_SIZE).read_to_end(&mut chunk)?;
+            if chunk.is_empty() {
+                break;
+            }
+            size += chunk.len() as u64;
+
+            let hash = format!("{:x}", Sha256::digest(&chunk));
+            let chunk_path = chunks_dir.join(&hash);
+            if !chunk_path.exists() {
+                // the chunk is renamed once fully written to never leave a truncated chunk
+                let tmp_path = chunks_dir.join(format!("{}.tmp", hash));
+                let mut encoder = GzEncoder::new(File::create(&tmp_path)?, Compression::default());
+                encoder.write_all(&chunk)?;
+                encoder.finish()?.sync_all()?;
+                fs::rename(&tmp_path, &chunk_path)?;
+                created.push(chunk_path);
+            }
+            chunks.push(hash);
+        }
+
+        files.push(SnapshotFile { path: relative_path(src, &path)?, size, chunks });
+    }
+
+    // the manifest is only written once the chunks are durable, and the previous
+    // snapshots are only pruned once the manifest is durable
+    sync_dir(&chunks_dir)?;
+    let manifest_path = manifests_dir.join(manifest_name);
+    let manifest = serde_json::to_vec(&SnapshotManifest { files })?;
+    write_durably(&manifest_path, &manifest)?;
+    sync_dir(&manifests_dir)?;
+    created.push(manifest_path);
+
+    Ok(created)
+}
+
+fn list_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            list_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the paths of the manifests of the incremental snapshots, from the oldest to the most recent.
+fn list_manifests(incremental_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut manifests = Vec::new();
+    for entry in fs::read_dir(incremental_dir.join("manifests"))? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == "json") {
+            manifests.push(path);
+        }
+    }
+    // the dates in the names sort the manifests chronologically
+    manifests.sort();
+    Ok(manifests)
+}
+
+fn read_manifest(manifest_path: &Path) -> Result<SnapshotManifest, Error> {
+    let manifest = fs::read(manifest_path)?;
+    Ok(serde_json::from_slice(&manifest)?)
+}
+
+/// Removes the manifests but the `retention` most recent ones, then the chunks none of the remaining
+/// manifests use anymore.
+fn prune_incremental_snapshots(incremental_dir: &Path, retention: usize) -> Result<(), Error> {
+    let manifests = list_manifests(incremental_dir)?;
+    let outdated = manifests.len().saturating_sub(retention);
+    for path in &manifests[..outdated] {
+        fs::remove_file(path)?;
+    }
+
+    remove_unused_chunks(incremental_dir, &manifests[outdated..])
+}
+
+fn remove_unused_chunks(incremental_dir: &Path, manifests: &[PathBuf]) -> Result<(), Error> {
+    let mut used = HashSet::new();
+    for path in manifests {
+        for file in read_manifest(path)?.files {
+            used.extend(file.chunks);
+        }
+    }
+
+    for entry in fs::read_dir(incremental_dir.join("chunks"))? {
+        let path = entry?.path();
+        let is_used = path.file_name().and_then(|name| name.to_str()).map_or(false, |name| used.contains(name));
+        if !is_used {
+            fs::remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Rebuilds the database from the chunks of an incremental snapshot, checking their hashes.
+fn restore_incremental_snapshot(snapshot_path: &Path, db_path: &Path) -> Result<(), Error> {
+    let manifest_path = if snapshot_path.is_dir() {
+        list_manifests(snapshot_path)?
+            .pop()
+            .ok_or_else(|| Error::Internal(format!("no incremental snapshot in {:?}", snapshot_path)))?
+    } else {
+        snapshot_path.to_path_buf()
+    };
+
+    let incremental_dir = manifest_path.parent()
+        .and_then(Path::parent)
+        .ok_or_else(|| Error::Internal(format!("invalid incremental snapshot {:?}", manifest_path)))?;
+    let chunks_dir = incremental_dir.join("chunks");
+
+    for file in read_manifest(&manifest_path)?.files {
+        let path = db_path.join(&file.path);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        let mut output = File::create(&path)?;
+        for hash in &file.chunks {
+            let mut chunk = Vec::new();
+            GzDecoder::new(File::open(chunks_dir.join(hash))?).read_to_end(&mut chunk)?;
+            if format!("{:x}", Sha256::digest(&chunk)) != *hash {
+                return Err(Error::Internal(format!("the snapshot chunk {} is corrupted", hash)));
+            }
+            output.write_all(&chunk)?;
+        }
+
+        if output.metadata()?.len() != file.size {
+            return Err(Error::Internal(format!("the snapshot file {} is incomplete", file.path)));
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,4 +704,88 @@ mod tests {
         let contents = fs::read_to_string(dest_dir.join(file_2_relative)).unwrap();
         assert_eq!(contents, "Hello_file_2");
     }
+
+    #[test]
+    fn prune_old_snapshots() {
+        let tempdir = TempDir::new().unwrap();
+        let dir = tempdir.path();
+
+        let names = [
+            "data.ms-20201001-030000000.tar.gz",
+            "data.ms-20201002-030000000.tar.gz",
+            "data.ms-20201003-030000000.tar.gz",
+            "data.ms.tar.gz",
+            "other.ms-20201001-030000000.tar.gz",
+        ];
+        for name in &names {
+            fs::File::create(dir.join(name)).unwrap();
+        }
+
+        prune_snapshots(dir, "data.ms", 2).unwrap();
+
+        assert!(!dir.join(names[0]).exists());
+        for name in &names[1..] {
+            assert!(dir.join(name).exists());
+        }
+    }
+
+    #[test]
+    fn incremental_snapshots() {
+        let tempdir = TempDir::new().unwrap();
+        let dir = tempdir.path();
+
+        let src = dir.join("src");
+        create_dir_all(src.join("main")).unwrap();
+        fs::write(src.join("VERSION"), "0.16.0").unwrap();
+        fs::write(src.join("main/data.mdb"), vec![1u8; CHUNK_SIZE as usize + 10]).unwrap();
+
+        let incremental_dir = dir.join("data.ms.incremental");
+        let created = store_incremental_snapshot(&src, &incremental_dir, "data.ms-20201001-030000000.json").unwrap();
+        // two chunks for the database, one for the version, and the manifest
+        assert_eq!(created.len(), 4);
+
+        // only the changed chunk is stored again
+        fs::write(src.join("VERSION"), "0.17.0").unwrap();
+        let created = store_incremental_snapshot(&src, &incremental_dir, "data.ms-20201002-030000000.json").unwrap();
+        assert_eq!(created.len(), 2);
+
+        prune_incremental_snapshots(&incremental_dir, 1).unwrap();
+        assert_eq!(list_manifests(&incremental_dir).unwrap().len(), 1);
+        assert_eq!(fs::read_dir(incremental_dir.join("chunks")).unwrap().count(), 3);
+
+        let db_path = dir.join("data.ms");
+        assert!(is_incremental_snapshot(&incremental_dir));
+        load_snapshot(db_path.to_str().unwrap(), &incremental_dir, false, false).unwrap();
+        assert_eq!(fs::read_to_string(db_path.join("VERSION")).unwrap(), "0.17.0");
+        assert_eq!(fs::read(db_path.join("main/data.mdb")).unwrap(), fs::read(src.join("main/data.mdb")).unwrap());
+    }
+
+    #[test]
+    fn verify_snapshot_manifest() {
+        let tempdir = TempDir::new().unwrap();
+        let dir = tempdir.path();
+
+        let src = dir.join("src");
+        create_dir_all(src.join("main")).unwrap();
+        fs::write(src.join("VERSION"), "0.17.0").unwrap();
+        fs::write(src.join("main/data.mdb"), vec![7u8; 100_000]).unwrap();
+
+        let archive_path = dir.join("data.ms.tar.gz");
+        compression::to_tar_gz(&src, &archive_path).unwrap();
+        write_archive_manifest(&src, &archive_path).unwrap();
+        assert!(archive_manifest_path(&archive_path).exists());
+        // the manifest is written next to its path and renamed
+        assert!(!tmp_path(&archive_manifest_path(&archive_path)).exists());
+
+        let db_path = dir.join("data.ms");
+        load_snapshot(db_path.to_str().unwrap(), &archive_path, false, false).unwrap();
+        assert_eq!(fs::read(db_path.join("main/data.mdb")).unwrap(), vec![7u8; 100_000]);
+
+        // a truncated archive is refused and doesn't create the database
+        let archive = fs::read(&archive_path).unwrap();
+        fs::write(&archive_path, &archive[..archive.len() / 2]).unwrap();
+        let db_path = dir.join("other.ms");
+        assert!(load_snapshot(db_path.to_str().unwrap(), &archive_path, false, false).is_err());
+        assert!(!db_path.exists());
+    }
 }
@@ -0,0 +1,300 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::env;
+
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::helpers::hmac::hmac_sha256;
+use crate::secrets::resolve_secret;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A destination where the snapshots and the dumps are copied once created.
+pub trait Storage: Send + Sync {
+    /// Stores the local file under the given name.
+    fn store(&self, file: &Path, name: &str) -> Result<(), Error>;
+
+    /// Describes where the file with the given name is stored.
+    fn location(&self, name: &str) -> String;
+}
+
+/// Creates the storage described by the url:
+///  - `s3://<bucket>/<prefix>` authenticated with the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`,
+///    optionally `AWS_SESSION_TOKEN`, environment variables, in the `AWS_REGION` region,
+///  - `gs://<bucket>/<prefix>` authenticated with the `GOOGLE_OAUTH_ACCESS_TOKEN` environment variable,
+///  - `azure://<account>/<container>/<prefix>` authenticated with the `AZURE_STORAGE_SAS_TOKEN` environment variable,
+///  - any other url is a path to a local directory.
+///
+/// The credentials are read each time a file is stored and, like the master key, can be read from
+/// a file, another environment variable or Vault, so that they can be renewed without restarting.
+pub fn storage_from_url(url: &str) -> Result<Box<dyn Storage>, Error> {
+    let invalid = |format: &str| Error::Internal(format!("the storage {:?} must be of the form {}", url, format));
+
+    if url.starts_with("s3://") {
+        let (bucket, prefix) = split_location(&url["s3://".len()..]).ok_or_else(|| invalid("s3://<bucket>/<prefix>"))?;
+        let region = env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        Ok(Box::new(S3Storage { bucket, prefix, region }))
+    } else if url.starts_with("gs://") {
+        let (bucket, prefix) = split_location(&url["gs://".len()..]).ok_or_else(|| invalid("gs://<bucket>/<prefix>"))?;
+        Ok(Box::new(GcsStorage { bucket, prefix }))
+    } else if url.starts_with("azure://") {
+        let (account, location) = split_location(&url["azure://".len()..])
+            .ok_or_else(|| invalid("azure://<account>/<container>/<prefix>"))?;
+        let (container, prefix) = split_location(&location).ok_or_else(|| invalid("azure://<account>/<container>/<prefix>"))?;
+        Ok(Box::new(AzureStorage { account, container, prefix }))
+    } else {
+        Ok(Box::new(LocalStorage { dir: PathBuf::from(url) }))
+    }
+}
+
+/// Splits `name/rest` in its first segment and the rest, which is empty or ends with a slash.
+fn split_location(location: &str) -> Option<(String, String)> {
+    let mut parts = location.splitn(2, '/');
+    let name = parts.next().filter(|name| !name.is_empty())?;
+    let rest = parts.next().unwrap_or("").trim_matches('/');
+    let rest = if rest.is_empty() { String::new() } else { format!("{}/", rest) };
+    Some((name.to_string(), rest))
+}
+
+fn credential(variable: &str) -> Result<String, Error> {
+    let value = env::var(variable).map_err(|_| Error::Internal(format!("the {} environment variable must be set", variable)))?;
+    resolve_secret(&value).map_err(Error::Internal)
+}
+
+/// Percent-encodes everything but the unreserved characters and, optionally, the slashes.
+fn uri_encode(input: &str, keep_slashes: bool) -> String {
+    let mut output = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => output.push(byte as char),
+            b'/' if keep_slashes => output.push('/'),
+            _ => output.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    output
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Sends the file with the request and checks that it succeeded.
+fn upload(mut request: ureq::Request, file: &Path, location: &str) -> Result<(), Error> {
+    // with a content length the file is streamed as is instead of being chunked
+    let length = fs::metadata(file)?.len();
+    request
+        .set("Content-Length", &length.to_string())
+        .set("Content-Type", "application/gzip")
+        .timeout_connect(CONNECT_TIMEOUT.as_millis() as u64);
+
+    let response = request.send(File::open(file)?);
+    if let Some(err) = response.synthetic_error() {
+        return Err(Error::Internal(format!("cannot upload to {}; {}", location, err)));
+    }
+    if !response.ok() {
+        let status = response.status();
+        let body = response.into_string().unwrap_or_default();
+        return Err(Error::Internal(format!("cannot upload to {}; the server responded with status {}: {}", location, status, body)));
+    }
+
+    Ok(())
+}
+
+pub struct LocalStorage {
+    dir: PathBuf,
+}
+
+impl Storage for LocalStorage {
+    fn store(&self, file: &Path, name: &str) -> Result<(), Error> {
+        let path = self.dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(file, path)?;
+        Ok(())
+    }
+
+    fn location(&self, name: &str) -> String {
+        self.dir.join(name).display().to_string()
+    }
+}
+
+pub struct S3Storage {
+    bucket: String,
+    prefix: String,
+    region: String,
+}
+
+impl S3Storage {
+    fn host(&self) -> String {
+        format!("{}.s3.{}.amazonaws.com", self.bucket, self.region)
+    }
+}
+
+impl Storage for S3Storage {
+    fn store(&self, file: &Path, name: &str) -> Result<(), Error> {
+        let access_key = credential("AWS_ACCESS_KEY_ID")?;
+        let secret_key = credential("AWS_SECRET_ACCESS_KEY")?;
+        let session_token = env::var("AWS_SESSION_TOKEN").ok();
+
+        let host = self.host();
+        let path = format!("/{}", uri_encode(&format!("{}{}", self.prefix, name), true));
+        let now = Utc::now();
+        let date_time = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        // the payload isn't signed to avoid reading the file twice, it is sent over HTTPS
+        let mut headers = vec![
+            ("host", host.clone()),
+            ("x-amz-content-sha256", "UNSIGNED-PAYLOAD".to_string()),
+            ("x-amz-date", date_time.clone()),
+        ];
+        if let Some(token) = &session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+        let canonical_request = format!("PUT\n{}\n\n{}\n{}\nUNSIGNED-PAYLOAD", path, canonical_headers, signed_headers);
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            date_time,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes())),
+        );
+        let signing_key = signing_key(&secret_key, &date, &self.region, "s3");
+        let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            access_key, scope, signed_headers, signature,
+        );
+
+        let mut request = ureq::put(&format!("https://{}{}", host, path));
+        for (name, value) in &headers[1..] {
+            request.set(name, value);
+        }
+        request.set("Authorization", &authorization);
+
+        upload(request, file, &self.location(name))
+    }
+
+    fn location(&self, name: &str) -> String {
+        format!("s3://{}/{}{}", self.bucket, self.prefix, name)
+    }
+}
+
+/// Derives the key signing the AWS requests of the day, in the region, for the service.
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request").to_vec()
+}
+
+pub struct GcsStorage {
+    bucket: String,
+    prefix: String,
+}
+
+impl Storage for GcsStorage {
+    fn store(&self, file: &Path, name: &str) -> Result<(), Error> {
+        let token = credential("GOOGLE_OAUTH_ACCESS_TOKEN")?;
+        let url = format!(
+            "https://storage.googleapis.com/upload/storage/v1/b/{}/o?uploadType=media&name={}",
+            uri_encode(&self.bucket, false),
+            uri_encode(&format!("{}{}", self.prefix, name), false),
+        );
+
+        let mut request = ureq::post(&url);
+        request.set("Authorization", &format!("Bearer {}", token));
+
+        upload(request, file, &self.location(name))
+    }
+
+    fn location(&self, name: &str) -> String {
+        format!("gs://{}/{}{}", self.bucket, self.prefix, name)
+    }
+}
+
+pub struct AzureStorage {
+    account: String,
+    container: String,
+    prefix: String,
+}
+
+impl Storage for AzureStorage {
+    fn store(&self, file: &Path, name: &str) -> Result<(), Error> {
+        let sas_token = credential("AZURE_STORAGE_SAS_TOKEN")?;
+        let url = format!(
+            "https://{}.blob.core.windows.net/{}/{}?{}",
+            self.account,
+            self.container,
+            uri_encode(&format!("{}{}", self.prefix, name), true),
+            sas_token.trim_start_matches('?'),
+        );
+
+        let mut request = ureq::put(&url);
+        request
+            .set("x-ms-blob-type", "BlockBlob")
+            .set("x-ms-version", "2019-12-12");
+
+        upload(request, file, &self.location(name))
+    }
+
+    fn location(&self, name: &str) -> String {
+        format!("azure://{}/{}/{}{}", self.account, self.container, self.prefix, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storages_from_urls() {
+        let storage = storage_from_url("s3://my-bucket/meilisearch/snapshots/").unwrap();
+        assert_eq!(storage.location("data.ms.tar.gz"), "s3://my-bucket/meilisearch/snapshots/data.ms.tar.gz");
+
+        let storage = storage_from_url("gs://my-bucket").unwrap();
+        assert_eq!(storage.location("data.ms.tar.gz"), "gs://my-bucket/data.ms.tar.gz");
+
+        let storage = storage_from_url("azure://account/backups/meili").unwrap();
+        assert_eq!(storage.location("data.ms.tar.gz"), "azure://account/backups/meili/data.ms.tar.gz");
+
+        assert!(storage_from_url("s3://").is_err());
+        assert!(storage_from_url("azure://account").is_err());
+
+        let storage = storage_from_url("/mnt/backups").unwrap();
+        assert_eq!(storage.location("data.ms.tar.gz"), "/mnt/backups/data.ms.tar.gz");
+    }
+
+    #[test]
+    fn local_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("snapshot.tar.gz");
+        fs::write(&file, b"snapshot").unwrap();
+
+        let storage = storage_from_url(dir.path().join("backups").to_str().unwrap()).unwrap();
+        storage.store(&file, "data.ms.tar.gz").unwrap();
+
+        assert_eq!(fs::read(dir.path().join("backups/data.ms.tar.gz")).unwrap(), b"snapshot");
+    }
+
+    #[test]
+    fn aws_signing_key() {
+        // example of the AWS signature version 4 documentation
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+
+    #[test]
+    fn encode_uris() {
+        assert_eq!(uri_encode("dumps/2020 10/a+b.tar.gz", true), "dumps/2020%2010/a%2Bb.tar.gz");
+        assert_eq!(uri_encode("dumps/a.tar.gz", false), "dumps%2Fa.tar.gz");
+    }
+}
@@ -0,0 +1,150 @@
+use std::env;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{debug, warn};
+
+use crate::Data;
+
+/// Sends a state to systemd, e.g. `READY=1`, when MeiliSearch runs as a `Type=notify` service.
+/// Returns false if the service manager doesn't expect notifications.
+pub fn notify(state: &str) -> io::Result<bool> {
+    match env::var_os("NOTIFY_SOCKET") {
+        Some(socket) => {
+            send_notification(&socket, state)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Logs the failure to notify systemd, the service keeps running.
+pub fn notify_or_warn(state: &str) {
+    if let Err(e) = notify(state) {
+        warn!("Unsuccessful notification of systemd ({}): {}", state, e);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn send_notification(socket: &std::ffi::OsStr, state: &str) -> io::Result<()> {
+    use std::mem;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = socket.as_bytes();
+    let mut addr: libc::sockaddr_un = unsafe { mem::zeroed() };
+    if path.is_empty() || path.len() >= addr.sun_path.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid NOTIFY_SOCKET"));
+    }
+
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    for (dst, src) in addr.sun_path.iter_mut().zip(path) {
+        *dst = *src as libc::c_char;
+    }
+    // a socket in the abstract namespace, its name starts with a null byte
+    if path[0] == b'@' {
+        addr.sun_path[0] = 0;
+    }
+    let addr_len = mem::size_of::<libc::sa_family_t>() + path.len();
+
+    // safety: the socket is closed below and the address is initialized up to addr_len
+    let fd = unsafe { libc::socket(libc::AF_UNIX, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let sent = unsafe {
+        libc::sendto(
+            fd,
+            state.as_ptr() as *const libc::c_void,
+            state.len(),
+            libc::MSG_NOSIGNAL,
+            &addr as *const libc::sockaddr_un as *const libc::sockaddr,
+            addr_len as libc::socklen_t,
+        )
+    };
+    let result = if sent < 0 { Err(io::Error::last_os_error()) } else { Ok(()) };
+    unsafe { libc::close(fd) };
+
+    result
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_notification(_socket: &std::ffi::OsStr, _state: &str) -> io::Result<()> {
+    Ok(())
+}
+
+/// The interval at which systemd expects the watchdog pings, if the watchdog is enabled
+/// for this process.
+pub fn watchdog_interval() -> Option<Duration> {
+    let usec = env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+    // the watchdog may be meant for another process of the service
+    if let Ok(pid) = env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    Some(Duration::from_micros(usec)).filter(|interval| *interval > Duration::from_secs(0))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Pings the systemd watchdog twice per interval as long as the server is responsive: the
+/// async runtime must have ticked recently and the database must be readable. A hung server
+/// stops pinging and is restarted by systemd.
+pub fn spawn_watchdog(data: Data, interval: Duration) {
+    let heartbeat = Arc::new(AtomicU64::new(now_ms()));
+
+    let runtime_heartbeat = heartbeat.clone();
+    actix_rt::spawn(async move {
+        loop {
+            runtime_heartbeat.store(now_ms(), Ordering::Relaxed);
+            actix_rt::time::delay_for(Duration::from_secs(1)).await;
+        }
+    });
+
+    thread::spawn(move || loop {
+        thread::sleep(interval / 2);
+
+        let runtime_lag = Duration::from_millis(now_ms().saturating_sub(heartbeat.load(Ordering::Relaxed)));
+        // the runtime ticks every second
+        if runtime_lag > interval / 2 + Duration::from_secs(1) {
+            warn!("The HTTP runtime has not ticked for {:?}, the watchdog is not pinged", runtime_lag);
+            continue;
+        }
+        if let Err(e) = data.db.main_read_txn() {
+            warn!("The database can't be read, the watchdog is not pinged: {}", e);
+            continue;
+        }
+
+        match notify("WATCHDOG=1") {
+            Ok(_) => debug!("watchdog pinged"),
+            Err(e) => warn!("Unsuccessful watchdog ping: {}", e),
+        }
+    });
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixDatagram;
+
+    #[test]
+    fn notifications_are_sent_to_the_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify.sock");
+        let socket = UnixDatagram::bind(&path).unwrap();
+
+        env::set_var("NOTIFY_SOCKET", &path);
+        assert!(notify("READY=1").unwrap());
+        env::remove_var("NOTIFY_SOCKET");
+        assert!(!notify("READY=1").unwrap());
+
+        let mut buf = [0; 64];
+        let len = socket.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+    }
+}
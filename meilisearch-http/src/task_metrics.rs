@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use meilisearch_core::{ProcessedUpdateResult, UpdateType};
+
+/// The tasks of a type processed since the server started.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TypeCounters {
+    pub processed: u64,
+    pub total_duration: f64,
+    pub max_duration: f64,
+}
+
+/// The documents of an index indexed since the server started.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IndexCounters {
+    pub indexed_documents: u64,
+    pub indexing_duration: f64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TaskCounters {
+    pub processed: u64,
+    pub failed: u64,
+    pub by_type: BTreeMap<&'static str, TypeCounters>,
+    pub indexes: BTreeMap<String, IndexCounters>,
+}
+
+/// Counts the tasks as they are processed, so that the statistics and the metrics never read
+/// the stored tasks. The counters start from zero when the server starts and only grow, the
+/// tasks deleted by hand or by the retention policy are still accounted.
+#[derive(Default)]
+pub struct TaskMetrics {
+    counters: Mutex<TaskCounters>,
+}
+
+impl TaskMetrics {
+    pub fn record(&self, index_uid: &str, result: &ProcessedUpdateResult) {
+        let mut counters = self.counters.lock().unwrap();
+        let failed = result.error.is_some();
+        if failed {
+            counters.failed += 1;
+        } else {
+            counters.processed += 1;
+        }
+
+        let type_counters = counters.by_type.entry(result.update_type.name()).or_default();
+        type_counters.processed += 1;
+        type_counters.total_duration += result.duration;
+        type_counters.max_duration = type_counters.max_duration.max(result.duration);
+
+        match result.update_type {
+            UpdateType::DocumentsAddition { number }
+            | UpdateType::DocumentsPartial { number }
+            | UpdateType::DocumentsImport { number: Some(number), .. } if !failed => {
+                let index_counters = counters.indexes.entry(index_uid.to_string()).or_default();
+                index_counters.indexed_documents += number as u64;
+                index_counters.indexing_duration += result.duration;
+            }
+            _ => (),
+        }
+    }
+
+    pub fn counters(&self) -> TaskCounters {
+        self.counters.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn result(update_type: UpdateType, duration: f64, error: Option<&str>) -> ProcessedUpdateResult {
+        ProcessedUpdateResult {
+            update_id: 0,
+            update_type,
+            error: error.map(String::from),
+            error_type: None,
+            error_code: None,
+            error_link: None,
+            duration,
+            enqueued_at: Utc::now(),
+            processed_at: Utc::now(),
+            canceled_at: None,
+            document_errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn counters_only_grow() {
+        let metrics = TaskMetrics::default();
+        metrics.record("movies", &result(UpdateType::DocumentsAddition { number: 2 }, 1.0, None));
+        metrics.record("movies", &result(UpdateType::DocumentsAddition { number: 5 }, 3.0, Some("invalid")));
+        metrics.record("movies", &result(UpdateType::ClearAll, 0.5, None));
+
+        let counters = metrics.counters();
+        assert_eq!((counters.processed, counters.failed), (2, 1));
+        assert_eq!(counters.by_type["DocumentsAddition"].processed, 2);
+        assert_eq!(counters.by_type["DocumentsAddition"].total_duration, 4.0);
+        assert_eq!(counters.by_type["DocumentsAddition"].max_duration, 3.0);
+        assert_eq!(counters.indexes["movies"].indexed_documents, 2);
+        assert_eq!(counters.indexes["movies"].indexing_duration, 1.0);
+    }
+}
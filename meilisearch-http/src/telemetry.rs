@@ -0,0 +1,467 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::future::Future;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use actix_service::{Service, Transform};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, web, HttpMessage, ResponseError};
+use chrono::{DateTime, Utc};
+use crossbeam_channel::{bounded, RecvTimeoutError, Sender, TrySendError};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use log::warn;
+use meilisearch_core::ProcessedUpdateResult;
+use serde_json::{json, Value};
+
+use crate::helpers::logging::RequestId;
+use crate::Data;
+
+const TRACEPARENT_HEADER: &str = "traceparent";
+const QUEUE_SIZE: usize = 8192;
+const BATCH_SIZE: usize = 512;
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// The number of enqueued updates waiting to be processed that are linked to the request that enqueued them.
+const MAX_PENDING_UPDATES: usize = 10_000;
+
+/// Identifies a span in a trace, as propagated in the W3C `traceparent` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpanContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub sampled: bool,
+}
+
+impl SpanContext {
+    /// Parses a `traceparent` header of the form `00-<trace id>-<parent id>-<flags>`.
+    pub fn from_traceparent(header: &str) -> Option<SpanContext> {
+        let mut parts = header.trim().split('-');
+        let version = parts.next().filter(|version| version.len() == 2 && *version != "ff")?;
+        let trace_id = parts.next().and_then(|id| decode_hex(id))?;
+        let span_id = parts.next().and_then(|id| decode_hex(id))?;
+        let flags = parts.next().and_then(|flags| decode_hex::<[u8; 1]>(flags))?;
+        // the future versions can append fields, the first version can't
+        if version == "00" && parts.next().is_some() {
+            return None;
+        }
+        if trace_id == [0; 16] || span_id == [0; 8] {
+            return None;
+        }
+        Some(SpanContext { trace_id, span_id, sampled: flags[0] & 1 == 1 })
+    }
+
+    pub fn to_traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", hex(&self.trace_id), hex(&self.span_id), self.sampled as u8)
+    }
+
+    fn root() -> SpanContext {
+        SpanContext { trace_id: rand::random(), span_id: rand::random(), sampled: true }
+    }
+
+    fn child(&self) -> SpanContext {
+        SpanContext { span_id: rand::random(), ..*self }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex<T: Default + AsMut<[u8]>>(input: &str) -> Option<T> {
+    let mut output = T::default();
+    let bytes = output.as_mut();
+    if input.len() != bytes.len() * 2 || !input.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&input[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(output)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SpanKind {
+    Internal = 1,
+    Server = 2,
+}
+
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub context: SpanContext,
+    pub parent_span_id: Option<[u8; 8]>,
+    pub name: String,
+    pub kind: SpanKind,
+    pub start_unix_nano: u64,
+    pub end_unix_nano: u64,
+    pub attributes: Vec<(&'static str, Value)>,
+    pub error: Option<String>,
+}
+
+impl Span {
+    fn to_otlp(&self) -> Value {
+        let attributes: Vec<_> = self.attributes.iter().map(|(key, value)| {
+            let value = match value {
+                // the 64 bits integers are encoded as strings
+                Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+                Value::Number(n) => json!({ "doubleValue": n }),
+                Value::Bool(b) => json!({ "boolValue": b }),
+                Value::String(s) => json!({ "stringValue": s }),
+                other => json!({ "stringValue": other.to_string() }),
+            };
+            json!({ "key": key, "value": value })
+        }).collect();
+
+        let status = match &self.error {
+            Some(message) => json!({ "code": 2, "message": message }),
+            None => json!({ "code": 0 }),
+        };
+
+        let mut span = json!({
+            "traceId": hex(&self.context.trace_id),
+            "spanId": hex(&self.context.span_id),
+            "name": self.name,
+            "kind": self.kind as u8,
+            "startTimeUnixNano": self.start_unix_nano.to_string(),
+            "endTimeUnixNano": self.end_unix_nano.to_string(),
+            "attributes": attributes,
+            "status": status,
+        });
+        if let Some(parent_span_id) = self.parent_span_id {
+            span["parentSpanId"] = hex(&parent_span_id).into();
+        }
+        span
+    }
+}
+
+fn unix_nano(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+fn date_unix_nano(date: DateTime<Utc>) -> u64 {
+    date.timestamp_nanos().max(0) as u64
+}
+
+/// Exports the spans to an OpenTelemetry collector, with the OTLP/HTTP protocol and its JSON encoding,
+/// from a dedicated thread. The spans are sent by batches and dropped when the collector can't keep up.
+#[derive(Clone)]
+pub struct Tracer {
+    sender: Sender<Span>,
+    /// The context of the requests that enqueued the updates not yet processed, by index and update id.
+    pending_updates: Arc<Mutex<HashMap<(String, u64), SpanContext>>>,
+}
+
+impl Tracer {
+    /// Spawns the exporter, the spans are sent to the `/v1/traces` route of the collector endpoint.
+    pub fn spawn(endpoint: &str, service_name: String) -> Tracer {
+        let endpoint = endpoint.trim_end_matches('/');
+        let url = if endpoint.ends_with("/v1/traces") { endpoint.to_string() } else { format!("{}/v1/traces", endpoint) };
+        let (sender, receiver) = bounded::<Span>(QUEUE_SIZE);
+
+        thread::spawn(move || {
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            let mut deadline = Instant::now() + EXPORT_INTERVAL;
+            loop {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                let disconnected = match receiver.recv_timeout(timeout) {
+                    Ok(span) => {
+                        batch.push(span);
+                        false
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => true,
+                };
+
+                if batch.len() >= BATCH_SIZE || Instant::now() >= deadline || disconnected {
+                    if !batch.is_empty() {
+                        export(&url, &service_name, &batch);
+                        batch.clear();
+                    }
+                    deadline = Instant::now() + EXPORT_INTERVAL;
+                }
+                if disconnected {
+                    break;
+                }
+            }
+        });
+
+        Tracer { sender, pending_updates: Arc::default() }
+    }
+
+    pub fn record(&self, span: Span) {
+        if !span.context.sampled {
+            return;
+        }
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(span) {
+            warn!("The trace exporter can't keep up, a span is dropped");
+        }
+    }
+
+    fn link_update(&self, index_uid: &str, update_id: u64, context: SpanContext) {
+        let mut pending = self.pending_updates.lock().unwrap();
+        // the updates enqueued in an index that is deleted are never processed
+        if pending.len() >= MAX_PENDING_UPDATES {
+            pending.clear();
+        }
+        pending.insert((index_uid.to_string(), update_id), context);
+    }
+
+    /// Records the time the update waited in the queue and the time it took to be processed,
+    /// in the trace of the request that enqueued it.
+    pub fn update_processed(&self, index_uid: &str, status: &ProcessedUpdateResult) {
+        let parent = self.pending_updates.lock().unwrap().remove(&(index_uid.to_string(), status.update_id));
+        let context = parent.map_or_else(SpanContext::root, |parent| parent.child());
+
+        let processed_at = date_unix_nano(status.processed_at);
+        let started_at = processed_at.saturating_sub((status.duration * 1e9) as u64);
+        let attributes = vec![
+            ("meilisearch.index_uid", Value::from(index_uid)),
+            ("meilisearch.update_id", Value::from(status.update_id)),
+            ("meilisearch.update_type", Value::from(status.update_type.name())),
+        ];
+
+        self.record(Span {
+            context: context.child(),
+            parent_span_id: Some(context.span_id),
+            name: "update queued".to_string(),
+            kind: SpanKind::Internal,
+            start_unix_nano: date_unix_nano(status.enqueued_at),
+            end_unix_nano: started_at,
+            attributes: attributes.clone(),
+            error: None,
+        });
+
+        self.record(Span {
+            context,
+            parent_span_id: parent.map(|parent| parent.span_id),
+            name: format!("update {}", status.update_type.name()),
+            kind: SpanKind::Internal,
+            start_unix_nano: date_unix_nano(status.enqueued_at),
+            end_unix_nano: processed_at,
+            attributes,
+            error: status.error.clone(),
+        });
+    }
+}
+
+fn export_payload(service_name: &str, spans: &[Span]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": service_name } },
+                    { "key": "service.version", "value": { "stringValue": env!("CARGO_PKG_VERSION") } },
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans.iter().map(Span::to_otlp).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+fn export(url: &str, service_name: &str, spans: &[Span]) {
+    let payload = export_payload(service_name, spans).to_string();
+    let mut request = ureq::post(url);
+    request
+        .timeout_connect(TIMEOUT.as_millis() as u64)
+        .timeout_read(TIMEOUT.as_millis() as u64)
+        .set("Content-Type", "application/json");
+
+    let response = request.send_string(&payload);
+    if let Some(err) = response.synthetic_error() {
+        warn!("Cannot export {} spans to {}: {}", spans.len(), url, err);
+    } else if !response.ok() {
+        warn!("Cannot export {} spans to {}: status {}", spans.len(), url, response.status());
+    }
+}
+
+thread_local! {
+    /// The slot of the request being handled by this thread in which the id of the update it enqueues is recorded.
+    static ENQUEUED_UPDATE: RefCell<Option<Rc<Cell<Option<u64>>>>> = RefCell::new(None);
+}
+
+/// Records that the request being handled enqueued this update, to link the processing of
+/// the update to the trace of the request.
+pub fn record_enqueued_update(update_id: u64) {
+    ENQUEUED_UPDATE.with(|slot| {
+        if let Some(slot) = &*slot.borrow() {
+            slot.set(Some(update_id));
+        }
+    });
+}
+
+/// Records a span for each request, in the trace given by its `traceparent` header if any,
+/// when the spans are exported. The context of the span is available in the request extensions.
+pub struct RequestTracing;
+
+impl<S, B> Transform<S> for RequestTracing
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type InitError = ();
+    type Transform = RequestTracingMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestTracingMiddleware { service })
+    }
+}
+
+pub struct RequestTracingMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service for RequestTracingMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let tracer = match req.app_data::<web::Data<Data>>().and_then(|data| data.tracer.clone()) {
+            Some(tracer) => tracer,
+            None => return self.service.call(req).boxed_local(),
+        };
+
+        let start = SystemTime::now();
+        let parent = req.headers()
+            .get(TRACEPARENT_HEADER)
+            .and_then(|header| header.to_str().ok())
+            .and_then(SpanContext::from_traceparent);
+        let context = parent.map_or_else(SpanContext::root, |parent| parent.child());
+        req.extensions_mut().insert(context);
+
+        let method = req.method().to_string();
+        let target = req.path().to_string();
+
+        // the handler runs while the future is polled, the update it enqueues is recorded in the slot
+        let enqueued_update = Rc::new(Cell::new(None));
+        let slot = enqueued_update.clone();
+        let mut fut = Box::pin(self.service.call(req));
+        let traced = futures::future::poll_fn(move |cx| {
+            let previous = ENQUEUED_UPDATE.with(|current| current.replace(Some(slot.clone())));
+            let poll = fut.as_mut().poll(cx);
+            ENQUEUED_UPDATE.with(|current| current.replace(previous));
+            poll
+        });
+
+        traced.map(move |result| {
+            let mut attributes = vec![
+                ("http.method", Value::from(method.as_str())),
+                ("http.target", Value::from(target.as_str())),
+            ];
+            let mut name = method.clone();
+            let mut error = None;
+
+            match &result {
+                Ok(res) => {
+                    let request = res.request();
+                    let status = res.status();
+                    if let Some(pattern) = request.match_pattern() {
+                        name = format!("{} {}", method, pattern);
+                    }
+                    attributes.push(("http.status_code", Value::from(status.as_u16())));
+                    if let Some(request_id) = request.extensions().get::<RequestId>() {
+                        attributes.push(("meilisearch.request_id", Value::from(request_id.0.as_str())));
+                    }
+                    if let Some(index_uid) = request.match_info().get("index_uid") {
+                        attributes.push(("meilisearch.index_uid", Value::from(index_uid)));
+                        if let Some(update_id) = enqueued_update.get() {
+                            attributes.push(("meilisearch.update_id", Value::from(update_id)));
+                            tracer.link_update(index_uid, update_id, context);
+                        }
+                    }
+                    if status.is_server_error() {
+                        error = status.canonical_reason().map(ToString::to_string);
+                    }
+                }
+                Err(e) => {
+                    let status = e.as_response_error().status_code();
+                    attributes.push(("http.status_code", Value::from(status.as_u16())));
+                    if status.is_server_error() {
+                        error = Some(e.to_string());
+                    }
+                }
+            }
+
+            tracer.record(Span {
+                context,
+                parent_span_id: parent.map(|parent| parent.span_id),
+                name,
+                kind: SpanKind::Server,
+                start_unix_nano: unix_nano(start),
+                end_unix_nano: unix_nano(SystemTime::now()),
+                attributes,
+                error,
+            });
+
+            result
+        })
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traceparent_headers() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = SpanContext::from_traceparent(header).unwrap();
+        assert_eq!(hex(&context.trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(hex(&context.span_id), "00f067aa0ba902b7");
+        assert!(context.sampled);
+        assert_eq!(context.to_traceparent(), header);
+
+        let context = SpanContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00").unwrap();
+        assert!(!context.sampled);
+
+        assert!(SpanContext::from_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        assert!(SpanContext::from_traceparent("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none());
+        assert!(SpanContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra").is_none());
+        assert!(SpanContext::from_traceparent("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none());
+        assert!(SpanContext::from_traceparent("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra").is_some());
+    }
+
+    #[test]
+    fn otlp_payload() {
+        let context = SpanContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        let span = Span {
+            context: context.child(),
+            parent_span_id: Some(context.span_id),
+            name: "POST /indexes/{index_uid}/documents".to_string(),
+            kind: SpanKind::Server,
+            start_unix_nano: 1_000,
+            end_unix_nano: 2_000,
+            attributes: vec![("http.status_code", Value::from(202))],
+            error: None,
+        };
+
+        let payload = export_payload("meilisearch", &[span]);
+        let span = &payload["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(span["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(span["kind"], 2);
+        assert_eq!(span["startTimeUnixNano"], "1000");
+        assert_eq!(span["attributes"][0], json!({ "key": "http.status_code", "value": { "intValue": "202" } }));
+    }
+}
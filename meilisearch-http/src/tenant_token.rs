@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::hmac::{constant_time_eq, hmac_sha256};
+use crate::keys::matches_pattern;
+
+/// The number of characters of the signing API key present in the token claims.
+pub const API_KEY_PREFIX_LENGTH: usize = 8;
+
+/// The indexes a tenant token gives access to and the filter applied to the searches on them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SearchRules {
+    /// Index uid patterns that can be searched without restriction.
+    Indexes(Vec<String>),
+    /// Index uid patterns associated to the rules of the searches on them.
+    Rules(BTreeMap<String, Option<IndexSearchRules>>),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexSearchRules {
+    pub filter: Option<String>,
+}
+
+impl SearchRules {
+    /// Returns `None` if the index can't be searched, the filter to apply otherwise.
+    /// An exact index uid takes precedence over the patterns.
+    pub fn index_rules(&self, index_uid: &str) -> Option<IndexSearchRules> {
+        match self {
+            SearchRules::Indexes(patterns) => patterns
+                .iter()
+                .find(|pattern| matches_pattern(pattern, index_uid))
+                .map(|_| IndexSearchRules::default()),
+            SearchRules::Rules(rules) => rules
+                .get(index_uid)
+                .or_else(|| {
+                    rules.iter()
+                        .find(|(pattern, _)| matches_pattern(pattern, index_uid))
+                        .map(|(_, rules)| rules)
+                })
+                .map(|rules| rules.clone().unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantClaims {
+    pub search_rules: SearchRules,
+    pub api_key_prefix: String,
+    /// The expiration date of the token, as a unix timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    alg: String,
+    typ: Option<String>,
+}
+
+/// Tenant tokens are JWTs, made of three base64 encoded parts separated by dots, the first one
+/// being a JSON header. Checking the header keeps the keys containing two dots from being taken for tokens.
+pub fn is_tenant_token(token: &str) -> bool {
+    let parts: Vec<_> = token.split('.').collect();
+    if parts.len() != 3 || parts.iter().any(|part| part.is_empty()) {
+        return false;
+    }
+
+    match (base64url_decode(parts[0]), base64url_decode(parts[1]), base64url_decode(parts[2])) {
+        (Some(header), Some(_), Some(_)) => serde_json::from_slice::<Header>(&header).is_ok(),
+        _ => false,
+    }
+}
+
+/// Generates a tenant token signed with the API key, clients usually generate them on their own.
+pub fn generate(claims: &TenantClaims, api_key: &str) -> String {
+    let header = Header { alg: "HS256".to_string(), typ: Some("JWT".to_string()) };
+    let header = base64url_encode(serde_json::to_string(&header).unwrap().as_bytes());
+    let payload = base64url_encode(serde_json::to_string(claims).unwrap().as_bytes());
+
+    let message = format!("{}.{}", header, payload);
+    let signature = base64url_encode(&hmac_sha256(api_key.as_bytes(), message.as_bytes()));
+
+    format!("{}.{}", message, signature)
+}
+
+/// Decodes the claims of the token without verifying its signature,
+/// they are only used to find the key the token must be verified with.
+pub fn decode_unverified(token: &str) -> Result<TenantClaims, String> {
+    let mut parts = token.split('.');
+    let (header, payload) = match (parts.next(), parts.next()) {
+        (Some(header), Some(payload)) => (header, payload),
+        _ => return Err("malformed tenant token".to_string()),
+    };
+
+    let header = base64url_decode(header).ok_or("the tenant token header is not valid base64")?;
+    let header: Header = serde_json::from_slice(&header).map_err(|e| e.to_string())?;
+    if header.alg != "HS256" {
+        return Err(format!("the tenant token algorithm must be HS256, found {}", header.alg));
+    }
+
+    let payload = base64url_decode(payload).ok_or("the tenant token payload is not valid base64")?;
+    serde_json::from_slice(&payload).map_err(|e| format!("invalid tenant token claims; {}", e))
+}
+
+/// Verifies the signature and the expiration date of the token.
+pub fn verify(token: &str, api_key: &str) -> bool {
+    let pos = match token.rfind('.') {
+        Some(pos) => pos,
+        None => return false,
+    };
+    let (message, signature) = (&token[..pos], &token[pos + 1..]);
+
+    let signature = match base64url_decode(signature) {
+        Some(signature) => signature,
+        None => return false,
+    };
+    let expected = hmac_sha256(api_key.as_bytes(), message.as_bytes());
+    if !constant_time_eq(&signature, &expected) {
+        return false;
+    }
+
+    match decode_unverified(token) {
+        Ok(claims) => claims.exp.map_or(true, |exp| exp > Utc::now().timestamp()),
+        Err(_) => false,
+    }
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            output.push(BASE64URL[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    output
+}
+
+fn base64url_decode(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim_end_matches('=');
+    if input.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64URL.iter().position(|b| b == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            output.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64url_roundtrip() {
+        for input in &["", "f", "fo", "foo", "foob", "fooba", "foobar", "\u{ff}\u{fe}?>"] {
+            let encoded = base64url_encode(input.as_bytes());
+            assert!(!encoded.contains('='));
+            assert_eq!(base64url_decode(&encoded).unwrap(), input.as_bytes());
+        }
+        assert_eq!(base64url_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64url_encode(b"fooba"), "Zm9vYmE");
+        assert!(base64url_decode("Zm9v!mFy").is_none());
+    }
+
+    #[test]
+    fn generate_and_verify() {
+        let claims = TenantClaims {
+            search_rules: SearchRules::Indexes(vec!["movies".to_string()]),
+            api_key_prefix: "abcdefgh".to_string(),
+            exp: None,
+        };
+        let token = generate(&claims, "abcdefghijkl");
+
+        assert!(is_tenant_token(&token));
+        assert!(!is_tenant_token("my.master.key"));
+        assert!(!is_tenant_token(&format!("{}.", token)));
+        assert!(verify(&token, "abcdefghijkl"));
+        assert!(!verify(&token, "another key"));
+
+        let expired = TenantClaims { exp: Some(Utc::now().timestamp() - 60), ..claims };
+        assert!(!verify(&generate(&expired, "abcdefghijkl"), "abcdefghijkl"));
+    }
+
+    #[test]
+    fn search_rules() {
+        let rules: SearchRules = serde_json::from_str(r#"{
+            "movies": { "filter": "user_id = 1" },
+            "movies_*": null
+        }"#).unwrap();
+
+        assert_eq!(rules.index_rules("movies").unwrap().filter.as_deref(), Some("user_id = 1"));
+        assert_eq!(rules.index_rules("movies_fr").unwrap().filter, None);
+        assert!(rules.index_rules("books").is_none());
+
+        let rules: SearchRules = serde_json::from_str(r#"["*"]"#).unwrap();
+        assert_eq!(rules.index_rules("books").unwrap().filter, None);
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Instant;
+
+use log::{info, warn};
+
+use crate::error::Error;
+use crate::Data;
+
+/// Loads the search structures of the given indexes in the page cache of the operating system,
+/// `*` stands for all the indexes. The indexes are warmed up one after the other, an index that
+/// can't be warmed up is logged and skipped.
+pub fn warmup_indexes(data: &Data, index_uids: &[String]) -> Result<(), Error> {
+    let index_uids = if index_uids.iter().any(|uid| uid == "*") {
+        data.db.indexes_uids()
+    } else {
+        index_uids.to_vec()
+    };
+
+    let reader = data.db.main_read_txn()?;
+    for index_uid in index_uids {
+        let index = match data.db.open_index(&index_uid) {
+            Some(index) => index,
+            None => {
+                warn!("The index {} can't be warmed up, it does not exist", index_uid);
+                continue;
+            }
+        };
+
+        let start = Instant::now();
+        match index.warmup(&reader) {
+            Ok(size) => info!("The index {} has been warmed up, {} bytes read in {:.2?}", index_uid, size, start.elapsed()),
+            Err(e) => warn!("The index {} can't be warmed up: {}", index_uid, e),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use crossbeam_channel::{bounded, Sender, TrySendError};
+use log::{error, warn};
+
+use crate::helpers::hmac::hmac_sha256;
+use crate::routes::task::Task;
+
+const SIGNATURE_HEADER: &str = "X-Meili-Signature";
+const TIMESTAMP_HEADER: &str = "X-Meili-Timestamp";
+const MAX_ATTEMPTS: u32 = 3;
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// The number of tasks waiting to be sent to an endpoint, the next ones are dropped.
+const QUEUE_CAPACITY: usize = 1000;
+
+/// Sends the finished tasks to the configured webhooks, every endpoint has its own thread and
+/// queue, this way neither the update loop nor the other endpoints are slowed down by a slow
+/// or unreachable endpoint.
+#[derive(Clone)]
+pub struct WebhookNotifier {
+    endpoints: Vec<Endpoint>,
+}
+
+#[derive(Clone)]
+struct Endpoint {
+    url: String,
+    sender: Sender<Arc<String>>,
+    /// The number of tasks dropped because the queue of the endpoint was full.
+    dropped: Arc<AtomicU64>,
+}
+
+impl WebhookNotifier {
+    pub fn spawn(urls: Vec<String>, secret: Option<String>) -> WebhookNotifier {
+        let endpoints = urls.into_iter().map(|url| {
+            let (sender, receiver) = bounded::<Arc<String>>(QUEUE_CAPACITY);
+            let thread_url = url.clone();
+            let secret = secret.clone();
+            thread::spawn(move || {
+                for payload in receiver {
+                    send(&thread_url, &payload, secret.as_deref());
+                }
+            });
+            Endpoint { url, sender, dropped: Arc::new(AtomicU64::new(0)) }
+        });
+
+        WebhookNotifier { endpoints: endpoints.collect() }
+    }
+
+    pub fn notify(&self, task: Task) {
+        let payload = match serde_json::to_string(&task) {
+            Ok(payload) => Arc::new(payload),
+            Err(e) => {
+                error!("Impossible to serialize the webhook payload: {}", e);
+                return;
+            }
+        };
+
+        for endpoint in &self.endpoints {
+            match endpoint.sender.try_send(payload.clone()) {
+                Ok(()) => (),
+                Err(TrySendError::Full(_)) => {
+                    let dropped = endpoint.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    warn!(
+                        "The queue of the webhook {} is full, the task {} is dropped ({} dropped so far)",
+                        endpoint.url, task.uid, dropped,
+                    );
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    error!("The thread of the webhook {} is not running anymore", endpoint.url);
+                }
+            }
+        }
+    }
+}
+
+/// Posts the payload, retrying with an exponential backoff, returns whether it has been received.
+/// The signature covers the timestamp sent along, for the receivers to reject the replayed payloads.
+pub(crate) fn send(url: &str, payload: &str, secret: Option<&str>) -> bool {
+    for attempt in 1..=MAX_ATTEMPTS {
+        let mut request = ureq::post(url);
+        request
+            .timeout_connect(TIMEOUT.as_millis() as u64)
+            .timeout_read(TIMEOUT.as_millis() as u64)
+            .set("Content-Type", "application/json");
+        if let Some(secret) = secret {
+            let timestamp = Utc::now().timestamp().to_string();
+            let signature = sign(secret.as_bytes(), &signed_message(&timestamp, payload));
+            request
+                .set(TIMESTAMP_HEADER, &timestamp)
+                .set(SIGNATURE_HEADER, &format!("sha256={}", signature));
+        }
+
+        let response = request.send_string(payload);
+        if response.ok() {
+            return true;
+        }
+
+        warn!(
+            "Unsuccessful call to webhook {} (attempt {}/{}): status {}",
+            url, attempt, MAX_ATTEMPTS, response.status(),
+        );
+        if attempt < MAX_ATTEMPTS {
+            thread::sleep(Duration::from_secs(2u64.pow(attempt)));
+        }
+    }
+    false
+}
+
+/// The message signed for a payload sent at the timestamp: `{timestamp}.{payload}`.
+fn signed_message(timestamp: &str, payload: &str) -> Vec<u8> {
+    format!("{}.{}", timestamp, payload).into_bytes()
+}
+
+/// Computes the hexadecimal HMAC-SHA256 of the message.
+fn sign(key: &[u8], message: &[u8]) -> String {
+    hmac_sha256(key, message).iter().map(|b| format!("{:02x}", b)).collect()
+}
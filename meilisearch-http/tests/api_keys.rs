@@ -0,0 +1,351 @@
+mod common;
+
+use actix_web::test;
+use chrono::Utc;
+use serde_json::json;
+
+#[actix_rt::test]
+async fn scoped_api_keys() {
+    let mut server = common::Server::with_master_key("movies", "masterKey");
+    for uid in &["movies", "movies_fr", "books"] {
+        let (_, status_code) = server.post_request_with_key("/indexes", json!({ "uid": uid }), "masterKey").await;
+        assert_eq!(status_code, 201);
+    }
+
+    server.add_api_key(json!({
+        "key": "moviesSearchKey",
+        "actions": ["search", "settings.get"],
+        "indexes": ["movies*"],
+        "createdAt": Utc::now(),
+    }));
+
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search?q=carol", "moviesSearchKey").await;
+    assert_eq!(status_code, 200);
+
+    let (_, status_code) = server.get_request_with_key("/indexes/movies_fr/settings", "moviesSearchKey").await;
+    assert_eq!(status_code, 200);
+
+    let (response, status_code) = server.get_request_with_key("/indexes/books/search?q=carol", "moviesSearchKey").await;
+    assert_eq!(status_code, 403);
+    assert_eq!(response["errorCode"], "invalid_token");
+
+    let body = json!([{ "id": 1, "title": "Carol" }]);
+    let (_, status_code) = server.post_request_with_key("/indexes/movies/documents", body, "moviesSearchKey").await;
+    assert_eq!(status_code, 403);
+
+    // an expired key is refused
+    server.add_api_key(json!({
+        "key": "expiredKey",
+        "actions": ["*"],
+        "indexes": ["*"],
+        "expiresAt": "2020-01-01T00:00:00Z",
+        "createdAt": "2019-01-01T00:00:00Z",
+    }));
+
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search?q=carol", "expiredKey").await;
+    assert_eq!(status_code, 403);
+}
+
+#[actix_rt::test]
+async fn scoped_api_keys_on_routes_without_index() {
+    let mut server = common::Server::with_master_key("movies", "masterKey");
+    for uid in &["movies", "books"] {
+        let (_, status_code) = server.post_request_with_key("/indexes", json!({ "uid": uid }), "masterKey").await;
+        assert_eq!(status_code, 201);
+    }
+
+    server.add_api_key(json!({
+        "key": "moviesAdminKey",
+        "actions": ["*"],
+        "indexes": ["movies*"],
+        "createdAt": Utc::now(),
+    }));
+
+    // the listings only contain the indexes of the key
+    let (response, status_code) = server.get_request_with_key("/indexes", "moviesAdminKey").await;
+    assert_eq!(status_code, 200);
+    let uids: Vec<_> = response.as_array().unwrap().iter().map(|index| index["uid"].as_str().unwrap()).collect();
+    assert_eq!(uids, vec!["movies"]);
+
+    let (response, status_code) = server.get_request_with_key("/tasks", "moviesAdminKey").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["total"], 0);
+
+    let (_, status_code) = server.post_request_with_key("/indexes", json!({ "uid": "movies_fr" }), "moviesAdminKey").await;
+    assert_eq!(status_code, 201);
+    let (_, status_code) = server.post_request_with_key("/indexes", json!({ "uid": "books_fr" }), "moviesAdminKey").await;
+    assert_eq!(status_code, 403);
+
+    let (_, status_code) = server.post_request_with_key("/tasks/cancel?uids=books:0", json!({}), "moviesAdminKey").await;
+    assert_eq!(status_code, 403);
+
+    // the other routes reach every index, even when the gRPC metadata names an index of the key
+    for url in &["/stats", "/metrics", "/dumps", "/tasks/scheduled"] {
+        let (_, status_code) = server.get_request_with_key(url, "moviesAdminKey").await;
+        assert_eq!(status_code, 403, "{}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&server.data)).await;
+        let req = test::TestRequest::get()
+            .uri(url)
+            .header("X-Meili-API-Key", "moviesAdminKey")
+            .header("x-meili-index-uid", "movies")
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), 403, "{}", url);
+    }
+}
+
+#[actix_rt::test]
+async fn tenant_tokens_restrict_searches() {
+    let mut server = common::Server::with_master_key("movies", "masterKey");
+    for uid in &["movies", "books"] {
+        let body = json!({ "uid": uid, "primaryKey": "id" });
+        let (_, status_code) = server.post_request_with_key("/indexes", body, "masterKey").await;
+        assert_eq!(status_code, 201);
+    }
+
+    let documents = json!([
+        { "id": 1, "title": "Carol", "user_id": 1 },
+        { "id": 2, "title": "Joy", "user_id": 2 },
+    ]);
+    let (_, status_code) = server.post_request_with_key("/indexes/movies/documents", documents, "masterKey").await;
+    assert_eq!(status_code, 202);
+    let (response, _) = server.get_request_with_key("/tasks/movies:0/wait?timeoutMs=10000", "masterKey").await;
+    assert_eq!(response["status"], "processed");
+
+    // the tenant tokens can only be verified with the keys derived from the master key
+    let body = json!({ "actions": ["search"], "indexes": ["*"] });
+    let (response, status_code) = server.post_request_with_key("/keys", body, "masterKey").await;
+    assert_eq!(status_code, 201);
+    let key = response["key"].as_str().unwrap().to_string();
+
+    let claims = serde_json::from_value(json!({
+        "searchRules": { "movies": { "filter": "user_id = 1" } },
+        "apiKeyPrefix": &key[..8],
+    })).unwrap();
+    let token = meilisearch_http::tenant_token::generate(&claims, &key);
+
+    let (response, status_code) = server.get_request_with_key("/indexes/movies/search", &token).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["hits"][0]["id"], 1);
+
+    // the filters of the search are combined with the rules of the token
+    let (response, status_code) = server.get_request_with_key("/indexes/movies/search?filters=user_id%20%3D%202", &token).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 0);
+
+    // the filters of the search can't escape the rules of the token
+    let body = json!({ "filters": "user_id = 3) OR (user_id = 2" });
+    let (response, status_code) = server.post_request_with_key("/indexes/movies/search", body, &token).await;
+    assert_eq!(status_code, 400);
+    assert!(response.get("hits").is_none());
+
+    let body = json!({ "filters": "user_id = 3 OR user_id = 2" });
+    let (response, status_code) = server.post_request_with_key("/indexes/movies/search", body, &token).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 0);
+
+    let (_, status_code) = server.get_request_with_key("/indexes/books/search", &token).await;
+    assert_eq!(status_code, 403);
+
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/settings", &token).await;
+    assert_eq!(status_code, 403);
+
+    // a token signed with another key is refused
+    let forged = meilisearch_http::tenant_token::generate(&claims, "searchKeyForgedOne");
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &forged).await;
+    assert_eq!(status_code, 403);
+}
+
+#[actix_rt::test]
+async fn manage_api_keys() {
+    let mut server = common::Server::with_master_key("movies", "masterKey");
+    let (_, status_code) = server.post_request_with_key("/indexes", json!({ "uid": "movies" }), "masterKey").await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({
+        "description": "search the movies",
+        "actions": ["search"],
+        "indexes": ["movies"],
+        "expiresAt": null,
+    });
+    let (response, status_code) = server.post_request_with_key("/keys", body, "masterKey").await;
+    assert_eq!(status_code, 201);
+    let uid = response["uid"].as_str().unwrap().to_string();
+    let key = response["key"].as_str().unwrap().to_string();
+    assert_eq!(response["description"], "search the movies");
+
+    // only the master key can manage the keys
+    let (_, status_code) = server.get_request_with_key("/keys", &key).await;
+    assert_eq!(status_code, 403);
+
+    let (response, status_code) = server.get_request_with_key("/keys", "masterKey").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["results"][0]["uid"], uid.as_str());
+
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &key).await;
+    assert_eq!(status_code, 200);
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/settings", &key).await;
+    assert_eq!(status_code, 403);
+
+    let url = format!("/keys/{}", uid);
+    let body = json!({ "actions": ["search", "settings.get"], "description": null });
+    let (response, status_code) = server.patch_request_with_key(&url, body, "masterKey").await;
+    assert_eq!(status_code, 200);
+    assert!(response.get("description").is_none());
+    // the key itself is only returned when it is created or rotated
+    assert!(response.get("key").is_none());
+    assert_eq!(response["prefix"], &key[..8]);
+
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/settings", &key).await;
+    assert_eq!(status_code, 200);
+
+    let (_, status_code) = server.patch_request_with_key(&url, json!({ "indexes": [] }), "masterKey").await;
+    assert_eq!(status_code, 400);
+
+    let (_, status_code) = server.delete_request_with_key(&url, "masterKey").await;
+    assert_eq!(status_code, 204);
+    let (_, status_code) = server.get_request_with_key(&url, "masterKey").await;
+    assert_eq!(status_code, 404);
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &key).await;
+    assert_eq!(status_code, 403);
+}
+
+#[actix_rt::test]
+async fn rate_limited_api_keys() {
+    let mut server = common::Server::with_master_key("movies", "masterKey");
+    let (_, status_code) = server.post_request_with_key("/indexes", json!({ "uid": "movies" }), "masterKey").await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({
+        "actions": ["search"],
+        "indexes": ["movies"],
+        "rateLimit": { "requestsPerSecond": 0 },
+    });
+    let (_, status_code) = server.post_request_with_key("/keys", body, "masterKey").await;
+    assert_eq!(status_code, 400);
+
+    let body = json!({
+        "actions": ["search"],
+        "indexes": ["movies"],
+        "rateLimit": { "requestsPerSecond": 0.001, "burst": 2 },
+    });
+    let (response, status_code) = server.post_request_with_key("/keys", body, "masterKey").await;
+    assert_eq!(status_code, 201);
+    assert_eq!(response["rateLimit"]["burst"], 2);
+    let uid = response["uid"].as_str().unwrap().to_string();
+    let key = response["key"].as_str().unwrap().to_string();
+
+    for _ in 0..2 {
+        let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &key).await;
+        assert_eq!(status_code, 200);
+    }
+
+    let (response, status_code) = server.get_request_with_key("/indexes/movies/search", &key).await;
+    assert_eq!(status_code, 429);
+    assert_eq!(response["errorCode"], "too_many_requests");
+
+    // the master key is never limited
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", "masterKey").await;
+    assert_eq!(status_code, 200);
+
+    // removing the limits of the key lifts them immediately
+    let url = format!("/keys/{}", uid);
+    let (_, status_code) = server.patch_request_with_key(&url, json!({ "rateLimit": null }), "masterKey").await;
+    assert_eq!(status_code, 200);
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &key).await;
+    assert_eq!(status_code, 200);
+}
+
+#[actix_rt::test]
+async fn indexing_quota_of_api_keys() {
+    let mut server = common::Server::with_master_key("movies", "masterKey");
+    let (_, status_code) = server.post_request_with_key("/indexes", json!({ "uid": "movies", "primaryKey": "id" }), "masterKey").await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({
+        "actions": ["documents.add"],
+        "indexes": ["movies"],
+        "rateLimit": { "indexingBytesPerDay": 100 },
+    });
+    let (response, status_code) = server.post_request_with_key("/keys", body, "masterKey").await;
+    assert_eq!(status_code, 201);
+    let key = response["key"].as_str().unwrap().to_string();
+
+    let (_, status_code) = server.post_request_with_key("/indexes/movies/documents", json!([{ "id": 1 }]), &key).await;
+    assert_eq!(status_code, 202);
+
+    // the payloads are counted as they are received, whether their length is sent or not
+    let documents: Vec<_> = (0..20).map(|id| json!({ "id": id, "title": "Carol" })).collect();
+    let (response, status_code) = server.post_request_with_key("/indexes/movies/documents", json!(documents), &key).await;
+    assert_eq!(status_code, 429);
+    assert_eq!(response["errorCode"], "too_many_requests");
+}
+
+#[actix_rt::test]
+async fn master_key_from_file() {
+    let dir = tempdir::TempDir::new("secrets").unwrap();
+    let path = dir.path().join("master-key");
+    std::fs::write(&path, "masterKeyFromFile\n").unwrap();
+
+    let reference = format!("file:{}", path.display());
+    let mut server = common::Server::with_master_key("movies", &reference);
+
+    let (_, status_code) = server.get_request_with_key("/indexes", "masterKeyFromFile").await;
+    assert_eq!(status_code, 200);
+    let (_, status_code) = server.get_request_with_key("/indexes", &reference).await;
+    assert_eq!(status_code, 403);
+}
+
+#[actix_rt::test]
+async fn rotate_api_keys() {
+    let mut server = common::Server::with_master_key("movies", "masterKey");
+    let (_, status_code) = server.post_request_with_key("/indexes", json!({ "uid": "movies" }), "masterKey").await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({ "actions": ["search"], "indexes": ["movies"] });
+    let (response, status_code) = server.post_request_with_key("/keys", body, "masterKey").await;
+    assert_eq!(status_code, 201);
+    let uid = response["uid"].as_str().unwrap().to_string();
+    let old_key = response["key"].as_str().unwrap().to_string();
+
+    // only a salted hash of the key is stored
+    let (response, _) = server.get_request_with_key("/keys", "masterKey").await;
+    assert!(!response.to_string().contains(&old_key));
+
+    let claims = serde_json::from_value(json!({
+        "searchRules": ["movies"],
+        "apiKeyPrefix": &old_key[..8],
+    })).unwrap();
+    let token = meilisearch_http::tenant_token::generate(&claims, &old_key);
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &token).await;
+    assert_eq!(status_code, 200);
+
+    let url = format!("/keys/{}/rotate", uid);
+    let (response, status_code) = server.post_request_with_key(&url, json!({ "gracePeriodSec": 3600 }), "masterKey").await;
+    assert_eq!(status_code, 200);
+    let new_key = response["key"].as_str().unwrap().to_string();
+    assert_ne!(new_key, old_key);
+    assert!(response["previousKeyValidUntil"].is_string());
+
+    // both keys are valid during the grace period, but not the tokens signed with the old one
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &new_key).await;
+    assert_eq!(status_code, 200);
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &old_key).await;
+    assert_eq!(status_code, 200);
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &token).await;
+    assert_eq!(status_code, 403);
+
+    // without grace period the old key is immediately invalid
+    let (response, status_code) = server.post_request_with_key(&url, json!({ "gracePeriodSec": 0 }), "masterKey").await;
+    assert_eq!(status_code, 200);
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", &new_key).await;
+    assert_eq!(status_code, 403);
+    let newest_key = response["key"].as_str().unwrap();
+    let (_, status_code) = server.get_request_with_key("/indexes/movies/search", newest_key).await;
+    assert_eq!(status_code, 200);
+
+    let (_, status_code) = server.post_request_with_key("/keys/unknown/rotate", json!({}), "masterKey").await;
+    assert_eq!(status_code, 404);
+}
@@ -0,0 +1,40 @@
+use actix_web::http::StatusCode;
+use actix_web::test;
+use futures::StreamExt;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn resume_changes_after_an_update() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "foo" }])).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 2, "title": "bar" }])).await;
+
+    let mut app = test::init_service(meilisearch_http::create_app(&server.data)).await;
+    let req = test::TestRequest::get().uri("/indexes/test/changes?after=0").to_request();
+    let mut res = test::call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("Content-Type").unwrap(), "text/event-stream");
+
+    // the stream stays open for the next changes, only the first event is read
+    let chunk = res.take_body().next().await.unwrap().unwrap();
+    let event = std::str::from_utf8(&chunk).unwrap();
+    assert!(event.starts_with("id: 1\nevent: DocumentsAddition\ndata: "));
+    assert!(event.ends_with("\n\n"));
+    assert!(event.contains(r#""indexUid":"test""#));
+}
+
+#[actix_rt::test]
+async fn changes_after_an_unknown_update_is_error() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test" })).await;
+
+    let (response, status) = server.get_request("/indexes/test/changes?after=42").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(response["errorCode"], "bad_parameter");
+
+    let (_, status) = server.get_request("/indexes/unknown/changes").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -34,16 +34,28 @@ pub struct Server {
 
 impl Server {
     pub fn with_uid(uid: &str) -> Server {
+        Server::new(uid, None)
+    }
+
+    pub fn with_master_key(uid: &str, master_key: &str) -> Server {
+        Server::new(uid, Some(master_key.to_string()))
+    }
+
+    fn new(uid: &str, master_key: Option<String>) -> Server {
+        Server::with_options(uid, |opt| opt.master_key = master_key)
+    }
+
+    /// A server whose options are changed by the given function before it starts.
+    pub fn with_options(uid: &str, options: impl FnOnce(&mut Opt)) -> Server {
         let tmp_dir = TempDir::new("meilisearch").unwrap();
 
         let default_db_options = DatabaseOptions::default();
 
-        let opt = Opt {
+        let mut opt = Opt {
             db_path: tmp_dir.path().join("db").to_str().unwrap().to_string(),
             dumps_folder: tmp_dir.path().join("dump"),
             dump_batch_size: 16,
             http_addr: "127.0.0.1:7700".to_owned(),
-            master_key: None,
             env: "development".to_owned(),
             no_analytics: true,
             max_mdb_size: default_db_options.main_map_size,
@@ -51,6 +63,7 @@ impl Server {
             http_payload_size_limit: 10000000,
             ..Opt::default()
         };
+        options(&mut opt);
 
         let data = Data::new(opt.clone()).unwrap();
 
@@ -136,7 +149,10 @@ impl Server {
             let (response, status_code) = self.get_update_status(update_id).await;
             assert_eq!(status_code, 200);
 
-            if response["status"] == "processed" || response["status"] == "failed" {
+            if response["status"] == "processed"
+                || response["status"] == "failed"
+                || response["status"] == "canceled"
+            {
                 // eprintln!("{:#?}", response);
                 return;
             }
@@ -179,6 +195,129 @@ impl Server {
         (response, status_code)
     }
 
+    pub async fn get_raw_request(&mut self, url: &str) -> (Vec<u8>, StatusCode) {
+        eprintln!("get_raw_request: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let req = test::TestRequest::get().uri(url).to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        (body.to_vec(), status_code)
+    }
+
+    pub async fn post_raw_request(&mut self, url: &str, body: Vec<u8>) -> (Value, StatusCode) {
+        eprintln!("post_raw_request: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let req = test::TestRequest::post()
+            .uri(url)
+            .header("Content-Type", "application/gzip")
+            .set_payload(body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub async fn get_request_with_key(&mut self, url: &str, key: &str) -> (Value, StatusCode) {
+        eprintln!("get_request_with_key: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let req = test::TestRequest::get()
+            .uri(url)
+            .header("X-Meili-API-Key", key)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub async fn post_request_with_key(&mut self, url: &str, body: Value, key: &str) -> (Value, StatusCode) {
+        eprintln!("post_request_with_key: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let req = test::TestRequest::post()
+            .uri(url)
+            .header("X-Meili-API-Key", key)
+            .set_json(&body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub async fn patch_request(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
+        eprintln!("patch_request: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let req = test::TestRequest::patch()
+            .uri(url)
+            .set_json(&body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub async fn patch_request_with_key(&mut self, url: &str, body: Value, key: &str) -> (Value, StatusCode) {
+        eprintln!("patch_request_with_key: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let req = test::TestRequest::patch()
+            .uri(url)
+            .header("X-Meili-API-Key", key)
+            .set_json(&body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub async fn delete_request_with_key(&mut self, url: &str, key: &str) -> (Value, StatusCode) {
+        eprintln!("delete_request_with_key: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let req = test::TestRequest::delete()
+            .uri(url)
+            .header("X-Meili-API-Key", key)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub fn add_api_key(&self, key: Value) {
+        let key = serde_json::from_value(key).unwrap();
+        self.data.keys.insert(&self.data.db, key).unwrap();
+    }
+
     pub async fn post_request_async(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
         eprintln!("post_request_async: {}", url);
 
@@ -294,6 +433,26 @@ impl Server {
         self.get_request(&url).await
     }
 
+    pub async fn get_tasks(&mut self, query: &str) -> (Value, StatusCode) {
+        let url = format!("/tasks?{}", query);
+        self.get_request(&url).await
+    }
+
+    pub async fn get_task(&mut self, task_uid: &str) -> (Value, StatusCode) {
+        let url = format!("/tasks/{}", task_uid);
+        self.get_request(&url).await
+    }
+
+    pub async fn wait_task(&mut self, task_uid: &str, query: &str) -> (Value, StatusCode) {
+        let url = format!("/tasks/{}/wait?{}", task_uid, query);
+        self.get_request(&url).await
+    }
+
+    pub async fn cancel_tasks(&mut self, uids: &str) -> (Value, StatusCode) {
+        let url = format!("/tasks/cancel?uids={}", uids);
+        self.post_request(&url, Value::Null).await
+    }
+
     pub async fn get_all_documents(&mut self) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/documents", self.uid);
         self.get_request(&url).await
@@ -340,6 +499,11 @@ impl Server {
         self.delete_request_async(&url).await
     }
 
+    pub async fn fetch_documents(&mut self, body: Value) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/documents/fetch", self.uid);
+        self.post_request(&url, body).await
+    }
+
     pub async fn delete_multiple_documents(&mut self, body: Value) {
         let url = format!("/indexes/{}/documents/delete-batch", self.uid);
         self.post_request_async(&url, body).await;
@@ -0,0 +1,37 @@
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn compact_database() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.post_request("/compact", json!({})).await;
+    assert_eq!(status_code, 202);
+    assert_eq!(response["status"], "processing");
+
+    // the compaction runs in the background
+    let mut response = json!(null);
+    for _ in 0..50 {
+        let (value, status_code) = server.get_request("/compact/status").await;
+        assert_eq!(status_code, 200);
+        assert_ne!(value["status"], "failed");
+        if value["status"] == "done" {
+            response = value;
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert!(response["compactedMainSize"].as_u64().unwrap() > 0);
+    assert!(response["compactedMainSize"].as_u64() <= response["mainSize"].as_u64());
+
+    // the copy waits for the next start to be installed
+    let compacted = Path::new(&server.data().db_path).join("compacted");
+    assert!(compacted.join("main/data.mdb").exists());
+    assert!(compacted.join("update/data.mdb").exists());
+    assert!(compacted.join("compaction.json").exists());
+}
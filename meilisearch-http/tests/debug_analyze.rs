@@ -0,0 +1,32 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn analyze_indexing_and_search_pipelines() {
+    let mut server = common::Server::with_uid("cities");
+    server.create_index(json!({ "uid": "cities", "primaryKey": "id" })).await;
+    server.post_request_async("/indexes/cities/settings/stop-words", json!(["the"])).await;
+    server.post_request_async("/indexes/cities/settings/synonyms", json!({ "nyc": ["new york"] })).await;
+
+    let (response, status_code) = server.post_request("/indexes/cities/debug/analyze", json!({ "text": "The Café NYC" })).await;
+    assert_eq!(status_code, 200);
+
+    let indexing = &response["indexing"];
+    assert_eq!(indexing[0]["token"], "The");
+    assert_eq!(indexing[0]["stopWord"], true);
+    assert_eq!(indexing[0]["indexedAs"], json!([]));
+    assert_eq!(indexing[1]["indexedAs"], json!(["café", "cafe"]));
+    assert_eq!(indexing[2]["indexedAs"], json!(["nyc"]));
+
+    let search = &response["search"];
+    assert_eq!(search["tokens"][0]["stopWord"], true);
+    assert_eq!(search["tokens"][1]["normalized"], "café");
+    assert_eq!(search["tokens"][1]["maxTypos"], 1);
+    assert_eq!(search["tokens"][2]["maxTypos"], 0);
+    assert_eq!(search["synonyms"], json!([{ "words": "nyc", "alternatives": ["new york"] }]));
+    assert!(search["queryTree"].as_str().unwrap().contains("york"));
+
+    let (_, status_code) = server.post_request("/indexes/unknown/debug/analyze", json!({ "text": "a" })).await;
+    assert_eq!(status_code, 404);
+}
@@ -215,3 +215,156 @@ async fn documents_with_same_id_are_overwritten() {
     assert_eq!(response.as_array().unwrap().len(), 1);
     assert_eq!(response.as_array().unwrap()[0].as_object().unwrap()["content"], "test2");
 }
+
+#[actix_rt::test]
+async fn partial_update_with_merge_strategies() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let body = json!([{
+        "id": 1,
+        "author": { "name": "Tolkien", "born": 1892 },
+        "tags": ["fantasy"],
+    }]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    // replace is the default strategy and keeps the previous behavior
+    let body = json!([{ "id": 1, "author": { "name": "J. R. R. Tolkien" } }]);
+    server.put_request_async("/indexes/test/documents", body).await;
+    let (response, _) = server.get_document(1).await;
+    assert_eq!(response["author"], json!({ "name": "J. R. R. Tolkien" }));
+
+    let body = json!([{ "id": 1, "author": { "born": 1892 }, "tags": ["classic"] }]);
+    server.put_request_async("/indexes/test/documents?mergeStrategy=deepMerge", body).await;
+    let (response, _) = server.get_document(1).await;
+    assert_eq!(response["author"], json!({ "name": "J. R. R. Tolkien", "born": 1892 }));
+    assert_eq!(response["tags"], json!(["classic"]));
+
+    let body = json!([{ "id": 1, "tags": ["fantasy"] }]);
+    server.put_request_async("/indexes/test/documents?mergeStrategy=append", body).await;
+    let (response, _) = server.get_document(1).await;
+    assert_eq!(response["author"], json!({ "name": "J. R. R. Tolkien", "born": 1892 }));
+    assert_eq!(response["tags"], json!(["classic", "fantasy"]));
+}
+
+#[actix_rt::test]
+async fn merge_strategy_on_documents_replacement_is_error() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let body = json!([{ "id": 1 }]);
+    let (response, status_code) = server
+        .post_request("/indexes/test/documents?mergeStrategy=append", body)
+        .await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+}
+
+#[actix_rt::test]
+async fn import_documents_from_unsupported_url_is_error() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let body = json!({ "url": "ftp://example.com/movies.json" });
+    let (response, status_code) = server.post_request("/indexes/test/documents/import", body).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+}
+
+#[actix_rt::test]
+async fn import_documents_from_private_hosts_is_error() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    for url in &[
+        "http://127.0.0.1:7700/movies.json",
+        "http://localhost/movies.json",
+        "http://[::1]/movies.json",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://10.0.0.1/movies.json",
+    ] {
+        let body = json!({ "url": url, "format": "ndjson" });
+        let (response, status_code) = server.post_request("/indexes/test/documents/import", body).await;
+        assert_eq!(status_code, 400, "{}", url);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    }
+}
+
+#[actix_rt::test]
+async fn import_documents_from_unlisted_buckets_is_error() {
+    let mut server = common::Server::with_options("test", |opt| opt.import_s3_buckets = vec!["movies-exports".to_string()]);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    for url in &["s3://private-bucket/movies.json", "s3://movies-exports-private/movies.json"] {
+        let body = json!({ "url": url });
+        let (response, status_code) = server.post_request("/indexes/test/documents/import", body).await;
+        assert_eq!(status_code, 400, "{}", url);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    }
+
+    // the import is enqueued as an update of the index
+    let body = json!({ "url": "s3://movies-exports/movies.json?versionId=1", "authorization": "Bearer secret" });
+    let (response, status_code) = server.post_request("/indexes/test/documents/import", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["type"]["name"], "DocumentsImport");
+    assert_eq!(response["type"]["url"], "s3://movies-exports/movies.json");
+    assert!(!response.to_string().contains("secret"));
+}
+
+#[actix_rt::test]
+async fn import_algolia_export_with_private_settings_url_is_error() {
+    let mut server = common::Server::with_options("test", |opt| opt.import_s3_buckets = vec!["my-bucket".to_string()]);
+    server.create_index(json!({ "uid": "test" })).await;
+
+    let body = json!({
+        "url": "s3://my-bucket/records.json",
+        "settingsUrl": "http://192.168.1.1/settings.json",
+        "format": "algolia",
+    });
+    let (response, status_code) = server.post_request("/indexes/test/documents/import", body).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+
+    let body = json!({ "url": "s3://my-bucket/records.json", "format": "solr" });
+    let (_, status_code) = server.post_request("/indexes/test/documents/import", body).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn dry_run_reports_invalid_documents() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "attributesForFaceting": ["genre"],
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(rating)"],
+    })).await;
+
+    let body = json!([
+        { "id": 1, "genre": "fantasy", "rating": 4 },
+        { "title": "no id" },
+        { "id": "not valid!", "genre": "fantasy" },
+        { "id": 4, "genre": { "name": "fantasy" } },
+        { "id": 5, "rating": "great" },
+    ]);
+    let (response, status_code) = server
+        .post_request("/indexes/test/documents?dryRun=true", body)
+        .await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["numberOfDocuments"], 5);
+    assert_eq!(response["primaryKey"], "id");
+
+    let errors = response["errors"].as_array().unwrap();
+    let indexes: Vec<_> = errors.iter().map(|e| e["index"].as_u64().unwrap()).collect();
+    assert_eq!(indexes, vec![1, 2, 3, 4]);
+    assert_eq!(errors[2]["documentId"], "4");
+
+    // nothing has been enqueued nor indexed
+    let (response, _) = server.get_all_updates_status().await;
+    assert!(response.as_array().unwrap().iter().all(|u| u["type"]["name"] != "DocumentsAddition"));
+    let (response, _) = server.get_all_documents().await;
+    assert!(response.as_array().unwrap().is_empty());
+}
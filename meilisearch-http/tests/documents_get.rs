@@ -1,5 +1,6 @@
 use serde_json::json;
 use actix_web::http::StatusCode;
+use actix_web::test;
 
 mod common;
 
@@ -21,3 +22,67 @@ async fn get_empty_documents_list() {
     assert_eq!(status, StatusCode::OK);
     assert!(response.as_array().unwrap().is_empty());
 }
+
+#[actix_rt::test]
+async fn fetch_documents_by_ids() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    let documents = json!([
+        { "id": 1, "title": "foo", "color": "red" },
+        { "id": 2, "title": "bar", "color": "blue" },
+        { "id": "three", "title": "baz", "color": "green" },
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let body = json!({
+        "ids": [1, "three", 42],
+        "attributesToRetrieve": ["id", "title"],
+    });
+    let (response, status) = server.fetch_documents(body).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["results"], json!([
+        { "id": 1, "title": "foo" },
+        { "id": "three", "title": "baz" },
+    ]));
+    assert_eq!(response["missing"], json!(["42"]));
+}
+
+#[actix_rt::test]
+async fn fetch_too_many_documents_is_error() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test" })).await;
+    let ids: Vec<usize> = (0..1001).collect();
+    let (response, status) = server.fetch_documents(json!({ "ids": ids })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(response["errorCode"], "bad_request");
+}
+
+#[actix_rt::test]
+async fn get_documents_as_ndjson() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    let documents: Vec<_> = (0..50).map(|id| json!({ "id": id, "title": "foo" })).collect();
+    server.add_or_replace_multiple_documents(json!(documents)).await;
+
+    let (response, status) = server.get_request("/indexes/test/documents?limit=30&attributesToRetrieve=id").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response.as_array().unwrap().len(), 30);
+    assert!(response[0].get("title").is_none());
+
+    let mut app = test::init_service(meilisearch_http::create_app(&server.data)).await;
+    let req = test::TestRequest::get()
+        .uri("/indexes/test/documents?offset=40&limit=30")
+        .header("Accept", "application/x-ndjson")
+        .to_request();
+    let res = test::call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("Content-Type").unwrap(), "application/x-ndjson");
+
+    let body = test::read_body(res).await;
+    let lines: Vec<serde_json::Value> = std::str::from_utf8(&body).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[0]["title"], "foo");
+}
@@ -264,6 +264,9 @@ async fn dump_index_settings_should_be_valid() {
             "in",
             "ad"
         ],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {
             "wolverine": ["xmen", "logan"],
             "logan": ["wolverine", "xmen"]
@@ -272,7 +275,18 @@ async fn dump_index_settings_should_be_valid() {
             "gender",
             "color",
             "tags"
-        ]
+        ],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" }
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false
     });
 
     server.update_all_settings(expected.clone()).await;
@@ -360,3 +374,58 @@ async fn get_unexisting_dump_status_should_return_not_found() {
 
     assert_eq!(status_code, 404);
 }
+
+#[actix_rt::test]
+#[ignore]
+async fn dump_api_keys_should_be_imported() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!({ "description": "search", "actions": ["search"], "indexes": ["test"] });
+    let (response, status_code) = server.post_request("/keys", body).await;
+    assert_eq!(status_code, 201);
+    let key_uid = response["uid"].as_str().unwrap().to_string();
+    let key = response["key"].as_str().unwrap().to_string();
+
+    let dump_uid = trigger_and_wait_dump(&mut server).await;
+    let dumps_folder = Path::new(&server.data().dumps_folder);
+    let dump_path = dumps_folder.join(format!("{}.tar.gz", dump_uid));
+
+    let tmp_dir = TempDir::new().unwrap();
+    compression::from_tar_gz(&dump_path, tmp_dir.path()).unwrap();
+    let file = File::open(tmp_dir.path().join("keys.json")).unwrap();
+    let keys: Value = serde_json::from_reader(file).unwrap();
+    assert_eq!(keys[0]["uid"], key_uid.as_str());
+    // only the hash of the key is exported
+    assert!(!keys.to_string().contains(&key));
+
+    let mut imported = common::Server::with_uid("test");
+    meilisearch_http::dump::import_dump(imported.data(), &dump_path, 16).unwrap();
+
+    let (response, status_code) = imported.get_request(&format!("/keys/{}", key_uid)).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["description"], "search");
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn dumps_should_be_listed_downloaded_and_deleted() {
+    let mut server = common::Server::test_server().await;
+
+    let dump_uid = trigger_and_wait_dump(&mut server).await;
+
+    let (response, status_code) = server.get_request("/dumps").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response[0]["uid"], dump_uid.as_str());
+    assert!(response[0]["size"].as_u64().unwrap() > 0);
+
+    let (_, status_code) = server.get_request(&format!("/dumps/{}/download", dump_uid)).await;
+    assert_eq!(status_code, 200);
+
+    let (_, status_code) = server.delete_request(&format!("/dumps/{}", dump_uid)).await;
+    assert_eq!(status_code, 204);
+
+    let (_, status_code) = server.get_request(&format!("/dumps/{}/download", dump_uid)).await;
+    assert_eq!(status_code, 404);
+    let (response, _) = server.get_request("/dumps").await;
+    assert_eq!(response, json!([]));
+}
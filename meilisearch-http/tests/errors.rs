@@ -65,21 +65,23 @@ async fn index_not_found_error() {
 }
 
 #[actix_rt::test]
-async fn primary_key_already_present_error() {
+async fn duplicate_document_id_error() {
     let mut server = common::Server::with_uid("test");
     let body = json!({
         "uid": "test",
-        "primaryKey": "test"
-    });
-    server.create_index(body.clone()).await;
-    let body = json!({
-        "primaryKey": "t"
+        "primaryKey": "id"
     });
-    assert_error!(
-        "primary_key_already_present",
+    server.create_index(body).await;
+    let documents = json!([
+        { "id": 1, "sku": "a" },
+        { "id": 2, "sku": "a" },
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+    assert_error_async!(
+        "duplicate_document_id",
         "invalid_request_error",
-        StatusCode::BAD_REQUEST,
-        server.update_index(body).await);
+        server,
+        server.update_index(json!({ "primaryKey": "sku" })).await);
 }
 
 #[actix_rt::test]
@@ -122,6 +124,35 @@ async fn missing_document_id() {
         server.add_or_replace_multiple_documents_sync(docs).await);
 }
 
+#[actix_rt::test]
+async fn invalid_documents_are_detailed() {
+    let mut server = common::Server::test_server().await;
+    let body = json!({
+        "uid": "test",
+        "primaryKey": "test"
+    });
+    server.create_index(body).await;
+    let docs = json!([
+        { "test": 1 },
+        { "foo": "bar" },
+        { "test": "invalid id" },
+        { "test": 2 },
+    ]);
+    let (response, _) = server.add_or_replace_multiple_documents_sync(docs).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, StatusCode::OK);
+    assert_eq!(response["status"], "failed");
+    assert_eq!(response["errorCode"], "missing_document_id");
+    assert_eq!(response["documentErrors"][0]["index"], 1);
+    assert!(response["documentErrors"][0].get("documentId").is_none());
+    assert_eq!(response["documentErrors"][1]["index"], 2);
+    assert_eq!(response["documentErrors"][1]["documentId"], "invalid id");
+    assert_eq!(response["documentErrors"].as_array().unwrap().len(), 2);
+}
+
 #[actix_rt::test]
 async fn facet_error() {
     let mut server = common::Server::test_server().await;
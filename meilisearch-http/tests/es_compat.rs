@@ -0,0 +1,68 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn search_with_the_elasticsearch_dsl() {
+    let mut server = common::Server::with_uid("movies");
+    server.patch_request("/experimental-features", json!({ "esCompat": true })).await;
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["genre"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Wonder Woman", "genre": "Action", "year": 2017 },
+        { "id": 2, "title": "Wonder Woman 1984", "genre": "Action", "year": 2020 },
+        { "id": 3, "title": "Wonder", "genre": "Drama", "year": 2017 },
+        { "id": 4, "title": "Carol", "genre": "Romance", "year": 2015 },
+    ])).await;
+
+    let body = json!({
+        "query": {
+            "bool": {
+                "must": { "match": { "title": "wonder" } },
+                "filter": { "range": { "year": { "lte": 2018 } } },
+                "must_not": { "term": { "genre": "Drama" } },
+            }
+        },
+        "_source": ["title"],
+    });
+    let (response, status) = server.post_request("/es-compat/movies/_search", body).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["timed_out"], false);
+    assert_eq!(response["hits"]["total"], json!({ "value": 1, "relation": "eq" }));
+    assert_eq!(response["hits"]["hits"], json!([
+        { "_index": "movies", "_id": "1", "_score": null, "_source": { "title": "Wonder Woman" } },
+    ]));
+
+    let body = json!({
+        "query": { "match_all": {} },
+        "size": 0,
+        "aggs": { "genres": { "terms": { "field": "genre", "size": 1 } } },
+    });
+    let (response, status) = server.post_request("/es-compat/movies/_search", body).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["hits"]["total"]["value"], 4);
+    assert_eq!(response["aggregations"]["genres"], json!({
+        "doc_count_error_upper_bound": 0,
+        "sum_other_doc_count": 2,
+        "buckets": [{ "key": "action", "doc_count": 2 }],
+    }));
+}
+
+#[actix_rt::test]
+async fn unsupported_searches_are_rejected() {
+    let mut server = common::Server::with_uid("movies");
+    server.patch_request("/experimental-features", json!({ "esCompat": true })).await;
+    server.create_index(json!({ "uid": "movies" })).await;
+
+    let body = json!({ "query": { "fuzzy": { "title": "wondr" } } });
+    let (_, status) = server.post_request("/es-compat/movies/_search", body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let body = json!({ "query": { "match_all": {} }, "sort": [{ "year": "desc" }] });
+    let (_, status) = server.post_request("/es-compat/movies/_search", body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, status) = server.post_request("/es-compat/series/_search", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
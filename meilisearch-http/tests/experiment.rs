@@ -0,0 +1,85 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect()
+}
+
+async fn movies_server() -> common::Server {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(rating)"],
+    })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Dune", "rating": 3 },
+        { "id": 2, "title": "Dune", "rating": 5 },
+        { "id": 3, "title": "Dune", "rating": 4 },
+    ])).await;
+    server
+}
+
+#[actix_rt::test]
+async fn searches_are_bucketed_by_user_token() {
+    let mut server = movies_server().await;
+    let (_, status_code) = server.put_request("/indexes/movies/experiment", json!({
+        "name": "rating-order",
+        "variants": [
+            { "name": "control", "weight": 0 },
+            { "name": "lowest-first", "weight": 1, "rankingRules": ["asc(rating)"] },
+        ],
+    })).await;
+    assert_eq!(status_code, 200);
+
+    let (response, _) = server.search_post(json!({ "q": "dune", "userToken": "user-1" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 3, 2]);
+    assert_eq!(response["experiment"], json!({ "name": "rating-order", "variant": "lowest-first" }));
+
+    // the searches without a user token are not part of the experiment
+    let (response, _) = server.search_post(json!({ "q": "dune" })).await;
+    assert_eq!(hit_ids(&response), vec![2, 3, 1]);
+    assert!(response.get("experiment").is_none());
+
+    let (_, status_code) = server.delete_request("/indexes/movies/experiment").await;
+    assert_eq!(status_code, 204);
+    let (response, _) = server.search_post(json!({ "q": "dune", "userToken": "user-1" })).await;
+    assert!(response.get("experiment").is_none());
+}
+
+#[actix_rt::test]
+async fn a_user_stays_in_the_same_variant() {
+    let mut server = movies_server().await;
+    server.put_request("/indexes/movies/experiment", json!({
+        "name": "split",
+        "variants": [{ "name": "a", "weight": 50 }, { "name": "b", "weight": 50 }],
+    })).await;
+
+    for user in 0..10 {
+        let user_token = format!("user-{}", user);
+        let (first, _) = server.search_post(json!({ "q": "dune", "userToken": user_token })).await;
+        let (second, _) = server.search_post(json!({ "q": "dune", "userToken": user_token })).await;
+        assert_eq!(first["experiment"], second["experiment"]);
+    }
+}
+
+#[actix_rt::test]
+async fn invalid_experiments() {
+    let mut server = movies_server().await;
+
+    let (_, status_code) = server.put_request("/indexes/movies/experiment", json!({
+        "name": "alone",
+        "variants": [{ "name": "a", "weight": 1 }],
+    })).await;
+    assert_eq!(status_code, 400);
+
+    // the values of an attribute are only sorted when the ranking rules of the index use it
+    let (_, status_code) = server.put_request("/indexes/movies/experiment", json!({
+        "name": "unranked",
+        "variants": [{ "name": "a", "weight": 1 }, { "name": "b", "weight": 1, "rankingRules": ["desc(year)"] }],
+    })).await;
+    assert_eq!(status_code, 400);
+
+    let (response, _) = server.get_request("/indexes/movies/experiment").await;
+    assert!(response.is_null());
+}
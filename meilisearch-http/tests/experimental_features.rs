@@ -0,0 +1,29 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn toggle_experimental_features() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (response, status) = server.get_request("/experimental-features").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response, json!({ "metrics": false, "esCompat": false }));
+
+    let (response, status) = server.patch_request("/experimental-features", json!({ "esCompat": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response, json!({ "metrics": false, "esCompat": true }));
+
+    let (response, status) = server.patch_request("/experimental-features", json!({ "metrics": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response, json!({ "metrics": true, "esCompat": true }));
+
+    let (_, status) = server.patch_request("/experimental-features", json!({ "vectorSearch": true })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // the features are persisted in the database
+    let reader = server.data.db.main_read_txn().unwrap();
+    let features = server.data.db.experimental_features::<serde_json::Value>(&reader).unwrap();
+    assert_eq!(features, Some(json!({ "metrics": true, "esCompat": true })));
+}
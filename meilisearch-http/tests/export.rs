@@ -0,0 +1,69 @@
+use std::io::Read;
+
+use actix_web::http::StatusCode;
+use actix_web::test;
+use serde_json::{json, Value};
+
+mod common;
+
+async fn export(server: &common::Server, body: Value) -> (Vec<u8>, StatusCode) {
+    let mut app = test::init_service(meilisearch_http::create_app(&server.data)).await;
+    let req = test::TestRequest::post()
+        .uri("/indexes/movies/export")
+        .set_json(&body)
+        .to_request();
+    let res = test::call_service(&mut app, req).await;
+    let status = res.status();
+    (test::read_body(res).await.to_vec(), status)
+}
+
+fn lines(body: &[u8]) -> Vec<Value> {
+    body.split(|b| *b == b'\n').filter(|line| !line.is_empty()).map(|line| serde_json::from_slice(line).unwrap()).collect()
+}
+
+#[actix_rt::test]
+async fn export_documents_by_batches() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Carol", "year": 2015 },
+        { "id": 2, "title": "Wonder Woman", "year": 2017 },
+        { "id": 3, "title": "Life of Pi", "year": 2012 },
+        { "id": 4, "title": "Mad Max", "year": 2015 },
+    ])).await;
+
+    let body = json!({ "filters": "year = 2015 OR year = 2017", "attributesToRetrieve": ["title"], "limit": 2 });
+    let (response, status) = export(&server, body).await;
+    assert_eq!(status, StatusCode::OK);
+    let first = lines(&response);
+    assert_eq!(first.len(), 2);
+    assert!(first.iter().all(|document| document.as_object().unwrap().len() == 2));
+
+    // the export resumes after the last document received
+    let body = json!({ "filters": "year = 2015 OR year = 2017", "cursor": first[1]["id"] });
+    let (response, _) = export(&server, body).await;
+    let rest = lines(&response);
+    assert_eq!(rest.len(), 1);
+
+    let mut ids: Vec<_> = first.iter().chain(&rest).map(|document| document["id"].as_u64().unwrap()).collect();
+    ids.sort();
+    assert_eq!(ids, vec![1, 2, 4]);
+}
+
+#[actix_rt::test]
+async fn export_gzipped_documents() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    let (response, status) = export(&server, json!({ "gzip": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(&response[..]).read_to_end(&mut decompressed).unwrap();
+    assert_eq!(lines(&decompressed), vec![json!({ "id": 1, "title": "Carol" })]);
+
+    let (_, status) = export(&server, json!({ "filters": "year = " })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, status) = export(&server, json!({ "cursor": 42 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
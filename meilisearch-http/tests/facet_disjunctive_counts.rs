@@ -0,0 +1,44 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn disjunctive_facets_distribution() {
+    let mut server = common::Server::with_uid("products");
+    server.create_index(json!({ "uid": "products", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["brand", "color"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "name": "phone", "brand": "acme", "color": "black" },
+        { "id": 2, "name": "phone", "brand": "acme", "color": "white" },
+        { "id": 3, "name": "phone", "brand": "volt", "color": "black" },
+        { "id": 4, "name": "phone", "brand": "zen", "color": "black" },
+        { "id": 5, "name": "laptop", "brand": "volt" },
+    ])).await;
+
+    let (response, status_code) = server.search_post(json!({
+        "q": "phone",
+        "facetFilters": [["brand:acme", "brand:volt"], "color:black"],
+        "facetsDistribution": ["brand"],
+        "disjunctiveFacetsDistribution": ["brand", "color"],
+    })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 2);
+
+    // the conjunctive counts are the ones of the documents found
+    assert_eq!(response["facetsDistribution"], json!({ "brand": { "acme": 1, "volt": 1, "zen": 0 } }));
+
+    // the disjunctive counts ignore the selected values of their own facet
+    assert_eq!(response["disjunctiveFacetsDistribution"], json!({
+        "brand": { "acme": 1, "volt": 1, "zen": 1 },
+        "color": { "black": 2, "white": 1 },
+    }));
+
+    // without a query, the counts are the ones of all the documents
+    let (response, _) = server.search_post(json!({
+        "q": "",
+        "facetFilters": ["brand:zen"],
+        "disjunctiveFacetsDistribution": ["brand"],
+    })).await;
+    assert_eq!(response["disjunctiveFacetsDistribution"], json!({ "brand": { "acme": 2, "volt": 2, "zen": 1 } }));
+    assert!(response.get("facetsDistribution").is_none());
+}
@@ -0,0 +1,53 @@
+use serde_json::json;
+
+mod common;
+
+async fn products_server() -> common::Server {
+    let mut server = common::Server::with_uid("products");
+    server.create_index(json!({ "uid": "products", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["price", "brand"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "name": "phone", "price": 199, "brand": "acme" },
+        { "id": 2, "name": "phone case", "price": 9.5, "brand": "acme" },
+        { "id": 3, "name": "phone charger", "price": 25, "brand": "volt" },
+        { "id": 4, "name": "laptop", "price": 1200, "brand": "volt" },
+        { "id": 5, "name": "phone stand", "price": 50, "brand": "acme" },
+    ])).await;
+    server
+}
+
+#[actix_rt::test]
+async fn facet_stats_of_the_matching_documents() {
+    let mut server = products_server().await;
+
+    let (response, status_code) = server.search_post(json!({ "q": "phone", "facetStats": ["price", "brand"] })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["facetStats"], json!({ "price": { "min": 9.5, "max": 199.0 } }));
+    assert!(response.get("facetsDistribution").is_none());
+
+    let (response, _) = server.search_get("q=&facetStats=%5B%22price%22%5D").await;
+    assert_eq!(response["facetStats"]["price"]["max"], 1200.0);
+}
+
+#[actix_rt::test]
+async fn facet_ranges_buckets() {
+    let mut server = products_server().await;
+
+    let (response, status_code) = server.search_post(json!({
+        "q": "phone",
+        "facetRanges": { "price": [0, 10, 50, 200] },
+        "facetsDistribution": ["brand"],
+    })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["facetRanges"]["price"], json!([
+        { "from": 0.0, "to": 10.0, "count": 1 },
+        { "from": 10.0, "to": 50.0, "count": 1 },
+        { "from": 50.0, "to": 200.0, "count": 2 },
+    ]));
+    assert_eq!(response["facetsDistribution"], json!({ "brand": { "acme": 3, "volt": 1 } }));
+
+    let (_, status_code) = server.search_post(json!({ "q": "phone", "facetRanges": { "price": [10, 0] } })).await;
+    assert_eq!(status_code, 400);
+    let (_, status_code) = server.search_post(json!({ "q": "phone", "facetRanges": { "name": [0, 10] } })).await;
+    assert_eq!(status_code, 400);
+}
@@ -0,0 +1,49 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn configure_the_firehose_of_an_index() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies" })).await;
+
+    let (response, status) = server.get_request("/indexes/movies/firehose").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response, json!([]));
+
+    let body = json!([{ "url": "http://localhost:8080/events" }, { "url": "https://cache.example.com/invalidate", "batchSize": 10 }]);
+    let (response, status) = server.put_request("/indexes/movies/firehose", body).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response[0], json!({ "url": "http://localhost:8080/events", "batchSize": 100 }));
+
+    let (response, _) = server.get_request("/indexes/movies/firehose").await;
+    assert_eq!(response.as_array().unwrap().len(), 2);
+    assert_eq!(response[1]["batchSize"], 10);
+
+    // the targets are removed along with the index
+    server.delete_index().await;
+    server.create_index(json!({ "uid": "movies" })).await;
+    let (response, _) = server.get_request("/indexes/movies/firehose").await;
+    assert_eq!(response, json!([]));
+}
+
+#[actix_rt::test]
+async fn invalid_firehose_targets() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (_, status) = server.put_request("/indexes/movies/firehose", json!([{ "url": "http://localhost/" }])).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    server.create_index(json!({ "uid": "movies" })).await;
+    let (_, status) = server.put_request("/indexes/movies/firehose", json!([{ "url": "ftp://localhost/" }])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, status) = server.put_request("/indexes/movies/firehose", json!([{ "url": "http://localhost/", "batchSize": 0 }])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    server.put_request("/indexes/movies/firehose", json!([{ "url": "http://localhost/" }])).await;
+    let (_, status) = server.delete_request("/indexes/movies/firehose").await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (response, _) = server.get_request("/indexes/movies/firehose").await;
+    assert_eq!(response, json!([]));
+}
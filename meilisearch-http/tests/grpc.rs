@@ -0,0 +1,104 @@
+use actix_web::http::StatusCode;
+use actix_web::test;
+use meilisearch_http::helpers::protobuf::{Decoder, Encoder, Field};
+use serde_json::{json, Value};
+
+mod common;
+
+/// Calls the method and returns the fields of the response message and the trailers.
+async fn call(server: &common::Server, method: &str, message: Encoder, index_uid: &str) -> (Vec<(u32, Vec<u8>)>, String) {
+    let message = message.into_bytes();
+    let mut body = vec![0];
+    body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    body.extend_from_slice(&message);
+
+    let mut app = test::init_service(meilisearch_http::create_app(&server.data)).await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/meilisearch.v1.Meilisearch/{}", method))
+        .header("Content-Type", "application/grpc-web+proto")
+        .header("x-meili-index-uid", index_uid)
+        .set_payload(body)
+        .to_request();
+    let res = test::call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = test::read_body(res).await;
+
+    let mut fields = Vec::new();
+    let mut trailers = String::new();
+    let mut rest = &body[..];
+    while rest.len() >= 5 {
+        let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        let payload = &rest[5..5 + len];
+        if rest[0] & 0x80 != 0 {
+            trailers = String::from_utf8(payload.to_vec()).unwrap();
+        } else {
+            for field in Decoder::new(payload) {
+                let (number, value) = field.unwrap();
+                let bytes = match value {
+                    Field::Bytes(bytes) => bytes.to_vec(),
+                    Field::Varint(value) => value.to_string().into_bytes(),
+                    _ => Vec::new(),
+                };
+                fields.push((number, bytes));
+            }
+        }
+        rest = &rest[5 + len..];
+    }
+
+    (fields, trailers)
+}
+
+fn field(fields: &[(u32, Vec<u8>)], number: u32) -> String {
+    fields.iter().find(|(n, _)| *n == number).map(|(_, bytes)| String::from_utf8(bytes.clone()).unwrap()).unwrap_or_default()
+}
+
+#[actix_rt::test]
+async fn add_documents_and_search() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies" })).await;
+
+    let mut message = Encoder::default();
+    message
+        .string(1, "movies")
+        .repeated_string(2, vec![r#"{ "id": 1, "title": "Carol" }"#, r#"{ "id": 2, "title": "Wonder Woman" }"#]);
+    let (fields, trailers) = call(&server, "AddDocuments", message, "movies").await;
+    assert_eq!(trailers, "grpc-status:0\r\n");
+    assert_eq!(field(&fields, 2), "movies:0");
+    server.wait_update_id(0).await;
+
+    let mut message = Encoder::default();
+    message.string(1, "movies:0");
+    let (fields, trailers) = call(&server, "GetTask", message, "movies").await;
+    assert_eq!(trailers, "grpc-status:0\r\n");
+    assert_eq!(field(&fields, 4), "DocumentsAddition");
+    assert_eq!(field(&fields, 5), "processed");
+
+    let mut message = Encoder::default();
+    message.string(1, "movies").string(2, "wonder");
+    let (fields, trailers) = call(&server, "Search", message, "movies").await;
+    assert_eq!(trailers, "grpc-status:0\r\n");
+    let hit: Value = serde_json::from_str(&field(&fields, 1)).unwrap();
+    assert_eq!(hit, json!({ "id": 2, "title": "Wonder Woman" }));
+    assert_eq!(field(&fields, 4), "1");
+
+    // the hits are the same as the ones of the REST API
+    let (response, _) = server.search_post(json!({ "q": "wonder" })).await;
+    assert_eq!(response["hits"][0], hit);
+}
+
+#[actix_rt::test]
+async fn errors_are_sent_in_the_trailers() {
+    let server = common::Server::with_uid("movies");
+
+    let mut message = Encoder::default();
+    message.string(1, "movies").string(2, "wonder");
+    let (fields, trailers) = call(&server, "Search", message, "movies").await;
+    assert!(fields.is_empty());
+    assert!(trailers.starts_with("grpc-status:5\r\ngrpc-message:"));
+
+    // the index of the message must be the one of the metadata
+    let mut message = Encoder::default();
+    message.string(1, "movies");
+    let (_, trailers) = call(&server, "Search", message, "series").await;
+    assert!(trailers.starts_with("grpc-status:3\r\n"));
+}
@@ -553,12 +553,12 @@ async fn create_index_and_add_indentifier_after() {
     assert_eq!(response["primaryKey"].as_str().unwrap(), "id");
 }
 
-// Test that it's impossible to change the primary_key
+// Test that changing the primary_key enqueues an update
 #[actix_rt::test]
 async fn create_index_and_update_indentifier_after() {
     let mut server = common::Server::with_uid("movies");
 
-    // 1 - Create the index with no primary_key
+    // 1 - Create the index with a primary_key
 
     let body = json!({
         "uid": "movies",
@@ -568,20 +568,60 @@ async fn create_index_and_update_indentifier_after() {
     assert_eq!(status_code, 201);
     assert_eq!(response["primaryKey"].as_str().unwrap(), "id");
 
-    // 2 - Update the index and add an primary_key.
+    // 2 - Update the index with another primary_key
 
     let body = json!({
         "primaryKey": "skuid",
     });
 
-    let (_response, status_code) = server.update_index(body).await;
-    assert_eq!(status_code, 400);
+    let (response, status_code) = server.update_index(body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
 
-    // 3 - Get index to verify if the primary_key still the first one
+    // 3 - Get index to verify that the primary_key has been changed
 
     let (response, status_code) = server.get_index().await;
     assert_eq!(status_code, 200);
-    assert_eq!(response["primaryKey"].as_str().unwrap(), "id");
+    assert_eq!(response["primaryKey"].as_str().unwrap(), "skuid");
+}
+
+// Test that the primary_key of an index containing documents can be changed
+#[actix_rt::test]
+async fn change_primary_key_of_non_empty_index() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({
+        "uid": "movies",
+        "primaryKey": "id",
+    });
+    server.create_index(body).await;
+
+    let documents = json!([
+        { "id": 1, "sku": "abc", "title": "Carol" },
+        { "id": 2, "sku": "def", "title": "Wonder Woman" },
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let (response, status_code) = server.update_index(json!({ "primaryKey": "sku" })).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+
+    let (response, status_code) = server.get_index().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["primaryKey"], "sku");
+
+    let (response, status_code) = server.get_document("def").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["title"], "Wonder Woman");
+
+    let (_response, status_code) = server.get_document(1).await;
+    assert_eq!(status_code, 404);
 }
 
 // Test that schema inference work well
@@ -767,22 +807,25 @@ async fn udpate_unexisting_index_is_error() {
 }
 
 #[actix_rt::test]
-async fn update_existing_primary_key_is_error() {
+async fn update_existing_primary_key_with_same_value_is_noop() {
     let mut server = common::Server::with_uid("test");
     server
         .create_index(json!({ "uid": "test", "primaryKey": "key" }))
         .await;
-    let (response, status) = server.update_index(json!({ "primaryKey": "test2" })).await;
-    assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert_eq!(response["errorCode"], "primary_key_already_present");
-    assert_eq!(response["errorType"], "invalid_request_error");
+    let (response, status) = server.update_index(json!({ "primaryKey": "key" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["primaryKey"], "key");
 }
 
 #[actix_rt::test]
 async fn test_facets_distribution_attribute() {
     let mut server = common::Server::test_server().await;
 
-    let (response, _status_code) = server.get_index_stats().await;
+    let (mut response, _status_code) = server.get_index_stats().await;
+
+    // the size depends on the encoding of the stores, it is only checked to be computed
+    let size = response.as_object_mut().unwrap().remove("size").unwrap();
+    assert!(size.as_u64().unwrap() > 0);
 
     let expected = json!({
         "isIndexing": false,
@@ -806,4 +849,156 @@ async fn test_facets_distribution_attribute() {
     });
 
     assert_json_eq!(expected, response, ordered: true);
-}
\ No newline at end of file
+}
+
+#[actix_rt::test]
+async fn export_and_restore_index_under_new_uid() {
+    let mut server = common::Server::test_server().await;
+
+    let (archive, status_code) = server.get_raw_request("/indexes/test/export").await;
+    assert_eq!(status_code, 200);
+
+    let (response, status_code) = server.post_raw_request("/indexes/restored/restore", archive.clone()).await;
+    assert_eq!(status_code, 201);
+    assert_eq!(response["uid"], "restored");
+    assert_eq!(response["primaryKey"], "id");
+
+    let (response, status_code) = server.get_request("/indexes/restored/documents/1").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["id"], 1);
+
+    let (response, _) = server.get_request("/indexes/restored/settings/searchable-attributes").await;
+    let (expected, _) = server.get_request("/indexes/test/settings/searchable-attributes").await;
+    assert_eq!(response, expected);
+
+    // the index is never overwritten
+    let (response, status_code) = server.post_raw_request("/indexes/test/restore", archive).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "index_already_exists");
+
+    let (_, status_code) = server.post_raw_request("/indexes/broken/restore", b"not an archive".to_vec()).await;
+    assert_eq!(status_code, 400);
+    let (_, status_code) = server.get_request("/indexes/broken").await;
+    assert_eq!(status_code, 404);
+}
+
+/// An archive with the layout of an index export whose index has the given uid.
+fn index_archive(index_uid: &str) -> Vec<u8> {
+    let metadata = json!({
+        "indexes": [{
+            "name": "evil",
+            "uid": index_uid,
+            "createdAt": "2020-01-01T00:00:00Z",
+            "updatedAt": "2020-01-01T00:00:00Z",
+            "primaryKey": "id",
+        }],
+        "dbVersion": "0.15.0",
+        "dumpVersion": "V1",
+    });
+    let metadata = serde_json::to_vec(&metadata).unwrap();
+
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(metadata.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, "metadata.json", metadata.as_slice()).unwrap();
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+#[actix_rt::test]
+async fn restore_index_checks_the_archive() {
+    let mut server = common::Server::with_options("test", |opt| opt.http_restore_payload_size_limit = Some(4096));
+
+    // the uid of the archived index names its folder in the archive
+    let (response, status_code) = server.post_raw_request("/indexes/evil/restore", index_archive("../../evil")).await;
+    assert_eq!(status_code, 400);
+    assert!(response["message"].as_str().unwrap().contains("invalid"));
+    let (_, status_code) = server.get_request("/indexes/evil").await;
+    assert_eq!(status_code, 404);
+
+    let (_, status_code) = server.post_raw_request("/indexes/evil/restore", vec![0; 8192]).await;
+    assert_eq!(status_code, 413);
+}
+
+#[actix_rt::test]
+async fn stats_report_capacity() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.get_request("/stats").await;
+    assert_eq!(status_code, 200);
+    let main = &response["capacity"]["maps"][0];
+    assert_eq!(main["name"], "main");
+    assert!(main["usedBytes"].as_u64().unwrap() > 0);
+    assert!(main["usedBytes"].as_u64().unwrap() <= main["mapSizeBytes"].as_u64().unwrap());
+    assert_eq!(response["capacity"]["maps"][1]["name"], "update");
+}
+
+#[actix_rt::test]
+async fn swap_indexes() {
+    let mut server = common::Server::with_uid("products");
+    server.create_index(json!({ "uid": "products", "primaryKey": "id" })).await;
+    server.create_index(json!({ "uid": "products_new", "primaryKey": "id", "name": "Products" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "old" }])).await;
+    server.post_request_async("/indexes/products_new/documents", json!([{ "id": 1, "title": "new" }])).await;
+
+    let (response, status_code) = server.post_request("/swap-indexes", json!({ "indexes": ["products", "products_new"] })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response.as_array().unwrap().len(), 2);
+
+    let (response, _) = server.get_request("/indexes/products/documents/1").await;
+    assert_eq!(response["title"], "new");
+    let (response, _) = server.get_request("/indexes/products_new/documents/1").await;
+    assert_eq!(response["title"], "old");
+
+    // the custom names stay with the indexes, the default ones follow the uids
+    let (response, _) = server.get_request("/indexes/products").await;
+    assert_eq!(response["name"], "Products");
+    let (response, _) = server.get_request("/indexes/products_new").await;
+    assert_eq!(response["name"], "products_new");
+
+    // the updates are made on the swapped index
+    server.add_or_replace_multiple_documents(json!([{ "id": 2, "title": "new" }])).await;
+    let (response, _) = server.get_request("/indexes/products/documents/2").await;
+    assert_eq!(response["title"], "new");
+
+    let (_, status_code) = server.post_request("/swap-indexes", json!({ "indexes": ["products", "unknown"] })).await;
+    assert_eq!(status_code, 404);
+    let (_, status_code) = server.post_request("/swap-indexes", json!({ "indexes": ["products"] })).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn clone_index() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "searchableAttributes": ["title"], "synonyms": { "film": ["movie"] } })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Carol" },
+        { "id": 2, "title": "Mad Max" },
+    ])).await;
+
+    let (response, status_code) = server.post_request("/indexes/movies/clone", json!({ "uid": "movies_copy" })).await;
+    assert_eq!(status_code, 202);
+    assert_eq!(response["uid"], "movies_copy");
+    assert_eq!(response["name"], "movies_copy");
+    assert_eq!(response["primaryKey"], "id");
+    server.uid = "movies_copy".to_string();
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, _) = server.get_request("/indexes/movies_copy/documents/2").await;
+    assert_eq!(response["title"], "Mad Max");
+    let (response, _) = server.get_request("/indexes/movies_copy/documents").await;
+    assert_eq!(response.as_array().unwrap().len(), 2);
+    let (response, _) = server.get_request("/indexes/movies_copy/settings").await;
+    assert_eq!(response["searchableAttributes"], json!(["title"]));
+    assert_eq!(response["synonyms"], json!({ "film": ["movie"] }));
+
+    let (_, status_code) = server.post_request("/indexes/movies/clone", json!({ "uid": "movies_copy" })).await;
+    assert_eq!(status_code, 400);
+    let (_, status_code) = server.post_request("/indexes/movies/clone", json!({ "uid": "movies copy" })).await;
+    assert_eq!(status_code, 400);
+    let (_, status_code) = server.post_request("/indexes/unknown/clone", json!({ "uid": "unknown_copy" })).await;
+    assert_eq!(status_code, 404);
+}
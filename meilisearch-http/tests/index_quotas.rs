@@ -0,0 +1,36 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn updates_exceeding_the_quotas_fail() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    let (response, status) = server.get_request("/indexes/movies/quotas").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response, json!({ "maxDocuments": null, "maxSize": null }));
+
+    let (response, status) = server.put_request("/indexes/movies/quotas", json!({ "maxDocuments": 2 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response, json!({ "maxDocuments": 2, "maxSize": null }));
+
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    let documents = json!([{ "id": 2, "title": "Wonder Woman" }, { "id": 3, "title": "Joker" }]);
+    let (response, _) = server.post_request("/indexes/movies/documents", documents).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+    let (response, _) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "failed");
+    assert_eq!(response["errorCode"], "index_quota_exceeded");
+
+    let (response, _) = server.get_all_documents().await;
+    assert_eq!(response.as_array().unwrap().len(), 1);
+
+    let (_, status) = server.put_request("/indexes/movies/quotas", json!({ "maxFiles": 2 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, status) = server.put_request("/indexes/unknown/quotas", json!({ "maxDocuments": 2 })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
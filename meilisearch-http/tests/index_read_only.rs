@@ -0,0 +1,42 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn read_only_index_rejects_the_writes() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    let (response, status) = server.get_request("/indexes/movies/read-only").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response, json!({ "readOnly": false }));
+
+    let (response, status) = server.put_request("/indexes/movies/read-only", json!({ "readOnly": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response, json!({ "readOnly": true }));
+
+    let (response, status) = server.add_or_replace_multiple_documents_sync(json!([{ "id": 2, "title": "Wonder Woman" }])).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(response["errorCode"], "index_read_only");
+    let (_, status) = server.post_request("/indexes/movies/settings", json!({ "stopWords": ["the"] })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (_, status) = server.update_index(json!({ "name": "Films" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (_, status) = server.delete_index().await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // the index is still searchable
+    let (response, status) = server.search_post(json!({ "q": "carol" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+
+    server.put_request("/indexes/movies/read-only", json!({ "readOnly": false })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 2, "title": "Wonder Woman" }])).await;
+    let (response, _) = server.get_all_documents().await;
+    assert_eq!(response.as_array().unwrap().len(), 2);
+
+    let (_, status) = server.put_request("/indexes/unknown/read-only", json!({ "readOnly": true })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
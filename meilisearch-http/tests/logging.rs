@@ -0,0 +1,53 @@
+use actix_web::test;
+use meilisearch_http::helpers::logging::{self, LogFormat, RequestLogger};
+use serde_json::json;
+use meilisearch_http::helpers::NormalizePath;
+
+mod common;
+
+#[actix_rt::test]
+async fn request_id_is_sent_back() {
+    let server = common::Server::with_uid("movies");
+    let mut app = test::init_service(
+        meilisearch_http::create_app(&server.data)
+            .wrap(RequestLogger)
+            .wrap(NormalizePath),
+    )
+    .await;
+
+    // the id given by a proxy is kept
+    let req = test::TestRequest::get()
+        .uri("/version")
+        .header("X-Request-Id", "proxy-42")
+        .to_request();
+    let res = test::call_service(&mut app, req).await;
+    assert_eq!(res.headers()["x-request-id"], "proxy-42");
+
+    let req = test::TestRequest::get().uri("/version").to_request();
+    let res = test::call_service(&mut app, req).await;
+    assert_eq!(res.headers()["x-request-id"].len(), 32);
+}
+
+#[actix_rt::test]
+async fn change_log_level_at_runtime() {
+    logging::init_logger(LogFormat::Text);
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "filters": "warn,meilisearch_core=trace" });
+    let (response, status_code) = server.put_request("/debug/log-level", body.clone()).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, body);
+    assert!(log::log_enabled!(target: "meilisearch_core", log::Level::Trace));
+    assert!(!log::log_enabled!(target: "meilisearch_http", log::Level::Info));
+
+    let (response, status_code) = server.get_request("/debug/log-level").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, body);
+
+    let body = json!({ "filters": "meilisearch_core=loud" });
+    let (_, status_code) = server.put_request("/debug/log-level", body).await;
+    assert_eq!(status_code, 400);
+
+    let (response, _) = server.get_request("/debug/log-level").await;
+    assert_eq!(response["filters"], "warn,meilisearch_core=trace");
+}
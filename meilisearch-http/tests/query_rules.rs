@@ -0,0 +1,106 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect()
+}
+
+async fn movies_server() -> common::Server {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Star Wars", "genre": "scifi" },
+        { "id": 2, "title": "Star Trek", "genre": "scifi" },
+        { "id": 3, "title": "A Star Is Born", "genre": "drama" },
+        { "id": 4, "title": "Stardust", "genre": "fantasy" },
+    ])).await;
+    server
+}
+
+#[actix_rt::test]
+async fn pinned_and_hidden_documents() {
+    let mut server = movies_server().await;
+    server.post_request_async("/indexes/movies/settings/query-rules", json!([{
+        "id": "star-promo",
+        "condition": { "is": "star" },
+        "pinned": ["4", "3", "404"],
+        "hidden": ["2"],
+        "banner": { "text": "May the force be with you" },
+    }])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "Star" })).await;
+    assert_eq!(&hit_ids(&response)[..2], &[4, 3]);
+    assert!(!hit_ids(&response).contains(&2));
+    // the pinned documents matching the query are counted once, the hidden one like any filtered document
+    assert_eq!(response["nbHits"], 4);
+    assert_eq!(response["banners"], json!([{ "text": "May the force be with you" }]));
+
+    // the pinned documents are part of the pagination
+    let (response, _) = server.search_post(json!({ "q": "star", "offset": 1, "limit": 2 })).await;
+    assert_eq!(hit_ids(&response), vec![3, 1]);
+
+    // the pinned documents must match the filters
+    let (response, _) = server.search_post(json!({ "q": "star", "filters": "genre = scifi" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    // the pinned documents must match the facet filters
+    server.update_all_settings(json!({ "attributesForFaceting": ["genre"] })).await;
+    let (response, _) = server.search_post(json!({ "q": "star", "facetFilters": ["genre:scifi"] })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+    let (response, _) = server.search_post(json!({ "q": "star", "facetFilters": ["genre:drama"] })).await;
+    assert_eq!(hit_ids(&response), vec![3]);
+    assert_eq!(response["nbHits"], 1);
+
+    // the other queries are left untouched
+    let (response, _) = server.search_post(json!({ "q": "star trek" })).await;
+    assert_eq!(hit_ids(&response)[0], 2);
+    assert!(response.get("banners").is_none());
+
+    let (response, _) = server.get_request("/indexes/movies/settings").await;
+    assert_eq!(response["queryRules"][0]["id"], "star-promo");
+
+    server.delete_request_async("/indexes/movies/settings/query-rules").await;
+    let (response, _) = server.get_request("/indexes/movies/settings/query-rules").await;
+    assert_eq!(response, json!([]));
+    let (response, _) = server.search_post(json!({ "q": "star" })).await;
+    assert!(hit_ids(&response).contains(&2));
+}
+
+#[actix_rt::test]
+async fn contains_and_pattern_conditions() {
+    let mut server = movies_server().await;
+    server.post_request_async("/indexes/movies/settings/query-rules", json!([
+        { "condition": { "contains": "born" }, "banner": "musicals" },
+        { "condition": { "pattern": "^star\\s+w" }, "pinned": ["4"] },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "a star is  BORN" })).await;
+    assert_eq!(response["banners"], json!(["musicals"]));
+    let (response, _) = server.search_post(json!({ "q": "stubborn" })).await;
+    assert!(response.get("banners").is_none());
+
+    let (response, _) = server.search_post(json!({ "q": "Star Wars" })).await;
+    assert_eq!(hit_ids(&response)[0], 4);
+}
+
+#[actix_rt::test]
+async fn invalid_query_rules() {
+    let mut server = movies_server().await;
+
+    let (response, status_code) = server.post_request("/indexes/movies/settings/query-rules", json!([
+        { "condition": { "pattern": "(" }, "pinned": ["1"] },
+    ])).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+
+    let (_, status_code) = server.post_request("/indexes/movies/settings/query-rules", json!([
+        { "condition": { "is": "star" } },
+    ])).await;
+    assert_eq!(status_code, 400);
+
+    let (_, status_code) = server.post_request("/indexes/movies/settings", json!({
+        "queryRules": [{ "condition": { "contains": " " }, "hidden": ["1"] }],
+    })).await;
+    assert_eq!(status_code, 400);
+}
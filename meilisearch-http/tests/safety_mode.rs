@@ -0,0 +1,39 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn writes_are_refused_when_the_storage_is_almost_full() {
+    // a free space that can't be reached, the safety mode is enabled on the first check
+    let mut server = common::Server::with_options("movies", |opt| opt.safety_mode_min_free_space = Some(u64::MAX));
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+    server.data.capacity.check();
+
+    let (response, status) = server.add_or_replace_multiple_documents_sync(json!([{ "id": 2, "title": "Wonder Woman" }])).await;
+    assert_eq!(status, StatusCode::INSUFFICIENT_STORAGE);
+    assert_eq!(response["errorCode"], "insufficient_storage");
+    let (_, status) = server.post_request("/indexes/movies/settings", json!({ "stopWords": ["the"] })).await;
+    assert_eq!(status, StatusCode::INSUFFICIENT_STORAGE);
+
+    // the updates enqueued out of the routes, by the importers or the scheduled jobs, are refused as well
+    let index = server.data.db.open_index("movies").unwrap();
+    let mut addition = index.documents_addition::<serde_json::Value>();
+    addition.update_document(json!({ "id": 3, "title": "Mulan" }));
+    let result = server.data.db.update_write(|writer| addition.finalize(writer));
+    assert!(matches!(result, Err(meilisearch_core::Error::InsufficientStorage)));
+
+    let (response, status) = server.get_request("/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["status"], "safetyMode");
+
+    let (response, status) = server.get_request("/stats").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["capacity"]["safetyMode"], true);
+
+    // the searches are still served
+    let (response, status) = server.search_post(json!({ "q": "carol" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+}
@@ -1696,3 +1696,44 @@ async fn update_documents_with_facet_distribution() {
     let (response2, _) = server.search_post(search).await;
     assert_json_eq!(expected_facet_distribution, response2["facetsDistribution"].clone());
 }
+
+#[actix_rt::test]
+async fn search_analytics() {
+    let mut server = common::Server::test_server().await;
+
+    server.search_post(json!({ "q": "exercitation" })).await;
+    server.search_post(json!({ "q": "Exercitation " })).await;
+    server.search_post(json!({ "q": "zzzzqqqq" })).await;
+    server.search_post(json!({})).await;
+
+    let (response, status_code) = server.get_request("/indexes/test/analytics?window=1h&limit=1").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["window"], "1h");
+    assert_eq!(response["totalQueries"], 4);
+    assert_eq!(response["placeholderQueries"], 1);
+    assert_eq!(response["zeroResultQueries"], 1);
+    assert_json_eq!(response["topQueries"].clone(), json!([{ "query": "exercitation", "count": 2 }]));
+    assert_json_eq!(response["topZeroResultQueries"].clone(), json!([{ "query": "zzzzqqqq", "count": 1 }]));
+    assert!(response["latencyMs"]["p50"].is_number());
+
+    let (_, status_code) = server.get_request("/indexes/test/analytics?window=2h").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_profile() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.search_post(json!({ "q": "exercitation", "limit": 3, "profile": true })).await;
+    assert_eq!(status_code, 200);
+    let profile = &response["profile"];
+    assert!(profile["queryTreeMs"].is_number());
+    assert!(profile["formattingMs"].is_number());
+    assert_eq!(profile["candidates"], response["nbHits"]);
+    let rules: Vec<_> = profile["rankingRules"].as_array().unwrap().iter().map(|rule| rule["name"].clone()).collect();
+    assert_eq!(rules.first(), Some(&json!("typo")));
+    assert_eq!(profile["rankingRules"][0]["sortedDocuments"], response["nbHits"]);
+
+    let (response, _) = server.search_get("q=exercitation").await;
+    assert!(response.get("profile").is_none());
+}
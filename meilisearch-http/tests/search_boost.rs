@@ -0,0 +1,37 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect()
+}
+
+#[actix_rt::test]
+async fn boosted_facet_values_break_ties() {
+    let mut server = common::Server::with_uid("products");
+    server.create_index(json!({ "uid": "products", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["brand"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "name": "phone", "brand": "acme" },
+        { "id": 2, "name": "phone", "brand": "volt" },
+        { "id": 3, "name": "phone", "brand": "Zen" },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "phone" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2, 3]);
+
+    let (response, status_code) = server.search_post(json!({
+        "q": "phone",
+        "boost": { "brand": { "zen": 2, "volt": 1.5, "acme": 0.5 } },
+    })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hit_ids(&response), vec![3, 2, 1]);
+
+    let (response, _) = server.search_get("q=phone&boost=%7B%22brand%22%3A%7B%22volt%22%3A3%7D%7D").await;
+    assert_eq!(hit_ids(&response)[0], 2);
+
+    let (_, status_code) = server.search_post(json!({ "q": "phone", "boost": { "name": { "phone": 2 } } })).await;
+    assert_eq!(status_code, 400);
+    let (_, status_code) = server.search_post(json!({ "q": "phone", "boost": { "brand": { "volt": -1 } } })).await;
+    assert_eq!(status_code, 400);
+}
@@ -0,0 +1,44 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect()
+}
+
+#[actix_rt::test]
+async fn filter_and_sort_dates() {
+    let mut server = common::Server::with_uid("posts");
+    server.create_index(json!({ "uid": "posts", "primaryKey": "id" })).await;
+
+    let yesterday = (Utc::now() - Duration::days(1)).to_rfc3339();
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "First post", "created_at": "2022-06-15" },
+        { "id": 2, "title": "Second post", "created_at": "2023-01-01T10:30:00Z" },
+        { "id": 3, "title": "Third post", "created_at": "2023-03-02 08:00:00" },
+        { "id": 4, "title": "Latest post", "created_at": yesterday },
+        // the numbers are compared as timestamps in seconds
+        { "id": 5, "title": "Imported post", "created_at": 1_640_995_200 },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "post", "filters": "created_at >= \"2023-01-01\"" })).await;
+    let mut ids = hit_ids(&response);
+    ids.sort_unstable();
+    assert_eq!(ids, vec![2, 3, 4]);
+
+    let (response, _) = server.search_post(json!({ "q": "post", "filters": "created_at < 2022-12-31" })).await;
+    let mut ids = hit_ids(&response);
+    ids.sort_unstable();
+    assert_eq!(ids, vec![1, 5]);
+
+    // the relative dates are computed when the filter is parsed, their documents are never cached
+    for _ in 0..3 {
+        let (response, _) = server.search_post(json!({ "q": "post", "filters": "created_at >= NOW-7d" })).await;
+        assert_eq!(hit_ids(&response), vec![4]);
+    }
+
+    server.update_ranking_rules(json!(["desc(created_at)", "typo", "words", "proximity", "attribute", "wordsPosition", "exactness"])).await;
+    let (response, _) = server.search_post(json!({ "q": "post" })).await;
+    assert_eq!(hit_ids(&response), vec![4, 3, 2, 1, 5]);
+}
@@ -0,0 +1,53 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<u64> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[actix_rt::test]
+async fn array_filters() {
+    let mut server = common::Server::with_uid("crates");
+    server.create_index(json!({ "uid": "crates", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["tags"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "name": "Tantivy engine", "tags": ["rust", "search"], "language": "Rust" },
+        { "id": 2, "name": "Tokio engine", "tags": ["rust", "async"], "language": "Rust" },
+        { "id": 3, "name": "Lucene engine", "tags": ["java", "search"], "language": "Java" },
+        { "id": 4, "name": "Bleve engine", "tags": "search", "language": "Go" },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "engine", "filters": "tags IN [async, java]" })).await;
+    assert_eq!(hit_ids(&response), vec![2, 3]);
+
+    let (response, _) = server.search_post(json!({ "q": "engine", "filters": "language IN [go, java]" })).await;
+    assert_eq!(hit_ids(&response), vec![3, 4]);
+
+    let (response, _) = server.search_post(json!({ "q": "engine", "filters": "tags ANY [async]" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+
+    let (response, _) = server.search_post(json!({ "q": "engine", "filters": "tags ALL [\"rust\", \"search\"]" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    // a single value is an array of one element
+    let (response, _) = server.search_post(json!({ "q": "engine", "filters": "tags ALL [search]" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 3, 4]);
+
+    let (response, _) = server.search_post(json!({ "q": "engine", "filters": "tags CONTAINS RUST" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+
+    let (response, _) = server.search_post(json!({ "q": "engine", "filters": "name CONTAINS tok" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+
+    // a document is counted once for each of its values, before the filters are applied
+    let (response, _) = server.search_post(json!({
+        "q": "engine",
+        "filters": "tags CONTAINS search",
+        "facetsDistribution": ["tags"],
+    })).await;
+    assert_eq!(hit_ids(&response), vec![1, 3, 4]);
+    assert_eq!(response["facetsDistribution"]["tags"], json!({ "search": 3, "rust": 2, "java": 1, "async": 1 }));
+}
@@ -0,0 +1,33 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<u64> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[actix_rt::test]
+async fn cached_filters_follow_the_updates() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Alien", "genre": "horror" },
+        { "id": 2, "title": "Amelie", "genre": "romance" },
+        { "id": 3, "title": "Scream", "genre": "horror" },
+    ])).await;
+
+    // the documents of the filter are cached from its second use
+    for _ in 0..3 {
+        let (response, _) = server.search_post(json!({ "q": "", "filters": "genre = horror" })).await;
+        assert_eq!(hit_ids(&response), vec![1, 3]);
+    }
+
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 2, "title": "Amelie", "genre": "horror" },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "", "filters": "genre = horror" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2, 3]);
+}
@@ -0,0 +1,41 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<u64> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[actix_rt::test]
+async fn boolean_and_existence_filters() {
+    let mut server = common::Server::with_uid("articles");
+    server.create_index(json!({ "uid": "articles", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Draft article", "is_published": false, "subtitle": "" },
+        { "id": 2, "title": "Published article", "is_published": true, "subtitle": "About filters", "tags": [] },
+        { "id": 3, "title": "Old article", "is_published": true, "subtitle": null, "tags": ["archive"] },
+        { "id": 4, "title": "Imported article" },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "article", "filters": "is_published = true" })).await;
+    assert_eq!(hit_ids(&response), vec![2, 3]);
+
+    // the attributes set to null exist
+    let (response, _) = server.search_post(json!({ "q": "article", "filters": "EXISTS subtitle" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2, 3]);
+
+    let (response, _) = server.search_post(json!({ "q": "article", "filters": "NOT EXISTS is_published" })).await;
+    assert_eq!(hit_ids(&response), vec![4]);
+
+    let (response, _) = server.search_post(json!({ "q": "article", "filters": "subtitle IS NULL" })).await;
+    assert_eq!(hit_ids(&response), vec![3]);
+
+    let (response, _) = server.search_post(json!({ "q": "article", "filters": "subtitle IS EMPTY OR tags IS EMPTY" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+
+    let (response, status_code) = server.search_post(json!({ "q": "article", "filters": "EXISTS unknown" })).await;
+    assert_eq!(status_code, 400);
+    assert!(response["message"].as_str().unwrap().contains("attribute `unknown` not found"));
+}
@@ -0,0 +1,54 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect()
+}
+
+#[actix_rt::test]
+async fn sort_by_geo_distance() {
+    let mut server = common::Server::with_uid("cafes");
+    server.create_index(json!({ "uid": "cafes", "primaryKey": "id" })).await;
+    server.update_ranking_rules(json!(["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(rating)"])).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Paris cafe", "rating": 4, "_geo": { "lat": 48.8566, "lng": 2.3522 } },
+        { "id": 2, "title": "Lyon cafe", "rating": 5, "_geo": { "lat": 45.764, "lng": 4.8357 } },
+        { "id": 3, "title": "London cafe", "rating": 4, "_geo": { "lat": "51.5074", "lng": "-0.1278" } },
+        { "id": 4, "title": "Berlin cafe", "rating": 5, "_geo": { "lat": 52.52, "lng": 13.405 } },
+        { "id": 5, "title": "Online cafe", "rating": 3 },
+    ])).await;
+
+    // the documents without location come last
+    let (response, status_code) = server.search_post(json!({ "q": "", "sort": ["_geoPoint(48.8566,2.3522):asc"] })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hit_ids(&response), vec![1, 3, 2, 4, 5]);
+
+    let (response, _) = server.search_post(json!({ "q": "cafe", "sort": ["_geoPoint(48.8566, 2.3522):desc"] })).await;
+    assert_eq!(hit_ids(&response), vec![4, 2, 3, 1, 5]);
+
+    // the next sort rules and the ranking rules order the documents at the same distance
+    let (response, _) = server.search_post(json!({ "q": "cafe", "sort": ["rating:desc", "_geoPoint(48.8566,2.3522):asc"] })).await;
+    assert_eq!(hit_ids(&response), vec![2, 4, 1, 3, 5]);
+
+    let (response, _) = server.search_post(json!({ "q": "", "sort": ["rating:desc", "_geoPoint(48.8566,2.3522):asc"] })).await;
+    assert_eq!(hit_ids(&response), vec![2, 4, 1, 3, 5]);
+
+    let (_, status_code) = server.search_post(json!({ "q": "cafe", "sort": ["_geoPoint(100,2.3522):asc"] })).await;
+    assert_eq!(status_code, 400);
+
+    let (_, status_code) = server.search_post(json!({ "q": "cafe", "sort": ["_geoPoint(48.8566,2.3522)"] })).await;
+    assert_eq!(status_code, 400);
+
+    // the attributes must be ranked to be sorted
+    let (_, status_code) = server.search_post(json!({ "q": "cafe", "sort": ["title:asc"] })).await;
+    assert_eq!(status_code, 400);
+    let (_, status_code) = server.search_post(json!({ "q": "cafe", "sort": ["unknown:asc"] })).await;
+    assert_eq!(status_code, 400);
+
+    // the locations follow the updates and the deletions of the documents
+    server.add_or_update_multiple_documents(json!([{ "id": 4, "_geo": { "lat": 48.86, "lng": 2.35 } }])).await;
+    server.delete_document(1).await;
+    let (response, _) = server.search_post(json!({ "q": "", "sort": ["_geoPoint(48.8566,2.3522):asc"] })).await;
+    assert_eq!(hit_ids(&response), vec![4, 3, 2, 5]);
+}
@@ -0,0 +1,42 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<u64> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn formatted_title(response: &serde_json::Value, id: u64) -> &str {
+    let hit = response["hits"].as_array().unwrap().iter().find(|hit| hit["id"] == id).unwrap();
+    hit["_formatted"]["title"].as_str().unwrap()
+}
+
+#[actix_rt::test]
+async fn arabic_and_hebrew_vowel_marks() {
+    let mut server = common::Server::with_uid("books");
+    server.create_index(json!({ "uid": "books", "primaryKey": "id" })).await;
+    server.post_request_async("/indexes/books/settings/normalization", json!({ "stripVowelMarks": true })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "كَتَبَ الطالبُ، الدرسَ" },
+        { "id": 2, "title": "كتب جديدة" },
+        { "id": 3, "title": "בֵּית־סֵפֶר גדול" },
+    ])).await;
+
+    // the unvocalized words match the vocalized ones
+    let (response, _) = server.search_post(json!({ "q": "كتب", "attributesToHighlight": ["title"] })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+    // the highlights cover the vowel marks of the words
+    assert_eq!(formatted_title(&response, 1), "<em>كَتَبَ</em> الطالبُ، الدرسَ");
+    assert_eq!(formatted_title(&response, 2), "<em>كتب</em> جديدة");
+
+    let (response, _) = server.search_post(json!({ "q": "الطالب", "attributesToHighlight": ["title"] })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+    assert_eq!(formatted_title(&response, 1), "كَتَبَ <em>الطالبُ</em>، الدرسَ");
+
+    // the maqaf separates the words
+    let (response, _) = server.search_post(json!({ "q": "ספר", "attributesToHighlight": ["title"] })).await;
+    assert_eq!(hit_ids(&response), vec![3]);
+    assert_eq!(formatted_title(&response, 3), "בֵּית־<em>סֵפֶר</em> גדול");
+}
@@ -43,11 +43,25 @@ async fn write_all_and_delete() {
             "in",
             "ut",
         ],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {
             "road": ["street", "avenue"],
             "street": ["avenue"],
         },
         "attributesForFaceting": ["name"],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     server.update_all_settings(body.clone()).await;
@@ -79,8 +93,22 @@ async fn write_all_and_delete() {
         "searchableAttributes": ["*"],
         "displayedAttributes": ["*"],
         "stopWords": [],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {},
         "attributesForFaceting": [],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     assert_json_eq!(expect, response, ordered: false);
@@ -127,11 +155,25 @@ async fn write_all_and_update() {
             "in",
             "ut",
         ],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {
             "road": ["street", "avenue"],
             "street": ["avenue"],
         },
         "attributesForFaceting": ["name"],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     server.update_all_settings(body.clone()).await;
@@ -168,11 +210,25 @@ async fn write_all_and_update() {
             "picture",
         ],
         "stopWords": [],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {
             "road": ["street", "avenue"],
             "street": ["avenue"],
         },
         "attributesForFaceting": ["title"],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     server.update_all_settings(body).await;
@@ -205,11 +261,25 @@ async fn write_all_and_update() {
             "picture",
         ],
         "stopWords": [],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {
             "road": ["street", "avenue"],
             "street": ["avenue"],
         },
         "attributesForFaceting": ["title"],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     assert_json_eq!(expected, response, ordered: false);
@@ -238,8 +308,22 @@ async fn test_default_settings() {
         "searchableAttributes": ["*"],
         "displayedAttributes": ["*"],
         "stopWords": [],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {},
         "attributesForFaceting": [],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -271,8 +355,22 @@ async fn test_default_settings_2() {
         "searchableAttributes": ["*"],
         "displayedAttributes": ["*"],
         "stopWords": [],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {},
         "attributesForFaceting": [],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -332,6 +430,9 @@ async fn write_setting_and_update_partial() {
             "in",
             "ut",
         ],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {
             "road": ["street", "avenue"],
             "street": ["avenue"],
@@ -376,11 +477,25 @@ async fn write_setting_and_update_partial() {
             "in",
             "ut",
         ],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {
             "road": ["street", "avenue"],
             "street": ["avenue"],
         },
         "attributesForFaceting": [],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -508,11 +623,25 @@ async fn test_displayed_attributes_field() {
             "in",
             "ut",
         ],
+        "separatorTokens": [],
+        "nonSeparatorTokens": [],
+        "dictionary": [],
         "synonyms": {
             "road": ["avenue", "street"],
             "street": ["avenue"],
         },
         "attributesForFaceting": ["name"],
+        "faceting": {
+            "maxValuesPerFacet": 100,
+            "sortFacetValuesBy": { "*": "alpha" },
+        },
+        "queryRules": [],
+        "typoTolerance": { "disableOnNumbers": false },
+        "rankingBehavior": { "maxProximity": 8, "exactness": "attribute" },
+        "normalization": { "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false },
+        "languages": [],
+        "attributeTokenizers": {},
+        "identifierSplitting": false,
     });
 
     server.update_all_settings(body.clone()).await;
@@ -520,4 +649,40 @@ async fn test_displayed_attributes_field() {
     let (response, _status_code) = server.get_all_settings().await;
 
     assert_json_eq!(body, response, ordered: true);
-}
\ No newline at end of file
+}
+#[actix_rt::test]
+async fn replace_all_settings() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "rankingRules": ["typo", "words"], "stopWords": ["the"] })).await;
+
+    // the settings missing from the body are reset
+    let (mut settings, _) = server.get_all_settings().await;
+    settings["synonyms"] = json!({ "film": ["movie"] });
+    settings.as_object_mut().unwrap().remove("stopWords");
+
+    let (response, status_code) = server.put_request("/indexes/movies/settings", settings.clone()).await;
+    assert_eq!(status_code, 202);
+    assert_eq!(response["changes"], json!(["stopWords", "synonyms"]));
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, _) = server.get_all_settings().await;
+    assert_eq!(response["rankingRules"], json!(["typo", "words"]));
+    assert_eq!(response["stopWords"], json!([]));
+    assert_eq!(response["synonyms"], json!({ "film": ["movie"] }));
+
+    // the details of the update only contain the modified settings
+    let (response, status_code) = server.put_request("/indexes/movies/settings", response).await;
+    assert_eq!(status_code, 202);
+    assert_eq!(response["changes"], json!([]));
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+    let (response, _) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["type"]["settings"]["ranking_rules"], "Nothing");
+
+    let (_, status_code) = server.put_request("/indexes/movies/settings", json!({ "rankingRules": ["unknown"] })).await;
+    assert_eq!(status_code, 400);
+    let (_, status_code) = server.put_request("/indexes/movies/settings", json!({ "unknown": [] })).await;
+    assert_eq!(status_code, 400);
+}
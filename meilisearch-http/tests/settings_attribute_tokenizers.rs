@@ -0,0 +1,51 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<u64> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[actix_rt::test]
+async fn keyword_and_ngram_attributes() {
+    let mut server = common::Server::with_uid("parts");
+    server.create_index(json!({ "uid": "parts", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "sku": "ABC-123", "reference": "XJ9000", "title": "Red shoes" },
+        { "id": 2, "sku": "ABC-124", "reference": "ZK4411", "title": "Blue shoes" },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "123" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    server.post_request_async("/indexes/parts/settings/attribute-tokenizers", json!({
+        "sku": "keyword",
+        "reference": "ngram",
+    })).await;
+
+    let (response, _) = server.get_request("/indexes/parts/settings/attribute-tokenizers").await;
+    assert_eq!(response, json!({ "reference": "ngram", "sku": "keyword" }));
+
+    // the keyword values are only matched whole
+    let (response, _) = server.search_post(json!({ "q": "123" })).await;
+    assert_eq!(hit_ids(&response), Vec::<u64>::new());
+
+    let (response, _) = server.search_post(json!({ "q": "abc-123" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    // a part of a ngram value matches
+    let (response, _) = server.search_post(json!({ "q": "9000" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    let (response, _) = server.search_post(json!({ "q": "k44" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+
+    // the other attributes use the standard tokenizer
+    let (response, _) = server.search_post(json!({ "q": "shoes" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+
+    let (_, status_code) = server.post_request("/indexes/parts/settings/attribute-tokenizers", json!({ "sku": "exact" })).await;
+    assert_eq!(status_code, 400);
+}
@@ -0,0 +1,39 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort();
+    ids
+}
+
+#[actix_rt::test]
+async fn dictionary_words_are_single_tokens() {
+    let mut server = common::Server::with_uid("chemicals");
+    server.create_index(json!({ "uid": "chemicals", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "name": "Sodium chloride solution" },
+        { "id": 2, "name": "Chloride ions" },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "chloride" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+
+    server.post_request_async("/indexes/chemicals/settings/dictionary", json!(["sodium chloride"])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "chloride" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+    let (response, _) = server.search_post(json!({ "q": "Sodium Chloride" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    let (response, _) = server.get_request("/indexes/chemicals/settings/dictionary").await;
+    assert_eq!(response, json!(["sodium chloride"]));
+
+    server.delete_request_async("/indexes/chemicals/settings/dictionary").await;
+    let (response, _) = server.search_post(json!({ "q": "chloride" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+
+    let (_, status_code) = server.post_request("/indexes/chemicals/settings/dictionary", json!([" "])).await;
+    assert_eq!(status_code, 400);
+}
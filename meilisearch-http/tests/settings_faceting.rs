@@ -0,0 +1,50 @@
+use serde_json::json;
+
+mod common;
+
+fn facet_values(response: &serde_json::Value, facet: &str) -> Vec<String> {
+    response["facetsDistribution"][facet].as_object().unwrap().keys().cloned().collect()
+}
+
+#[actix_rt::test]
+async fn facet_values_order_and_limit() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["genre", "year"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "genre": "drama", "year": "2001" },
+        { "id": 2, "genre": "horror", "year": "2001" },
+        { "id": 3, "genre": "horror", "year": "1999" },
+        { "id": 4, "genre": "comedy", "year": "2010" },
+        { "id": 5, "genre": "horror", "year": "2010" },
+        { "id": 6, "genre": "drama", "year": "2010" },
+    ])).await;
+    let search = json!({ "q": "", "facetsDistribution": ["genre", "year"] });
+
+    // the values are in the alphabetical order by default
+    let (response, _) = server.search_post(search.clone()).await;
+    assert_eq!(facet_values(&response, "genre"), vec!["comedy", "drama", "horror"]);
+    assert_eq!(facet_values(&response, "year"), vec!["1999", "2001", "2010"]);
+
+    server.post_request_async("/indexes/movies/settings/faceting", json!({
+        "maxValuesPerFacet": 2,
+        "sortFacetValuesBy": { "*": "count", "year": "alpha" },
+    })).await;
+
+    let (response, _) = server.search_post(search.clone()).await;
+    assert_eq!(facet_values(&response, "genre"), vec!["horror", "drama"]);
+    assert_eq!(response["facetsDistribution"]["genre"]["horror"], 3);
+    assert_eq!(facet_values(&response, "year"), vec!["1999", "2001"]);
+
+    let (response, _) = server.get_request("/indexes/movies/settings").await;
+    assert_eq!(response["faceting"]["maxValuesPerFacet"], 2);
+
+    server.delete_request_async("/indexes/movies/settings/faceting").await;
+    let (response, _) = server.get_request("/indexes/movies/settings/faceting").await;
+    assert_eq!(response, json!({ "maxValuesPerFacet": 100, "sortFacetValuesBy": { "*": "alpha" } }));
+
+    let (_, status_code) = server.post_request("/indexes/movies/settings/faceting", json!({
+        "sortFacetValuesBy": { "genre": "random" },
+    })).await;
+    assert_eq!(status_code, 400);
+}
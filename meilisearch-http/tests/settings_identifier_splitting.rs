@@ -0,0 +1,39 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<u64> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[actix_rt::test]
+async fn camel_case_identifiers() {
+    let mut server = common::Server::with_uid("api");
+    server.create_index(json!({ "uid": "api", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "name": "getUserById", "description": "Returns a user" },
+        { "id": 2, "name": "delete_user_token", "description": "Revokes a token" },
+        { "id": 3, "name": "HTTPServer", "description": "Handles the requests" },
+    ])).await;
+
+    let (response, _) = server.get_request("/indexes/api/settings/identifier-splitting").await;
+    assert_eq!(response, json!(false));
+
+    // the camelCase identifiers are single words
+    let (response, _) = server.search_post(json!({ "q": "server" })).await;
+    assert_eq!(hit_ids(&response), Vec::<u64>::new());
+
+    server.post_request_async("/indexes/api/settings/identifier-splitting", json!(true)).await;
+
+    let (response, _) = server.search_post(json!({ "q": "server" })).await;
+    assert_eq!(hit_ids(&response), vec![3]);
+
+    let (response, _) = server.search_post(json!({ "q": "user by id" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    // the queries are split the same way
+    let (response, _) = server.search_post(json!({ "q": "deleteUserToken" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+}
@@ -0,0 +1,110 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<u64> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[actix_rt::test]
+async fn thai_segmentation() {
+    let mut server = common::Server::with_uid("products");
+    server.create_index(json!({ "uid": "products", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "ร้านอาหารไทยราคาถูก" },
+        { "id": 2, "title": "โทรศัพท์มือถือใหม่" },
+    ])).await;
+
+    let (response, _) = server.get_request("/indexes/products/settings/languages").await;
+    assert_eq!(response, json!([]));
+
+    // a run of Thai characters is a single word
+    let (response, _) = server.search_post(json!({ "q": "อาหาร" })).await;
+    assert_eq!(hit_ids(&response), Vec::<u64>::new());
+
+    // the documents are indexed again, word by word
+    server.post_request_async("/indexes/products/settings/languages", json!(["th"])).await;
+    let (response, _) = server.get_request("/indexes/products/settings").await;
+    assert_eq!(response["languages"], json!(["th"]));
+
+    let (response, _) = server.search_post(json!({ "q": "ราคา", "attributesToHighlight": ["title"] })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+    assert_eq!(response["hits"][0]["_formatted"]["title"], "ร้านอาหารไทย<em>ราคา</em>ถูก");
+
+    let (response, _) = server.search_post(json!({ "q": "มือถือ" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+
+    let (_, status_code) = server.post_request("/indexes/products/settings/languages", json!(["klingon"])).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn japanese_segmentation() {
+    let mut server = common::Server::with_uid("recipes");
+    server.create_index(json!({ "uid": "recipes", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "東京に住んでいます" },
+        { "id": 2, "title": "私はコーヒーが好きです" },
+        { "id": 3, "title": "ｺｰﾋｰ豆" },
+    ])).await;
+
+    server.post_request_async("/indexes/recipes/settings/languages", json!(["ja"])).await;
+    server.post_request_async("/indexes/recipes/settings/normalization", json!({ "kanaFolding": true })).await;
+
+    let (response, _) = server.search_post(json!({ "q": "住んでいます" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    // the katakana, in full or half width, match the hiragana with the same reading
+    let (response, _) = server.search_post(json!({ "q": "こーひー", "attributesToHighlight": ["title"] })).await;
+    assert_eq!(hit_ids(&response), vec![2, 3]);
+    let formatted: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["_formatted"]["title"].clone()).collect();
+    assert!(formatted.contains(&json!("私は<em>コーヒー</em>が好きです")));
+}
+
+#[actix_rt::test]
+async fn chinese_segmentation() {
+    let mut server = common::Server::with_uid("articles");
+    server.create_index(json!({ "uid": "articles", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "我喜欢吃中国菜" },
+        { "id": 2, "title": "中國的歷史" },
+        { "id": 3, "title": "国王" },
+    ])).await;
+
+    server.post_request_async("/indexes/articles/settings/languages", json!(["zh"])).await;
+    server.post_request_async("/indexes/articles/settings/normalization", json!({ "traditionalChineseFolding": true })).await;
+
+    // the simplified and the traditional characters match each other
+    let (response, _) = server.search_post(json!({ "q": "中國", "attributesToHighlight": ["title"] })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+    let formatted: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["_formatted"]["title"].clone()).collect();
+    assert!(formatted.contains(&json!("我喜欢吃<em>中国</em>菜")));
+
+    let (response, _) = server.search_post(json!({ "q": "历史" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+}
+
+#[actix_rt::test]
+async fn korean_particles() {
+    let mut server = common::Server::with_uid("posts");
+    server.create_index(json!({ "uid": "posts", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "학생들이 도서관에서 공부한다" },
+        { "id": 2, "title": "도서관 이용 시간" },
+        { "id": 3, "title": "서울의 날씨" },
+    ])).await;
+
+    server.post_request_async("/indexes/posts/settings/languages", json!(["ko"])).await;
+
+    // the words match whatever particle follows them
+    let (response, _) = server.search_post(json!({ "q": "도서관은", "attributesToHighlight": ["title"] })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+    let formatted: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["_formatted"]["title"].clone()).collect();
+    assert!(formatted.contains(&json!("학생들이 <em>도서관</em>에서 공부한다")));
+
+    // the decomposed jamo match the syllables
+    let (response, _) = server.search_post(json!({ "q": "\u{1109}\u{1165}\u{110b}\u{116e}\u{11af}" })).await;
+    assert_eq!(hit_ids(&response), vec![3]);
+}
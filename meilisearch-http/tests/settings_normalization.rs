@@ -0,0 +1,76 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<u64> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[actix_rt::test]
+async fn disable_diacritic_and_case_folding() {
+    let mut server = common::Server::with_uid("drinks");
+    server.create_index(json!({ "uid": "drinks", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Café au lait" },
+        { "id": 2, "title": "Cafe latte" },
+        { "id": 3, "title": "cafe mocha" },
+    ])).await;
+
+    let (response, _) = server.get_request("/indexes/drinks/settings/normalization").await;
+    assert_eq!(response, json!({ "diacriticFolding": true, "caseFolding": true, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false }));
+
+    let (response, _) = server.search_post(json!({ "q": "cafe" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2, 3]);
+
+    // the documents are indexed again, `é` no longer matches `e`
+    server.post_request_async("/indexes/drinks/settings/normalization", json!({ "diacriticFolding": false })).await;
+    let (response, _) = server.search_post(json!({ "q": "cafe" })).await;
+    assert_eq!(hit_ids(&response), vec![2, 3]);
+
+    server.post_request_async("/indexes/drinks/settings/normalization", json!({ "diacriticFolding": false, "caseFolding": false })).await;
+    let (response, _) = server.get_request("/indexes/drinks/settings").await;
+    assert_eq!(response["normalization"], json!({ "diacriticFolding": false, "caseFolding": false, "stripVowelMarks": false, "kanaFolding": false, "traditionalChineseFolding": false, "numberFolding": false }));
+    let (response, _) = server.search_post(json!({ "q": "Cafe" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+
+    // the highlights cover the characters of the matched word
+    let (response, _) = server.search_post(json!({ "q": "Café", "attributesToHighlight": ["title"] })).await;
+    let hit = response["hits"].as_array().unwrap().iter().find(|hit| hit["id"] == 1).unwrap();
+    assert_eq!(hit["_formatted"]["title"], "<em>Café</em> au lait");
+
+    let (_, status_code) = server.post_request("/indexes/drinks/settings/normalization", json!({ "caseFolding": "no" })).await;
+    assert_eq!(status_code, 400);
+
+    server.delete_request_async("/indexes/drinks/settings/normalization").await;
+    let (response, _) = server.search_post(json!({ "q": "CAFE" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2, 3]);
+}
+
+#[actix_rt::test]
+async fn number_folding() {
+    let mut server = common::Server::with_uid("hardware");
+    server.create_index(json!({ "uid": "hardware", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Box of 1,000 screws" },
+        { "id": 2, "title": "Hammer", "sku": "007" },
+        { "id": 3, "title": "Box of 250 nails, 2.50 each" },
+    ])).await;
+
+    server.post_request_async("/indexes/hardware/settings/normalization", json!({ "numberFolding": true })).await;
+    let (response, _) = server.get_request("/indexes/hardware/settings/normalization").await;
+    assert_eq!(response["numberFolding"], json!(true));
+
+    // the grouped digits are a single number
+    let (response, _) = server.search_post(json!({ "q": "1000 screws" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    // the leading zeros are ignored
+    let (response, _) = server.search_post(json!({ "q": "7" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+
+    // the trailing zeros of the decimals too
+    let (response, _) = server.search_post(json!({ "q": "2.5 nails" })).await;
+    assert_eq!(hit_ids(&response), vec![3]);
+}
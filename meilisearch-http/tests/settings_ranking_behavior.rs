@@ -0,0 +1,37 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect()
+}
+
+#[actix_rt::test]
+async fn disable_proximity() {
+    let mut server = common::Server::with_uid("sentences");
+    server.create_index(json!({ "uid": "sentences", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "blue ocean and sky" },
+        { "id": 2, "title": "the blue sky" },
+    ])).await;
+
+    let (response, _) = server.get_request("/indexes/sentences/settings/ranking-behavior").await;
+    assert_eq!(response, json!({ "maxProximity": 8, "exactness": "attribute" }));
+
+    let (response, _) = server.search_post(json!({ "q": "blue sky" })).await;
+    assert_eq!(hit_ids(&response), vec![2, 1]);
+
+    // the documents are no longer sorted by the distance between the query words
+    server.post_request_async("/indexes/sentences/settings/ranking-behavior", json!({ "maxProximity": 0, "exactness": "word" })).await;
+    let (response, _) = server.get_request("/indexes/sentences/settings").await;
+    assert_eq!(response["rankingBehavior"], json!({ "maxProximity": 0, "exactness": "word" }));
+    let (response, _) = server.search_post(json!({ "q": "blue sky" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+
+    let (_, status_code) = server.post_request("/indexes/sentences/settings/ranking-behavior", json!({ "exactness": "prefix" })).await;
+    assert_eq!(status_code, 400);
+
+    server.delete_request_async("/indexes/sentences/settings/ranking-behavior").await;
+    let (response, _) = server.search_post(json!({ "q": "blue sky" })).await;
+    assert_eq!(hit_ids(&response), vec![2, 1]);
+}
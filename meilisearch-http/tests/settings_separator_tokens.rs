@@ -0,0 +1,58 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    let mut ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect();
+    ids.sort();
+    ids
+}
+
+#[actix_rt::test]
+async fn documents_are_tokenized_with_the_custom_tokens() {
+    let mut server = common::Server::with_uid("parts");
+    server.create_index(json!({ "uid": "parts", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "name": "k8s.io ingress" },
+        { "id": 2, "name": "io ports" },
+        { "id": 3, "name": "pn|7781" },
+    ])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "io" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+    let (response, _) = server.search_post(json!({ "q": "7781" })).await;
+    assert_eq!(hit_ids(&response), Vec::<u64>::new());
+
+    server.post_request_async("/indexes/parts/settings/non-separator-tokens", json!(["."])).await;
+    server.post_request_async("/indexes/parts/settings/separator-tokens", json!(["|"])).await;
+
+    let (response, _) = server.search_post(json!({ "q": "io" })).await;
+    assert_eq!(hit_ids(&response), vec![2]);
+    let (response, _) = server.search_post(json!({ "q": "k8s.io" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+    let (response, _) = server.search_post(json!({ "q": "7781" })).await;
+    assert_eq!(hit_ids(&response), vec![3]);
+
+    let (response, _) = server.get_request("/indexes/parts/settings").await;
+    assert_eq!(response["separatorTokens"], json!(["|"]));
+    assert_eq!(response["nonSeparatorTokens"], json!(["."]));
+
+    // the documents are tokenized with the default rules again
+    server.delete_request_async("/indexes/parts/settings/non-separator-tokens").await;
+    let (response, _) = server.search_post(json!({ "q": "io" })).await;
+    assert_eq!(hit_ids(&response), vec![1, 2]);
+}
+
+#[actix_rt::test]
+async fn tokens_must_be_single_characters() {
+    let mut server = common::Server::with_uid("parts");
+    server.create_index(json!({ "uid": "parts" })).await;
+
+    let (_, status_code) = server.post_request("/indexes/parts/settings/separator-tokens", json!(["&sep"])).await;
+    assert_eq!(status_code, 400);
+    let (_, status_code) = server.post_request("/indexes/parts/settings", json!({ "nonSeparatorTokens": [""] })).await;
+    assert_eq!(status_code, 400);
+
+    let (response, _) = server.get_request("/indexes/parts/settings/separator-tokens").await;
+    assert_eq!(response, json!([]));
+}
@@ -59,3 +59,25 @@ async fn add_documents_and_stop_words() {
 
     // assert!(!response["hits"].as_array().unwrap().is_empty());
 }
+
+#[actix_rt::test]
+async fn stop_words_presets() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    server.update_stop_words(json!({ "preset": "en", "words": ["thee"] })).await;
+    let (response, _status_code) = server.get_stop_words().await;
+    let words = response.as_array().unwrap();
+    assert!(words.contains(&json!("the")));
+    assert!(words.contains(&json!("thee")));
+
+    server.update_all_settings(json!({ "stopWords": { "preset": "fr" } })).await;
+    let (response, _status_code) = server.get_all_settings().await;
+    let words = response["stopWords"].as_array().unwrap();
+    assert!(words.contains(&json!("les")));
+    assert!(!words.contains(&json!("thee")));
+
+    let (response, status_code) = server.post_request("/indexes/movies/settings/stop-words", json!({ "preset": "klingon" })).await;
+    assert_eq!(status_code, 400);
+    assert!(response["message"].as_str().unwrap().contains("unknown stop words preset"));
+}
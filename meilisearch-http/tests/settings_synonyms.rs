@@ -0,0 +1,77 @@
+use serde_json::json;
+
+mod common;
+
+fn hit_ids(response: &serde_json::Value) -> Vec<u64> {
+    response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_u64().unwrap()).collect()
+}
+
+#[actix_rt::test]
+async fn multi_word_synonyms() {
+    let mut server = common::Server::with_uid("cities");
+    server.create_index(json!({ "uid": "cities", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "I love New York" },
+        { "id": 2, "title": "The NYC subway" },
+        { "id": 3, "title": "A new car from York" },
+    ])).await;
+
+    server.post_request_async("/indexes/cities/settings/synonyms", json!({
+        "New York": ["NYC"],
+        "nyc": ["new  york"],
+    })).await;
+
+    // the synonyms are stored normalized
+    let (response, _) = server.get_request("/indexes/cities/settings/synonyms").await;
+    assert_eq!(response, json!({ "new york": ["nyc"], "nyc": ["new york"] }));
+
+    // the words of a multi-word synonym must follow each other
+    let (response, _) = server.search_post(json!({ "q": "nyc" })).await;
+    let mut ids = hit_ids(&response);
+    ids.sort_unstable();
+    assert_eq!(ids, vec![1, 2]);
+
+    let (response, _) = server.search_post(json!({ "q": "New-York" })).await;
+    assert!(hit_ids(&response).contains(&2));
+    assert_eq!(hit_ids(&response)[0], 1);
+}
+
+#[actix_rt::test]
+async fn one_way_synonyms_rules() {
+    let mut server = common::Server::with_uid("products");
+    server.create_index(json!({ "uid": "products", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "iPhone" },
+        { "id": 2, "title": "Android" },
+        { "id": 3, "title": "Mobile charger" },
+        { "id": 4, "title": "Sofa" },
+    ])).await;
+
+    server.post_request_async("/indexes/products/settings/synonyms", json!([
+        "mobile => iphone, android",
+        "couch, sofa",
+    ])).await;
+
+    let (response, _) = server.get_request("/indexes/products/settings/synonyms").await;
+    assert_eq!(response, json!({
+        "couch": ["sofa"],
+        "mobile": ["android", "iphone"],
+        "sofa": ["couch"],
+    }));
+
+    // the generic word expands to the specific ones
+    let (response, _) = server.search_post(json!({ "q": "mobile" })).await;
+    let mut ids = hit_ids(&response);
+    ids.sort_unstable();
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    // but the specific words are left untouched
+    let (response, _) = server.search_post(json!({ "q": "iphone" })).await;
+    assert_eq!(hit_ids(&response), vec![1]);
+
+    let (response, _) = server.search_post(json!({ "q": "couch" })).await;
+    assert_eq!(hit_ids(&response), vec![4]);
+
+    let (_, status_code) = server.post_request("/indexes/products/settings/synonyms", json!(["sofa"])).await;
+    assert_eq!(status_code, 400);
+}
@@ -0,0 +1,36 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn typos_disabled_on_numbers() {
+    let mut server = common::Server::with_uid("products");
+    server.create_index(json!({ "uid": "products", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "sku": "ab1234", "name": "charger" },
+        { "id": 2, "sku": "ab1299", "name": "cable" },
+    ])).await;
+
+    let (response, _) = server.get_request("/indexes/products/settings/typo-tolerance").await;
+    assert_eq!(response, json!({ "disableOnNumbers": false }));
+
+    let (response, _) = server.search_post(json!({ "q": "ab1235" })).await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+
+    server.post_request_async("/indexes/products/settings/typo-tolerance", json!({ "disableOnNumbers": true })).await;
+    let (response, _) = server.get_request("/indexes/products/settings").await;
+    assert_eq!(response["typoTolerance"], json!({ "disableOnNumbers": true }));
+
+    let (response, _) = server.search_post(json!({ "q": "ab1235" })).await;
+    assert!(response["hits"].as_array().unwrap().is_empty());
+    let (response, _) = server.search_post(json!({ "q": "ab1234" })).await;
+    assert_eq!(response["hits"][0]["id"], 1);
+
+    // the other words are still corrected
+    let (response, _) = server.search_post(json!({ "q": "chargre" })).await;
+    assert_eq!(response["hits"][0]["id"], 1);
+
+    server.delete_request_async("/indexes/products/settings/typo-tolerance").await;
+    let (response, _) = server.search_post(json!({ "q": "ab1235" })).await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,30 @@
+use actix_web::http::StatusCode;
+use meilisearch_http::helpers::shutdown::begin_shutdown;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn writes_are_refused_while_shutting_down() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    begin_shutdown(&server.data);
+
+    let (response, status) = server.add_or_replace_multiple_documents_sync(json!([{ "id": 2, "title": "Wonder Woman" }])).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response["errorCode"], "shutting_down");
+    let (_, status) = server.delete_index().await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let (_, status) = server.get_request("/health").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    // the reads are still served
+    let (response, status) = server.search_post(json!({ "q": "carol" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    let (response, status) = server.get_all_documents().await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response.as_array().unwrap().len(), 1);
+}
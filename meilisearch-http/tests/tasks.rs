@@ -0,0 +1,236 @@
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+mod common;
+
+#[actix_rt::test]
+async fn list_tasks_of_all_indexes() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    server.uid = "books".to_string();
+    server.create_index(json!({ "uid": "books", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Dune" }])).await;
+    server.update_all_settings(json!({ "searchableAttributes": ["title"] })).await;
+
+    let (response, status_code) = server.get_tasks("").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["total"], 3);
+    // the most recently enqueued task comes first
+    assert_eq!(response["results"][0]["uid"], "books:1");
+    assert_eq!(response["results"][0]["indexUid"], "books");
+    assert_eq!(response["results"][0]["type"]["name"], "Settings");
+    assert_eq!(response["results"][0]["status"], "processed");
+
+    let (response, _) = server.get_tasks("indexUid=movies").await;
+    assert_eq!(response["total"], 1);
+    assert_eq!(response["results"][0]["uid"], "movies:0");
+
+    let (response, _) = server.get_tasks("type=DocumentsAddition&status=processed").await;
+    assert_eq!(response["total"], 2);
+
+    let (response, _) = server.get_tasks("status=failed,enqueued").await;
+    assert_eq!(response["total"], 0);
+
+    let (response, _) = server.get_tasks("beforeEnqueuedAt=2000-01-01T00:00:00Z").await;
+    assert_eq!(response["total"], 0);
+
+    let (response, _) = server.get_tasks("limit=1&offset=1").await;
+    assert_eq!(response["total"], 3);
+    assert_eq!(response["results"].as_array().unwrap().len(), 1);
+    assert_eq!(response["results"][0]["uid"], "books:0");
+}
+
+#[actix_rt::test]
+async fn get_single_task() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    let (response, status_code) = server.get_task("movies:0").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["uid"], "movies:0");
+    assert_eq!(response["updateId"], 0);
+    assert_eq!(response["status"], "processed");
+
+    let (response, status_code) = server.get_task("movies:12").await;
+    assert_eq!(status_code, 404);
+    assert_eq!(response["errorCode"], "not_found");
+
+    let (response, status_code) = server.get_task("movies").await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+}
+
+#[actix_rt::test]
+async fn processed_tasks_can_not_be_canceled() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    let (response, status_code) = server.cancel_tasks("movies:0,movies:7").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["canceled"], json!([]));
+    assert_eq!(response["notCanceled"], json!(["movies:0", "movies:7"]));
+
+    let (response, status_code) = server.cancel_tasks("books:0").await;
+    assert_eq!(status_code, 404);
+    assert_eq!(response["errorCode"], "index_not_found");
+}
+
+#[actix_rt::test]
+async fn delete_finished_tasks() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 2, "title": "Joy" }])).await;
+    server.update_all_settings(json!({ "searchableAttributes": ["title"] })).await;
+
+    let (response, status_code) = server.delete_request("/tasks?type=DocumentsAddition").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["deletedTasks"], 2);
+
+    // the most recent task is always kept to preserve the update ids sequence
+    let (response, status_code) = server.delete_request("/tasks").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["deletedTasks"], 0);
+
+    let (response, _) = server.get_tasks("").await;
+    assert_eq!(response["total"], 1);
+    assert_eq!(response["results"][0]["uid"], "movies:2");
+
+    // new updates don't reuse the ids of deleted tasks
+    server.add_or_replace_multiple_documents(json!([{ "id": 3, "title": "Up" }])).await;
+    let (response, status_code) = server.get_task("movies:3").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+}
+
+#[actix_rt::test]
+async fn wait_for_task() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents_sync(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    let (response, status_code) = server.wait_task("movies:0", "timeoutMs=10000").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["uid"], "movies:0");
+    assert_eq!(response["status"], "processed");
+
+    let (response, status_code) = server.get_document(1).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["title"], "Carol");
+
+    let (response, status_code) = server.wait_task("movies:0", "timeoutMs=3600000").await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+}
+
+#[actix_rt::test]
+async fn manage_scheduled_tasks() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "job": { "type": "documentsExpiration", "attribute": "expiresAt" }, "cron": "*/10 * * * *" });
+    let (response, status_code) = server.post_request("/tasks/scheduled", body).await;
+    assert_eq!(status_code, 201);
+    assert_eq!(response["id"], 0);
+    assert_eq!(response["runs"], json!([]));
+
+    let body = json!({ "job": { "type": "snapshot" }, "cron": "0 25 * * *" });
+    let (response, status_code) = server.post_request("/tasks/scheduled", body).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_request");
+
+    let (response, status_code) = server.get_request("/tasks/scheduled").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response.as_array().unwrap().len(), 1);
+    assert_eq!(response[0]["job"]["type"], "documentsExpiration");
+    assert_eq!(response[0]["cron"], "*/10 * * * *");
+
+    let (_, status_code) = server.delete_request("/tasks/scheduled/0").await;
+    assert_eq!(status_code, 204);
+
+    let (_, status_code) = server.delete_request("/tasks/scheduled/0").await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn schedule_s3_crawls() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({
+        "job": { "type": "s3Crawl", "indexUid": "movies", "url": "s3://datasets/movies/", "primaryKey": "id" },
+        "cron": "0 * * * *",
+    });
+    let (response, status_code) = server.post_request("/tasks/scheduled", body).await;
+    assert_eq!(status_code, 201);
+    assert_eq!(response["job"]["url"], "s3://datasets/movies/");
+
+    let body = json!({ "job": { "type": "s3Crawl", "indexUid": "movies", "url": "https://datasets/movies/" }, "cron": "0 * * * *" });
+    let (response, status_code) = server.post_request("/tasks/scheduled", body).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+
+    let body = json!({ "job": { "type": "s3Crawl", "indexUid": "not valid", "url": "s3://datasets" }, "cron": "0 * * * *" });
+    let (_, status_code) = server.post_request("/tasks/scheduled", body).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn schedule_web_crawls() {
+    let mut server = common::Server::with_uid("docs");
+
+    let body = json!({
+        "job": { "type": "webCrawl", "indexUid": "docs", "urls": ["https://example.com/sitemap.xml"], "maxPages": 500 },
+        "cron": "0 3 * * *",
+    });
+    let (response, status_code) = server.post_request("/tasks/scheduled", body).await;
+    assert_eq!(status_code, 201);
+    assert_eq!(response["job"]["maxPages"], 500);
+
+    let body = json!({ "job": { "type": "webCrawl", "indexUid": "docs", "urls": ["ftp://example.com"] }, "cron": "0 3 * * *" });
+    let (response, status_code) = server.post_request("/tasks/scheduled", body).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+
+    let body = json!({ "job": { "type": "webCrawl", "indexUid": "docs", "urls": [] }, "cron": "0 3 * * *" });
+    let (_, status_code) = server.post_request("/tasks/scheduled", body).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn tasks_stats() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }, { "id": 2, "title": "Joy" }])).await;
+    server.add_or_replace_multiple_documents(json!([{ "title": "Up" }])).await;
+
+    // the tasks are counted once the update callback ran, right after the updates are processed
+    let mut response = Value::Null;
+    for _ in 0..20 {
+        let (value, status_code) = server.get_request("/stats/tasks").await;
+        assert_eq!(status_code, 200);
+        response = value;
+        if response["byType"]["DocumentsAddition"]["processed"] == 2 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(response["queueDepth"], 0);
+    assert_eq!(response["byStatus"]["processed"], 1);
+    assert_eq!(response["byStatus"]["failed"], 1);
+    assert_eq!(response["byType"]["DocumentsAddition"]["processed"], 2);
+    assert_eq!(response["indexes"]["movies"]["queueDepth"], 0);
+    assert_eq!(response["indexes"]["movies"]["indexedDocuments"], 2);
+
+    let (response, status_code) = server.get_request("/metrics").await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "feature_not_enabled");
+
+    server.patch_request("/experimental-features", json!({ "metrics": true })).await;
+    let (_, status_code) = server.get_request("/metrics").await;
+    assert_eq!(status_code, 200);
+}
@@ -0,0 +1,18 @@
+use meilisearch_http::warmup::warmup_indexes;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn warmup_existing_and_missing_indexes() {
+    let mut server = common::Server::test_server().await;
+
+    // the unknown indexes are skipped
+    warmup_indexes(server.data(), &["test".to_string(), "unknown".to_string()]).unwrap();
+    warmup_indexes(server.data(), &["*".to_string()]).unwrap();
+
+    // the searches are answered the same after the warmup
+    let (response, status_code) = server.search_post(json!({ "q": "exercitation" })).await;
+    assert_eq!(status_code, 200);
+    assert!(!response["hits"].as_array().unwrap().is_empty());
+}
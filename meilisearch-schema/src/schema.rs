@@ -81,6 +81,12 @@ impl Schema {
             return Err(Error::PrimaryKeyAlreadyPresent)
         }
 
+        self.change_primary_key(name)
+    }
+
+    /// Replace the primary key, even if one is already set. The caller is responsible
+    /// for rebuilding the documents ids mapping according to the new primary key.
+    pub fn change_primary_key(&mut self, name: &str) -> SResult<FieldId> {
         let id = self.insert(name)?;
         self.primary_key = Some(id);
         self.set_indexed(name)?;
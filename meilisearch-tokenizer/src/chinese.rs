@@ -0,0 +1,135 @@
+//! Chinese is written without spaces between the words, the runs of Han characters are split
+//! into the words of a dictionary, the traditional characters are looked up in simplified Chinese.
+
+use crate::segmentation;
+
+/// The words of simplified Chinese, one per line and sorted, the ones used at least five times
+/// in the dictionary of jieba, see `dictionaries/README.md`.
+const CHINESE_WORDS: &str = include_str!("../dictionaries/chinese.txt");
+
+/// The common traditional characters and their simplified form, sorted.
+const TRADITIONAL_TO_SIMPLIFIED: &[(char, char)] = &[
+    ('亂', '乱'), ('來', '来'), ('係', '系'), ('倆', '俩'), ('個', '个'), ('們', '们'), ('價', '价'), ('億', '亿'),
+    ('優', '优'), ('儲', '储'), ('兒', '儿'), ('兩', '两'), ('冊', '册'), ('創', '创'), ('劃', '划'), ('劇', '剧'),
+    ('動', '动'), ('務', '务'), ('勞', '劳'), ('勢', '势'), ('勵', '励'), ('區', '区'), ('協', '协'), ('卻', '却'),
+    ('參', '参'), ('叢', '丛'), ('吳', '吴'), ('員', '员'), ('問', '问'), ('啟', '启'), ('單', '单'), ('嗎', '吗'),
+    ('嚴', '严'), ('國', '国'), ('圍', '围'), ('園', '园'), ('圓', '圆'), ('圖', '图'), ('團', '团'), ('報', '报'),
+    ('場', '场'), ('塊', '块'), ('壓', '压'), ('壞', '坏'), ('夠', '够'), ('夢', '梦'), ('奧', '奥'), ('婦', '妇'),
+    ('媽', '妈'), ('孫', '孙'), ('學', '学'), ('實', '实'), ('寧', '宁'), ('寫', '写'), ('寶', '宝'), ('將', '将'),
+    ('專', '专'), ('對', '对'), ('導', '导'), ('層', '层'), ('島', '岛'), ('師', '师'), ('帶', '带'), ('幣', '币'),
+    ('幫', '帮'), ('幾', '几'), ('庫', '库'), ('廣', '广'), ('廳', '厅'), ('張', '张'), ('彈', '弹'), ('彙', '汇'),
+    ('後', '后'), ('徑', '径'), ('從', '从'), ('復', '复'), ('徵', '征'), ('愛', '爱'), ('態', '态'), ('憶', '忆'),
+    ('應', '应'), ('懷', '怀'), ('戰', '战'), ('戲', '戏'), ('換', '换'), ('擁', '拥'), ('擇', '择'), ('擊', '击'),
+    ('擔', '担'), ('據', '据'), ('擴', '扩'), ('攝', '摄'), ('敗', '败'), ('敵', '敌'), ('數', '数'), ('斷', '断'),
+    ('於', '于'), ('時', '时'), ('晉', '晋'), ('曉', '晓'), ('書', '书'), ('會', '会'), ('東', '东'), ('條', '条'),
+    ('業', '业'), ('極', '极'), ('槍', '枪'), ('樂', '乐'), ('樓', '楼'), ('標', '标'), ('樣', '样'), ('樹', '树'),
+    ('橋', '桥'), ('機', '机'), ('檢', '检'), ('櫃', '柜'), ('權', '权'), ('歐', '欧'), ('歡', '欢'), ('歲', '岁'),
+    ('歷', '历'), ('歸', '归'), ('殺', '杀'), ('氣', '气'), ('決', '决'), ('沒', '没'), ('況', '况'), ('淚', '泪'),
+    ('淨', '净'), ('測', '测'), ('湧', '涌'), ('湯', '汤'), ('準', '准'), ('溝', '沟'), ('溫', '温'), ('滅', '灭'),
+    ('滿', '满'), ('漁', '渔'), ('漢', '汉'), ('潔', '洁'), ('濃', '浓'), ('濟', '济'), ('灣', '湾'), ('災', '灾'),
+    ('為', '为'), ('烏', '乌'), ('無', '无'), ('煙', '烟'), ('熱', '热'), ('燈', '灯'), ('營', '营'), ('爺', '爷'),
+    ('爾', '尔'), ('牆', '墙'), ('狀', '状'), ('獎', '奖'), ('獨', '独'), ('獲', '获'), ('現', '现'), ('環', '环'),
+    ('瓊', '琼'), ('產', '产'), ('畢', '毕'), ('畫', '画'), ('異', '异'), ('當', '当'), ('療', '疗'), ('發', '发'),
+    ('盡', '尽'), ('監', '监'), ('盤', '盘'), ('眾', '众'), ('睏', '困'), ('確', '确'), ('碼', '码'), ('礎', '础'),
+    ('禍', '祸'), ('禮', '礼'), ('種', '种'), ('穀', '谷'), ('穩', '稳'), ('窮', '穷'), ('競', '竞'), ('筆', '笔'),
+    ('節', '节'), ('範', '范'), ('篩', '筛'), ('簡', '简'), ('簽', '签'), ('籃', '篮'), ('糧', '粮'), ('紀', '纪'),
+    ('約', '约'), ('紅', '红'), ('紙', '纸'), ('級', '级'), ('細', '细'), ('終', '终'), ('組', '组'), ('結', '结'),
+    ('絡', '络'), ('給', '给'), ('統', '统'), ('經', '经'), ('綜', '综'), ('綠', '绿'), ('網', '网'), ('緊', '紧'),
+    ('線', '线'), ('練', '练'), ('縣', '县'), ('縮', '缩'), ('總', '总'), ('織', '织'), ('繼', '继'), ('續', '续'),
+    ('罰', '罚'), ('罵', '骂'), ('義', '义'), ('習', '习'), ('聖', '圣'), ('聞', '闻'), ('聯', '联'), ('聲', '声'),
+    ('職', '职'), ('聽', '听'), ('肅', '肃'), ('腦', '脑'), ('腳', '脚'), ('腸', '肠'), ('膚', '肤'), ('臉', '脸'),
+    ('臨', '临'), ('臺', '台'), ('與', '与'), ('興', '兴'), ('舉', '举'), ('舊', '旧'), ('莊', '庄'), ('華', '华'),
+    ('萬', '万'), ('葉', '叶'), ('蓋', '盖'), ('蔥', '葱'), ('薦', '荐'), ('藍', '蓝'), ('藝', '艺'), ('藥', '药'),
+    ('蘇', '苏'), ('蘋', '苹'), ('蘭', '兰'), ('處', '处'), ('虛', '虚'), ('號', '号'), ('蝦', '虾'), ('蟲', '虫'),
+    ('術', '术'), ('衛', '卫'), ('衝', '冲'), ('裏', '里'), ('補', '补'), ('裝', '装'), ('裡', '里'), ('襯', '衬'),
+    ('見', '见'), ('規', '规'), ('視', '视'), ('親', '亲'), ('覺', '觉'), ('覽', '览'), ('觀', '观'), ('計', '计'),
+    ('訊', '讯'), ('記', '记'), ('訪', '访'), ('設', '设'), ('許', '许'), ('評', '评'), ('詞', '词'), ('試', '试'),
+    ('話', '话'), ('詳', '详'), ('認', '认'), ('語', '语'), ('誤', '误'), ('說', '说'), ('課', '课'), ('調', '调'),
+    ('談', '谈'), ('請', '请'), ('論', '论'), ('諾', '诺'), ('講', '讲'), ('謝', '谢'), ('證', '证'), ('識', '识'),
+    ('議', '议'), ('護', '护'), ('讀', '读'), ('變', '变'), ('讓', '让'), ('讚', '赞'), ('豐', '丰'), ('貓', '猫'),
+    ('貝', '贝'), ('負', '负'), ('財', '财'), ('貨', '货'), ('責', '责'), ('貴', '贵'), ('買', '买'), ('費', '费'),
+    ('資', '资'), ('賓', '宾'), ('賣', '卖'), ('質', '质'), ('賺', '赚'), ('購', '购'), ('賽', '赛'), ('贏', '赢'),
+    ('趕', '赶'), ('趙', '赵'), ('趨', '趋'), ('跡', '迹'), ('蹤', '踪'), ('車', '车'), ('軍', '军'), ('軟', '软'),
+    ('較', '较'), ('載', '载'), ('輕', '轻'), ('輛', '辆'), ('輸', '输'), ('轉', '转'), ('辦', '办'), ('辭', '辞'),
+    ('農', '农'), ('這', '这'), ('連', '连'), ('週', '周'), ('進', '进'), ('遊', '游'), ('運', '运'), ('過', '过'),
+    ('達', '达'), ('遠', '远'), ('適', '适'), ('遲', '迟'), ('選', '选'), ('遺', '遗'), ('還', '还'), ('邊', '边'),
+    ('邏', '逻'), ('郵', '邮'), ('鄉', '乡'), ('鄰', '邻'), ('醫', '医'), ('醬', '酱'), ('釋', '释'), ('針', '针'),
+    ('鈔', '钞'), ('銀', '银'), ('鋼', '钢'), ('錄', '录'), ('錢', '钱'), ('錯', '错'), ('錶', '表'), ('鍋', '锅'),
+    ('鍵', '键'), ('鎖', '锁'), ('鏡', '镜'), ('鐘', '钟'), ('鐵', '铁'), ('長', '长'), ('門', '门'), ('閃', '闪'),
+    ('閉', '闭'), ('開', '开'), ('間', '间'), ('閱', '阅'), ('闆', '板'), ('闊', '阔'), ('關', '关'), ('陰', '阴'),
+    ('陳', '陈'), ('陸', '陆'), ('陽', '阳'), ('隊', '队'), ('階', '阶'), ('際', '际'), ('隨', '随'), ('險', '险'),
+    ('隻', '只'), ('雖', '虽'), ('雙', '双'), ('雜', '杂'), ('雞', '鸡'), ('離', '离'), ('難', '难'), ('雲', '云'),
+    ('電', '电'), ('靈', '灵'), ('韓', '韩'), ('響', '响'), ('頁', '页'), ('頂', '顶'), ('項', '项'), ('順', '顺'),
+    ('須', '须'), ('預', '预'), ('頓', '顿'), ('領', '领'), ('頭', '头'), ('頸', '颈'), ('頻', '频'), ('題', '题'),
+    ('顏', '颜'), ('類', '类'), ('顧', '顾'), ('顯', '显'), ('風', '风'), ('飛', '飞'), ('飯', '饭'), ('飲', '饮'),
+    ('飽', '饱'), ('飾', '饰'), ('餅', '饼'), ('餘', '余'), ('館', '馆'), ('馬', '马'), ('驅', '驱'), ('驗', '验'),
+    ('驚', '惊'), ('髒', '脏'), ('體', '体'), ('髮', '发'), ('鬆', '松'), ('鬥', '斗'), ('魚', '鱼'), ('魯', '鲁'),
+    ('鮮', '鲜'), ('鳥', '鸟'), ('鳳', '凤'), ('鴨', '鸭'), ('鹽', '盐'), ('麥', '麦'), ('麵', '面'), ('麼', '么'),
+    ('黃', '黄'), ('點', '点'), ('黨', '党'), ('齊', '齐'), ('齒', '齿'), ('龍', '龙'), ('龜', '龟'),
+];
+
+pub(crate) fn is_han(c: char) -> bool {
+    (c >= '\u{3400}' && c <= '\u{4dbf}') // CJK Unified Ideographs Extension A
+        || (c >= '\u{4e00}' && c <= '\u{9fff}') // CJK Unified Ideographs
+        || (c >= '\u{f900}' && c <= '\u{faff}') // CJK Compatibility Ideographs
+}
+
+/// Writes the traditional characters in simplified Chinese, so that a word written in both
+/// forms matches.
+pub fn fold_traditional(word: &str) -> String {
+    word.chars().map(simplified).collect()
+}
+
+fn simplified(c: char) -> char {
+    match TRADITIONAL_TO_SIMPLIFIED.binary_search_by_key(&c, |(traditional, _)| *traditional) {
+        Ok(i) => TRADITIONAL_TO_SIMPLIFIED[i].1,
+        Err(_) => c,
+    }
+}
+
+/// Whether the word in simplified Chinese is a word of the dictionary or of the sorted words of the index.
+fn is_word(word: &str, words: &[String]) -> bool {
+    segmentation::contains_line(CHINESE_WORDS, word) || words.binary_search_by(|w| w.as_str().cmp(word)).is_ok()
+}
+
+/// Returns the length in bytes of the first word of a run of Han characters, the characters
+/// matching no word of the dictionary nor of the sorted words of the index are words of their own.
+pub(crate) fn first_word_len(text: &str, words: &[String]) -> usize {
+    let simplified = fold_traditional(text);
+    let len = segmentation::first_word_len(&simplified, |word| is_word(word, words), false);
+    let chars = simplified[..len].chars().count();
+    text.char_indices().nth(chars).map_or(text.len(), |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split<'a>(mut text: &'a str, words: &[String]) -> Vec<&'a str> {
+        let mut split = Vec::new();
+        while !text.is_empty() {
+            let len = first_word_len(text, words);
+            split.push(&text[..len]);
+            text = &text[len..];
+        }
+        split
+    }
+
+    #[test]
+    fn simplified_and_traditional() {
+        assert_eq!(split("我喜欢吃中国菜", &[]), vec!["我", "喜欢", "吃", "中国", "菜"]);
+        assert_eq!(split("我喜歡吃中國菜", &[]), vec!["我", "喜歡", "吃", "中國", "菜"]);
+        // the longest words are preferred and the unknown characters are words of their own
+        assert_eq!(split("搜索引擎很快龘靐", &[]), vec!["搜索引擎", "很快", "龘", "靐"]);
+        assert_eq!(split("南京市长江大桥", &[]), vec!["南京市", "长江大桥"]);
+
+        // the words of the index are also searched
+        assert_eq!(split("他来到了网易杭研大厦", &[]), vec!["他", "来到", "了", "网易", "杭", "研", "大厦"]);
+        let words = vec!["杭研".to_string()];
+        assert_eq!(split("他来到了网易杭研大厦", &words), vec!["他", "来到", "了", "网易", "杭研", "大厦"]);
+
+        assert_eq!(fold_traditional("中國"), "中国");
+        assert!(TRADITIONAL_TO_SIMPLIFIED.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(CHINESE_WORDS.lines().zip(CHINESE_WORDS.lines().skip(1)).all(|(a, b)| a < b));
+    }
+}
@@ -0,0 +1,161 @@
+//! Japanese is written without spaces between the words, the runs of Japanese characters are
+//! split with the scripts they are written in: a word written in kanji ends with its hiragana
+//! inflection, the particles written in hiragana are words of their own, a word written in
+//! katakana ends where the katakana end.
+
+/// The particles that follow the words, the longest first.
+const PARTICLES: &[&str] = &[
+    "から", "まで", "より", "には", "では", "とは", "への", "での", "ので", "のに", "けど",
+    "は", "が", "を", "に", "で", "と", "の", "へ", "も", "や", "か",
+];
+
+/// The half-width katakana from `U+FF66` to `U+FF9D` written in full width.
+const FULL_WIDTH_KATAKANA: &str = "ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Script {
+    Kanji,
+    Hiragana,
+    Katakana,
+}
+
+fn script(c: char) -> Option<Script> {
+    match c {
+        '\u{3041}'..='\u{309f}' => Some(Script::Hiragana),
+        '\u{30a0}'..='\u{30ff}' | '\u{ff66}'..='\u{ff9f}' => Some(Script::Katakana),
+        '\u{3005}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}' => Some(Script::Kanji),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_japanese(c: char) -> bool {
+    script(c).is_some()
+}
+
+/// A particle is followed by another word, not by the rest of an inflection written in hiragana.
+fn particle_len(text: &str) -> Option<usize> {
+    PARTICLES.iter()
+        .find(|particle| {
+            text.starts_with(*particle)
+                && text[particle.len()..].chars().next().map_or(true, |c| script(c) != Some(Script::Hiragana))
+        })
+        .map(|particle| particle.len())
+}
+
+/// Returns the length in bytes of the first word of a run of Japanese characters. The words
+/// of the dictionary are kept whole, the other words end where a dictionary word starts.
+pub(crate) fn first_word_len(text: &str, dictionary: &[String]) -> usize {
+    let dictionary_len = |text: &str| dictionary.iter().find(|word| text.starts_with(word.as_str())).map(String::len);
+    if let Some(len) = dictionary_len(text) {
+        return len;
+    }
+
+    let mut chars = text.char_indices();
+    let first = match chars.next() {
+        Some((_, c)) => script(c),
+        None => return 0,
+    };
+    if first == Some(Script::Hiragana) {
+        if let Some(len) = particle_len(text) {
+            return len;
+        }
+    }
+
+    let mut previous = first;
+    for (i, c) in chars {
+        let rest = &text[i..];
+        if dictionary_len(rest).is_some() {
+            return i;
+        }
+
+        let current = script(c);
+        match (previous, current) {
+            // the inflection of a word ends at the next particle
+            (Some(Script::Kanji), Some(Script::Hiragana)) | (Some(Script::Hiragana), Some(Script::Hiragana)) => {
+                if particle_len(rest).is_some() {
+                    return i;
+                }
+            }
+            (previous, current) if previous == current => (),
+            _ => return i,
+        }
+        previous = current;
+    }
+
+    text.len()
+}
+
+/// Writes the katakana in hiragana and the half-width katakana in full width,
+/// so that the words written with the same reading match.
+pub fn fold_kana(word: &str) -> String {
+    let mut folded: Vec<char> = Vec::with_capacity(word.len());
+    for c in word.chars() {
+        let c = match c {
+            '\u{ff66}'..='\u{ff9d}' => FULL_WIDTH_KATAKANA.chars().nth(c as usize - 0xff66).unwrap_or(c),
+            // the voiced and semi-voiced marks are composed with the previous katakana
+            '\u{ff9e}' | '\u{ff9f}' => {
+                let semi_voiced = c == '\u{ff9f}';
+                match folded.last().copied().and_then(|previous| compose_voiced(previous, semi_voiced)) {
+                    Some(composed) => {
+                        folded.pop();
+                        composed
+                    }
+                    None => c,
+                }
+            }
+            c => c,
+        };
+        folded.push(c);
+    }
+
+    folded.into_iter().map(|c| match c {
+        '\u{30a1}'..='\u{30f6}' => std::char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        c => c,
+    })
+    .collect()
+}
+
+fn compose_voiced(c: char, semi_voiced: bool) -> Option<char> {
+    let offset = match c {
+        'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ' if semi_voiced => 2,
+        'ウ' if !semi_voiced => return Some('ヴ'),
+        _ if semi_voiced => return None,
+        'カ' | 'キ' | 'ク' | 'ケ' | 'コ' | 'サ' | 'シ' | 'ス' | 'セ' | 'ソ' => 1,
+        'タ' | 'チ' | 'ツ' | 'テ' | 'ト' | 'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ' => 1,
+        _ => return None,
+    };
+    std::char::from_u32(c as u32 + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split<'a>(mut text: &'a str, dictionary: &[String]) -> Vec<&'a str> {
+        let mut split = Vec::new();
+        while !text.is_empty() {
+            let len = first_word_len(text, dictionary);
+            split.push(&text[..len]);
+            text = &text[len..];
+        }
+        split
+    }
+
+    #[test]
+    fn scripts_and_particles() {
+        assert_eq!(split("東京に住んでいます", &[]), vec!["東京", "に", "住んでいます"]);
+        assert_eq!(split("私はコーヒーが好きです", &[]), vec!["私", "は", "コーヒー", "が", "好きです"]);
+        assert_eq!(split("日本語から英語まで", &[]), vec!["日本語", "から", "英語", "まで"]);
+
+        let dictionary = vec!["東京".to_string()];
+        assert_eq!(split("東京大学", &dictionary), vec!["東京", "大学"]);
+    }
+
+    #[test]
+    fn readings() {
+        assert_eq!(fold_kana("コーヒー"), "こーひー");
+        assert_eq!(fold_kana("ｺｰﾋｰ"), "こーひー");
+        assert_eq!(fold_kana("ｶﾞﾊﾟﾝ"), "がぱん");
+        assert_eq!(fold_kana("東京"), "東京");
+    }
+}
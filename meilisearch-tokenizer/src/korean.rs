@@ -0,0 +1,118 @@
+//! Korean words, the eojeol, are separated by spaces and end with the particles that mark their
+//! role in the sentence. The particles are not part of the indexed words, so that a word matches
+//! whatever particle follows it.
+
+/// The particles that end the words, the longest first.
+const PARTICLES: &[&str] = &[
+    "으로부터", "에서부터", "에게서", "한테서", "으로서", "으로써", "이라고", "께서", "에서", "에게",
+    "한테", "으로", "로서", "로써", "부터", "까지", "보다", "처럼", "마다", "이나", "이랑", "하고",
+    "은", "는", "이", "가", "을", "를", "에", "께", "의", "와", "과", "도", "만", "로", "랑",
+];
+
+const SYLLABLE_BASE: u32 = 0xac00;
+const SYLLABLE_COUNT: u32 = 11172;
+const LEADING_BASE: u32 = 0x1100;
+const LEADING_COUNT: u32 = 19;
+const VOWEL_BASE: u32 = 0x1161;
+const VOWEL_COUNT: u32 = 21;
+const TRAILING_BASE: u32 = 0x11a7;
+const TRAILING_COUNT: u32 = 28;
+
+pub(crate) fn is_hangul(c: char) -> bool {
+    (c >= '\u{1100}' && c <= '\u{11ff}') // Hangul Jamo
+        || (c >= '\u{3130}' && c <= '\u{318f}') // Hangul Compatibility Jamo
+        || (c >= '\u{a960}' && c <= '\u{a97f}') // Hangul Jamo Extended-A
+        || (c >= '\u{ac00}' && c <= '\u{d7a3}') // Hangul Syllables
+        || (c >= '\u{d7b0}' && c <= '\u{d7ff}') // Hangul Jamo Extended-B
+}
+
+fn is_conjoining_jamo(c: char) -> bool {
+    c >= '\u{1100}' && c <= '\u{11ff}'
+}
+
+/// Composes a syllable, or a syllable without its final consonant, with the next jamo.
+fn compose_pair(a: char, b: char) -> Option<char> {
+    let (a, b) = (a as u32, b as u32);
+    if (LEADING_BASE..LEADING_BASE + LEADING_COUNT).contains(&a) && (VOWEL_BASE..VOWEL_BASE + VOWEL_COUNT).contains(&b) {
+        std::char::from_u32(SYLLABLE_BASE + ((a - LEADING_BASE) * VOWEL_COUNT + (b - VOWEL_BASE)) * TRAILING_COUNT)
+    } else if (SYLLABLE_BASE..SYLLABLE_BASE + SYLLABLE_COUNT).contains(&a)
+        && (a - SYLLABLE_BASE) % TRAILING_COUNT == 0
+        && (TRAILING_BASE + 1..TRAILING_BASE + TRAILING_COUNT).contains(&b)
+    {
+        std::char::from_u32(a + b - TRAILING_BASE)
+    } else {
+        None
+    }
+}
+
+/// The characters of the text with the conjoining jamo composed into syllables,
+/// and the byte where each of them ends in the text.
+fn compose(text: &str) -> Vec<(char, usize)> {
+    let mut composed: Vec<(char, usize)> = Vec::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        if let Some((last, last_end)) = composed.last_mut() {
+            if let Some(syllable) = compose_pair(*last, c) {
+                *last = syllable;
+                *last_end = end;
+                continue;
+            }
+        }
+        composed.push((c, end));
+    }
+    composed
+}
+
+/// Composes the conjoining jamo into syllables, so that a decomposed word matches the
+/// same word written with syllables.
+pub fn compose_jamo(word: &str) -> String {
+    if word.contains(is_conjoining_jamo) {
+        compose(word).into_iter().map(|(c, _)| c).collect()
+    } else {
+        word.to_string()
+    }
+}
+
+/// Returns the length in bytes of the word without the particles that end it. A particle of
+/// a single syllable is only removed when the rest of the word has two syllables or more, the
+/// words ending with a syllable that is also a particle are not stripped to a single syllable.
+pub(crate) fn stem_len(word: &str) -> usize {
+    let composed = compose(word);
+    let syllables: String = composed.iter().map(|(c, _)| *c).collect();
+
+    let mut stem = syllables.as_str();
+    while let Some(particle) = PARTICLES.iter().find(|particle| {
+        let min_stem = if particle.chars().count() == 1 { 2 } else { 1 };
+        stem.ends_with(*particle) && stem[..stem.len() - particle.len()].chars().count() >= min_stem
+    }) {
+        stem = &stem[..stem.len() - particle.len()];
+    }
+
+    match stem.chars().count() {
+        0 => 0,
+        count => composed[count - 1].1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stem(word: &str) -> &str {
+        &word[..stem_len(word)]
+    }
+
+    #[test]
+    fn particles_and_jamo() {
+        assert_eq!(stem("학교에서"), "학교");
+        assert_eq!(stem("학생들이"), "학생들");
+        // the word and the word followed by a particle have the same stem
+        assert_eq!(stem("고양이가"), stem("고양이"));
+        assert_eq!(stem("아이"), "아이");
+        assert_eq!(stem("밥을"), "밥을");
+
+        let decomposed = "\u{1112}\u{1161}\u{11a8}\u{1100}\u{116d}\u{110b}\u{1166}\u{1109}\u{1165}";
+        assert_eq!(compose_jamo(decomposed), "학교에서");
+        assert_eq!(compose_jamo(stem(decomposed)), "학교");
+    }
+}
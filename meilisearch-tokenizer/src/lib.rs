@@ -1,8 +1,24 @@
+mod chinese;
+mod japanese;
+mod korean;
+mod number;
+mod segmentation;
+mod thai;
+
 use self::SeparatorCategory::*;
+use self::chinese::is_han;
+use self::japanese::is_japanese;
+use self::korean::is_hangul;
+use self::thai::is_thai;
 use deunicode::deunicode_char;
 use slice_group_by::StrGroupBy;
 use std::iter::Peekable;
 
+pub use self::chinese::fold_traditional;
+pub use self::japanese::fold_kana;
+pub use self::korean::compose_jamo;
+pub use self::number::fold_number;
+
 pub fn is_cjk(c: char) -> bool {
     (c >= '\u{1100}' && c <= '\u{11ff}')  // Hangul Jamo
         || (c >= '\u{2e80}' && c <= '\u{2eff}')  // CJK Radicals Supplement
@@ -22,6 +38,24 @@ pub fn is_cjk(c: char) -> bool {
         || (c >= '\u{ff00}' && c <= '\u{ffef}') // Full-width roman characters and half-width katakana
 }
 
+/// The marks written over or under the letters of Arabic and Hebrew words that are usually
+/// omitted, the harakat, the tatweel used to stretch the words, the niqqud and the cantillation.
+pub fn is_vowel_mark(c: char) -> bool {
+    (c >= '\u{0591}' && c <= '\u{05bd}') // Hebrew cantillation and niqqud
+        || c == '\u{05bf}' // Hebrew rafe
+        || (c >= '\u{05c1}' && c <= '\u{05c2}') // Hebrew shin and sin dots
+        || (c >= '\u{05c4}' && c <= '\u{05c5}') // Hebrew upper and lower dots
+        || c == '\u{05c7}' // Hebrew qamats qatan
+        || (c >= '\u{0610}' && c <= '\u{061a}') // Arabic honorifics
+        || c == '\u{0640}' // Arabic tatweel
+        || (c >= '\u{064b}' && c <= '\u{065f}') // Arabic harakat
+        || c == '\u{0670}' // Arabic superscript alef
+        || (c >= '\u{06d6}' && c <= '\u{06dc}') // Quranic annotations
+        || (c >= '\u{06df}' && c <= '\u{06e4}')
+        || (c >= '\u{06e7}' && c <= '\u{06e8}')
+        || (c >= '\u{06ea}' && c <= '\u{06ed}')
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum SeparatorCategory {
     Soft,
@@ -45,13 +79,194 @@ impl SeparatorCategory {
     }
 }
 
-fn is_separator(c: char) -> bool {
-    classify_separator(c).is_some()
+/// The characters that an index tokenizes differently from the default rules,
+/// and the words that must never be split.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Separators {
+    separators: Vec<char>,
+    non_separators: Vec<char>,
+    /// Lowercased, the longest words first.
+    dictionary: Vec<String>,
+    /// The runs of Thai characters are split into the words of the Thai dictionary.
+    thai: bool,
+    /// Sorted, the words of the dictionary searched with the Thai ones.
+    thai_words: Vec<String>,
+    /// The runs of Han characters are split into the words of the Chinese dictionary.
+    chinese: bool,
+    /// Sorted and in simplified Chinese, the words of the dictionary searched with the Chinese ones.
+    chinese_words: Vec<String>,
+    /// The runs of Japanese characters are split into words.
+    japanese: bool,
+    /// The particles that end the Korean words are not part of the tokens.
+    korean: bool,
+    /// The camelCase and PascalCase identifiers are split into their components.
+    identifier_splitting: bool,
+    /// The numbers written with thousands separators or a decimal comma are single tokens.
+    number_grouping: bool,
+}
+
+static DEFAULT_SEPARATORS: Separators = Separators {
+    separators: Vec::new(),
+    non_separators: Vec::new(),
+    dictionary: Vec::new(),
+    thai: false,
+    thai_words: Vec::new(),
+    chinese: false,
+    chinese_words: Vec::new(),
+    japanese: false,
+    korean: false,
+    identifier_splitting: false,
+    number_grouping: false,
+};
+
+impl Separators {
+    /// A character both in `separators` and in `non_separators` is a separator.
+    pub fn new<S, N>(separators: S, non_separators: N) -> Separators
+    where
+        S: IntoIterator<Item = char>,
+        N: IntoIterator<Item = char>,
+    {
+        Separators {
+            separators: separators.into_iter().collect(),
+            non_separators: non_separators.into_iter().collect(),
+            dictionary: Vec::new(),
+            thai: false,
+            thai_words: Vec::new(),
+            chinese: false,
+            chinese_words: Vec::new(),
+            japanese: false,
+            korean: false,
+            identifier_splitting: false,
+            number_grouping: false,
+        }
+    }
+
+    /// Splits the runs of Thai characters into words, the words are searched in a Thai
+    /// dictionary and in the dictionary, that must be set first.
+    pub fn with_thai_segmentation(mut self) -> Separators {
+        let dictionary = self.dictionary.iter().filter(|word| word.chars().all(is_thai)).cloned();
+        let mut words: Vec<String> = dictionary.collect();
+        words.sort_unstable();
+        words.dedup();
+        self.thai = true;
+        self.thai_words = words;
+        self
+    }
+
+    /// Splits the runs of Han characters into words, the words are searched in a Chinese
+    /// dictionary and in the dictionary, that must be set first. The Japanese segmentation
+    /// takes precedence over this one.
+    pub fn with_chinese_segmentation(mut self) -> Separators {
+        let dictionary = self.dictionary.iter().filter(|word| word.chars().all(is_han)).map(|word| fold_traditional(word));
+        let mut words: Vec<String> = dictionary.collect();
+        words.sort_unstable();
+        words.dedup();
+        self.chinese = true;
+        self.chinese_words = words;
+        self
+    }
+
+    /// Splits the runs of Japanese characters into words at the changes of script and at the
+    /// particles, the words of the dictionary are kept whole.
+    pub fn with_japanese_segmentation(mut self) -> Separators {
+        self.japanese = true;
+        self
+    }
+
+    /// Keeps the runs of Hangul characters together and removes the particles that end them
+    /// from the tokens.
+    pub fn with_korean_segmentation(mut self) -> Separators {
+        self.korean = true;
+        self
+    }
+
+    /// Splits the camelCase and PascalCase identifiers into their components, `getUserById`
+    /// into `get`, `User`, `By` and `Id`, the snake_case identifiers are already split.
+    pub fn with_identifier_splitting(mut self) -> Separators {
+        self.identifier_splitting = true;
+        self
+    }
+
+    /// Keeps the numbers written with thousands separators or with a decimal comma, `1,000`
+    /// or `3,5`, as single tokens, when they are followed by a separator.
+    pub fn with_number_grouping(mut self) -> Separators {
+        self.number_grouping = true;
+        self
+    }
+
+    /// The words of the dictionary are kept as single tokens, whatever the characters they
+    /// contain, when they are followed by a separator or by the end of the text.
+    pub fn with_dictionary<I, S>(mut self, words: I) -> Separators
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut dictionary: Vec<_> = words
+            .into_iter()
+            .map(|word| word.as_ref().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect();
+        dictionary.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()));
+        self.dictionary = dictionary;
+        self
+    }
+
+    /// Returns the length in bytes of the dictionary word the text starts with.
+    fn dictionary_match(&self, text: &str) -> Option<usize> {
+        self.dictionary.iter().find_map(|word| {
+            let mut text_chars = text.char_indices();
+            for expected in word.chars() {
+                match text_chars.next() {
+                    Some((_, c)) if c.to_lowercase().eq(expected.to_lowercase()) => (),
+                    _ => return None,
+                }
+            }
+            match text_chars.next() {
+                None => Some(text.len()),
+                Some((i, c)) if is_separator(c, self) || is_cjk(c) => Some(i),
+                Some(_) => None,
+            }
+        })
+    }
+
+    /// Returns the length in bytes of the number written with separators the text starts with.
+    fn number_match(&self, text: &str) -> Option<usize> {
+        if self.number_grouping {
+            number::grouped_number_len(text, |c| is_separator(c, self))
+        } else {
+            None
+        }
+    }
+
+    fn classify(&self, c: char) -> Option<SeparatorCategory> {
+        if self.separators.contains(&c) {
+            Some(Soft)
+        } else if self.non_separators.contains(&c) {
+            None
+        } else {
+            classify_separator(c)
+        }
+    }
+}
+
+fn is_separator(c: char, separators: &Separators) -> bool {
+    separators.classify(c).is_some()
 }
 
 fn classify_separator(c: char) -> Option<SeparatorCategory> {
     match c {
         c if c.is_whitespace() => Some(Soft), // whitespaces
+        // transliterated to quotes, but they are part of the words
+        '\u{05f3}' | '\u{05f4}' => None, // Hebrew geresh and gershayim, used in the abbreviations
+        '\u{0621}' | '\u{0674}' => None, // Arabic hamza
+        c if is_vowel_mark(c) => None,
+        '\u{200e}' | '\u{200f}' | '\u{061c}' => Some(Soft), // directional marks
+        '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' => Some(Soft), // directional embeddings and isolates
+        '\u{05be}' => Some(Soft), // Hebrew maqaf
+        '\u{060c}' | '\u{061b}' | '\u{061f}' | '\u{06d4}' | '\u{05c3}' => Some(Hard), // Arabic and Hebrew punctuation
+        '\u{3001}' | '\u{3002}' => Some(Hard), // ideographic comma and full stop
+        '\u{ff01}' | '\u{ff08}' | '\u{ff09}' | '\u{ff0c}' | '\u{ff1b}' | '\u{ff1f}' => Some(Hard), // full-width punctuation
+        '\u{300a}'..='\u{300f}' | '\u{30fb}' | '\u{ff1a}' => Some(Soft), // title and corner brackets, middle dot, colon
         c if deunicode_char(c) == Some("'") => Some(Soft), // quotes
         c if deunicode_char(c) == Some("\"") => Some(Soft), // double quotes
         '-' | '_' | '\'' | ':' | '/' | '\\' | '@' => Some(Soft),
@@ -64,25 +279,56 @@ fn classify_separator(c: char) -> Option<SeparatorCategory> {
 enum CharCategory {
     Separator(SeparatorCategory),
     Cjk,
+    Japanese,
+    Chinese,
+    Korean,
+    Thai,
     Other,
 }
 
-fn classify_char(c: char) -> CharCategory {
-    if let Some(category) = classify_separator(c) {
+fn classify_char(c: char, separators: &Separators) -> CharCategory {
+    if let Some(category) = separators.classify(c) {
         CharCategory::Separator(category)
+    } else if separators.japanese && is_japanese(c) {
+        CharCategory::Japanese
+    } else if separators.chinese && is_han(c) {
+        CharCategory::Chinese
+    } else if separators.korean && is_hangul(c) {
+        CharCategory::Korean
     } else if is_cjk(c) {
         CharCategory::Cjk
+    } else if separators.thai && is_thai(c) {
+        CharCategory::Thai
     } else {
         CharCategory::Other
     }
 }
 
-fn is_str_word(s: &str) -> bool {
-    !s.chars().any(is_separator)
+/// Returns the length in bytes of the first component of a camelCase or PascalCase identifier,
+/// `getUserById` starts with `get` and `HTTPServer` with `HTTP`.
+fn first_component_len(word: &str) -> usize {
+    let mut chars = word.char_indices().peekable();
+    let mut previous = None;
+    while let Some((i, c)) = chars.next() {
+        if let Some(previous) = previous {
+            let next = chars.peek().map(|(_, c)| *c);
+            let lower_to_upper = previous.is_lowercase() && c.is_uppercase();
+            let acronym_end = previous.is_uppercase() && c.is_uppercase() && next.map_or(false, char::is_lowercase);
+            if lower_to_upper || acronym_end {
+                return i;
+            }
+        }
+        previous = Some(c);
+    }
+    word.len()
+}
+
+fn is_str_word(s: &str, separators: &Separators) -> bool {
+    !s.chars().any(|c| is_separator(c, separators))
 }
 
-fn same_group_category(a: char, b: char) -> bool {
-    match (classify_char(a), classify_char(b)) {
+fn same_group_category(a: char, b: char, separators: &Separators) -> bool {
+    match (classify_char(a, separators), classify_char(b, separators)) {
         (CharCategory::Cjk, _) | (_, CharCategory::Cjk) => false,
         (CharCategory::Separator(_), CharCategory::Separator(_)) => true,
         (a, b) => a == b,
@@ -98,6 +344,10 @@ pub fn split_query_string(query: &str) -> impl Iterator<Item = &str> {
     Tokenizer::new(query).map(|t| t.word)
 }
 
+pub fn split_query_string_with_separators<'a>(query: &'a str, separators: &'a Separators) -> impl Iterator<Item = &'a str> {
+    Tokenizer::with_separators(query, separators).map(|t| t.word)
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Token<'a> {
     pub word: &'a str,
@@ -112,15 +362,20 @@ pub struct Tokenizer<'a> {
     inner: &'a str,
     word_index: usize,
     char_index: usize,
+    separators: &'a Separators,
 }
 
 impl<'a> Tokenizer<'a> {
     pub fn new(string: &str) -> Tokenizer {
+        Tokenizer::with_separators(string, &DEFAULT_SEPARATORS)
+    }
+
+    pub fn with_separators(string: &'a str, separators: &'a Separators) -> Tokenizer<'a> {
         // skip every separator and set `char_index`
         // to the number of char trimmed
         let (count, index) = string
             .char_indices()
-            .take_while(|(_, c)| is_separator(*c))
+            .take_while(|(i, c)| is_separator(*c, separators) && separators.dictionary_match(&string[*i..]).is_none())
             .fold((0, 0), chars_count_index);
 
         Tokenizer {
@@ -128,6 +383,7 @@ impl<'a> Tokenizer<'a> {
             inner: &string[index..],
             word_index: 0,
             char_index: count,
+            separators,
         }
     }
 }
@@ -136,30 +392,80 @@ impl<'a> Iterator for Tokenizer<'a> {
     type Item = Token<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut iter = self.inner.linear_group_by(same_group_category).peekable();
+        let separators = self.separators;
+        let mut iter = self.inner.linear_group_by(|a, b| same_group_category(a, b, separators)).peekable();
 
         while let (Some(string), next_string) = (iter.next(), iter.peek()) {
-            let (count, index) = string.char_indices().fold((0, 0), chars_count_index);
+            let whole_word = separators.dictionary_match(self.inner).or_else(|| separators.number_match(self.inner));
+            if let Some(length) = whole_word {
+                let (word, rest) = self.inner.split_at(length);
+                let token = Token {
+                    word,
+                    index: self.count,
+                    word_index: self.word_index,
+                    char_index: self.char_index,
+                };
+
+                if rest.chars().next().map_or(false, |c| !is_separator(c, separators)) {
+                    self.word_index += 1;
+                }
+
+                self.count += 1;
+                self.char_index += word.chars().count();
+                self.inner = rest;
+
+                return Some(token);
+            }
+
+            if !is_str_word(string, separators) {
+                // a dictionary word can start in the middle of the separators,
+                // the groups must then be computed again from its first char
+                let dictionary_start = string
+                    .char_indices()
+                    .skip(1)
+                    .map(|(i, _)| i)
+                    .find(|i| separators.dictionary_match(&self.inner[*i..]).is_some());
+                let string = dictionary_start.map_or(string, |i| &string[..i]);
+                let (count, index) = string.char_indices().fold((0, 0), chars_count_index);
 
-            if !is_str_word(string) {
                 self.word_index += string
                     .chars()
-                    .filter_map(classify_separator)
+                    .filter_map(|c| separators.classify(c))
                     .fold(Soft, |a, x| a.merge(x))
                     .to_usize();
                 self.char_index += count;
                 self.inner = &self.inner[index..];
+
+                if dictionary_start.is_some() {
+                    return self.next();
+                }
                 continue;
             }
 
+            // a run of Thai, Japanese or Chinese characters or an identifier contains several words
+            let group_len = string.len();
+            let string = match string.chars().next().map(|c| classify_char(c, separators)) {
+                Some(CharCategory::Thai) => &string[..thai::first_word_len(string, &separators.thai_words)],
+                Some(CharCategory::Japanese) => &string[..japanese::first_word_len(string, &separators.dictionary)],
+                Some(CharCategory::Chinese) => &string[..chinese::first_word_len(string, &separators.chinese_words)],
+                Some(CharCategory::Other) if separators.identifier_splitting => &string[..first_component_len(string)],
+                _ => string,
+            };
+            // the particles that end a Korean word are skipped
+            let word = match string.chars().next().map(|c| classify_char(c, separators)) {
+                Some(CharCategory::Korean) => &string[..korean::stem_len(string)],
+                _ => string,
+            };
+            let (count, index) = string.char_indices().fold((0, 0), chars_count_index);
+
             let token = Token {
-                word: string,
+                word,
                 index: self.count,
                 word_index: self.word_index,
                 char_index: self.char_index,
             };
 
-            if next_string.filter(|s| is_str_word(s)).is_some() {
+            if string.len() < group_len || next_string.filter(|s| is_str_word(s, separators)).is_some() {
                 self.word_index += 1;
             }
 
@@ -184,20 +490,26 @@ where
     count: usize,
     word_offset: usize,
     char_offset: usize,
+    separators: &'a Separators,
 }
 
 impl<'a, I> SeqTokenizer<'a, I>
 where
     I: Iterator<Item = &'a str>,
 {
-    pub fn new(mut iter: I) -> SeqTokenizer<'a, I> {
-        let current = iter.next().map(|s| Tokenizer::new(s).peekable());
+    pub fn new(iter: I) -> SeqTokenizer<'a, I> {
+        SeqTokenizer::with_separators(iter, &DEFAULT_SEPARATORS)
+    }
+
+    pub fn with_separators(mut iter: I, separators: &'a Separators) -> SeqTokenizer<'a, I> {
+        let current = iter.next().map(|s| Tokenizer::with_separators(s, separators).peekable());
         SeqTokenizer {
             inner: iter,
             current,
             count: 0,
             word_offset: 0,
             char_offset: 0,
+            separators,
         }
     }
 }
@@ -235,7 +547,8 @@ where
                     None => {
                         // no more words in this text we must
                         // start tokenizing the next text
-                        self.current = self.inner.next().map(|s| Tokenizer::new(s).peekable());
+                        let separators = self.separators;
+                        self.current = self.inner.next().map(|s| Tokenizer::with_separators(s, separators).peekable());
                         self.next()
                     }
                 }
@@ -545,4 +858,157 @@ mod tests {
         );
         assert_eq!(tokenizer.next(), None);
     }
+
+    #[test]
+    fn custom_separators() {
+        let separators = Separators::new(vec!['|'], vec!['.', '-']);
+        let words: Vec<_> = split_query_string_with_separators("k8s.io|pn-1234 a.b", &separators).collect();
+        assert_eq!(words, vec!["k8s.io", "pn-1234", "a.b"]);
+
+        let words: Vec<_> = split_query_string("k8s.io|pn-1234").collect();
+        assert_eq!(words, vec!["k8s", "io|pn", "1234"]);
+
+        let mut tokenizer = Tokenizer::with_separators("C++|rust", &separators);
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index)), Some(("C++", 0)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index)), Some(("rust", 1)));
+        assert_eq!(tokenizer.next(), None);
+    }
+
+    #[test]
+    fn dictionary_words() {
+        let separators = Separators::default().with_dictionary(vec!["J.R.R.", "sodium chloride", ".NET"]);
+        let words: Vec<_> = split_query_string_with_separators("j.r.r. Tolkien, sodium chloride", &separators).collect();
+        assert_eq!(words, vec!["j.r.r.", "Tolkien", "sodium chloride"]);
+
+        let mut tokenizer = Tokenizer::with_separators(".NET and ASP .net", &separators);
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some((".NET", 0, 0)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("and", 1, 5)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("ASP", 2, 9)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some((".net", 3, 13)));
+        assert_eq!(tokenizer.next(), None);
+
+        // a dictionary word must end at a separator
+        let words: Vec<_> = split_query_string_with_separators("sodium chlorides", &separators).collect();
+        assert_eq!(words, vec!["sodium", "chlorides"]);
+    }
+
+    #[test]
+    fn right_to_left_scripts() {
+        // the Arabic comma and question mark are separators but not the harakat
+        let words: Vec<_> = split_query_string("كَتَبَ، الكِتاب؟").collect();
+        assert_eq!(words, vec!["كَتَبَ", "الكِتاب"]);
+
+        let words: Vec<_> = split_query_string("ماء بارد").collect();
+        assert_eq!(words, vec!["ماء", "بارد"]);
+
+        // the maqaf joins two words, the gershayim is part of the abbreviation
+        let words: Vec<_> = split_query_string("בֵּית־סֵפֶר של צה״ל").collect();
+        assert_eq!(words, vec!["בֵּית", "סֵפֶר", "של", "צה״ל"]);
+
+        // the directional marks are not part of the words
+        let mut tokenizer = Tokenizer::new("\u{200f}שלום\u{200f} world");
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("שלום", 0, 1)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("world", 1, 7)));
+        assert_eq!(tokenizer.next(), None);
+
+        assert!("كَتَبَ".chars().filter(|c| is_vowel_mark(*c)).count() == 3);
+        assert!(!"בית".chars().any(is_vowel_mark));
+    }
+
+    #[test]
+    fn thai_segmentation() {
+        let words: Vec<_> = split_query_string("iPhoneราคาถูก").collect();
+        assert_eq!(words, vec!["iPhoneราคาถูก"]);
+
+        let separators = Separators::default().with_dictionary(vec!["ไอโฟน"]).with_thai_segmentation();
+        let mut tokenizer = Tokenizer::with_separators("iPhoneราคาถูก มาก", &separators);
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("iPhone", 0, 0)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("ราคา", 1, 6)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("ถูก", 2, 10)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("มาก", 3, 14)));
+        assert_eq!(tokenizer.next(), None);
+
+        // the words of the dictionary are also searched
+        let words: Vec<_> = split_query_string_with_separators("ซื้อไอโฟนใหม่", &separators).collect();
+        assert_eq!(words, vec!["ซื้อ", "ไอโฟน", "ใหม่"]);
+    }
+
+    #[test]
+    fn japanese_segmentation() {
+        // every character is a word of its own by default, the punctuation is not a word
+        let words: Vec<_> = split_query_string("東京、日本。").collect();
+        assert_eq!(words, vec!["東", "京", "日", "本"]);
+
+        let separators = Separators::default().with_japanese_segmentation();
+        let mut tokenizer = Tokenizer::with_separators("私はコーヒーが好きです。iPhone", &separators);
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("私", 0, 0)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("は", 1, 1)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("コーヒー", 2, 2)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("が", 3, 6)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("好きです", 4, 7)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("iPhone", 12, 12)));
+        assert_eq!(tokenizer.next(), None);
+
+        let separators = Separators::default().with_dictionary(vec!["東京"]).with_japanese_segmentation();
+        let words: Vec<_> = split_query_string_with_separators("東京大学", &separators).collect();
+        assert_eq!(words, vec!["東京", "大学"]);
+    }
+
+    #[test]
+    fn chinese_segmentation() {
+        let separators = Separators::default().with_dictionary(vec!["美莎"]).with_chinese_segmentation();
+        let mut tokenizer = Tokenizer::with_separators("我喜歡吃中國菜，美莎", &separators);
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("我", 0, 0)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("喜歡", 1, 1)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("吃", 2, 3)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("中國", 3, 4)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("菜", 4, 6)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("美莎", 12, 8)));
+        assert_eq!(tokenizer.next(), None);
+    }
+
+    #[test]
+    fn korean_segmentation() {
+        // every syllable is a word of its own by default
+        let words: Vec<_> = split_query_string("학교에서").collect();
+        assert_eq!(words, vec!["학", "교", "에", "서"]);
+
+        let separators = Separators::default().with_korean_segmentation();
+        let mut tokenizer = Tokenizer::with_separators("학생들이 도서관에서 한국어를 공부한다", &separators);
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("학생들", 0, 0)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("도서관", 1, 5)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("한국어", 2, 11)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("공부한다", 3, 16)));
+        assert_eq!(tokenizer.next(), None);
+    }
+
+    #[test]
+    fn identifier_splitting() {
+        let words: Vec<_> = split_query_string("getUserById user_id_token").collect();
+        assert_eq!(words, vec!["getUserById", "user", "id", "token"]);
+
+        let separators = Separators::default().with_identifier_splitting();
+        let mut tokenizer = Tokenizer::with_separators("getUserById HTTPServer", &separators);
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("get", 0, 0)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("User", 1, 3)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("By", 2, 7)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("Id", 3, 9)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("HTTP", 4, 12)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("Server", 5, 16)));
+        assert_eq!(tokenizer.next(), None);
+    }
+
+    #[test]
+    fn number_grouping() {
+        let words: Vec<_> = split_query_string("1,000 apples").collect();
+        assert_eq!(words, vec!["1", "000", "apples"]);
+
+        let separators = Separators::default().with_number_grouping();
+        let mut tokenizer = Tokenizer::with_separators("1,000.50 apples, 3.5kg.", &separators);
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("1,000.50", 0, 0)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("apples", 1, 9)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("3", 9, 17)));
+        assert_eq!(tokenizer.next().map(|t| (t.word, t.word_index, t.char_index)), Some(("5kg", 17, 19)));
+        assert_eq!(tokenizer.next(), None);
+    }
 }
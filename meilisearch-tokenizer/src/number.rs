@@ -0,0 +1,101 @@
+//! The numbers written with thousands separators or with a decimal comma are kept as single
+//! words and written the same way, so that `1,000` matches `1000` and `3,5` matches `3.5`.
+
+fn is_number_separator(b: u8) -> bool {
+    b == b',' || b == b'.'
+}
+
+/// Returns the length in bytes of the number written with separators that starts the text,
+/// if the number is followed by the end of the text or by a character matching `ends_word`.
+pub(crate) fn grouped_number_len(text: &str, ends_word: impl Fn(char) -> bool) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let digits = |from: usize| bytes[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+
+    let mut len = digits(0);
+    if len == 0 {
+        return None;
+    }
+
+    let mut grouped = false;
+    while len < bytes.len() && is_number_separator(bytes[len]) {
+        let count = digits(len + 1);
+        if count == 0 {
+            break;
+        }
+        len += 1 + count;
+        grouped = true;
+    }
+
+    if grouped && text[len..].chars().next().map_or(true, ends_word) {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+/// Writes a number without its thousands separators and its leading zeros, with a point as
+/// decimal separator and without the trailing zeros of its decimals. A single separator is a
+/// thousands separator when it is followed by exactly three digits. The other words are
+/// returned untouched.
+pub fn fold_number(word: &str) -> String {
+    let bytes = word.as_bytes();
+    let is_number = bytes.first().map_or(false, u8::is_ascii_digit)
+        && bytes.last().map_or(false, u8::is_ascii_digit)
+        && bytes.iter().all(|b| b.is_ascii_digit() || is_number_separator(*b))
+        && !bytes.windows(2).any(|w| is_number_separator(w[0]) && is_number_separator(w[1]));
+    if !is_number {
+        return word.to_string();
+    }
+
+    let separators: Vec<(usize, u8)> = bytes.iter().copied().enumerate().filter(|(_, b)| is_number_separator(*b)).collect();
+    let decimal = match separators.last() {
+        None => None,
+        Some(&(i, last)) => {
+            let same_kind = separators.iter().filter(|(_, b)| *b == last).count();
+            let mixed = same_kind != separators.len();
+            if same_kind == 1 && (mixed || bytes.len() - i - 1 != 3) {
+                Some(i)
+            } else {
+                None
+            }
+        }
+    };
+
+    let (integer, decimals) = match decimal {
+        Some(i) => (&word[..i], &word[i + 1..]),
+        None => (word, ""),
+    };
+
+    let integer: String = integer.chars().filter(char::is_ascii_digit).collect();
+    let integer = integer.trim_start_matches('0');
+    let integer = if integer.is_empty() { "0" } else { integer };
+    let decimals = decimals.trim_end_matches('0');
+
+    if decimals.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, decimals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(fold_number("1,000"), "1000");
+        assert_eq!(fold_number("1.000.000"), "1000000");
+        assert_eq!(fold_number("1,000.50"), "1000.5");
+        assert_eq!(fold_number("1.000,50"), "1000.5");
+        assert_eq!(fold_number("3,5"), "3.5");
+        assert_eq!(fold_number("007"), "7");
+        assert_eq!(fold_number("0.0"), "0");
+        assert_eq!(fold_number("v007"), "v007");
+
+        let ends_word = |c: char| c.is_whitespace() || c == '.';
+        assert_eq!(grouped_number_len("1,000. Next", ends_word), Some(5));
+        assert_eq!(grouped_number_len("3.5kg", ends_word), None);
+        assert_eq!(grouped_number_len("42 apples", ends_word), None);
+    }
+}
@@ -0,0 +1,86 @@
+//! The scripts written without spaces between the words are split into the words of a dictionary.
+
+use std::cmp::Ordering;
+
+/// The longest word searched in the dictionary, in characters.
+const MAX_WORD_CHARS: usize = 30;
+
+/// Returns the length in bytes of the first word of a run of characters. The run is split
+/// into the fewest characters matching no word of the dictionary, then into the fewest
+/// words. The consecutive characters matching no word are kept together if `merge_unknown`,
+/// otherwise each of them is a word.
+pub(crate) fn first_word_len<F>(text: &str, is_word: F, merge_unknown: bool) -> usize
+where
+    F: Fn(&str) -> bool,
+{
+    let bounds: Vec<usize> = text.char_indices().map(|(i, _)| i).chain(Some(text.len())).collect();
+    let n = bounds.len() - 1;
+
+    // the cost, unmatched characters then words, of the best split of the text from each char
+    let mut costs = vec![(0, 0); n + 1];
+    let mut is_word = vec![false; n + 1];
+    let mut ends = vec![n; n + 1];
+    for i in (0..n).rev() {
+        let (unmatched, count) = costs[i + 1];
+        costs[i] = (unmatched + 1, count);
+        ends[i] = i + 1;
+        is_word[i] = false;
+
+        for j in (i + 1)..=n.min(i + MAX_WORD_CHARS) {
+            let candidate = &text[bounds[i]..bounds[j]];
+            if is_word(candidate) {
+                let (unmatched, count) = costs[j];
+                if (unmatched, count + 1) < costs[i] {
+                    costs[i] = (unmatched, count + 1);
+                    ends[i] = j;
+                    is_word[i] = true;
+                }
+            }
+        }
+    }
+
+    let mut end = ends[0];
+    if merge_unknown && !is_word[0] {
+        while end < n && !is_word[end] {
+            end = ends[end];
+        }
+    }
+
+    bounds[end]
+}
+
+/// Whether the word is a line of the dictionary, whose lines are sorted in byte order.
+pub(crate) fn contains_line(dictionary: &str, word: &str) -> bool {
+    let bytes = dictionary.as_bytes();
+    let (mut low, mut high) = (0, bytes.len());
+    while low < high {
+        // the line around the middle of the remaining lines
+        let middle = low + (high - low) / 2;
+        let start = bytes[low..middle].iter().rposition(|b| *b == b'\n').map_or(low, |i| low + i + 1);
+        let end = bytes[middle..high].iter().position(|b| *b == b'\n').map_or(high, |i| middle + i);
+        match dictionary[start..end].cmp(word) {
+            Ordering::Equal => return true,
+            Ordering::Less => low = end + 1,
+            Ordering::Greater => high = start,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted_lines() {
+        let dictionary = "a\nab\nb\nbcd\nc\n";
+        for word in &["a", "ab", "b", "bcd", "c"] {
+            assert!(contains_line(dictionary, word), "{}", word);
+        }
+        for word in &["", "aa", "abc", "bc", "d", "\n"] {
+            assert!(!contains_line(dictionary, word), "{:?}", word);
+        }
+        assert!(contains_line("a", "a"));
+        assert!(!contains_line("", "a"));
+    }
+}
@@ -0,0 +1,53 @@
+//! Thai is written without spaces between the words, the runs of Thai characters are split
+//! into the words of a dictionary.
+
+use crate::segmentation;
+
+/// The Thai words, one per line and sorted, from the dictionary of ICU, see `dictionaries/README.md`.
+const THAI_WORDS: &str = include_str!("../dictionaries/thai.txt");
+
+pub(crate) fn is_thai(c: char) -> bool {
+    c >= '\u{0e00}' && c <= '\u{0e7f}'
+}
+
+/// Whether the word is a word of the dictionary or of the sorted words of the index.
+fn is_word(word: &str, words: &[String]) -> bool {
+    segmentation::contains_line(THAI_WORDS, word) || words.binary_search_by(|w| w.as_str().cmp(word)).is_ok()
+}
+
+/// Returns the length in bytes of the first word of a run of Thai characters, the consecutive
+/// characters matching no word of the dictionary nor of the sorted words of the index are kept together.
+pub(crate) fn first_word_len(text: &str, words: &[String]) -> usize {
+    segmentation::first_word_len(text, |word| is_word(word, words), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split<'a>(mut text: &'a str, words: &[String]) -> Vec<&'a str> {
+        let mut split = Vec::new();
+        while !text.is_empty() {
+            let len = first_word_len(text, words);
+            split.push(&text[..len]);
+            text = &text[len..];
+        }
+        split
+    }
+
+    #[test]
+    fn fewest_words() {
+        assert_eq!(split("ผมชอบกินข้าว", &[]), vec!["ผม", "ชอบ", "กิน", "ข้าว"]);
+        // the longest words are preferred when they lead to fewer words
+        assert_eq!(split("ประเทศไทยสวย", &[]), vec!["ประเทศไทย", "สวย"]);
+        // the unknown characters are kept together
+        assert_eq!(split("ฉันรักกขฃ", &[]), vec!["ฉัน", "รัก", "กขฃ"]);
+
+        // the words of the index are also searched
+        assert_eq!(split("ซื้อไอโฟนใหม่", &[]), vec!["ซื้อ", "ไอ", "โฟน", "ใหม่"]);
+        let words = vec!["ไอโฟน".to_string()];
+        assert_eq!(split("ซื้อไอโฟนใหม่", &words), vec!["ซื้อ", "ไอโฟน", "ใหม่"]);
+
+        assert!(THAI_WORDS.lines().zip(THAI_WORDS.lines().skip(1)).all(|(a, b)| a < b));
+    }
+}